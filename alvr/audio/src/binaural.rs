@@ -0,0 +1,161 @@
+// Binaural downmix of 5.1 and 7.1 audio. Each speaker is rendered as a virtual source using a
+// spherical head model (Brown and Duda): the interaural time difference is a per-ear delay and the
+// head shadow is a first order shelving filter whose high frequency gain depends on the angle
+// between the source and the ear.
+
+use alvr_common::anyhow::{bail, Result};
+use std::{collections::VecDeque, f32::consts::PI};
+
+const HEAD_RADIUS_M: f32 = 0.0875;
+const SPEED_OF_SOUND_MPS: f32 = 343.0;
+const MIN_SHADOW_GAIN: f32 = 0.1;
+const MIN_SHADOW_ANGLE: f32 = 150.0 * PI / 180.0;
+const LFE_GAIN: f32 = 0.5;
+// Headroom for the sum of all virtual speakers
+const OUTPUT_GAIN: f32 = 0.5;
+
+// Azimuth in degrees, positive to the right. None for the LFE channel. Channel order follows the
+// WAVE/WASAPI layout
+fn speaker_azimuths(channels_count: usize) -> Result<Vec<Option<f32>>> {
+    Ok(match channels_count {
+        // FL, FR, FC, LFE, SL, SR
+        6 => vec![
+            Some(-30.0),
+            Some(30.0),
+            Some(0.0),
+            None,
+            Some(-110.0),
+            Some(110.0),
+        ],
+        // FL, FR, FC, LFE, BL, BR, SL, SR
+        8 => vec![
+            Some(-30.0),
+            Some(30.0),
+            Some(0.0),
+            None,
+            Some(-150.0),
+            Some(150.0),
+            Some(-90.0),
+            Some(90.0),
+        ],
+        _ => bail!("Unsupported channel count for binaural downmix: {channels_count}"),
+    })
+}
+
+// Path from a virtual speaker to one ear
+struct EarPath {
+    delay: VecDeque<f32>,
+    b0: f32,
+    b1: f32,
+    a1: f32,
+    last_input: f32,
+    last_output: f32,
+}
+
+impl EarPath {
+    // ear_angle: angle between the source and the ear axis, in radians, range [0, PI]
+    fn new(ear_angle: f32, sample_rate: u32) -> Self {
+        let head_time = HEAD_RADIUS_M / SPEED_OF_SOUND_MPS;
+
+        // Delay relative to the center of the head, offset to be always positive
+        let delay_s = if ear_angle < PI / 2.0 {
+            head_time * (1.0 - ear_angle.cos())
+        } else {
+            head_time * (1.0 + ear_angle - PI / 2.0)
+        };
+        let delay_samples = (delay_s * sample_rate as f32).round() as usize;
+
+        let alpha = (1.0 + MIN_SHADOW_GAIN / 2.0)
+            + (1.0 - MIN_SHADOW_GAIN / 2.0) * (ear_angle / MIN_SHADOW_ANGLE * PI).cos();
+
+        // H(s) = (1 + alpha * tau * s) / (1 + tau * s) with tau = head_time / 2, discretized with
+        // the bilinear transform
+        let k = 2.0 * sample_rate as f32 * (head_time / 2.0);
+        let norm = 1.0 + k;
+
+        Self {
+            delay: vec![0.0; delay_samples].into(),
+            b0: (1.0 + alpha * k) / norm,
+            b1: (1.0 - alpha * k) / norm,
+            a1: (1.0 - k) / norm,
+            last_input: 0.0,
+            last_output: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        self.delay.push_back(input);
+        let input = self.delay.pop_front().unwrap();
+
+        let output = self.b0 * input + self.b1 * self.last_input - self.a1 * self.last_output;
+        self.last_input = input;
+        self.last_output = output;
+
+        output
+    }
+}
+
+enum Speaker {
+    Positional { left: EarPath, right: EarPath },
+    LowFrequency,
+}
+
+pub struct BinauralDownmixer {
+    speakers: Vec<Speaker>,
+}
+
+impl BinauralDownmixer {
+    pub fn new(channels_count: usize, sample_rate: u32) -> Result<Self> {
+        let speakers = speaker_azimuths(channels_count)?
+            .into_iter()
+            .map(|azimuth| match azimuth {
+                Some(azimuth) => {
+                    let ear_angle = |ear_azimuth: f32| {
+                        let diff = (azimuth - ear_azimuth).abs() % 360.0;
+                        f32::min(diff, 360.0 - diff).to_radians()
+                    };
+
+                    Speaker::Positional {
+                        left: EarPath::new(ear_angle(-90.0), sample_rate),
+                        right: EarPath::new(ear_angle(90.0), sample_rate),
+                    }
+                }
+                None => Speaker::LowFrequency,
+            })
+            .collect();
+
+        Ok(Self { speakers })
+    }
+
+    pub fn channels_count(&self) -> usize {
+        self.speakers.len()
+    }
+
+    // Converts interleaved multichannel samples into interleaved stereo samples
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let mut output = Vec::with_capacity(input.len() / self.speakers.len() * 2);
+
+        for frame in input.chunks_exact(self.speakers.len()) {
+            let mut left_sum = 0.0;
+            let mut right_sum = 0.0;
+
+            for (speaker, sample) in self.speakers.iter_mut().zip(frame) {
+                match speaker {
+                    Speaker::Positional { left, right } => {
+                        left_sum += left.process(*sample);
+                        right_sum += right.process(*sample);
+                    }
+                    Speaker::LowFrequency => {
+                        left_sum += sample * LFE_GAIN;
+                        right_sum += sample * LFE_GAIN;
+                    }
+                }
+            }
+
+            output.push((left_sum * OUTPUT_GAIN).clamp(-1.0, 1.0));
+            output.push((right_sum * OUTPUT_GAIN).clamp(-1.0, 1.0));
+        }
+
+        output
+    }
+}
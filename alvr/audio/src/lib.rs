@@ -1,6 +1,9 @@
+mod binaural;
 #[cfg(windows)]
 mod windows;
 
+pub use binaural::*;
+
 #[cfg(windows)]
 pub use crate::windows::*;
 
@@ -12,19 +15,20 @@ use alvr_common::{
     ConnectionError, RelaxedAtomic, ToAny,
 };
 use alvr_session::{
-    AudioBufferingConfig, CustomAudioDeviceConfig, LinuxAudioBackend, MicrophoneDevicesConfig,
+    AudioBufferingAutoTuneConfig, AudioBufferingConfig, CustomAudioDeviceConfig, LinuxAudioBackend,
+    MicrophoneDevicesConfig,
 };
 use alvr_sockets::{StreamReceiver, StreamSender};
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
     BufferSize, Device, Host, Sample, SampleFormat, StreamConfig,
 };
-use rodio::{OutputStream, Source};
+use rodio::{buffer::SamplesBuffer, OutputStream, Sink, Source};
 use std::{
     collections::{HashMap, VecDeque},
     sync::Arc,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 static VIRTUAL_MICROPHONE_PAIRS: Lazy<HashMap<&str, &str>> = Lazy::new(|| {
@@ -185,6 +189,15 @@ impl AudioDevice {
         ))
     }
 
+    pub fn input_channels_count(&self) -> Result<u16> {
+        let config = self
+            .inner
+            .default_input_config()
+            .or_else(|_| self.inner.default_output_config())?;
+
+        Ok(config.channels())
+    }
+
     pub fn input_sample_rate(&self) -> Result<u32> {
         let config = self
             .inner
@@ -204,6 +217,91 @@ pub fn is_same_device(device1: &AudioDevice, device2: &AudioDevice) -> bool {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+pub enum AudioBufferEvent {
+    Underrun,
+    Overrun,
+    BufferingChanged(Duration), // new average buffering chosen by the auto-tuner
+}
+
+const AUTO_TUNE_INCREASE_STEP: Duration = Duration::from_millis(10);
+const AUTO_TUNE_DECREASE_STEP: Duration = Duration::from_millis(2);
+// Glitches come in bursts, count them as one
+const AUTO_TUNE_GLITCH_COOLDOWN: Duration = Duration::from_secs(1);
+const AUTO_TUNE_INITIAL_STABLE_INTERVAL: Duration = Duration::from_secs(15);
+const AUTO_TUNE_MAX_STABLE_INTERVAL: Duration = Duration::from_secs(300);
+
+// Raises the average buffering on glitches and lowers it after a period without glitches. The
+// required stable period doubles at every glitch, so the buffering converges instead of oscillating
+pub struct BufferingAutoTuner {
+    sample_rate: u32,
+    min_frames: usize,
+    max_frames: usize,
+    stable_interval: Duration,
+    last_glitch_instant: Option<Instant>,
+    last_change_instant: Instant,
+}
+
+impl BufferingAutoTuner {
+    pub fn new(config: &AudioBufferingAutoTuneConfig, sample_rate: u32) -> Self {
+        let min_frames = sample_rate as usize * config.min_buffering_ms as usize / 1000;
+
+        Self {
+            sample_rate,
+            min_frames,
+            max_frames: usize::max(
+                sample_rate as usize * config.max_buffering_ms as usize / 1000,
+                min_frames,
+            ),
+            stable_interval: AUTO_TUNE_INITIAL_STABLE_INTERVAL,
+            last_glitch_instant: None,
+            last_change_instant: Instant::now(),
+        }
+    }
+
+    fn frames(&self, duration: Duration) -> usize {
+        (self.sample_rate as f32 * duration.as_secs_f32()) as usize
+    }
+
+    pub fn buffering(&self, frames_count: usize) -> Duration {
+        Duration::from_secs_f32(frames_count as f32 / self.sample_rate as f32)
+    }
+
+    pub fn clamp(&self, frames_count: usize) -> usize {
+        frames_count.clamp(self.min_frames, self.max_frames)
+    }
+
+    // Returns the new average buffer size in frames, if changed
+    pub fn report_glitch(&mut self, frames_count: usize) -> Option<usize> {
+        let now = Instant::now();
+        if self
+            .last_glitch_instant
+            .map_or(false, |instant| now < instant + AUTO_TUNE_GLITCH_COOLDOWN)
+        {
+            return None;
+        }
+        self.last_glitch_instant = Some(now);
+        self.last_change_instant = now;
+        self.stable_interval =
+            Duration::min(self.stable_interval * 2, AUTO_TUNE_MAX_STABLE_INTERVAL);
+
+        let new_frames_count = self.clamp(frames_count + self.frames(AUTO_TUNE_INCREASE_STEP));
+        (new_frames_count != frames_count).then_some(new_frames_count)
+    }
+
+    pub fn update(&mut self, frames_count: usize) -> Option<usize> {
+        let now = Instant::now();
+        if now < self.last_change_instant + self.stable_interval {
+            return None;
+        }
+        self.last_change_instant = now;
+
+        let new_frames_count =
+            self.clamp(frames_count.saturating_sub(self.frames(AUTO_TUNE_DECREASE_STEP)));
+        (new_frames_count != frames_count).then_some(new_frames_count)
+    }
+}
+
 pub enum AudioRecordState {
     Recording,
     ShouldStop,
@@ -216,6 +314,7 @@ pub fn record_audio_blocking(
     mut sender: StreamSender<()>,
     device: &AudioDevice,
     channels_count: u16,
+    binaural_downmix: bool,
     mute: bool,
 ) -> Result<()> {
     let config = device
@@ -224,12 +323,20 @@ pub fn record_audio_blocking(
         // On Windows, loopback devices are not recognized as input devices. Use output config.
         .or_else(|_| device.inner.default_output_config())?;
 
-    if config.channels() > 2 {
-        // todo: handle more than 2 channels
-        bail!(
-            "Audio devices with more than 2 channels are not supported. {}",
-            "Please turn off surround audio."
-        );
+    // Surround audio is either sent as is (downmixed by the client) or downmixed here
+    let mut downmixer = None;
+    if config.channels() > 2 && config.channels() != channels_count {
+        if channels_count == 2 && binaural_downmix {
+            downmixer = Some(BinauralDownmixer::new(
+                config.channels() as _,
+                config.sample_rate().0,
+            )?);
+        } else {
+            bail!(
+                "Audio devices with more than 2 channels are not supported. {}",
+                "Please turn off surround audio or enable 5.1/7.1 surround in the settings."
+            );
+        }
     }
 
     let stream_config = StreamConfig {
@@ -247,7 +354,25 @@ pub fn record_audio_blocking(
             let state = Arc::clone(&state);
             let is_streaming = Arc::clone(&is_streaming);
             move |data, _| {
-                let data = if config.sample_format() == SampleFormat::F32 {
+                let data = if let Some(downmixer) = &mut downmixer {
+                    let samples = if config.sample_format() == SampleFormat::F32 {
+                        data.bytes()
+                            .chunks_exact(4)
+                            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                            .collect::<Vec<_>>()
+                    } else {
+                        data.bytes()
+                            .chunks_exact(2)
+                            .map(|b| i16::from_ne_bytes([b[0], b[1]]).to_sample::<f32>())
+                            .collect::<Vec<_>>()
+                    };
+
+                    downmixer
+                        .process(&samples)
+                        .into_iter()
+                        .flat_map(|sample| sample.to_sample::<i16>().to_ne_bytes())
+                        .collect()
+                } else if config.sample_format() == SampleFormat::F32 {
                     data.bytes()
                         .chunks_exact(4)
                         .flat_map(|b| {
@@ -349,15 +474,27 @@ pub fn get_next_frame_batch(
 // callback will gracefully handle an interruption, and the callback timing and sound wave
 // continuity will not be affected.
 pub fn receive_samples_loop(
-    running: Arc<RelaxedAtomic>,
-    mut receiver: StreamReceiver<()>,
+    running: impl Fn() -> bool,
+    receiver: &mut StreamReceiver<()>,
     sample_buffer: Arc<Mutex<VecDeque<f32>>>,
     channels_count: usize,
     batch_frames_count: usize,
-    average_buffer_frames_count: usize,
+    mut average_buffer_frames_count: usize,
+    mut auto_tuner: Option<BufferingAutoTuner>,
+    // Converts the received surround audio to stereo. channels_count refers to the output
+    mut downmixer: Option<BinauralDownmixer>,
+    mut report_buffer_event: impl FnMut(AudioBufferEvent),
+    // Called with the samples of every received packet, before they are buffered
+    mut inspect_samples: impl FnMut(&[f32]),
 ) -> Result<()> {
+    if let Some(tuner) = &auto_tuner {
+        average_buffer_frames_count = tuner.clamp(average_buffer_frames_count);
+    }
+
     let mut recovery_sample_buffer = vec![];
-    while running.value() {
+    // Underruns are counted only once the playback started, and not after packet loss
+    let mut is_playing = false;
+    while running() {
         let data = match receiver.recv(Duration::from_millis(500)) {
             Ok(data) => data,
             Err(ConnectionError::TryAgain(_)) => continue,
@@ -365,13 +502,19 @@ pub fn receive_samples_loop(
         };
         let (_, packet) = data.get()?;
 
-        let new_samples = packet
+        let mut new_samples = packet
             .chunks_exact(2)
             .map(|c| i16::from_ne_bytes([c[0], c[1]]).to_sample::<f32>())
             .collect::<Vec<_>>();
+        if let Some(downmixer) = &mut downmixer {
+            new_samples = downmixer.process(&new_samples);
+        }
+        inspect_samples(&new_samples);
 
         let mut sample_buffer_ref = sample_buffer.lock();
 
+        let mut glitched = false;
+
         if data.had_packet_loss() {
             info!("Audio packet loss!");
 
@@ -383,9 +526,16 @@ pub fn receive_samples_loop(
             }
 
             recovery_sample_buffer.clear();
+            is_playing = false;
         }
 
         if sample_buffer_ref.len() / channels_count < batch_frames_count {
+            if is_playing {
+                report_buffer_event(AudioBufferEvent::Underrun);
+                is_playing = false;
+                glitched = true;
+            }
+
             recovery_sample_buffer.extend(sample_buffer_ref.drain(..));
         }
 
@@ -419,6 +569,7 @@ pub fn receive_samples_loop(
                 }
 
                 sample_buffer_ref.extend(recovery_sample_buffer.drain(..));
+                is_playing = true;
                 info!("Audio recovered");
             }
         } else {
@@ -429,6 +580,8 @@ pub fn receive_samples_loop(
         let buffer_frames_size = sample_buffer_ref.len() / channels_count;
         if buffer_frames_size > 2 * average_buffer_frames_count + batch_frames_count {
             info!("Audio buffer overflow! size: {buffer_frames_size}");
+            report_buffer_event(AudioBufferEvent::Overrun);
+            glitched = true;
 
             let drained_samples = sample_buffer_ref
                 .drain(0..(buffer_frames_size - average_buffer_frames_count) * channels_count)
@@ -444,6 +597,22 @@ pub fn receive_samples_loop(
                 }
             }
         }
+
+        if let Some(tuner) = &mut auto_tuner {
+            let new_frames_count = if glitched {
+                tuner.report_glitch(average_buffer_frames_count)
+            } else {
+                tuner.update(average_buffer_frames_count)
+            };
+
+            if let Some(frames_count) = new_frames_count {
+                average_buffer_frames_count = frames_count;
+
+                let buffering = tuner.buffering(frames_count);
+                info!("Audio buffering set to {} ms", buffering.as_millis());
+                report_buffer_event(AudioBufferEvent::BufferingChanged(buffering));
+            }
+        }
     }
 
     Ok(())
@@ -504,7 +673,9 @@ pub fn play_audio_loop(
     channels_count: u16,
     sample_rate: u32,
     config: AudioBufferingConfig,
-    receiver: StreamReceiver<()>,
+    mut receiver: StreamReceiver<()>,
+    report_buffer_event: impl FnMut(AudioBufferEvent),
+    inspect_samples: impl FnMut(&[f32]),
 ) -> Result<()> {
     // Size of a chunk of frames. It corresponds to the duration if a fade-in/out in frames.
     let batch_frames_count = sample_rate as usize * config.batch_ms as usize / 1000;
@@ -527,14 +698,37 @@ pub fn play_audio_loop(
     })?;
 
     receive_samples_loop(
-        running,
-        receiver,
+        || running.value(),
+        &mut receiver,
         sample_buffer,
         channels_count as _,
         batch_frames_count,
         average_buffer_frames_count,
+        config
+            .auto_tune
+            .as_option()
+            .map(|config| BufferingAutoTuner::new(config, sample_rate)),
+        None,
+        report_buffer_event,
+        inspect_samples,
     )
     .ok();
 
     Ok(())
 }
+
+// Plays the interleaved samples and returns once they have been played
+pub fn play_samples_blocking(
+    device: &AudioDevice,
+    channels_count: u16,
+    sample_rate: u32,
+    samples: Vec<f32>,
+) -> Result<()> {
+    let (_stream, handle) = OutputStream::try_from_device(&device.inner)?;
+    let sink = Sink::try_new(&handle)?;
+
+    sink.append(SamplesBuffer::new(channels_count, sample_rate, samples));
+    sink.sleep_until_end();
+
+    Ok(())
+}
@@ -40,6 +40,15 @@ extern "C" void prepareLobbyRoom(int viewWidth,
 extern "C" void destroyRenderers();
 extern "C" void streamStartNative(FfiStreamConfig config);
 extern "C" void updateLobbyHudTexture(const unsigned char *data);
+// Must be called from the rendering thread. data is RGBA, tightly packed
+extern "C" void updateOverlayLayerTexture(unsigned int texture,
+                                          unsigned int width,
+                                          unsigned int height,
+                                          const unsigned char *data);
+// Pass null to hide the caption
+extern "C" void updateCaptionTexture(const unsigned char *data);
+// Column major 3x3 matrix applied to the stream colors. Pass null to remove the filter
+extern "C" void setColorBlindnessFilterNative(const float *matrix);
 extern "C" void renderLobbyNative(const FfiViewInput eyeInputs[2]);
 extern "C" void renderStreamNative(void *streamHardwareBuffer,
                                    const unsigned int swapchainIndices[2]);
@@ -16,6 +16,9 @@ const int MAX_PROGRAM_UNIFORMS = 8;
 const int MAX_PROGRAM_TEXTURES = 8;
 const int HUD_TEXTURE_WIDTH = 1280;
 const int HUD_TEXTURE_HEIGHT = 720;
+// Side by side, one square half per eye
+const int CAPTION_TEXTURE_WIDTH = 2048;
+const int CAPTION_TEXTURE_HEIGHT = 1024;
 
 /// Integer version of ovrRectf
 typedef struct Recti_ {
@@ -73,6 +76,8 @@ typedef struct {
     ovrGeometry Panel;
     gl_render_utils::Texture *streamTexture;
     GLuint hudTexture;
+    GLuint captionTexture;
+    bool showCaption;
     GltfModel *lobbyScene;
     std::unique_ptr<FFR> ffr;
     std::unique_ptr<SrgbCorrectionPass> srgbCorrectionPass;
@@ -108,10 +113,12 @@ enum E1test {
     UNIFORM_ALPHA,
     UNIFORM_COLOR,
     UNIFORM_M_MATRIX,
-    UNIFORM_MODE
+    UNIFORM_MODE,
+    UNIFORM_COLOR_FILTER
 };
 enum E2test {
     UNIFORM_TYPE_VECTOR4,
+    UNIFORM_TYPE_MATRIX3X3,
     UNIFORM_TYPE_MATRIX4X4,
     UNIFORM_TYPE_INT,
     UNIFORM_TYPE_BUFFER,
@@ -130,6 +137,7 @@ static ovrUniform ProgramUniforms[] = {
     {UNIFORM_COLOR, UNIFORM_TYPE_VECTOR4, "Color"},
     {UNIFORM_M_MATRIX, UNIFORM_TYPE_MATRIX4X4, "mMatrix"},
     {UNIFORM_MODE, UNIFORM_TYPE_INT, "Mode"},
+    {UNIFORM_COLOR_FILTER, UNIFORM_TYPE_MATRIX3X3, "colorFilter"},
 };
 
 class GraphicsContext {
@@ -139,12 +147,20 @@ class GraphicsContext {
     std::vector<uint8_t> hudTextureBitmap;
     std::mutex hudTextureMutex;
     std::unique_ptr<Texture> hudTexture;
+    std::vector<uint8_t> captionTextureBitmap;
+    bool captionVisible;
+    std::mutex captionTextureMutex;
+    std::unique_ptr<Texture> captionTexture;
     std::vector<GLuint> lobbySwapchainTextures[2];
     std::unique_ptr<ovrRenderer> lobbyRenderer;
 
     std::unique_ptr<Texture> streamTexture;
     std::vector<GLuint> streamSwapchainTextures[2];
     std::unique_ptr<ovrRenderer> streamRenderer;
+
+    // Column major, identity when no filter is set
+    float colorBlindnessFilter[9];
+    std::mutex colorBlindnessFilterMutex;
 };
 
 namespace {
@@ -192,9 +208,11 @@ in lowp vec2 uv;
 in lowp vec4 fragmentColor;
 out lowp vec4 outColor;
 uniform sampler2D Texture0;
+uniform lowp mat3 colorFilter;
 void main()
 {
     outColor = texture(Texture0, uv);
+    outColor.rgb = clamp(colorFilter * outColor.rgb, 0.0, 1.0);
 }
 )glsl";
 
@@ -574,6 +592,8 @@ void ovrRenderer_Create(ovrRenderer *renderer,
 
     renderer->streamTexture = streamTexture;
     renderer->hudTexture = hudTexture;
+    renderer->captionTexture = 0;
+    renderer->showCaption = false;
     renderer->SceneCreated = false;
     renderer->lobbyScene = new GltfModel();
     renderer->lobbyScene->load();
@@ -659,11 +679,29 @@ void renderEye(
                               (float *)mvpMatrix));
 
         GL(glUniform1f(renderer->streamProgram.UniformLocation[UNIFORM_ALPHA], 2.0f));
+        {
+            std::lock_guard<std::mutex> lock(g_ctx.colorBlindnessFilterMutex);
+            GL(glUniformMatrix3fv(renderer->streamProgram.UniformLocation[UNIFORM_COLOR_FILTER],
+                                  1,
+                                  false,
+                                  g_ctx.colorBlindnessFilter));
+        }
         GL(glActiveTexture(GL_TEXTURE0));
         GL(glBindTexture(GL_TEXTURE_2D, renderer->streamRenderTexture));
 
         GL(glDrawElements(GL_TRIANGLES, renderer->Panel.IndexCount, GL_UNSIGNED_SHORT, NULL));
 
+        if (renderer->showCaption) {
+            // The caption texture has the same side by side layout as the stream
+            GL(glEnable(GL_BLEND));
+            GL(glBlendFunc(GL_SRC_ALPHA, GL_ONE_MINUS_SRC_ALPHA));
+            GL(glBindTexture(GL_TEXTURE_2D, renderer->captionTexture));
+
+            GL(glDrawElements(GL_TRIANGLES, renderer->Panel.IndexCount, GL_UNSIGNED_SHORT, NULL));
+
+            GL(glDisable(GL_BLEND));
+        }
+
         GL(glBindVertexArray(0));
 
         GL(glActiveTexture(GL_TEXTURE0));
@@ -734,6 +772,17 @@ void initGraphicsNative() {
     g_ctx.streamTexture = std::make_unique<Texture>(false, 0, true);
     g_ctx.hudTexture = std::make_unique<Texture>(
         false, 0, false, 1280, 720, GL_RGBA8, GL_RGBA, std::vector<uint8_t>(1280 * 720 * 4, 0));
+    g_ctx.captionTexture = std::make_unique<Texture>(
+        false,
+        0,
+        false,
+        CAPTION_TEXTURE_WIDTH,
+        CAPTION_TEXTURE_HEIGHT,
+        GL_RGBA8,
+        GL_RGBA,
+        std::vector<uint8_t>(CAPTION_TEXTURE_WIDTH * CAPTION_TEXTURE_HEIGHT * 4, 0));
+    g_ctx.captionVisible = false;
+    setColorBlindnessFilterNative(nullptr);
     
     const GLubyte *sVendor, *sRenderer, *sVersion, *sExts;
 
@@ -750,6 +799,7 @@ void destroyGraphicsNative() {
     LOGV("Resetting stream texture and hud texture %p, %p", g_ctx.streamTexture.get(), g_ctx.hudTexture.get());
     g_ctx.streamTexture.reset();
     g_ctx.hudTexture.reset();
+    g_ctx.captionTexture.reset();
     LOGV("Resetted stream texture and hud texture to %p, %p", g_ctx.streamTexture.get(), g_ctx.hudTexture.get());
 }
 
@@ -820,6 +870,7 @@ void streamStartNative(FfiStreamConfig config) {
                         config.foveationEdgeRatioX,
                         config.foveationEdgeRatioY},
                        false);
+    g_ctx.streamRenderer->captionTexture = g_ctx.captionTexture->GetGLTexture();
 }
 
 void updateLobbyHudTexture(const unsigned char *data) {
@@ -830,6 +881,39 @@ void updateLobbyHudTexture(const unsigned char *data) {
     memcpy(&g_ctx.hudTextureBitmap[0], data, HUD_TEXTURE_WIDTH * HUD_TEXTURE_HEIGHT * 4);
 }
 
+void updateOverlayLayerTexture(unsigned int texture,
+                               unsigned int width,
+                               unsigned int height,
+                               const unsigned char *data) {
+    GL(glBindTexture(GL_TEXTURE_2D, texture));
+    GL(glPixelStorei(GL_UNPACK_ALIGNMENT, 1));
+    GL(glTexSubImage2D(GL_TEXTURE_2D, 0, 0, 0, width, height, GL_RGBA, GL_UNSIGNED_BYTE, data));
+    GL(glPixelStorei(GL_UNPACK_ALIGNMENT, 4));
+    GL(glBindTexture(GL_TEXTURE_2D, 0));
+}
+
+void updateCaptionTexture(const unsigned char *data) {
+    std::lock_guard<std::mutex> lock(g_ctx.captionTextureMutex);
+
+    if (data == nullptr) {
+        g_ctx.captionVisible = false;
+        g_ctx.captionTextureBitmap.clear();
+        return;
+    }
+
+    g_ctx.captionVisible = true;
+    g_ctx.captionTextureBitmap.resize(CAPTION_TEXTURE_WIDTH * CAPTION_TEXTURE_HEIGHT * 4);
+
+    memcpy(&g_ctx.captionTextureBitmap[0], data, CAPTION_TEXTURE_WIDTH * CAPTION_TEXTURE_HEIGHT * 4);
+}
+
+void setColorBlindnessFilterNative(const float *matrix) {
+    static const float IDENTITY[9] = {1, 0, 0, 0, 1, 0, 0, 0, 1};
+
+    std::lock_guard<std::mutex> lock(g_ctx.colorBlindnessFilterMutex);
+    memcpy(g_ctx.colorBlindnessFilter, matrix != nullptr ? matrix : IDENTITY, sizeof(IDENTITY));
+}
+
 void renderLobbyNative(const FfiViewInput eyeInputs[2]) {
     // update text image
     {
@@ -873,6 +957,26 @@ void renderStreamNative(void *streamHardwareBuffer, const unsigned int swapchain
         GL(eglDestroyImageKHR(g_ctx.eglDisplay, image));
     }
 
+    // update caption image
+    {
+        std::lock_guard<std::mutex> lock(g_ctx.captionTextureMutex);
+
+        if (!g_ctx.captionTextureBitmap.empty()) {
+            GL(glBindTexture(GL_TEXTURE_2D, g_ctx.captionTexture->GetGLTexture()));
+            GL(glTexSubImage2D(GL_TEXTURE_2D,
+                            0,
+                            0,
+                            0,
+                            CAPTION_TEXTURE_WIDTH,
+                            CAPTION_TEXTURE_HEIGHT,
+                            GL_RGBA,
+                            GL_UNSIGNED_BYTE,
+                            &g_ctx.captionTextureBitmap[0]));
+        }
+        g_ctx.captionTextureBitmap.clear();
+        renderer->showCaption = g_ctx.captionVisible;
+    }
+
     FfiViewInput eyeInputs[2] = {};
     eyeInputs[0].swapchainIndex = swapchainIndices[0];
     eyeInputs[1].swapchainIndex = swapchainIndices[1];
@@ -1,6 +1,8 @@
-use alvr_audio::{AudioDevice, AudioRecordState};
+use crate::platform::{self, AudioOutputDevice};
+use alvr_audio::{AudioBufferEvent, AudioDevice, AudioRecordState, BinauralDownmixer};
 use alvr_common::{
     anyhow::{bail, Result},
+    info,
     parking_lot::Mutex,
     RelaxedAtomic, ToAny,
 };
@@ -8,8 +10,8 @@ use alvr_session::AudioBufferingConfig;
 use alvr_sockets::{StreamReceiver, StreamSender};
 use oboe::{
     AudioInputCallback, AudioInputStreamSafe, AudioOutputCallback, AudioOutputStreamSafe,
-    AudioStream, AudioStreamBuilder, DataCallbackResult, InputPreset, Mono, PerformanceMode,
-    SampleRateConversionQuality, Stereo, Usage,
+    AudioStream, AudioStreamBase, AudioStreamBuilder, DataCallbackResult, InputPreset, Mono,
+    PerformanceMode, SampleRateConversionQuality, Stereo, Usage,
 };
 use std::{collections::VecDeque, mem, sync::Arc, thread, time::Duration};
 
@@ -99,9 +101,13 @@ pub fn record_audio_blocking(
     res
 }
 
+// Bluetooth links deliver audio in bursts, less buffering than this causes frequent underruns
+const BLUETOOTH_MIN_BUFFERING_MS: usize = 100;
+
 struct PlayerCallback {
     sample_buffer: Arc<Mutex<VecDeque<f32>>>,
     batch_frames_count: usize,
+    disconnected: Arc<RelaxedAtomic>,
 }
 
 impl AudioOutputCallback for PlayerCallback {
@@ -126,8 +132,16 @@ impl AudioOutputCallback for PlayerCallback {
 
         DataCallbackResult::Continue
     }
+
+    // The stream is closed when the audio route changes (for example when Bluetooth headphones
+    // connect) or after any other error. In both cases it must be reopened
+    fn on_error_after_close(&mut self, _: &mut dyn AudioOutputStreamSafe, error: oboe::Error) {
+        info!("Audio output stream closed: {error:?}");
+        self.disconnected.set(true);
+    }
 }
 
+// report_route_change is called when the playback is restarted on a new output device
 #[allow(unused_variables)]
 pub fn play_audio_loop(
     running: Arc<RelaxedAtomic>,
@@ -135,7 +149,9 @@ pub fn play_audio_loop(
     channels_count: u16,
     sample_rate: u32,
     config: AudioBufferingConfig,
-    receiver: StreamReceiver<()>,
+    mut receiver: StreamReceiver<()>,
+    mut report_buffer_event: impl FnMut(AudioBufferEvent),
+    mut report_route_change: impl FnMut(&AudioOutputDevice),
 ) -> Result<()> {
     // the client sends invalid sample rates sometimes, and we crash if we try and use one
     // (batch_frames_count ends up zero and the audio callback gets confused)
@@ -144,41 +160,82 @@ pub fn play_audio_loop(
     }
 
     let batch_frames_count = sample_rate as usize * config.batch_ms as usize / 1000;
-    let average_buffer_frames_count =
-        sample_rate as usize * config.average_buffering_ms as usize / 1000;
 
-    let sample_buffer = Arc::new(Mutex::new(VecDeque::new()));
+    let mut is_first_route = true;
+    while running.value() {
+        // Surround audio is sent by the streamer only when this client does the binaural downmix
+        let downmixer = if channels_count > 2 {
+            Some(BinauralDownmixer::new(channels_count as _, sample_rate)?)
+        } else {
+            None
+        };
+
+        // Samples buffered for the old route are dropped, they would only add latency
+        let sample_buffer = Arc::new(Mutex::new(VecDeque::new()));
+        let disconnected = Arc::new(RelaxedAtomic::new(false));
+
+        let mut stream = AudioStreamBuilder::default()
+            .set_shared()
+            .set_performance_mode(PerformanceMode::LowLatency)
+            .set_sample_rate(sample_rate as _)
+            .set_sample_rate_conversion_quality(SampleRateConversionQuality::Fastest)
+            .set_stereo()
+            .set_f32()
+            .set_frames_per_callback(batch_frames_count as _)
+            .set_output()
+            .set_usage(Usage::Game)
+            .set_callback(PlayerCallback {
+                sample_buffer: Arc::clone(&sample_buffer),
+                batch_frames_count,
+                disconnected: Arc::clone(&disconnected),
+            })
+            .open_stream()?;
+
+        stream.start()?;
+
+        let output_device = platform::audio_output_device(stream.get_device_id());
+
+        let mut average_buffering_ms = config.average_buffering_ms as usize;
+        if let Some(output_device) = &output_device {
+            info!(
+                "Playing game audio on {} (bluetooth: {})",
+                output_device.name, output_device.is_bluetooth
+            );
+
+            if output_device.is_bluetooth {
+                average_buffering_ms = average_buffering_ms.max(BLUETOOTH_MIN_BUFFERING_MS);
+            }
+
+            if !is_first_route {
+                report_route_change(output_device);
+            }
+        }
+        is_first_route = false;
 
-    let mut stream = AudioStreamBuilder::default()
-        .set_shared()
-        .set_performance_mode(PerformanceMode::LowLatency)
-        .set_sample_rate(sample_rate as _)
-        .set_sample_rate_conversion_quality(SampleRateConversionQuality::Fastest)
-        .set_stereo()
-        .set_f32()
-        .set_frames_per_callback(batch_frames_count as _)
-        .set_output()
-        .set_usage(Usage::Game)
-        .set_callback(PlayerCallback {
-            sample_buffer: Arc::clone(&sample_buffer),
+        let res = alvr_audio::receive_samples_loop(
+            || running.value() && !disconnected.value(),
+            &mut receiver,
+            sample_buffer,
+            2,
             batch_frames_count,
-        })
-        .open_stream()?;
-
-    stream.start()?;
+            sample_rate as usize * average_buffering_ms / 1000,
+            config
+                .auto_tune
+                .as_option()
+                .map(|config| alvr_audio::BufferingAutoTuner::new(config, sample_rate)),
+            downmixer,
+            &mut report_buffer_event,
+            |_| (),
+        );
 
-    alvr_audio::receive_samples_loop(
-        running,
-        receiver,
-        sample_buffer,
-        2,
-        batch_frames_count,
-        average_buffer_frames_count,
-    )
-    .ok();
+        // Note: Oboe crahes if stream.stop() is NOT called on AudioPlayer
+        stream.stop_with_timeout(0).ok();
 
-    // Note: Oboe crahes if stream.stop() is NOT called on AudioPlayer
-    stream.stop_with_timeout(0).ok();
+        // The stream socket was closed
+        if res.is_err() {
+            break;
+        }
+    }
 
     Ok(())
 }
@@ -1,6 +1,7 @@
 use crate::{
     opengl::{self, RenderViewInput},
-    ClientCoreEvent,
+    ClientCapabilities, ClientCoreContext, ClientCoreEvent, ConnectionFailureReason, EventCallback,
+    HudMessageSeverity, PresentMode, StreamConfigHook, StreamConfigProposal,
 };
 use alvr_common::{
     debug, error,
@@ -11,16 +12,22 @@ use alvr_common::{
     warn, DeviceMotion, Fov, Pose,
 };
 use alvr_packets::{ButtonEntry, ButtonValue, Tracking};
-use alvr_session::{CodecType, FoveatedRenderingConfig};
+use alvr_session::{
+    CaptionsConfig, CodecType, ColorBlindnessFilterConfig, ExternalHapticsDevice,
+    FoveatedRenderingConfig,
+};
 use std::{
     collections::VecDeque,
     ffi::{c_char, c_void, CStr, CString},
-    ptr, slice,
+    mem, ptr, slice,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
 // Core interface:
 
+static CLIENT_CORE_CONTEXT: Lazy<Mutex<Option<ClientCoreContext>>> = Lazy::new(|| Mutex::new(None));
+
 struct ReconstructedNal {
     timestamp_ns: u64,
     data: Vec<u8>,
@@ -28,6 +35,15 @@ struct ReconstructedNal {
 static NAL_QUEUE: Lazy<Mutex<VecDeque<ReconstructedNal>>> =
     Lazy::new(|| Mutex::new(VecDeque::new()));
 static HUD_MESSAGE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("".into()));
+static HUD_MESSAGE_SEVERITY: Lazy<Mutex<HudMessageSeverity>> =
+    Lazy::new(|| Mutex::new(HudMessageSeverity::Info));
+static HUD_QR_CODE_URL: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("".into()));
+static DISCOVERED_SERVERS: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("".into()));
+static CAPTION: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("".into()));
+static CAPTIONS_CONFIG: Lazy<Mutex<Option<CaptionsConfig>>> = Lazy::new(|| Mutex::new(None));
+static COLOR_BLINDNESS_FILTER: Lazy<Mutex<Option<ColorBlindnessFilterConfig>>> =
+    Lazy::new(|| Mutex::new(None));
+static PRESENT_MODE: Lazy<Mutex<PresentMode>> = Lazy::new(|| Mutex::new(PresentMode::default()));
 
 #[repr(u8)]
 pub enum AlvrCodec {
@@ -35,8 +51,40 @@ pub enum AlvrCodec {
     H265 = 1,
 }
 
+#[repr(u8)]
+pub enum AlvrExternalHapticsDevice {
+    Bhaptics = 0,
+    Owo = 1,
+}
+
+#[repr(u8)]
+pub enum AlvrConnectionFailureReason {
+    NetworkUnreachable,
+    SocketError,
+    Timeout,
+    ServerDisconnected,
+    ServerRestarting,
+    UnexpectedPacket,
+    InvalidStreamConfig,
+    UntrustedServer,
+    RejectedByFrontend,
+}
+
+#[repr(u8)]
+pub enum AlvrHudMessageSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
 #[repr(u8)]
 pub enum AlvrEvent {
+    // New variants must be added at the end, to keep the discriminants of the existing ones.
+    // Payloads must not grow past the size of the largest one (StreamingStarted), since the size of
+    // AlvrEvent is part of the ABI. Additional data is retrieved with getters
+
+    // The text, severity and QR code are retrieved with alvr_hud_message(),
+    // alvr_hud_message_severity() and alvr_hud_qr_code_url()
     HudMessageUpdated,
     StreamingStarted {
         view_width: u32,
@@ -61,6 +109,41 @@ pub enum AlvrEvent {
         codec: AlvrCodec,
     },
     FrameReady,
+    ServersDiscovered {
+        count: u64,
+    },
+    ConnectionFailed {
+        reason: AlvrConnectionFailureReason,
+    },
+    ExitRequested,
+    DisplayOffRequested,
+    PassthroughToggleRequested,
+    PerformanceOverlayToggleRequested,
+    StreamPaused,
+    StreamResumed,
+    SessionTimeRemaining {
+        remaining_s: f32,
+    },
+    RefreshRateChanged {
+        refresh_rate: f32,
+    },
+    AudioOutputChanged {
+        is_bluetooth: bool,
+    },
+    ExternalHaptics {
+        device: AlvrExternalHapticsDevice,
+        duration_s: f32,
+        frequency: f32,
+        amplitude: f32,
+    },
+    // The label is not forwarded, the frame can be saved with any file name
+    ScreenshotRequested,
+    // Apply it with alvr_update_color_blindness_filter_opengl()
+    ColorBlindnessFilterChanged,
+    // The text is retrieved with alvr_caption()
+    CaptionUpdated {
+        duration_s: f32,
+    },
 }
 
 #[repr(C)]
@@ -131,7 +214,6 @@ pub unsafe extern "C" fn alvr_log_time(tag: *const c_char) {
 
 /// On non-Android platforms, java_vm and constext should be null.
 /// NB: context must be thread safe.
-#[allow(unused_variables)]
 #[no_mangle]
 pub unsafe extern "C" fn alvr_initialize(
     java_vm: *mut c_void,
@@ -142,24 +224,153 @@ pub unsafe extern "C" fn alvr_initialize(
     refresh_rates_count: i32,
     external_decoder: bool,
 ) {
+    let capabilities = AlvrClientCapabilities {
+        struct_size: mem::size_of::<AlvrClientCapabilities>() as _,
+        recommended_view_width,
+        recommended_view_height,
+        refresh_rates,
+        refresh_rates_count,
+        external_decoder,
+        supports_foveated_encoding: false,
+        supports_extra_latency_mode: false,
+        supports_front_buffer_rendering: false,
+        runtime_name: ptr::null(),
+    };
+
+    alvr_initialize_with_capabilities(java_vm, context, &capabilities);
+}
+
+/// Fields are only ever added at the end. struct_size must be set to the size of the struct known
+/// by the caller, the fields that come after it are treated as zeroed
+#[repr(C)]
+pub struct AlvrClientCapabilities {
+    struct_size: u32,
+    recommended_view_width: u32,
+    recommended_view_height: u32,
+    refresh_rates: *const f32,
+    refresh_rates_count: i32,
+    external_decoder: bool,
+    supports_foveated_encoding: bool,
+    supports_extra_latency_mode: bool,
+    supports_front_buffer_rendering: bool,
+    // Can be null
+    runtime_name: *const c_char,
+}
+
+/// Same as alvr_initialize(), for the capabilities that were added later.
+/// On non-Android platforms, java_vm and constext should be null.
+/// NB: context must be thread safe.
+#[allow(unused_variables)]
+#[no_mangle]
+pub unsafe extern "C" fn alvr_initialize_with_capabilities(
+    java_vm: *mut c_void,
+    context: *mut c_void,
+    capabilities: *const AlvrClientCapabilities,
+) {
+    // Initializing again without alvr_destroy (for example when the activity is recreated)
+    // replaces the previous context. Only one context can exist at a time. The old context is
+    // dropped outside of the lock, since shutting it down waits for its threads
+    let old_context = CLIENT_CORE_CONTEXT.lock().take();
+    if old_context.is_some() {
+        drop(old_context);
+
+        #[cfg(target_os = "android")]
+        ndk_context::release_android_context();
+    }
+
     #[cfg(target_os = "android")]
     ndk_context::initialize_android_context(java_vm, context);
 
-    let recommended_view_resolution = UVec2::new(recommended_view_width, recommended_view_height);
+    // Callers built against an older header pass a smaller struct
+    let mut caps = mem::MaybeUninit::<AlvrClientCapabilities>::zeroed();
+    ptr::copy_nonoverlapping(
+        capabilities as *const u8,
+        caps.as_mut_ptr() as *mut u8,
+        ((*capabilities).struct_size as usize).min(mem::size_of::<AlvrClientCapabilities>()),
+    );
+    let caps = caps.assume_init();
+
+    let capabilities = ClientCapabilities {
+        default_view_resolution: UVec2::new(
+            caps.recommended_view_width,
+            caps.recommended_view_height,
+        ),
+        refresh_rates: if caps.refresh_rates.is_null() || caps.refresh_rates_count <= 0 {
+            vec![]
+        } else {
+            slice::from_raw_parts(caps.refresh_rates, caps.refresh_rates_count as _).to_vec()
+        },
+        foveated_encoding: caps.supports_foveated_encoding,
+        external_decoder: caps.external_decoder,
+        pcm_haptics_sample_rate: None,
+        extra_latency_mode: caps.supports_extra_latency_mode,
+        front_buffer_rendering: caps.supports_front_buffer_rendering,
+        overlay_layers: false,
+        runtime_name: if caps.runtime_name.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(caps.runtime_name)
+                .to_string_lossy()
+                .into_owned()
+        },
+    };
 
-    let supported_refresh_rates =
-        slice::from_raw_parts(refresh_rates, refresh_rates_count as _).to_vec();
+    *CLIENT_CORE_CONTEXT.lock() = Some(ClientCoreContext::new(capabilities));
+}
 
-    crate::initialize(
-        recommended_view_resolution,
-        supported_refresh_rates,
-        external_decoder,
-    );
+#[repr(C)]
+pub struct AlvrStreamConfigProposal {
+    view_width: u32,
+    view_height: u32,
+    // Can be edited in place. The count can only be lowered
+    refresh_rates: *mut f32,
+    refresh_rates_count: i32,
+    supports_foveated_encoding: bool,
+}
+
+/// The hook returns false to reject the connection. If the view resolution is changed, the
+/// resolutions recommended for the headset model are ignored by the streamer. Pass null to remove
+/// the hook
+#[no_mangle]
+pub extern "C" fn alvr_set_stream_config_hook(
+    hook: Option<unsafe extern "C" fn(*mut AlvrStreamConfigProposal) -> bool>,
+) {
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.set_stream_config_hook(hook.map(|hook| {
+            Box::new(move |proposal: &mut StreamConfigProposal| {
+                let mut refresh_rates = proposal.refresh_rates.clone();
+                let mut c_proposal = AlvrStreamConfigProposal {
+                    view_width: proposal.view_resolution.x,
+                    view_height: proposal.view_resolution.y,
+                    refresh_rates: refresh_rates.as_mut_ptr(),
+                    refresh_rates_count: refresh_rates.len() as _,
+                    supports_foveated_encoding: proposal.foveated_encoding,
+                };
+
+                if !unsafe { hook(&mut c_proposal) } {
+                    return Err("Connection rejected by the app".into());
+                }
+
+                let view_resolution = UVec2::new(c_proposal.view_width, c_proposal.view_height);
+                if view_resolution != proposal.view_resolution {
+                    proposal.view_resolution = view_resolution;
+                    proposal.recommended_view_resolutions = None;
+                }
+                refresh_rates.truncate(c_proposal.refresh_rates_count.max(0) as _);
+                proposal.refresh_rates = refresh_rates;
+                proposal.foveated_encoding = c_proposal.supports_foveated_encoding;
+
+                Ok(())
+            }) as StreamConfigHook
+        }));
+    }
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn alvr_destroy() {
-    crate::destroy();
+    // Dropped outside of the lock
+    let context = CLIENT_CORE_CONTEXT.lock().take();
+    drop(context);
 
     #[cfg(target_os = "android")]
     ndk_context::release_android_context();
@@ -167,91 +378,248 @@ pub unsafe extern "C" fn alvr_destroy() {
 
 #[no_mangle]
 pub extern "C" fn alvr_resume() {
-    crate::resume();
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.resume();
+    }
 }
 
 #[no_mangle]
 pub extern "C" fn alvr_pause() {
-    crate::pause();
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.pause();
+    }
 }
 
-/// Returns true if there was a new event
+/// Returns false if the URI is not valid
 #[no_mangle]
-pub extern "C" fn alvr_poll_event(out_event: *mut AlvrEvent) -> bool {
-    if let Some(event) = crate::poll_event() {
-        let event = match event {
-            ClientCoreEvent::UpdateHudMessage(message) => {
-                *HUD_MESSAGE.lock() = message;
+pub unsafe extern "C" fn alvr_pair_with_uri(uri: *const c_char) -> bool {
+    let uri = CStr::from_ptr(uri).to_str().unwrap_or_default();
+
+    CLIENT_CORE_CONTEXT
+        .lock()
+        .as_ref()
+        .map_or(false, |context| context.pair_with_uri(uri))
+}
 
-                AlvrEvent::HudMessageUpdated
+/// kiosk_config_json is a JSON-encoded KioskConfig, or null to unlock the client. Returns false
+/// if the JSON is not valid
+#[no_mangle]
+pub unsafe extern "C" fn alvr_set_kiosk_config(kiosk_config_json: *const c_char) -> bool {
+    if kiosk_config_json.is_null() {
+        crate::set_kiosk_config(None);
+
+        return true;
+    }
+
+    if let Ok(config) = serde_json::from_str(
+        CStr::from_ptr(kiosk_config_json)
+            .to_str()
+            .unwrap_or_default(),
+    ) {
+        crate::set_kiosk_config(Some(config));
+
+        true
+    } else {
+        false
+    }
+}
+
+/// trusted_networks_json is a JSON-encoded TrustedNetworksConfig, or null to allow any network.
+/// Returns false if the JSON or one of the subnets is not valid
+#[no_mangle]
+pub unsafe extern "C" fn alvr_set_trusted_networks(trusted_networks_json: *const c_char) -> bool {
+    if trusted_networks_json.is_null() {
+        return crate::set_trusted_networks(None);
+    }
+
+    if let Ok(config) = serde_json::from_str(
+        CStr::from_ptr(trusted_networks_json)
+            .to_str()
+            .unwrap_or_default(),
+    ) {
+        crate::set_trusted_networks(Some(config))
+    } else {
+        false
+    }
+}
+
+// Returns None for the events not exposed by the C API
+fn to_alvr_event(event: ClientCoreEvent) -> Option<AlvrEvent> {
+    let event = match event {
+        ClientCoreEvent::UpdateHudMessage(message) => {
+            *HUD_MESSAGE.lock() = message.to_plain_text();
+            *HUD_MESSAGE_SEVERITY.lock() = message.severity;
+            *HUD_QR_CODE_URL.lock() = message.qr_code_url.clone().unwrap_or_default();
+
+            AlvrEvent::HudMessageUpdated
+        }
+        ClientCoreEvent::ServersDiscovered(servers) => {
+            *DISCOVERED_SERVERS.lock() = serde_json::to_string(&servers).unwrap();
+
+            AlvrEvent::ServersDiscovered {
+                count: servers.len() as u64,
             }
-            ClientCoreEvent::StreamingStarted {
-                view_resolution,
-                refresh_rate_hint,
-                settings,
-            } => {
-                let foveated_rendering = settings.video.foveated_rendering.as_option();
-                AlvrEvent::StreamingStarted {
-                    view_width: view_resolution.x,
-                    view_height: view_resolution.y,
-                    refresh_rate_hint,
-                    enable_foveation: foveated_rendering.is_some(),
-                    foveation_center_size_x: foveated_rendering
-                        .map(|f| f.center_size_x)
-                        .unwrap_or_default(),
-                    foveation_center_size_y: foveated_rendering
-                        .map(|f| f.center_size_y)
-                        .unwrap_or_default(),
-                    foveation_center_shift_x: foveated_rendering
-                        .map(|f| f.center_shift_x)
-                        .unwrap_or_default(),
-                    foveation_center_shift_y: foveated_rendering
-                        .map(|f| f.center_shift_y)
-                        .unwrap_or_default(),
-                    foveation_edge_ratio_x: foveated_rendering
-                        .map(|f| f.edge_ratio_x)
-                        .unwrap_or_default(),
-                    foveation_edge_ratio_y: foveated_rendering
-                        .map(|f| f.edge_ratio_y)
-                        .unwrap_or_default(),
+        }
+        ClientCoreEvent::ConnectionFailed { reason, .. } => AlvrEvent::ConnectionFailed {
+            reason: match reason {
+                ConnectionFailureReason::NetworkUnreachable => {
+                    AlvrConnectionFailureReason::NetworkUnreachable
                 }
-            }
-            ClientCoreEvent::StreamingStopped => AlvrEvent::StreamingStopped,
-            ClientCoreEvent::Haptics {
-                device_id,
-                duration,
-                frequency,
-                amplitude,
-            } => AlvrEvent::Haptics {
-                device_id,
-                duration_s: duration.as_secs_f32(),
-                frequency,
-                amplitude,
-            },
-            ClientCoreEvent::CreateDecoder { codec, config_nal } => {
-                NAL_QUEUE.lock().push_back(ReconstructedNal {
-                    timestamp_ns: 0,
-                    data: config_nal,
-                });
-
-                AlvrEvent::CreateDecoder {
-                    codec: if matches!(codec, CodecType::H264) {
-                        AlvrCodec::H264
-                    } else {
-                        AlvrCodec::H265
-                    },
+                ConnectionFailureReason::SocketError => AlvrConnectionFailureReason::SocketError,
+                ConnectionFailureReason::Timeout => AlvrConnectionFailureReason::Timeout,
+                ConnectionFailureReason::ServerDisconnected => {
+                    AlvrConnectionFailureReason::ServerDisconnected
+                }
+                ConnectionFailureReason::ServerRestarting => {
+                    AlvrConnectionFailureReason::ServerRestarting
+                }
+                ConnectionFailureReason::UnexpectedPacket => {
+                    AlvrConnectionFailureReason::UnexpectedPacket
+                }
+                ConnectionFailureReason::InvalidStreamConfig => {
+                    AlvrConnectionFailureReason::InvalidStreamConfig
+                }
+                ConnectionFailureReason::UntrustedServer => {
+                    AlvrConnectionFailureReason::UntrustedServer
                 }
+                ConnectionFailureReason::RejectedByFrontend => {
+                    AlvrConnectionFailureReason::RejectedByFrontend
+                }
+            },
+        },
+        ClientCoreEvent::StreamingStarted {
+            view_resolution,
+            refresh_rate_hint,
+            present_mode,
+            settings,
+        } => {
+            *CAPTIONS_CONFIG.lock() = settings.headset.captions.clone().into_option();
+            *COLOR_BLINDNESS_FILTER.lock() =
+                settings.video.color_blindness_filter.clone().into_option();
+            *PRESENT_MODE.lock() = present_mode;
+
+            let foveated_rendering = settings.video.foveated_rendering.as_option();
+            AlvrEvent::StreamingStarted {
+                view_width: view_resolution.x,
+                view_height: view_resolution.y,
+                refresh_rate_hint,
+                enable_foveation: foveated_rendering.is_some(),
+                foveation_center_size_x: foveated_rendering
+                    .map(|f| f.center_size_x)
+                    .unwrap_or_default(),
+                foveation_center_size_y: foveated_rendering
+                    .map(|f| f.center_size_y)
+                    .unwrap_or_default(),
+                foveation_center_shift_x: foveated_rendering
+                    .map(|f| f.center_shift_x)
+                    .unwrap_or_default(),
+                foveation_center_shift_y: foveated_rendering
+                    .map(|f| f.center_shift_y)
+                    .unwrap_or_default(),
+                foveation_edge_ratio_x: foveated_rendering
+                    .map(|f| f.edge_ratio_x)
+                    .unwrap_or_default(),
+                foveation_edge_ratio_y: foveated_rendering
+                    .map(|f| f.edge_ratio_y)
+                    .unwrap_or_default(),
             }
-            ClientCoreEvent::FrameReady { timestamp, nal } => {
-                NAL_QUEUE.lock().push_back(ReconstructedNal {
-                    timestamp_ns: timestamp.as_nanos() as _,
-                    data: nal,
-                });
+        }
+        ClientCoreEvent::StreamingStopped => AlvrEvent::StreamingStopped,
+        ClientCoreEvent::ExitRequested => AlvrEvent::ExitRequested,
+        ClientCoreEvent::DisplayOffRequested => AlvrEvent::DisplayOffRequested,
+        ClientCoreEvent::PassthroughToggleRequested => AlvrEvent::PassthroughToggleRequested,
+        ClientCoreEvent::PerformanceOverlayToggleRequested => {
+            AlvrEvent::PerformanceOverlayToggleRequested
+        }
+        ClientCoreEvent::StreamPaused => AlvrEvent::StreamPaused,
+        ClientCoreEvent::StreamResumed => AlvrEvent::StreamResumed,
+        ClientCoreEvent::SessionTimeRemaining { remaining } => AlvrEvent::SessionTimeRemaining {
+            remaining_s: remaining.as_secs_f32(),
+        },
+        ClientCoreEvent::RefreshRateChanged { refresh_rate } => {
+            AlvrEvent::RefreshRateChanged { refresh_rate }
+        }
+        ClientCoreEvent::AudioOutputChanged { is_bluetooth, .. } => {
+            AlvrEvent::AudioOutputChanged { is_bluetooth }
+        }
+        ClientCoreEvent::Haptics {
+            device_id,
+            duration,
+            frequency,
+            amplitude,
+            ..
+        } => AlvrEvent::Haptics {
+            device_id,
+            duration_s: duration.as_secs_f32(),
+            frequency,
+            amplitude,
+        },
+        ClientCoreEvent::ExternalHaptics {
+            device,
+            duration,
+            frequency,
+            amplitude,
+        } => AlvrEvent::ExternalHaptics {
+            device: match device {
+                ExternalHapticsDevice::Bhaptics => AlvrExternalHapticsDevice::Bhaptics,
+                ExternalHapticsDevice::Owo => AlvrExternalHapticsDevice::Owo,
+            },
+            duration_s: duration.as_secs_f32(),
+            frequency,
+            amplitude,
+        },
+        ClientCoreEvent::ScreenshotRequested { .. } => AlvrEvent::ScreenshotRequested,
+        // Overlay layers are not advertised in the capabilities of the C API
+        ClientCoreEvent::OverlayLayer { .. } => return None,
+        ClientCoreEvent::ColorBlindnessFilterChanged(filter) => {
+            *COLOR_BLINDNESS_FILTER.lock() = filter;
+
+            AlvrEvent::ColorBlindnessFilterChanged
+        }
+        ClientCoreEvent::Caption { text, duration } => {
+            *CAPTION.lock() = text;
 
-                AlvrEvent::FrameReady
+            AlvrEvent::CaptionUpdated {
+                duration_s: duration.as_secs_f32(),
+            }
+        }
+        ClientCoreEvent::CreateDecoder { codec, config_nal } => {
+            NAL_QUEUE.lock().push_back(ReconstructedNal {
+                timestamp_ns: 0,
+                data: config_nal,
+            });
+
+            AlvrEvent::CreateDecoder {
+                codec: if matches!(codec, CodecType::H264) {
+                    AlvrCodec::H264
+                } else {
+                    AlvrCodec::H265
+                },
             }
-        };
+        }
+        ClientCoreEvent::FrameReady { timestamp, nal } => {
+            NAL_QUEUE.lock().push_back(ReconstructedNal {
+                timestamp_ns: timestamp.as_nanos() as _,
+                data: nal,
+            });
 
+            AlvrEvent::FrameReady
+        }
+    };
+
+    Some(event)
+}
+
+/// Returns true if there was a new event
+#[no_mangle]
+pub extern "C" fn alvr_poll_event(out_event: *mut AlvrEvent) -> bool {
+    let event = CLIENT_CORE_CONTEXT
+        .lock()
+        .as_ref()
+        .and_then(|context| context.poll_event())
+        .and_then(to_alvr_event);
+
+    if let Some(event) = event {
         unsafe { *out_event = event };
 
         true
@@ -260,6 +628,26 @@ pub extern "C" fn alvr_poll_event(out_event: *mut AlvrEvent) -> bool {
     }
 }
 
+/// Alternative to alvr_poll_event(): the callback is invoked from the thread that produced the
+/// event, as soon as it is produced. It must return quickly, streaming threads are blocked
+/// meanwhile. Events already queued are still returned by alvr_poll_event(). Pass null to go back
+/// to polling.
+/// The event can be produced inside another call of this API, so the callback should only copy the
+/// event data: it can call the getters of the event data (alvr_hud_message(), alvr_caption(), etc)
+/// but no other function, including alvr_set_event_callback() and alvr_destroy()
+#[no_mangle]
+pub extern "C" fn alvr_set_event_callback(callback: Option<unsafe extern "C" fn(AlvrEvent)>) {
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.set_event_callback(callback.map(|callback| {
+            Arc::new(move |event| {
+                if let Some(event) = to_alvr_event(event) {
+                    unsafe { callback(event) };
+                }
+            }) as EventCallback
+        }));
+    }
+}
+
 /// Call only with external decoder
 /// Returns the number of bytes of the next nal, or 0 if there are no nals ready.
 /// If out_nal or out_timestamp_ns is null, no nal is dequeued. Use to get the nal allocation size.
@@ -284,23 +672,86 @@ pub extern "C" fn alvr_poll_nal(out_nal: *mut c_char, out_timestamp_ns: *mut u64
     }
 }
 
-// Returns the length of the message. message_buffer can be null.
-#[no_mangle]
-pub extern "C" fn alvr_hud_message(message_buffer: *mut c_char) -> u64 {
-    let cstring = CString::new(HUD_MESSAGE.lock().clone()).unwrap();
-    if !message_buffer.is_null() {
+fn copy_to_c_string(string: String, buffer: *mut c_char) -> u64 {
+    let cstring = CString::new(string).unwrap();
+    if !buffer.is_null() {
         unsafe {
-            ptr::copy_nonoverlapping(
-                cstring.as_ptr(),
-                message_buffer,
-                cstring.as_bytes_with_nul().len(),
-            );
+            ptr::copy_nonoverlapping(cstring.as_ptr(), buffer, cstring.as_bytes_with_nul().len());
         }
     }
 
     cstring.as_bytes_with_nul().len() as u64
 }
 
+// Returns the length of the message. message_buffer can be null.
+#[no_mangle]
+pub extern "C" fn alvr_hud_message(message_buffer: *mut c_char) -> u64 {
+    copy_to_c_string(HUD_MESSAGE.lock().clone(), message_buffer)
+}
+
+// Severity of the last HUD message
+#[no_mangle]
+pub extern "C" fn alvr_hud_message_severity() -> AlvrHudMessageSeverity {
+    match *HUD_MESSAGE_SEVERITY.lock() {
+        HudMessageSeverity::Info => AlvrHudMessageSeverity::Info,
+        HudMessageSeverity::Warning => AlvrHudMessageSeverity::Warning,
+        HudMessageSeverity::Error => AlvrHudMessageSeverity::Error,
+    }
+}
+
+#[repr(C)]
+pub struct AlvrPresentMode {
+    // If false, keep the runtime default for extra_latency_mode
+    extra_latency_mode_set: bool,
+    extra_latency_mode: bool,
+    front_buffer_rendering: bool,
+}
+
+// Present mode negotiated for the last StreamingStarted event
+#[no_mangle]
+pub extern "C" fn alvr_present_mode() -> AlvrPresentMode {
+    let present_mode = *PRESENT_MODE.lock();
+
+    AlvrPresentMode {
+        extra_latency_mode_set: present_mode.extra_latency_mode.is_some(),
+        extra_latency_mode: present_mode.extra_latency_mode.unwrap_or_default(),
+        front_buffer_rendering: present_mode.front_buffer_rendering,
+    }
+}
+
+// Returns the length of the caption of the last CaptionUpdated event. caption_buffer can be null.
+#[no_mangle]
+pub extern "C" fn alvr_caption(caption_buffer: *mut c_char) -> u64 {
+    copy_to_c_string(CAPTION.lock().clone(), caption_buffer)
+}
+
+// Returns the length of the URL to be shown as QR code, empty if none. url_buffer can be null.
+#[no_mangle]
+pub extern "C" fn alvr_hud_qr_code_url(url_buffer: *mut c_char) -> u64 {
+    copy_to_c_string(HUD_QR_CODE_URL.lock().clone(), url_buffer)
+}
+
+// Returns the length of the JSON list of streamers of the last ServersDiscovered event, each with
+// "hostname", "ip", "version", "gpu_names" and "codec". servers_buffer can be null.
+#[no_mangle]
+pub extern "C" fn alvr_discovered_servers(servers_buffer: *mut c_char) -> u64 {
+    copy_to_c_string(DISCOVERED_SERVERS.lock().clone(), servers_buffer)
+}
+
+/// Returns false if the IP is not valid
+#[no_mangle]
+pub unsafe extern "C" fn alvr_select_server(ip: *const c_char) -> bool {
+    if let Ok(ip) = CStr::from_ptr(ip).to_str().unwrap_or_default().parse() {
+        if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+            context.select_server(ip);
+        }
+
+        true
+    } else {
+        false
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn alvr_send_views_config(fov: *const AlvrFov, ipd_m: f32) {
     let fov = slice::from_raw_parts(fov, 2);
@@ -319,20 +770,28 @@ pub unsafe extern "C" fn alvr_send_views_config(fov: *const AlvrFov, ipd_m: f32)
         },
     ];
 
-    crate::send_views_config(fov, ipd_m);
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.send_views_config(fov, ipd_m, [Quat::IDENTITY; 2]);
+    }
 }
 
 #[no_mangle]
 pub extern "C" fn alvr_send_battery(device_id: u64, gauge_value: f32, is_plugged: bool) {
-    crate::send_battery(device_id, gauge_value, is_plugged);
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.send_battery(device_id, gauge_value, is_plugged);
+    }
 }
 
 #[no_mangle]
 pub extern "C" fn alvr_send_playspace(width: f32, height: f32) {
     if width != 0.0 && height != 0.0 {
-        crate::send_playspace(Some(Vec2::new(width, height)));
+        if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+            context.send_playspace(Some(Vec2::new(width, height)));
+        }
     } else {
-        crate::send_playspace(None);
+        if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+            context.send_playspace(None);
+        }
     }
 }
 
@@ -343,7 +802,9 @@ pub extern "C" fn alvr_send_button(path_id: u64, value: AlvrButtonValue) {
         AlvrButtonValue::Scalar(value) => ButtonValue::Scalar(value),
     };
 
-    crate::send_buttons(vec![ButtonEntry { path_id, value }]);
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.send_buttons(vec![ButtonEntry { path_id, value }]);
+    }
 }
 
 #[no_mangle]
@@ -388,43 +849,86 @@ pub extern "C" fn alvr_send_tracking(
         ..Default::default()
     };
 
-    crate::send_tracking(tracking);
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.send_tracking(tracking);
+    }
 }
 
 #[no_mangle]
 pub extern "C" fn alvr_get_head_prediction_offset_ns() -> u64 {
-    crate::get_head_prediction_offset().as_nanos() as _
+    CLIENT_CORE_CONTEXT.lock().as_ref().map_or(0, |context| {
+        context.get_head_prediction_offset().as_nanos() as _
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn alvr_get_tracker_prediction_offset_ns() -> u64 {
-    crate::get_tracker_prediction_offset().as_nanos() as _
+    CLIENT_CORE_CONTEXT.lock().as_ref().map_or(0, |context| {
+        context.get_tracker_prediction_offset().as_nanos() as _
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn alvr_report_submit(target_timestamp_ns: u64, vsync_queue_ns: u64) {
-    crate::report_submit(
-        Duration::from_nanos(target_timestamp_ns),
-        Duration::from_nanos(vsync_queue_ns),
-    );
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.report_submit(
+            Duration::from_nanos(target_timestamp_ns),
+            Duration::from_nanos(vsync_queue_ns),
+        );
+    }
+}
+
+/// Call when no new frame was ready and the previous one is displayed again
+#[no_mangle]
+pub extern "C" fn alvr_report_frame_reprojected() {
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.report_frame_reprojected();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn alvr_request_refresh_rate(refresh_rate: f32) {
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.request_refresh_rate(refresh_rate);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn alvr_pause_stream() {
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.pause_stream();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn alvr_resume_stream() {
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.resume_stream();
+    }
 }
 
 /// Call only with external decoder
 #[no_mangle]
 pub extern "C" fn alvr_request_idr() {
-    crate::request_idr();
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.request_idr();
+    }
 }
 
 /// Call only with external decoder
 #[no_mangle]
 pub extern "C" fn alvr_report_frame_decoded(target_timestamp_ns: u64) {
-    crate::report_frame_decoded(Duration::from_nanos(target_timestamp_ns as _));
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.report_frame_decoded(Duration::from_nanos(target_timestamp_ns as _));
+    }
 }
 
 /// Call only with external decoder
 #[no_mangle]
 pub extern "C" fn alvr_report_compositor_start(target_timestamp_ns: u64) {
-    crate::report_compositor_start(Duration::from_nanos(target_timestamp_ns as _));
+    if let Some(context) = &*CLIENT_CORE_CONTEXT.lock() {
+        context.report_compositor_start(Duration::from_nanos(target_timestamp_ns as _));
+    }
 }
 
 /// Call only with internal decoder (Android only)
@@ -521,6 +1025,20 @@ pub unsafe extern "C" fn alvr_update_hud_message_opengl(message: *const c_char)
     opengl::update_hud_message(CStr::from_ptr(message).to_str().unwrap());
 }
 
+// Applies the filter of the last StreamingStarted or ColorBlindnessFilterChanged event
+#[no_mangle]
+pub extern "C" fn alvr_update_color_blindness_filter_opengl() {
+    opengl::set_color_blindness_filter(COLOR_BLINDNESS_FILTER.lock().as_ref());
+}
+
+// Pass an empty string to hide the caption
+#[no_mangle]
+pub unsafe extern "C" fn alvr_update_caption_opengl(caption: *const c_char) {
+    if let Some(config) = &*CAPTIONS_CONFIG.lock() {
+        opengl::update_caption(CStr::from_ptr(caption).to_str().unwrap(), config);
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn alvr_start_stream_opengl(config: AlvrStreamConfig) {
     let view_resolution = UVec2::new(config.view_resolution_width, config.view_resolution_height);
@@ -0,0 +1,86 @@
+use alvr_common::{hash_string, warn};
+use alvr_packets::{ButtonEntry, ButtonValue};
+use alvr_session::{ChordAction, ControllerChordConfig};
+use std::collections::HashSet;
+
+struct Chord {
+    buttons: Vec<u64>,
+    action: ChordAction,
+}
+
+pub struct ChordMapper {
+    chords: Vec<Chord>,
+    pressed: HashSet<u64>,
+    // Buttons that completed a chord. Their press was not forwarded so neither is their release
+    swallowed: HashSet<u64>,
+}
+
+impl ChordMapper {
+    pub fn new(config: Vec<ControllerChordConfig>) -> Self {
+        let chords = config
+            .into_iter()
+            .filter_map(|chord| {
+                if chord.buttons.is_empty() {
+                    warn!("Ignoring controller chord without buttons");
+                    return None;
+                }
+
+                Some(Chord {
+                    buttons: chord.buttons.iter().map(|path| hash_string(path)).collect(),
+                    action: chord.action,
+                })
+            })
+            .collect();
+
+        Self {
+            chords,
+            pressed: HashSet::new(),
+            swallowed: HashSet::new(),
+        }
+    }
+
+    // Returns the entries to forward to the streamer and the triggered actions
+    pub fn process(&mut self, entries: Vec<ButtonEntry>) -> (Vec<ButtonEntry>, Vec<ChordAction>) {
+        let mut forwarded = Vec::with_capacity(entries.len());
+        let mut actions = vec![];
+
+        for entry in entries {
+            let ButtonValue::Binary(pressed) = entry.value else {
+                forwarded.push(entry);
+                continue;
+            };
+
+            if !pressed {
+                self.pressed.remove(&entry.path_id);
+                if !self.swallowed.remove(&entry.path_id) {
+                    forwarded.push(entry);
+                }
+
+                continue;
+            }
+
+            if !self.pressed.insert(entry.path_id) {
+                forwarded.push(entry);
+                continue;
+            }
+
+            let mut completed_chord = false;
+            for chord in &self.chords {
+                if chord.buttons.contains(&entry.path_id)
+                    && chord.buttons.iter().all(|id| self.pressed.contains(id))
+                {
+                    actions.push(chord.action);
+                    completed_chord = true;
+                }
+            }
+
+            if completed_chord {
+                self.swallowed.insert(entry.path_id);
+            } else {
+                forwarded.push(entry);
+            }
+        }
+
+        (forwarded, actions)
+    }
+}
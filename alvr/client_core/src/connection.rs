@@ -1,33 +1,55 @@
 #![allow(clippy::if_same_then_else)]
 
 use crate::{
+    chords::ChordMapper,
     decoder::{self, DECODER_INIT_CONFIG},
+    device_profiles,
+    haptics_router::HapticsRouter,
+    journal,
     logging_backend::{LogMirrorData, LOG_CHANNEL_SENDER},
-    platform,
-    sockets::AnnouncerSocket,
+    platform, push_event,
+    sockets::{self, AnnouncerSocket},
     statistics::StatisticsManager,
     storage::Config,
-    ClientCoreEvent, EVENT_QUEUE, IS_ALIVE, IS_RESUMED, IS_STREAMING, STATISTICS_MANAGER,
+    ClientCapabilities, ClientCoreEvent, ConnectionFailureReason, DiscoveredServer, HudMessage,
+    HudMessageSeverity, PresentMode, StreamConfigProposal, CHORD_MAPPER, IS_ALIVE, IS_RESUMED,
+    IS_STREAMING, STATISTICS_MANAGER, STREAM_CONFIG_HOOK,
 };
 use alvr_audio::AudioDevice;
 use alvr_common::{
-    debug, error, glam::UVec2, info, once_cell::sync::Lazy, parking_lot::Mutex, warn, AnyhowToCon,
-    ConResult, ConnectionError, ToCon, ALVR_VERSION,
+    anyhow::{bail, Result},
+    debug, error,
+    glam::UVec2,
+    info,
+    once_cell::sync::Lazy,
+    parking_lot::Mutex,
+    warn, AnyhowToCon, ConResult, ConnectionError, RelaxedAtomic, ALVR_VERSION,
 };
 use alvr_packets::{
-    ClientConnectionResult, ClientControlPacket, ClientStatistics, Haptics, ServerControlPacket,
+    simulated_network_conditions, ClientConnectionResult, ClientControlPacket, ClientStatistics,
+    ControlPacketKind, DecoderOverloadReport, FrameDropReason, HandshakeWarning, Haptics,
+    JournalCategory, OverlayLayerHeader, ServerControlPacket, ServerHandshakeInfo,
     StreamConfigPacket, Tracking, VideoPacketHeader, VideoStreamingCapabilities, AUDIO, HAPTICS,
-    STATISTICS, TRACKING, VIDEO,
+    MICROPHONE, OVERLAY_LAYERS, STATISTICS, TRACKING, VIDEO,
+};
+use alvr_session::{
+    experiments, settings_schema::Switch, ClientDisconnectAction, ClientHardwareInfo,
+    SessionConfig, Settings, SocketBufferSize,
 };
-use alvr_session::{settings_schema::Switch, SessionConfig};
 use alvr_sockets::{
-    ControlSocketSender, PeerType, ProtoControlSocket, StreamSender, StreamSocketBuilder,
-    KEEPALIVE_INTERVAL,
+    BackpressurePolicy, ControlChannelSender, ControlSocketReceiver, ControlSocketSender, PeerType,
+    ProtoControlSocket, StreamChannelSender, StreamSocket, StreamSocketBuilder, KEEPALIVE_INTERVAL,
 };
 use serde_json as json;
 use std::{
-    collections::HashMap,
-    sync::{mpsc, Arc},
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    iter,
+    net::IpAddr,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
     thread,
     time::{Duration, Instant},
 };
@@ -43,61 +65,139 @@ const INITIAL_MESSAGE: &str = concat!(
     "next to the client entry",
 );
 const NETWORK_UNREACHABLE_MESSAGE: &str = "Cannot connect to the internet";
-// const INCOMPATIBLE_VERSIONS_MESSAGE: &str = concat!(
-//     "Streamer and client have\n",
-//     "incompatible types.\n",
-//     "Please update either the app\n",
-//     "on the PC or on the headset",
-// );
+const INCOMPATIBLE_VERSIONS_MESSAGE: &str = concat!(
+    "Streamer and client have\n",
+    "incompatible versions.\n",
+    "Please update either the app\n",
+    "on the PC or on the headset",
+);
 const STREAM_STARTING_MESSAGE: &str = "The stream will begin soon\nPlease wait...";
 const SERVER_RESTART_MESSAGE: &str = "The streamer is restarting\nPlease wait...";
 const SERVER_DISCONNECTED_MESSAGE: &str = "The streamer has disconnected.";
+const STREAM_PAUSED_MESSAGE: &str = "The stream is paused\nResume it from the dashboard";
+const THERMAL_THROTTLING_MESSAGE: &str =
+    "The headset is overheating\nPerformance is reduced until it cools down";
+const SESSION_EXPIRED_MESSAGE: &str = "Your session is over\nThank you for playing!";
+const UNTRUSTED_SERVER_MESSAGE: &str = "This headset is locked to another streamer";
+const UNTRUSTED_NETWORK_MESSAGE: &str = concat!(
+    "This network is not trusted\n",
+    "Connect to a trusted Wi-Fi network\n",
+    "to search for streamers",
+);
+const MULTIPLE_SERVERS_MESSAGE: &str = "Multiple streamers found:";
+const VPN_ACTIVE_MESSAGE: &str = concat!(
+    "A VPN is active on the headset\n",
+    "If the streamer is not found, disable it\n",
+    "or allow local network traffic",
+);
 
 const DISCOVERY_RETRY_PAUSE: Duration = Duration::from_millis(500);
 const RETRY_CONNECT_MIN_INTERVAL: Duration = Duration::from_secs(1);
 const CONNECTION_RETRY_INTERVAL: Duration = Duration::from_secs(1);
 const HANDSHAKE_ACTION_TIMEOUT: Duration = Duration::from_secs(2);
 const STREAMING_RECV_TIMEOUT: Duration = Duration::from_millis(500);
+const PACKET_SIZE_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+// Mirrored log lines sent in a row before checking again the periodic control packets
+const MAX_LOG_PACKETS_PER_BATCH: usize = 16;
+// Streamers retry connecting every second. Collect them for a while before choosing one
+const SERVER_COLLECTION_WINDOW: Duration = Duration::from_secs(2);
+const DISCOVERED_SERVER_TIMEOUT: Duration = Duration::from_secs(5);
+// Used when the frontend doesn't select any of the discovered streamers
+const SERVER_SELECTION_TIMEOUT: Duration = Duration::from_secs(30);
 
 const MAX_UNREAD_PACKETS: usize = 10; // Applies per stream
 
+// Drops because of decoder saturation within the window that trigger an overload report
+const DECODER_OVERLOAD_DROPS: usize = 3;
+const DECODER_OVERLOAD_WINDOW: Duration = Duration::from_secs(1);
+
 static DISCONNECT_SERVER_NOTIFIER: Lazy<Mutex<Option<mpsc::Sender<()>>>> =
     Lazy::new(|| Mutex::new(None));
 
-pub static CONTROL_SENDER: Lazy<Mutex<Option<ControlSocketSender<ClientControlPacket>>>> =
+// Streamer IP and trust token scanned from the dashboard QR code
+pub static PAIRING_TARGET: Lazy<Mutex<Option<(IpAddr, u64)>>> = Lazy::new(|| Mutex::new(None));
+
+// Streamer chosen by the frontend after a ServersDiscovered event
+pub static SELECTED_SERVER: Lazy<Mutex<Option<IpAddr>>> = Lazy::new(|| Mutex::new(None));
+
+// Reported by the streamer once its encoder is created
+static ENCODER_INTRA_REFRESH: RelaxedAtomic = RelaxedAtomic::new(false);
+
+pub static CONTROL_SENDER: Lazy<Mutex<Option<ControlChannelSender<ClientControlPacket>>>> =
     Lazy::new(|| Mutex::new(None));
-pub static TRACKING_SENDER: Lazy<Mutex<Option<StreamSender<Tracking>>>> =
+pub static TRACKING_SENDER: Lazy<Mutex<Option<StreamChannelSender<Tracking>>>> =
     Lazy::new(|| Mutex::new(None));
-pub static STATISTICS_SENDER: Lazy<Mutex<Option<StreamSender<ClientStatistics>>>> =
+pub static STATISTICS_SENDER: Lazy<Mutex<Option<StreamChannelSender<ClientStatistics>>>> =
     Lazy::new(|| Mutex::new(None));
 
-fn set_hud_message(message: &str) {
-    let message = format!(
-        "ALVR v{}\nhostname: {}\nIP: {}\n\n{message}",
-        *ALVR_VERSION,
-        Config::load().hostname,
-        platform::local_ip(),
-    );
+fn push_hud_message(title: String, severity: HudMessageSeverity, message: &str) {
+    let config = Config::load();
+
+    let hide_connection_details = config
+        .kiosk
+        .map_or(false, |kiosk| kiosk.hide_connection_details);
 
-    EVENT_QUEUE
-        .lock()
-        .push_back(ClientCoreEvent::UpdateHudMessage(message));
+    let (body, qr_code_url) = if hide_connection_details {
+        (message.to_owned(), None)
+    } else {
+        (
+            format!(
+                "hostname: {}\nIP: {}\n\n{message}",
+                config.hostname,
+                platform::local_ip(),
+            ),
+            config.dashboard_url,
+        )
+    };
+
+    push_event(ClientCoreEvent::UpdateHudMessage(HudMessage {
+        title,
+        body,
+        severity,
+        qr_code_url,
+    }));
+}
+
+fn set_hud_message(severity: HudMessageSeverity, message: &str) {
+    push_hud_message(format!("ALVR v{}", *ALVR_VERSION), severity, message);
 }
 
-pub fn connection_lifecycle_loop(
-    recommended_view_resolution: UVec2,
-    supported_refresh_rates: Vec<f32>,
-) {
-    set_hud_message(INITIAL_MESSAGE);
+// The pairing instructions can be replaced by a message set by the streamer admin
+fn set_lobby_hud_message() {
+    if let Some(welcome_message) = Config::load().welcome_message {
+        push_hud_message(
+            welcome_message.title,
+            HudMessageSeverity::Info,
+            &welcome_message.body,
+        );
+    } else {
+        set_hud_message(HudMessageSeverity::Info, INITIAL_MESSAGE);
+    }
+}
+
+pub fn connection_lifecycle_loop(capabilities: ClientCapabilities) {
+    set_lobby_hud_message();
 
     while IS_ALIVE.value() {
         if IS_RESUMED.value() {
-            if let Err(e) =
-                connection_pipeline(recommended_view_resolution, supported_refresh_rates.clone())
-            {
-                let message = format!("Connection error:\n{e}\nCheck the PC for more details");
-                set_hud_message(&message);
-                error!("Connection error: {e}");
+            match connection_pipeline(&capabilities) {
+                Ok(ClientDisconnectAction::ReturnToLobby) => (),
+                Ok(ClientDisconnectAction::QuitApp) => {
+                    push_event(ClientCoreEvent::ExitRequested);
+                }
+                Ok(ClientDisconnectAction::RestartDiscovery) => {
+                    set_lobby_hud_message();
+
+                    continue;
+                }
+                Ok(ClientDisconnectAction::TurnOffDisplay) => {
+                    push_event(ClientCoreEvent::DisplayOffRequested);
+                }
+                Err(e) => {
+                    let message = format!("Connection error:\n{e}\nCheck the PC for more details");
+                    set_hud_message(HudMessageSeverity::Error, &message);
+                    error!("Connection error: {e}");
+                }
             }
         } else {
             debug!("Skip try connection because the device is sleeping");
@@ -107,83 +207,456 @@ pub fn connection_lifecycle_loop(
     }
 }
 
-fn connection_pipeline(
-    recommended_view_resolution: UVec2,
-    supported_refresh_rates: Vec<f32>,
-) -> ConResult {
-    let (mut proto_control_socket, server_ip) = {
+// Keeps track of the streamers trying to connect during discovery. Other streamers are put on
+// standby until one is chosen, in order: frontend selection, last used streamer, the only one found
+#[derive(Default)]
+struct ServerSelector {
+    candidates: Vec<(DiscoveredServer, Instant)>,
+    collection_start: Option<Instant>,
+    announced: Vec<DiscoveredServer>,
+    announce_time: Option<Instant>,
+}
+
+impl ServerSelector {
+    fn should_connect(&mut self, server: &DiscoveredServer) -> bool {
+        let now = Instant::now();
+
+        self.candidates
+            .retain(|(s, seen)| s.ip != server.ip && now - *seen < DISCOVERED_SERVER_TIMEOUT);
+        self.candidates.push((server.clone(), now));
+        self.candidates.sort_by_key(|(s, _)| s.ip);
+
+        if let Some(ip) = *SELECTED_SERVER.lock() {
+            if self.candidates.iter().any(|(s, _)| s.ip == ip) {
+                return server.ip == ip;
+            }
+        }
+
+        if let Some(hostname) = Config::load().last_server_hostname {
+            if self
+                .candidates
+                .iter()
+                .any(|(s, _)| s.hostname.eq_ignore_ascii_case(&hostname))
+            {
+                return server.hostname.eq_ignore_ascii_case(&hostname);
+            }
+        }
+
+        if now < *self.collection_start.get_or_insert(now) + SERVER_COLLECTION_WINDOW {
+            return false;
+        }
+
+        if self.candidates.len() == 1 {
+            return true;
+        }
+
+        let servers = self
+            .candidates
+            .iter()
+            .map(|(s, _)| s.clone())
+            .collect::<Vec<_>>();
+        if servers != self.announced {
+            let list = servers
+                .iter()
+                .map(|s| format!("{} ({}): {}", s.hostname, s.ip, s.summary()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            set_hud_message(
+                HudMessageSeverity::Info,
+                &format!("{MULTIPLE_SERVERS_MESSAGE}\n{list}"),
+            );
+
+            push_event(ClientCoreEvent::ServersDiscovered(servers.clone()));
+
+            self.announced = servers;
+        }
+
+        now > *self.announce_time.get_or_insert(now) + SERVER_SELECTION_TIMEOUT
+            && server.ip == self.announced[0].ip
+    }
+}
+
+struct HandshakeFailure {
+    reason: ConnectionFailureReason,
+    message: String,
+}
+
+impl HandshakeFailure {
+    fn new(reason: ConnectionFailureReason, message: impl Display) -> Self {
+        Self {
+            reason,
+            message: message.to_string(),
+        }
+    }
+}
+
+trait ToHandshakeFailure<T> {
+    fn fail_with(self, reason: ConnectionFailureReason) -> Result<T, HandshakeFailure>;
+}
+
+impl<T, E: Display> ToHandshakeFailure<T> for Result<T, E> {
+    fn fail_with(self, reason: ConnectionFailureReason) -> Result<T, HandshakeFailure> {
+        self.map_err(|e| HandshakeFailure::new(reason, e))
+    }
+}
+
+// Connection errors carry the timeout information, which takes precedence over the given reason
+fn timeout_or(reason: ConnectionFailureReason) -> impl Fn(ConnectionError) -> HandshakeFailure {
+    move |e| match e {
+        ConnectionError::TryAgain(e) => HandshakeFailure::new(ConnectionFailureReason::Timeout, e),
+        ConnectionError::Other(e) => HandshakeFailure::new(reason, e),
+    }
+}
+
+struct HandshakeOutput {
+    settings: Settings,
+    view_resolution: UVec2,
+    refresh_rate_hint: f32,
+    game_audio_sample_rate: u32,
+    game_audio_channels: u16,
+    slice_count: u32,
+    max_buffering_frames: Option<f32>,
+    present_mode: PresentMode,
+    overlay_layers: bool,
+    control_sender: ControlSocketSender<ClientControlPacket>,
+    control_receiver: ControlSocketReceiver<ServerControlPacket>,
+    stream_socket: StreamSocket,
+}
+
+// Does nothing if the control socket is not connected
+fn send_control_packet(kind: ControlPacketKind, packet: ClientControlPacket) -> Result<()> {
+    if let Some(sender) = &*CONTROL_SENDER.lock() {
+        sender.send(packet)?;
+    } else {
+        return Ok(());
+    }
+
+    if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+        stats.report_control_packet_sent(kind);
+    }
+
+    Ok(())
+}
+
+fn report_frame_drop(reason: FrameDropReason) {
+    warn!("Dropped video frame. Reason: {}", reason.label());
+
+    if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+        stats.report_frame_dropped(reason);
+    }
+}
+
+fn decode_overlay_png(png: &[u8]) -> Result<(UVec2, Vec<u8>)> {
+    let mut reader = png::Decoder::new(png).read_info()?;
+
+    let mut rgba = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut rgba)?;
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        bail!("Unexpected overlay layer format");
+    }
+    rgba.truncate(info.buffer_size());
+
+    Ok((UVec2::new(info.width, info.height), rgba))
+}
+
+fn report_handshake_failure(failure: HandshakeFailure) {
+    let HandshakeFailure { reason, message } = failure;
+
+    info!("Handshake failed ({reason:?}): {message}");
+    // Not recorded while waiting for a network, it would flood the journal
+    if reason != ConnectionFailureReason::NetworkUnreachable {
+        journal::record(
+            JournalCategory::Error,
+            format!("Connection failed ({reason:?}): {message}"),
+        );
+    }
+
+    match reason {
+        ConnectionFailureReason::NetworkUnreachable => {
+            set_hud_message(HudMessageSeverity::Warning, NETWORK_UNREACHABLE_MESSAGE);
+        }
+        ConnectionFailureReason::ServerRestarting => {
+            set_hud_message(HudMessageSeverity::Info, SERVER_RESTART_MESSAGE)
+        }
+        ConnectionFailureReason::ServerDisconnected => {
+            set_hud_message(HudMessageSeverity::Warning, SERVER_DISCONNECTED_MESSAGE)
+        }
+        ConnectionFailureReason::UnexpectedPacket => {
+            set_hud_message(HudMessageSeverity::Error, "Unexpected packet")
+        }
+        ConnectionFailureReason::UntrustedServer => {
+            set_hud_message(HudMessageSeverity::Warning, UNTRUSTED_SERVER_MESSAGE)
+        }
+        ConnectionFailureReason::RejectedByFrontend => {
+            set_hud_message(HudMessageSeverity::Warning, &message)
+        }
+        ConnectionFailureReason::SocketError
+        | ConnectionFailureReason::Timeout
+        | ConnectionFailureReason::InvalidStreamConfig => set_hud_message(
+            HudMessageSeverity::Error,
+            &format!("Connection error:\n{message}\nCheck the PC for more details"),
+        ),
+    }
+
+    push_event(ClientCoreEvent::ConnectionFailed { reason, message });
+
+    if reason == ConnectionFailureReason::NetworkUnreachable {
+        thread::sleep(RETRY_CONNECT_MIN_INTERVAL);
+
+        set_lobby_hud_message();
+    }
+}
+
+// Returns None if the client is shutting down. All sockets created here are owned by the function
+// or by its output, so any failure closes them before returning.
+fn handshake(
+    capabilities: &ClientCapabilities,
+) -> Result<Option<HandshakeOutput>, HandshakeFailure> {
+    use ConnectionFailureReason as Reason;
+
+    let kiosk_config = Config::load().kiosk.unwrap_or_default();
+    let trusted_networks = Config::load().trusted_networks.unwrap_or_default();
+
+    // The announcer and listener sockets are scoped to the discovery phase
+    let (mut proto_control_socket, server) = {
         let config = Config::load();
-        let announcer_socket = AnnouncerSocket::new(&config.hostname).to_con()?;
-        let listener_socket =
-            alvr_sockets::get_server_listener(HANDSHAKE_ACTION_TIMEOUT).to_con()?;
+        let announcer_socket =
+            AnnouncerSocket::new(&config.hostname).fail_with(Reason::SocketError)?;
+        let listener_socket = alvr_sockets::get_server_listener(HANDSHAKE_ACTION_TIMEOUT)
+            .fail_with(Reason::SocketError)?;
+
+        let pairing_server_ip = PAIRING_TARGET.lock().map(|(ip, _)| ip);
+        if let Some(vpn_ip) = sockets::vpn_local_ip(pairing_server_ip) {
+            warn!("Traffic is routed through a VPN interface ({vpn_ip})");
+            set_hud_message(
+                HudMessageSeverity::Warning,
+                &format!("{VPN_ACTIVE_MESSAGE}\n\n{INITIAL_MESSAGE}"),
+            );
+        }
 
+        let mut broadcast_failed = false;
+        let mut untrusted_network = false;
+        let mut incompatible_server_reported = false;
+        let mut server_selector = ServerSelector::default();
         loop {
             if !IS_ALIVE.value() {
-                return Ok(());
+                return Ok(None);
             }
 
-            if let Err(e) = announcer_socket.broadcast() {
-                warn!("Broadcast error: {e:?}");
+            // Don't announce the headset nor accept any streamer outside of trusted networks
+            if !trusted_networks.trusts_wifi_ssid(platform::wifi_ssid().as_deref()) {
+                if !untrusted_network {
+                    set_hud_message(HudMessageSeverity::Warning, UNTRUSTED_NETWORK_MESSAGE);
+                    untrusted_network = true;
+                }
 
-                set_hud_message(NETWORK_UNREACHABLE_MESSAGE);
+                thread::sleep(DISCOVERY_RETRY_PAUSE);
 
-                thread::sleep(RETRY_CONNECT_MIN_INTERVAL);
+                continue;
+            } else if untrusted_network {
+                set_lobby_hud_message();
+                untrusted_network = false;
+            }
 
-                set_hud_message(INITIAL_MESSAGE);
+            // Broadcasting is skipped when pairing since the streamer address is already known
+            if let Some((server_ip, token)) = *PAIRING_TARGET.lock() {
+                if let Err(e) = announcer_socket.send_pairing(server_ip, token) {
+                    warn!("Pairing error: {e:?}");
 
-                return Ok(());
+                    return Err(HandshakeFailure::new(Reason::NetworkUnreachable, e));
+                }
+            } else if let Err(e) = announcer_socket.broadcast() {
+                // The streamer can still connect if it knows the headset IP (client added
+                // manually), so keep listening even if broadcasts cannot leave this network
+                if !broadcast_failed {
+                    warn!("Broadcast error: {e:?}");
+                    broadcast_failed = true;
+                }
             }
 
-            if let Ok(pair) = ProtoControlSocket::connect_to(
+            if let Ok((mut socket, server_ip)) = ProtoControlSocket::connect_to(
                 DISCOVERY_RETRY_PAUSE,
                 PeerType::Server(&listener_socket),
             ) {
-                break pair;
+                let info = match socket.recv::<ServerHandshakeInfo>(HANDSHAKE_ACTION_TIMEOUT) {
+                    Ok(info) => info,
+                    Err(ConnectionError::TryAgain(_)) => continue,
+                    // Streamers of other major versions may send an info that cannot be parsed
+                    Err(ConnectionError::Other(e)) => {
+                        debug!("Invalid handshake info from {server_ip}: {e}");
+                        if !incompatible_server_reported {
+                            set_hud_message(
+                                HudMessageSeverity::Error,
+                                INCOMPATIBLE_VERSIONS_MESSAGE,
+                            );
+                            incompatible_server_reported = true;
+                        }
+
+                        continue;
+                    }
+                };
+
+                let server = DiscoveredServer {
+                    hostname: info.hostname,
+                    ip: server_ip,
+                    version: info.version,
+                    gpu_names: info.gpu_names,
+                    codec: info.codec,
+                };
+
+                if info.protocol_id != alvr_common::protocol_id() {
+                    if !incompatible_server_reported {
+                        warn!(
+                            "Streamer {} has incompatible version v{}",
+                            server.hostname, server.version
+                        );
+                        set_hud_message(
+                            HudMessageSeverity::Error,
+                            &format!(
+                                "{INCOMPATIBLE_VERSIONS_MESSAGE}\nStreamer: v{}\nClient: v{}",
+                                server.version, *ALVR_VERSION
+                            ),
+                        );
+                        incompatible_server_reported = true;
+                    }
+                    socket.send(&ClientConnectionResult::ClientStandby).ok();
+
+                    continue;
+                }
+
+                // Streamers outside of the trusted subnets are kept on standby, so that other
+                // streamers can still connect. A streamer that is being paired is always chosen
+                if !trusted_networks.trusts_server_ip(server_ip)
+                    || (PAIRING_TARGET.lock().is_none() && !server_selector.should_connect(&server))
+                {
+                    socket.send(&ClientConnectionResult::ClientStandby).ok();
+
+                    continue;
+                }
+
+                // Once connected the client is trusted, the token is not valid anymore
+                *PAIRING_TARGET.lock() = None;
+
+                break (socket, server);
             }
         }
     };
+    let server_ip = server.ip;
+    let server_hostname = server.hostname.clone();
 
-    let (disconnect_sender, disconnect_receiver) = mpsc::channel();
-    *DISCONNECT_SERVER_NOTIFIER.lock() = Some(disconnect_sender);
+    if !kiosk_config.trusted_server_ips.is_empty()
+        && !kiosk_config.trusted_server_ips.contains(&server_ip)
+    {
+        return Err(HandshakeFailure::new(
+            Reason::UntrustedServer,
+            format!("Streamer {server_ip} is not trusted"),
+        ));
+    }
 
-    struct DropGuard;
-    impl Drop for DropGuard {
-        fn drop(&mut self) {
-            *DISCONNECT_SERVER_NOTIFIER.lock() = None;
-        }
+    let mut proposal = StreamConfigProposal {
+        server_ip,
+        view_resolution: capabilities.default_view_resolution,
+        recommended_view_resolutions: device_profiles::recommended_view_resolutions(
+            &platform::device_model(),
+        ),
+        refresh_rates: capabilities.refresh_rates.clone(),
+        foveated_encoding: capabilities.foveated_encoding,
+    };
+    if let Some(hook) = &mut *STREAM_CONFIG_HOOK.lock() {
+        hook(&mut proposal).map_err(|e| HandshakeFailure::new(Reason::RejectedByFrontend, e))?;
+    }
+    if proposal.view_resolution.min_element() == 0 || proposal.refresh_rates.is_empty() {
+        return Err(HandshakeFailure::new(
+            Reason::InvalidStreamConfig,
+            "The stream config hook left no view resolution or refresh rate",
+        ));
     }
-    let _connection_drop_guard = DropGuard;
 
     let microphone_sample_rate = AudioDevice::new_input(None)
         .unwrap()
         .input_sample_rate()
         .unwrap();
 
+    #[cfg(target_os = "android")]
+    let max_decoder_resolutions = platform::max_decoder_resolutions();
+    #[cfg(not(target_os = "android"))]
+    let max_decoder_resolutions = vec![];
+
     proto_control_socket
         .send(&ClientConnectionResult::ConnectionAccepted {
             client_protocol_id: alvr_common::protocol_id(),
             display_name: platform::device_model(),
             server_ip,
+            hardware_info: ClientHardwareInfo {
+                soc_model: platform::soc_model(),
+                os_version: platform::os_version(),
+                runtime_name: capabilities.runtime_name.clone(),
+                available_ram_mb: platform::available_ram_mb(),
+                decoders: platform::video_decoders(),
+            },
             streaming_capabilities: Some(VideoStreamingCapabilities {
-                default_view_resolution: recommended_view_resolution,
-                supported_refresh_rates,
+                default_view_resolution: proposal.view_resolution,
+                supported_refresh_rates: proposal.refresh_rates,
                 microphone_sample_rate,
+                supports_foveated_encoding: proposal.foveated_encoding,
+                max_decoder_resolutions,
+                supports_binaural_downmix: true,
+                pcm_haptics_sample_rate: capabilities.pcm_haptics_sample_rate,
+                recommended_view_resolutions: proposal.recommended_view_resolutions,
+                supports_extra_latency_mode: capabilities.extra_latency_mode,
+                supports_front_buffer_rendering: capabilities.front_buffer_rendering,
+                supported_experiments: experiments::supported_experiments(),
+                supports_overlay_layers: capabilities.overlay_layers,
+                wifi_link: platform::wifi_link_info(),
             }),
         })
-        .to_con()?;
-    let config_packet =
-        proto_control_socket.recv::<StreamConfigPacket>(HANDSHAKE_ACTION_TIMEOUT)?;
+        .fail_with(Reason::ServerDisconnected)?;
+    let config_packet = proto_control_socket
+        .recv::<StreamConfigPacket>(HANDSHAKE_ACTION_TIMEOUT)
+        .map_err(timeout_or(Reason::ServerDisconnected))?;
+
+    let negotiated_config =
+        json::from_str::<HashMap<String, json::Value>>(&config_packet.negotiated)
+            .fail_with(Reason::InvalidStreamConfig)?;
+
+    // Checked before storing anything received from the streamer
+    if !kiosk_config.trusted_server_hostnames.is_empty()
+        && !kiosk_config
+            .trusted_server_hostnames
+            .iter()
+            .any(|hostname| hostname.eq_ignore_ascii_case(&server_hostname))
+    {
+        return Err(HandshakeFailure::new(
+            Reason::UntrustedServer,
+            format!("Streamer {server_hostname} is not trusted"),
+        ));
+    }
 
     let settings = {
         let mut session_desc = SessionConfig::default();
         session_desc
-            .merge_from_json(&json::from_str(&config_packet.session).to_con()?)
-            .to_con()?;
+            .merge_from_json(
+                &json::from_str(&config_packet.session).fail_with(Reason::InvalidStreamConfig)?,
+            )
+            .fail_with(Reason::InvalidStreamConfig)?;
         session_desc.to_settings()
     };
 
-    let negotiated_config =
-        json::from_str::<HashMap<String, json::Value>>(&config_packet.negotiated).to_con()?;
+    {
+        let mut config = Config::load();
+        config.welcome_message = settings
+            .connection
+            .lobby_welcome_message
+            .clone()
+            .into_option();
+        config.dashboard_url = Some(format!(
+            "http://{server_ip}:{}",
+            settings.connection.web_server_port
+        ));
+        config.last_server_hostname = Some(server_hostname);
+        config.wifi_lock_only_while_streaming = settings.connection.wifi_lock_only_while_streaming;
+        config.store();
+    }
 
     let view_resolution = negotiated_config
         .get("view_resolution")
@@ -197,47 +670,95 @@ fn connection_pipeline(
         .get("game_audio_sample_rate")
         .and_then(|v| v.as_u64())
         .unwrap_or(44100) as u32;
-
-    let streaming_start_event = ClientCoreEvent::StreamingStarted {
-        view_resolution,
-        refresh_rate_hint,
-        settings: Box::new(settings.clone()),
+    let game_audio_channels = negotiated_config
+        .get("game_audio_channels")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(2) as u16;
+    let slice_count = negotiated_config
+        .get("slice_count")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1) as u32;
+    // Set by the streamer latency budget
+    let max_buffering_frames = negotiated_config
+        .get("max_buffering_frames")
+        .and_then(|v| v.as_f64())
+        .map(|v| v as f32);
+    let present_mode = PresentMode {
+        extra_latency_mode: negotiated_config
+            .get("extra_latency_mode")
+            .and_then(|v| v.as_bool()),
+        front_buffer_rendering: negotiated_config
+            .get("front_buffer_rendering")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
     };
-
-    *STATISTICS_MANAGER.lock() = Some(StatisticsManager::new(
-        settings.connection.statistics_history_size,
-        Duration::from_secs_f32(1.0 / refresh_rate_hint),
-        if let Switch::Enabled(config) = settings.headset.controllers {
-            config.steamvr_pipeline_frames
-        } else {
-            0.0
-        },
-    ));
+    let packet_size_probe = negotiated_config
+        .get("packet_size_probe")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let overlay_layers = negotiated_config
+        .get("overlay_layers")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let enabled_experiments = negotiated_config
+        .get("experiments")
+        .and_then(|v| json::from_value::<Vec<String>>(v.clone()).ok())
+        .unwrap_or_default();
+    experiments::set_enabled(&enabled_experiments);
+    if !enabled_experiments.is_empty() {
+        info!("Enabled experiments: {}", enabled_experiments.join(", "));
+    }
+    let handshake_warnings = negotiated_config
+        .get("handshake_warnings")
+        .and_then(|v| json::from_value::<Vec<HandshakeWarning>>(v.clone()).ok())
+        .unwrap_or_default();
+    for warning in &handshake_warnings {
+        warn!("{}", warning.message);
+    }
 
     let (mut control_sender, mut control_receiver) = proto_control_socket
         .split(STREAMING_RECV_TIMEOUT)
-        .to_con()?;
+        .fail_with(Reason::SocketError)?;
 
-    match control_receiver.recv(HANDSHAKE_ACTION_TIMEOUT) {
+    // Streamer settings are not known before this point
+    let handshake_timeout = Duration::from_millis(settings.connection.handshake_timeout_ms);
+
+    match control_receiver.recv(handshake_timeout) {
         Ok(ServerControlPacket::StartStream) => {
             info!("Stream starting");
-            set_hud_message(STREAM_STARTING_MESSAGE);
+            if handshake_warnings.is_empty() {
+                set_hud_message(
+                    HudMessageSeverity::Info,
+                    &format!("Streamer: {}\n{STREAM_STARTING_MESSAGE}", server.summary()),
+                );
+            } else {
+                let warnings = handshake_warnings
+                    .iter()
+                    .map(|warning| format!("- {}", warning.message))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                set_hud_message(
+                    HudMessageSeverity::Warning,
+                    &format!(
+                        "Streamer: {}\n{STREAM_STARTING_MESSAGE}\n\n{warnings}",
+                        server.summary()
+                    ),
+                );
+            }
         }
         Ok(ServerControlPacket::Restarting) => {
-            info!("Server restarting");
-            set_hud_message(SERVER_RESTART_MESSAGE);
-            return Ok(());
-        }
-        Err(e) => {
-            info!("Server disconnected. Cause: {e}");
-            set_hud_message(SERVER_DISCONNECTED_MESSAGE);
-            return Ok(());
+            return Err(HandshakeFailure::new(
+                Reason::ServerRestarting,
+                "Server restarting",
+            ));
         }
-        _ => {
-            info!("Unexpected packet");
-            set_hud_message("Unexpected packet");
-            return Ok(());
+        Ok(_) => {
+            return Err(HandshakeFailure::new(
+                Reason::UnexpectedPacket,
+                "Expected StartStream packet",
+            ));
         }
+        Err(e) => return Err(timeout_or(Reason::ServerDisconnected)(e)),
     }
 
     let stream_socket_builder = StreamSocketBuilder::listen_for_server(
@@ -247,58 +768,222 @@ fn connection_pipeline(
         settings.connection.client_send_buffer_bytes,
         settings.connection.client_recv_buffer_bytes,
     )
-    .to_con()?;
+    .fail_with(Reason::SocketError)?;
 
-    if let Err(e) = control_sender.send(&ClientControlPacket::StreamReady) {
-        info!("Server disconnected. Cause: {e:?}");
-        set_hud_message(SERVER_DISCONNECTED_MESSAGE);
-        return Ok(());
+    control_sender
+        .send(ClientControlPacket::StreamReady)
+        .fail_with(Reason::ServerDisconnected)?;
+
+    let mut stream_socket = stream_socket_builder
+        .accept_from_server(
+            server_ip,
+            settings.connection.stream_port,
+            settings.connection.packet_size as _,
+            handshake_timeout,
+            settings
+                .connection
+                .network_simulation
+                .as_option()
+                .map(simulated_network_conditions)
+                .unwrap_or_default(),
+        )
+        .map_err(timeout_or(Reason::SocketError))?;
+    if matches!(
+        settings.connection.client_recv_buffer_bytes,
+        SocketBufferSize::Autotune
+    ) {
+        stream_socket.enable_recv_buffer_autotune();
     }
 
-    let mut stream_socket = stream_socket_builder.accept_from_server(
-        server_ip,
-        settings.connection.stream_port,
-        settings.connection.packet_size as _,
-        HANDSHAKE_ACTION_TIMEOUT,
-    )?;
+    // The streamer sends the probes right after connecting and waits for the report before
+    // starting the streams
+    if packet_size_probe {
+        let size = stream_socket.recv_packet_size_probes(PACKET_SIZE_PROBE_TIMEOUT);
+        control_sender
+            .send(ClientControlPacket::PacketSizeProbeReport(
+                size.map(|size| size as u32),
+            ))
+            .fail_with(Reason::ServerDisconnected)?;
+
+        if let Some(size) = size {
+            info!("Jumbo frames enabled, packet size: {size}B");
+            stream_socket.set_max_packet_size(size);
+        }
+    }
 
     info!("Connected to server");
+    journal::record(
+        JournalCategory::Connection,
+        format!("Connected to streamer at {server_ip}"),
+    );
+
+    Ok(Some(HandshakeOutput {
+        settings,
+        view_resolution,
+        refresh_rate_hint,
+        game_audio_sample_rate,
+        game_audio_channels,
+        slice_count,
+        max_buffering_frames,
+        present_mode,
+        overlay_layers,
+        control_sender,
+        control_receiver,
+        stream_socket,
+    }))
+}
+
+fn connection_pipeline(capabilities: &ClientCapabilities) -> ConResult<ClientDisconnectAction> {
+    let HandshakeOutput {
+        settings,
+        view_resolution,
+        refresh_rate_hint,
+        game_audio_sample_rate,
+        game_audio_channels,
+        slice_count,
+        max_buffering_frames,
+        present_mode,
+        overlay_layers,
+        control_sender,
+        mut control_receiver,
+        mut stream_socket,
+    } = match handshake(capabilities) {
+        Ok(Some(output)) => output,
+        Ok(None) => return Ok(ClientDisconnectAction::ReturnToLobby),
+        Err(failure) => {
+            report_handshake_failure(failure);
+
+            return Ok(ClientDisconnectAction::ReturnToLobby);
+        }
+    };
+
+    let disconnect_action = settings.connection.client_disconnect_action;
+
+    let (disconnect_sender, disconnect_receiver) = mpsc::channel();
+    *DISCONNECT_SERVER_NOTIFIER.lock() = Some(disconnect_sender);
+
+    struct DropGuard;
+    impl Drop for DropGuard {
+        fn drop(&mut self) {
+            *DISCONNECT_SERVER_NOTIFIER.lock() = None;
+        }
+    }
+    let _connection_drop_guard = DropGuard;
+
+    let streaming_start_event = ClientCoreEvent::StreamingStarted {
+        view_resolution,
+        refresh_rate_hint,
+        present_mode,
+        settings: Box::new(settings.clone()),
+    };
+
+    *CHORD_MAPPER.lock() = settings
+        .headset
+        .controllers
+        .as_option()
+        .map(|config| ChordMapper::new(config.chords.clone()));
+
+    *STATISTICS_MANAGER.lock() = Some(StatisticsManager::new(
+        settings.connection.statistics_history_size,
+        Duration::from_secs_f32(1.0 / refresh_rate_hint),
+        if let Switch::Enabled(config) = settings.headset.controllers {
+            config.steamvr_pipeline_frames
+        } else {
+            0.0
+        },
+    ));
 
     {
         let config = &mut *DECODER_INIT_CONFIG.lock();
 
-        config.max_buffering_frames = settings.video.max_buffering_frames;
+        config.max_buffering_frames =
+            max_buffering_frames.unwrap_or(settings.video.max_buffering_frames);
         config.buffering_history_weight = settings.video.buffering_history_weight;
+        config.slice_count = slice_count;
+        config.priming_frames = settings.video.decoder_priming_frames;
         config.options = settings.video.mediacodec_extra_options;
     }
 
-    let mut video_receiver =
-        stream_socket.subscribe_to_stream::<VideoPacketHeader>(VIDEO, MAX_UNREAD_PACKETS);
-    let game_audio_receiver = stream_socket.subscribe_to_stream(AUDIO, MAX_UNREAD_PACKETS);
-    let tracking_sender = stream_socket.request_stream(TRACKING);
-    let mut haptics_receiver =
-        stream_socket.subscribe_to_stream::<Haptics>(HAPTICS, MAX_UNREAD_PACKETS);
+    let mut video_receiver = stream_socket.subscribe_to_stream::<VideoPacketHeader>(
+        VIDEO,
+        MAX_UNREAD_PACKETS,
+        BackpressurePolicy::DropNewest,
+    );
+    stream_socket.set_reorder_window(VIDEO, settings.connection.video_packet_reorder_window);
+    let game_audio_receiver = stream_socket.subscribe_to_stream(
+        AUDIO,
+        MAX_UNREAD_PACKETS,
+        BackpressurePolicy::DropOldest,
+    );
+    let mut tracking_sender = stream_socket.request_stream(TRACKING);
+    tracking_sender.set_redundancy(
+        settings
+            .connection
+            .input_packet_redundancy
+            .as_option()
+            .map(|config| Duration::from_millis(config.max_duplicate_delay_ms)),
+    );
+    let mut haptics_receiver = stream_socket.subscribe_to_stream::<Haptics>(
+        HAPTICS,
+        MAX_UNREAD_PACKETS,
+        BackpressurePolicy::DropOldest,
+    );
     let statistics_sender = stream_socket.request_stream(STATISTICS);
+    let mut overlay_layers_receiver = stream_socket.subscribe_to_stream::<OverlayLayerHeader>(
+        OVERLAY_LAYERS,
+        MAX_UNREAD_PACKETS,
+        BackpressurePolicy::DropOldest,
+    );
 
     // Important: To make sure this is successfully unset when stopping streaming, the rest of the
     // function MUST be infallible
     IS_STREAMING.set(true);
-    *CONTROL_SENDER.lock() = Some(control_sender);
-    *TRACKING_SENDER.lock() = Some(tracking_sender);
-    *STATISTICS_SENDER.lock() = Some(statistics_sender);
+    ENCODER_INTRA_REFRESH.set(false);
+    journal::record(JournalCategory::Stream, "Streaming started");
+    *CONTROL_SENDER.lock() = Some(control_sender.into_channel());
+    #[cfg(target_os = "android")]
+    platform::acquire_wifi_lock();
+    *TRACKING_SENDER.lock() = Some(tracking_sender.into_channel());
+    *STATISTICS_SENDER.lock() = Some(statistics_sender.into_channel());
+
+    // On quick reconnects the stream config is likely unchanged: create the decoder right away and
+    // let the server confirm the config or send the new one
+    if let Some(cached_config) = Config::load().decoder_config {
+        decoder::create_decoder(cached_config.clone());
+
+        if let Some(sender) = &*CONTROL_SENDER.lock() {
+            sender
+                .send(ClientControlPacket::CachedDecoderConfig(cached_config))
+                .ok();
+        }
+    }
 
     let (log_channel_sender, log_channel_receiver) = mpsc::channel();
     if let Switch::Enabled(filter_level) = settings.logging.client_log_report_level {
+        let anonymize = settings.logging.anonymize_logs;
+        if anonymize {
+            let config = Config::load();
+            alvr_common::register_sensitive_name(&config.hostname);
+            if let Some(server_hostname) = &config.last_server_hostname {
+                alvr_common::register_sensitive_name(server_hostname);
+            }
+            if let Some(ssid) = platform::wifi_ssid() {
+                alvr_common::register_sensitive_name(&ssid);
+            }
+        }
+
         *LOG_CHANNEL_SENDER.lock() = Some(LogMirrorData {
             sender: log_channel_sender,
             filter_level,
+            anonymize,
         });
     }
 
-    EVENT_QUEUE.lock().push_back(streaming_start_event);
+    push_event(streaming_start_event);
 
     let video_receive_thread = thread::spawn(move || {
         let mut stream_corrupted = false;
+        let mut saturation_drops = VecDeque::new();
         while IS_STREAMING.value() {
             let data = match video_receiver.recv(STREAMING_RECV_TIMEOUT) {
                 Ok(data) => data,
@@ -313,29 +998,69 @@ fn connection_pipeline(
                 stats.report_video_packet_received(header.timestamp);
             }
 
+            if data.had_packet_loss() {
+                report_frame_drop(FrameDropReason::NetworkLoss);
+            }
+
             if header.is_idr {
                 stream_corrupted = false;
-            } else if data.had_packet_loss() {
+            } else if data.had_packet_loss() && !ENCODER_INTRA_REFRESH.value() {
+                // Otherwise the damaged area is repaired by the next intra refresh cycle
                 stream_corrupted = true;
-                if let Some(sender) = &mut *CONTROL_SENDER.lock() {
-                    sender.send(&ClientControlPacket::RequestIdr).ok();
+                if let Some(sender) = &*CONTROL_SENDER.lock() {
+                    sender.send_urgent(ClientControlPacket::RequestIdr).ok();
                 }
-                warn!("Network dropped video packet");
             }
 
             if !stream_corrupted || !settings.connection.avoid_video_glitching {
-                if !decoder::push_nal(header.timestamp, nal) {
+                if decoder::push_nal(header.timestamp, nal) {
+                    if header.is_idr {
+                        decoder::cache_priming_frame(nal);
+                    }
+
+                    if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+                        stats.report_frame_submitted_to_decoder(header.timestamp);
+                    }
+                } else {
                     stream_corrupted = true;
-                    if let Some(sender) = &mut *CONTROL_SENDER.lock() {
-                        sender.send(&ClientControlPacket::RequestIdr).ok();
+
+                    let now = Instant::now();
+                    saturation_drops.push_back(now);
+                    while saturation_drops
+                        .front()
+                        .map(|drop| *drop + DECODER_OVERLOAD_WINDOW < now)
+                        .unwrap_or(false)
+                    {
+                        saturation_drops.pop_front();
                     }
-                    warn!("Dropped video packet. Reason: Decoder saturation")
+
+                    // The server requests an IDR on its own when receiving the overload report
+                    let packet = if saturation_drops.len() >= DECODER_OVERLOAD_DROPS {
+                        let dropped_frames_per_sec =
+                            saturation_drops.len() as f32 / DECODER_OVERLOAD_WINDOW.as_secs_f32();
+                        saturation_drops.clear();
+
+                        ClientControlPacket::DecoderOverload(DecoderOverloadReport {
+                            dropped_frames_per_sec,
+                            decoder_queue_depth: STATISTICS_MANAGER
+                                .lock()
+                                .as_ref()
+                                .map(|stats| stats.decoder_queue_depth() as u32)
+                                .unwrap_or_default(),
+                        })
+                    } else {
+                        ClientControlPacket::RequestIdr
+                    };
+                    if let Some(sender) = &*CONTROL_SENDER.lock() {
+                        sender.send(packet).ok();
+                    }
+                    report_frame_drop(FrameDropReason::DecoderSaturation);
                 }
             } else {
-                if let Some(sender) = &mut *CONTROL_SENDER.lock() {
-                    sender.send(&ClientControlPacket::RequestIdr).ok();
+                if let Some(sender) = &*CONTROL_SENDER.lock() {
+                    sender.send_urgent(ClientControlPacket::RequestIdr).ok();
                 }
-                warn!("Dropped video packet. Reason: Waiting for IDR frame")
+                report_frame_drop(FrameDropReason::WaitingForIdr);
             }
         }
     });
@@ -344,14 +1069,49 @@ fn connection_pipeline(
         let device = AudioDevice::new_output(None, None).to_con()?;
 
         thread::spawn(move || {
-            alvr_common::show_err(audio::play_audio_loop(
+            let report_buffer_event = |event| {
+                if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+                    stats.report_audio_buffer_event(event);
+                }
+            };
+
+            // Only the Android player follows audio route changes
+            #[cfg(target_os = "android")]
+            let res = audio::play_audio_loop(
                 Arc::clone(&IS_STREAMING),
                 device,
-                2,
+                game_audio_channels,
                 game_audio_sample_rate,
                 config.buffering,
                 game_audio_receiver,
-            ));
+                report_buffer_event,
+                |output_device| {
+                    journal::record(
+                        JournalCategory::Stream,
+                        &format!("Audio output switched to {}", output_device.name),
+                    );
+                    set_hud_message(
+                        HudMessageSeverity::Info,
+                        &format!("Audio output switched to {}", output_device.name),
+                    );
+                    push_event(ClientCoreEvent::AudioOutputChanged {
+                        device_name: output_device.name.clone(),
+                        is_bluetooth: output_device.is_bluetooth,
+                    });
+                },
+            );
+            #[cfg(not(target_os = "android"))]
+            let res = audio::play_audio_loop(
+                Arc::clone(&IS_STREAMING),
+                device,
+                game_audio_channels,
+                game_audio_sample_rate,
+                config.buffering,
+                game_audio_receiver,
+                report_buffer_event,
+            );
+
+            alvr_common::show_err(res);
         })
     } else {
         thread::spawn(|| ())
@@ -360,7 +1120,7 @@ fn connection_pipeline(
     let microphone_thread = if matches!(settings.audio.microphone, Switch::Enabled(_)) {
         let device = AudioDevice::new_input(None).to_con()?;
 
-        let microphone_sender = stream_socket.request_stream(AUDIO);
+        let microphone_sender = stream_socket.request_stream(MICROPHONE);
 
         thread::spawn(move || {
             while IS_STREAMING.value() {
@@ -384,6 +1144,7 @@ fn connection_pipeline(
         thread::spawn(|| ())
     };
 
+    let haptics_router = HapticsRouter::new(settings.headset.external_haptics.into_option());
     let haptics_receive_thread = thread::spawn(move || {
         while IS_STREAMING.value() {
             let data = match haptics_receiver.recv(STREAMING_RECV_TIMEOUT) {
@@ -395,15 +1156,51 @@ fn connection_pipeline(
                 return;
             };
 
-            EVENT_QUEUE.lock().push_back(ClientCoreEvent::Haptics {
-                device_id: haptics.device_id,
-                duration: haptics.duration,
-                frequency: haptics.frequency,
-                amplitude: haptics.amplitude,
-            });
+            if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+                stats.report_haptics_received();
+            }
+
+            for event in haptics_router.route(&haptics) {
+                push_event(event);
+            }
         }
     });
 
+    let overlay_layers_thread = if overlay_layers {
+        thread::spawn(move || {
+            while IS_STREAMING.value() {
+                let data = match overlay_layers_receiver.recv(STREAMING_RECV_TIMEOUT) {
+                    Ok(data) => data,
+                    Err(ConnectionError::TryAgain(_)) => continue,
+                    Err(ConnectionError::Other(_)) => return,
+                };
+                let Ok((header, png)) = data.get() else {
+                    return;
+                };
+
+                let image = if png.is_empty() {
+                    None
+                } else {
+                    match decode_overlay_png(png) {
+                        Ok(image) => Some(image),
+                        Err(e) => {
+                            warn!("Failed to decode overlay layer: {e}");
+                            continue;
+                        }
+                    }
+                };
+
+                push_event(ClientCoreEvent::OverlayLayer {
+                    layer_index: header.layer_index,
+                    eye_index: header.eye_index as usize,
+                    image,
+                });
+            }
+        })
+    } else {
+        thread::spawn(|| ())
+    };
+
     let control_send_thread = thread::spawn(move || {
         let mut keepalive_deadline = Instant::now();
 
@@ -411,42 +1208,122 @@ fn connection_pipeline(
         let battery_manager = platform::android::BatteryManager::new();
         #[cfg(target_os = "android")]
         let mut battery_deadline = Instant::now();
+        #[cfg(target_os = "android")]
+        let mut device_load_deadline = Instant::now();
+        #[cfg(target_os = "android")]
+        let mut last_thermal_status = alvr_packets::ThermalStatus::None;
+        #[cfg(target_os = "android")]
+        let mut wifi_link_monitor = platform::WifiLinkMonitor::new();
+        #[cfg(target_os = "android")]
+        let mut wifi_link_deadline = Instant::now();
 
-        while IS_STREAMING.value() && IS_RESUMED.value() && IS_ALIVE.value() {
-            if let (Ok(packet), Some(sender)) = (
-                log_channel_receiver.recv_timeout(STREAMING_RECV_TIMEOUT),
-                &mut *CONTROL_SENDER.lock(),
-            ) {
-                if let Err(e) = sender.send(&packet) {
-                    info!("Server disconnected. Cause: {e:?}");
-                    set_hud_message(SERVER_DISCONNECTED_MESSAGE);
+        'control_send: while IS_STREAMING.value() && IS_RESUMED.value() && IS_ALIVE.value() {
+            if Instant::now() > keepalive_deadline {
+                send_control_packet(ControlPacketKind::KeepAlive, ClientControlPacket::KeepAlive)
+                    .ok();
+                send_control_packet(
+                    ControlPacketKind::ClockSync,
+                    ClientControlPacket::ClockSyncRequest(journal::unix_time()),
+                )
+                .ok();
+                for entry in journal::take_entries() {
+                    send_control_packet(
+                        ControlPacketKind::Journal,
+                        ClientControlPacket::JournalEntry(entry),
+                    )
+                    .ok();
+                }
+
+                keepalive_deadline = Instant::now() + KEEPALIVE_INTERVAL;
+            }
 
-                    break;
+            #[cfg(target_os = "android")]
+            if Instant::now() > battery_deadline {
+                let (gauge_value, is_plugged) = battery_manager.status();
+                send_control_packet(
+                    ControlPacketKind::Battery,
+                    ClientControlPacket::Battery(crate::BatteryPacket {
+                        device_id: *alvr_common::HEAD_ID,
+                        gauge_value,
+                        is_plugged,
+                    }),
+                )
+                .ok();
+
+                battery_deadline = Instant::now() + Duration::from_secs(5);
+            }
+
+            #[cfg(target_os = "android")]
+            if Instant::now() > device_load_deadline {
+                use alvr_packets::ThermalStatus;
+
+                let thermal_status = platform::thermal_status();
+                if thermal_status >= ThermalStatus::Moderate
+                    && last_thermal_status < ThermalStatus::Moderate
+                {
+                    warn!("Headset is thermally throttling: {thermal_status:?}");
+                    set_hud_message(HudMessageSeverity::Warning, THERMAL_THROTTLING_MESSAGE);
+                }
+                last_thermal_status = thermal_status;
+
+                if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+                    stats.report_device_load(thermal_status, platform::cpu_frequency_ratio());
                 }
+
+                device_load_deadline = Instant::now() + Duration::from_secs(2);
             }
 
-            if Instant::now() > keepalive_deadline {
-                if let Some(sender) = &mut *CONTROL_SENDER.lock() {
-                    sender.send(&ClientControlPacket::KeepAlive).ok();
+            #[cfg(target_os = "android")]
+            if Instant::now() > wifi_link_deadline {
+                for event in wifi_link_monitor.poll() {
+                    info!("{event}");
 
-                    keepalive_deadline = Instant::now() + KEEPALIVE_INTERVAL;
+                    if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+                        stats.report_wifi_link_event();
+                    }
                 }
+
+                wifi_link_deadline = Instant::now() + Duration::from_millis(500);
             }
 
+            // Wait for log lines only until the next periodic packet is due, and send them in
+            // bounded batches, so log spam cannot delay the keepalive
+            let mut next_deadline = keepalive_deadline;
             #[cfg(target_os = "android")]
-            if Instant::now() > battery_deadline {
-                let (gauge_value, is_plugged) = battery_manager.status();
-                if let Some(sender) = &mut *CONTROL_SENDER.lock() {
-                    sender
-                        .send(&ClientControlPacket::Battery(crate::BatteryPacket {
-                            device_id: *alvr_common::HEAD_ID,
-                            gauge_value,
-                            is_plugged,
-                        }))
-                        .ok();
+            {
+                next_deadline = next_deadline
+                    .min(battery_deadline)
+                    .min(device_load_deadline)
+                    .min(wifi_link_deadline);
+            }
+            let timeout = next_deadline
+                .saturating_duration_since(Instant::now())
+                .min(STREAMING_RECV_TIMEOUT);
+
+            let first_packet = match log_channel_receiver.recv_timeout(timeout) {
+                Ok(packet) => packet,
+                Err(RecvTimeoutError::Timeout) => continue,
+                // Log mirroring stopped
+                Err(RecvTimeoutError::Disconnected) => {
+                    thread::sleep(timeout);
+                    continue;
                 }
+            };
+            for packet in iter::once(first_packet).chain(
+                log_channel_receiver
+                    .try_iter()
+                    .take(MAX_LOG_PACKETS_PER_BATCH - 1),
+            ) {
+                if let Err(e) = send_control_packet(ControlPacketKind::Log, packet) {
+                    info!("Server disconnected. Cause: {e:?}");
+                    journal::record(
+                        JournalCategory::Error,
+                        format!("Streamer disconnected: {e}"),
+                    );
+                    set_hud_message(HudMessageSeverity::Warning, SERVER_DISCONNECTED_MESSAGE);
 
-                battery_deadline = Instant::now() + Duration::from_secs(5);
+                    break 'control_send;
+                }
             }
         }
 
@@ -461,11 +1338,82 @@ fn connection_pipeline(
 
             match maybe_packet {
                 Ok(ServerControlPacket::InitializeDecoder(config)) => {
-                    decoder::create_decoder(config);
+                    if decoder::create_decoder(config.clone()) {
+                        let mut storage_config = Config::load();
+                        storage_config.decoder_config = Some(config);
+                        storage_config.store();
+                    }
+                }
+                Ok(ServerControlPacket::DecoderConfigUnchanged) => {
+                    info!("Cached decoder configuration confirmed by the server");
+                }
+                Ok(ServerControlPacket::RefreshRateChanged(refresh_rate)) => {
+                    if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+                        stats.set_nominal_server_frame_interval(Duration::from_secs_f32(
+                            1.0 / refresh_rate,
+                        ));
+                    }
+
+                    push_event(ClientCoreEvent::RefreshRateChanged { refresh_rate });
                 }
+                Ok(ServerControlPacket::StreamPaused) => {
+                    journal::record(JournalCategory::Stream, "Stream paused");
+                    set_hud_message(HudMessageSeverity::Info, STREAM_PAUSED_MESSAGE);
+                    push_event(ClientCoreEvent::StreamPaused);
+                }
+                Ok(ServerControlPacket::StreamResumed) => {
+                    journal::record(JournalCategory::Stream, "Stream resumed");
+                    push_event(ClientCoreEvent::StreamResumed);
+                }
+                Ok(ServerControlPacket::SessionTimeRemaining(remaining)) => {
+                    let seconds = remaining.as_secs();
+                    set_hud_message(
+                        HudMessageSeverity::Warning,
+                        &format!("The session ends in {}:{:02}", seconds / 60, seconds % 60),
+                    );
+                    push_event(ClientCoreEvent::SessionTimeRemaining { remaining });
+                }
+                Ok(ServerControlPacket::SessionTimeExpired) => {
+                    info!("Session time expired");
+                    journal::record(JournalCategory::Stream, "Session time expired");
+                    set_hud_message(HudMessageSeverity::Info, SESSION_EXPIRED_MESSAGE);
+                    if let Some(notifier) = &*DISCONNECT_SERVER_NOTIFIER.lock() {
+                        notifier.send(()).ok();
+                    }
+
+                    return;
+                }
+                Ok(ServerControlPacket::RequestWifiScan) => {
+                    // The scan takes a few seconds
+                    thread::spawn(|| {
+                        let result = platform::scan_wifi().map_err(|e| e.to_string());
+                        if let Some(sender) = &*CONTROL_SENDER.lock() {
+                            sender
+                                .send(ClientControlPacket::WifiScanResult(result))
+                                .ok();
+                        }
+                    });
+                }
+                Ok(ServerControlPacket::CaptureScreenshot(label)) => {
+                    push_event(ClientCoreEvent::ScreenshotRequested { label });
+                }
+                Ok(ServerControlPacket::ColorBlindnessFilterChanged(filter)) => {
+                    push_event(ClientCoreEvent::ColorBlindnessFilterChanged(filter));
+                }
+                Ok(ServerControlPacket::Caption { text, duration }) => {
+                    push_event(ClientCoreEvent::Caption { text, duration });
+                }
+                Ok(ServerControlPacket::IntraRefreshChanged(enabled)) => {
+                    ENCODER_INTRA_REFRESH.set(enabled);
+                }
+                Ok(ServerControlPacket::ClockSyncResponse {
+                    client_time,
+                    server_time,
+                }) => journal::report_clock_sync(client_time, server_time),
                 Ok(ServerControlPacket::Restarting) => {
                     info!("{SERVER_RESTART_MESSAGE}");
-                    set_hud_message(SERVER_RESTART_MESSAGE);
+                    journal::record(JournalCategory::Connection, "Streamer restarting");
+                    set_hud_message(HudMessageSeverity::Info, SERVER_RESTART_MESSAGE);
                     if let Some(notifier) = &*DISCONNECT_SERVER_NOTIFIER.lock() {
                         notifier.send(()).ok();
                     }
@@ -476,7 +1424,11 @@ fn connection_pipeline(
                 Err(ConnectionError::TryAgain(_)) => continue,
                 Err(e) => {
                     info!("{SERVER_DISCONNECTED_MESSAGE} Cause: {e}");
-                    set_hud_message(SERVER_DISCONNECTED_MESSAGE);
+                    journal::record(
+                        JournalCategory::Error,
+                        format!("Streamer disconnected: {e}"),
+                    );
+                    set_hud_message(HudMessageSeverity::Warning, SERVER_DISCONNECTED_MESSAGE);
                     if let Some(notifier) = &*DISCONNECT_SERVER_NOTIFIER.lock() {
                         notifier.send(()).ok();
                     }
@@ -491,11 +1443,21 @@ fn connection_pipeline(
         while IS_STREAMING.value() {
             let res = stream_socket.recv();
             match res {
-                Ok(()) => (),
+                Ok(()) => {
+                    let dropped_packets = stream_socket.take_dropped_packets();
+                    let reordered_packets = stream_socket.take_reordered_packets();
+                    if dropped_packets > 0 || reordered_packets > 0 {
+                        if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+                            stats.report_dropped_packets(dropped_packets);
+                            stats.report_reordered_packets(reordered_packets);
+                        }
+                    }
+                }
                 Err(ConnectionError::TryAgain(_)) => continue,
                 Err(e) => {
                     info!("Client disconnected. Cause: {e}");
-                    set_hud_message(SERVER_DISCONNECTED_MESSAGE);
+                    journal::record(JournalCategory::Error, format!("Stream socket error: {e}"));
+                    set_hud_message(HudMessageSeverity::Warning, SERVER_DISCONNECTED_MESSAGE);
                     if let Some(notifier) = &*DISCONNECT_SERVER_NOTIFIER.lock() {
                         notifier.send(()).ok();
                     }
@@ -511,27 +1473,30 @@ fn connection_pipeline(
 
     IS_STREAMING.set(false);
     *CONTROL_SENDER.lock() = None;
+    journal::record(JournalCategory::Stream, "Streaming stopped");
+    journal::reset_clock_sync();
+    *CHORD_MAPPER.lock() = None;
     *LOG_CHANNEL_SENDER.lock() = None;
     *TRACKING_SENDER.lock() = None;
     *STATISTICS_SENDER.lock() = None;
 
-    EVENT_QUEUE
-        .lock()
-        .push_back(ClientCoreEvent::StreamingStopped);
+    push_event(ClientCoreEvent::StreamingStopped);
 
     #[cfg(target_os = "android")]
-    {
-        *crate::decoder::DECODER_SINK.lock() = None;
-        *crate::decoder::DECODER_SOURCE.lock() = None;
+    if settings.connection.wifi_lock_only_while_streaming {
+        platform::release_wifi_lock();
     }
 
+    decoder::destroy_decoder();
+
     video_receive_thread.join().ok();
     game_audio_thread.join().ok();
     microphone_thread.join().ok();
     haptics_receive_thread.join().ok();
+    overlay_layers_thread.join().ok();
     control_send_thread.join().ok();
     control_receive_thread.join().ok();
     stream_receive_thread.join().ok();
 
-    Ok(())
+    Ok(disconnect_action)
 }
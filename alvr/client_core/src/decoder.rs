@@ -1,14 +1,73 @@
-use crate::{ClientCoreEvent, EVENT_QUEUE};
-use alvr_common::{once_cell::sync::Lazy, parking_lot::Mutex, RelaxedAtomic};
+use crate::{push_event, ClientCoreEvent};
+use alvr_common::{
+    anyhow::{self, Result},
+    error,
+    once_cell::sync::Lazy,
+    parking_lot::Mutex,
+    RelaxedAtomic,
+};
 use alvr_packets::DecoderInitializationConfig;
 use alvr_session::{CodecType, MediacodecDataType};
-use std::time::Duration;
+use std::{
+    ffi::c_void,
+    thread,
+    time::{Duration, Instant},
+};
+
+// Timestamp used for priming frames, whose output is discarded. Real frames never use it
+pub const PRIMING_TIMESTAMP: Duration = Duration::ZERO;
+const PRIMING_PUSH_TIMEOUT: Duration = Duration::from_millis(100);
+
+// Input side of a hardware decoder
+pub trait VideoDecoderSink: Send {
+    // return: the frame has been enqueued. Can block for a short time if the decoder is busy
+    fn push_frame_nal(&mut self, timestamp: Duration, nal: &[u8]) -> Result<bool>;
+}
+
+// Output side of a hardware decoder. The buffer type depends on the platform (AHardwareBuffer on
+// Android)
+pub trait VideoDecoderSource: Send {
+    // The frontend must finish using the returned buffer before calling this function again
+    fn dequeue_frame(&mut self) -> Option<(Duration, *mut c_void)>;
+}
+
+type DecoderPair = (Box<dyn VideoDecoderSink>, Box<dyn VideoDecoderSource>);
+
+// Creates the hardware decoder of the current platform. Only MediaCodec is implemented, other
+// platforms use the software decoder or an external decoder. Also returns the keys of the extra
+// decoder options that were rejected
+#[allow(unused_variables)]
+fn create_platform_decoder(
+    config: DecoderInitConfig,
+    config_nal: Vec<u8>,
+    dequeued_frame_callback: impl Fn(Duration) + Send + 'static,
+) -> Result<(DecoderPair, Vec<String>)> {
+    #[cfg(target_os = "android")]
+    {
+        let (sink, source, rejected_option_keys) =
+            crate::platform::video_decoder_split(config, config_nal, dequeued_frame_callback)?;
+
+        Ok(((Box::new(sink), Box::new(source)), rejected_option_keys))
+    }
+    #[cfg(all(not(target_os = "android"), feature = "software-decoder"))]
+    if config.codec == CodecType::H264 {
+        let (sink, source) =
+            crate::software_decoder::software_decoder_split(&config_nal, dequeued_frame_callback)?;
+
+        return Ok(((Box::new(sink), Box::new(source)), vec![]));
+    }
+    // Other platforms implement VideoDecoderSink and VideoDecoderSource here
+    #[cfg(not(target_os = "android"))]
+    anyhow::bail!("No hardware decoder available on this platform. Use an external decoder");
+}
 
 #[derive(Clone)]
 pub struct DecoderInitConfig {
     pub codec: CodecType,
     pub max_buffering_frames: f32,
     pub buffering_history_weight: f32,
+    pub slice_count: u32,
+    pub priming_frames: u32,
     pub options: Vec<(String, MediacodecDataType)>,
 }
 
@@ -17,95 +76,181 @@ pub static DECODER_INIT_CONFIG: Lazy<Mutex<DecoderInitConfig>> = Lazy::new(|| {
         codec: CodecType::H264,
         max_buffering_frames: 1.0,
         buffering_history_weight: 0.9,
+        slice_count: 1,
+        priming_frames: 0,
         options: vec![],
     })
 });
-#[cfg(target_os = "android")]
-pub static DECODER_SINK: Lazy<Mutex<Option<crate::platform::VideoDecoderSink>>> =
+pub static DECODER_SINK: Lazy<Mutex<Option<Box<dyn VideoDecoderSink>>>> =
     Lazy::new(|| Mutex::new(None));
-#[cfg(target_os = "android")]
-pub static DECODER_SOURCE: Lazy<Mutex<Option<crate::platform::VideoDecoderSource>>> =
+pub static DECODER_SOURCE: Lazy<Mutex<Option<Box<dyn VideoDecoderSource>>>> =
     Lazy::new(|| Mutex::new(None));
 
 pub static EXTERNAL_DECODER: RelaxedAtomic = RelaxedAtomic::new(false);
 
-pub fn create_decoder(lazy_config: DecoderInitializationConfig) {
+// An IDR frame from a previous stream, used to warm up a new decoder created with the same config
+struct PrimingFrame {
+    config_nal: Vec<u8>,
+    nal: Vec<u8>,
+}
+
+static PRIMING_FRAME: Lazy<Mutex<Option<PrimingFrame>>> = Lazy::new(|| Mutex::new(None));
+static ACTIVE_CONFIG_NAL: Lazy<Mutex<Option<Vec<u8>>>> = Lazy::new(|| Mutex::new(None));
+
+// Decode the cached IDR a few times, so the first real frame doesn't hit a cold decoder
+fn prime_decoder(sink: &mut dyn VideoDecoderSink, config_nal: &[u8], count: u32) {
+    let priming_frame = PRIMING_FRAME.lock();
+    let Some(priming_frame) = priming_frame
+        .as_ref()
+        .filter(|frame| frame.config_nal == config_nal)
+    else {
+        return;
+    };
+
+    for _ in 0..count {
+        let deadline = Instant::now() + PRIMING_PUSH_TIMEOUT;
+        while !matches!(
+            sink.push_frame_nal(PRIMING_TIMESTAMP, &priming_frame.nal),
+            Ok(true)
+        ) {
+            if Instant::now() > deadline {
+                alvr_common::warn!("Decoder priming timed out");
+                return;
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
+
+// Remember the first IDR of the stream for the next decoder priming
+pub fn cache_priming_frame(nal: &[u8]) {
+    if EXTERNAL_DECODER.value() {
+        return;
+    }
+
+    if let Some(config_nal) = &*ACTIVE_CONFIG_NAL.lock() {
+        let mut priming_frame = PRIMING_FRAME.lock();
+        if priming_frame
+            .as_ref()
+            .map(|frame| frame.config_nal != *config_nal)
+            .unwrap_or(true)
+        {
+            *priming_frame = Some(PrimingFrame {
+                config_nal: config_nal.clone(),
+                nal: nal.to_vec(),
+            });
+        }
+    }
+}
+
+// return: the configuration differs from the one of the active decoder
+pub fn create_decoder(lazy_config: DecoderInitializationConfig) -> bool {
+    {
+        let mut active_config_nal = ACTIVE_CONFIG_NAL.lock();
+        // The decoder might have been already created from the cached configuration
+        if active_config_nal.as_ref() == Some(&lazy_config.config_buffer) {
+            return false;
+        }
+        *active_config_nal = Some(lazy_config.config_buffer.clone());
+    }
+
     let mut config = DECODER_INIT_CONFIG.lock();
     config.codec = lazy_config.codec;
 
     if EXTERNAL_DECODER.value() {
-        EVENT_QUEUE
-            .lock()
-            .push_back(ClientCoreEvent::CreateDecoder {
-                codec: config.codec,
-                config_nal: lazy_config.config_buffer,
-            });
+        push_event(ClientCoreEvent::CreateDecoder {
+            codec: config.codec,
+            config_nal: lazy_config.config_buffer,
+        });
     } else {
-        #[cfg(target_os = "android")]
-        if DECODER_SINK.lock().is_none() {
-            let (enqueuer, dequeuer) = crate::platform::video_decoder_split(
-                config.clone(),
-                lazy_config.config_buffer,
-                |target_timestamp| {
-                    if let Some(stats) = &mut *crate::STATISTICS_MANAGER.lock() {
-                        stats.report_frame_decoded(target_timestamp);
-                    }
-                },
-            )
-            .unwrap();
+        // Drop the decoder created with a stale configuration, if any
+        *DECODER_SINK.lock() = None;
+        *DECODER_SOURCE.lock() = None;
+
+        let res = create_platform_decoder(
+            config.clone(),
+            lazy_config.config_buffer.clone(),
+            |target_timestamp| {
+                if let Some(stats) = &mut *crate::STATISTICS_MANAGER.lock() {
+                    stats.report_frame_decoded(target_timestamp);
+                }
+            },
+        );
+
+        match res {
+            Ok(((mut sink, source), rejected_option_keys)) => {
+                prime_decoder(
+                    &mut *sink,
+                    &lazy_config.config_buffer,
+                    config.priming_frames,
+                );
 
-            *DECODER_SINK.lock() = Some(enqueuer);
-            *DECODER_SOURCE.lock() = Some(dequeuer);
+                *DECODER_SINK.lock() = Some(sink);
+                *DECODER_SOURCE.lock() = Some(source);
 
-            if let Some(sender) = &mut *crate::connection::CONTROL_SENDER.lock() {
-                sender
-                    .send(&alvr_packets::ClientControlPacket::RequestIdr)
-                    .ok();
+                if let Some(sender) = &*crate::connection::CONTROL_SENDER.lock() {
+                    sender
+                        .send_urgent(alvr_packets::ClientControlPacket::RequestIdr)
+                        .ok();
+
+                    // Reported even if nothing was rejected, so the streamer clears stale warnings
+                    if !config.options.is_empty() {
+                        let accepted_option_keys = config
+                            .options
+                            .iter()
+                            .map(|(key, _)| key.clone())
+                            .filter(|key| !rejected_option_keys.contains(key))
+                            .collect();
+                        sender
+                            .send(alvr_packets::ClientControlPacket::DecoderOptionsReport {
+                                accepted_option_keys,
+                                rejected_option_keys,
+                            })
+                            .ok();
+                    }
+                }
             }
+            Err(e) => error!("Failed to create the video decoder: {e}"),
         }
     }
+
+    true
+}
+
+pub fn destroy_decoder() {
+    *ACTIVE_CONFIG_NAL.lock() = None;
+
+    *DECODER_SINK.lock() = None;
+    *DECODER_SOURCE.lock() = None;
 }
 
 // return: frame has been successfully enqueued
 pub fn push_nal(timestamp: Duration, nal: &[u8]) -> bool {
     if EXTERNAL_DECODER.value() {
-        EVENT_QUEUE.lock().push_back(ClientCoreEvent::FrameReady {
+        push_event(ClientCoreEvent::FrameReady {
             timestamp,
             nal: nal.to_vec(),
         });
         true
+    } else if let Some(decoder) = &mut *DECODER_SINK.lock() {
+        matches!(
+            alvr_common::show_err(decoder.push_frame_nal(timestamp, nal)),
+            Some(true)
+        )
     } else {
-        #[cfg(target_os = "android")]
-        if let Some(decoder) = &mut *DECODER_SINK.lock() {
-            matches!(
-                alvr_common::show_err(decoder.push_frame_nal(timestamp, nal)),
-                Some(true)
-            )
-        } else {
-            false
-        }
-        #[cfg(not(target_os = "android"))]
         false
     }
 }
 
-/// Call only with internal decoder (Android only)
-/// If a frame is available, return the timestamp and the AHardwareBuffer.
-pub fn get_frame() -> Option<(Duration, *mut std::ffi::c_void)> {
-    #[cfg(target_os = "android")]
-    if let Some(decoder) = &mut *DECODER_SOURCE.lock() {
-        if let Some((timestamp, buffer_ptr)) = decoder.dequeue_frame() {
-            if let Some(stats) = &mut *crate::STATISTICS_MANAGER.lock() {
-                stats.report_compositor_start(timestamp);
-            }
+/// Call only with internal decoder
+/// If a frame is available, return the timestamp and the platform buffer.
+pub fn get_frame() -> Option<(Duration, *mut c_void)> {
+    let (timestamp, buffer_ptr) = DECODER_SOURCE.lock().as_mut()?.dequeue_frame()?;
 
-            Some((timestamp, buffer_ptr))
-        } else {
-            None
-        }
-    } else {
-        None
+    if let Some(stats) = &mut *crate::STATISTICS_MANAGER.lock() {
+        stats.report_compositor_start(timestamp);
     }
-    #[cfg(not(target_os = "android"))]
-    None
+
+    Some((timestamp, buffer_ptr))
 }
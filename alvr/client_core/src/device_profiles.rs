@@ -0,0 +1,57 @@
+// Recommended per-eye resolutions of known headsets, matched by device model name. The encoding
+// resolution is close to the panel resolution, the game renders at a higher resolution to
+// compensate for the lens distortion.
+
+use alvr_common::glam::UVec2;
+use alvr_packets::RecommendedViewResolutions;
+
+struct DeviceProfile {
+    models: &'static [&'static str],
+    transcoding: (u32, u32),
+    emulated_headset: (u32, u32),
+}
+
+const DEVICE_PROFILES: &[DeviceProfile] = &[
+    DeviceProfile {
+        models: &["Quest"],
+        transcoding: (1440, 1600),
+        emulated_headset: (1728, 1920),
+    },
+    DeviceProfile {
+        models: &["Quest 2", "Quest 3S"],
+        transcoding: (1824, 1920),
+        emulated_headset: (2208, 2304),
+    },
+    DeviceProfile {
+        models: &["Quest 3"],
+        transcoding: (2048, 2208),
+        emulated_headset: (2464, 2656),
+    },
+    DeviceProfile {
+        models: &["Quest Pro"],
+        transcoding: (1792, 1920),
+        emulated_headset: (2144, 2304),
+    },
+    // Pico 4 and Pico 4 Pro
+    DeviceProfile {
+        models: &["A8110", "A8150"],
+        transcoding: (2144, 2144),
+        emulated_headset: (2592, 2592),
+    },
+    DeviceProfile {
+        models: &["VIVE Focus 3"],
+        transcoding: (2432, 2432),
+        emulated_headset: (2912, 2912),
+    },
+];
+
+pub fn recommended_view_resolutions(device_model: &str) -> Option<RecommendedViewResolutions> {
+    let profile = DEVICE_PROFILES
+        .iter()
+        .find(|profile| profile.models.contains(&device_model))?;
+
+    Some(RecommendedViewResolutions {
+        transcoding: UVec2::from(profile.transcoding),
+        emulated_headset: UVec2::from(profile.emulated_headset),
+    })
+}
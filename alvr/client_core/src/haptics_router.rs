@@ -0,0 +1,48 @@
+use crate::ClientCoreEvent;
+use alvr_common::{LEFT_HAND_ID, RIGHT_HAND_ID};
+use alvr_packets::Haptics;
+use alvr_session::{ExternalHapticsConfig, HapticsForwardingRule, HapticsSourceDevice};
+
+// Decides which devices receive a haptics event. Controllers always receive their own events,
+// while external accessories are delivered through the frontend, which owns the Bluetooth
+// connection.
+pub struct HapticsRouter {
+    rules: Vec<HapticsForwardingRule>,
+}
+
+impl HapticsRouter {
+    pub fn new(config: Option<ExternalHapticsConfig>) -> Self {
+        Self {
+            rules: config.map(|c| c.rules).unwrap_or_default(),
+        }
+    }
+
+    pub fn route(&self, haptics: &Haptics) -> Vec<ClientCoreEvent> {
+        let mut events = vec![ClientCoreEvent::Haptics {
+            device_id: haptics.device_id,
+            duration: haptics.duration,
+            frequency: haptics.frequency,
+            amplitude: haptics.amplitude,
+            pcm: haptics.pcm.clone(),
+        }];
+
+        for rule in &self.rules {
+            let matches = match rule.source {
+                HapticsSourceDevice::LeftController => haptics.device_id == *LEFT_HAND_ID,
+                HapticsSourceDevice::RightController => haptics.device_id == *RIGHT_HAND_ID,
+                HapticsSourceDevice::Any => true,
+            };
+
+            if matches {
+                events.push(ClientCoreEvent::ExternalHaptics {
+                    device: rule.target,
+                    duration: haptics.duration,
+                    frequency: haptics.frequency,
+                    amplitude: f32::min(haptics.amplitude * rule.intensity_multiplier, 1.0),
+                });
+            }
+        }
+
+        events
+    }
+}
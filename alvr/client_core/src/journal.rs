@@ -0,0 +1,102 @@
+// Client side of the event journal. Entries are timestamped with the local clock and converted to
+// the streamer clock when sent, once the clock offset has been estimated. Entries recorded while
+// disconnected are kept and sent after the next connection.
+
+use alvr_common::{once_cell::sync::Lazy, parking_lot::Mutex};
+use alvr_packets::{JournalCategory, JournalEntry, JournalSource};
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const MAX_PENDING_ENTRIES: usize = 100;
+// The offset is estimated from the recent clock sync sample with the lowest round trip time,
+// which has the smallest error
+const CLOCK_SYNC_HISTORY_SIZE: usize = 16;
+
+struct PendingEntry {
+    local_time: Duration,
+    category: JournalCategory,
+    message: String,
+}
+
+struct ClockSyncSample {
+    round_trip_time: Duration,
+    offset_ns: i128, // server minus client
+}
+
+#[derive(Default)]
+struct Journal {
+    pending_entries: VecDeque<PendingEntry>,
+    clock_sync_samples: VecDeque<ClockSyncSample>,
+}
+
+static JOURNAL: Lazy<Mutex<Journal>> = Lazy::new(|| Mutex::new(Journal::default()));
+
+pub fn unix_time() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+pub fn record(category: JournalCategory, message: impl Into<String>) {
+    let mut journal = JOURNAL.lock();
+
+    journal.pending_entries.push_back(PendingEntry {
+        local_time: unix_time(),
+        category,
+        message: message.into(),
+    });
+    if journal.pending_entries.len() > MAX_PENDING_ENTRIES {
+        journal.pending_entries.pop_front();
+    }
+}
+
+// client_time is the time the request was sent, server_time is the time the server replied
+pub fn report_clock_sync(client_time: Duration, server_time: Duration) {
+    let now = unix_time();
+    let round_trip_time = now.saturating_sub(client_time);
+    let offset_ns = (server_time + round_trip_time / 2).as_nanos() as i128 - now.as_nanos() as i128;
+
+    let mut journal = JOURNAL.lock();
+    journal.clock_sync_samples.push_back(ClockSyncSample {
+        round_trip_time,
+        offset_ns,
+    });
+    if journal.clock_sync_samples.len() > CLOCK_SYNC_HISTORY_SIZE {
+        journal.clock_sync_samples.pop_front();
+    }
+}
+
+// The next streamer could be a different machine
+pub fn reset_clock_sync() {
+    JOURNAL.lock().clock_sync_samples.clear();
+}
+
+// Returns the pending entries converted to the streamer clock. Returns nothing until the clock
+// offset is known
+pub fn take_entries() -> Vec<JournalEntry> {
+    let mut journal = JOURNAL.lock();
+
+    let Some(offset_ns) = journal
+        .clock_sync_samples
+        .iter()
+        .min_by_key(|sample| sample.round_trip_time)
+        .map(|sample| sample.offset_ns)
+    else {
+        return vec![];
+    };
+
+    journal
+        .pending_entries
+        .drain(..)
+        .map(|entry| JournalEntry {
+            timestamp: Duration::from_nanos(
+                (entry.local_time.as_nanos() as i128 + offset_ns).max(0) as u64,
+            ),
+            source: JournalSource::Client,
+            category: entry.category,
+            message: entry.message,
+        })
+        .collect()
+}
@@ -6,11 +6,17 @@
 )]
 
 mod c_api;
+mod chords;
 mod connection;
 mod decoder;
+mod device_profiles;
+mod haptics_router;
+mod journal;
 mod logging_backend;
 mod platform;
 mod sockets;
+#[cfg(feature = "software-decoder")]
+mod software_decoder;
 mod statistics;
 mod storage;
 
@@ -19,57 +25,213 @@ pub mod opengl;
 #[cfg(target_os = "android")]
 mod audio;
 
-pub use decoder::get_frame;
 pub use logging_backend::init_logging;
 #[cfg(target_os = "android")]
 pub use platform::try_get_permission;
+#[cfg(feature = "software-decoder")]
+pub use software_decoder::SoftwareFrame;
+pub use storage::{KioskConfig, TrustedNetworksConfig};
 
 use alvr_common::{
     error,
-    glam::{UVec2, Vec2},
+    glam::{Quat, UVec2, Vec2},
     once_cell::sync::Lazy,
-    parking_lot::Mutex,
-    Fov, RelaxedAtomic,
+    parking_lot::{Mutex, RwLock},
+    warn, Fov, RelaxedAtomic, LEFT_HAND_ID, RIGHT_HAND_ID,
 };
-use alvr_packets::{BatteryPacket, ButtonEntry, ClientControlPacket, Tracking, ViewsConfig};
-use alvr_session::{CodecType, Settings};
-use connection::{CONTROL_SENDER, STATISTICS_SENDER, TRACKING_SENDER};
+use alvr_packets::{
+    BatteryPacket, ButtonEntry, ClientControlPacket, HapticsPcm, RecommendedViewResolutions,
+    Tracking, ViewsConfig,
+};
+use alvr_session::{
+    ChordAction, CodecType, ColorBlindnessFilterConfig, ExternalHapticsDevice, Settings,
+};
+use chords::ChordMapper;
+use connection::{
+    CONTROL_SENDER, PAIRING_TARGET, SELECTED_SERVER, STATISTICS_SENDER, TRACKING_SENDER,
+};
+use crossbeam_queue::SegQueue;
 use decoder::EXTERNAL_DECODER;
 use serde::{Deserialize, Serialize};
 use statistics::StatisticsManager;
 use std::{
-    collections::VecDeque,
-    sync::Arc,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     thread::{self, JoinHandle},
     time::Duration,
 };
 use storage::Config;
 
+// Enough for a few seconds of haptics events, if the frontend stops polling. Other events are
+// never dropped
+const MAX_QUEUED_HAPTICS_EVENTS: usize = 1024;
+
 static STATISTICS_MANAGER: Lazy<Mutex<Option<StatisticsManager>>> = Lazy::new(|| Mutex::new(None));
+static CHORD_MAPPER: Lazy<Mutex<Option<ChordMapper>>> = Lazy::new(|| Mutex::new(None));
 
-static EVENT_QUEUE: Lazy<Mutex<VecDeque<ClientCoreEvent>>> =
-    Lazy::new(|| Mutex::new(VecDeque::new()));
+// Lock-free, so streaming threads pushing events never contend with the thread polling them
+static EVENT_QUEUE: Lazy<SegQueue<ClientCoreEvent>> = Lazy::new(SegQueue::new);
+static QUEUED_HAPTICS_EVENTS: AtomicUsize = AtomicUsize::new(0);
+static EVENT_CALLBACK: Lazy<RwLock<Option<EventCallback>>> = Lazy::new(|| RwLock::new(None));
+static ARE_HAPTICS_EVENTS_DROPPED: RelaxedAtomic = RelaxedAtomic::new(false);
 
-static IS_ALIVE: RelaxedAtomic = RelaxedAtomic::new(true);
+static STREAM_CONFIG_HOOK: Lazy<Mutex<Option<StreamConfigHook>>> = Lazy::new(|| Mutex::new(None));
+static IS_ALIVE: RelaxedAtomic = RelaxedAtomic::new(false);
 static IS_RESUMED: RelaxedAtomic = RelaxedAtomic::new(false);
 static IS_STREAMING: Lazy<Arc<RelaxedAtomic>> = Lazy::new(|| Arc::new(RelaxedAtomic::new(false)));
 
-static CONNECTION_THREAD: Lazy<Mutex<Option<JoinHandle<()>>>> = Lazy::new(|| Mutex::new(None));
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConnectionFailureReason {
+    NetworkUnreachable,
+    SocketError,
+    Timeout,
+    ServerDisconnected,
+    ServerRestarting,
+    UnexpectedPacket,
+    InvalidStreamConfig,
+    UntrustedServer,    // rejected by the kiosk configuration
+    RejectedByFrontend, // rejected by the stream config hook
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HudMessageSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+// The frontend is free to choose how to present each field
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HudMessage {
+    pub title: String,
+    pub body: String,
+    pub severity: HudMessageSeverity,
+    // Dashboard URL of the last streamer, meant to be shown as a QR code
+    pub qr_code_url: Option<String>,
+}
+
+impl HudMessage {
+    pub fn to_plain_text(&self) -> String {
+        format!("{}\n{}", self.title, self.body)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct DiscoveredServer {
+    pub hostname: String,
+    pub ip: IpAddr,
+    pub version: String,
+    pub gpu_names: Vec<String>,
+    pub codec: CodecType,
+}
+
+impl DiscoveredServer {
+    /// Short description meant for the lobby, for example "NVIDIA GeForce RTX 3080, HEVC, v21.0.0"
+    pub fn summary(&self) -> String {
+        let codec = match self.codec {
+            CodecType::H264 => "H264",
+            CodecType::Hevc => "HEVC",
+        };
+
+        if let Some(gpu_name) = self.gpu_names.first() {
+            format!("{gpu_name}, {codec}, v{}", self.version)
+        } else {
+            format!("{codec}, v{}", self.version)
+        }
+    }
+}
+
+/// Present mode negotiated with the streamer. Only options the frontend declared support for in
+/// ClientCapabilities are set
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+pub struct PresentMode {
+    /// None to keep the runtime default
+    pub extra_latency_mode: Option<bool>,
+    pub front_buffer_rendering: bool,
+}
 
 #[derive(Serialize, Deserialize)]
 pub enum ClientCoreEvent {
-    UpdateHudMessage(String),
+    UpdateHudMessage(HudMessage),
+    // Emitted when more than one streamer tries to connect and none of them was used last time.
+    // The frontend should call select_server(), otherwise the first one is chosen after a while
+    ServersDiscovered(Vec<DiscoveredServer>),
+    // Emitted when the handshake fails. A new connection attempt is made automatically
+    ConnectionFailed {
+        reason: ConnectionFailureReason,
+        message: String,
+    },
     StreamingStarted {
         view_resolution: UVec2,
         refresh_rate_hint: f32,
+        present_mode: PresentMode,
         settings: Box<Settings>,
     },
     StreamingStopped,
+    // Sent after StreamingStopped, depending on the disconnect action configured on the streamer
+    ExitRequested,
+    DisplayOffRequested,
+    // Requested with a controller chord. Handling is up to the frontend
+    PassthroughToggleRequested,
+    PerformanceOverlayToggleRequested,
+    // Video and game audio are suspended but the connection is kept. The frontend should show the
+    // lobby with the HUD message until StreamResumed
+    StreamPaused,
+    StreamResumed,
+    // Sent periodically when the streamer enforces a session time limit and the end is near
+    SessionTimeRemaining {
+        remaining: Duration,
+    },
+    // The server accepted a refresh rate requested with request_refresh_rate()
+    RefreshRateChanged {
+        refresh_rate: f32,
+    },
+    // Game audio playback was restarted on a new output device, for example after Bluetooth
+    // headphones connected. A HUD message is set too
+    AudioOutputChanged {
+        device_name: String,
+        is_bluetooth: bool,
+    },
     Haptics {
         device_id: u64,
         duration: Duration,
         frequency: f32,
         amplitude: f32,
+        // Sampled waveform, sent only if pcm_haptics_sample_rate was set in the capabilities.
+        // When present it should be played instead of the simple vibration
+        pcm: Option<HapticsPcm>,
+    },
+    // Haptics for accessories paired with the headset. Delivery is up to the frontend
+    ExternalHaptics {
+        device: ExternalHapticsDevice,
+        duration: Duration,
+        frequency: f32,
+        amplitude: f32,
+    },
+    // Requested by an A/B comparison on the streamer. The frontend should save the next displayed
+    // frame, the label identifies it in the comparison report
+    ScreenshotRequested {
+        label: String,
+    },
+    // Overlay layer to be shown head-locked over the stream, only if overlay_layers was set in the
+    // capabilities. The image is RGBA, not premultiplied, and covers the view of the eye. None
+    // hides the layer. Layers with a higher index go on top
+    OverlayLayer {
+        layer_index: u32,
+        eye_index: usize,
+        image: Option<(UVec2, Vec<u8>)>,
+    },
+    // The color blindness filter setting was changed during the stream. The initial one is part of
+    // the settings of StreamingStarted
+    ColorBlindnessFilterChanged(Option<ColorBlindnessFilterConfig>),
+    // Replaces the caption shown over the stream, in the region set by the captions settings. The
+    // frontend should hide it after the duration. An empty text hides it immediately
+    Caption {
+        text: String,
+        duration: Duration,
     },
     CreateDecoder {
         codec: CodecType,
@@ -81,150 +243,431 @@ pub enum ClientCoreEvent {
     },
 }
 
+/// Can be called before creating the ClientCoreContext
 pub fn manufacturer_name() -> String {
     platform::manufacturer_name()
 }
 
-pub fn initialize(
-    recommended_view_resolution: UVec2,
-    supported_refresh_rates: Vec<f32>,
-    external_decoder: bool,
-) {
-    logging_backend::init_logging();
+/// Restricts the client for public deployments. The configuration is persisted, pass None to unlock.
+pub fn set_kiosk_config(kiosk_config: Option<KioskConfig>) {
+    let mut config = Config::load();
+    config.kiosk = kiosk_config;
+    config.store();
+}
+
+/// Restricts connection attempts to the given Wi-Fi networks and streamer subnets. The configuration
+/// is persisted, pass None to allow any network. Returns false if a subnet is not valid.
+pub fn set_trusted_networks(trusted_networks: Option<TrustedNetworksConfig>) -> bool {
+    if let Some(trusted_networks) = &trusted_networks {
+        if !trusted_networks.is_valid() {
+            return false;
+        }
 
-    // Make sure to reset config in case of version compat mismatch.
-    if Config::load().protocol_id != alvr_common::protocol_id() {
-        // NB: Config::default() sets the current protocol ID
-        Config::default().store();
+        // Reading the Wi-Fi SSID requires the location permission
+        #[cfg(target_os = "android")]
+        if !trusted_networks.wifi_ssids.is_empty() {
+            platform::try_get_permission(platform::LOCATION_PERMISSION);
+        }
     }
 
-    #[cfg(target_os = "android")]
-    platform::try_get_permission(platform::MICROPHONE_PERMISSION);
-    #[cfg(target_os = "android")]
-    platform::acquire_wifi_lock();
+    let mut config = Config::load();
+    config.trusted_networks = trusted_networks;
+    config.store();
 
-    EXTERNAL_DECODER.set(external_decoder);
+    true
+}
 
-    *CONNECTION_THREAD.lock() = Some(thread::spawn(move || {
-        connection::connection_lifecycle_loop(recommended_view_resolution, supported_refresh_rates)
-    }));
+/// Capabilities of the headset and the frontend, sent to the streamer during the handshake
+#[derive(Clone)]
+pub struct ClientCapabilities {
+    pub default_view_resolution: UVec2,
+    pub refresh_rates: Vec<f32>,
+    pub foveated_encoding: bool,
+    /// The frontend decodes the video from FrameReady events instead of using get_frame()
+    pub external_decoder: bool,
+    /// Sample rate of PCM haptics playback, None if only simple vibrations are supported
+    pub pcm_haptics_sample_rate: Option<f32>,
+    /// The frontend can turn the runtime extra latency mode (additional queued frame) on or off
+    pub extra_latency_mode: bool,
+    /// The frontend can render directly to the front buffer
+    pub front_buffer_rendering: bool,
+    /// The frontend can composite the OverlayLayer events
+    pub overlay_layers: bool,
+    /// Name and version of the XR runtime, reported to the streamer
+    pub runtime_name: String,
 }
 
-pub fn destroy() {
-    IS_ALIVE.set(false);
+/// Stream parameters sent to the streamer during the handshake. They are initialized from
+/// ClientCapabilities and can be adjusted by the stream config hook
+#[derive(Clone)]
+pub struct StreamConfigProposal {
+    pub server_ip: IpAddr,
+    pub view_resolution: UVec2,
+    /// Used instead of view_resolution by the streamer if set. Clear it to make view_resolution
+    /// take effect
+    pub recommended_view_resolutions: Option<RecommendedViewResolutions>,
+    pub refresh_rates: Vec<f32>,
+    pub foveated_encoding: bool,
+}
+
+/// Called from the thread that produced the event. It must return quickly, streaming threads are
+/// blocked meanwhile. The event can be produced inside a method of ClientCoreContext while internal
+/// locks are held, so the callback should only copy the event data: set_event_callback() is the
+/// only method it can call, and it must not drop the context
+pub type EventCallback = Arc<dyn Fn(ClientCoreEvent) + Send + Sync>;
+
+/// Returns an error to reject the connection, the message is shown in the HUD
+pub type StreamConfigHook = Box<dyn FnMut(&mut StreamConfigProposal) -> Result<(), String> + Send>;
+
+/// Entry point for frontends. Creating the context starts discovering and connecting to streamers,
+/// dropping it disconnects and stops all background threads. Only one context can exist at a time.
+///
+/// The context is Send and Sync. Tracking and input can be submitted from a different thread than
+/// the one polling events.
+pub struct ClientCoreContext {
+    connection_thread: Option<JoinHandle<()>>,
+}
+
+impl ClientCoreContext {
+    pub fn new(capabilities: ClientCapabilities) -> Self {
+        assert!(
+            !IS_ALIVE.value(),
+            "Only one ClientCoreContext can exist at a time"
+        );
+        IS_ALIVE.set(true);
+
+        logging_backend::init_logging();
 
-    if let Some(thread) = CONNECTION_THREAD.lock().take() {
-        thread.join().ok();
+        // Make sure to reset config in case of version compat mismatch.
+        if Config::load().protocol_id != alvr_common::protocol_id() {
+            // NB: Config::default() sets the current protocol ID
+            Config::default().store();
+        }
+
+        #[cfg(target_os = "android")]
+        platform::try_get_permission(platform::MICROPHONE_PERMISSION);
+        #[cfg(target_os = "android")]
+        if Config::load()
+            .trusted_networks
+            .map_or(false, |networks| !networks.wifi_ssids.is_empty())
+        {
+            platform::try_get_permission(platform::LOCATION_PERMISSION);
+        }
+        #[cfg(target_os = "android")]
+        if !Config::load().wifi_lock_only_while_streaming {
+            platform::acquire_wifi_lock();
+        }
+
+        EXTERNAL_DECODER.set(capabilities.external_decoder);
+
+        let connection_thread =
+            thread::spawn(move || connection::connection_lifecycle_loop(capabilities));
+
+        Self {
+            connection_thread: Some(connection_thread),
+        }
     }
 
-    #[cfg(target_os = "android")]
-    platform::release_wifi_lock();
-}
+    /// Call when the app gains focus. Connections are made only while resumed
+    pub fn resume(&self) {
+        IS_RESUMED.set(true);
+    }
 
-pub fn resume() {
-    IS_RESUMED.set(true);
-}
+    /// Call when the app loses focus. The current stream is stopped
+    pub fn pause(&self) {
+        IS_RESUMED.set(false);
+    }
 
-pub fn pause() {
-    IS_RESUMED.set(false);
-}
+    /// Pair with the streamer using the content of the QR code shown by the dashboard. Scanning the
+    /// code is up to the frontend. Returns false if the URI is not valid.
+    pub fn pair_with_uri(&self, uri: &str) -> bool {
+        if let Some(target) = alvr_sockets::parse_pairing_uri(uri) {
+            *PAIRING_TARGET.lock() = Some(target);
 
-pub fn poll_event() -> Option<ClientCoreEvent> {
-    EVENT_QUEUE.lock().pop_front()
-}
+            true
+        } else {
+            false
+        }
+    }
 
-pub fn send_views_config(fov: [Fov; 2], ipd_m: f32) {
-    if let Some(sender) = &mut *CONTROL_SENDER.lock() {
-        sender
-            .send(&ClientControlPacket::ViewsConfig(ViewsConfig {
-                fov,
-                ipd_m,
-            }))
-            .ok();
+    /// Called before each handshake with the stream parameters that are going to be sent. The hook
+    /// can adjust them, for example to cap the resolution or to force a refresh rate, or reject the
+    /// connection. Pass None to remove it
+    pub fn set_stream_config_hook(&self, hook: Option<StreamConfigHook>) {
+        *STREAM_CONFIG_HOOK.lock() = hook;
     }
-}
 
-pub fn send_battery(device_id: u64, gauge_value: f32, is_plugged: bool) {
-    if let Some(sender) = &mut *CONTROL_SENDER.lock() {
-        sender
-            .send(&ClientControlPacket::Battery(BatteryPacket {
-                device_id,
-                gauge_value,
-                is_plugged,
-            }))
-            .ok();
+    /// Choose which streamer to connect to among the ones listed by ServersDiscovered
+    pub fn select_server(&self, ip: IpAddr) {
+        *SELECTED_SERVER.lock() = Some(ip);
     }
-}
 
-pub fn send_playspace(area: Option<Vec2>) {
-    if let Some(sender) = &mut *CONTROL_SENDER.lock() {
-        sender.send(&ClientControlPacket::PlayspaceSync(area)).ok();
+    /// Call in a loop until it returns None, at least once per frame. Not needed if an event
+    /// callback is set
+    pub fn poll_event(&self) -> Option<ClientCoreEvent> {
+        let event = EVENT_QUEUE.pop();
+
+        if let Some(event) = &event {
+            if is_haptics_event(event) {
+                QUEUED_HAPTICS_EVENTS.fetch_sub(1, Ordering::Relaxed);
+            }
+            report_event_delivered(event);
+        }
+
+        event
     }
-}
 
-pub fn send_buttons(entries: Vec<ButtonEntry>) {
-    if let Some(sender) = &mut *CONTROL_SENDER.lock() {
-        sender.send(&ClientControlPacket::Buttons(entries)).ok();
+    /// Alternative to poll_event(): events are delivered as soon as they are produced. Events
+    /// already queued are still returned by poll_event(). Pass None to go back to polling. The
+    /// previous callback may still be running on another thread when this returns
+    pub fn set_event_callback(&self, callback: Option<EventCallback>) {
+        *EVENT_CALLBACK.write() = callback;
+    }
+
+    /// Call when the views change, for example after the IPD was adjusted
+    pub fn send_views_config(&self, fov: [Fov; 2], ipd_m: f32, orientations: [Quat; 2]) {
+        if let Some(sender) = &*CONTROL_SENDER.lock() {
+            sender
+                .send(ClientControlPacket::ViewsConfig(ViewsConfig {
+                    fov,
+                    ipd_m,
+                    orientations,
+                }))
+                .ok();
+        }
     }
-}
 
-pub fn send_tracking(tracking: Tracking) {
-    if let Some(sender) = &mut *TRACKING_SENDER.lock() {
-        sender.send_header(&tracking).ok();
+    pub fn send_battery(&self, device_id: u64, gauge_value: f32, is_plugged: bool) {
+        if let Some(sender) = &*CONTROL_SENDER.lock() {
+            sender
+                .send(ClientControlPacket::Battery(BatteryPacket {
+                    device_id,
+                    gauge_value,
+                    is_plugged,
+                }))
+                .ok();
+        }
+    }
 
-        if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
-            stats.report_input_acquired(tracking.target_timestamp);
+    /// Pass the size of the guardian rectangle, or None if not available. The streamer recenters
+    /// when this is received
+    pub fn send_playspace(&self, area: Option<Vec2>) {
+        if let Some(sender) = &*CONTROL_SENDER.lock() {
+            sender.send(ClientControlPacket::PlayspaceSync(area)).ok();
         }
     }
-}
 
-pub fn get_head_prediction_offset() -> Duration {
-    if let Some(stats) = &*STATISTICS_MANAGER.lock() {
-        stats.average_total_pipeline_latency()
-    } else {
-        Duration::ZERO
+    /// Send only the buttons that changed. Controller chords are handled before forwarding
+    pub fn send_buttons(&self, mut entries: Vec<ButtonEntry>) {
+        let mut actions = vec![];
+        if let Some(mapper) = &mut *CHORD_MAPPER.lock() {
+            (entries, actions) = mapper.process(entries);
+        }
+
+        for action in actions {
+            match action {
+                ChordAction::Recenter => {
+                    if let Some(sender) = &*CONTROL_SENDER.lock() {
+                        sender.send(ClientControlPacket::Recenter).ok();
+                    }
+                }
+                ChordAction::TogglePassthrough => {
+                    push_event(ClientCoreEvent::PassthroughToggleRequested)
+                }
+                ChordAction::TogglePerformanceOverlay => {
+                    push_event(ClientCoreEvent::PerformanceOverlayToggleRequested)
+                }
+                ChordAction::PauseStream => self.pause_stream(),
+                ChordAction::ToggleSeatedMode => {
+                    if let Some(sender) = &*CONTROL_SENDER.lock() {
+                        sender.send(ClientControlPacket::ToggleSeatedMode).ok();
+                    }
+                }
+            }
+        }
+
+        if entries.is_empty() {
+            return;
+        }
+
+        if let Some(sender) = &*CONTROL_SENDER.lock() {
+            sender.send(ClientControlPacket::Buttons(entries)).ok();
+        }
     }
-}
 
-pub fn get_tracker_prediction_offset() -> Duration {
-    if let Some(stats) = &*STATISTICS_MANAGER.lock() {
-        stats.tracker_prediction_offset()
-    } else {
-        Duration::ZERO
+    /// The poses should be predicted to tracking.target_timestamp, which is the current time plus
+    /// get_head_prediction_offset()
+    pub fn send_tracking(&self, tracking: Tracking) {
+        if let Some(sender) = &*TRACKING_SENDER.lock() {
+            if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+                stats.report_input_acquired(tracking.target_timestamp);
+                stats.report_tracking_sent();
+
+                let controllers_angular_speed = tracking
+                    .device_motions
+                    .iter()
+                    .filter(|(id, _)| *id == *LEFT_HAND_ID || *id == *RIGHT_HAND_ID)
+                    .map(|(_, motion)| motion.angular_velocity.length())
+                    .fold(0.0, f32::max);
+                stats
+                    .report_controller_motion(tracking.target_timestamp, controllers_angular_speed);
+            }
+
+            sender.send_header(tracking).ok();
+        }
+    }
+
+    pub fn get_head_prediction_offset(&self) -> Duration {
+        if let Some(stats) = &*STATISTICS_MANAGER.lock() {
+            stats.average_total_pipeline_latency()
+        } else {
+            Duration::ZERO
+        }
     }
-}
 
-pub fn report_submit(target_timestamp: Duration, vsync_queue: Duration) {
-    if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
-        stats.report_submit(target_timestamp, vsync_queue);
+    pub fn get_tracker_prediction_offset(&self) -> Duration {
+        if let Some(stats) = &*STATISTICS_MANAGER.lock() {
+            stats.tracker_prediction_offset()
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// Returns the timestamp and the hardware buffer of the latest decoded frame. Call only without
+    /// external decoder
+    pub fn get_frame(&self) -> Option<(Duration, *mut std::ffi::c_void)> {
+        decoder::get_frame()
+    }
 
-        if let Some(sender) = &mut *STATISTICS_SENDER.lock() {
-            if let Some(stats) = stats.summary(target_timestamp) {
-                sender.send_header(&stats).ok();
-            } else {
-                error!("Statistics summary not ready!");
+    /// Call when no new frame was available and the previous one was shown again
+    pub fn report_frame_reprojected(&self) {
+        if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+            stats.report_frame_reprojected();
+        }
+    }
+
+    /// Call after submitting a frame to the compositor
+    pub fn report_submit(&self, target_timestamp: Duration, vsync_queue: Duration) {
+        if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+            stats.report_submit(target_timestamp, vsync_queue);
+
+            if let Some(sender) = &*STATISTICS_SENDER.lock() {
+                if let Some(stats) = stats.summary(target_timestamp) {
+                    sender.send_header(stats).ok();
+                } else {
+                    error!("Statistics summary not ready!");
+                }
             }
         }
     }
+
+    /// The new refresh rate must be one of ClientCapabilities::refresh_rates
+    pub fn request_refresh_rate(&self, refresh_rate: f32) {
+        if let Some(sender) = &*CONTROL_SENDER.lock() {
+            sender
+                .send(ClientControlPacket::RequestRefreshRate(refresh_rate))
+                .ok();
+        }
+    }
+
+    /// Suspend video and game audio while keeping the connection
+    pub fn pause_stream(&self) {
+        if Config::load()
+            .kiosk
+            .map_or(false, |kiosk| kiosk.disable_manual_disconnection)
+        {
+            warn!("Pausing the stream is disabled by the kiosk configuration");
+            return;
+        }
+
+        if let Some(sender) = &*CONTROL_SENDER.lock() {
+            sender.send(ClientControlPacket::PauseStream).ok();
+        }
+    }
+
+    pub fn resume_stream(&self) {
+        if let Some(sender) = &*CONTROL_SENDER.lock() {
+            sender.send(ClientControlPacket::ResumeStream).ok();
+        }
+    }
+
+    /// Call only with external decoder
+    pub fn request_idr(&self) {
+        if let Some(sender) = &*CONTROL_SENDER.lock() {
+            sender.send_urgent(ClientControlPacket::RequestIdr).ok();
+        }
+    }
+
+    /// Call only with external decoder
+    pub fn report_frame_decoded(&self, target_timestamp: Duration) {
+        if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+            stats.report_frame_decoded(target_timestamp);
+        }
+    }
+
+    /// Call only with external decoder
+    pub fn report_compositor_start(&self, target_timestamp: Duration) {
+        if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+            stats.report_compositor_start(target_timestamp);
+        }
+    }
 }
 
-/// Call only with external decoder
-pub fn request_idr() {
-    if let Some(sender) = &mut *CONTROL_SENDER.lock() {
-        sender.send(&ClientControlPacket::RequestIdr).ok();
+fn report_event_delivered(event: &ClientCoreEvent) {
+    if let ClientCoreEvent::Haptics { .. } = event {
+        if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+            stats.report_haptics_delivered();
+        }
     }
 }
 
-/// Call only with external decoder
-pub fn report_frame_decoded(target_timestamp: Duration) {
-    if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
-        stats.report_frame_decoded(target_timestamp);
+fn is_haptics_event(event: &ClientCoreEvent) -> bool {
+    matches!(
+        event,
+        ClientCoreEvent::Haptics { .. } | ClientCoreEvent::ExternalHaptics { .. }
+    )
+}
+
+// Can be called from any thread
+fn push_event(event: ClientCoreEvent) {
+    // The lock is released before invoking the callback, so the callback can replace itself
+    let callback = EVENT_CALLBACK.read().clone();
+    if let Some(callback) = callback {
+        report_event_delivered(&event);
+        callback(event);
+
+        return;
     }
+
+    // Only haptics are frequent enough to fill the memory if the frontend stops polling, and they
+    // are useless when late
+    if is_haptics_event(&event) {
+        if QUEUED_HAPTICS_EVENTS.load(Ordering::Relaxed) >= MAX_QUEUED_HAPTICS_EVENTS {
+            if !ARE_HAPTICS_EVENTS_DROPPED.value() {
+                // Warn only once until the queue is drained
+                ARE_HAPTICS_EVENTS_DROPPED.set(true);
+                warn!("Haptics events are being dropped. Is poll_event() called?");
+            }
+
+            return;
+        }
+
+        QUEUED_HAPTICS_EVENTS.fetch_add(1, Ordering::Relaxed);
+        ARE_HAPTICS_EVENTS_DROPPED.set(false);
+    }
+
+    EVENT_QUEUE.push(event);
 }
 
-/// Call only with external decoder
-pub fn report_compositor_start(target_timestamp: Duration) {
-    if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
-        stats.report_compositor_start(target_timestamp);
+impl Drop for ClientCoreContext {
+    fn drop(&mut self) {
+        IS_ALIVE.set(false);
+
+        if let Some(thread) = self.connection_thread.take() {
+            thread.join().ok();
+        }
+
+        #[cfg(target_os = "android")]
+        platform::release_wifi_lock();
     }
 }
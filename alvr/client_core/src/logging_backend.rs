@@ -15,6 +15,8 @@ const LOG_REPEAT_TIMEOUT: Duration = Duration::from_secs(1);
 pub struct LogMirrorData {
     pub sender: mpsc::Sender<ClientControlPacket>,
     pub filter_level: LogSeverity,
+    // Applied before the logs leave the headset, the SSID is only known here
+    pub anonymize: bool,
 }
 
 pub static LOG_CHANNEL_SENDER: Lazy<Mutex<Option<LogMirrorData>>> = Lazy::new(|| Mutex::new(None));
@@ -49,7 +51,11 @@ pub fn init_logging() {
             return;
         }
 
-        let message = format!("{}", record.args());
+        let message = if data.anonymize {
+            alvr_common::anonymize_log(&format!("{}", record.args()))
+        } else {
+            format!("{}", record.args())
+        };
 
         let mut last_log_event_lock = LAST_LOG_EVENT.lock();
 
@@ -1,7 +1,12 @@
 #![allow(unused_variables)]
 
-use alvr_common::{glam::UVec2, Fov, Pose};
-use alvr_session::FoveatedRenderingConfig;
+use alvr_common::{
+    glam::{Mat3, UVec2},
+    Fov, Pose,
+};
+use alvr_session::{
+    CaptionsConfig, ColorBlindnessFilterConfig, ColorBlindnessType, FoveatedRenderingConfig,
+};
 use glyph_brush_layout::{
     ab_glyph::{Font, FontRef, ScaleFont},
     FontId, GlyphPositioner, HorizontalAlign, Layout, SectionGeometry, SectionText, VerticalAlign,
@@ -13,6 +18,11 @@ include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 const HUD_TEXTURE_WIDTH: usize = 1280;
 const HUD_TEXTURE_HEIGHT: usize = 720;
 const FONT_SIZE: f32 = 50_f32;
+// Side by side, one square half per eye
+const CAPTION_TEXTURE_WIDTH: usize = 2048;
+const CAPTION_TEXTURE_HEIGHT: usize = 1024;
+const CAPTION_EYE_SIZE: usize = CAPTION_TEXTURE_WIDTH / 2;
+const CAPTION_BACKGROUND_ALPHA: f32 = 0.6;
 
 pub struct RenderViewInput {
     pub pose: Pose,
@@ -157,6 +167,150 @@ pub fn update_hud_message(message: &str) {
     }
 }
 
+// texture is a swapchain image of the same resolution. rgba is not premultiplied
+pub fn update_overlay_layer_texture(texture: u32, resolution: UVec2, rgba: &[u8]) {
+    #[cfg(target_os = "android")]
+    unsafe {
+        updateOverlayLayerTexture(texture, resolution.x, resolution.y, rgba.as_ptr());
+    }
+}
+
+// An empty text hides the caption
+pub fn update_caption(text: &str, config: &CaptionsConfig) {
+    if text.is_empty() {
+        #[cfg(target_os = "android")]
+        unsafe {
+            updateCaptionTexture(std::ptr::null());
+        }
+
+        return;
+    }
+
+    let ubuntu_font =
+        FontRef::try_from_slice(include_bytes!("../resources/Ubuntu-Medium.ttf")).unwrap();
+
+    let size = CAPTION_EYE_SIZE as f32;
+    let min_x = ((config.region_center_x - config.region_width / 2.0).max(0.0) * size) as usize;
+    let max_x = ((config.region_center_x + config.region_width / 2.0).min(1.0) * size) as usize;
+    let min_y = ((config.region_center_y - config.region_height / 2.0).max(0.0) * size) as usize;
+    let max_y = ((config.region_center_y + config.region_height / 2.0).min(1.0) * size) as usize;
+
+    // Lines are stacked from the bottom of the region, the oldest ones are cut when they don't fit
+    let section_glyphs = Layout::default_wrap()
+        .h_align(HorizontalAlign::Center)
+        .v_align(VerticalAlign::Bottom)
+        .calculate_glyphs(
+            &[&ubuntu_font],
+            &SectionGeometry {
+                screen_position: ((min_x + max_x) as f32 / 2_f32, max_y as f32),
+                bounds: ((max_x - min_x) as f32, f32::INFINITY),
+            },
+            &[SectionText {
+                text,
+                scale: config.font_size.into(),
+                font_id: FontId(0),
+            }],
+        );
+
+    let scaled_font = ubuntu_font.as_scaled(config.font_size);
+
+    let mut coverage = vec![0_f32; CAPTION_EYE_SIZE * CAPTION_EYE_SIZE];
+    for section_glyph in section_glyphs {
+        if let Some(outlined) = scaled_font.outline_glyph(section_glyph.glyph) {
+            let bounds = outlined.px_bounds();
+            outlined.draw(|x, y, alpha| {
+                let x = x as i32 + bounds.min.x as i32;
+                let y = y as i32 + bounds.min.y as i32;
+                if (min_x as i32..max_x as i32).contains(&x)
+                    && (min_y as i32..max_y as i32).contains(&y)
+                {
+                    coverage[y as usize * CAPTION_EYE_SIZE + x as usize] = alpha;
+                }
+            });
+        }
+    }
+
+    // White text over a translucent black box, copied to both halves
+    let mut buffer = vec![0_u8; CAPTION_TEXTURE_WIDTH * CAPTION_TEXTURE_HEIGHT * 4];
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let text_alpha = coverage[y * CAPTION_EYE_SIZE + x];
+            let alpha = text_alpha + (1.0 - text_alpha) * CAPTION_BACKGROUND_ALPHA;
+            let pixel = [
+                (text_alpha / alpha * 255.0) as u8,
+                (text_alpha / alpha * 255.0) as u8,
+                (text_alpha / alpha * 255.0) as u8,
+                (alpha * 255.0) as u8,
+            ];
+
+            for eye_offset in [0, CAPTION_EYE_SIZE] {
+                let index = (y * CAPTION_TEXTURE_WIDTH + eye_offset + x) * 4;
+                buffer[index..index + 4].copy_from_slice(&pixel);
+            }
+        }
+    }
+
+    #[cfg(target_os = "android")]
+    unsafe {
+        updateCaptionTexture(buffer.as_ptr());
+    }
+}
+
+// Daltonization: the colors lost with the deficiency are simulated and the error is shifted towards
+// the colors that can still be told apart
+fn color_blindness_filter_matrix(config: &ColorBlindnessFilterConfig) -> Mat3 {
+    // Matrices are written by rows
+    let rgb_to_lms = Mat3::from_cols_array(&[
+        17.8824, 43.5161, 4.11935, //
+        3.45565, 27.1554, 3.86714, //
+        0.0299566, 0.184309, 1.46709,
+    ])
+    .transpose();
+    let lms_simulation = match config.deficiency {
+        ColorBlindnessType::Protanopia => Mat3::from_cols_array(&[
+            0.0, 2.02344, -2.52581, //
+            0.0, 1.0, 0.0, //
+            0.0, 0.0, 1.0,
+        ]),
+        ColorBlindnessType::Deuteranopia => Mat3::from_cols_array(&[
+            1.0, 0.0, 0.0, //
+            0.494207, 0.0, 1.24827, //
+            0.0, 0.0, 1.0,
+        ]),
+        ColorBlindnessType::Tritanopia => Mat3::from_cols_array(&[
+            1.0, 0.0, 0.0, //
+            0.0, 1.0, 0.0, //
+            -0.395913, 0.801109, 0.0,
+        ]),
+    }
+    .transpose();
+    let error_shift = Mat3::from_cols_array(&[
+        0.0, 0.0, 0.0, //
+        0.7, 1.0, 0.0, //
+        0.7, 0.0, 1.0,
+    ])
+    .transpose();
+
+    let simulation = rgb_to_lms.inverse() * lms_simulation * rgb_to_lms;
+    let correction = error_shift * (Mat3::IDENTITY - simulation);
+
+    Mat3::IDENTITY + correction * config.strength
+}
+
+// Applied when displaying the stream, pass None to remove the filter
+pub fn set_color_blindness_filter(config: Option<&ColorBlindnessFilterConfig>) {
+    let matrix = config.map(color_blindness_filter_matrix);
+
+    #[cfg(target_os = "android")]
+    unsafe {
+        if let Some(matrix) = matrix {
+            setColorBlindnessFilterNative(matrix.to_cols_array().as_ptr());
+        } else {
+            setColorBlindnessFilterNative(std::ptr::null());
+        }
+    }
+}
+
 pub fn render_lobby(view_inputs: [RenderViewInput; 2]) {
     #[cfg(target_os = "android")]
     unsafe {
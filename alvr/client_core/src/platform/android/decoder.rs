@@ -1,8 +1,8 @@
-use crate::decoder::DecoderInitConfig;
+use crate::decoder::{DecoderInitConfig, VideoDecoderSink, VideoDecoderSource, PRIMING_TIMESTAMP};
 use alvr_common::{
-    anyhow::{bail, Result},
+    anyhow::{anyhow, bail, Context, Result},
     error, info,
-    parking_lot::{Condvar, Mutex},
+    parking_lot::Mutex,
     warn, RelaxedAtomic,
 };
 use alvr_session::{CodecType, MediacodecDataType};
@@ -15,13 +15,14 @@ use ndk::{
             MediaCodecDirection, MediaFormat,
         },
     },
+    native_window::NativeWindow,
 };
 use std::{
     collections::VecDeque,
     ffi::c_void,
     ops::Deref,
     ptr,
-    sync::Arc,
+    sync::{mpsc, Arc},
     thread::{self, JoinHandle},
     time::Duration,
 };
@@ -40,15 +41,15 @@ impl<T> Deref for FakeThreadSafe<T> {
 
 type SharedMediaCodec = Arc<FakeThreadSafe<MediaCodec>>;
 
-pub struct VideoDecoderSink {
+pub struct MediacodecDecoderSink {
     inner: Arc<Mutex<Option<SharedMediaCodec>>>,
 }
 
-unsafe impl Send for VideoDecoderSink {}
+unsafe impl Send for MediacodecDecoderSink {}
 
-impl VideoDecoderSink {
+impl VideoDecoderSink for MediacodecDecoderSink {
     // Block until the buffer has been written or timeout is reached. Returns false if timeout.
-    pub fn push_frame_nal(&mut self, timestamp: Duration, data: &[u8]) -> Result<bool> {
+    fn push_frame_nal(&mut self, timestamp: Duration, data: &[u8]) -> Result<bool> {
         let Some(decoder) = &*self.inner.lock() else {
             // This might happen only during destruction
             return Ok(false);
@@ -85,7 +86,7 @@ struct QueuedImage {
 unsafe impl Send for QueuedImage {}
 
 // Access the image queue synchronously.
-pub struct VideoDecoderSource {
+pub struct MediacodecDecoderSource {
     running: Arc<RelaxedAtomic>,
     dequeue_thread: Option<JoinHandle<()>>,
     image_queue: Arc<Mutex<VecDeque<QueuedImage>>>,
@@ -93,11 +94,11 @@ pub struct VideoDecoderSource {
     buffering_running_average: f32,
 }
 
-unsafe impl Send for VideoDecoderSource {}
+unsafe impl Send for MediacodecDecoderSource {}
 
-impl VideoDecoderSource {
+impl VideoDecoderSource for MediacodecDecoderSource {
     // The application MUST finish using the returned buffer before calling this function again
-    pub fn dequeue_frame(&mut self) -> Option<(Duration, *mut c_void)> {
+    fn dequeue_frame(&mut self) -> Option<(Duration, *mut c_void)> {
         let mut image_queue_lock = self.image_queue.lock();
 
         if let Some(queued_image) = image_queue_lock.front() {
@@ -135,7 +136,7 @@ impl VideoDecoderSource {
     }
 }
 
-impl Drop for VideoDecoderSource {
+impl Drop for MediacodecDecoderSource {
     fn drop(&mut self) {
         self.running.set(false);
 
@@ -144,22 +145,104 @@ impl Drop for VideoDecoderSource {
     }
 }
 
-// Create a sink/source pair
+fn media_format(
+    mime: &str,
+    csd_0: &[u8],
+    config: &DecoderInitConfig,
+    options: &[(String, MediacodecDataType)],
+) -> MediaFormat {
+    let format = MediaFormat::new();
+    format.set_str("mime", mime);
+    format.set_i32("width", 512);
+    format.set_i32("height", 1024);
+    format.set_buffer("csd-0", csd_0);
+
+    // Let the decoder output a frame as soon as all its slices are decoded instead of waiting for
+    // more input. User options can still override this
+    if config.slice_count > 1 {
+        format.set_i32("low-latency", 1);
+    }
+
+    for (key, value) in options {
+        match value {
+            MediacodecDataType::Float(value) => format.set_f32(key, *value),
+            MediacodecDataType::Int32(value) => format.set_i32(key, *value),
+            MediacodecDataType::Int64(value) => format.set_i64(key, *value),
+            MediacodecDataType::String(value) => format.set_str(key, value),
+        }
+    }
+
+    format
+}
+
+fn start_codec(mime: &str, format: &MediaFormat, window: &NativeWindow) -> Result<MediaCodec> {
+    let codec = MediaCodec::from_decoder_type(mime).context("No decoder for this codec")?;
+    codec.configure(format, Some(window), MediaCodecDirection::Decoder)?;
+    codec.start()?;
+
+    info!("Using AMediaCoded format:{} ", format);
+
+    Ok(codec)
+}
+
+// If the decoder does not start with the extra options, they are added back one at a time to find
+// the incompatible ones. Returns the started decoder and the keys of the rejected options
+fn start_codec_with_fallback(
+    mime: &str,
+    csd_0: &[u8],
+    config: &DecoderInitConfig,
+    window: &NativeWindow,
+) -> Result<(MediaCodec, Vec<String>)> {
+    match start_codec(
+        mime,
+        &media_format(mime, csd_0, config, &config.options),
+        window,
+    ) {
+        Ok(codec) => return Ok((codec, vec![])),
+        Err(e) if config.options.is_empty() => return Err(e),
+        Err(e) => warn!("Decoder failed to start with the extra options ({e}), retrying"),
+    }
+
+    let mut accepted_options = vec![];
+    let mut rejected_keys = vec![];
+    for option in &config.options {
+        accepted_options.push(option.clone());
+
+        let format = media_format(mime, csd_0, config, &accepted_options);
+        match start_codec(mime, &format, window) {
+            Ok(codec) => {
+                codec.stop().ok();
+            }
+            Err(e) => {
+                warn!("Decoder option {} is not supported: {e}", option.0);
+
+                accepted_options.pop();
+                rejected_keys.push(option.0.clone());
+            }
+        }
+    }
+
+    let format = media_format(mime, csd_0, config, &accepted_options);
+    let codec = start_codec(mime, &format, window)?;
+
+    Ok((codec, rejected_keys))
+}
+
+// Create a sink/source pair. Also returns the keys of the extra options rejected by the decoder
 pub fn video_decoder_split(
     config: DecoderInitConfig,
     csd_0: Vec<u8>,
     dequeued_frame_callback: impl Fn(Duration) + Send + 'static,
-) -> Result<(VideoDecoderSink, VideoDecoderSource)> {
+) -> Result<(MediacodecDecoderSink, MediacodecDecoderSource, Vec<String>)> {
     let running = Arc::new(RelaxedAtomic::new(true));
     let decoder_sink = Arc::new(Mutex::new(None::<SharedMediaCodec>));
-    let decoder_ready_notifier = Arc::new(Condvar::new());
+    let (startup_sender, startup_receiver) = mpsc::channel::<Result<Vec<String>>>();
     let image_queue = Arc::new(Mutex::new(VecDeque::<QueuedImage>::new()));
 
     let dequeue_thread = thread::spawn({
         let config = config.clone();
         let running = Arc::clone(&running);
         let decoder_sink = Arc::clone(&decoder_sink);
-        let decoder_ready_notifier = Arc::clone(&decoder_ready_notifier);
         let image_queue = Arc::clone(&image_queue);
         move || {
             const MAX_BUFFERING_FRAMES: usize = 10;
@@ -172,21 +255,6 @@ pub fn video_decoder_split(
                 CodecType::Hevc => "video/hevc",
             };
 
-            let format = MediaFormat::new();
-            format.set_str("mime", mime);
-            format.set_i32("width", 512);
-            format.set_i32("height", 1024);
-            format.set_buffer("csd-0", &csd_0);
-
-            for (key, value) in &config.options {
-                match value {
-                    MediacodecDataType::Float(value) => format.set_f32(key, *value),
-                    MediacodecDataType::Int32(value) => format.set_i32(key, *value),
-                    MediacodecDataType::Int64(value) => format.set_i64(key, *value),
-                    MediacodecDataType::String(value) => format.set_str(key, value),
-                }
-            }
-
             let mut image_reader = ImageReader::new_with_usage(
                 1,
                 1,
@@ -213,6 +281,11 @@ pub fn video_decoder_split(
                                 let timestamp =
                                     Duration::from_nanos(image.get_timestamp().unwrap() as u64);
 
+                                // Output of priming frames is never shown
+                                if timestamp == PRIMING_TIMESTAMP {
+                                    return;
+                                }
+
                                 dequeued_frame_callback(timestamp);
 
                                 image_queue_lock.push_back(QueuedImage {
@@ -242,27 +315,25 @@ pub fn video_decoder_split(
                 .set_buffer_removed_listener(Box::new(|_, _| ()))
                 .unwrap();
 
-            let decoder = Arc::new(FakeThreadSafe(
-                MediaCodec::from_decoder_type(&mime).unwrap(),
-            ));
-
-            info!("Using AMediaCoded format:{} ", format);
-            decoder
-                .configure(
-                    &format,
-                    Some(&image_reader.get_window().unwrap()),
-                    MediaCodecDirection::Decoder,
-                )
-                .unwrap();
-            decoder.start().unwrap();
-
-            {
-                let mut decoder_lock = decoder_sink.lock();
-
-                *decoder_lock = Some(Arc::clone(&decoder));
+            let res = image_reader
+                .get_window()
+                .map_err(|e| anyhow!("{e}"))
+                .and_then(|window| start_codec_with_fallback(mime, &csd_0, &config, &window));
+            let decoder = match res {
+                Ok((decoder, rejected_option_keys)) => {
+                    let decoder = Arc::new(FakeThreadSafe(decoder));
+                    *decoder_sink.lock() = Some(Arc::clone(&decoder));
+                    startup_sender.send(Ok(rejected_option_keys)).ok();
+
+                    decoder
+                }
+                Err(e) => {
+                    startup_sender.send(Err(e)).ok();
+                    Box::leak(Box::new(image_reader));
 
-                decoder_ready_notifier.notify_one();
-            }
+                    return;
+                }
+            };
 
             while running.value() {
                 match decoder.dequeue_output_buffer(Duration::from_millis(1)) {
@@ -300,19 +371,14 @@ pub fn video_decoder_split(
 
     // Make sure the decoder is ready: we don't want to try to enqueue frame and lose them, to avoid
     // image corruption.
-    {
-        let mut decoder_lock = decoder_sink.lock();
-
-        if decoder_lock.is_none() {
-            // No spurious wakeups
-            decoder_ready_notifier.wait(&mut decoder_lock);
-        }
-    }
+    let rejected_option_keys = startup_receiver
+        .recv()
+        .map_err(|_| anyhow!("Decoder thread panicked"))??;
 
-    let sink = VideoDecoderSink {
+    let sink = MediacodecDecoderSink {
         inner: decoder_sink,
     };
-    let source = VideoDecoderSource {
+    let source = MediacodecDecoderSource {
         running,
         dequeue_thread: Some(dequeue_thread),
         image_queue,
@@ -320,5 +386,5 @@ pub fn video_decoder_split(
         buffering_running_average: 0.0,
     };
 
-    Ok((sink, source))
+    Ok((sink, source, rejected_option_keys))
 }
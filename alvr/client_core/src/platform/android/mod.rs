@@ -1,18 +1,24 @@
 mod decoder;
+mod wifi;
 
 pub use decoder::*;
+pub use wifi::*;
 
-use alvr_common::{once_cell::sync::Lazy, parking_lot::Mutex};
+use alvr_common::glam::UVec2;
+use alvr_packets::ThermalStatus;
+use alvr_session::{CodecType, VideoDecoderInfo};
 use jni::{
-    objects::{GlobalRef, JObject},
+    objects::{GlobalRef, JObject, JObjectArray},
     sys::jobject,
     JNIEnv, JavaVM,
 };
-use std::net::{IpAddr, Ipv4Addr};
+use std::{
+    fs,
+    net::{IpAddr, Ipv4Addr},
+};
 
 pub const MICROPHONE_PERMISSION: &str = "android.permission.RECORD_AUDIO";
-
-static WIFI_LOCK: Lazy<Mutex<Option<GlobalRef>>> = Lazy::new(|| Mutex::new(None));
+pub const LOCATION_PERMISSION: &str = "android.permission.ACCESS_FINE_LOCATION";
 
 pub fn vm() -> JavaVM {
     unsafe { JavaVM::from_raw(ndk_context::android_context().vm().cast()).unwrap() }
@@ -95,6 +101,42 @@ pub fn manufacturer_name() -> String {
     name_raw.to_string_lossy().as_ref().to_owned()
 }
 
+fn build_string(class: &str, field: &str) -> String {
+    let vm = vm();
+    let mut env = vm.attach_current_thread().unwrap();
+
+    let jstring = env
+        .get_static_field(class, field, "Ljava/lang/String;")
+        .unwrap()
+        .l()
+        .unwrap();
+    let string_raw = env.get_string((&jstring).into()).unwrap();
+
+    string_raw.to_string_lossy().as_ref().to_owned()
+}
+
+pub fn soc_model() -> String {
+    // Build.SOC_MODEL is available from API level 31
+    if get_api_level() >= 31 {
+        format!(
+            "{} {}",
+            build_string("android/os/Build", "SOC_MANUFACTURER"),
+            build_string("android/os/Build", "SOC_MODEL")
+        )
+    } else {
+        build_string("android/os/Build", "HARDWARE")
+    }
+}
+
+// Build.DISPLAY identifies the firmware build
+pub fn os_version() -> String {
+    format!(
+        "Android {} ({})",
+        build_string("android/os/Build$VERSION", "RELEASE"),
+        build_string("android/os/Build", "DISPLAY")
+    )
+}
+
 fn get_system_service<'a>(env: &mut JNIEnv<'a>, service_name: &str) -> JObject<'a> {
     let service_str = env.new_string(service_name).unwrap();
 
@@ -136,51 +178,338 @@ pub fn local_ip() -> IpAddr {
     IpAddr::V4(Ipv4Addr::new(ip_arr[0], ip_arr[1], ip_arr[2], ip_arr[3]))
 }
 
-// This is needed to avoid wifi scans that disrupt streaming.
-pub fn acquire_wifi_lock() {
-    let mut maybe_wifi_lock = WIFI_LOCK.lock();
+// Returns None if not connected to Wi-Fi or if the location permission was not granted
+pub fn wifi_ssid() -> Option<String> {
+    let vm = vm();
+    let mut env = vm.attach_current_thread().unwrap();
 
-    if maybe_wifi_lock.is_none() {
-        let vm = vm();
-        let mut env = vm.attach_current_thread().unwrap();
+    let wifi_manager = get_system_service(&mut env, "wifi");
+    let wifi_info = env
+        .call_method(
+            wifi_manager,
+            "getConnectionInfo",
+            "()Landroid/net/wifi/WifiInfo;",
+            &[],
+        )
+        .ok()?
+        .l()
+        .ok()?;
+    if wifi_info.is_null() {
+        return None;
+    }
+
+    let jssid = env
+        .call_method(wifi_info, "getSSID", "()Ljava/lang/String;", &[])
+        .ok()?
+        .l()
+        .ok()?;
+    if jssid.is_null() {
+        return None;
+    }
+    let ssid = env
+        .get_string((&jssid).into())
+        .ok()?
+        .to_string_lossy()
+        .as_ref()
+        .to_owned();
+
+    // UTF-8 SSIDs are surrounded by quotes, otherwise the SSID is hex encoded
+    if ssid == "<unknown ssid>" {
+        None
+    } else if let Some(ssid) = ssid.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(ssid.to_owned())
+    } else {
+        Some(ssid)
+    }
+}
 
-        let wifi_mode = if get_api_level() >= 29 {
-            // Recommended for virtual reality since it disables WIFI scans
-            4 // WIFI_MODE_FULL_LOW_LATENCY
-        } else {
-            3 // WIFI_MODE_FULL_HIGH_PERF
-        };
+pub fn thermal_status() -> ThermalStatus {
+    // PowerManager.getCurrentThermalStatus() is available from API level 29
+    if get_api_level() < 29 {
+        return ThermalStatus::None;
+    }
 
-        let wifi_manager = get_system_service(&mut env, "wifi");
-        let wifi_lock_jstring = env.new_string("alvr_wifi_lock").unwrap();
-        let wifi_lock = env
-            .call_method(
-                wifi_manager,
-                "createWifiLock",
-                "(ILjava/lang/String;)Landroid/net/wifi/WifiManager$WifiLock;",
-                &[wifi_mode.into(), (&wifi_lock_jstring).into()],
-            )
-            .unwrap()
-            .l()
-            .unwrap();
-        env.call_method(&wifi_lock, "acquire", "()V", &[]).unwrap();
+    let vm = vm();
+    let mut env = vm.attach_current_thread().unwrap();
+
+    let power_manager = get_system_service(&mut env, "power");
+    let status = env
+        .call_method(power_manager, "getCurrentThermalStatus", "()I", &[])
+        .unwrap()
+        .i()
+        .unwrap();
 
-        *maybe_wifi_lock = Some(env.new_global_ref(wifi_lock).unwrap());
+    match status {
+        1 => ThermalStatus::Light,
+        2 => ThermalStatus::Moderate,
+        3 => ThermalStatus::Severe,
+        4 => ThermalStatus::Critical,
+        5 => ThermalStatus::Emergency,
+        6 => ThermalStatus::Shutdown,
+        _ => ThermalStatus::None,
     }
 }
 
-pub fn release_wifi_lock() {
-    if let Some(wifi_lock) = WIFI_LOCK.lock().take() {
-        let vm = vm();
-        let mut env = vm.attach_current_thread().unwrap();
+// Average over all cores of the current over max frequency. Returns None if cpufreq is not
+// readable, which depends on the device
+pub fn cpu_frequency_ratio() -> Option<f32> {
+    let read_khz =
+        |path: String| -> Option<f32> { fs::read_to_string(path).ok()?.trim().parse().ok() };
+
+    let ratios = fs::read_dir("/sys/devices/system/cpu/cpufreq")
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let policy_dir = entry.path().to_string_lossy().into_owned();
+            let current = read_khz(format!("{policy_dir}/scaling_cur_freq"))?;
+            let max = read_khz(format!("{policy_dir}/cpuinfo_max_freq"))?;
+
+            (max > 0.0).then_some(current / max)
+        })
+        .collect::<Vec<_>>();
+
+    (!ratios.is_empty()).then(|| ratios.iter().sum::<f32>() / ratios.len() as f32)
+}
 
-        env.call_method(wifi_lock.as_obj(), "release", "()V", &[])
-            .unwrap();
-        // TODO: all JVM.call_method sometimes result in JavaExceptions, unwrap will only report Error as 'JavaException', ideally before unwrapping
-        // need to call JVM.describe_error() which will actually check if there is an exception and print error to stderr/logcat. Then unwrap.
+fn range_upper(env: &mut JNIEnv, object: &JObject, method: &str) -> jni::errors::Result<i32> {
+    let range = env
+        .call_method(object, method, "()Landroid/util/Range;", &[])?
+        .l()?;
+    let upper = env
+        .call_method(&range, "getUpper", "()Ljava/lang/Comparable;", &[])?
+        .l()?;
+
+    env.call_method(&upper, "intValue", "()I", &[])?.i()
+}
+
+// Largest frame size supported by any decoder of each codec, according to MediaCodecList. Codecs
+// without decoders are omitted
+pub fn max_decoder_resolutions() -> Vec<(CodecType, UVec2)> {
+    let vm = vm();
+    let mut env = vm.attach_current_thread().unwrap();
+
+    // 0: MediaCodecList.REGULAR_CODECS
+    let codec_list = env
+        .new_object("android/media/MediaCodecList", "(I)V", &[0.into()])
+        .unwrap();
+    let codec_infos = JObjectArray::from(
+        env.call_method(
+            &codec_list,
+            "getCodecInfos",
+            "()[Landroid/media/MediaCodecInfo;",
+            &[],
+        )
+        .unwrap()
+        .l()
+        .unwrap(),
+    );
+    let codecs_count = env.get_array_length(&codec_infos).unwrap();
+
+    let mut resolutions = vec![];
+    for (codec, mime) in [
+        (CodecType::H264, "video/avc"),
+        (CodecType::Hevc, "video/hevc"),
+    ] {
+        let mut max_resolution = None::<UVec2>;
+        for idx in 0..codecs_count {
+            // Use a local frame per codec to avoid overflowing the local reference table
+            let resolution = env.with_local_frame(16, |env| -> jni::errors::Result<_> {
+                let info = env.get_object_array_element(&codec_infos, idx)?;
+                if env.call_method(&info, "isEncoder", "()Z", &[])?.z()? {
+                    return Ok(None);
+                }
+
+                let mime_jstring = env.new_string(mime)?;
+                let capabilities = match env.call_method(
+                    &info,
+                    "getCapabilitiesForType",
+                    "(Ljava/lang/String;)Landroid/media/MediaCodecInfo$CodecCapabilities;",
+                    &[(&mime_jstring).into()],
+                ) {
+                    Ok(capabilities) => capabilities.l()?,
+                    Err(_) => {
+                        // Thrown when the codec doesn't support this type
+                        env.exception_clear()?;
+                        return Ok(None);
+                    }
+                };
+                let video_capabilities = env
+                    .call_method(
+                        &capabilities,
+                        "getVideoCapabilities",
+                        "()Landroid/media/MediaCodecInfo$VideoCapabilities;",
+                        &[],
+                    )?
+                    .l()?;
+                if video_capabilities.is_null() {
+                    return Ok(None);
+                }
+
+                Ok(Some(UVec2::new(
+                    range_upper(env, &video_capabilities, "getSupportedWidths")? as u32,
+                    range_upper(env, &video_capabilities, "getSupportedHeights")? as u32,
+                )))
+            });
+
+            if let Ok(Some(resolution)) = resolution {
+                max_resolution = Some(max_resolution.map_or(resolution, |max| max.max(resolution)));
+            }
+        }
+
+        if let Some(resolution) = max_resolution {
+            resolutions.push((codec, resolution));
+        }
+    }
+
+    resolutions
+}
+
+// Video decoders listed by MediaCodecList that support at least one of the codecs of the stream
+pub fn video_decoders() -> Vec<VideoDecoderInfo> {
+    // MediaCodecInfo.isHardwareAccelerated() is available from API level 29
+    let can_query_acceleration = get_api_level() >= 29;
+
+    let vm = vm();
+    let mut env = vm.attach_current_thread().unwrap();
+
+    // 0: MediaCodecList.REGULAR_CODECS
+    let codec_list = env
+        .new_object("android/media/MediaCodecList", "(I)V", &[0.into()])
+        .unwrap();
+    let codec_infos = JObjectArray::from(
+        env.call_method(
+            &codec_list,
+            "getCodecInfos",
+            "()[Landroid/media/MediaCodecInfo;",
+            &[],
+        )
+        .unwrap()
+        .l()
+        .unwrap(),
+    );
+    let codecs_count = env.get_array_length(&codec_infos).unwrap();
+
+    let mut decoders = vec![];
+    for idx in 0..codecs_count {
+        // Use a local frame per codec to avoid overflowing the local reference table
+        let decoder = env.with_local_frame(16, |env| -> jni::errors::Result<_> {
+            let info = env.get_object_array_element(&codec_infos, idx)?;
+            if env.call_method(&info, "isEncoder", "()Z", &[])?.z()? {
+                return Ok(None);
+            }
+
+            let types = JObjectArray::from(
+                env.call_method(&info, "getSupportedTypes", "()[Ljava/lang/String;", &[])?
+                    .l()?,
+            );
+            let mut codecs = vec![];
+            for type_idx in 0..env.get_array_length(&types)? {
+                let mime = env.get_object_array_element(&types, type_idx)?;
+                let mime = env
+                    .get_string((&mime).into())?
+                    .to_string_lossy()
+                    .to_lowercase();
+                match mime.as_str() {
+                    "video/avc" => codecs.push(CodecType::H264),
+                    "video/hevc" => codecs.push(CodecType::Hevc),
+                    _ => (),
+                }
+            }
+            if codecs.is_empty() {
+                return Ok(None);
+            }
+
+            let name = env
+                .call_method(&info, "getName", "()Ljava/lang/String;", &[])?
+                .l()?;
+            let name = env
+                .get_string((&name).into())?
+                .to_string_lossy()
+                .into_owned();
+
+            let hardware_accelerated = if can_query_acceleration {
+                Some(
+                    env.call_method(&info, "isHardwareAccelerated", "()Z", &[])?
+                        .z()?,
+                )
+            } else {
+                None
+            };
+
+            Ok(Some(VideoDecoderInfo {
+                name,
+                codecs,
+                hardware_accelerated,
+            }))
+        });
+
+        if let Ok(Some(decoder)) = decoder {
+            decoders.push(decoder);
+        }
+    }
+
+    decoders
+}
+
+pub struct AudioOutputDevice {
+    pub name: String,
+    pub is_bluetooth: bool,
+}
+
+fn find_audio_output_device(
+    env: &mut JNIEnv,
+    device_id: i32,
+) -> jni::errors::Result<Option<AudioOutputDevice>> {
+    // AudioDeviceInfo types: TYPE_BLUETOOTH_SCO, TYPE_BLUETOOTH_A2DP, TYPE_BLE_HEADSET,
+    // TYPE_BLE_SPEAKER
+    const BLUETOOTH_TYPES: [i32; 4] = [7, 8, 26, 27];
+
+    let audio_manager = get_system_service(env, "audio");
+    // 2: AudioManager.GET_DEVICES_OUTPUTS
+    let devices = JObjectArray::from(
+        env.call_method(
+            audio_manager,
+            "getDevices",
+            "(I)[Landroid/media/AudioDeviceInfo;",
+            &[2.into()],
+        )?
+        .l()?,
+    );
+
+    for i in 0..env.get_array_length(&devices)? {
+        let device = env.get_object_array_element(&devices, i)?;
+
+        if env.call_method(&device, "getId", "()I", &[])?.i()? != device_id {
+            continue;
+        }
 
-        // wifi_lock is dropped here
+        let device_type = env.call_method(&device, "getType", "()I", &[])?.i()?;
+        let jname = env
+            .call_method(&device, "getProductName", "()Ljava/lang/CharSequence;", &[])?
+            .l()?;
+        let jname = env
+            .call_method(&jname, "toString", "()Ljava/lang/String;", &[])?
+            .l()?;
+        let name = env
+            .get_string((&jname).into())?
+            .to_string_lossy()
+            .into_owned();
+
+        return Ok(Some(AudioOutputDevice {
+            name,
+            is_bluetooth: BLUETOOTH_TYPES.contains(&device_type),
+        }));
     }
+
+    Ok(None)
+}
+
+// device_id is the one reported by the audio stream. Returns None if the device is not found
+pub fn audio_output_device(device_id: i32) -> Option<AudioOutputDevice> {
+    let vm = vm();
+    let mut env = vm.attach_current_thread().unwrap();
+
+    find_audio_output_device(&mut env, device_id).ok().flatten()
 }
 
 pub struct BatteryManager {
@@ -0,0 +1,327 @@
+use super::{get_api_level, get_system_service, try_get_permission, vm, LOCATION_PERMISSION};
+use alvr_common::{
+    anyhow::{bail, Result},
+    once_cell::sync::Lazy,
+    parking_lot::Mutex,
+};
+use alvr_packets::{WifiLinkInfo, WifiNetwork, WifiScanReport};
+use jni::{
+    objects::{GlobalRef, JObject},
+    JNIEnv,
+};
+use std::{thread, time::Duration};
+
+// Scan results are delivered asynchronously with a broadcast. Waiting is simpler and the scan
+// takes a few seconds anyway
+const SCAN_RESULTS_WAIT: Duration = Duration::from_secs(4);
+
+static WIFI_LOCK: Lazy<Mutex<Option<GlobalRef>>> = Lazy::new(|| Mutex::new(None));
+
+// This is needed to avoid wifi scans that disrupt streaming.
+pub fn acquire_wifi_lock() {
+    let mut maybe_wifi_lock = WIFI_LOCK.lock();
+
+    if maybe_wifi_lock.is_none() {
+        let vm = vm();
+        let mut env = vm.attach_current_thread().unwrap();
+
+        let wifi_mode = if get_api_level() >= 29 {
+            // Recommended for virtual reality since it disables WIFI scans
+            4 // WIFI_MODE_FULL_LOW_LATENCY
+        } else {
+            3 // WIFI_MODE_FULL_HIGH_PERF
+        };
+
+        let wifi_manager = get_system_service(&mut env, "wifi");
+        let wifi_lock_jstring = env.new_string("alvr_wifi_lock").unwrap();
+        let wifi_lock = env
+            .call_method(
+                wifi_manager,
+                "createWifiLock",
+                "(ILjava/lang/String;)Landroid/net/wifi/WifiManager$WifiLock;",
+                &[wifi_mode.into(), (&wifi_lock_jstring).into()],
+            )
+            .unwrap()
+            .l()
+            .unwrap();
+        env.call_method(&wifi_lock, "acquire", "()V", &[]).unwrap();
+
+        *maybe_wifi_lock = Some(env.new_global_ref(wifi_lock).unwrap());
+    }
+}
+
+pub fn release_wifi_lock() {
+    if let Some(wifi_lock) = WIFI_LOCK.lock().take() {
+        let vm = vm();
+        let mut env = vm.attach_current_thread().unwrap();
+
+        env.call_method(wifi_lock.as_obj(), "release", "()V", &[])
+            .unwrap();
+        // TODO: all JVM.call_method sometimes result in JavaExceptions, unwrap will only report Error as 'JavaException', ideally before unwrapping
+        // need to call JVM.describe_error() which will actually check if there is an exception and print error to stderr/logcat. Then unwrap.
+
+        // wifi_lock is dropped here
+    }
+}
+
+// The lock can be released by the system, for example when the Wi-Fi is toggled
+fn wifi_lock_lost() -> bool {
+    if let Some(wifi_lock) = &*WIFI_LOCK.lock() {
+        let vm = vm();
+        let mut env = vm.attach_current_thread().unwrap();
+
+        !env.call_method(wifi_lock.as_obj(), "isHeld", "()Z", &[])
+            .and_then(|held| held.z())
+            .unwrap_or(true)
+    } else {
+        false
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct WifiLinkState {
+    associated: bool,
+    bssid: Option<String>,
+    frequency_mhz: i32,
+    device_idle: bool,
+}
+
+fn link_state(env: &mut JNIEnv) -> jni::errors::Result<WifiLinkState> {
+    let wifi_manager = get_system_service(env, "wifi");
+    let wifi_info = env
+        .call_method(
+            wifi_manager,
+            "getConnectionInfo",
+            "()Landroid/net/wifi/WifiInfo;",
+            &[],
+        )?
+        .l()?;
+
+    let associated = env
+        .call_method(&wifi_info, "getNetworkId", "()I", &[])?
+        .i()?
+        != -1;
+    let jbssid = env
+        .call_method(&wifi_info, "getBSSID", "()Ljava/lang/String;", &[])?
+        .l()?;
+    let bssid = if jbssid.is_null() {
+        None
+    } else {
+        Some(env.get_string((&jbssid).into())?.into())
+    };
+    let frequency_mhz = env
+        .call_method(&wifi_info, "getFrequency", "()I", &[])?
+        .i()?;
+
+    // Doze: network access is deferred and the Wi-Fi chip can enter power save
+    let power_manager = get_system_service(env, "power");
+    let device_idle = env
+        .call_method(power_manager, "isDeviceIdleMode", "()Z", &[])?
+        .z()?;
+
+    Ok(WifiLinkState {
+        associated,
+        bssid,
+        frequency_mhz,
+        device_idle,
+    })
+}
+
+fn link_info(env: &mut JNIEnv) -> jni::errors::Result<Option<WifiLinkInfo>> {
+    let wifi_manager = get_system_service(env, "wifi");
+    let wifi_info = env
+        .call_method(
+            wifi_manager,
+            "getConnectionInfo",
+            "()Landroid/net/wifi/WifiInfo;",
+            &[],
+        )?
+        .l()?;
+    if wifi_info.is_null()
+        || env
+            .call_method(&wifi_info, "getNetworkId", "()I", &[])?
+            .i()?
+            == -1
+    {
+        return Ok(None);
+    }
+
+    let frequency_mhz = env
+        .call_method(&wifi_info, "getFrequency", "()I", &[])?
+        .i()?;
+    // The video is received by the headset, prefer the receive link speed where available
+    let link_speed_mbps = if get_api_level() >= 29 {
+        env.call_method(&wifi_info, "getRxLinkSpeedMbps", "()I", &[])?
+            .i()?
+    } else {
+        env.call_method(&wifi_info, "getLinkSpeed", "()I", &[])?
+            .i()?
+    };
+
+    // Negative values mean unknown
+    if frequency_mhz <= 0 || link_speed_mbps <= 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(WifiLinkInfo {
+        frequency_mhz: frequency_mhz as u32,
+        link_speed_mbps: link_speed_mbps as u32,
+    }))
+}
+
+// Returns None if not connected to Wi-Fi
+pub fn wifi_link_info() -> Option<WifiLinkInfo> {
+    let vm = vm();
+    let mut env = vm.attach_current_thread().unwrap();
+
+    env.with_local_frame(16, link_info).ok().flatten()
+}
+
+// Detects Wi-Fi reassociations, roaming and power save transitions by sampling the link state.
+// Short events between two samples are missed.
+pub struct WifiLinkMonitor {
+    last_state: Option<WifiLinkState>,
+}
+
+impl WifiLinkMonitor {
+    pub fn new() -> Self {
+        Self { last_state: None }
+    }
+
+    // Returns the description of the link events since the previous call
+    pub fn poll(&mut self) -> Vec<String> {
+        let vm = vm();
+        let mut env = vm.attach_current_thread().unwrap();
+
+        let Ok(state) = env.with_local_frame(16, link_state) else {
+            return vec![];
+        };
+
+        let mut events = vec![];
+        if let Some(last) = &self.last_state {
+            if last.associated && !state.associated {
+                events.push("Wi-Fi disassociated".into());
+            } else if !last.associated && state.associated {
+                events.push("Wi-Fi reassociated".into());
+            } else if state.associated && last.bssid != state.bssid {
+                events.push("Wi-Fi roamed to another access point".into());
+            } else if state.associated && last.frequency_mhz != state.frequency_mhz {
+                events.push(format!(
+                    "Wi-Fi channel changed from {} MHz to {} MHz",
+                    last.frequency_mhz, state.frequency_mhz
+                ));
+            }
+
+            if !last.device_idle && state.device_idle {
+                events.push("Device entered Doze".into());
+            }
+        }
+        if wifi_lock_lost() {
+            events.push("Wi-Fi lock released by the system, acquiring it again".into());
+            WIFI_LOCK.lock().take();
+            acquire_wifi_lock();
+        }
+
+        self.last_state = Some(state);
+
+        events
+    }
+}
+
+fn get_string_field(env: &mut JNIEnv, object: &JObject, name: &str) -> jni::errors::Result<String> {
+    let jstring = env.get_field(object, name, "Ljava/lang/String;")?.l()?;
+    if jstring.is_null() {
+        return Ok(String::new());
+    }
+
+    Ok(env.get_string((&jstring).into())?.into())
+}
+
+// Values of ScanResult.CHANNEL_WIDTH_*. 80+80 MHz is counted as 160 MHz
+fn channel_width_mhz(width: i32) -> u32 {
+    match width {
+        1 => 40,
+        2 => 80,
+        3 | 4 => 160,
+        5 => 320,
+        _ => 20,
+    }
+}
+
+fn scan_results(env: &mut JNIEnv) -> jni::errors::Result<Vec<WifiNetwork>> {
+    let wifi_manager = get_system_service(env, "wifi");
+    let results = env
+        .call_method(wifi_manager, "getScanResults", "()Ljava/util/List;", &[])?
+        .l()?;
+    let count = env.call_method(&results, "size", "()I", &[])?.i()?;
+
+    (0..count)
+        .map(|index| {
+            env.with_local_frame(8, |env| {
+                let result = env
+                    .call_method(&results, "get", "(I)Ljava/lang/Object;", &[index.into()])?
+                    .l()?;
+
+                let frequency_mhz = env.get_field(&result, "frequency", "I")?.i()? as u32;
+                // Zero for 20 MHz channels
+                let center_frequency_mhz = env.get_field(&result, "centerFreq0", "I")?.i()? as u32;
+
+                Ok(WifiNetwork {
+                    ssid: get_string_field(env, &result, "SSID")?,
+                    bssid: get_string_field(env, &result, "BSSID")?,
+                    frequency_mhz,
+                    center_frequency_mhz: if center_frequency_mhz > 0 {
+                        center_frequency_mhz
+                    } else {
+                        frequency_mhz
+                    },
+                    channel_width_mhz: channel_width_mhz(
+                        env.get_field(&result, "channelWidth", "I")?.i()?,
+                    ),
+                    signal_dbm: env.get_field(&result, "level", "I")?.i()?,
+                })
+            })
+        })
+        .collect()
+}
+
+// Blocks for a few seconds. Scans are disabled by the system while the low latency Wi-Fi lock is
+// held and are throttled to a few per minute, in that case the results of the last scan are
+// returned.
+pub fn scan_wifi() -> Result<WifiScanReport> {
+    // Required to get the scan results
+    try_get_permission(LOCATION_PERMISSION);
+
+    let vm = vm();
+    let mut env = vm.attach_current_thread()?;
+
+    let fresh = {
+        let wifi_manager = get_system_service(&mut env, "wifi");
+        env.call_method(wifi_manager, "startScan", "()Z", &[])
+            .and_then(|started| started.z())
+            .unwrap_or(false)
+    };
+    if fresh {
+        thread::sleep(SCAN_RESULTS_WAIT);
+    }
+
+    let networks = match env.with_local_frame(16, scan_results) {
+        Ok(networks) => networks,
+        Err(e) => {
+            env.exception_clear().ok();
+            bail!("Wi-Fi scan failed, the location permission may be missing: {e}");
+        }
+    };
+
+    let connected_bssid = env
+        .with_local_frame(16, link_state)
+        .ok()
+        .filter(|state| state.associated)
+        .and_then(|state| state.bssid);
+
+    Ok(WifiScanReport {
+        connected_bssid,
+        networks,
+        fresh,
+    })
+}
@@ -14,6 +14,57 @@ pub fn manufacturer_name() -> String {
     "Unknown".into()
 }
 
+#[cfg(not(target_os = "android"))]
+pub fn soc_model() -> String {
+    "Unknown".into()
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn os_version() -> String {
+    std::env::consts::OS.into()
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn video_decoders() -> Vec<alvr_session::VideoDecoderInfo> {
+    vec![]
+}
+
+// Reads MemAvailable from /proc/meminfo. Returns 0 where it is not available
+pub fn available_ram_mb() -> u64 {
+    std::fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|meminfo| {
+            meminfo
+                .lines()
+                .find_map(|line| line.strip_prefix("MemAvailable:"))
+                .and_then(|value| {
+                    value
+                        .trim()
+                        .trim_end_matches("kB")
+                        .trim()
+                        .parse::<u64>()
+                        .ok()
+                })
+        })
+        .map(|kbytes| kbytes / 1024)
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn wifi_ssid() -> Option<String> {
+    None
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn wifi_link_info() -> Option<alvr_packets::WifiLinkInfo> {
+    None
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn scan_wifi() -> alvr_common::anyhow::Result<alvr_packets::WifiScanReport> {
+    alvr_common::anyhow::bail!("Wi-Fi scanning is supported only on Android")
+}
+
 #[cfg(not(any(target_os = "android", target_os = "macos")))]
 pub fn local_ip() -> std::net::IpAddr {
     use std::net::{IpAddr, Ipv4Addr};
@@ -1,6 +1,30 @@
+use crate::platform;
 use alvr_common::{anyhow::Result, ALVR_NAME};
-use alvr_sockets::{CONTROL_PORT, LOCAL_IP};
-use std::net::{Ipv4Addr, UdpSocket};
+use alvr_sockets::{
+    CONTROL_PORT, HANDSHAKE_PACKET_SIZE_BYTES, LOCAL_IP, PAIRING_PACKET_SIZE_BYTES,
+};
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+
+// IP of the interface used to reach the given host. No packet is actually sent
+fn route_local_ip(peer_ip: IpAddr) -> Option<IpAddr> {
+    let socket = UdpSocket::bind((LOCAL_IP, 0)).ok()?;
+    socket.connect((peer_ip, 80)).ok()?;
+
+    Some(socket.local_addr().ok()?.ip())
+}
+
+// VPN apps route the traffic through their own interface instead of the Wi-Fi one. Returns the IP
+// of that interface if it is used to reach the given host, or the internet if None
+pub fn vpn_local_ip(peer_ip: Option<IpAddr>) -> Option<IpAddr> {
+    let lan_ip = platform::local_ip();
+    if lan_ip.is_unspecified() {
+        return None;
+    }
+
+    let local_ip = route_local_ip(peer_ip.unwrap_or(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8))))?;
+
+    (local_ip != lan_ip).then_some(local_ip)
+}
 
 pub struct AnnouncerSocket {
     socket: UdpSocket,
@@ -26,4 +50,15 @@ impl AnnouncerSocket {
 
         Ok(())
     }
+
+    // Announce directly to a known streamer, presenting the trust token obtained from its QR code
+    pub fn send_pairing(&self, server_ip: IpAddr, token: u64) -> Result<()> {
+        let mut packet = [0; PAIRING_PACKET_SIZE_BYTES];
+        packet[..HANDSHAKE_PACKET_SIZE_BYTES].copy_from_slice(&self.packet);
+        packet[HANDSHAKE_PACKET_SIZE_BYTES..].copy_from_slice(&token.to_le_bytes());
+
+        self.socket.send_to(&packet, (server_ip, CONTROL_PORT))?;
+
+        Ok(())
+    }
 }
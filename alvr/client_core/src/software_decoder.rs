@@ -0,0 +1,98 @@
+// H264 software decoder for desktop clients. It is slow and supports only H264, it is meant for
+// testing the network and encoder chain without a headset.
+
+use crate::decoder::{VideoDecoderSink, VideoDecoderSource, PRIMING_TIMESTAMP};
+use alvr_common::{anyhow::Result, parking_lot::Mutex};
+use openh264::decoder::Decoder;
+use std::{collections::VecDeque, ffi::c_void, sync::Arc, time::Duration};
+
+// Frames decoded but not yet dequeued. Older frames are dropped
+const MAX_QUEUED_FRAMES: usize = 2;
+
+/// Frame returned by get_frame() when using the software decoder. The pointer returned by
+/// get_frame() points to this struct.
+pub struct SoftwareFrame {
+    pub width: usize,
+    pub height: usize,
+    /// Packed RGB, 3 bytes per pixel
+    pub rgb: Vec<u8>,
+}
+
+type FrameQueue = Arc<Mutex<VecDeque<(Duration, SoftwareFrame)>>>;
+
+pub struct SoftwareDecoderSink {
+    decoder: Decoder,
+    frame_queue: FrameQueue,
+    dequeued_frame_callback: Box<dyn Fn(Duration) + Send>,
+}
+
+// The decoder is used only by the thread that owns the sink
+unsafe impl Send for SoftwareDecoderSink {}
+
+impl VideoDecoderSink for SoftwareDecoderSink {
+    // Decoding is synchronous, this never times out
+    fn push_frame_nal(&mut self, timestamp: Duration, nal: &[u8]) -> Result<bool> {
+        let Some(yuv) = self.decoder.decode(nal)? else {
+            return Ok(true);
+        };
+
+        if timestamp == PRIMING_TIMESTAMP {
+            return Ok(true);
+        }
+
+        let (width, height) = yuv.dimension_rgb();
+        let mut rgb = vec![0; width * height * 3];
+        yuv.write_rgb8(&mut rgb);
+
+        {
+            let mut frame_queue = self.frame_queue.lock();
+            frame_queue.push_back((timestamp, SoftwareFrame { width, height, rgb }));
+            while frame_queue.len() > MAX_QUEUED_FRAMES {
+                frame_queue.pop_front();
+            }
+        }
+
+        (self.dequeued_frame_callback)(timestamp);
+
+        Ok(true)
+    }
+}
+
+pub struct SoftwareDecoderSource {
+    frame_queue: FrameQueue,
+    // Kept alive until the next call to dequeue_frame()
+    current_frame: Option<SoftwareFrame>,
+}
+
+impl VideoDecoderSource for SoftwareDecoderSource {
+    fn dequeue_frame(&mut self) -> Option<(Duration, *mut c_void)> {
+        let (timestamp, frame) = self.frame_queue.lock().pop_front()?;
+
+        let frame = self.current_frame.insert(frame);
+
+        Some((timestamp, (frame as *mut SoftwareFrame).cast()))
+    }
+}
+
+pub fn software_decoder_split(
+    config_nal: &[u8],
+    dequeued_frame_callback: impl Fn(Duration) + Send + 'static,
+) -> Result<(SoftwareDecoderSink, SoftwareDecoderSource)> {
+    let mut decoder = Decoder::new()?;
+    // SPS and PPS don't produce a frame
+    decoder.decode(config_nal)?;
+
+    let frame_queue = Arc::new(Mutex::new(VecDeque::new()));
+
+    let sink = SoftwareDecoderSink {
+        decoder,
+        frame_queue: Arc::clone(&frame_queue),
+        dequeued_frame_callback: Box::new(dequeued_frame_callback),
+    };
+    let source = SoftwareDecoderSource {
+        frame_queue,
+        current_frame: None,
+    };
+
+    Ok((sink, source))
+}
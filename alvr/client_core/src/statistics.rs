@@ -1,10 +1,17 @@
+use alvr_audio::AudioBufferEvent;
 use alvr_common::SlidingWindowAverage;
-use alvr_packets::ClientStatistics;
+use alvr_packets::{ClientStatistics, ControlPacketKind, FrameDropReason, ThermalStatus};
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
+    mem,
     time::{Duration, Instant},
 };
 
+// Controller angular speeds used to detect the start of a motion, in rad/s
+const MOTION_REST_THRESHOLD: f32 = 0.3;
+const MOTION_ONSET_THRESHOLD: f32 = 1.5;
+const MOTION_TO_PHOTON_HISTORY_SIZE: usize = 10;
+
 struct HistoryFrame {
     input_acquired: Instant,
     video_packet_received: Instant,
@@ -14,9 +21,33 @@ struct HistoryFrame {
 pub struct StatisticsManager {
     history_buffer: VecDeque<HistoryFrame>,
     max_history_size: usize,
+    frames_in_decoder: VecDeque<Duration>,
+    // Frames waiting for the next pipeline stage, used to detect the ones skipped
+    decoded_frames: VecDeque<Duration>,
+    composited_frames: VecDeque<Duration>,
+    frame_drops: HashMap<FrameDropReason, u32>,
+    control_packets_sent: HashMap<ControlPacketKind, u32>,
     prev_vsync: Instant,
     total_pipeline_latency_average: SlidingWindowAverage<Duration>,
+    steamvr_pipeline_frames: f32,
     steamvr_pipeline_latency: Duration,
+    audio_underruns: u32,
+    audio_overruns: u32,
+    audio_buffering: Option<Duration>,
+    reprojected_frames: u32,
+    wifi_link_events: u32,
+    dropped_packets: u32,
+    reordered_packets: u32,
+    last_tracking_sent: Option<Instant>,
+    tracking_send_interval_average: SlidingWindowAverage<Duration>,
+    tracking_send_jitter_average: SlidingWindowAverage<Duration>,
+    haptics_received: VecDeque<Instant>,
+    haptics_delivery_latency_average: SlidingWindowAverage<Duration>,
+    thermal_status: ThermalStatus,
+    cpu_frequency_ratio: Option<f32>,
+    controllers_at_rest: bool,
+    motion_onsets: VecDeque<(Duration, Instant)>, // target timestamp and acquisition time
+    motion_to_photon_average: Option<SlidingWindowAverage<Duration>>,
 }
 
 impl StatisticsManager {
@@ -28,14 +59,169 @@ impl StatisticsManager {
         Self {
             max_history_size,
             history_buffer: VecDeque::new(),
+            frames_in_decoder: VecDeque::new(),
+            decoded_frames: VecDeque::new(),
+            composited_frames: VecDeque::new(),
+            frame_drops: HashMap::new(),
+            control_packets_sent: HashMap::new(),
             prev_vsync: Instant::now(),
             total_pipeline_latency_average: SlidingWindowAverage::new(
                 Duration::ZERO,
                 max_history_size,
             ),
+            steamvr_pipeline_frames,
             steamvr_pipeline_latency: Duration::from_secs_f32(
                 steamvr_pipeline_frames * nominal_server_frame_interval.as_secs_f32(),
             ),
+            audio_underruns: 0,
+            audio_overruns: 0,
+            audio_buffering: None,
+            reprojected_frames: 0,
+            wifi_link_events: 0,
+            dropped_packets: 0,
+            reordered_packets: 0,
+            last_tracking_sent: None,
+            tracking_send_interval_average: SlidingWindowAverage::new(
+                nominal_server_frame_interval,
+                max_history_size,
+            ),
+            tracking_send_jitter_average: SlidingWindowAverage::new(
+                Duration::ZERO,
+                max_history_size,
+            ),
+            haptics_received: VecDeque::new(),
+            haptics_delivery_latency_average: SlidingWindowAverage::new(
+                Duration::ZERO,
+                max_history_size,
+            ),
+            thermal_status: ThermalStatus::None,
+            cpu_frequency_ratio: None,
+            controllers_at_rest: false,
+            motion_onsets: VecDeque::new(),
+            motion_to_photon_average: None,
+        }
+    }
+
+    pub fn set_nominal_server_frame_interval(&mut self, interval: Duration) {
+        self.steamvr_pipeline_latency =
+            Duration::from_secs_f32(self.steamvr_pipeline_frames * interval.as_secs_f32());
+    }
+
+    pub fn report_tracking_sent(&mut self) {
+        let now = Instant::now();
+
+        if let Some(last_sent) = self.last_tracking_sent {
+            let interval = now.saturating_duration_since(last_sent);
+            let average_interval = self.tracking_send_interval_average.get_average();
+
+            self.tracking_send_jitter_average
+                .submit_sample(if interval > average_interval {
+                    interval - average_interval
+                } else {
+                    average_interval - interval
+                });
+            self.tracking_send_interval_average.submit_sample(interval);
+        }
+
+        self.last_tracking_sent = Some(now);
+    }
+
+    pub fn report_audio_buffer_event(&mut self, event: AudioBufferEvent) {
+        match event {
+            AudioBufferEvent::Underrun => self.audio_underruns += 1,
+            AudioBufferEvent::Overrun => self.audio_overruns += 1,
+            AudioBufferEvent::BufferingChanged(buffering) => self.audio_buffering = Some(buffering),
+        }
+    }
+
+    // Called by the frontend when no new frame was ready for the vsync and the previous one is shown
+    pub fn report_frame_reprojected(&mut self) {
+        self.reprojected_frames += 1;
+    }
+
+    pub fn report_wifi_link_event(&mut self) {
+        self.wifi_link_events += 1;
+    }
+
+    // Packets received but discarded because they were not read fast enough
+    pub fn report_dropped_packets(&mut self, count: usize) {
+        self.dropped_packets += count as u32;
+    }
+
+    // Packets completed out of order, tolerated by the reorder window instead of being lost
+    pub fn report_reordered_packets(&mut self, count: usize) {
+        self.reordered_packets += count as u32;
+    }
+
+    pub fn report_frame_dropped(&mut self, reason: FrameDropReason) {
+        self.add_frame_drops(reason, 1);
+    }
+
+    pub fn report_control_packet_sent(&mut self, kind: ControlPacketKind) {
+        *self.control_packets_sent.entry(kind).or_default() += 1;
+    }
+
+    fn add_frame_drops(&mut self, reason: FrameDropReason, count: u32) {
+        if count > 0 {
+            *self.frame_drops.entry(reason).or_default() += count;
+        }
+    }
+
+    // Removes the frames up to target_timestamp from the queue and returns how many were older
+    fn skip_frames(queue: &mut VecDeque<Duration>, target_timestamp: Duration) -> u32 {
+        let mut skipped = 0;
+        while let Some(&timestamp) = queue.front() {
+            if timestamp > target_timestamp {
+                break;
+            }
+            queue.pop_front();
+
+            if timestamp < target_timestamp {
+                skipped += 1;
+            }
+        }
+
+        skipped
+    }
+
+    pub fn report_haptics_received(&mut self) {
+        self.haptics_received.push_back(Instant::now());
+        if self.haptics_received.len() > self.max_history_size {
+            self.haptics_received.pop_front();
+        }
+    }
+
+    // Haptics are delivered in order, when the frontend polls the corresponding event
+    pub fn report_haptics_delivered(&mut self) {
+        if let Some(received) = self.haptics_received.pop_front() {
+            self.haptics_delivery_latency_average
+                .submit_sample(Instant::now().saturating_duration_since(received));
+        }
+    }
+
+    // Sampled periodically, reported with every frame
+    pub fn report_device_load(
+        &mut self,
+        thermal_status: ThermalStatus,
+        cpu_frequency_ratio: Option<f32>,
+    ) {
+        self.thermal_status = thermal_status;
+        self.cpu_frequency_ratio = cpu_frequency_ratio;
+    }
+
+    // angular_speed is the highest angular speed among the controllers, in rad/s. A motion starting
+    // from rest is tracked until the first frame rendered with its tracking sample is displayed
+    pub fn report_controller_motion(&mut self, target_timestamp: Duration, angular_speed: f32) {
+        if angular_speed < MOTION_REST_THRESHOLD {
+            self.controllers_at_rest = true;
+        } else if self.controllers_at_rest && angular_speed > MOTION_ONSET_THRESHOLD {
+            self.controllers_at_rest = false;
+
+            self.motion_onsets
+                .push_back((target_timestamp, Instant::now()));
+            if self.motion_onsets.len() > self.max_history_size {
+                self.motion_onsets.pop_front();
+            }
         }
     }
 
@@ -71,7 +257,35 @@ impl StatisticsManager {
         }
     }
 
+    pub fn report_frame_submitted_to_decoder(&mut self, target_timestamp: Duration) {
+        if let Some(frame) = self
+            .history_buffer
+            .iter_mut()
+            .find(|frame| frame.client_stats.target_timestamp == target_timestamp)
+        {
+            frame.client_stats.video_decode_concurrency = self.frames_in_decoder.len() as u32;
+        }
+
+        self.frames_in_decoder.push_back(target_timestamp);
+        if self.frames_in_decoder.len() > self.max_history_size {
+            self.frames_in_decoder.pop_front();
+        }
+    }
+
+    pub fn decoder_queue_depth(&self) -> usize {
+        self.frames_in_decoder.len()
+    }
+
     pub fn report_frame_decoded(&mut self, target_timestamp: Duration) {
+        // Frames are decoded in order, so older frames still in the queue have been dropped
+        let decoder_drops = Self::skip_frames(&mut self.frames_in_decoder, target_timestamp);
+        self.add_frame_drops(FrameDropReason::DecoderSaturation, decoder_drops);
+
+        self.decoded_frames.push_back(target_timestamp);
+        if self.decoded_frames.len() > self.max_history_size {
+            self.decoded_frames.pop_front();
+        }
+
         if let Some(frame) = self
             .history_buffer
             .iter_mut()
@@ -83,6 +297,14 @@ impl StatisticsManager {
     }
 
     pub fn report_compositor_start(&mut self, target_timestamp: Duration) {
+        let pacing_discards = Self::skip_frames(&mut self.decoded_frames, target_timestamp);
+        self.add_frame_drops(FrameDropReason::PacingDiscard, pacing_discards);
+
+        self.composited_frames.push_back(target_timestamp);
+        if self.composited_frames.len() > self.max_history_size {
+            self.composited_frames.pop_front();
+        }
+
         if let Some(frame) = self
             .history_buffer
             .iter_mut()
@@ -99,6 +321,9 @@ impl StatisticsManager {
     pub fn report_submit(&mut self, target_timestamp: Duration, vsync_queue: Duration) {
         let now = Instant::now();
 
+        let late_frames = Self::skip_frames(&mut self.composited_frames, target_timestamp);
+        self.add_frame_drops(FrameDropReason::CompositorLate, late_frames);
+
         if let Some(frame) = self
             .history_buffer
             .iter_mut()
@@ -119,13 +344,59 @@ impl StatisticsManager {
             frame.client_stats.frame_interval = vsync.saturating_duration_since(self.prev_vsync);
             self.prev_vsync = vsync;
         }
+
+        // Frames can be dropped, so the motion is displayed by the first frame at or after it
+        while let Some(&(onset_timestamp, onset_instant)) = self.motion_onsets.front() {
+            if onset_timestamp > target_timestamp {
+                break;
+            }
+            self.motion_onsets.pop_front();
+
+            self.motion_to_photon_average
+                .get_or_insert_with(|| {
+                    SlidingWindowAverage::new(Duration::ZERO, MOTION_TO_PHOTON_HISTORY_SIZE)
+                })
+                .submit_sample((now + vsync_queue).saturating_duration_since(onset_instant));
+        }
     }
 
-    pub fn summary(&self, target_timestamp: Duration) -> Option<ClientStatistics> {
-        self.history_buffer
+    pub fn summary(&mut self, target_timestamp: Duration) -> Option<ClientStatistics> {
+        let mut stats = self
+            .history_buffer
             .iter()
             .find(|frame| frame.client_stats.target_timestamp == target_timestamp)
-            .map(|frame| frame.client_stats.clone())
+            .map(|frame| frame.client_stats.clone())?;
+
+        stats.audio_underruns = mem::take(&mut self.audio_underruns);
+        stats.audio_overruns = mem::take(&mut self.audio_overruns);
+        stats.audio_buffering = self.audio_buffering;
+        stats.reprojected_frames = mem::take(&mut self.reprojected_frames);
+        stats.wifi_link_events = mem::take(&mut self.wifi_link_events);
+        stats.dropped_packets = mem::take(&mut self.dropped_packets);
+        stats.reordered_packets = mem::take(&mut self.reordered_packets);
+        stats.frame_drops = mem::take(&mut self.frame_drops).into_iter().collect();
+        stats.control_packets_sent = mem::take(&mut self.control_packets_sent)
+            .into_iter()
+            .collect();
+        stats.tracking_send_jitter = self.tracking_send_jitter_average.get_average();
+        stats.haptics_delivery_latency = self.haptics_delivery_latency_average.get_average();
+        stats.thermal_status = self.thermal_status;
+        stats.cpu_frequency_ratio = self.cpu_frequency_ratio;
+        stats.motion_to_photon_latency = self
+            .motion_to_photon_average
+            .as_ref()
+            .map(|average| average.get_average());
+
+        // Rough estimate: frames decoded concurrently share the decode time
+        stats.decoder_utilization = (stats.video_decode.as_secs_f32()
+            / ((stats.video_decode_concurrency + 1) as f32
+                * stats
+                    .frame_interval
+                    .max(Duration::from_millis(1))
+                    .as_secs_f32()))
+        .min(1.0);
+
+        Some(stats)
     }
 
     // latency used for head prediction
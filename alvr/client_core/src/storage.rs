@@ -1,8 +1,10 @@
 use alvr_common::{error, info};
+use alvr_packets::DecoderInitializationConfig;
+use alvr_session::LobbyMessageConfig;
 use app_dirs2::{AppDataType, AppInfo};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use std::{fs, net::IpAddr, path::PathBuf};
 
 fn config_path() -> PathBuf {
     app_dirs2::app_root(
@@ -16,10 +18,99 @@ fn config_path() -> PathBuf {
     .join("session.json")
 }
 
+// Lockdown for headsets handed to the public. Set by the deployment, never by the streamer
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct KioskConfig {
+    // Show only the status in the HUD, without hostname, IP and dashboard QR code
+    pub hide_connection_details: bool,
+    // Ignore stream pause requests from the headset
+    pub disable_manual_disconnection: bool,
+    // Empty lists allow any streamer
+    pub trusted_server_hostnames: Vec<String>,
+    pub trusted_server_ips: Vec<IpAddr>,
+}
+
+// Restricts where the headset looks for streamers. Empty lists allow any network
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct TrustedNetworksConfig {
+    pub wifi_ssids: Vec<String>,
+    // CIDR notation, for example "192.168.1.0/24". A plain IP matches only that address
+    pub server_subnets: Vec<String>,
+}
+
+impl TrustedNetworksConfig {
+    pub fn is_valid(&self) -> bool {
+        self.server_subnets
+            .iter()
+            .all(|subnet| parse_subnet(subnet).is_some())
+    }
+
+    // The SSID is None if it could not be read, for example when not connected to Wi-Fi
+    pub fn trusts_wifi_ssid(&self, ssid: Option<&str>) -> bool {
+        self.wifi_ssids.is_empty()
+            || ssid.map_or(false, |ssid| self.wifi_ssids.iter().any(|s| s == ssid))
+    }
+
+    pub fn trusts_server_ip(&self, ip: IpAddr) -> bool {
+        self.server_subnets.is_empty()
+            || self
+                .server_subnets
+                .iter()
+                .filter_map(|subnet| parse_subnet(subnet))
+                .any(|(network, prefix_len)| subnet_contains(network, prefix_len, ip))
+    }
+}
+
+fn parse_subnet(subnet: &str) -> Option<(IpAddr, u32)> {
+    let (address, prefix_len) = subnet.split_once('/').unwrap_or((subnet, ""));
+
+    let address = address.trim().parse::<IpAddr>().ok()?;
+    let max_prefix_len = if address.is_ipv4() { 32 } else { 128 };
+    let prefix_len = if prefix_len.is_empty() {
+        max_prefix_len
+    } else {
+        prefix_len.trim().parse().ok()?
+    };
+
+    (prefix_len <= max_prefix_len).then_some((address, prefix_len))
+}
+
+fn subnet_contains(network: IpAddr, prefix_len: u32, ip: IpAddr) -> bool {
+    match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len).unwrap_or(0);
+            u32::from(network) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len).unwrap_or(0);
+            u128::from(network) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     pub protocol_id: u64,
     pub hostname: String,
+    // Decoder configuration of the last stream, used to create the decoder early on reconnection
+    #[serde(default)]
+    pub decoder_config: Option<DecoderInitializationConfig>,
+    // Lobby customization received from the last streamer
+    #[serde(default)]
+    pub welcome_message: Option<LobbyMessageConfig>,
+    #[serde(default)]
+    pub dashboard_url: Option<String>,
+    #[serde(default)]
+    pub kiosk: Option<KioskConfig>,
+    #[serde(default)]
+    pub trusted_networks: Option<TrustedNetworksConfig>,
+    // Preferred when several streamers try to connect at the same time
+    #[serde(default)]
+    pub last_server_hostname: Option<String>,
+    // Received from the last streamer, needed before the first connection
+    #[serde(default)]
+    pub wifi_lock_only_while_streaming: bool,
 }
 
 impl Default for Config {
@@ -35,6 +126,13 @@ impl Default for Config {
                 rng.gen_range(0..10),
                 rng.gen_range(0..10),
             ),
+            decoder_config: None,
+            welcome_message: None,
+            dashboard_url: None,
+            kiosk: None,
+            trusted_networks: None,
+            last_server_hostname: None,
+            wifi_lock_only_while_streaming: false,
         }
     }
 }
@@ -66,3 +164,58 @@ impl Config {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_subnet() {
+        assert_eq!(
+            parse_subnet("192.168.1.0/24"),
+            Some((ip("192.168.1.0"), 24))
+        );
+        assert_eq!(parse_subnet(" 10.0.0.1 "), Some((ip("10.0.0.1"), 32)));
+        assert_eq!(parse_subnet("0.0.0.0/0"), Some((ip("0.0.0.0"), 0)));
+        assert_eq!(parse_subnet("fd00::/8"), Some((ip("fd00::"), 8)));
+        assert_eq!(parse_subnet("fd00::1"), Some((ip("fd00::1"), 128)));
+
+        assert_eq!(parse_subnet("192.168.1.0/33"), None);
+        assert_eq!(parse_subnet("fd00::/129"), None);
+        assert_eq!(parse_subnet("192.168.1.0/-1"), None);
+        assert_eq!(parse_subnet("192.168.1.0/abc"), None);
+        assert_eq!(parse_subnet("192.168.1/24"), None);
+        assert_eq!(parse_subnet(""), None);
+    }
+
+    #[test]
+    fn test_subnet_contains() {
+        let (network, prefix_len) = parse_subnet("192.168.1.0/24").unwrap();
+        assert!(subnet_contains(network, prefix_len, ip("192.168.1.42")));
+        assert!(!subnet_contains(network, prefix_len, ip("192.168.2.42")));
+        assert!(!subnet_contains(
+            network,
+            prefix_len,
+            ip("::ffff:192.168.1.42")
+        ));
+
+        // /0 matches every address of the same family
+        assert!(subnet_contains(ip("0.0.0.0"), 0, ip("8.8.8.8")));
+        assert!(subnet_contains(ip("::"), 0, ip("2001:db8::1")));
+        assert!(!subnet_contains(ip("0.0.0.0"), 0, ip("2001:db8::1")));
+
+        // /32 and /128 match only the same address
+        assert!(subnet_contains(ip("10.0.0.1"), 32, ip("10.0.0.1")));
+        assert!(!subnet_contains(ip("10.0.0.1"), 32, ip("10.0.0.2")));
+        assert!(subnet_contains(ip("fd00::1"), 128, ip("fd00::1")));
+        assert!(!subnet_contains(ip("fd00::1"), 128, ip("fd00::2")));
+
+        let (network, prefix_len) = parse_subnet("fd00::/8").unwrap();
+        assert!(subnet_contains(network, prefix_len, ip("fdab:1234::1")));
+        assert!(!subnet_contains(network, prefix_len, ip("fe80::1")));
+    }
+}
@@ -0,0 +1,65 @@
+// Head movement driven by the first connected gamepad: the right stick looks around and the left
+// stick walks on the floor plane.
+
+use alvr_common::{
+    glam::{Quat, Vec3},
+    warn,
+};
+use gilrs::{Axis, Gilrs};
+use std::{f32::consts::FRAC_PI_2, time::Instant};
+
+const LOOK_SPEED_RAD_PER_S: f32 = 2.0;
+const WALK_SPEED_M_PER_S: f32 = 1.0;
+
+pub struct GamepadInput {
+    gilrs: Option<Gilrs>,
+    last_update: Instant,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub position_offset: Vec3,
+}
+
+impl GamepadInput {
+    pub fn new() -> Self {
+        let gilrs = Gilrs::new()
+            .map_err(|e| warn!("Gamepad input not available: {e}"))
+            .ok();
+
+        Self {
+            gilrs,
+            last_update: Instant::now(),
+            yaw: 0.0,
+            pitch: 0.0,
+            position_offset: Vec3::ZERO,
+        }
+    }
+
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let dt = (now - self.last_update).as_secs_f32();
+        self.last_update = now;
+
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+
+        // Events must be drained for the gamepad state to update
+        while gilrs.next_event().is_some() {}
+
+        let Some((_, gamepad)) = gilrs.gamepads().next() else {
+            return;
+        };
+
+        self.yaw -= gamepad.value(Axis::RightStickX) * LOOK_SPEED_RAD_PER_S * dt;
+        self.pitch = (self.pitch + gamepad.value(Axis::RightStickY) * LOOK_SPEED_RAD_PER_S * dt)
+            .clamp(-FRAC_PI_2, FRAC_PI_2);
+
+        let walk_direction = Quat::from_rotation_y(self.yaw)
+            * Vec3::new(
+                gamepad.value(Axis::LeftStickX),
+                0.0,
+                -gamepad.value(Axis::LeftStickY),
+            );
+        self.position_offset += walk_direction * WALK_SPEED_M_PER_S * dt;
+    }
+}
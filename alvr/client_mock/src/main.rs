@@ -1,4 +1,10 @@
-use alvr_client_core::ClientCoreEvent;
+#[cfg(feature = "desktop-stream")]
+mod gamepad;
+
+use alvr_client_core::{
+    ClientCapabilities, ClientCoreContext, ClientCoreEvent, ConnectionFailureReason, HudMessage,
+    HudMessageSeverity,
+};
 use alvr_common::{
     glam::{Quat, UVec2, Vec3},
     parking_lot::RwLock,
@@ -7,7 +13,10 @@ use alvr_common::{
 use alvr_packets::Tracking;
 use alvr_session::CodecType;
 use eframe::{
-    egui::{self, CentralPanel, Context, RichText, Slider},
+    egui::{
+        self, CentralPanel, Color32, ColorImage, Context, RichText, Slider, TextureHandle,
+        TextureOptions,
+    },
     Frame, NativeOptions,
 };
 use std::{
@@ -20,6 +29,19 @@ use std::{
     time::{Duration, Instant},
 };
 
+#[cfg(feature = "desktop-stream")]
+fn save_screenshot(label: &str, frame: &alvr_client_core::SoftwareFrame) {
+    // Binary PPM, it needs no image encoder
+    let mut data = format!("P6\n{} {}\n255\n", frame.width, frame.height).into_bytes();
+    data.extend_from_slice(&frame.rgb);
+
+    let path = format!("{label}.ppm");
+    match std::fs::write(&path, data) {
+        Ok(()) => alvr_common::info!("Screenshot saved to {path}"),
+        Err(e) => alvr_common::warn!("Failed to save screenshot {path}: {e}"),
+    }
+}
+
 #[derive(Clone, PartialEq)]
 struct WindowInput {
     height: f32,
@@ -51,23 +73,30 @@ impl Default for WindowInput {
 
 #[derive(Clone)]
 struct WindowOutput {
-    hud_message: String,
+    hud_message: Option<HudMessage>,
+    caption: String,
     fps: f32,
     connected: bool,
+    connection_failure: Option<ConnectionFailureReason>,
     resolution: UVec2,
     decoder_codec: Option<CodecType>,
     current_frame_timestamp: Duration,
+    // Decoded frame, only with the desktop-stream feature
+    frame: Option<Arc<ColorImage>>,
 }
 
 impl Default for WindowOutput {
     fn default() -> Self {
         Self {
-            hud_message: "".into(),
+            hud_message: None,
+            caption: String::new(),
             fps: 60.0,
             connected: false,
+            connection_failure: None,
             resolution: UVec2::ZERO,
             decoder_codec: None,
             current_frame_timestamp: Duration::ZERO,
+            frame: None,
         }
     }
 }
@@ -77,6 +106,7 @@ pub struct Window {
     input_sender: mpsc::Sender<WindowInput>,
     output: WindowOutput,
     output_receiver: mpsc::Receiver<WindowOutput>,
+    frame_texture: Option<TextureHandle>,
 }
 
 impl Window {
@@ -89,24 +119,55 @@ impl Window {
             input_sender,
             output: WindowOutput::default(),
             output_receiver,
+            frame_texture: None,
         }
     }
 }
 
 impl eframe::App for Window {
     fn update(&mut self, context: &Context, _: &mut Frame) {
-        while let Ok(output) = self.output_receiver.try_recv() {
+        let mut latest_frame = None;
+        while let Ok(mut output) = self.output_receiver.try_recv() {
+            if let Some(frame) = output.frame.take() {
+                latest_frame = Some(frame);
+            }
             self.output = output;
         }
 
+        if let Some(frame) = latest_frame {
+            let image = Arc::try_unwrap(frame).unwrap_or_else(|frame| (*frame).clone());
+            if let Some(texture) = &mut self.frame_texture {
+                texture.set(image, TextureOptions::LINEAR);
+            } else {
+                self.frame_texture =
+                    Some(context.load_texture("stream_frame", image, TextureOptions::LINEAR));
+            }
+        }
+
         let mut input = self.input.clone();
 
         CentralPanel::default().show(context, |ui| {
             ui.vertical_centered(|ui| {
-                ui.heading(RichText::new(&self.output.hud_message));
+                if let Some(message) = &self.output.hud_message {
+                    let color = match message.severity {
+                        HudMessageSeverity::Info => Color32::LIGHT_GRAY,
+                        HudMessageSeverity::Warning => Color32::YELLOW,
+                        HudMessageSeverity::Error => Color32::RED,
+                    };
+                    ui.heading(RichText::new(&message.title).color(color));
+                    ui.label(&message.body);
+                    if let Some(url) = &message.qr_code_url {
+                        ui.hyperlink(url);
+                    }
+                }
             });
+            ui.label(format!("Last caption: {}", self.output.caption));
             ui.label(format!("FPS: {}", self.output.fps));
             ui.label(format!("Connected: {}", self.output.connected));
+            ui.label(format!(
+                "Last connection failure: {:?}",
+                self.output.connection_failure
+            ));
             ui.label(format!("View resolution: {}", self.output.resolution));
             ui.label(format!("Codec: {:?}", self.output.decoder_codec));
             ui.label(format!(
@@ -141,8 +202,19 @@ impl eframe::App for Window {
                     0..=10_000,
                 ));
             });
+
+            if let Some(texture) = &self.frame_texture {
+                ui.add_space(10.0);
+                let size = texture.size_vec2();
+                let width = ui.available_width();
+                ui.image(texture.id(), egui::vec2(width, width * size.y / size.x));
+            }
         });
 
+        if self.output.connected {
+            context.request_repaint();
+        }
+
         if input != self.input {
             self.input = input;
 
@@ -153,18 +225,38 @@ impl eframe::App for Window {
     }
 }
 
-fn tracking_thread(streaming: Arc<RelaxedAtomic>, fps: f32, input: Arc<RwLock<WindowInput>>) {
+fn tracking_thread(
+    context: Arc<ClientCoreContext>,
+    streaming: Arc<RelaxedAtomic>,
+    fps: f32,
+    input: Arc<RwLock<WindowInput>>,
+) {
     let timestamp_origin = Instant::now();
 
     let mut position_offset = Vec3::ZERO;
 
+    #[cfg(feature = "desktop-stream")]
+    let mut gamepad = gamepad::GamepadInput::new();
+
     let mut loop_deadline = Instant::now();
     let mut random_position_deadline = Instant::now();
     while streaming.value() {
         let input_lock = input.read();
 
-        let orientation =
-            Quat::from_rotation_y(input_lock.yaw) * Quat::from_rotation_x(input_lock.pitch);
+        #[allow(unused_mut)]
+        let (mut yaw, mut pitch) = (input_lock.yaw, input_lock.pitch);
+        #[allow(unused_mut)]
+        let mut position = Vec3::new(0.0, input_lock.height, 0.0);
+
+        #[cfg(feature = "desktop-stream")]
+        {
+            gamepad.update();
+            yaw += gamepad.yaw;
+            pitch += gamepad.pitch;
+            position += gamepad.position_offset;
+        }
+
+        let orientation = Quat::from_rotation_y(yaw) * Quat::from_rotation_x(pitch);
 
         if input_lock.use_random_position && Instant::now() > random_position_deadline {
             random_position_deadline =
@@ -175,11 +267,11 @@ fn tracking_thread(streaming: Arc<RelaxedAtomic>, fps: f32, input: Arc<RwLock<Wi
                 * input_lock.random_position_offset_magnitude;
         }
 
-        let position = Vec3::new(0.0, input_lock.height, 0.0) + position_offset;
+        let position = position + position_offset;
 
-        alvr_client_core::send_tracking(Tracking {
+        context.send_tracking(Tracking {
             target_timestamp: Instant::now() - timestamp_origin
-                + alvr_client_core::get_head_prediction_offset(),
+                + context.get_head_prediction_offset(),
             device_motions: vec![(
                 *HEAD_ID,
                 DeviceMotion {
@@ -205,12 +297,19 @@ fn client_thread(
     output_sender: mpsc::Sender<WindowOutput>,
     input_receiver: mpsc::Receiver<WindowInput>,
 ) {
-    alvr_client_core::initialize(
-        UVec2::new(1920, 1832),
-        vec![60.0, 72.0, 80.0, 90.0, 120.0],
-        true,
-    );
-    alvr_client_core::resume();
+    let context = Arc::new(ClientCoreContext::new(ClientCapabilities {
+        default_view_resolution: UVec2::new(1920, 1832),
+        refresh_rates: vec![60.0, 72.0, 80.0, 90.0, 120.0],
+        foveated_encoding: false,
+        // With desktop-stream the frames are decoded in software and shown in the window
+        external_decoder: !cfg!(feature = "desktop-stream"),
+        pcm_haptics_sample_rate: None,
+        extra_latency_mode: false,
+        front_buffer_rendering: false,
+        overlay_layers: false,
+        runtime_name: "Mock".into(),
+    }));
+    context.resume();
 
     let streaming = Arc::new(RelaxedAtomic::new(true));
     let mut maybe_tracking_thread = None;
@@ -218,14 +317,21 @@ fn client_thread(
     let mut window_output = WindowOutput::default();
     let window_input = Arc::new(RwLock::new(WindowInput::default()));
 
+    // Saved with the next decoded frame
+    #[cfg(feature = "desktop-stream")]
+    let mut pending_screenshot = None;
+
     let mut deadline = Instant::now();
     'main_loop: loop {
         let input_lock = window_input.read();
 
-        while let Some(event) = alvr_client_core::poll_event() {
+        while let Some(event) = context.poll_event() {
             match event {
                 ClientCoreEvent::UpdateHudMessage(message) => {
-                    window_output.hud_message = message;
+                    window_output.hud_message = Some(message);
+                }
+                ClientCoreEvent::ConnectionFailed { reason, .. } => {
+                    window_output.connection_failure = Some(reason);
                 }
                 ClientCoreEvent::StreamingStarted {
                     view_resolution,
@@ -236,10 +342,11 @@ fn client_thread(
                     window_output.connected = true;
                     window_output.resolution = view_resolution;
 
+                    let context = Arc::clone(&context);
                     let streaming = Arc::clone(&streaming);
                     let input = Arc::clone(&window_input);
                     maybe_tracking_thread = Some(thread::spawn(move || {
-                        tracking_thread(streaming, fps, input)
+                        tracking_thread(context, streaming, fps, input)
                     }));
                 }
                 ClientCoreEvent::StreamingStopped => {
@@ -248,7 +355,26 @@ fn client_thread(
                         thread.join().ok();
                     }
                 }
-                ClientCoreEvent::Haptics { .. } => (),
+                ClientCoreEvent::ServersDiscovered(_)
+                | ClientCoreEvent::RefreshRateChanged { .. }
+                | ClientCoreEvent::AudioOutputChanged { .. }
+                | ClientCoreEvent::ExitRequested
+                | ClientCoreEvent::DisplayOffRequested
+                | ClientCoreEvent::PassthroughToggleRequested
+                | ClientCoreEvent::PerformanceOverlayToggleRequested
+                | ClientCoreEvent::StreamPaused
+                | ClientCoreEvent::StreamResumed
+                | ClientCoreEvent::SessionTimeRemaining { .. }
+                | ClientCoreEvent::ColorBlindnessFilterChanged(_)
+                | ClientCoreEvent::Haptics { .. }
+                | ClientCoreEvent::ExternalHaptics { .. }
+                | ClientCoreEvent::OverlayLayer { .. } => (),
+                // Shown until replaced, the duration is ignored
+                ClientCoreEvent::Caption { text, .. } => window_output.caption = text,
+                #[cfg(feature = "desktop-stream")]
+                ClientCoreEvent::ScreenshotRequested { label } => pending_screenshot = Some(label),
+                #[cfg(not(feature = "desktop-stream"))]
+                ClientCoreEvent::ScreenshotRequested { .. } => (),
                 ClientCoreEvent::CreateDecoder { codec, .. } => {
                     window_output.decoder_codec = Some(codec)
                 }
@@ -256,20 +382,39 @@ fn client_thread(
                     window_output.current_frame_timestamp = timestamp;
 
                     thread::sleep(Duration::from_millis(input_lock.emulated_decode_ms));
-                    alvr_client_core::report_frame_decoded(timestamp);
+                    context.report_frame_decoded(timestamp);
                 }
             }
 
             output_sender.send(window_output.clone()).ok();
         }
 
+        #[cfg(feature = "desktop-stream")]
+        if let Some((timestamp, buffer)) = context.get_frame() {
+            let frame = unsafe { &*(buffer as *const alvr_client_core::SoftwareFrame) };
+
+            if let Some(label) = pending_screenshot.take() {
+                save_screenshot(&label, frame);
+            }
+
+            window_output.current_frame_timestamp = timestamp;
+            window_output.frame = Some(Arc::new(ColorImage::from_rgb(
+                [frame.width, frame.height],
+                &frame.rgb,
+            )));
+            output_sender.send(window_output.clone()).ok();
+            window_output.frame = None;
+        }
+
         thread::sleep(Duration::from_millis(3));
 
-        alvr_client_core::report_compositor_start(window_output.current_frame_timestamp);
+        // get_frame() reports the compositor start with the internal decoder
+        #[cfg(not(feature = "desktop-stream"))]
+        context.report_compositor_start(window_output.current_frame_timestamp);
 
         thread::sleep(Duration::from_millis(input_lock.emulated_compositor_ms));
 
-        alvr_client_core::report_submit(
+        context.report_submit(
             window_output.current_frame_timestamp,
             Duration::from_millis(input_lock.emulated_vsync_ms),
         );
@@ -291,8 +436,7 @@ fn client_thread(
         thread.join().unwrap();
     }
 
-    alvr_client_core::pause();
-    alvr_client_core::destroy();
+    context.pause();
 }
 
 fn main() {
@@ -308,7 +452,11 @@ fn main() {
     eframe::run_native(
         "Mock client",
         NativeOptions {
-            initial_window_size: Some(egui::vec2(400.0, 400.0)),
+            initial_window_size: Some(if cfg!(feature = "desktop-stream") {
+                egui::vec2(800.0, 800.0)
+            } else {
+                egui::vec2(400.0, 400.0)
+            }),
             ..Default::default()
         },
         Box::new(|_| Box::new(Window::new(input_sender, output_receiver))),
@@ -1,8 +1,8 @@
 use crate::{to_pose, to_quat, to_vec3, Platform};
 use alvr_common::{glam::Vec3, *};
-use alvr_packets::{ButtonEntry, ButtonValue};
+use alvr_packets::{ButtonEntry, ButtonValue, HapticsPcm};
 use openxr as xr;
-use std::collections::HashMap;
+use std::{collections::HashMap, ptr};
 
 enum BindingType {
     Binary,
@@ -28,6 +28,9 @@ pub struct ButtonBindingInfo {
     binding_type: BindingType,
 }
 
+// Length used to place finger joints not located by the runtime, along their parent direction
+const SYNTHESIZED_BONE_LENGTH: f32 = 0.03;
+
 const QUEST_CONTROLLER_PROFILE: &str = "/interaction_profiles/oculus/touch_controller";
 const PICO_CONTROLLER_PROFILE: &str = "/interaction_profiles/pico/neo3_controller";
 const FOCUS3_CONTROLLER_PROFILE: &str = "/interaction_profiles/htc/vive_focus3_controller";
@@ -455,6 +458,15 @@ pub fn initialize_hands_interaction(
     }
 }
 
+// OpenXR hand joint hierarchy. The first joint of each finger is attached to the wrist
+fn hand_joint_parent(joint: usize) -> usize {
+    match joint {
+        0 | 1 => 0,
+        2 | 6 | 11 | 16 | 21 => 1,
+        _ => joint - 1,
+    }
+}
+
 pub fn get_hand_motion(
     session: &xr::Session<xr::AnyGraphics>,
     reference_space: &xr::Space,
@@ -484,12 +496,32 @@ pub fn get_hand_motion(
                 angular_velocity: Vec3::ZERO,
             };
 
-            let joints = joint_locations
-                .iter()
-                .map(|j| to_pose(j.pose))
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap();
+            // Runtimes can lose single finger joints (for example when occluded). Synthesize them
+            // from their parent so the streamer always receives a complete skeleton
+            let mut joints = [Pose::default(); 26];
+            for (index, location) in joint_locations.iter().enumerate() {
+                let mut pose = to_pose(location.pose);
+
+                if index >= 2 {
+                    let parent = joints[hand_joint_parent(index)];
+
+                    if !location
+                        .location_flags
+                        .contains(xr::SpaceLocationFlags::ORIENTATION_VALID)
+                    {
+                        pose.orientation = parent.orientation;
+                    }
+                    if !location
+                        .location_flags
+                        .contains(xr::SpaceLocationFlags::POSITION_VALID)
+                    {
+                        pose.position = parent.position
+                            + parent.orientation * Vec3::new(0.0, 0.0, -SYNTHESIZED_BONE_LENGTH);
+                    }
+                }
+
+                joints[index] = pose;
+            }
 
             return (Some(root_motion), Some(joints));
         }
@@ -746,3 +778,39 @@ pub fn get_htc_lip_expression(context: &FaceInputContext) -> Option<Vec<f32>> {
         .and_then(|t| t.get_facial_expressions().ok().flatten())
         .map(|w| w.weights.into_iter().collect())
 }
+
+// Requires XR_FB_haptic_pcm. The samples are resampled by the runtime if the rate doesn't match
+// the device rate
+pub fn apply_pcm_haptics<G>(
+    xr_session: &xr::Session<G>,
+    action: &xr::Action<xr::Haptic>,
+    pcm: &HapticsPcm,
+) {
+    let mut samples_consumed = 0;
+    let vibration = xr::sys::HapticPcmVibrationFB {
+        ty: xr::sys::HapticPcmVibrationFB::TYPE,
+        next: ptr::null(),
+        buffer_size: pcm.samples.len() as u32,
+        buffer: pcm.samples.as_ptr(),
+        sample_rate: pcm.sample_rate,
+        append: pcm.append.into(),
+        samples_consumed: &mut samples_consumed,
+    };
+    let action_info = xr::sys::HapticActionInfo {
+        ty: xr::sys::HapticActionInfo::TYPE,
+        next: ptr::null(),
+        action: action.as_raw(),
+        subaction_path: xr::Path::NULL,
+    };
+
+    let result = unsafe {
+        (xr_session.instance().fp().apply_haptic_feedback)(
+            xr_session.as_raw(),
+            &action_info,
+            (&vibration as *const xr::sys::HapticPcmVibrationFB).cast(),
+        )
+    };
+    if result.into_raw() < 0 {
+        warn!("Failed to apply PCM haptics: {result}");
+    }
+}
@@ -1,6 +1,8 @@
 mod interaction;
 
-use alvr_client_core::{opengl::RenderViewInput, ClientCoreEvent};
+use alvr_client_core::{
+    opengl::RenderViewInput, ClientCapabilities, ClientCoreContext, ClientCoreEvent,
+};
 use alvr_common::{
     error,
     glam::{Quat, UVec2, Vec2, Vec3},
@@ -14,7 +16,7 @@ use interaction::{FaceInputContext, HandsInteractionContext};
 use khronos_egl::{self as egl, EGL1_4};
 use openxr as xr;
 use std::{
-    collections::VecDeque,
+    collections::{BTreeMap, VecDeque},
     path::Path,
     ptr,
     sync::{mpsc, Arc},
@@ -25,7 +27,12 @@ use std::{
 // When the latency goes too high, if prediction offset is not capped tracking poll will fail.
 const MAX_PREDICTION: Duration = Duration::from_millis(80);
 const IPD_CHANGE_EPS: f32 = 0.001;
+const CANTING_CHANGE_EPS: f32 = 0.001; // rad
 const DECODER_MAX_TIMEOUT_MULTIPLIER: f32 = 0.8;
+// Sample rate of the Quest controllers actuators
+const PCM_HAPTICS_SAMPLE_RATE: f32 = 2000.0;
+// Each overlay quad is seen by one eye only, so its distance does not change how it looks
+const OVERLAY_LAYER_DISTANCE: f32 = 1.0;
 
 // Platform of the device. It is used to match the VR runtime and enable features conditionally.
 #[derive(PartialEq, Eq, Clone, Copy)]
@@ -43,6 +50,7 @@ struct HistoryView {
 }
 
 struct StreamingInputContext {
+    core_context: Arc<ClientCoreContext>,
     platform: Platform,
     xr_instance: xr::Instance,
     xr_session: xr::Session<xr::AnyGraphics>,
@@ -51,6 +59,7 @@ struct StreamingInputContext {
     history_view_sender: mpsc::Sender<HistoryView>,
     reference_space: Arc<xr::Space>,
     last_ipd: f32,
+    last_view_orientations: [Quat; 2],
     last_hand_positions: [Vec3; 2],
 }
 
@@ -87,6 +96,23 @@ fn to_fov(f: xr::Fovf) -> Fov {
     }
 }
 
+// With overscan, the stream frame covers a larger area than the display. The runtime reprojects it
+// to the latest head pose, showing the extra area instead of black borders
+fn layer_fov(fov: xr::Fovf, overscan: Option<f32>) -> xr::Fovf {
+    if let Some(overscan) = overscan {
+        let fov = to_fov(fov).with_overscan(overscan);
+
+        xr::Fovf {
+            angle_left: fov.left,
+            angle_right: fov.right,
+            angle_up: fov.up,
+            angle_down: fov.down,
+        }
+    } else {
+        fov
+    }
+}
+
 fn to_xr_time(timestamp: Duration) -> xr::Time {
     xr::Time::from_nanos(timestamp.as_nanos() as _)
 }
@@ -218,6 +244,86 @@ pub fn create_swapchain(
     }
 }
 
+fn create_overlay_swapchain(
+    session: &xr::Session<xr::OpenGlEs>,
+    resolution: UVec2,
+) -> xr::Swapchain<xr::OpenGlEs> {
+    session
+        .create_swapchain(&xr::SwapchainCreateInfo {
+            create_flags: xr::SwapchainCreateFlags::EMPTY,
+            usage_flags: xr::SwapchainUsageFlags::TRANSFER_DST | xr::SwapchainUsageFlags::SAMPLED,
+            format: glow::SRGB8_ALPHA8,
+            sample_count: 1,
+            width: resolution.x,
+            height: resolution.y,
+            face_count: 1,
+            array_size: 1,
+            mip_count: 1,
+        })
+        .unwrap()
+}
+
+// Head-locked quad covering the field of view of one eye. view is relative to the view space
+fn overlay_quad<'a>(
+    view_space: &'a xr::Space,
+    view: &xr::View,
+    eye_index: usize,
+    swapchain: &'a xr::Swapchain<xr::OpenGlEs>,
+    resolution: UVec2,
+) -> xr::CompositionLayerQuad<'a, xr::OpenGlEs> {
+    let tan_left = view.fov.angle_left.tan();
+    let tan_right = view.fov.angle_right.tan();
+    let tan_up = view.fov.angle_up.tan();
+    let tan_down = view.fov.angle_down.tan();
+
+    // The FOV can be asymmetric, the quad center is not necessarily in front of the eye
+    let eye_pose = to_pose(view.pose);
+    let center = eye_pose.position
+        + eye_pose.orientation
+            * Vec3::new(
+                (tan_left + tan_right) / 2.0,
+                (tan_up + tan_down) / 2.0,
+                -1.0,
+            )
+            * OVERLAY_LAYER_DISTANCE;
+
+    xr::CompositionLayerQuad::new()
+        .layer_flags(
+            xr::CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA
+                | xr::CompositionLayerFlags::UNPREMULTIPLIED_ALPHA,
+        )
+        .space(view_space)
+        .eye_visibility(if eye_index == 0 {
+            xr::EyeVisibility::LEFT
+        } else {
+            xr::EyeVisibility::RIGHT
+        })
+        .sub_image(
+            xr::SwapchainSubImage::new()
+                .swapchain(swapchain)
+                .image_array_index(0)
+                .image_rect(xr::Rect2Di {
+                    offset: xr::Offset2Di { x: 0, y: 0 },
+                    extent: xr::Extent2Di {
+                        width: resolution.x as _,
+                        height: resolution.y as _,
+                    },
+                }),
+        )
+        .pose(xr::Posef {
+            orientation: view.pose.orientation,
+            position: xr::Vector3f {
+                x: center.x,
+                y: center.y,
+                z: center.z,
+            },
+        })
+        .size(xr::Extent2Df {
+            width: (tan_right - tan_left) * OVERLAY_LAYER_DISTANCE,
+            height: (tan_up - tan_down) * OVERLAY_LAYER_DISTANCE,
+        })
+}
+
 // This function is allowed to return errors. It can happen when the session is destroyed
 // asynchronously
 fn update_streaming_input(ctx: &mut StreamingInputContext) {
@@ -238,7 +344,7 @@ fn update_streaming_input(ctx: &mut StreamingInputContext) {
 
     let target_timestamp = now
         + Duration::min(
-            alvr_client_core::get_head_prediction_offset(),
+            ctx.core_context.get_head_prediction_offset(),
             MAX_PREDICTION,
         );
 
@@ -260,19 +366,34 @@ fn update_streaming_input(ctx: &mut StreamingInputContext) {
             break 'head_tracking;
         }
 
+        // Note: The head position is approximated as the center point between the eyes. For canted
+        // displays the head orientation is halfway between the view orientations.
+        let head_position =
+            (to_vec3(views[0].pose.position) + to_vec3(views[1].pose.position)) / 2.0;
+        let head_orientation =
+            to_quat(views[0].pose.orientation).slerp(to_quat(views[1].pose.orientation), 0.5);
+
         let ipd = (to_vec3(views[0].pose.position) - to_vec3(views[1].pose.position)).length();
-        if f32::abs(ctx.last_ipd - ipd) > IPD_CHANGE_EPS {
-            alvr_client_core::send_views_config([to_fov(views[0].fov), to_fov(views[1].fov)], ipd);
+        let view_orientations = [
+            head_orientation.inverse() * to_quat(views[0].pose.orientation),
+            head_orientation.inverse() * to_quat(views[1].pose.orientation),
+        ];
+        if f32::abs(ctx.last_ipd - ipd) > IPD_CHANGE_EPS
+            || (0..2).any(|i| {
+                ctx.last_view_orientations[i].angle_between(view_orientations[i])
+                    > CANTING_CHANGE_EPS
+            })
+        {
+            ctx.core_context.send_views_config(
+                [to_fov(views[0].fov), to_fov(views[1].fov)],
+                ipd,
+                view_orientations,
+            );
 
             ctx.last_ipd = ipd;
+            ctx.last_view_orientations = view_orientations;
         }
 
-        // Note: Here is assumed that views are on the same plane and orientation. The head position
-        // is approximated as the center point between the eyes.
-        let head_position =
-            (to_vec3(views[0].pose.position) + to_vec3(views[1].pose.position)) / 2.0;
-        let head_orientation = to_quat(views[0].pose.orientation);
-
         ctx.history_view_sender
             .send(HistoryView {
                 timestamp: target_timestamp,
@@ -295,7 +416,7 @@ fn update_streaming_input(ctx: &mut StreamingInputContext) {
 
     let tracker_time = to_xr_time(
         now + Duration::min(
-            alvr_client_core::get_tracker_prediction_offset(),
+            ctx.core_context.get_tracker_prediction_offset(),
             MAX_PREDICTION,
         ),
     );
@@ -333,7 +454,7 @@ fn update_streaming_input(ctx: &mut StreamingInputContext) {
         Default::default()
     };
 
-    alvr_client_core::send_tracking(Tracking {
+    ctx.core_context.send_tracking(Tracking {
         target_timestamp,
         device_motions,
         hand_skeletons: [left_hand_skeleton, right_hand_skeleton],
@@ -346,7 +467,7 @@ fn update_streaming_input(ctx: &mut StreamingInputContext) {
         &ctx.hands_context.button_actions,
     );
     if !button_entries.is_empty() {
-        alvr_client_core::send_buttons(button_entries);
+        ctx.core_context.send_buttons(button_entries);
     }
 }
 
@@ -390,6 +511,7 @@ pub fn entry_point() {
     exts.fb_face_tracking = available_extensions.fb_face_tracking;
     exts.fb_foveation = available_extensions.fb_foveation;
     exts.fb_foveation_configuration = available_extensions.fb_foveation_configuration;
+    exts.fb_haptic_pcm = available_extensions.fb_haptic_pcm;
     exts.fb_swapchain_update_state = available_extensions.fb_swapchain_update_state;
     exts.htc_facial_tracking = available_extensions.htc_facial_tracking;
     exts.htc_vive_focus3_controller_interaction =
@@ -437,9 +559,17 @@ pub fn entry_point() {
             .unwrap();
         assert_eq!(views_config.len(), 2);
 
-        let recommended_view_resolution = UVec2::new(
-            views_config[0].recommended_image_rect_width,
-            views_config[0].recommended_image_rect_height,
+        // The two views are encoded with the same size, so with asymmetric displays use the
+        // largest so that neither view is undersampled
+        let recommended_view_resolution = UVec2::max(
+            UVec2::new(
+                views_config[0].recommended_image_rect_width,
+                views_config[0].recommended_image_rect_height,
+            ),
+            UVec2::new(
+                views_config[1].recommended_image_rect_width,
+                views_config[1].recommended_image_rect_height,
+            ),
         );
 
         let supported_refresh_rates = if exts.fb_display_refresh_rate {
@@ -448,7 +578,24 @@ pub fn entry_point() {
             vec![90.0]
         };
 
-        alvr_client_core::initialize(recommended_view_resolution, supported_refresh_rates, false);
+        // Foveated encoding is decoded by alvr_client_core::opengl
+        let core_context = Arc::new(ClientCoreContext::new(ClientCapabilities {
+            default_view_resolution: recommended_view_resolution,
+            refresh_rates: supported_refresh_rates,
+            foveated_encoding: true,
+            external_decoder: false,
+            pcm_haptics_sample_rate: exts.fb_haptic_pcm.then_some(PCM_HAPTICS_SAMPLE_RATE),
+            // OpenXR gives no control over the runtime frame queue nor the front buffer
+            extra_latency_mode: false,
+            front_buffer_rendering: false,
+            overlay_layers: true,
+            runtime_name: xr_instance
+                .properties()
+                .map(|properties| {
+                    format!("{} {}", properties.runtime_name, properties.runtime_version)
+                })
+                .unwrap_or_default(),
+        }));
         alvr_client_core::opengl::initialize();
 
         let hands_context = Arc::new(interaction::initialize_hands_interaction(
@@ -465,9 +612,21 @@ pub fn entry_point() {
                 .create_reference_space(xr::ReferenceSpaceType::STAGE, xr::Posef::IDENTITY)
                 .unwrap(),
         );
+        let view_space = xr_session
+            .create_reference_space(xr::ReferenceSpaceType::VIEW, xr::Posef::IDENTITY)
+            .unwrap();
         let mut lobby_swapchains = None;
         let mut stream_swapchains = None;
+        // Keyed by layer and eye index, in compositing order
+        let mut overlay_layers = BTreeMap::<(u32, usize), (xr::Swapchain<_>, UVec2)>::new();
         let mut stream_view_resolution = UVec2::ZERO;
+        let mut stream_overscan = None::<f32>;
+        let mut stream_reprojection = true;
+        // While paused, tracking is still sent but the lobby is rendered
+        let mut stream_paused = false;
+        let mut display_off = false;
+        let mut captions_config = None;
+        let mut caption_deadline = None::<Instant>;
         let mut streaming_input_thread = None::<thread::JoinHandle<_>>;
         let mut views_history = VecDeque::new();
 
@@ -541,7 +700,7 @@ pub fn entry_point() {
                                 ],
                             );
 
-                            alvr_client_core::resume();
+                            core_context.resume();
                         }
                         xr::SessionState::STOPPING => {
                             // Make sure streaming resources are destroyed before pausing
@@ -555,7 +714,7 @@ pub fn entry_point() {
                                 }
                             }
 
-                            alvr_client_core::pause();
+                            core_context.pause();
 
                             alvr_client_core::opengl::pause();
 
@@ -590,7 +749,7 @@ pub fn entry_point() {
                             sender.send(Arc::clone(&reference_space)).ok();
                         }
 
-                        alvr_client_core::send_playspace(
+                        core_context.send_playspace(
                             xr_session
                                 .reference_space_bounds_rect(xr::ReferenceSpaceType::STAGE)
                                 .unwrap()
@@ -635,17 +794,28 @@ pub fn entry_point() {
                 continue;
             };
 
-            while let Some(event) = alvr_client_core::poll_event() {
+            while let Some(event) = core_context.poll_event() {
                 match event {
                     ClientCoreEvent::UpdateHudMessage(message) => {
-                        alvr_client_core::opengl::update_hud_message(&message);
+                        alvr_client_core::opengl::update_hud_message(&message.to_plain_text());
                     }
+                    // The HUD message already describes the failure
+                    ClientCoreEvent::ConnectionFailed { .. } => (),
                     ClientCoreEvent::StreamingStarted {
                         view_resolution,
                         refresh_rate_hint,
                         settings,
+                        ..
                     } => {
                         stream_view_resolution = view_resolution;
+                        stream_overscan = settings
+                            .video
+                            .reprojection_overscan
+                            .as_option()
+                            .map(|percentage| *percentage / 100.0);
+                        stream_reprojection = settings.video.client_reprojection;
+                        captions_config = settings.headset.captions.clone().into_option();
+                        display_off = false;
 
                         if exts.fb_display_refresh_rate {
                             xr_session
@@ -686,6 +856,7 @@ pub fn entry_point() {
                             };
 
                         let mut context = StreamingInputContext {
+                            core_context: Arc::clone(&core_context),
                             platform,
                             xr_instance: xr_instance.clone(),
                             xr_session: xr_session.clone().into_any_graphics(),
@@ -694,6 +865,7 @@ pub fn entry_point() {
                             history_view_sender: history_view_sender.clone(),
                             reference_space: Arc::clone(&reference_space),
                             last_ipd: 0.0,
+                            last_view_orientations: [Quat::IDENTITY; 2],
                             last_hand_positions: [Vec3::ZERO; 2],
                         };
 
@@ -767,6 +939,10 @@ pub fn entry_point() {
                             ]
                         });
 
+                        alvr_client_core::opengl::set_color_blindness_filter(
+                            settings.video.color_blindness_filter.as_option(),
+                        );
+
                         alvr_client_core::opengl::start_stream(
                             view_resolution,
                             [
@@ -786,7 +962,7 @@ pub fn entry_point() {
                             settings.video.foveated_rendering.into_option(),
                         );
 
-                        alvr_client_core::send_playspace(
+                        core_context.send_playspace(
                             xr_session
                                 .reference_space_bounds_rect(xr::ReferenceSpaceType::STAGE)
                                 .unwrap()
@@ -795,6 +971,14 @@ pub fn entry_point() {
                     }
                     ClientCoreEvent::StreamingStopped => {
                         stream_swapchains.take();
+                        overlay_layers.clear();
+                        stream_paused = false;
+
+                        if caption_deadline.take().is_some() {
+                            if let Some(config) = &captions_config {
+                                alvr_client_core::opengl::update_caption("", config);
+                            }
+                        }
 
                         is_streaming.set(false);
 
@@ -802,11 +986,41 @@ pub fn entry_point() {
                             thread.join().unwrap();
                         }
                     }
+                    ClientCoreEvent::ExitRequested => {
+                        xr_session.request_exit().unwrap();
+                    }
+                    // Rendering resumes with the next stream
+                    ClientCoreEvent::DisplayOffRequested => display_off = true,
+                    // Not implemented by this frontend yet
+                    ClientCoreEvent::PassthroughToggleRequested
+                    | ClientCoreEvent::PerformanceOverlayToggleRequested => {
+                        info!("Passthrough and performance overlay are not supported yet");
+                    }
+                    ClientCoreEvent::StreamPaused => stream_paused = true,
+                    ClientCoreEvent::StreamResumed => stream_paused = false,
+                    // The HUD message already shows the countdown
+                    ClientCoreEvent::SessionTimeRemaining { .. } => (),
+                    // The HUD message already names the new output device
+                    ClientCoreEvent::AudioOutputChanged { .. } => (),
+                    // The HUD message lists the streamers, the first one is chosen after a while
+                    ClientCoreEvent::ServersDiscovered(_) => (),
+                    // Reading back the swapchain is not supported yet
+                    ClientCoreEvent::ScreenshotRequested { label } => {
+                        info!("Screenshot {label} requested, not supported by this client");
+                    }
+                    ClientCoreEvent::RefreshRateChanged { refresh_rate } => {
+                        if exts.fb_display_refresh_rate {
+                            if let Err(e) = xr_session.request_display_refresh_rate(refresh_rate) {
+                                warn!("Failed to switch refresh rate to {refresh_rate}Hz: {e}");
+                            }
+                        }
+                    }
                     ClientCoreEvent::Haptics {
                         device_id,
                         duration,
                         frequency,
                         amplitude,
+                        pcm,
                     } => {
                         let action = if device_id == *LEFT_HAND_ID {
                             &hands_context.hand_sources[0].vibration_action
@@ -814,21 +1028,80 @@ pub fn entry_point() {
                             &hands_context.hand_sources[1].vibration_action
                         };
 
-                        action
-                            .apply_feedback(
-                                &xr_session,
-                                xr::Path::NULL,
-                                &xr::HapticVibration::new()
-                                    .amplitude(amplitude)
-                                    .frequency(frequency)
-                                    .duration(xr::Duration::from_nanos(duration.as_nanos() as _)),
-                            )
-                            .unwrap();
+                        if let Some(pcm) = pcm {
+                            interaction::apply_pcm_haptics(&xr_session, action, &pcm);
+                        } else {
+                            action
+                                .apply_feedback(
+                                    &xr_session,
+                                    xr::Path::NULL,
+                                    &xr::HapticVibration::new()
+                                        .amplitude(amplitude)
+                                        .frequency(frequency)
+                                        .duration(xr::Duration::from_nanos(
+                                            duration.as_nanos() as _
+                                        )),
+                                )
+                                .unwrap();
+                        }
+                    }
+                    // Bluetooth accessories are not supported by this client yet
+                    ClientCoreEvent::ExternalHaptics { .. } => (),
+                    ClientCoreEvent::OverlayLayer {
+                        layer_index,
+                        eye_index,
+                        image,
+                    } => {
+                        let key = (layer_index, eye_index);
+                        if let Some((resolution, rgba)) = image {
+                            if overlay_layers
+                                .get(&key)
+                                .map(|(_, current)| *current != resolution)
+                                .unwrap_or(true)
+                            {
+                                overlay_layers.insert(
+                                    key,
+                                    (
+                                        create_overlay_swapchain(&xr_session, resolution),
+                                        resolution,
+                                    ),
+                                );
+                            }
+
+                            let (swapchain, _) = overlay_layers.get_mut(&key).unwrap();
+                            let image_index = swapchain.acquire_image().unwrap();
+                            swapchain.wait_image(xr::Duration::INFINITE).unwrap();
+                            alvr_client_core::opengl::update_overlay_layer_texture(
+                                swapchain.enumerate_images().unwrap()[image_index as usize],
+                                resolution,
+                                &rgba,
+                            );
+                            swapchain.release_image().unwrap();
+                        } else {
+                            overlay_layers.remove(&key);
+                        }
+                    }
+                    ClientCoreEvent::ColorBlindnessFilterChanged(filter) => {
+                        alvr_client_core::opengl::set_color_blindness_filter(filter.as_ref());
+                    }
+                    ClientCoreEvent::Caption { text, duration } => {
+                        if let Some(config) = &captions_config {
+                            alvr_client_core::opengl::update_caption(&text, config);
+                            caption_deadline =
+                                (!text.is_empty()).then(|| Instant::now() + duration);
+                        }
                     }
                     _ => panic!(),
                 }
             }
 
+            if caption_deadline.map_or(false, |deadline| Instant::now() > deadline) {
+                caption_deadline = None;
+                if let Some(config) = &captions_config {
+                    alvr_client_core::opengl::update_caption("", config);
+                }
+            }
+
             let frame_state = match xr_frame_waiter.wait() {
                 Ok(state) => state,
                 Err(e) => {
@@ -843,7 +1116,7 @@ pub fn entry_point() {
 
             xr_frame_stream.begin().unwrap();
 
-            if !frame_state.should_render {
+            if !frame_state.should_render || display_off {
                 xr_frame_stream
                     .end(
                         frame_state.predicted_display_time,
@@ -855,10 +1128,9 @@ pub fn entry_point() {
                 continue;
             }
 
-            let swapchains = if let Some(swapchains) = &mut stream_swapchains {
-                swapchains
-            } else {
-                lobby_swapchains
+            let swapchains = match &mut stream_swapchains {
+                Some(swapchains) if !stream_paused => swapchains,
+                _ => lobby_swapchains,
             };
 
             let left_swapchain_idx = swapchains[0].acquire_image().unwrap();
@@ -871,14 +1143,15 @@ pub fn entry_point() {
 
             let display_time;
             let view_resolution;
-            if is_streaming.value() {
+            let overscan;
+            if is_streaming.value() && !stream_paused {
                 let frame_poll_deadline = Instant::now()
                     + Duration::from_secs_f32(
                         frame_interval.as_secs_f32() * DECODER_MAX_TIMEOUT_MULTIPLIER,
                     );
                 let mut frame_result = None;
                 while frame_result.is_none() && Instant::now() < frame_poll_deadline {
-                    frame_result = alvr_client_core::get_frame();
+                    frame_result = core_context.get_frame();
                     thread::yield_now();
                 }
 
@@ -886,6 +1159,8 @@ pub fn entry_point() {
                     pair
                 } else {
                     warn!("Timed out when waiting for frame!");
+                    core_context.report_frame_reprojected();
+
                     (vsync_time, ptr::null_mut())
                 };
 
@@ -903,6 +1178,19 @@ pub fn entry_point() {
                     }
                 }
 
+                // Without reprojection the frame is displayed as if rendered for the current pose
+                if !stream_reprojection {
+                    if let Ok((flags, current_views)) = xr_session.locate_views(
+                        xr::ViewConfigurationType::PRIMARY_STEREO,
+                        frame_state.predicted_display_time,
+                        &reference_space,
+                    ) {
+                        if flags.contains(xr::ViewStateFlags::ORIENTATION_VALID) {
+                            views = current_views;
+                        }
+                    }
+                }
+
                 alvr_client_core::opengl::render_stream(
                     hardware_buffer,
                     [left_swapchain_idx, right_swapchain_idx],
@@ -910,13 +1198,18 @@ pub fn entry_point() {
 
                 if !hardware_buffer.is_null() {
                     if let Some(now) = xr_runtime_now(&xr_instance) {
-                        alvr_client_core::report_submit(timestamp, vsync_time.saturating_sub(now));
+                        core_context.report_submit(timestamp, vsync_time.saturating_sub(now));
                     }
                 }
 
-                display_time = timestamp;
+                display_time = if stream_reprojection {
+                    timestamp
+                } else {
+                    vsync_time
+                };
 
                 view_resolution = stream_view_resolution;
+                overscan = stream_overscan;
             } else {
                 display_time = vsync_time;
 
@@ -933,6 +1226,7 @@ pub fn entry_point() {
                 }
 
                 view_resolution = recommended_view_resolution;
+                overscan = None;
 
                 alvr_client_core::opengl::render_lobby([
                     RenderViewInput {
@@ -959,31 +1253,63 @@ pub fn entry_point() {
                 },
             };
 
+            // The overlays are located for the displayed head pose, so they stay head-locked
+            let overlay_views = if is_streaming.value() && !stream_paused {
+                xr_session
+                    .locate_views(
+                        xr::ViewConfigurationType::PRIMARY_STEREO,
+                        frame_state.predicted_display_time,
+                        &view_space,
+                    )
+                    .map(|(_, views)| views)
+                    .unwrap_or_default()
+            } else {
+                vec![]
+            };
+            let overlay_quads = overlay_layers
+                .iter()
+                .filter_map(|((_, eye_index), (swapchain, resolution))| {
+                    Some(overlay_quad(
+                        &view_space,
+                        overlay_views.get(*eye_index)?,
+                        *eye_index,
+                        swapchain,
+                        *resolution,
+                    ))
+                })
+                .collect::<Vec<_>>();
+
+            let projection_views = [
+                xr::CompositionLayerProjectionView::new()
+                    .pose(views[0].pose)
+                    .fov(layer_fov(views[0].fov, overscan))
+                    .sub_image(
+                        xr::SwapchainSubImage::new()
+                            .swapchain(&swapchains[0])
+                            .image_array_index(0)
+                            .image_rect(rect),
+                    ),
+                xr::CompositionLayerProjectionView::new()
+                    .pose(views[1].pose)
+                    .fov(layer_fov(views[1].fov, overscan))
+                    .sub_image(
+                        xr::SwapchainSubImage::new()
+                            .swapchain(&swapchains[1])
+                            .image_array_index(0)
+                            .image_rect(rect),
+                    ),
+            ];
+            let projection_layer = xr::CompositionLayerProjection::new()
+                .space(&reference_space)
+                .views(&projection_views);
+
+            let mut layers: Vec<&xr::CompositionLayerBase<_>> = vec![&projection_layer];
+            layers.extend(overlay_quads.iter().map(|quad| &**quad));
+
             let res = xr_frame_stream.end(
                 to_xr_time(display_time),
                 xr::EnvironmentBlendMode::OPAQUE,
-                &[&xr::CompositionLayerProjection::new()
-                    .space(&reference_space)
-                    .views(&[
-                        xr::CompositionLayerProjectionView::new()
-                            .pose(views[0].pose)
-                            .fov(views[0].fov)
-                            .sub_image(
-                                xr::SwapchainSubImage::new()
-                                    .swapchain(&swapchains[0])
-                                    .image_array_index(0)
-                                    .image_rect(rect),
-                            ),
-                        xr::CompositionLayerProjectionView::new()
-                            .pose(views[1].pose)
-                            .fov(views[1].fov)
-                            .sub_image(
-                                xr::SwapchainSubImage::new()
-                                    .swapchain(&swapchains[1])
-                                    .image_array_index(0)
-                                    .image_rect(rect),
-                            ),
-                    ])],
+                &layers,
             );
 
             if let Err(e) = res {
@@ -1001,11 +1327,16 @@ pub fn entry_point() {
 
             last_good_views = views.clone();
         }
+
+        // The core context is dropped at the end of the session, the streaming thread must not
+        // keep it alive
+        is_streaming.set(false);
+        if let Some(thread) = streaming_input_thread.take() {
+            thread.join().ok();
+        }
     }
 
     alvr_client_core::opengl::destroy();
-
-    alvr_client_core::destroy();
 }
 
 #[allow(unused)]
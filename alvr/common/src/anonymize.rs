@@ -0,0 +1,174 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::net::{IpAddr, Ipv4Addr};
+
+// Hostnames and Wi-Fi SSIDs cannot be recognized by their shape, they are registered when known
+static SENSITIVE_NAMES: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(vec![]));
+
+// Shorter names would corrupt unrelated words
+const MIN_SENSITIVE_NAME_LEN: usize = 3;
+
+pub fn register_sensitive_name(name: &str) {
+    if name.len() < MIN_SENSITIVE_NAME_LEN {
+        return;
+    }
+
+    let mut names = SENSITIVE_NAMES.lock();
+    if !names.iter().any(|n| n == name) {
+        names.push(name.to_owned());
+        // Replace longer names first, in case a name contains another
+        names.sort_by_key(|n| std::cmp::Reverse(n.len()));
+    }
+}
+
+fn is_address_char(c: char) -> bool {
+    c.is_ascii_hexdigit() || c == '.' || c == ':'
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_mac_address(token: &str) -> bool {
+    let groups = token.split(':').collect::<Vec<_>>();
+
+    groups.len() == 6
+        && groups
+            .iter()
+            .all(|g| g.len() == 2 && g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn redact_address(token: &str) -> Option<String> {
+    if token.parse::<IpAddr>().is_ok() {
+        Some("<ip>".into())
+    } else if is_mac_address(token) {
+        Some("<mac>".into())
+    } else if let Some((ip, port)) = token.rsplit_once(':') {
+        (ip.parse::<Ipv4Addr>().is_ok() && port.parse::<u16>().is_ok())
+            .then(|| format!("<ip>:{port}"))
+    } else {
+        None
+    }
+}
+
+// Replaces IP and MAC addresses and the registered hostnames and SSIDs
+pub fn anonymize_log(message: &str) -> String {
+    let mut result = String::with_capacity(message.len());
+
+    let mut last_end = 0;
+    let mut run_start = None;
+    // The trailing space terminates a run at the end of the message
+    for (index, c) in message.char_indices().chain([(message.len(), ' ')]) {
+        if is_address_char(c) {
+            run_start.get_or_insert(index);
+            continue;
+        }
+
+        let Some(start) = run_start.take() else {
+            continue;
+        };
+        let is_whole_word = !is_word_char(c)
+            && !message[..start]
+                .chars()
+                .next_back()
+                .map_or(false, is_word_char);
+        if !is_whole_word {
+            continue;
+        }
+
+        // Punctuation at the end of a sentence is not part of the address
+        let token = message[start..index].trim_end_matches(|c| c == '.' || c == ':');
+        if let Some(replacement) = redact_address(token) {
+            result.push_str(&message[last_end..start]);
+            result.push_str(&replacement);
+            last_end = start + token.len();
+        }
+    }
+    result.push_str(&message[last_end..]);
+
+    for name in &*SENSITIVE_NAMES.lock() {
+        result = result.replace(name.as_str(), "<redacted>");
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ipv4() {
+        assert_eq!(
+            anonymize_log("Client 192.168.1.34 connected"),
+            "Client <ip> connected"
+        );
+        // Punctuation after the address is kept
+        assert_eq!(
+            anonymize_log("Connecting to 10.0.0.2..."),
+            "Connecting to <ip>..."
+        );
+        assert_eq!(anonymize_log("192.168.1.34"), "<ip>");
+    }
+
+    #[test]
+    fn test_ipv6() {
+        assert_eq!(
+            anonymize_log("Link address fe80::1c2b:3cff:fe4d:5e6f is up"),
+            "Link address <ip> is up"
+        );
+        assert_eq!(anonymize_log("Bound to [::1]:9943"), "Bound to [<ip>]:9943");
+    }
+
+    #[test]
+    fn test_ip_with_port() {
+        assert_eq!(
+            anonymize_log("Stream socket 192.168.1.34:9944 closed"),
+            "Stream socket <ip>:9944 closed"
+        );
+        // Not a port
+        assert_eq!(
+            anonymize_log("Peer 192.168.1.34:99999"),
+            "Peer 192.168.1.34:99999"
+        );
+    }
+
+    #[test]
+    fn test_mac() {
+        assert_eq!(
+            anonymize_log("Adapter a4:5E:60:c1:22:0f found"),
+            "Adapter <mac> found"
+        );
+        // Wrong group count or group length
+        assert_eq!(anonymize_log("a4:5e:60:c1:22"), "a4:5e:60:c1:22");
+        assert_eq!(anonymize_log("a4:5e:60:c1:22:0f1"), "a4:5e:60:c1:22:0f1");
+    }
+
+    #[test]
+    fn test_hex_like_words_are_kept() {
+        for message in [
+            "Frame deadbeef decoded",
+            "Texture handle 0x1f2e3d",
+            "Decoder cafe: ok",
+            "Timestamp 12:34:56",
+            "ALVR v21.0.0-dev00",
+            "abc1.2.3.4",
+            "1.2.3.4x",
+            "Hash ab12cd34ef",
+        ] {
+            assert_eq!(anonymize_log(message), message);
+        }
+    }
+
+    #[test]
+    fn test_sensitive_names() {
+        register_sensitive_name("LivingRoomWifi");
+        // Too short to be replaced safely
+        register_sensitive_name("ok");
+
+        assert_eq!(
+            anonymize_log("Connected to LivingRoomWifi: ok"),
+            "Connected to <redacted>: ok"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+mod anonymize;
 mod average;
 mod connection_result;
 mod logging;
@@ -14,6 +15,7 @@ pub use parking_lot;
 pub use semver;
 pub use settings_schema;
 
+pub use anonymize::*;
 pub use average::*;
 pub use connection_result::*;
 pub use log::{debug, error, info, warn};
@@ -14,6 +14,20 @@ pub struct Fov {
     pub down: f32,
 }
 
+impl Fov {
+    // Enlarges each side by a fraction of its tangent, so that the view plane is scaled uniformly
+    pub fn with_overscan(self, overscan: f32) -> Fov {
+        let scale = |angle: f32| f32::atan(angle.tan() * (1.0 + overscan));
+
+        Fov {
+            left: scale(self.left),
+            right: scale(self.right),
+            up: scale(self.up),
+            down: scale(self.down),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
 pub struct Pose {
     pub orientation: Quat, // NB: default Quat is identity
@@ -22,9 +36,11 @@ pub struct Pose {
 
 impl Pose {
     pub fn inverse(&self) -> Pose {
+        let inverse_orientation = self.orientation.conjugate();
+
         Pose {
-            orientation: self.orientation.conjugate(),
-            position: -self.position,
+            orientation: inverse_orientation,
+            position: -(inverse_orientation * self.position),
         }
     }
 }
@@ -46,3 +62,21 @@ pub struct DeviceMotion {
     pub linear_velocity: Vec3,
     pub angular_velocity: Vec3,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pose_inverse() {
+        let pose = Pose {
+            orientation: Quat::from_euler(glam::EulerRot::YXZ, 0.7, -0.3, 0.2),
+            position: Vec3::new(0.5, 1.6, -0.2),
+        };
+
+        for identity in [pose * pose.inverse(), pose.inverse() * pose] {
+            assert!(identity.orientation.abs_diff_eq(Quat::IDENTITY, 1e-6));
+            assert!(identity.position.abs_diff_eq(Vec3::ZERO, 1e-6));
+        }
+    }
+}
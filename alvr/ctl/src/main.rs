@@ -0,0 +1,239 @@
+use alvr_common::anyhow::{bail, Context, Result};
+use alvr_events::{Event, EventType};
+use alvr_packets::{
+    ApiClientAction, ApiClientRequest, ClientListAction, PathSegment, PathValuePair,
+};
+use alvr_server_io::ServerDataManager;
+use pico_args::Arguments;
+use std::{
+    env,
+    fs::File,
+    io::{self, Write},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(500);
+
+const HELP_STR: &str = r#"
+alvr_ctl
+Control ALVR from the command line. Requests are sent to the running streamer, or applied to the
+session file if the streamer is not running.
+
+USAGE:
+    alvr_ctl <SUBCOMMAND> [FLAGS] [ARGS]
+
+SUBCOMMANDS:
+    get <PATH>                  Print a session value as JSON, for example
+                                session_settings.video.preferred_fps
+    set <PATH> <VALUE>          Set a session value. VALUE is parsed as JSON, or used as a string
+    restart-steamvr             Restart SteamVR
+    list-clients                List the known clients
+    trust <HOSTNAME>            Trust a client
+    capture-statistics          Print streaming statistics as JSON lines until interrupted
+
+FLAGS:
+    --help                      Print this text
+
+ARGS:
+    --port <PORT>               Web server port. Read from the session by default
+    --duration <SECONDS>        Stop capture-statistics after the specified time
+    --output <PATH>             Write capture-statistics output to a file instead of stdout
+"#;
+
+// Parses paths like session_settings.headset.controllers.content.extra_openvr_props[0]
+fn parse_path(path: &str) -> Result<Vec<PathSegment>> {
+    let mut segments = vec![];
+    for part in path.split('.') {
+        let mut indices = part.split('[');
+
+        let name = indices.next().unwrap_or_default();
+        if name.is_empty() {
+            bail!("Invalid path {path}");
+        }
+        segments.push(PathSegment::Name(name.to_owned()));
+
+        for index in indices {
+            let index = index
+                .strip_suffix(']')
+                .and_then(|index| index.parse().ok())
+                .with_context(|| format!("Invalid index in path {path}"))?;
+            segments.push(PathSegment::Index(index));
+        }
+    }
+
+    Ok(segments)
+}
+
+fn local_data_manager() -> ServerDataManager {
+    let session_file_path =
+        alvr_filesystem::filesystem_layout_from_dashboard_exe(&env::current_exe().unwrap())
+            .session();
+
+    ServerDataManager::new(&session_file_path)
+}
+
+fn is_server_running(port: u16) -> bool {
+    ureq::AgentBuilder::new()
+        .timeout_connect(REQUEST_TIMEOUT)
+        .build()
+        .get(&format!("http://127.0.0.1:{port}/api/v1/version"))
+        .call()
+        .is_ok()
+}
+
+fn send_request(port: u16, endpoint: &str, body: impl serde::Serialize) -> Result<()> {
+    ureq::AgentBuilder::new()
+        .timeout_connect(REQUEST_TIMEOUT)
+        .build()
+        .post(&format!("http://127.0.0.1:{port}/api/v1{endpoint}"))
+        .send_json(body)?;
+
+    Ok(())
+}
+
+fn get_value(data_manager: &ServerDataManager, path: &str) -> Result<()> {
+    let mut value = serde_json::to_value(data_manager.session())?;
+    for segment in parse_path(path)? {
+        value = match segment {
+            PathSegment::Name(name) => value.get(&name).cloned(),
+            PathSegment::Index(index) => value.get(index).cloned(),
+        }
+        .with_context(|| format!("Path {path} not found"))?;
+    }
+
+    println!("{}", serde_json::to_string_pretty(&value)?);
+
+    Ok(())
+}
+
+fn capture_statistics(port: u16, duration: Option<Duration>, output: Option<String>) -> Result<()> {
+    let mut output: Box<dyn Write> = if let Some(path) = output {
+        Box::new(File::create(path)?)
+    } else {
+        Box::new(io::stdout())
+    };
+
+    let socket = TcpStream::connect(format!("127.0.0.1:{port}"))?;
+    let (mut ws, _) = tungstenite::client(format!("ws://127.0.0.1:{port}/api/v1/events"), socket)?;
+
+    let begin_instant = Instant::now();
+    while duration
+        .map(|duration| begin_instant.elapsed() < duration)
+        .unwrap_or(true)
+    {
+        if let tungstenite::Message::Text(json_string) = ws.read()? {
+            if let Ok(Event {
+                event_type: EventType::StatisticsSummary(stats),
+                ..
+            }) = serde_json::from_str(&json_string)
+            {
+                writeln!(output, "{}", serde_json::to_string(&stats)?)?;
+                output.flush()?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run(subcommand: &str, mut args: Arguments) -> Result<()> {
+    let mut data_manager = local_data_manager();
+
+    let port = args
+        .opt_value_from_str("--port")?
+        .unwrap_or(data_manager.settings().connection.web_server_port);
+    let duration: Option<f32> = args.opt_value_from_str("--duration")?;
+    let output: Option<String> = args.opt_value_from_str("--output")?;
+    let free_args = args
+        .finish()
+        .into_iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+
+    let server_running = is_server_running(port);
+
+    match (subcommand, free_args.as_slice()) {
+        ("get", [path]) => get_value(&data_manager, path)?,
+        ("set", [path, value]) => {
+            let value = serde_json::from_str::<serde_json::Value>(value)
+                .unwrap_or(serde_json::Value::String(value.clone()));
+            let descs = vec![PathValuePair {
+                path: parse_path(path)?,
+                value,
+            }];
+
+            if server_running {
+                send_request(port, "/session/values", descs)?;
+            } else {
+                data_manager.set_values(descs)?;
+            }
+        }
+        ("restart-steamvr", []) => {
+            if !server_running {
+                bail!("The streamer is not running");
+            }
+            send_request(port, "/steamvr/restart", ())?;
+        }
+        ("list-clients", []) => {
+            for (hostname, client) in data_manager.client_list() {
+                let mut ips = client.manual_ips.iter().collect::<Vec<_>>();
+                ips.extend(&client.current_ip);
+
+                println!(
+                    "{hostname}\t{}\t{}\t{:?}\t{ips:?}",
+                    client.display_name,
+                    if client.trusted {
+                        "trusted"
+                    } else {
+                        "untrusted"
+                    },
+                    client.connection_state,
+                );
+            }
+        }
+        ("trust", [hostname]) => {
+            if !data_manager.client_list().contains_key(hostname) {
+                bail!("Unknown client {hostname}");
+            }
+
+            if server_running {
+                send_request(
+                    port,
+                    "/clients",
+                    ApiClientRequest {
+                        hostname: hostname.clone(),
+                        action: ApiClientAction::Trust,
+                    },
+                )?;
+            } else {
+                data_manager.update_client_list(hostname.clone(), ClientListAction::Trust);
+            }
+        }
+        ("capture-statistics", []) => {
+            if !server_running {
+                bail!("The streamer is not running");
+            }
+            capture_statistics(port, duration.map(Duration::from_secs_f32), output)?;
+        }
+        _ => bail!("Wrong arguments. Run alvr_ctl --help for usage"),
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let mut args = Arguments::from_env();
+
+    if args.contains(["-h", "--help"]) {
+        println!("{HELP_STR}");
+    } else if let Ok(Some(subcommand)) = args.subcommand() {
+        if let Err(e) = run(&subcommand, args) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    } else {
+        println!("\nMissing subcommand.");
+        println!("{HELP_STR}");
+    }
+}
@@ -1,24 +1,95 @@
 use crate::dashboard::ServerRequest;
+use alvr_common::settings_schema::Switch;
 use alvr_gui_common::theme::{self, log_colors};
 use alvr_packets::ClientListAction;
-use alvr_session::{ClientConnectionConfig, ConnectionState, SessionConfig};
+use alvr_session::{
+    ClientCalibration, ClientConnectionConfig, ClientHardwareInfo, ConnectionState, SessionConfig,
+};
 use eframe::{
-    egui::{Frame, Grid, Layout, RichText, TextEdit, Ui, Window},
-    emath::{Align, Align2},
+    egui::{Frame, Grid, Layout, RichText, Sense, TextEdit, Ui, Window},
+    emath::{Align, Align2, Rect, Vec2},
     epaint::Color32,
 };
+use qrcode::QrCode;
 use std::net::{IpAddr, Ipv4Addr};
 
+const QR_MODULE_SIZE: f32 = 6.0;
+const QR_QUIET_ZONE_MODULES: usize = 4;
+const FLOOR_NUDGE_STEP: f32 = 0.01; // meters
+
+fn hardware_info_text(info: &ClientHardwareInfo) -> String {
+    let decoders = info
+        .decoders
+        .iter()
+        .map(|decoder| {
+            let codecs = decoder
+                .codecs
+                .iter()
+                .map(|codec| format!("{codec:?}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let acceleration = match decoder.hardware_accelerated {
+                Some(true) => ", hardware",
+                Some(false) => ", software",
+                None => "",
+            };
+
+            format!("\n    {} ({codecs}{acceleration})", decoder.name)
+        })
+        .collect::<String>();
+
+    format!(
+        "SoC: {}\nOS: {}\nRuntime: {}\nAvailable RAM: {} MB\nDecoders:{decoders}",
+        info.soc_model, info.os_version, info.runtime_name, info.available_ram_mb
+    )
+}
+
+fn qr_code_ui(ui: &mut Ui, data: &str) {
+    let Ok(code) = QrCode::new(data) else {
+        ui.label("Failed to create the QR code");
+        return;
+    };
+
+    let width = code.width();
+    let side = (width + 2 * QR_QUIET_ZONE_MODULES) as f32 * QR_MODULE_SIZE;
+    let (rect, _) = ui.allocate_exact_size(Vec2::splat(side), Sense::hover());
+
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, Color32::WHITE);
+
+    for (idx, color) in code.to_colors().into_iter().enumerate() {
+        if color == qrcode::Color::Dark {
+            let x = (idx % width + QR_QUIET_ZONE_MODULES) as f32;
+            let y = (idx / width + QR_QUIET_ZONE_MODULES) as f32;
+            painter.rect_filled(
+                Rect::from_min_size(
+                    rect.min + Vec2::new(x, y) * QR_MODULE_SIZE,
+                    Vec2::splat(QR_MODULE_SIZE),
+                ),
+                0.0,
+                Color32::BLACK,
+            );
+        }
+    }
+}
+
 struct EditPopupState {
     new_client: bool,
     hostname: String,
     ips: Vec<String>,
+    time_limit_min: String,   // empty for the default limit
+    max_bitrate_mbps: String, // empty for no limit
 }
 
 pub struct ConnectionsTab {
     new_clients: Option<Vec<(String, ClientConnectionConfig)>>,
     trusted_clients: Option<Vec<(String, ClientConnectionConfig)>>,
     edit_popup_state: Option<EditPopupState>,
+    pairing_uri: Option<String>,
+    colocation_enabled: bool,
+    session_time_limit_enabled: bool,
+    stream_paused: bool,
+    seated_mode: bool,
 }
 
 impl ConnectionsTab {
@@ -27,6 +98,11 @@ impl ConnectionsTab {
             new_clients: None,
             trusted_clients: None,
             edit_popup_state: None,
+            pairing_uri: None,
+            colocation_enabled: false,
+            session_time_limit_enabled: false,
+            stream_paused: false,
+            seated_mode: false,
         }
     }
 
@@ -40,6 +116,36 @@ impl ConnectionsTab {
 
         self.trusted_clients = Some(trusted_clients);
         self.new_clients = Some(untrusted_clients);
+
+        if !session
+            .client_connections
+            .values()
+            .any(|data| data.connection_state == ConnectionState::Streaming)
+        {
+            self.stream_paused = false;
+            self.seated_mode = false;
+        }
+
+        self.session_time_limit_enabled = matches!(
+            session.session_settings.connection.session_time_limit,
+            Switch::Enabled(_)
+        );
+        self.colocation_enabled = matches!(
+            session.session_settings.headset.colocation,
+            Switch::Enabled(_)
+        );
+    }
+
+    pub fn set_stream_paused(&mut self, paused: bool) {
+        self.stream_paused = paused;
+    }
+
+    pub fn set_seated_mode(&mut self, active: bool) {
+        self.seated_mode = active;
+    }
+
+    pub fn show_pairing_uri(&mut self, uri: String) {
+        self.pairing_uri = Some(uri);
     }
 
     pub fn ui(&mut self, ui: &mut Ui, connected_to_server: bool) -> Vec<ServerRequest> {
@@ -119,12 +225,15 @@ impl ConnectionsTab {
                             for (hostname, data) in clients {
                                 ui.horizontal(|ui| {
                                     ui.add_space(10.0);
-                                    ui.label(format!(
+                                    let label = ui.label(format!(
                                         "{hostname}: {} ({})",
                                         data.current_ip
                                             .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
                                         data.display_name
                                     ));
+                                    if let Some(info) = &data.hardware_info {
+                                        label.on_hover_text(hardware_info_text(info));
+                                    }
                                     match data.connection_state {
                                         ConnectionState::Disconnected => {
                                             ui.colored_label(Color32::GRAY, "Disconnected")
@@ -134,6 +243,9 @@ impl ConnectionsTab {
                                         ConnectionState::Connected => {
                                             ui.colored_label(theme::OK_GREEN, "Connected")
                                         }
+                                        ConnectionState::Streaming if self.stream_paused => {
+                                            ui.colored_label(log_colors::WARNING_LIGHT, "Paused")
+                                        }
                                         ConnectionState::Streaming => {
                                             ui.colored_label(theme::OK_GREEN, "Streaming")
                                         }
@@ -141,6 +253,17 @@ impl ConnectionsTab {
                                             log_colors::WARNING_LIGHT,
                                             "Disconnecting",
                                         ),
+                                    };
+                                    if !data.rejected_decoder_options.is_empty() {
+                                        ui.colored_label(
+                                            log_colors::WARNING_LIGHT,
+                                            "⚠ Incompatible decoder options",
+                                        )
+                                        .on_hover_text(format!(
+                                            "The decoder of this client does not start with: {}. \
+                                            Remove them from the MediaCodec extra options",
+                                            data.rejected_decoder_options.join(", ")
+                                        ));
                                     }
                                 });
                                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
@@ -159,20 +282,129 @@ impl ConnectionsTab {
                                                 .iter()
                                                 .map(|addr| addr.to_string())
                                                 .collect::<Vec<String>>(),
+                                            time_limit_min: data
+                                                .session_time_limit_min
+                                                .map(|limit| limit.to_string())
+                                                .unwrap_or_default(),
+                                            max_bitrate_mbps: data
+                                                .max_bitrate_mbps
+                                                .map(|bitrate| bitrate.to_string())
+                                                .unwrap_or_default(),
                                         });
                                     }
+                                    if let Some(refresh_rate) =
+                                        data.calibration.preferred_refresh_rate
+                                    {
+                                        if ui
+                                            .button(format!("Reset {refresh_rate}Hz"))
+                                            .on_hover_text(
+                                                "Use the preferred FPS setting again instead of the refresh rate last chosen on the headset",
+                                            )
+                                            .clicked()
+                                        {
+                                            requests.push(ServerRequest::UpdateClientList {
+                                                hostname: hostname.clone(),
+                                                action: ClientListAction::SetCalibration(
+                                                    ClientCalibration {
+                                                        preferred_refresh_rate: None,
+                                                        ..data.calibration.clone()
+                                                    },
+                                                ),
+                                            });
+                                        }
+                                    }
+                                    if self.session_time_limit_enabled
+                                        && data.connection_state == ConnectionState::Disconnected
+                                        && ui
+                                            .button("Reset timer")
+                                            .on_hover_text(
+                                                "Let the client connect again after its session is over",
+                                            )
+                                            .clicked()
+                                    {
+                                        requests.push(ServerRequest::ResetSessionTimer(
+                                            hostname.clone(),
+                                        ));
+                                    }
+                                    if data.connection_state == ConnectionState::Streaming {
+                                        if self.stream_paused {
+                                            if ui.button("Resume").clicked() {
+                                                requests.push(ServerRequest::ResumeStream);
+                                            }
+                                        } else if ui.button("Pause").clicked() {
+                                            requests.push(ServerRequest::PauseStream);
+                                        }
+
+                                        if ui
+                                            .button("Calibrate floor")
+                                            .on_hover_text(
+                                                "Place the left controller flat on the floor first",
+                                            )
+                                            .clicked()
+                                        {
+                                            requests.push(ServerRequest::CalibrateFloorHeight);
+                                        }
+                                        if ui
+                                            .small_button("⏶")
+                                            .on_hover_text("Raise the tracked devices by 1 cm")
+                                            .clicked()
+                                        {
+                                            requests.push(ServerRequest::NudgeFloorHeight(
+                                                FLOOR_NUDGE_STEP,
+                                            ));
+                                        }
+                                        if ui
+                                            .small_button("⏷")
+                                            .on_hover_text("Lower the tracked devices by 1 cm")
+                                            .clicked()
+                                        {
+                                            requests.push(ServerRequest::NudgeFloorHeight(
+                                                -FLOOR_NUDGE_STEP,
+                                            ));
+                                        }
+                                        ui.label(format!(
+                                            "Floor offset: {:.0} cm",
+                                            data.calibration.floor_height_offset * 100.0
+                                        ));
+                                        if ui
+                                            .selectable_label(self.seated_mode, "Seated mode")
+                                            .on_hover_text(
+                                                "Raise the view by the seated mode offset set in the headset settings, instead of using the floor calibration",
+                                            )
+                                            .clicked()
+                                        {
+                                            requests.push(ServerRequest::ToggleSeatedMode);
+                                        }
+                                    }
                                 });
                                 ui.end_row();
                             }
                         });
 
-                        if ui.button("Add client manually").clicked() {
-                            self.edit_popup_state = Some(EditPopupState {
-                                hostname: "XXXX.client.alvr".into(),
-                                new_client: true,
-                                ips: Vec::new(),
-                            });
-                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Add client manually").clicked() {
+                                self.edit_popup_state = Some(EditPopupState {
+                                    hostname: String::new(),
+                                    new_client: true,
+                                    ips: vec!["192.168.X.X".into()],
+                                    time_limit_min: String::new(),
+                                    max_bitrate_mbps: String::new(),
+                                });
+                            }
+                            if ui.button("Pair with QR code").clicked() {
+                                requests.push(ServerRequest::CreatePairingToken);
+                            }
+                            if self.colocation_enabled
+                                && ui
+                                    .button("Align playspace")
+                                    .on_hover_text(
+                                        "Place the left controller on the shared marker first",
+                                    )
+                                    .clicked()
+                            {
+                                requests.push(ServerRequest::CaptureColocationMarker);
+                            }
+                        });
                     });
             }
         });
@@ -187,7 +419,8 @@ impl ConnectionsTab {
                         ui[0].label("Hostname:");
                         ui[1].add_enabled(
                             state.new_client,
-                            TextEdit::singleline(&mut state.hostname),
+                            TextEdit::singleline(&mut state.hostname)
+                                .hint_text("Optional, defaults to the IP"),
                         );
                         ui[0].label("IP Addresses:");
                         for address in &mut state.ips {
@@ -196,6 +429,18 @@ impl ConnectionsTab {
                         if ui[1].button("Add new").clicked() {
                             state.ips.push("192.168.X.X".to_string());
                         }
+                        ui[0].label("Max bitrate (Mbps):");
+                        ui[1].add(
+                            TextEdit::singleline(&mut state.max_bitrate_mbps)
+                                .hint_text("Unlimited"),
+                        );
+                        if self.session_time_limit_enabled {
+                            ui[0].label("Session time limit (min):");
+                            ui[1].add(
+                                TextEdit::singleline(&mut state.time_limit_min)
+                                    .hint_text("Default"),
+                            );
+                        }
                     });
                     ui.columns(2, |ui| {
                         if ui[0].button("Cancel").clicked() {
@@ -203,12 +448,25 @@ impl ConnectionsTab {
                         }
 
                         if ui[1].button("Save").clicked() {
-                            let manual_ips =
-                                state.ips.iter().filter_map(|s| s.parse().ok()).collect();
+                            let manual_ips = state
+                                .ips
+                                .iter()
+                                .filter_map(|s| s.trim().parse().ok())
+                                .collect::<Vec<IpAddr>>();
+
+                            // The streamer initiates the connection to manual IPs, so the hostname
+                            // is only used as the name of the entry
+                            if state.hostname.trim().is_empty() {
+                                let Some(ip) = manual_ips.first() else {
+                                    self.edit_popup_state = Some(state);
+                                    return;
+                                };
+                                state.hostname = ip.to_string();
+                            }
 
                             if state.new_client {
                                 requests.push(ServerRequest::UpdateClientList {
-                                    hostname: state.hostname,
+                                    hostname: state.hostname.clone(),
                                     action: ClientListAction::AddIfMissing {
                                         trusted: true,
                                         manual_ips,
@@ -216,10 +474,26 @@ impl ConnectionsTab {
                                 });
                             } else {
                                 requests.push(ServerRequest::UpdateClientList {
-                                    hostname: state.hostname,
+                                    hostname: state.hostname.clone(),
                                     action: ClientListAction::SetManualIps(manual_ips),
                                 });
                             }
+
+                            requests.push(ServerRequest::UpdateClientList {
+                                hostname: state.hostname.clone(),
+                                action: ClientListAction::SetMaxBitrate(
+                                    state.max_bitrate_mbps.trim().parse().ok(),
+                                ),
+                            });
+
+                            if self.session_time_limit_enabled {
+                                requests.push(ServerRequest::UpdateClientList {
+                                    hostname: state.hostname,
+                                    action: ClientListAction::SetSessionTimeLimit(
+                                        state.time_limit_min.trim().parse().ok(),
+                                    ),
+                                });
+                            }
                         } else {
                             self.edit_popup_state = Some(state);
                         }
@@ -227,6 +501,30 @@ impl ConnectionsTab {
                 });
         }
 
+        if let Some(uri) = &self.pairing_uri {
+            let mut close = false;
+
+            Window::new("Pair with QR code")
+                .anchor(Align2::CENTER_CENTER, (0.0, 0.0))
+                .resizable(false)
+                .collapsible(false)
+                .show(ui.ctx(), |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.label(
+                            "Scan this code from the headset. It can be used only once, within 5 minutes.",
+                        );
+                        qr_code_ui(ui, uri);
+                        ui.label(uri);
+                        close = ui.button("Close").clicked();
+                    });
+                });
+
+            if close {
+                self.pairing_uri = None;
+                requests.push(ServerRequest::ClearPairingToken);
+            }
+        }
+
         requests
     }
 }
@@ -1,26 +1,289 @@
+use super::{NestingInfo, SettingControl};
+use alvr_common::{LEFT_HAND_ID, RIGHT_HAND_ID};
+use alvr_events::{MicrophoneLevel, MicrophoneLoopbackPhase};
 use alvr_packets::ServerRequest;
-use eframe::egui::Ui;
+use alvr_session::{settings_schema::SchemaNode, SessionSettings, Settings};
+use eframe::egui::{CollapsingHeader, Grid, ProgressBar, Slider, TextEdit, Ui};
+use serde_json as json;
+use std::time::Duration;
 
-pub fn debug_tab_ui(ui: &mut Ui) -> Option<ServerRequest> {
-    let mut request = None;
+// Lower bound of the microphone level meter
+const METER_MIN_DBFS: f32 = -60.0;
 
-    ui.columns(4, |ui| {
-        if ui[0].button("Capture frame").clicked() {
-            request = Some(ServerRequest::CaptureFrame);
-        }
+fn find_entry(node: SchemaNode, name: &str) -> Option<SchemaNode> {
+    if let SchemaNode::Section(entries) = node {
+        entries
+            .into_iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.content)
+    } else {
+        None
+    }
+}
 
-        if ui[1].button("Insert IDR").clicked() {
-            request = Some(ServerRequest::InsertIdr);
-        }
+struct HapticsTestPulse {
+    left_hand: bool,
+    duration_ms: u64,
+    frequency: f32,
+    amplitude: f32,
+}
 
-        if ui[2].button("Start recording").clicked() {
-            request = Some(ServerRequest::StartRecording);
+pub struct DebugTab {
+    network_simulation_control: SettingControl,
+    session_settings_json: Option<json::Value>,
+    tracking_replay_path: String,
+    haptics_test_pulse: HapticsTestPulse,
+    microphone_test_active: bool,
+    microphone_level: Option<MicrophoneLevel>,
+    microphone_loopback: Option<MicrophoneLoopbackPhase>,
+}
+
+impl DebugTab {
+    pub fn new() -> Self {
+        let schema = Settings::schema(alvr_session::session_settings_default());
+
+        // The network simulation settings are hidden from the settings tab
+        let network_simulation_control = find_entry(schema, "connection")
+            .and_then(|node| find_entry(node, "network_simulation"))
+            .map(|node| {
+                SettingControl::new(
+                    NestingInfo {
+                        path: vec![
+                            "session_settings".into(),
+                            "connection".into(),
+                            "network_simulation".into(),
+                        ],
+                        indentation_level: 0,
+                    },
+                    node,
+                )
+            })
+            .unwrap_or(SettingControl::None);
+
+        Self {
+            network_simulation_control,
+            session_settings_json: None,
+            tracking_replay_path: String::new(),
+            haptics_test_pulse: HapticsTestPulse {
+                left_hand: true,
+                duration_ms: 200,
+                frequency: 160.0,
+                amplitude: 0.5,
+            },
+            microphone_test_active: false,
+            microphone_level: None,
+            microphone_loopback: None,
         }
+    }
+
+    pub fn update_session(&mut self, session_settings: &SessionSettings) {
+        self.session_settings_json = Some(json::to_value(session_settings).unwrap());
+    }
+
+    pub fn update_microphone_level(&mut self, level: MicrophoneLevel) {
+        self.microphone_level = Some(level);
+    }
+
+    pub fn update_microphone_loopback(&mut self, phase: Option<MicrophoneLoopbackPhase>) {
+        self.microphone_loopback = phase;
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) -> Vec<ServerRequest> {
+        let mut requests = vec![];
+
+        ui.columns(4, |ui| {
+            if ui[0].button("Capture frame").clicked() {
+                requests.push(ServerRequest::CaptureFrame);
+            }
+
+            if ui[1].button("Insert IDR").clicked() {
+                requests.push(ServerRequest::InsertIdr);
+            }
+
+            if ui[2].button("Start recording").clicked() {
+                requests.push(ServerRequest::StartRecording);
+            }
+
+            if ui[3].button("Stop recording").clicked() {
+                requests.push(ServerRequest::StopRecording);
+            }
+        });
+
+        ui.add_space(10.0);
+
+        CollapsingHeader::new("Tracking recording")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.label("Recordings are saved in the log folder");
+
+                ui.horizontal(|ui| {
+                    if ui.button("Start recording").clicked() {
+                        requests.push(ServerRequest::StartTrackingRecording);
+                    }
+                    if ui.button("Stop recording").clicked() {
+                        requests.push(ServerRequest::StopTrackingRecording);
+                    }
+                });
+
+                ui.label("Replay a recording into SteamVR while no client is connected");
+                ui.horizontal(|ui| {
+                    ui.add(
+                        TextEdit::singleline(&mut self.tracking_replay_path)
+                            .hint_text("Recording path"),
+                    );
+                    if ui.button("Replay").clicked() && !self.tracking_replay_path.is_empty() {
+                        requests.push(ServerRequest::ReplayTracking(
+                            self.tracking_replay_path.clone().into(),
+                        ));
+                    }
+                    if ui.button("Stop").clicked() {
+                        requests.push(ServerRequest::StopTrackingReplay);
+                    }
+                });
+            });
+
+        CollapsingHeader::new("Haptics test")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.label(
+                    "Send a pulse to a controller of the streaming client. The controller haptics \
+                    settings are applied",
+                );
+
+                let pulse = &mut self.haptics_test_pulse;
+                Grid::new("haptics_test_grid")
+                    .striped(true)
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Device");
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut pulse.left_hand, true, "Left hand");
+                            ui.selectable_value(&mut pulse.left_hand, false, "Right hand");
+                        });
+                        ui.end_row();
+
+                        ui.label("Duration");
+                        ui.add(Slider::new(&mut pulse.duration_ms, 10..=2000).suffix(" ms"));
+                        ui.end_row();
+
+                        ui.label("Frequency");
+                        ui.add(Slider::new(&mut pulse.frequency, 0.0..=320.0).suffix(" Hz"));
+                        ui.end_row();
+
+                        ui.label("Amplitude");
+                        ui.add(Slider::new(&mut pulse.amplitude, 0.0..=1.0));
+                        ui.end_row();
+                    });
+
+                if ui.button("Send pulse").clicked() {
+                    requests.push(ServerRequest::TestHaptics {
+                        device_id: if pulse.left_hand {
+                            *LEFT_HAND_ID
+                        } else {
+                            *RIGHT_HAND_ID
+                        },
+                        duration: Duration::from_millis(pulse.duration_ms),
+                        frequency: pulse.frequency,
+                        amplitude: pulse.amplitude,
+                    });
+                }
+            });
+
+        CollapsingHeader::new("Microphone test")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.label(
+                    "Show the level of the microphone audio received from the streaming client. \
+                    The loopback records a few seconds and plays them on the speakers of this PC",
+                );
+
+                ui.horizontal(|ui| {
+                    let label = if self.microphone_test_active {
+                        "Stop test"
+                    } else {
+                        "Start test"
+                    };
+                    if ui.button(label).clicked() {
+                        self.microphone_test_active = !self.microphone_test_active;
+                        self.microphone_level = None;
+                        requests.push(ServerRequest::SetMicrophoneTest(
+                            self.microphone_test_active,
+                        ));
+                    }
+
+                    if ui
+                        .add_enabled(
+                            self.microphone_test_active && self.microphone_loopback.is_none(),
+                            eframe::egui::Button::new("Loopback"),
+                        )
+                        .clicked()
+                    {
+                        requests.push(ServerRequest::StartMicrophoneLoopback);
+                    }
+
+                    match self.microphone_loopback {
+                        Some(MicrophoneLoopbackPhase::Recording) => {
+                            ui.spinner();
+                            ui.label("Recording, speak into the headset");
+                        }
+                        Some(MicrophoneLoopbackPhase::Playing) => {
+                            ui.spinner();
+                            ui.label("Playing");
+                        }
+                        None => (),
+                    }
+                });
+
+                if self.microphone_test_active {
+                    if let Some(level) = self.microphone_level {
+                        Grid::new("microphone_level_grid")
+                            .num_columns(2)
+                            .show(ui, |ui| {
+                                for (label, dbfs) in
+                                    [("RMS", level.rms_dbfs), ("Peak", level.peak_dbfs)]
+                                {
+                                    ui.label(label);
+                                    ui.add(
+                                        ProgressBar::new(
+                                            (1.0 - dbfs / METER_MIN_DBFS).clamp(0.0, 1.0),
+                                        )
+                                        .desired_width(200.0)
+                                        .text(format!("{dbfs:.0} dBFS")),
+                                    );
+                                    ui.end_row();
+                                }
+                            });
+                    } else {
+                        ui.label("No microphone audio received");
+                    }
+                }
+            });
+
+        if let Some(session_settings_json) = &mut self.session_settings_json {
+            CollapsingHeader::new("Network simulation")
+                .default_open(false)
+                .show(ui, |ui| {
+                    ui.label("Applied at the next connection. Do not use for normal streaming!");
+
+                    Grid::new("network_simulation_grid")
+                        .striped(true)
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label("Enabled");
+
+                            if let Some(pair) = self.network_simulation_control.ui(
+                                ui,
+                                &mut session_settings_json["connection"]["network_simulation"],
+                                true,
+                            ) {
+                                requests.push(ServerRequest::SetValues(vec![pair]));
+                            }
 
-        if ui[3].button("Stop recording").clicked() {
-            request = Some(ServerRequest::StopRecording);
+                            ui.end_row();
+                        });
+                });
         }
-    });
 
-    request
+        requests
+    }
 }
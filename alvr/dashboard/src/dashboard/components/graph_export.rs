@@ -0,0 +1,300 @@
+// Export of the statistics graphs for bug reports. The shapes of every graph are recorded while
+// drawing and converted to SVG. PNG exports are cropped from a screenshot of the window, so they
+// contain only the graphs currently visible.
+
+use eframe::egui::{Align, Align2, Color32, FontId, Painter, Pos2, Rect, Rounding, Shape, Stroke};
+use std::{cell::RefCell, fmt::Write};
+
+const SVG_MARGIN: f32 = 10.0;
+const SVG_TITLE_HEIGHT: f32 = 30.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GraphExportFormat {
+    Svg,
+    Png,
+}
+
+pub enum GraphShape {
+    Rect {
+        rect: Rect,
+        color: Color32,
+    },
+    Line {
+        points: Vec<Pos2>,
+        color: Color32,
+    },
+    Text {
+        pos: Pos2,
+        anchor: Align2,
+        text: String,
+        size: f32,
+        color: Color32,
+    },
+}
+
+// Paints on the dashboard and, during an export, records the shapes too
+pub struct GraphPainter {
+    painter: Painter,
+    recorded_shapes: Option<RefCell<Vec<GraphShape>>>,
+}
+
+impl GraphPainter {
+    pub fn new(painter: Painter, record: bool) -> Self {
+        Self {
+            painter,
+            recorded_shapes: record.then(|| RefCell::new(vec![])),
+        }
+    }
+
+    fn record(&self, shape: impl FnOnce() -> GraphShape) {
+        if let Some(shapes) = &self.recorded_shapes {
+            shapes.borrow_mut().push(shape());
+        }
+    }
+
+    pub fn rect_filled(&self, rect: Rect, rounding: Rounding, color: Color32) {
+        self.record(|| GraphShape::Rect { rect, color });
+        self.painter.rect_filled(rect, rounding, color);
+    }
+
+    pub fn line(&self, points: Vec<Pos2>, color: Color32) {
+        self.record(|| GraphShape::Line {
+            points: points.clone(),
+            color,
+        });
+        self.painter
+            .add(Shape::line(points, Stroke::new(1.0, color)));
+    }
+
+    pub fn text(&self, pos: Pos2, anchor: Align2, text: String, font_id: FontId, color: Color32) {
+        self.record(|| GraphShape::Text {
+            pos,
+            anchor,
+            text: text.clone(),
+            size: font_id.size,
+            color,
+        });
+        self.painter.text(pos, anchor, text, font_id, color);
+    }
+
+    pub fn into_recorded_shapes(self) -> Vec<GraphShape> {
+        self.recorded_shapes
+            .map(RefCell::into_inner)
+            .unwrap_or_default()
+    }
+}
+
+pub struct RecordedGraph {
+    pub title: String,
+    // In screen coordinates, like the shapes
+    pub rect: Rect,
+    pub shapes: Vec<GraphShape>,
+}
+
+// attribute is "fill" or "stroke"
+fn svg_paint(attribute: &str, color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+
+    format!(
+        "{attribute}=\"rgb({r},{g},{b})\" {attribute}-opacity=\"{:.3}\"",
+        a as f32 / 255.0
+    )
+}
+
+fn svg_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn svg_text(out: &mut String, pos: Pos2, anchor: Align2, text: &str, size: f32, color: Color32) {
+    let text_anchor = match anchor.x() {
+        Align::Min => "start",
+        Align::Center => "middle",
+        Align::Max => "end",
+    };
+    let baseline = match anchor.y() {
+        Align::Min => "hanging",
+        Align::Center => "middle",
+        Align::Max => "text-after-edge",
+    };
+
+    writeln!(
+        out,
+        "<text x=\"{:.1}\" y=\"{:.1}\" font-family=\"sans-serif\" font-size=\"{size:.0}\" \
+        text-anchor=\"{text_anchor}\" dominant-baseline=\"{baseline}\" {}>{}</text>",
+        pos.x,
+        pos.y,
+        svg_paint("fill", color),
+        svg_escape(text)
+    )
+    .ok();
+}
+
+pub fn to_svg(graphs: &[RecordedGraph], caption: &str, background: Color32) -> String {
+    let width = graphs
+        .iter()
+        .map(|graph| graph.rect.width())
+        .fold(0.0, f32::max)
+        + 2.0 * SVG_MARGIN;
+
+    let mut body = String::new();
+    let mut y = SVG_MARGIN;
+
+    svg_text(
+        &mut body,
+        Pos2::new(SVG_MARGIN, y),
+        Align2::LEFT_TOP,
+        caption,
+        14.0,
+        Color32::GRAY,
+    );
+    y += SVG_TITLE_HEIGHT;
+
+    for (index, graph) in graphs.iter().enumerate() {
+        svg_text(
+            &mut body,
+            Pos2::new(SVG_MARGIN, y),
+            Align2::LEFT_TOP,
+            &graph.title,
+            20.0,
+            Color32::WHITE,
+        );
+        y += SVG_TITLE_HEIGHT;
+
+        let offset = Pos2::new(SVG_MARGIN, y) - graph.rect.min;
+        let canvas = graph.rect.translate(offset);
+
+        writeln!(
+            body,
+            "<clipPath id=\"graph{index}\"><rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" \
+            height=\"{:.1}\"/></clipPath>\n<g clip-path=\"url(#graph{index})\">",
+            canvas.min.x,
+            canvas.min.y,
+            canvas.width(),
+            canvas.height()
+        )
+        .ok();
+
+        for shape in &graph.shapes {
+            match shape {
+                GraphShape::Rect { rect, color } => {
+                    let rect = Rect::from_two_pos(rect.min, rect.max).translate(offset);
+                    writeln!(
+                        body,
+                        "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" {}/>",
+                        rect.min.x,
+                        rect.min.y,
+                        rect.width(),
+                        rect.height(),
+                        svg_paint("fill", *color)
+                    )
+                    .ok();
+                }
+                GraphShape::Line { points, color } => {
+                    if points.len() < 2 {
+                        continue;
+                    }
+
+                    let points = points
+                        .iter()
+                        .map(|point| {
+                            let point = *point + offset;
+                            format!("{:.1},{:.1}", point.x, point.y)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    writeln!(
+                        body,
+                        "<polyline points=\"{points}\" fill=\"none\" stroke-width=\"1\" {}/>",
+                        svg_paint("stroke", *color)
+                    )
+                    .ok();
+                }
+                GraphShape::Text {
+                    pos,
+                    anchor,
+                    text,
+                    size,
+                    color,
+                } => svg_text(&mut body, *pos + offset, *anchor, text, *size, *color),
+            }
+        }
+
+        writeln!(body, "</g>").ok();
+
+        y += graph.rect.height() + SVG_MARGIN;
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width:.0}\" height=\"{y:.0}\" \
+        viewBox=\"0 0 {width:.0} {y:.0}\">\n<rect width=\"100%\" height=\"100%\" {}/>\n\
+        {body}</svg>\n",
+        svg_paint("fill", background)
+    )
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file {
+    use alvr_common::anyhow::Result;
+    use eframe::egui::{ColorImage, Rect};
+    use std::{
+        env,
+        fs::{self, File},
+        io::BufWriter,
+        path::PathBuf,
+    };
+
+    fn export_path(extension: &str) -> PathBuf {
+        let log_dir =
+            alvr_filesystem::filesystem_layout_from_dashboard_exe(&env::current_exe().unwrap())
+                .log_dir;
+
+        log_dir.join(format!(
+            "graphs_{}.{extension}",
+            chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")
+        ))
+    }
+
+    pub fn save_svg(svg: &str) -> Result<PathBuf> {
+        let path = export_path("svg");
+        fs::write(&path, svg)?;
+
+        Ok(path)
+    }
+
+    // The region is in points, the screenshot in physical pixels
+    pub fn save_png(
+        screenshot: &ColorImage,
+        region: Rect,
+        pixels_per_point: f32,
+    ) -> Result<PathBuf> {
+        let [image_width, image_height] = screenshot.size;
+        let min_x = ((region.min.x * pixels_per_point) as usize).min(image_width);
+        let min_y = ((region.min.y * pixels_per_point) as usize).min(image_height);
+        let max_x = ((region.max.x * pixels_per_point) as usize).clamp(min_x, image_width);
+        let max_y = ((region.max.y * pixels_per_point) as usize).clamp(min_y, image_height);
+
+        let mut data = Vec::with_capacity((max_x - min_x) * (max_y - min_y) * 4);
+        for y in min_y..max_y {
+            for pixel in &screenshot.pixels[y * image_width + min_x..y * image_width + max_x] {
+                data.extend_from_slice(&pixel.to_srgba_unmultiplied());
+            }
+        }
+
+        let path = export_path("png");
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(&path)?),
+            (max_x - min_x) as u32,
+            (max_y - min_y) as u32,
+        );
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&data)?;
+
+        Ok(path)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use file::*;
@@ -0,0 +1,357 @@
+use alvr_events::{AbComparisonReport, EncoderBenchmarkReport, StreamSummary};
+use alvr_gui_common::theme;
+use alvr_packets::ServerRequest;
+use chrono::{Local, NaiveDateTime, TimeZone};
+use eframe::egui::{CollapsingHeader, Frame, Grid, ProgressBar, RichText, ScrollArea, Ui};
+
+fn format_start_time(unix_secs: u64) -> String {
+    NaiveDateTime::from_timestamp_opt(unix_secs as i64, 0)
+        .map(|time| {
+            Local
+                .from_utc_datetime(&time)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+fn summary_ui(ui: &mut Ui, index: usize, summary: &StreamSummary) {
+    Grid::new(("stream_info", index))
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Stream");
+            ui.label(format!(
+                "{}x{} @ {:.0} Hz, {}",
+                summary.view_resolution.0, summary.view_resolution.1, summary.fps, summary.codec
+            ));
+            ui.end_row();
+
+            ui.label("Disconnect reason");
+            ui.label(&summary.disconnect_reason);
+            ui.end_row();
+
+            ui.label("Packets lost");
+            ui.label(format!(
+                "{} / {}",
+                summary.packets_lost_total, summary.video_packets_total
+            ));
+            ui.end_row();
+
+            ui.label("Dropped frames");
+            ui.label(summary.reprojected_frames_total.to_string());
+            ui.end_row();
+
+            ui.label("Average bitrate");
+            ui.label(format!("{:.1} Mbps", summary.average_bitrate_mbps));
+            ui.end_row();
+
+            if !summary.experiments.is_empty() {
+                ui.label("Experiments");
+                ui.label(summary.experiments.join(", "));
+                ui.end_row();
+            }
+        });
+
+    ui.add_space(5.0);
+    ui.label(RichText::new("Latency").strong());
+    Grid::new(("stream_latency", index))
+        .striped(true)
+        .num_columns(3)
+        .show(ui, |ui| {
+            ui.label("Stage");
+            ui.label("Average");
+            ui.label("99th percentile");
+            ui.end_row();
+
+            for stage in &summary.latency_stages {
+                ui.label(&stage.name);
+                ui.label(format!("{:.2} ms", stage.average_ms));
+                ui.label(format!("{:.2} ms", stage.p99_ms));
+                ui.end_row();
+            }
+        });
+
+    // Empty for sessions recorded before the traffic was tracked
+    if !summary.traffic_mbytes.is_empty() {
+        ui.add_space(5.0);
+        ui.label(RichText::new("Traffic").strong());
+        Grid::new(("stream_traffic", index))
+            .striped(true)
+            .num_columns(3)
+            .show(ui, |ui| {
+                ui.label("Stream");
+                ui.label("Sent");
+                ui.label("Received");
+                ui.end_row();
+
+                for (stream, [sent_mbytes, received_mbytes]) in &summary.traffic_mbytes {
+                    ui.label(stream.label());
+                    ui.label(format!("{sent_mbytes:.1} MB"));
+                    ui.label(format!("{received_mbytes:.1} MB"));
+                    ui.end_row();
+                }
+            });
+    }
+
+    let intervals_count = summary.bitrate_histogram.iter().sum::<u32>();
+    if intervals_count > 0 {
+        ui.add_space(5.0);
+        ui.label(RichText::new("Bitrate distribution").strong());
+        Grid::new(("stream_bitrate", index))
+            .num_columns(2)
+            .show(ui, |ui| {
+                for (bucket, count) in summary.bitrate_histogram.iter().enumerate() {
+                    if *count == 0 {
+                        continue;
+                    }
+
+                    let low = bucket as f32 * summary.bitrate_histogram_bucket_mbps;
+                    let high = low + summary.bitrate_histogram_bucket_mbps;
+                    let fraction = *count as f32 / intervals_count as f32;
+
+                    ui.label(format!("{low:.0}-{high:.0} Mbps"));
+                    ui.add(
+                        ProgressBar::new(fraction)
+                            .desired_width(200.0)
+                            .text(format!("{:.0}%", fraction * 100.0)),
+                    );
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+fn ab_comparison_ui(ui: &mut Ui, index: usize, report: &AbComparisonReport) {
+    if !report.completed {
+        ui.label("Stopped before completion");
+    }
+
+    Grid::new(("ab_comparison", index))
+        .striped(true)
+        .num_columns(report.results.len() + 1)
+        .show(ui, |ui| {
+            let mut row = |label: &str, value: &dyn Fn(usize) -> String| {
+                ui.label(label);
+                for variant_index in 0..report.results.len() {
+                    ui.label(value(variant_index));
+                }
+                ui.end_row();
+            };
+
+            row("Variant", &|i| {
+                let result = &report.results[i];
+                if result.codec.is_empty() {
+                    format!("{} ({} Mbps)", result.name, result.bitrate_mbps)
+                } else {
+                    format!(
+                        "{} ({} Mbps, {})",
+                        result.name, result.bitrate_mbps, result.codec
+                    )
+                }
+            });
+            row("Frames", &|i| report.results[i].frames.to_string());
+            row("Total latency", &|i| {
+                format!("{:.2} ms", report.results[i].average_total_latency_ms)
+            });
+            row("Encoder latency", &|i| {
+                format!("{:.2} ms", report.results[i].average_encoder_latency_ms)
+            });
+            row("Network latency", &|i| {
+                format!("{:.2} ms", report.results[i].average_network_latency_ms)
+            });
+            row("Decoder latency", &|i| {
+                format!("{:.2} ms", report.results[i].average_decoder_latency_ms)
+            });
+            row("Actual bitrate", &|i| {
+                format!("{:.1} Mbps", report.results[i].average_actual_bitrate_mbps)
+            });
+            row("Encoder QP", &|i| {
+                report.results[i]
+                    .average_encoder_qp
+                    .map(|qp| format!("{qp:.1}"))
+                    .unwrap_or_else(|| "-".into())
+            });
+            row("Dropped frames", &|i| {
+                report.results[i].frame_drops.to_string()
+            });
+            row("Screenshots", &|i| report.results[i].screenshots.join("\n"));
+        });
+}
+
+fn encoder_benchmark_ui(ui: &mut Ui, report: &EncoderBenchmarkReport) {
+    if !report.completed {
+        ui.label("Interrupted before completion");
+    }
+
+    Grid::new("encoder_benchmark")
+        .striped(true)
+        .num_columns(5)
+        .show(ui, |ui| {
+            ui.label("Case");
+            ui.label("Throughput");
+            ui.label("Average latency");
+            ui.label("Max latency");
+            ui.label("Average frame size");
+            ui.end_row();
+
+            for result in &report.results {
+                let case = format!(
+                    "{}x{} at {} Mbps",
+                    result.width, result.height, result.bitrate_mbps
+                );
+                if result.current_settings {
+                    ui.label(RichText::new(format!("{case} (current settings)")).strong());
+                } else {
+                    ui.label(case);
+                }
+                ui.label(format!("{:.0} FPS", result.frames_per_sec));
+                ui.label(format!("{:.2} ms", result.average_latency_ms));
+                ui.label(format!("{:.2} ms", result.max_latency_ms));
+                ui.label(format!("{:.0} KB", result.average_frame_size_kb));
+                ui.end_row();
+            }
+        });
+}
+
+pub struct HistoryTab {
+    history: Vec<StreamSummary>,
+    ab_comparison_progress: Option<String>,
+    ab_comparison_reports: Vec<AbComparisonReport>,
+    encoder_benchmark_progress: Option<String>,
+    encoder_benchmark_report: Option<EncoderBenchmarkReport>,
+    requested: bool,
+}
+
+impl HistoryTab {
+    pub fn new() -> Self {
+        Self {
+            history: vec![],
+            ab_comparison_progress: None,
+            ab_comparison_reports: vec![],
+            encoder_benchmark_progress: None,
+            encoder_benchmark_report: None,
+            requested: false,
+        }
+    }
+
+    pub fn update_history(&mut self, history: Vec<StreamSummary>) {
+        self.history = history;
+    }
+
+    pub fn update_ab_comparison_progress(&mut self, progress: Option<String>) {
+        self.ab_comparison_progress = progress;
+    }
+
+    pub fn update_ab_comparison_reports(&mut self, reports: Vec<AbComparisonReport>) {
+        self.ab_comparison_reports = reports;
+    }
+
+    pub fn update_encoder_benchmark_progress(&mut self, progress: Option<String>) {
+        self.encoder_benchmark_progress = progress;
+    }
+
+    pub fn update_encoder_benchmark_report(&mut self, report: Option<EncoderBenchmarkReport>) {
+        self.encoder_benchmark_report = report;
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<ServerRequest> {
+        let mut request = None;
+
+        if !self.requested {
+            request = Some(ServerRequest::GetStreamHistory);
+            self.requested = true;
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Refresh").clicked() {
+                request = Some(ServerRequest::GetStreamHistory);
+            }
+
+            if let Some(progress) = &self.ab_comparison_progress {
+                if ui.button("Stop A/B comparison").clicked() {
+                    request = Some(ServerRequest::StopAbComparison);
+                }
+                ui.label(progress);
+            } else if ui.button("Start A/B comparison").clicked() {
+                request = Some(ServerRequest::StartAbComparison);
+            }
+
+            // Hidden unless Shift is held, the benchmark is meant for diagnostics
+            if let Some(progress) = &self.encoder_benchmark_progress {
+                ui.label(format!("Encoder benchmark: {progress}"));
+            } else if ui.input(|input| input.modifiers.shift)
+                && ui
+                    .button("Run encoder benchmark")
+                    .on_hover_text("Disconnect the client first")
+                    .clicked()
+            {
+                request = Some(ServerRequest::StartEncoderBenchmark);
+            }
+        });
+
+        ScrollArea::vertical().show(ui, |ui| {
+            if !self.ab_comparison_reports.is_empty() {
+                ui.label(RichText::new("A/B comparisons").strong());
+
+                for (index, report) in self.ab_comparison_reports.iter().enumerate().rev() {
+                    Frame::group(ui.style())
+                        .fill(theme::SECTION_BG)
+                        .show(ui, |ui| {
+                            CollapsingHeader::new(format!(
+                                "{} - {}, {} x {} s",
+                                format_start_time(report.start_unix_secs),
+                                report.codec,
+                                report.rounds,
+                                report.phase_duration_s
+                            ))
+                            .id_source(("ab_comparison_report", index))
+                            .default_open(index + 1 == self.ab_comparison_reports.len())
+                            .show(ui, |ui| ab_comparison_ui(ui, index, report));
+                        });
+                }
+
+                ui.add_space(10.0);
+                ui.label(RichText::new("Streaming sessions").strong());
+            }
+
+            if let Some(report) = &self.encoder_benchmark_report {
+                Frame::group(ui.style())
+                    .fill(theme::SECTION_BG)
+                    .show(ui, |ui| {
+                        CollapsingHeader::new(format!(
+                            "Encoder benchmark - {}, {}",
+                            format_start_time(report.start_unix_secs),
+                            report.codec
+                        ))
+                        .id_source("encoder_benchmark_report")
+                        .show(ui, |ui| encoder_benchmark_ui(ui, report));
+                    });
+
+                ui.add_space(10.0);
+            }
+
+            if self.history.is_empty() {
+                ui.label("No streaming sessions recorded yet.");
+            }
+
+            // Most recent first
+            for (index, summary) in self.history.iter().enumerate().rev() {
+                Frame::group(ui.style())
+                    .fill(theme::SECTION_BG)
+                    .show(ui, |ui| {
+                        CollapsingHeader::new(format!(
+                            "{} - {} ({:.0} min)",
+                            format_start_time(summary.start_unix_secs),
+                            summary.client_hostname,
+                            summary.duration_s / 60.0
+                        ))
+                        .id_source(("stream_summary", index))
+                        .default_open(index + 1 == self.history.len())
+                        .show(ui, |ui| summary_ui(ui, index, summary));
+                    });
+            }
+        });
+
+        request
+    }
+}
@@ -0,0 +1,94 @@
+use alvr_gui_common::theme::log_colors;
+use alvr_packets::{JournalCategory, JournalEntry, JournalSource};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use eframe::{
+    egui::{Grid, ScrollArea, Ui},
+    epaint::Color32,
+};
+
+const MAX_ENTRIES: usize = 1000;
+
+fn format_time(entry: &JournalEntry) -> String {
+    NaiveDateTime::from_timestamp_opt(
+        entry.timestamp.as_secs() as i64,
+        entry.timestamp.subsec_nanos(),
+    )
+    .map(|time| {
+        Local
+            .from_utc_datetime(&time)
+            .format("%H:%M:%S%.3f")
+            .to_string()
+    })
+    .unwrap_or_default()
+}
+
+// Merged timeline of the server and client journals. Client entries can arrive late, so entries
+// are kept sorted by timestamp instead of by arrival.
+pub struct JournalTab {
+    entries: Vec<JournalEntry>,
+    show_server: bool,
+    show_client: bool,
+}
+
+impl JournalTab {
+    pub fn new() -> Self {
+        Self {
+            entries: vec![],
+            show_server: true,
+            show_client: true,
+        }
+    }
+
+    pub fn push_entry(&mut self, entry: JournalEntry) {
+        let index = self
+            .entries
+            .partition_point(|other| other.timestamp <= entry.timestamp);
+        self.entries.insert(index, entry);
+
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.show_server, "Streamer");
+            ui.checkbox(&mut self.show_client, "Client");
+            if ui.button("Clear").clicked() {
+                self.entries.clear();
+            }
+        });
+
+        ScrollArea::both()
+            .stick_to_bottom(true)
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                Grid::new("journal")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for entry in &self.entries {
+                            let source = match entry.source {
+                                JournalSource::Server if self.show_server => "Streamer",
+                                JournalSource::Client if self.show_client => "Client",
+                                _ => continue,
+                            };
+                            let (color, category) = match entry.category {
+                                JournalCategory::Connection => {
+                                    (log_colors::INFO_LIGHT, "Connection")
+                                }
+                                JournalCategory::Stream => (Color32::LIGHT_GRAY, "Stream"),
+                                JournalCategory::Error => (log_colors::ERROR_LIGHT, "Error"),
+                            };
+
+                            ui.colored_label(color, format_time(entry));
+                            ui.colored_label(color, source);
+                            ui.colored_label(color, category);
+                            ui.colored_label(color, &entry.message);
+
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
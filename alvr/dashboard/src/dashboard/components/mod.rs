@@ -1,7 +1,11 @@
 mod about;
 mod connections;
 mod debug;
+mod graph_export;
+mod history;
+mod journal;
 mod logs;
+mod network_health;
 mod notifications;
 mod settings;
 mod settings_controls;
@@ -14,7 +18,10 @@ mod installation;
 pub use about::*;
 pub use connections::*;
 pub use debug::*;
+pub use history::*;
+pub use journal::*;
 pub use logs::*;
+pub use network_health::*;
 pub use notifications::*;
 pub use settings::*;
 pub use settings_controls::*;
@@ -0,0 +1,241 @@
+use alvr_gui_common::theme::{self, log_colors};
+use alvr_packets::{ServerRequest, WifiNetwork, WifiScanReport};
+use eframe::egui::{CollapsingHeader, Frame, Grid, RichText, Ui};
+
+// 5 GHz channels that can be used with 20 MHz width and up
+const CANDIDATE_CHANNELS_5GHZ: [u32; 25] = [
+    36, 40, 44, 48, 52, 56, 60, 64, 100, 104, 108, 112, 116, 120, 124, 128, 132, 136, 140, 144,
+    149, 153, 157, 161, 165,
+];
+const WEAK_SIGNAL_DBM: i32 = -67;
+// Networks weaker than this don't interfere significantly
+const INTERFERENCE_THRESHOLD_DBM: i32 = -82;
+
+fn channel(frequency_mhz: u32) -> Option<u32> {
+    match frequency_mhz {
+        2412..=2472 => Some((frequency_mhz - 2407) / 5),
+        2484 => Some(14),
+        5160..=5885 => Some((frequency_mhz - 5000) / 5),
+        5955..=7115 => Some((frequency_mhz - 5950) / 5),
+        _ => None,
+    }
+}
+
+fn band(frequency_mhz: u32) -> &'static str {
+    if frequency_mhz < 3000 {
+        "2.4 GHz"
+    } else if frequency_mhz < 5900 {
+        "5 GHz"
+    } else {
+        "6 GHz"
+    }
+}
+
+// Channels 52-144 require radar detection. Many access points avoid them so they are less crowded
+fn is_dfs(channel: u32) -> bool {
+    (52..=144).contains(&channel)
+}
+
+fn frequency_range(center_mhz: u32, width_mhz: u32) -> (u32, u32) {
+    (center_mhz - width_mhz / 2, center_mhz + width_mhz / 2)
+}
+
+// Number of other networks strong enough to interfere within the frequency range
+fn congestion(networks: &[WifiNetwork], range: (u32, u32), own_bssid: Option<&str>) -> usize {
+    networks
+        .iter()
+        .filter(|network| {
+            let other = frequency_range(network.center_frequency_mhz, network.channel_width_mhz);
+
+            Some(network.bssid.as_str()) != own_bssid
+                && network.signal_dbm > INTERFERENCE_THRESHOLD_DBM
+                && other.0 < range.1
+                && range.0 < other.1
+        })
+        .count()
+}
+
+fn recommendations(report: &WifiScanReport, connected: &WifiNetwork) -> Vec<String> {
+    let mut recommendations = vec![];
+
+    if connected.frequency_mhz < 3000 {
+        recommendations.push(
+            "The headset is connected on 2.4 GHz. Use a 5 GHz or 6 GHz network for streaming"
+                .into(),
+        );
+    }
+
+    if connected.signal_dbm < WEAK_SIGNAL_DBM {
+        recommendations.push(format!(
+            "Weak signal ({} dBm). Move the access point closer to the play area, in line of sight",
+            connected.signal_dbm
+        ));
+    }
+
+    if band(connected.frequency_mhz) == "5 GHz" {
+        if connected.channel_width_mhz < 80 {
+            recommendations.push(format!(
+                "The channel width is {} MHz. Use 80 MHz or more for higher bitrates",
+                connected.channel_width_mhz
+            ));
+        }
+
+        let width = connected.channel_width_mhz;
+        let current_congestion = congestion(
+            &report.networks,
+            frequency_range(connected.center_frequency_mhz, width),
+            Some(&connected.bssid),
+        );
+
+        // Approximation: candidate channels are evaluated as if they were the center channel
+        let best_channel = CANDIDATE_CHANNELS_5GHZ
+            .iter()
+            .map(|&channel| {
+                let range = frequency_range(5000 + channel * 5, width);
+                let congestion = congestion(&report.networks, range, Some(&connected.bssid));
+
+                (channel, congestion)
+            })
+            .min_by_key(|(_, congestion)| *congestion);
+
+        if let Some((channel, congestion)) = best_channel {
+            if congestion < current_congestion {
+                recommendations.push(format!(
+                    "{current_congestion} networks overlap the current channel. Switch the access \
+                    point to channel {channel}{}, where {congestion} networks overlap",
+                    if is_dfs(channel) { " (DFS)" } else { "" }
+                ));
+            }
+        }
+    }
+
+    recommendations
+}
+
+// Shows the Wi-Fi networks seen by the headset, requested on demand since scanning can disrupt
+// the stream
+pub struct NetworkHealthPanel {
+    report: Option<Result<WifiScanReport, String>>,
+    scanning: bool,
+}
+
+impl NetworkHealthPanel {
+    pub fn new() -> Self {
+        Self {
+            report: None,
+            scanning: false,
+        }
+    }
+
+    pub fn update_report(&mut self, report: Result<WifiScanReport, String>) {
+        self.report = Some(report);
+        self.scanning = false;
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<ServerRequest> {
+        let mut request = None;
+
+        Frame::group(ui.style())
+            .fill(theme::SECTION_BG)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("Network health");
+                    if ui
+                        .add_enabled(!self.scanning, eframe::egui::Button::new("Scan Wi-Fi"))
+                        .on_hover_text("Scan from the headset. It can cause a short stutter")
+                        .clicked()
+                    {
+                        request = Some(ServerRequest::ScanClientWifi);
+                        self.scanning = true;
+                    }
+                    if self.scanning {
+                        ui.spinner();
+                    }
+                });
+
+                match &self.report {
+                    None => {
+                        ui.label("Scan to check the channel congestion around the headset.");
+                    }
+                    Some(Err(message)) => {
+                        ui.colored_label(log_colors::ERROR_LIGHT, message);
+                    }
+                    Some(Ok(report)) => report_ui(ui, report),
+                }
+            });
+
+        request
+    }
+}
+
+fn report_ui(ui: &mut Ui, report: &WifiScanReport) {
+    if !report.fresh {
+        ui.colored_label(
+            log_colors::WARNING_LIGHT,
+            "The system throttled the scan, these results may be outdated",
+        );
+    }
+
+    let connected = report.connected_bssid.as_ref().and_then(|bssid| {
+        report
+            .networks
+            .iter()
+            .find(|network| &network.bssid == bssid)
+    });
+
+    if let Some(connected) = connected {
+        ui.label(format!(
+            "Connected to {} on {}, channel {}, {} MHz wide, {} dBm",
+            connected.ssid,
+            band(connected.frequency_mhz),
+            channel(connected.frequency_mhz).unwrap_or_default(),
+            connected.channel_width_mhz,
+            connected.signal_dbm
+        ));
+
+        let recommendations = recommendations(report, connected);
+        if recommendations.is_empty() {
+            ui.colored_label(theme::OK_GREEN, "No issues found");
+        } else {
+            for recommendation in recommendations {
+                ui.label(
+                    RichText::new(format!("• {recommendation}")).color(log_colors::WARNING_LIGHT),
+                );
+            }
+        }
+    } else {
+        ui.label("The network of the headset was not found in the scan results.");
+    }
+
+    CollapsingHeader::new(format!("Nearby networks ({})", report.networks.len())).show(ui, |ui| {
+        let mut networks = report.networks.iter().collect::<Vec<_>>();
+        networks.sort_by_key(|network| -network.signal_dbm);
+
+        Grid::new("wifi_networks")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("SSID");
+                ui.label("Channel");
+                ui.label("Width");
+                ui.label("Signal");
+                ui.end_row();
+
+                for network in networks {
+                    ui.label(if network.ssid.is_empty() {
+                        "(hidden)"
+                    } else {
+                        &network.ssid
+                    });
+                    ui.label(format!(
+                        "{} ({})",
+                        channel(network.frequency_mhz).unwrap_or_default(),
+                        band(network.frequency_mhz)
+                    ));
+                    ui.label(format!("{} MHz", network.channel_width_mhz));
+                    ui.label(format!("{} dBm", network.signal_dbm));
+                    ui.end_row();
+                }
+            });
+    });
+}
@@ -4,17 +4,17 @@ use super::{
     NestingInfo, SettingControl, INDENTATION_STEP,
 };
 use crate::dashboard::{DisplayString, ServerRequest};
-use alvr_packets::AudioDevicesList;
+use alvr_packets::{AudioDevicesList, PathSegment, PathValuePair};
 use alvr_session::{SessionSettings, Settings};
-use eframe::egui::{Grid, Label, RichText, ScrollArea, Ui};
+use eframe::egui::{Grid, Label, RichText, ScrollArea, Sense, Ui};
 use serde_json as json;
 
 #[cfg(target_arch = "wasm32")]
 use instant::Instant;
 use settings_schema::SchemaNode;
-use std::time::Duration;
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
+use std::{collections::BTreeSet, time::Duration};
 
 const DATA_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
 
@@ -27,12 +27,55 @@ pub struct SettingsTab {
     resolution_preset: PresetControl,
     framerate_preset: PresetControl,
     encoder_preset: PresetControl,
+    network_profile_preset: PresetControl,
     game_audio_preset: Option<PresetControl>,
     microphone_preset: Option<PresetControl>,
     eye_face_tracking_preset: PresetControl,
     top_level_entries: Vec<TopLevelEntry>,
     session_settings_json: Option<json::Value>,
     last_update_instant: Instant,
+    // Ids of the top level sections
+    collapsed_sections: BTreeSet<String>,
+    advanced_collapsed: bool,
+    dangerous_defaults: Vec<PathValuePair>,
+}
+
+fn collapsing_arrow(collapsed: bool) -> &'static str {
+    if collapsed {
+        "⏵"
+    } else {
+        "⏷"
+    }
+}
+
+// Default values of the entries flagged as dangerous. Only nested sections are searched
+fn collect_dangerous_defaults(
+    schema: &SchemaNode,
+    default_fragment: &json::Value,
+    path: &[PathSegment],
+    defaults: &mut Vec<PathValuePair>,
+) {
+    let SchemaNode::Section(entries) = schema else {
+        return;
+    };
+
+    for entry in entries {
+        let Some(default_value) = default_fragment.get(&entry.name) else {
+            continue;
+        };
+
+        let mut path = path.to_vec();
+        path.push(entry.name.clone().into());
+
+        if entry.flags.contains("dangerous") {
+            defaults.push(PathValuePair {
+                path,
+                value: default_value.clone(),
+            });
+        } else {
+            collect_dangerous_defaults(&entry.content, default_value, &path, defaults);
+        }
+    }
 }
 
 impl SettingsTab {
@@ -43,6 +86,14 @@ impl SettingsTab {
         };
         let schema = Settings::schema(alvr_session::session_settings_default());
 
+        let mut dangerous_defaults = vec![];
+        collect_dangerous_defaults(
+            &schema,
+            &json::to_value(alvr_session::session_settings_default()).unwrap(),
+            &nesting_info.path,
+            &mut dangerous_defaults,
+        );
+
         // Top level node must be a section
         let schema_entries = if let SchemaNode::Section(entries) = schema {
             entries
@@ -70,15 +121,32 @@ impl SettingsTab {
             resolution_preset: PresetControl::new(builtin_schema::resolution_schema()),
             framerate_preset: PresetControl::new(builtin_schema::framerate_schema()),
             encoder_preset: PresetControl::new(builtin_schema::encoder_preset_schema()),
+            network_profile_preset: PresetControl::new(builtin_schema::network_profile_schema()),
             game_audio_preset: None,
             microphone_preset: None,
             eye_face_tracking_preset: PresetControl::new(builtin_schema::eye_face_tracking_schema()),
             top_level_entries,
             session_settings_json: None,
             last_update_instant: Instant::now(),
+            collapsed_sections: BTreeSet::new(),
+            advanced_collapsed: false,
+            dangerous_defaults,
         }
     }
 
+    pub fn collapsed_sections(&self) -> &BTreeSet<String> {
+        &self.collapsed_sections
+    }
+
+    pub fn advanced_collapsed(&self) -> bool {
+        self.advanced_collapsed
+    }
+
+    pub fn set_collapsed_sections(&mut self, sections: BTreeSet<String>, advanced_collapsed: bool) {
+        self.collapsed_sections = sections;
+        self.advanced_collapsed = advanced_collapsed;
+    }
+
     pub fn update_session(&mut self, session_settings: &SessionSettings) {
         let settings_json = json::to_value(session_settings).unwrap();
 
@@ -87,6 +155,8 @@ impl SettingsTab {
         self.framerate_preset
             .update_session_settings(&settings_json);
         self.encoder_preset.update_session_settings(&settings_json);
+        self.network_profile_preset
+            .update_session_settings(&settings_json);
         if let Some(preset) = self.game_audio_preset.as_mut() {
             preset.update_session_settings(&settings_json)
         }
@@ -152,6 +222,9 @@ impl SettingsTab {
                                 path_value_pairs.extend(self.encoder_preset.ui(ui));
                                 ui.end_row();
 
+                                path_value_pairs.extend(self.network_profile_preset.ui(ui));
+                                ui.end_row();
+
                                 if let Some(preset) = &mut self.game_audio_preset {
                                     path_value_pairs.extend(preset.ui(ui));
                                     ui.end_row();
@@ -170,11 +243,35 @@ impl SettingsTab {
                 ui.add_space(15.0);
 
                 ui.horizontal(|ui| {
-                    ui.add(Label::new(
-                        RichText::new("All Settings (Advanced)").size(20.0),
-                    ));
+                    let label_res = ui.add(
+                        Label::new(
+                            RichText::new(format!(
+                                "{} All Settings (Advanced)",
+                                collapsing_arrow(self.advanced_collapsed)
+                            ))
+                            .size(20.0),
+                        )
+                        .sense(Sense::click()),
+                    );
+                    if label_res.clicked() {
+                        self.advanced_collapsed = !self.advanced_collapsed;
+                    }
                     notice::notice(ui, "Changing some advanced settings may break ALVR");
+                    if ui
+                        .button("Reset dangerous settings")
+                        .on_hover_text(
+                            "Restore the default value of the settings marked with a lock, \
+                            which often prevent the stream from working",
+                        )
+                        .clicked()
+                    {
+                        path_value_pairs.extend(self.dangerous_defaults.iter().cloned());
+                    }
                 });
+                if self.advanced_collapsed {
+                    return;
+                }
+
                 ScrollArea::new([true, false])
                     .id_source("advanced_scroll")
                     .show(ui, |ui| {
@@ -187,26 +284,47 @@ impl SettingsTab {
                                         session_fragment.as_object_mut().unwrap();
 
                                     for entry in self.top_level_entries.iter_mut() {
-                                        ui.horizontal(|ui| {
-                                            ui.add_space(INDENTATION_STEP);
-                                            let label_res = ui.add(Label::new(
-                                                RichText::new(&entry.id.display)
-                                                    .size(18.0)
-                                                    .monospace(),
-                                            ));
-                                            if cfg!(debug_assertions) {
-                                                label_res.on_hover_text(&*entry.id);
-                                            }
-                                        });
+                                        let collapsed =
+                                            self.collapsed_sections.contains(&entry.id.id);
+
+                                        let clicked = ui
+                                            .horizontal(|ui| {
+                                                ui.add_space(INDENTATION_STEP);
+                                                let label_res = ui.add(
+                                                    Label::new(
+                                                        RichText::new(format!(
+                                                            "{} {}",
+                                                            collapsing_arrow(collapsed),
+                                                            entry.id.display
+                                                        ))
+                                                        .size(18.0)
+                                                        .monospace(),
+                                                    )
+                                                    .sense(Sense::click()),
+                                                );
+                                                let clicked = label_res.clicked();
+                                                if cfg!(debug_assertions) {
+                                                    label_res.on_hover_text(&*entry.id);
+                                                }
+
+                                                clicked
+                                            })
+                                            .inner;
+                                        if clicked && !self.collapsed_sections.remove(&entry.id.id)
+                                        {
+                                            self.collapsed_sections.insert(entry.id.id.clone());
+                                        }
 
-                                        let response = entry.control.ui(
-                                            ui,
-                                            &mut session_fragments_mut[&entry.id.id],
-                                            true,
-                                        );
+                                        if !collapsed {
+                                            let response = entry.control.ui(
+                                                ui,
+                                                &mut session_fragments_mut[&entry.id.id],
+                                                true,
+                                            );
 
-                                        if let Some(response) = response {
-                                            path_value_pairs.push(response);
+                                            if let Some(response) = response {
+                                                path_value_pairs.push(response);
+                                            }
                                         }
 
                                         ui.end_row();
@@ -30,6 +30,15 @@ fn bool_modifier(target_path: &str, value: bool) -> PresetModifier {
 }
 
 pub fn resolution_schema() -> PresetSchemaNode {
+    let recommended_option = HigherOrderChoiceOption {
+        display_name: "Headset recommended".into(),
+        modifiers: vec![bool_modifier(
+            "session_settings.video.use_headset_recommended_resolution",
+            true,
+        )],
+        content: None,
+    };
+
     PresetSchemaNode::HigherOrderChoice(HigherOrderChoiceSchema {
         name: "resolution".into(),
         strings: HashMap::new(),
@@ -46,6 +55,10 @@ pub fn resolution_schema() -> PresetSchemaNode {
         .map(|(key, value)| HigherOrderChoiceOption {
             display_name: key.into(),
             modifiers: [
+                bool_modifier(
+                    "session_settings.video.use_headset_recommended_resolution",
+                    false,
+                ),
                 string_modifier(
                     "session_settings.video.transcoding_view_resolution.variant",
                     "Absolute",
@@ -75,8 +88,10 @@ pub fn resolution_schema() -> PresetSchemaNode {
             .collect(),
             content: None,
         })
+        .chain([recommended_option])
         .collect(),
-        default_option_index: 2,
+        // Headset recommended
+        default_option_index: 6,
         gui: ChoiceControlType::Dropdown,
     })
 }
@@ -142,6 +157,84 @@ pub fn encoder_preset_schema() -> PresetSchemaNode {
     })
 }
 
+pub fn network_profile_schema() -> PresetSchemaNode {
+    const BITRATE_PREFIX: &str = "session_settings.video.bitrate.mode";
+
+    PresetSchemaNode::HigherOrderChoice(HigherOrderChoiceSchema {
+        name: "network_profile".into(),
+        strings: [(
+            "help".into(),
+            "Constrained: for 5 GHz hotspots, VPN and WAN links. Caps the bitrate to 10-15 Mbps, increases video and audio buffering and relaxes the connection timeouts".into(),
+        )]
+        .into_iter()
+        .collect(),
+        flags: ["steamvr-restart".into()].into_iter().collect(),
+        options: [
+            ("Local network", false, "2.0", "50", "2000"),
+            ("Constrained", true, "4.0", "150", "6000"),
+        ]
+        .into_iter()
+        .map(
+            |(key, constrained, buffering_frames, audio_buffering_ms, handshake_timeout_ms)| {
+                let mut modifiers = vec![
+                    string_modifier(&format!("{BITRATE_PREFIX}.variant"), "Adaptive"),
+                    bool_modifier(
+                        &format!("{BITRATE_PREFIX}.Adaptive.max_bitrate_mbps.enabled"),
+                        constrained,
+                    ),
+                    bool_modifier(
+                        &format!("{BITRATE_PREFIX}.Adaptive.min_bitrate_mbps.enabled"),
+                        constrained,
+                    ),
+                    num_modifier("session_settings.video.max_buffering_frames", buffering_frames),
+                    num_modifier(
+                        "session_settings.audio.game_audio.content.buffering.average_buffering_ms",
+                        audio_buffering_ms,
+                    ),
+                    num_modifier(
+                        "session_settings.audio.microphone.content.buffering.average_buffering_ms",
+                        audio_buffering_ms,
+                    ),
+                    num_modifier(
+                        "session_settings.connection.handshake_timeout_ms",
+                        handshake_timeout_ms,
+                    ),
+                    bool_modifier(
+                        "session_settings.connection.aggressive_keyframe_resend",
+                        constrained,
+                    ),
+                    // The warning suggests this profile
+                    bool_modifier(
+                        "session_settings.connection.high_latency_warning_ms.enabled",
+                        !constrained,
+                    ),
+                ];
+                if constrained {
+                    modifiers.extend([
+                        num_modifier(
+                            &format!("{BITRATE_PREFIX}.Adaptive.max_bitrate_mbps.content"),
+                            "15",
+                        ),
+                        num_modifier(
+                            &format!("{BITRATE_PREFIX}.Adaptive.min_bitrate_mbps.content"),
+                            "10",
+                        ),
+                    ]);
+                }
+
+                HigherOrderChoiceOption {
+                    display_name: key.into(),
+                    modifiers,
+                    content: None,
+                }
+            },
+        )
+        .collect(),
+        default_option_index: 0,
+        gui: ChoiceControlType::ButtonGroup,
+    })
+}
+
 pub fn game_audio_schema(devices: Vec<String>) -> PresetSchemaNode {
     let mut game_audio_options = vec![
         HigherOrderChoiceOption {
@@ -6,7 +6,7 @@ use alvr_gui_common::theme::{
 };
 use alvr_packets::PathValuePair;
 use alvr_session::settings_schema::{SchemaEntry, SchemaNode};
-use eframe::egui::{self, popup, Ui};
+use eframe::egui::{self, popup, Align2, Label, RichText, Sense, Ui, Window};
 use serde_json as json;
 
 const POPUP_ID: &str = "setpopup";
@@ -17,6 +17,11 @@ struct Entry {
     // notice: Option<String>,
     steamvr_restart_flag: bool,
     real_time_flag: bool,
+    // Edits must be confirmed until the entry is unlocked
+    dangerous_flag: bool,
+    unlocked: bool,
+    // Edit waiting for confirmation
+    pending_edit: Option<PathValuePair>,
     control: SettingControl,
 }
 
@@ -32,8 +37,10 @@ impl Control {
     ) -> Self {
         nesting_info.indentation_level += 1;
 
+        // Hidden entries are still part of the session but are edited from dedicated panels
         let entries = schema_entries
             .into_iter()
+            .filter(|entry| !entry.flags.contains("hidden"))
             .map(|entry| {
                 let id = entry.name;
                 let display = super::get_display_name(&id, &entry.strings);
@@ -41,6 +48,7 @@ impl Control {
                 // let notice = entry.strings.get("notice").cloned();
                 let steamvr_restart_flag = entry.flags.contains("steamvr-restart");
                 let real_time_flag = entry.flags.contains("real-time");
+                let dangerous_flag = entry.flags.contains("dangerous");
 
                 let mut nesting_info = nesting_info.clone();
                 nesting_info.path.push(id.clone().into());
@@ -51,6 +59,9 @@ impl Control {
                     // notice,
                     steamvr_restart_flag,
                     real_time_flag,
+                    dangerous_flag,
+                    unlocked: false,
+                    pending_edit: None,
                     control: SettingControl::new(nesting_info, entry.content),
                 }
             })
@@ -107,11 +118,79 @@ impl Control {
                         "This setting can be changed in real-time during streaming!",
                     );
                 }
+
+                if entry.dangerous_flag {
+                    let (icon, text) = if entry.unlocked {
+                        ("🔓", "Unlocked for editing. Click to lock again")
+                    } else {
+                        (
+                            "🔒",
+                            "Changing this setting often prevents the stream from working. \
+                            Edits must be confirmed",
+                        )
+                    };
+                    let icon_res = ui.add(
+                        Label::new(RichText::new(icon).color(WARNING_LIGHT)).sense(Sense::click()),
+                    );
+                    if icon_res.hovered() {
+                        popup::show_tooltip_text(ui.ctx(), egui::Id::new(POPUP_ID), text);
+                    }
+                    if entry.unlocked && icon_res.clicked() {
+                        entry.unlocked = false;
+                    }
+                }
             });
-            response = entry
-                .control
-                .ui(ui, &mut session_fragments_mut[&entry.id.id], true)
-                .or(response);
+
+            let session_fragment = &mut session_fragments_mut[&entry.id.id];
+            let locked_value =
+                (entry.dangerous_flag && !entry.unlocked).then(|| session_fragment.clone());
+            let entry_response = entry.control.ui(ui, session_fragment, true);
+            if let Some(locked_value) = locked_value {
+                // Shown as unchanged until confirmed
+                *session_fragment = locked_value;
+                if entry_response.is_some() {
+                    entry.pending_edit = entry_response;
+                }
+            } else {
+                response = entry_response.or(response);
+            }
+
+            if entry.pending_edit.is_some() {
+                let mut confirmed = None;
+                Window::new("Dangerous setting")
+                    .id(egui::Id::new(("dangerous_setting", &entry.id.id)))
+                    .anchor(Align2::CENTER_CENTER, (0.0, 0.0))
+                    .resizable(false)
+                    .collapsible(false)
+                    .show(ui.ctx(), |ui| {
+                        ui.label(format!(
+                            "Changing \"{}\" often prevents the stream from working. \
+                            If this happens, use \"Reset dangerous settings\".",
+                            entry.id.display
+                        ));
+                        if let Some(help) = &entry.help {
+                            ui.add_space(5.0);
+                            ui.label(help);
+                        }
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            if ui.button("Apply and unlock").clicked() {
+                                confirmed = Some(true);
+                            }
+                            if ui.button("Cancel").clicked() {
+                                confirmed = Some(false);
+                            }
+                        });
+                    });
+
+                if let Some(confirmed) = confirmed {
+                    let pending_edit = entry.pending_edit.take();
+                    if confirmed {
+                        entry.unlocked = true;
+                        response = pending_edit;
+                    }
+                }
+            }
 
             if i != entries_count - 1 {
                 ui.end_row();
@@ -1,36 +1,79 @@
+use super::{
+    graph_export::{self, GraphExportFormat, GraphPainter, RecordedGraph},
+    NetworkHealthPanel,
+};
 use crate::{dashboard::theme::graph_colors, dashboard::ServerRequest};
-use alvr_events::{GraphStatistics, StatisticsSummary};
+use alvr_events::{GraphStatistics, LatencyPlan, StatisticsSummary};
 use alvr_gui_common::theme;
+use alvr_packets::{ThermalStatus, WifiScanReport};
+use alvr_session::Settings;
 use eframe::{
     egui::{
-        popup, pos2, vec2, Align2, Color32, FontId, Frame, Id, Painter, Rect, RichText, Rounding,
-        ScrollArea, Shape, Stroke, Ui,
+        popup, pos2, vec2, Align2, Color32, ComboBox, FontId, Frame, Id, Rect, RichText, Rounding,
+        ScrollArea, Ui,
     },
     emath::RectTransform,
     epaint::Pos2,
 };
 use statrs::statistics::{self, OrderStatistics};
-use std::{collections::VecDeque, ops::RangeInclusive};
+use std::{cell::RefCell, collections::VecDeque, ops::RangeInclusive};
 
-const GRAPH_HISTORY_SIZE: usize = 1000;
+pub const DEFAULT_GRAPH_HISTORY_SIZE: usize = 1000;
+const GRAPH_HISTORY_SIZES: [usize; 4] = [250, 500, 1000, 2000];
 const UPPER_QUANTILE: f64 = 0.90;
+// Markers within this many samples of the cursor are listed in the tooltip
+const MARKER_HOVER_RANGE: usize = 5;
+
+fn draw_lines(painter: &GraphPainter, points: Vec<Pos2>, color: Color32) {
+    painter.line(points, color);
+}
 
-fn draw_lines(painter: &Painter, points: Vec<Pos2>, color: Color32) {
-    painter.add(Shape::line(points, Stroke::new(1.0, color)));
+// Returns the name, color and duration of the stage that contributed the most to the latency
+fn dominant_stage(stats: &GraphStatistics) -> (&'static str, Color32, f32) {
+    [
+        ("Encode", graph_colors::HEATMAP_ENCODE, stats.encoder_s),
+        ("Network", graph_colors::HEATMAP_NETWORK, stats.network_s),
+        ("Decode", graph_colors::HEATMAP_DECODE, stats.decoder_s),
+        (
+            "Compose",
+            graph_colors::HEATMAP_COMPOSE,
+            stats.server_compositor_s + stats.client_compositor_s,
+        ),
+    ]
+    .into_iter()
+    .fold(("None", Color32::TRANSPARENT, 0.0), |max, stage| {
+        if stage.2 > max.2 {
+            stage
+        } else {
+            max
+        }
+    })
 }
 
 pub struct StatisticsTab {
     history: VecDeque<GraphStatistics>,
     last_statistics_summary: Option<StatisticsSummary>,
+    network_health: NetworkHealthPanel,
+    latency_plan: Option<LatencyPlan>,
+    // Set for the frame during which the graphs are recorded
+    export_format: Option<GraphExportFormat>,
+    recorded_graphs: RefCell<Vec<RecordedGraph>>,
+    // Region of the window to crop from the next screenshot, and pixels per point
+    pending_png_export: Option<(Rect, f32)>,
 }
 
 impl StatisticsTab {
     pub fn new() -> Self {
         Self {
-            history: vec![GraphStatistics::default(); GRAPH_HISTORY_SIZE]
+            history: vec![GraphStatistics::default(); DEFAULT_GRAPH_HISTORY_SIZE]
                 .into_iter()
                 .collect(),
             last_statistics_summary: None,
+            network_health: NetworkHealthPanel::new(),
+            latency_plan: None,
+            export_format: None,
+            recorded_graphs: RefCell::new(vec![]),
+            pending_png_export: None,
         }
     }
 
@@ -38,25 +81,162 @@ impl StatisticsTab {
         self.last_statistics_summary = Some(statistics);
     }
 
+    pub fn update_wifi_scan(&mut self, report: Result<WifiScanReport, String>) {
+        self.network_health.update_report(report);
+    }
+
+    pub fn update_latency_plan(&mut self, plan: LatencyPlan) {
+        self.latency_plan = Some(plan);
+    }
+
+    pub fn update_settings(&mut self, settings: &Settings) {
+        if !settings.video.latency_budget.enabled() {
+            self.latency_plan = None;
+        }
+    }
+
     pub fn update_graph_statistics(&mut self, statistics: GraphStatistics) {
         self.history.pop_front();
         self.history.push_back(statistics);
     }
 
+    pub fn graph_history_size(&self) -> usize {
+        self.history.len()
+    }
+
+    // Older samples are dropped, or padded with empty samples
+    pub fn set_graph_history_size(&mut self, size: usize) {
+        let size = size.clamp(GRAPH_HISTORY_SIZES[0], GRAPH_HISTORY_SIZES[3]);
+
+        while self.history.len() > size {
+            self.history.pop_front();
+        }
+        while self.history.len() < size {
+            self.history.push_front(GraphStatistics::default());
+        }
+    }
+
+    fn time_range_caption(&self) -> String {
+        let displayed_fps = self
+            .history
+            .iter()
+            .map(|stats| stats.client_fps)
+            .filter(|fps| *fps > 0.0)
+            .collect::<Vec<_>>();
+
+        if displayed_fps.is_empty() {
+            format!("Last {} frames", self.history.len())
+        } else {
+            let average_fps = displayed_fps.iter().sum::<f32>() / displayed_fps.len() as f32;
+
+            format!(
+                "Last {} frames (~{:.1} s)",
+                self.history.len(),
+                self.history.len() as f32 / average_fps
+            )
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn wants_screenshot(&self) -> bool {
+        self.pending_png_export.is_some()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_png(&mut self, screenshot: &eframe::egui::ColorImage) {
+        if let Some((region, pixels_per_point)) = self.pending_png_export.take() {
+            match graph_export::save_png(screenshot, region, pixels_per_point) {
+                Ok(path) => alvr_common::info!("Graphs exported to {}", path.display()),
+                Err(e) => alvr_common::error!("Failed to export graphs: {e}"),
+            }
+        }
+    }
+
     pub fn ui(&mut self, ui: &mut Ui) -> Option<ServerRequest> {
-        if let Some(stats) = &self.last_statistics_summary {
+        let mut request = None;
+
+        // Cloned, the graphs are drawn while the network health panel is mutably borrowed
+        if let Some(stats) = self.last_statistics_summary.clone() {
+            ui.horizontal(|ui| {
+                ComboBox::from_label("Graph range")
+                    .selected_text(format!("{} frames", self.history.len()))
+                    .show_ui(ui, |ui| {
+                        for size in GRAPH_HISTORY_SIZES {
+                            if ui
+                                .selectable_label(
+                                    self.history.len() == size,
+                                    format!("{size} frames"),
+                                )
+                                .clicked()
+                            {
+                                self.set_graph_history_size(size);
+                            }
+                        }
+                    });
+
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    if ui.button("Export graphs as SVG").clicked() {
+                        self.export_format = Some(GraphExportFormat::Svg);
+                    }
+                    if ui.button("Export visible graphs as PNG").clicked() {
+                        self.export_format = Some(GraphExportFormat::Png);
+                    }
+                }
+            });
+
             ScrollArea::new([false, true]).show(ui, |ui| {
+                let caption = self.time_range_caption();
+                let caption_rect = ui.label(&caption).rect;
+
                 let available_width = ui.available_width();
                 self.draw_latency_graph(ui, available_width);
+                self.draw_latency_heatmap(ui, available_width);
                 self.draw_fps_graph(ui, available_width);
                 self.draw_bitrate_graph(ui, available_width);
-                self.draw_statistics_overview(ui, stats);
+                self.draw_encoder_qp_graph(ui, available_width);
+                self.draw_auxiliary_streams_graph(ui, available_width);
+                self.draw_resource_usage_graph(ui, available_width);
+                self.draw_headset_load_graph(ui, available_width);
+
+                let graphs = self.recorded_graphs.take();
+                match self.export_format.take() {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    Some(GraphExportFormat::Svg) => {
+                        let caption = format!(
+                            "{caption}, exported {}",
+                            chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+                        );
+                        match graph_export::save_svg(&graph_export::to_svg(
+                            &graphs,
+                            &caption,
+                            theme::BG,
+                        )) {
+                            Ok(path) => alvr_common::info!("Graphs exported to {}", path.display()),
+                            Err(e) => alvr_common::error!("Failed to export graphs: {e}"),
+                        }
+                    }
+                    Some(GraphExportFormat::Png) => {
+                        let region = graphs
+                            .iter()
+                            .fold(caption_rect, |region, graph| region.union(graph.rect))
+                            .intersect(ui.clip_rect());
+                        self.pending_png_export = Some((region, ui.ctx().pixels_per_point()));
+                    }
+                    _ => (),
+                }
+
+                self.draw_statistics_overview(ui, &stats);
+                if let Some(plan) = &self.latency_plan {
+                    Self::draw_latency_plan(ui, plan);
+                }
+                request = self.network_health.ui(ui);
             });
         } else {
             ui.heading("No statistics available");
         }
 
-        None
+        request
     }
 
     fn draw_graph(
@@ -65,7 +245,7 @@ impl StatisticsTab {
         available_width: f32,
         title: &str,
         data_range: RangeInclusive<f32>,
-        graph_content: impl FnOnce(&Painter, RectTransform),
+        graph_content: impl FnOnce(&GraphPainter, RectTransform),
         tooltip_content: impl FnOnce(&mut Ui, &GraphStatistics),
     ) {
         ui.add_space(10.0);
@@ -79,21 +259,43 @@ impl StatisticsTab {
 
             let max = *data_range.end();
             let min = *data_range.start();
-            let data_rect = Rect::from_x_y_ranges(0.0..=GRAPH_HISTORY_SIZE as f32, max..=min);
+            let data_rect = Rect::from_x_y_ranges(0.0..=self.history.len() as f32, max..=min);
             let to_screen = RectTransform::from_to(data_rect, canvas_rect);
 
-            let painter = ui.painter().with_clip_rect(canvas_rect);
+            let painter = GraphPainter::new(
+                ui.painter().with_clip_rect(canvas_rect),
+                self.export_format.is_some(),
+            );
 
             graph_content(&painter, to_screen);
 
-            ui.painter().text(
+            for (i, stats) in self.history.iter().enumerate() {
+                if let Some(label) = stats.markers.first() {
+                    painter.line(
+                        vec![
+                            to_screen * pos2(i as f32 + 1.0, max),
+                            to_screen * pos2(i as f32 + 1.0, min),
+                        ],
+                        graph_colors::MARKER,
+                    );
+                    painter.text(
+                        to_screen * pos2(i as f32 + 2.0, max),
+                        Align2::LEFT_TOP,
+                        label.clone(),
+                        FontId::proportional(11.0),
+                        graph_colors::MARKER,
+                    );
+                }
+            }
+
+            painter.text(
                 to_screen * pos2(0.0, min),
                 Align2::LEFT_BOTTOM,
                 format!("{:.0}", min),
                 FontId::monospace(20.0),
                 Color32::GRAY,
             );
-            ui.painter().text(
+            painter.text(
                 to_screen * pos2(0.0, max),
                 Align2::LEFT_TOP,
                 format!("{:.0}", max),
@@ -101,6 +303,14 @@ impl StatisticsTab {
                 Color32::GRAY,
             );
 
+            if self.export_format.is_some() {
+                self.recorded_graphs.borrow_mut().push(RecordedGraph {
+                    title: title.into(),
+                    rect: canvas_rect,
+                    shapes: painter.into_recorded_shapes(),
+                });
+            }
+
             data_rect
         });
 
@@ -109,7 +319,16 @@ impl StatisticsTab {
                 RectTransform::from_to(canvas_response.response.rect, canvas_response.inner) * pos;
 
             popup::show_tooltip(ui.ctx(), Id::new("popup"), |ui| {
-                tooltip_content(ui, self.history.get(graph_pos.x as usize).unwrap())
+                let index = graph_pos.x as usize;
+                tooltip_content(ui, self.history.get(index).unwrap());
+
+                let range = index.saturating_sub(MARKER_HOVER_RANGE)
+                    ..usize::min(index + MARKER_HOVER_RANGE + 1, self.history.len());
+                for stats in self.history.range(range) {
+                    for label in &stats.markers {
+                        ui.colored_label(graph_colors::MARKER, label);
+                    }
+                }
             });
         }
     }
@@ -122,13 +341,15 @@ impl StatisticsTab {
                 .collect::<Vec<_>>(),
         );
 
+        let max = (data.quantile(UPPER_QUANTILE) * 1.2) as f32 * 1000.0;
+
         self.draw_graph(
             ui,
             available_width,
             "Latency",
-            0.0..=(data.quantile(UPPER_QUANTILE) * 1.2) as f32 * 1000.0,
+            0.0..=max,
             |painter, to_screen_trans| {
-                for i in 0..GRAPH_HISTORY_SIZE {
+                for i in 0..self.history.len() {
                     let stats = self.history.get(i).unwrap();
                     let mut offset = 0.0;
                     for (value, color) in &[
@@ -152,6 +373,18 @@ impl StatisticsTab {
                         offset += value * 1000.0;
                     }
                 }
+
+                let motion_to_photon = self
+                    .history
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, stats)| {
+                        stats
+                            .motion_to_photon_s
+                            .map(|value| to_screen_trans * pos2(i as f32, value * 1000.0))
+                    })
+                    .collect();
+                draw_lines(painter, motion_to_photon, graph_colors::MOTION_TO_PHOTON);
             },
             |ui, stats| {
                 use graph_colors::*;
@@ -166,6 +399,9 @@ impl StatisticsTab {
                     stats.total_pipeline_latency_s,
                     theme::FG,
                 );
+                if let Some(value_s) = stats.motion_to_photon_s {
+                    label(ui, "Estimated motion-to-photon", value_s, MOTION_TO_PHOTON);
+                }
                 label(ui, "Client VSync", stats.vsync_queue_s, IDLE);
                 label(ui, "Client compositor", stats.client_compositor_s, RENDER);
                 label(ui, "Decoder queue", stats.decoder_queue_s, IDLE);
@@ -174,6 +410,69 @@ impl StatisticsTab {
                 label(ui, "Encode", stats.encoder_s, TRANSCODE);
                 label(ui, "Streamer compositor", stats.server_compositor_s, RENDER);
                 label(ui, "Game render", stats.game_time_s, RENDER_VARIANT);
+                if stats.client_wifi_link_events > 0 {
+                    ui.colored_label(
+                        WIFI_LINK_EVENT,
+                        format!("Wi-Fi link events: {}", stats.client_wifi_link_events),
+                    );
+                }
+            },
+        );
+    }
+
+    // Each column is a frame, colored by the stage that dominated its latency. Brighter columns
+    // have higher total latency
+    fn draw_latency_heatmap(&self, ui: &mut Ui, available_width: f32) {
+        let mut data = statistics::Data::new(
+            self.history
+                .iter()
+                .map(|stats| stats.total_pipeline_latency_s as f64)
+                .collect::<Vec<_>>(),
+        );
+        let max = (data.quantile(UPPER_QUANTILE) * 1.2).max(0.001) as f32 * 1000.0;
+
+        self.draw_graph(
+            ui,
+            available_width,
+            "Latency heatmap",
+            0.0..=max,
+            |painter, to_screen_trans| {
+                for i in 0..self.history.len() {
+                    let stats = &self.history[i];
+                    let (_, color, _) = dominant_stage(stats);
+                    let intensity = (stats.total_pipeline_latency_s * 1000.0 / max).clamp(0.1, 1.0);
+
+                    painter.rect_filled(
+                        Rect {
+                            min: to_screen_trans * pos2(i as f32, max),
+                            max: to_screen_trans * pos2(i as f32 + 2.0, 0.0),
+                        },
+                        Rounding::none(),
+                        color.linear_multiply(intensity),
+                    );
+                }
+            },
+            |ui, stats| {
+                let (name, color, value_s) = dominant_stage(stats);
+
+                ui.label(format!(
+                    "Total latency: {:.2}ms",
+                    stats.total_pipeline_latency_s * 1000.0
+                ));
+                ui.colored_label(
+                    color,
+                    format!("Dominant stage: {name} ({:.2}ms)", value_s * 1000.0),
+                );
+                ui.horizontal(|ui| {
+                    for (name, color) in [
+                        ("Encode", graph_colors::HEATMAP_ENCODE),
+                        ("Network", graph_colors::HEATMAP_NETWORK),
+                        ("Decode", graph_colors::HEATMAP_DECODE),
+                        ("Compose", graph_colors::HEATMAP_COMPOSE),
+                    ] {
+                        ui.colored_label(color, name);
+                    }
+                });
             },
         );
     }
@@ -199,7 +498,21 @@ impl StatisticsTab {
             "Framerate",
             min as f32..=max as f32,
             |painter, to_screen_trans| {
-                let (server_fps_points, client_fps_points) = (0..GRAPH_HISTORY_SIZE)
+                // Frames displayed again by the headset are marked with a full height bar
+                for i in 0..self.history.len() {
+                    if self.history[i].client_reprojected_frames > 0 {
+                        painter.rect_filled(
+                            Rect {
+                                min: to_screen_trans * pos2(i as f32, max as f32),
+                                max: to_screen_trans * pos2(i as f32 + 2.0, min as f32),
+                            },
+                            Rounding::none(),
+                            graph_colors::REPROJECTED_FRAME,
+                        );
+                    }
+                }
+
+                let (server_fps_points, client_fps_points) = (0..self.history.len())
                     .map(|i| {
                         (
                             to_screen_trans * pos2(i as f32, self.history[i].server_fps),
@@ -220,6 +533,10 @@ impl StatisticsTab {
                     graph_colors::CLIENT_FPS,
                     format!("Client FPS: {:.2}", stats.client_fps),
                 );
+                ui.label(format!(
+                    "Reprojected frames: {}",
+                    stats.client_reprojected_frames
+                ));
             },
         );
     }
@@ -238,15 +555,16 @@ impl StatisticsTab {
             "Bitrate",
             0.0..=(data.quantile(UPPER_QUANTILE) * 2.0) as f32 / 1e6,
             |painter, to_screen_trans| {
-                let mut scaled_calculated = Vec::with_capacity(GRAPH_HISTORY_SIZE);
-                let mut decoder_latency_limiter = Vec::with_capacity(GRAPH_HISTORY_SIZE);
-                let mut network_latency_limiter = Vec::with_capacity(GRAPH_HISTORY_SIZE);
-                let mut encoder_latency_limiter = Vec::with_capacity(GRAPH_HISTORY_SIZE);
-                let mut manual_max = Vec::with_capacity(GRAPH_HISTORY_SIZE);
-                let mut manual_min = Vec::with_capacity(GRAPH_HISTORY_SIZE);
-                let mut requested = Vec::with_capacity(GRAPH_HISTORY_SIZE);
-                let mut actual = Vec::with_capacity(GRAPH_HISTORY_SIZE);
-                for i in 0..GRAPH_HISTORY_SIZE {
+                let mut scaled_calculated = Vec::with_capacity(self.history.len());
+                let mut decoder_latency_limiter = Vec::with_capacity(self.history.len());
+                let mut network_latency_limiter = Vec::with_capacity(self.history.len());
+                let mut encoder_latency_limiter = Vec::with_capacity(self.history.len());
+                let mut manual_max = Vec::with_capacity(self.history.len());
+                let mut manual_min = Vec::with_capacity(self.history.len());
+                let mut client_max = Vec::with_capacity(self.history.len());
+                let mut requested = Vec::with_capacity(self.history.len());
+                let mut actual = Vec::with_capacity(self.history.len());
+                for i in 0..self.history.len() {
                     let nom_br = &self.history[i].nominal_bitrate;
 
                     if let Some(value) = nom_br.scaled_calculated_bps {
@@ -267,6 +585,9 @@ impl StatisticsTab {
                     if let Some(value) = nom_br.manual_min_bps {
                         manual_min.push(to_screen_trans * pos2(i as f32, value / 1e6))
                     }
+                    if let Some(value) = nom_br.client_max_bps {
+                        client_max.push(to_screen_trans * pos2(i as f32, value / 1e6))
+                    }
                     requested.push(to_screen_trans * pos2(i as f32, nom_br.requested_bps / 1e6));
                     actual.push(
                         to_screen_trans * pos2(i as f32, self.history[i].actual_bitrate_bps / 1e6),
@@ -279,6 +600,7 @@ impl StatisticsTab {
                 draw_lines(painter, decoder_latency_limiter, graph_colors::TRANSCODE);
                 draw_lines(painter, manual_max, graph_colors::RENDER);
                 draw_lines(painter, manual_min, graph_colors::RENDER);
+                draw_lines(painter, client_max, graph_colors::RENDER);
                 draw_lines(painter, requested, theme::OK_GREEN);
                 draw_lines(painter, actual, theme::FG);
             },
@@ -322,6 +644,7 @@ impl StatisticsTab {
                 );
                 maybe_label(ui, "Manual max", n.manual_max_bps, graph_colors::RENDER);
                 maybe_label(ui, "Manual min", n.manual_min_bps, graph_colors::RENDER);
+                maybe_label(ui, "Client max", n.client_max_bps, graph_colors::RENDER);
                 maybe_label(ui, "Requested", Some(n.requested_bps), theme::OK_GREEN);
                 maybe_label(
                     ui,
@@ -333,6 +656,265 @@ impl StatisticsTab {
         )
     }
 
+    // High QP while the requested bitrate sits at its cap points to bitrate starvation, while
+    // low QP with visible artifacts at the image edges points to the foveation settings.
+    fn draw_encoder_qp_graph(&self, ui: &mut Ui, available_width: f32) {
+        self.draw_graph(
+            ui,
+            available_width,
+            "Encoder QP",
+            0.0..=51.0,
+            |painter, to_screen_trans| {
+                let mut qp = Vec::with_capacity(self.history.len());
+                let mut center_qp = Vec::with_capacity(self.history.len());
+                let mut edges_qp = Vec::with_capacity(self.history.len());
+                for i in 0..self.history.len() {
+                    let stats = &self.history[i];
+                    if let Some(value) = stats.encoder_qp {
+                        qp.push(to_screen_trans * pos2(i as f32, value));
+                    }
+                    if let Some(value) = stats.encoder_qp_foveation_center {
+                        center_qp.push(to_screen_trans * pos2(i as f32, value));
+                    }
+                    if let Some(value) = stats.encoder_qp_foveation_edges {
+                        edges_qp.push(to_screen_trans * pos2(i as f32, value));
+                    }
+                }
+
+                draw_lines(painter, edges_qp, graph_colors::ENCODER_QP_EDGES);
+                draw_lines(painter, center_qp, graph_colors::ENCODER_QP_CENTER);
+                draw_lines(painter, qp, graph_colors::ENCODER_QP);
+            },
+            |ui, stats| {
+                if let Some(value) = stats.encoder_qp {
+                    ui.colored_label(graph_colors::ENCODER_QP, format!("Average QP: {value:.1}"));
+                } else {
+                    ui.colored_label(graph_colors::ENCODER_QP, "Average QP: unavailable");
+                }
+                // Only reported by encoders that expose the QP of each block
+                if let (Some(center), Some(edges)) = (
+                    stats.encoder_qp_foveation_center,
+                    stats.encoder_qp_foveation_edges,
+                ) {
+                    ui.colored_label(
+                        graph_colors::ENCODER_QP_CENTER,
+                        format!("Foveation center QP: {center:.1}"),
+                    );
+                    ui.colored_label(
+                        graph_colors::ENCODER_QP_EDGES,
+                        format!("Foveation edges QP: {edges:.1}"),
+                    );
+                }
+                ui.label(format!(
+                    "Requested bitrate: {:.1} Mbps",
+                    stats.nominal_bitrate.requested_bps / 1e6
+                ));
+            },
+        );
+    }
+
+    fn draw_auxiliary_streams_graph(&self, ui: &mut Ui, available_width: f32) {
+        let mut data = statistics::Data::new(
+            self.history
+                .iter()
+                .map(|stats| stats.tracking_send_jitter_s)
+                .chain(self.history.iter().map(|stats| stats.haptics_latency_s))
+                .map(|v| v as f64)
+                .collect::<Vec<_>>(),
+        );
+        let max = (data.quantile(UPPER_QUANTILE) * 2.0).max(0.001) as f32 * 1000.0;
+
+        self.draw_graph(
+            ui,
+            available_width,
+            "Tracking, haptics and audio",
+            0.0..=max,
+            |painter, to_screen_trans| {
+                let mut tracking_jitter = Vec::with_capacity(self.history.len());
+                let mut haptics_latency = Vec::with_capacity(self.history.len());
+                for i in 0..self.history.len() {
+                    let stats = &self.history[i];
+
+                    // Audio glitches are marked with a full height bar
+                    for (count, color) in [
+                        (stats.audio_underruns, graph_colors::AUDIO_UNDERRUN),
+                        (stats.audio_overruns, graph_colors::AUDIO_OVERRUN),
+                    ] {
+                        if count > 0 {
+                            painter.rect_filled(
+                                Rect {
+                                    min: to_screen_trans * pos2(i as f32, max),
+                                    max: to_screen_trans * pos2(i as f32 + 2.0, 0.0),
+                                },
+                                Rounding::none(),
+                                color,
+                            );
+                        }
+                    }
+
+                    tracking_jitter.push(
+                        to_screen_trans * pos2(i as f32, stats.tracking_send_jitter_s * 1000.0),
+                    );
+                    haptics_latency
+                        .push(to_screen_trans * pos2(i as f32, stats.haptics_latency_s * 1000.0));
+                }
+
+                draw_lines(painter, tracking_jitter, graph_colors::TRACKING);
+                draw_lines(painter, haptics_latency, graph_colors::HAPTICS);
+            },
+            |ui, stats| {
+                ui.colored_label(
+                    graph_colors::TRACKING,
+                    format!(
+                        "Tracking send jitter: {:.2}ms",
+                        stats.tracking_send_jitter_s * 1000.0
+                    ),
+                );
+                ui.colored_label(
+                    graph_colors::HAPTICS,
+                    format!(
+                        "Haptics delivery latency: {:.2}ms",
+                        stats.haptics_latency_s * 1000.0
+                    ),
+                );
+                ui.colored_label(
+                    graph_colors::AUDIO_UNDERRUN,
+                    format!("Audio underruns: {}", stats.audio_underruns),
+                );
+                ui.colored_label(
+                    graph_colors::AUDIO_OVERRUN,
+                    format!("Audio overruns: {}", stats.audio_overruns),
+                );
+            },
+        );
+    }
+
+    fn draw_resource_usage_graph(&self, ui: &mut Ui, available_width: f32) {
+        self.draw_graph(
+            ui,
+            available_width,
+            "Streamer load (%)",
+            0.0..=100.0,
+            |painter, to_screen_trans| {
+                let mut server_cpu = Vec::with_capacity(self.history.len());
+                let mut steamvr_cpu = Vec::with_capacity(self.history.len());
+                let mut game_cpu = Vec::with_capacity(self.history.len());
+                let mut render_gpu = Vec::with_capacity(self.history.len());
+                let mut encoder_gpu = Vec::with_capacity(self.history.len());
+                for i in 0..self.history.len() {
+                    let stats = &self.history[i];
+
+                    server_cpu.push(to_screen_trans * pos2(i as f32, stats.server_cpu_load));
+                    steamvr_cpu.push(to_screen_trans * pos2(i as f32, stats.steamvr_cpu_load));
+                    if let Some(value) = stats.game_cpu_load {
+                        game_cpu.push(to_screen_trans * pos2(i as f32, value));
+                    }
+                    if let Some(value) = stats.render_gpu_load {
+                        render_gpu.push(to_screen_trans * pos2(i as f32, value));
+                    }
+                    if let Some(value) = stats.encoder_gpu_load {
+                        encoder_gpu.push(to_screen_trans * pos2(i as f32, value));
+                    }
+                }
+
+                draw_lines(painter, server_cpu, graph_colors::SERVER_CPU);
+                draw_lines(painter, steamvr_cpu, graph_colors::STEAMVR_CPU);
+                draw_lines(painter, game_cpu, graph_colors::GAME_CPU);
+                draw_lines(painter, render_gpu, graph_colors::RENDER_GPU);
+                draw_lines(painter, encoder_gpu, graph_colors::ENCODER_GPU);
+            },
+            |ui, stats| {
+                fn maybe_label(ui: &mut Ui, text: &str, maybe_value: Option<f32>, color: Color32) {
+                    if let Some(value) = maybe_value {
+                        ui.colored_label(color, &format!("{text}: {value:.0}%"));
+                    } else {
+                        ui.colored_label(color, &format!("{text}: unavailable"));
+                    }
+                }
+
+                maybe_label(
+                    ui,
+                    "Total CPU",
+                    Some(stats.server_cpu_load),
+                    graph_colors::SERVER_CPU,
+                );
+                maybe_label(
+                    ui,
+                    "SteamVR CPU",
+                    Some(stats.steamvr_cpu_load),
+                    graph_colors::STEAMVR_CPU,
+                );
+                maybe_label(ui, "Game CPU", stats.game_cpu_load, graph_colors::GAME_CPU);
+                maybe_label(
+                    ui,
+                    "GPU render",
+                    stats.render_gpu_load,
+                    graph_colors::RENDER_GPU,
+                );
+                maybe_label(
+                    ui,
+                    "GPU encoder",
+                    stats.encoder_gpu_load,
+                    graph_colors::ENCODER_GPU,
+                );
+            },
+        );
+    }
+
+    fn draw_headset_load_graph(&self, ui: &mut Ui, available_width: f32) {
+        self.draw_graph(
+            ui,
+            available_width,
+            "Headset load (%)",
+            0.0..=100.0,
+            |painter, to_screen_trans| {
+                let mut cpu_frequency = Vec::with_capacity(self.history.len());
+                let mut decoder_utilization = Vec::with_capacity(self.history.len());
+                for i in 0..self.history.len() {
+                    let stats = &self.history[i];
+
+                    // Thermal throttling is marked with a full height bar
+                    if stats.client_thermal_status >= ThermalStatus::Moderate {
+                        painter.rect_filled(
+                            Rect {
+                                min: to_screen_trans * pos2(i as f32, 100.0),
+                                max: to_screen_trans * pos2(i as f32 + 2.0, 0.0),
+                            },
+                            Rounding::none(),
+                            graph_colors::THERMAL_THROTTLING,
+                        );
+                    }
+
+                    if let Some(ratio) = stats.client_cpu_frequency_ratio {
+                        cpu_frequency.push(to_screen_trans * pos2(i as f32, ratio * 100.0));
+                    }
+                    decoder_utilization.push(
+                        to_screen_trans * pos2(i as f32, stats.client_decoder_utilization * 100.0),
+                    );
+                }
+
+                draw_lines(painter, cpu_frequency, graph_colors::CLIENT_CPU_FREQUENCY);
+                draw_lines(painter, decoder_utilization, graph_colors::CLIENT_DECODER);
+            },
+            |ui, stats| {
+                ui.label(format!("Thermal status: {:?}", stats.client_thermal_status));
+                if let Some(ratio) = stats.client_cpu_frequency_ratio {
+                    ui.colored_label(
+                        graph_colors::CLIENT_CPU_FREQUENCY,
+                        format!("CPU frequency: {:.0}% of max", ratio * 100.0),
+                    );
+                }
+                ui.colored_label(
+                    graph_colors::CLIENT_DECODER,
+                    format!(
+                        "Decoder utilization: {:.0}%",
+                        stats.client_decoder_utilization * 100.0
+                    ),
+                );
+            },
+        );
+    }
+
     fn draw_statistics_overview(&self, ui: &mut Ui, statistics: &StatisticsSummary) {
         ui.add_space(10.0);
 
@@ -349,9 +931,27 @@ impl StatisticsTab {
             ui[0].label("Bitrate:");
             ui[1].label(&format!("{:.1} Mbps", statistics.video_mbits_per_sec));
 
+            ui[0].label("Encoder QP (min/avg/max):");
+            ui[1].label(&if let (Some(min), Some(avg), Some(max)) = (
+                statistics.encoder_qp_min,
+                statistics.encoder_qp_avg,
+                statistics.encoder_qp_max,
+            ) {
+                format!("{min:.0} / {avg:.1} / {max:.0}")
+            } else {
+                "Not reported by the encoder".into()
+            });
+
             ui[0].label("Total latency:");
             ui[1].label(&format!("{:.0} ms", statistics.total_latency_ms));
 
+            ui[0].label("Estimated motion-to-photon:");
+            ui[1].label(&if let Some(value) = statistics.motion_to_photon_ms {
+                format!("{value:.0} ms")
+            } else {
+                "Move a controller to measure".into()
+            });
+
             ui[0].label("Encoder latency:");
             ui[1].label(&format!("{:.2} ms", statistics.encode_latency_ms));
 
@@ -361,12 +961,85 @@ impl StatisticsTab {
             ui[0].label("Decoder latency:");
             ui[1].label(&format!("{:.2} ms", statistics.decode_latency_ms));
 
+            ui[0].label("Decoder concurrency:");
+            ui[1].label(&format!("{} frames", statistics.decode_concurrency));
+
             ui[0].label("Total packets lost:");
             ui[1].label(&format!(
                 "{} packets ({} packets/s)",
                 statistics.packets_lost_total, statistics.packets_lost_per_sec
             ));
 
+            ui[0].label("Packets dropped (backpressure):");
+            ui[1].label(&format!("{} packets", statistics.packets_dropped_total));
+
+            ui[0].label("Packets reordered:");
+            ui[1].label(&format!(
+                "{} packets ({} packets/s)",
+                statistics.packets_reordered_total, statistics.packets_reordered_per_sec
+            ));
+
+            ui[0].label("Frame drops:");
+            ui[1].label(&format!(
+                "{} frames",
+                statistics
+                    .frame_drops_total
+                    .iter()
+                    .map(|(_, count)| count)
+                    .sum::<usize>()
+            ));
+            for (reason, count) in &statistics.frame_drops_total {
+                ui[0].label(format!("    {}:", reason.label()));
+                ui[1].label(format!("{count}"));
+            }
+
+            ui[0].label("Client control packets:");
+            ui[1].label("");
+            for (kind, rate) in &statistics.control_packets_per_sec {
+                ui[0].label(format!("    {}:", kind.label()));
+                ui[1].label(format!("{rate:.1} packets/s"));
+            }
+
+            ui[0].label("Audio underruns/overruns:");
+            ui[1].label(&format!(
+                "{} / {}",
+                statistics.audio_underruns_total, statistics.audio_overruns_total
+            ));
+
+            if let Some(buffering_ms) = statistics.client_audio_buffering_ms {
+                ui[0].label("Audio buffering (auto-tuned):");
+                ui[1].label(&format!("{buffering_ms:.0} ms"));
+            }
+
+            ui[0].label("Reprojected frames:");
+            ui[1].label(&format!("{}", statistics.reprojected_frames_total));
+
+            ui[0].label("Wi-Fi link events:");
+            ui[1].label(&format!("{}", statistics.wifi_link_events_total));
+
+            ui[0].label("Traffic (sent / received):");
+            ui[1].label("");
+            for (stream, [sent_mbytes, received_mbytes]) in &statistics.stream_traffic_mbytes_total
+            {
+                ui[0].label(format!("    {}:", stream.label()));
+                ui[1].label(format!("{sent_mbytes:.1} / {received_mbytes:.1} MB"));
+            }
+
+            ui[0].label("Controller tracking gaps (L/R):");
+            ui[1].label(&format!(
+                "{} / {} (longest {:.0} / {:.0} ms)",
+                statistics.controller_tracking_gaps_total[0],
+                statistics.controller_tracking_gaps_total[1],
+                statistics.controller_longest_tracking_gap_ms[0],
+                statistics.controller_longest_tracking_gap_ms[1]
+            ));
+
+            ui[0].label("Tracking send jitter:");
+            ui[1].label(&format!("{:.2} ms", statistics.tracking_send_jitter_ms));
+
+            ui[0].label("Haptics delivery latency:");
+            ui[1].label(&format!("{:.2} ms", statistics.haptics_latency_ms));
+
             ui[0].label("Client FPS:");
             ui[1].label(&format!("{} FPS", statistics.client_fps));
 
@@ -383,6 +1056,29 @@ impl StatisticsTab {
                     "unplugged"
                 }
             ));
+
+            ui[0].label("Headset thermal status:");
+            ui[1].label(&format!("{:?}", statistics.client_thermal_status));
+        });
+    }
+
+    // Read-only, the values are derived by the streamer from the latency budget setting
+    fn draw_latency_plan(ui: &mut Ui, plan: &LatencyPlan) {
+        ui.add_space(10.0);
+        ui.heading("Latency budget");
+
+        ui.columns(2, |ui| {
+            ui[0].label("Target motion-to-photon:");
+            ui[1].label(&format!("{:.0} ms", plan.target_ms));
+
+            ui[0].label("Measured motion-to-photon:");
+            ui[1].label(&format!("{:.0} ms", plan.measured_ms));
+
+            ui[0].label("Encoder max saturation:");
+            ui[1].label(&format!("{:.0}%", plan.encoder_max_saturation * 100.0));
+
+            ui[0].label("Client max buffering (next connection):");
+            ui[1].label(&format!("{:.2} frames", plan.client_max_buffering_frames));
         });
     }
 }
@@ -1,8 +1,10 @@
 mod basic_components;
 mod components;
+mod ui_state;
 
 use self::components::{
-    ConnectionsTab, LogsTab, NotificationBar, SettingsTab, SetupWizard, SetupWizardRequest,
+    ConnectionsTab, DebugTab, HistoryTab, JournalTab, LogsTab, NotificationBar, SettingsTab,
+    SetupWizard, SetupWizardRequest,
 };
 use crate::{dashboard::components::StatisticsTab, DataSources};
 use alvr_common::parking_lot::{Condvar, Mutex};
@@ -13,11 +15,13 @@ use alvr_session::SessionConfig;
 use eframe::egui::{
     self, style::Margin, Align, CentralPanel, Frame, Layout, RichText, SidePanel, Stroke,
 };
+use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
     ops::Deref,
     sync::{atomic::AtomicUsize, Arc},
 };
+use ui_state::UiState;
 
 #[derive(Clone)]
 pub struct DisplayString {
@@ -45,10 +49,12 @@ fn get_id() -> usize {
     NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
-enum Tab {
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Tab {
     Connections,
     Statistics,
+    History,
+    Journal,
     Settings,
     #[cfg(not(target_arch = "wasm32"))]
     Installation,
@@ -66,14 +72,19 @@ pub struct Dashboard {
     tab_labels: BTreeMap<Tab, &'static str>,
     connections_tab: ConnectionsTab,
     statistics_tab: StatisticsTab,
+    history_tab: HistoryTab,
+    journal_tab: JournalTab,
     settings_tab: SettingsTab,
     #[cfg(not(target_arch = "wasm32"))]
     installation_tab: components::InstallationTab,
     logs_tab: LogsTab,
+    debug_tab: DebugTab,
     notification_bar: NotificationBar,
     setup_wizard: SetupWizard,
     setup_wizard_open: bool,
     session: Option<SessionConfig>,
+    // Last saved
+    ui_state: UiState,
 }
 
 impl Dashboard {
@@ -84,15 +95,28 @@ impl Dashboard {
         data_sources.request(ServerRequest::GetSession);
         data_sources.request(ServerRequest::GetAudioDevices);
 
+        let ui_state = ui_state::load();
+
+        let mut settings_tab = SettingsTab::new();
+        settings_tab.set_collapsed_sections(
+            ui_state.collapsed_settings_sections.clone(),
+            ui_state.advanced_settings_collapsed,
+        );
+
+        let mut statistics_tab = StatisticsTab::new();
+        statistics_tab.set_graph_history_size(ui_state.graph_history_size);
+
         Self {
             data_sources,
             just_opened: true,
             server_restarting: Arc::new(Mutex::new(false)),
             server_restarting_condvar: Arc::new(Condvar::new()),
-            selected_tab: Tab::Connections,
+            selected_tab: ui_state.selected_tab,
             tab_labels: [
                 (Tab::Connections, "🔌  Connections"),
                 (Tab::Statistics, "📈  Statistics"),
+                (Tab::History, "🕑  History"),
+                (Tab::Journal, "📋  Journal"),
                 (Tab::Settings, "⚙  Settings"),
                 #[cfg(not(target_arch = "wasm32"))]
                 (Tab::Installation, "💾  Installation"),
@@ -103,15 +127,33 @@ impl Dashboard {
             .into_iter()
             .collect(),
             connections_tab: ConnectionsTab::new(),
-            statistics_tab: StatisticsTab::new(),
-            settings_tab: SettingsTab::new(),
+            statistics_tab,
+            history_tab: HistoryTab::new(),
+            journal_tab: JournalTab::new(),
+            settings_tab,
             #[cfg(not(target_arch = "wasm32"))]
             installation_tab: components::InstallationTab::new(),
             logs_tab: LogsTab::new(),
+            debug_tab: DebugTab::new(),
             notification_bar: NotificationBar::new(),
             setup_wizard: SetupWizard::new(),
             setup_wizard_open: false,
             session: None,
+            ui_state,
+        }
+    }
+
+    fn save_ui_state_if_changed(&mut self) {
+        let ui_state = UiState {
+            selected_tab: self.selected_tab,
+            collapsed_settings_sections: self.settings_tab.collapsed_sections().clone(),
+            advanced_settings_collapsed: self.settings_tab.advanced_collapsed(),
+            graph_history_size: self.statistics_tab.graph_history_size(),
+        };
+
+        if ui_state != self.ui_state {
+            ui_state::save(&ui_state);
+            self.ui_state = ui_state;
         }
     }
 
@@ -143,7 +185,7 @@ impl Dashboard {
 }
 
 impl eframe::App for Dashboard {
-    fn update(&mut self, context: &egui::Context, _: &mut eframe::Frame) {
+    fn update(&mut self, context: &egui::Context, frame: &mut eframe::Frame) {
         let mut requests = vec![];
 
         let connected_to_server = self.data_sources.server_connected();
@@ -161,13 +203,35 @@ impl eframe::App for Dashboard {
                 EventType::StatisticsSummary(statistics) => {
                     self.statistics_tab.update_statistics(statistics)
                 }
+                EventType::StreamHistory(history) => self.history_tab.update_history(history),
+                EventType::AbComparisonProgress(progress) => {
+                    self.history_tab.update_ab_comparison_progress(progress)
+                }
+                EventType::AbComparisonReports(reports) => {
+                    self.history_tab.update_ab_comparison_reports(reports)
+                }
+                EventType::EncoderBenchmarkProgress(progress) => {
+                    self.history_tab.update_encoder_benchmark_progress(progress)
+                }
+                EventType::EncoderBenchmarkReport(report) => {
+                    self.history_tab.update_encoder_benchmark_report(report)
+                }
+                EventType::MicrophoneLevel(level) => self.debug_tab.update_microphone_level(level),
+                EventType::MicrophoneLoopback(phase) => {
+                    self.debug_tab.update_microphone_loopback(phase)
+                }
+                EventType::WifiScan(report) => self.statistics_tab.update_wifi_scan(report),
+                EventType::LatencyPlan(plan) => self.statistics_tab.update_latency_plan(plan),
+                EventType::Journal(entry) => self.journal_tab.push_entry(entry),
                 EventType::Session(session) => {
                     let settings = session.to_settings();
 
                     self.connections_tab.update_client_list(&session);
                     self.settings_tab.update_session(&session.session_settings);
+                    self.debug_tab.update_session(&session.session_settings);
                     self.logs_tab.update_settings(&settings);
                     self.notification_bar.update_settings(&settings);
+                    self.statistics_tab.update_settings(&settings);
                     if self.just_opened {
                         if settings.open_setup_wizard {
                             self.setup_wizard_open = true;
@@ -180,6 +244,9 @@ impl eframe::App for Dashboard {
                 }
                 EventType::ServerRequestsSelfRestart => self.restart_steamvr(&mut requests),
                 EventType::AudioDevices(list) => self.settings_tab.update_audio_devices(list),
+                EventType::PairingUri(uri) => self.connections_tab.show_pairing_uri(uri),
+                EventType::StreamPaused(paused) => self.connections_tab.set_stream_paused(paused),
+                EventType::SeatedMode(active) => self.connections_tab.set_seated_mode(active),
                 #[cfg(not(target_arch = "wasm32"))]
                 EventType::DriversList(list) => self.installation_tab.update_drivers(list),
                 _ => (),
@@ -294,6 +361,12 @@ impl eframe::App for Dashboard {
                                     requests.push(request);
                                 }
                             }
+                            Tab::History => {
+                                if let Some(request) = self.history_tab.ui(ui) {
+                                    requests.push(request);
+                                }
+                            }
+                            Tab::Journal => self.journal_tab.ui(ui),
                             Tab::Settings => {
                                 requests.extend(self.settings_tab.ui(ui));
                             }
@@ -314,9 +387,7 @@ impl eframe::App for Dashboard {
                             }
                             Tab::Logs => self.logs_tab.ui(ui),
                             Tab::Debug => {
-                                if let Some(request) = components::debug_tab_ui(ui) {
-                                    requests.push(request);
-                                }
+                                requests.extend(self.debug_tab.ui(ui));
                             }
                             Tab::About => components::about_tab_ui(ui),
                         }
@@ -327,6 +398,20 @@ impl eframe::App for Dashboard {
         for request in requests {
             self.data_sources.request(request);
         }
+
+        self.save_ui_state_if_changed();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.statistics_tab.wants_screenshot() {
+            frame.request_screenshot();
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn post_rendering(&mut self, _: [u32; 2], frame: &eframe::Frame) {
+        if let Some(screenshot) = frame.screenshot() {
+            self.statistics_tab.export_png(&screenshot);
+        }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -0,0 +1,60 @@
+// UI state restored when the dashboard is reopened. It is not part of the session: it is saved by
+// each dashboard installation, and not at all by the web dashboard.
+
+use super::{components::DEFAULT_GRAPH_HISTORY_SIZE, Tab};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct UiState {
+    pub selected_tab: Tab,
+    // Ids of the top level settings sections
+    pub collapsed_settings_sections: BTreeSet<String>,
+    pub advanced_settings_collapsed: bool,
+    pub graph_history_size: usize,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        Self {
+            selected_tab: Tab::Connections,
+            collapsed_settings_sections: BTreeSet::new(),
+            advanced_settings_collapsed: false,
+            graph_history_size: DEFAULT_GRAPH_HISTORY_SIZE,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn ui_state_path() -> std::path::PathBuf {
+    alvr_filesystem::filesystem_layout_from_dashboard_exe(&std::env::current_exe().unwrap())
+        .dashboard_ui_state()
+}
+
+// A missing or corrupted file results in the default state
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load() -> UiState {
+    std::fs::read_to_string(ui_state_path())
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(state: &UiState) {
+    if let Err(e) = std::fs::write(
+        ui_state_path(),
+        serde_json::to_string_pretty(state).unwrap(),
+    ) {
+        alvr_common::warn!("Failed to save the dashboard UI state: {e}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load() -> UiState {
+    UiState::default()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save(_: &UiState) {}
@@ -1,3 +1,4 @@
+use crate::steamvr_launcher;
 use alvr_common::{debug, error, info, parking_lot::Mutex, warn, RelaxedAtomic};
 use alvr_events::{Event, EventType};
 use alvr_packets::ServerRequest;
@@ -171,10 +172,64 @@ impl DataSources {
                                         )
                                     }
                                 }
+                                ServerRequest::GetStreamHistory => {
+                                    let layout =
+                                        alvr_filesystem::filesystem_layout_from_dashboard_exe(
+                                            &env::current_exe().unwrap(),
+                                        );
+
+                                    report_event_local(
+                                        &context,
+                                        &events_sender,
+                                        EventType::StreamHistory(
+                                            alvr_server_io::load_stream_history(
+                                                &layout.stream_history(),
+                                            ),
+                                        ),
+                                    );
+                                    report_event_local(
+                                        &context,
+                                        &events_sender,
+                                        EventType::AbComparisonReports(
+                                            alvr_server_io::load_ab_comparison_reports(
+                                                &layout.ab_comparison_reports(),
+                                            ),
+                                        ),
+                                    );
+                                    report_event_local(
+                                        &context,
+                                        &events_sender,
+                                        EventType::EncoderBenchmarkReport(
+                                            alvr_server_io::load_encoder_benchmark_report(
+                                                &layout.encoder_benchmark(),
+                                            ),
+                                        ),
+                                    )
+                                }
                                 ServerRequest::CaptureFrame
                                 | ServerRequest::InsertIdr
+                                | ServerRequest::CreatePairingToken
+                                | ServerRequest::ClearPairingToken
+                                | ServerRequest::CaptureColocationMarker
+                                | ServerRequest::CalibrateFloorHeight
+                                | ServerRequest::NudgeFloorHeight(_)
+                                | ServerRequest::ToggleSeatedMode
+                                | ServerRequest::PauseStream
+                                | ServerRequest::ResumeStream
+                                | ServerRequest::ResetSessionTimer(_)
+                                | ServerRequest::ScanClientWifi
+                                | ServerRequest::TestHaptics { .. }
+                                | ServerRequest::SetMicrophoneTest(_)
+                                | ServerRequest::StartMicrophoneLoopback
                                 | ServerRequest::StartRecording
-                                | ServerRequest::StopRecording => {
+                                | ServerRequest::StopRecording
+                                | ServerRequest::StartAbComparison
+                                | ServerRequest::StopAbComparison
+                                | ServerRequest::StartEncoderBenchmark
+                                | ServerRequest::StartTrackingRecording
+                                | ServerRequest::StopTrackingRecording
+                                | ServerRequest::ReplayTracking(_)
+                                | ServerRequest::StopTrackingReplay => {
                                     warn!("Cannot perform action, streamer (SteamVR) is not connected.")
                                 }
                                 ServerRequest::RestartSteamvr | ServerRequest::ShutdownSteamvr => {
@@ -269,8 +324,18 @@ impl DataSources {
                             *data_source_lock = DataSource::Remote;
                         } else if !connected && matches!(*data_source_lock, DataSource::Remote) {
                             info!("Server disconnected");
-                            *data_source_lock =
-                                DataSource::Local(Box::new(get_local_data_source()));
+                            let mut data_manager = get_local_data_source();
+
+                            if steamvr_launcher::has_crashed(&data_manager) {
+                                warn!("SteamVR stopped unexpectedly, restarting...");
+                                data_manager.clean_client_list();
+
+                                thread::spawn(|| {
+                                    steamvr_launcher::LAUNCHER.lock().restart_steamvr()
+                                });
+                            }
+
+                            *data_source_lock = DataSource::Local(Box::new(data_manager));
                         }
                     }
 
@@ -1,7 +1,8 @@
 use crate::data_sources;
 use alvr_common::{debug, once_cell::sync::Lazy, parking_lot::Mutex};
 use alvr_filesystem as afs;
-use alvr_session::{DriverLaunchAction, DriversBackup};
+use alvr_server_io::ServerDataManager;
+use alvr_session::{ConnectionState, DriverLaunchAction, DriversBackup};
 use std::{
     env,
     marker::PhantomData,
@@ -31,6 +32,23 @@ pub fn is_steamvr_running() -> bool {
         != 0
 }
 
+// The driver marks all clients as disconnecting when it shuts down. A client still marked as
+// connected means that SteamVR crashed or unloaded the driver.
+pub fn has_crashed(data_manager: &ServerDataManager) -> bool {
+    data_manager
+        .settings()
+        .steamvr_launcher
+        .restart_steamvr_on_crash
+        && data_manager.client_list().values().any(|client| {
+            matches!(
+                client.connection_state,
+                ConnectionState::Connecting
+                    | ConnectionState::Connected
+                    | ConnectionState::Streaming
+            )
+        })
+}
+
 #[cfg(target_os = "linux")]
 pub fn maybe_wrap_vrcompositor_launcher() -> alvr_common::anyhow::Result<()> {
     use std::fs;
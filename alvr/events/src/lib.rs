@@ -1,5 +1,8 @@
 use alvr_common::{info, DeviceMotion, LogEntry, Pose};
-use alvr_packets::{AudioDevicesList, ButtonValue};
+use alvr_packets::{
+    AudioDevicesList, ButtonValue, ControlPacketKind, FrameDropReason, JournalEntry, ThermalStatus,
+    TrafficStream, WifiScanReport,
+};
 use alvr_session::SessionConfig;
 use serde::{Deserialize, Serialize};
 use std::{path::PathBuf, time::Duration};
@@ -14,12 +17,38 @@ pub struct StatisticsSummary {
     pub network_latency_ms: f32,
     pub encode_latency_ms: f32,
     pub decode_latency_ms: f32,
+    pub decode_concurrency: u32,
     pub packets_lost_total: usize,
     pub packets_lost_per_sec: usize,
+    // Received but discarded because they were not read fast enough
+    pub packets_dropped_total: usize,
+    // Received out of order but within the reorder window, so not counted as lost
+    pub packets_reordered_total: usize,
+    pub packets_reordered_per_sec: usize,
+    pub frame_drops_total: Vec<(FrameDropReason, usize)>,
+    // Sent by the client control send thread
+    pub control_packets_per_sec: Vec<(ControlPacketKind, f32)>,
+    pub audio_underruns_total: usize,
+    pub audio_overruns_total: usize,
+    pub client_audio_buffering_ms: Option<f32>,
+    pub reprojected_frames_total: usize,
+    pub wifi_link_events_total: usize,
+    // Since the start of the stream, (sent, received) as seen by the server
+    pub stream_traffic_mbytes_total: Vec<(TrafficStream, [f32; 2])>,
+    // Interruptions of the controller poses, per hand
+    pub controller_tracking_gaps_total: [usize; 2],
+    pub controller_longest_tracking_gap_ms: [f32; 2],
+    pub tracking_send_jitter_ms: f32,
+    pub haptics_latency_ms: f32,
     pub client_fps: u32,
     pub server_fps: u32,
     pub battery_hmd: u32,
     pub hmd_plugged: bool,
+    pub client_thermal_status: ThermalStatus,
+    pub motion_to_photon_ms: Option<f32>,
+    pub encoder_qp_min: Option<f32>,
+    pub encoder_qp_avg: Option<f32>,
+    pub encoder_qp_max: Option<f32>,
 }
 
 // Bitrate statistics minus the empirical output value
@@ -30,6 +59,7 @@ pub struct NominalBitrateStats {
     pub network_latency_limiter_bps: Option<f32>,
     pub encoder_latency_limiter_bps: Option<f32>,
     pub manual_max_bps: Option<f32>,
+    pub client_max_bps: Option<f32>,
     pub manual_min_bps: Option<f32>,
     pub requested_bps: f32,
 }
@@ -49,6 +79,138 @@ pub struct GraphStatistics {
     pub server_fps: f32,
     pub nominal_bitrate: NominalBitrateStats,
     pub actual_bitrate_bps: f32,
+    pub tracking_send_jitter_s: f32,
+    pub haptics_latency_s: f32,
+    pub audio_underruns: u32,
+    pub audio_overruns: u32,
+    pub client_reprojected_frames: u32,
+    pub client_wifi_link_events: u32,
+    pub server_cpu_load: f32,
+    pub steamvr_cpu_load: f32,
+    pub game_cpu_load: Option<f32>,
+    pub render_gpu_load: Option<f32>,
+    pub encoder_gpu_load: Option<f32>,
+    pub client_thermal_status: ThermalStatus,
+    pub client_cpu_frequency_ratio: Option<f32>,
+    pub client_decoder_utilization: f32,
+    pub motion_to_photon_s: Option<f32>,
+    pub encoder_qp: Option<f32>,
+    // Average QP of the blocks inside and outside the foveation center region
+    pub encoder_qp_foveation_center: Option<f32>,
+    pub encoder_qp_foveation_edges: Option<f32>,
+    // User and system events that happened since the previous sample
+    pub markers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StageLatencySummary {
+    pub name: String,
+    pub average_ms: f32,
+    pub p99_ms: f32,
+}
+
+// Generated by the server when a stream ends, used to compare sessions after changing settings
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StreamSummary {
+    pub start_unix_secs: u64,
+    pub duration_s: f32,
+    pub client_hostname: String,
+    pub view_resolution: (u32, u32),
+    pub fps: f32,
+    pub codec: String,
+    pub latency_stages: Vec<StageLatencySummary>,
+    pub video_packets_total: usize,
+    pub packets_lost_total: usize,
+    pub reprojected_frames_total: usize,
+    pub average_bitrate_mbps: f32,
+    pub bitrate_histogram_bucket_mbps: f32,
+    // Number of report intervals that fall in each bitrate bucket
+    pub bitrate_histogram: Vec<u32>,
+    // (sent, received) as seen by the server
+    #[serde(default)]
+    pub traffic_mbytes: Vec<(TrafficStream, [f32; 2])>,
+    // Experiments enabled during the stream
+    #[serde(default)]
+    pub experiments: Vec<String>,
+    pub disconnect_reason: String,
+}
+
+// Parameters currently derived from the latency budget
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LatencyPlan {
+    pub target_ms: f32,
+    pub measured_ms: f32,
+    pub encoder_max_saturation: f32,
+    // Applied at the next connection
+    pub client_max_buffering_frames: f32,
+}
+
+// Statistics of one variant of an A/B comparison, accumulated over all its phases
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AbVariantResult {
+    pub name: String,
+    pub bitrate_mbps: u64,
+    // Empty in the reports saved before the codec could change between variants
+    #[serde(default)]
+    pub codec: String,
+    pub frames: usize,
+    pub average_total_latency_ms: f32,
+    pub average_encoder_latency_ms: f32,
+    pub average_network_latency_ms: f32,
+    pub average_decoder_latency_ms: f32,
+    pub average_actual_bitrate_mbps: f32,
+    pub average_encoder_qp: Option<f32>,
+    pub frame_drops: usize,
+    // Labels of the screenshots requested to the client
+    pub screenshots: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AbComparisonReport {
+    pub start_unix_secs: u64,
+    pub codec: String,
+    pub phase_duration_s: u64,
+    pub rounds: u64,
+    // False if the comparison was stopped early or the client disconnected
+    pub completed: bool,
+    pub results: Vec<AbVariantResult>,
+}
+
+// Encoder measurements for one resolution and bitrate, with synthetic frames
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EncoderBenchmarkResult {
+    pub width: u32,
+    pub height: u32,
+    pub bitrate_mbps: u64,
+    // Resolution of the last stream and bitrate of the current settings
+    pub current_settings: bool,
+    pub frames_per_sec: f32,
+    // From the submission of a frame to its output
+    pub average_latency_ms: f32,
+    pub max_latency_ms: f32,
+    pub average_frame_size_kb: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EncoderBenchmarkReport {
+    pub start_unix_secs: u64,
+    pub codec: String,
+    // False if the benchmark was interrupted or the encoder could not be initialized
+    pub completed: bool,
+    pub results: Vec<EncoderBenchmarkResult>,
+}
+
+// Measured over the last fraction of a second, in dB relative to full scale
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct MicrophoneLevel {
+    pub rms_dbfs: f32,
+    pub peak_dbfs: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MicrophoneLoopbackPhase {
+    Recording,
+    Playing,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -88,6 +250,19 @@ pub enum EventType {
     Haptics(HapticsEvent),
     AudioDevices(AudioDevicesList),
     DriversList(Vec<PathBuf>),
+    StreamHistory(Vec<StreamSummary>),
+    PairingUri(String),
+    StreamPaused(bool),
+    SeatedMode(bool),
+    Journal(JournalEntry),
+    WifiScan(Result<WifiScanReport, String>),
+    LatencyPlan(LatencyPlan),
+    AbComparisonProgress(Option<String>),
+    AbComparisonReports(Vec<AbComparisonReport>),
+    EncoderBenchmarkProgress(Option<String>),
+    EncoderBenchmarkReport(Option<EncoderBenchmarkReport>),
+    MicrophoneLevel(MicrophoneLevel),
+    MicrophoneLoopback(Option<MicrophoneLoopbackPhase>),
     ServerRequestsSelfRestart,
 }
 
@@ -77,6 +77,14 @@ pub fn dashboard_fname() -> &'static str {
     }
 }
 
+pub fn ctl_fname() -> &'static str {
+    if cfg!(windows) {
+        "alvr_ctl.exe"
+    } else {
+        "alvr_ctl"
+    }
+}
+
 // Layout of the ALVR installation. All paths are absolute
 #[derive(Clone)]
 pub struct Layout {
@@ -198,6 +206,10 @@ impl Layout {
         self.executables_dir.join(dashboard_fname())
     }
 
+    pub fn ctl_exe(&self) -> PathBuf {
+        self.executables_dir.join(ctl_fname())
+    }
+
     pub fn resources_dir(&self) -> PathBuf {
         self.openvr_driver_root_dir.join("resources")
     }
@@ -214,6 +226,29 @@ impl Layout {
         self.config_dir.join("session.json")
     }
 
+    // User additions to the per-game tuning database
+    pub fn app_tuning(&self) -> PathBuf {
+        self.config_dir.join("app_tuning.json")
+    }
+
+    // Summaries of the last streaming sessions
+    pub fn stream_history(&self) -> PathBuf {
+        self.config_dir.join("stream_history.json")
+    }
+
+    // Selected tab and other dashboard UI state, restored at the next launch
+    pub fn dashboard_ui_state(&self) -> PathBuf {
+        self.config_dir.join("dashboard_ui_state.json")
+    }
+
+    pub fn ab_comparison_reports(&self) -> PathBuf {
+        self.config_dir.join("ab_comparison_reports.json")
+    }
+
+    pub fn encoder_benchmark(&self) -> PathBuf {
+        self.config_dir.join("encoder_benchmark.json")
+    }
+
     pub fn session_log(&self) -> PathBuf {
         if cfg!(target_os = "linux") {
             self.log_dir.join("alvr_session_log.txt")
@@ -32,8 +32,33 @@ pub mod graph_colors {
     pub const NETWORK: Color32 = Color32::DARK_GRAY;
     pub const TRANSCODE: Color32 = Color32::LIGHT_BLUE;
     pub const IDLE: Color32 = Color32::YELLOW;
+    pub const MOTION_TO_PHOTON: Color32 = Color32::WHITE;
     pub const SERVER_FPS: Color32 = Color32::LIGHT_BLUE;
     pub const CLIENT_FPS: Color32 = Color32::KHAKI;
+    pub const REPROJECTED_FRAME: Color32 = Color32::from_rgb(80, 0, 80);
+    pub const WIFI_LINK_EVENT: Color32 = Color32::from_rgb(0, 160, 255);
+    pub const TRACKING: Color32 = Color32::LIGHT_GREEN;
+    pub const HAPTICS: Color32 = Color32::GOLD;
+    pub const AUDIO_UNDERRUN: Color32 = Color32::RED;
+    pub const AUDIO_OVERRUN: Color32 = Color32::YELLOW;
+    pub const SERVER_CPU: Color32 = Color32::LIGHT_GRAY;
+    pub const STEAMVR_CPU: Color32 = Color32::LIGHT_BLUE;
+    pub const GAME_CPU: Color32 = Color32::from_rgb(255, 50, 0);
+    pub const RENDER_GPU: Color32 = Color32::RED;
+    pub const ENCODER_GPU: Color32 = Color32::GOLD;
+    pub const ENCODER_QP: Color32 = Color32::LIGHT_RED;
+    pub const ENCODER_QP_CENTER: Color32 = Color32::from_rgb(255, 120, 200);
+    pub const ENCODER_QP_EDGES: Color32 = Color32::from_rgb(160, 60, 60);
+    pub const MARKER: Color32 = Color32::from_rgb(255, 200, 0);
+    pub const CLIENT_CPU_FREQUENCY: Color32 = Color32::LIGHT_BLUE;
+    pub const CLIENT_DECODER: Color32 = Color32::KHAKI;
+    pub const THERMAL_THROTTLING: Color32 = Color32::from_rgb(100, 20, 0);
+
+    // Latency heatmap stages. Encode and decode need distinct colors here
+    pub const HEATMAP_ENCODE: Color32 = Color32::LIGHT_BLUE;
+    pub const HEATMAP_NETWORK: Color32 = Color32::LIGHT_GREEN;
+    pub const HEATMAP_DECODE: Color32 = Color32::from_rgb(200, 100, 255);
+    pub const HEATMAP_COMPOSE: Color32 = Color32::RED;
 }
 
 pub fn set_theme(ctx: &Context) {
@@ -1,10 +1,14 @@
 use alvr_common::{
-    glam::{UVec2, Vec2},
+    glam::{Quat, UVec2, Vec2},
     DeviceMotion, Fov, LogEntry, LogSeverity, Pose,
 };
-use alvr_session::{CodecType, ConnectionState, SessionConfig};
+use alvr_session::{
+    ClientCalibration, ClientHardwareInfo, CodecType, ColorBlindnessFilterConfig, ConnectionState,
+    NetworkConditionsConfig, NetworkSimulationConfig, SessionConfig,
+};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     fmt::{self, Debug},
     net::IpAddr,
     path::PathBuf,
@@ -16,12 +20,98 @@ pub const HAPTICS: u16 = 1;
 pub const AUDIO: u16 = 2;
 pub const VIDEO: u16 = 3;
 pub const STATISTICS: u16 = 4;
+// Client to server only, AUDIO carries the game audio
+pub const MICROPHONE: u16 = 5;
+// Server to client only, PNG images of the head-locked overlay layers
+pub const OVERLAY_LAYERS: u16 = 6;
+
+// Network conditions to simulate on the stream socket, by stream ID
+pub fn simulated_network_conditions(
+    config: &NetworkSimulationConfig,
+) -> HashMap<u16, NetworkConditionsConfig> {
+    [
+        TRACKING,
+        HAPTICS,
+        AUDIO,
+        VIDEO,
+        STATISTICS,
+        MICROPHONE,
+        OVERLAY_LAYERS,
+    ]
+    .into_iter()
+    .filter_map(|stream_id| {
+        let conditions = match stream_id {
+            VIDEO => &config.video,
+            TRACKING => &config.tracking,
+            _ => &config.other_streams,
+        };
+
+        conditions
+            .as_option()
+            .map(|conditions| (stream_id, conditions.clone()))
+    })
+    .collect()
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecommendedViewResolutions {
+    pub transcoding: UVec2,
+    pub emulated_headset: UVec2,
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct VideoStreamingCapabilities {
     pub default_view_resolution: UVec2,
     pub supported_refresh_rates: Vec<f32>,
     pub microphone_sample_rate: u32,
+    pub supports_foveated_encoding: bool,
+    // Maximum frame size of the decoders, per codec. Empty if unknown
+    pub max_decoder_resolutions: Vec<(CodecType, UVec2)>,
+    pub supports_binaural_downmix: bool,
+    // Sample rate of the controller PCM haptics, if supported
+    pub pcm_haptics_sample_rate: Option<f32>,
+    // From the built-in table of known headsets. None if the headset model is unknown
+    pub recommended_view_resolutions: Option<RecommendedViewResolutions>,
+    pub supports_extra_latency_mode: bool,
+    pub supports_front_buffer_rendering: bool,
+    // Names from the experiments registry of the client build
+    pub supported_experiments: Vec<String>,
+    pub supports_overlay_layers: bool,
+    // None if the headset is not connected with Wi-Fi or the platform does not tell
+    pub wifi_link: Option<WifiLinkInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct WifiLinkInfo {
+    pub frequency_mhz: u32,
+    pub link_speed_mbps: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandshakeWarningKind {
+    HighLatencyLink,
+    Wifi24Ghz,
+    BitrateAboveLinkSpeed,
+    UnsupportedCodec,
+}
+
+// Suspicious negotiated parameters, shown in the dashboard and in the headset before the stream
+// starts. Sent in the negotiated config
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HandshakeWarning {
+    pub kind: HandshakeWarningKind,
+    pub message: String,
+}
+
+// Sent by the streamer right after connecting, lets the client choose between several streamers
+// and refuse incompatible ones before committing
+#[derive(Serialize, Deserialize)]
+pub struct ServerHandshakeInfo {
+    pub hostname: String,
+    pub version: String,
+    pub protocol_id: u64,
+    pub gpu_names: Vec<String>,
+    pub codec: CodecType,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -31,6 +121,7 @@ pub enum ClientConnectionResult {
         display_name: String,
         server_ip: IpAddr,
         streaming_capabilities: Option<VideoStreamingCapabilities>,
+        hardware_info: ClientHardwareInfo,
     },
     ClientStandby,
 }
@@ -48,6 +139,12 @@ pub struct DecoderInitializationConfig {
     pub config_buffer: Vec<u8>, // e.g. SPS + PPS NALs
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DecoderOverloadReport {
+    pub dropped_frames_per_sec: f32,
+    pub decoder_queue_depth: u32,
+}
+
 #[derive(Serialize, Deserialize)]
 pub enum ServerControlPacket {
     StartStream,
@@ -55,15 +152,40 @@ pub enum ServerControlPacket {
     Restarting,
     KeepAlive,
     ServerPredictionAverage(Duration), // todo: remove
+    DecoderConfigUnchanged,
+    RefreshRateChanged(f32),
+    StreamPaused,
+    StreamResumed,
+    SessionTimeRemaining(Duration),
+    SessionTimeExpired,
+    // Reply to ClientControlPacket::ClockSyncRequest. Times are durations since the UNIX epoch
+    ClockSyncResponse {
+        client_time: Duration,
+        server_time: Duration,
+    },
+    RequestWifiScan,
+    // The label identifies the screenshot in the A/B comparison report
+    CaptureScreenshot(String),
+    // Sent when the setting is changed during the stream
+    ColorBlindnessFilterChanged(Option<ColorBlindnessFilterConfig>),
+    // Replaces the caption currently shown. An empty text hides it
+    Caption {
+        text: String,
+        duration: Duration,
+    },
+    // Sent when the encoder is created. With intra refresh, packet loss is repaired without IDR
+    IntraRefreshChanged(bool),
     Reserved(String),
     ReservedBuffer(Vec<u8>),
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ViewsConfig {
-    // Note: the head-to-eye transform is always a translation along the x axis
+    // Note: the head-to-eye translation is always along the x axis
     pub ipd_m: f32,
     pub fov: [Fov; 2],
+    // Orientation of each view relative to the head. Not identity for canted displays
+    pub orientations: [Quat; 2],
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -73,6 +195,19 @@ pub struct BatteryPacket {
     pub is_plugged: bool,
 }
 
+// Mirrors the Android PowerManager thermal status. The device throttles from Moderate upwards
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ThermalStatus {
+    #[default]
+    None,
+    Light,
+    Moderate,
+    Severe,
+    Critical,
+    Emergency,
+    Shutdown,
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 pub enum ButtonValue {
     Binary(bool),
@@ -95,12 +230,77 @@ pub enum ClientControlPacket {
     Battery(BatteryPacket),
     VideoErrorReport, // legacy
     Buttons(Vec<ButtonEntry>),
-    ActiveInteractionProfile { device_id: u64, profile_id: u64 },
-    Log { level: LogSeverity, message: String },
+    ActiveInteractionProfile {
+        device_id: u64,
+        profile_id: u64,
+    },
+    Log {
+        level: LogSeverity,
+        message: String,
+    },
+    CachedDecoderConfig(DecoderInitializationConfig),
+    DecoderOverload(DecoderOverloadReport), // Replaces RequestIdr when the decoder cannot keep up
+    // Extra decoder options, split by whether the decoder could start with them
+    DecoderOptionsReport {
+        accepted_option_keys: Vec<String>,
+        rejected_option_keys: Vec<String>,
+    },
+    RequestRefreshRate(f32),
+    PauseStream,
+    ResumeStream,
+    Recenter,
+    ToggleSeatedMode,
+    ClockSyncRequest(Duration), // client time since the UNIX epoch
+    JournalEntry(JournalEntry),
+    WifiScanResult(Result<WifiScanReport, String>),
+    PacketSizeProbeReport(Option<u32>), // Largest probe received, None if all were lost
     Reserved(String),
     ReservedBuffer(Vec<u8>),
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    pub bssid: String,
+    pub frequency_mhz: u32, // primary 20 MHz channel
+    // Center of the whole channel, different from frequency_mhz for channels wider than 20 MHz
+    pub center_frequency_mhz: u32,
+    pub channel_width_mhz: u32,
+    pub signal_dbm: i32,
+}
+
+// Networks seen by the headset, requested from the dashboard
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WifiScanReport {
+    pub connected_bssid: Option<String>,
+    pub networks: Vec<WifiNetwork>,
+    // False if the system throttled the scan and the results come from an older scan
+    pub fresh: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JournalSource {
+    Server,
+    Client,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JournalCategory {
+    Connection,
+    Stream,
+    Error,
+}
+
+// Entry of the event journal shared by client and server. The timestamp is the time since the
+// UNIX epoch on the streamer clock, client entries are converted using the estimated clock offset.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+    pub timestamp: Duration,
+    pub source: JournalSource,
+    pub category: JournalCategory,
+    pub message: String,
+}
+
 #[derive(Serialize, Deserialize, Default)]
 pub struct FaceData {
     pub eye_gazes: [Option<Pose>; 2],
@@ -115,6 +315,13 @@ pub struct VideoPacketHeader {
     pub is_idr: bool,
 }
 
+// The payload is a PNG image covering the eye view. An empty payload hides the layer
+#[derive(Serialize, Deserialize)]
+pub struct OverlayLayerHeader {
+    pub layer_index: u32,
+    pub eye_index: u32,
+}
+
 // Note: face_data does not respect target_timestamp.
 #[derive(Serialize, Deserialize, Default)]
 pub struct Tracking {
@@ -124,12 +331,23 @@ pub struct Tracking {
     pub face_data: FaceData,
 }
 
+// Sampled vibration amplitude in the range [-1, 1]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HapticsPcm {
+    pub sample_rate: f32,
+    pub samples: Vec<f32>,
+    // Continue the waveform that is still playing instead of replacing it
+    pub append: bool,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Haptics {
     pub device_id: u64,
     pub duration: Duration,
     pub frequency: f32,
     pub amplitude: f32,
+    // Sent only to clients that support PCM haptics. The other fields are still valid
+    pub pcm: Option<HapticsPcm>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -191,20 +409,151 @@ pub enum ClientListAction {
     SetDisplayName(String),
     Trust,
     SetManualIps(Vec<IpAddr>),
+    SetSessionTimeLimit(Option<u64>), // minutes
+    SetMaxBitrate(Option<u64>),       // Mbps
+    SetCalibration(ClientCalibration),
+    SetHardwareInfo(ClientHardwareInfo),
+    SetRejectedDecoderOptions(Vec<String>),
     RemoveEntry,
     UpdateCurrentIp(Option<IpAddr>),
     SetConnectionState(ConnectionState),
 }
 
+// Why a video frame was not displayed
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FrameDropReason {
+    NetworkLoss,       // one or more frames were lost in transit
+    DecoderSaturation, // not accepted or discarded by the decoder
+    WaitingForIdr,     // received after a loss, discarded until the next IDR frame
+    PacingDiscard,     // decoded but replaced by a newer frame before being composited
+    CompositorLate,    // composited but replaced by a newer frame before being submitted
+}
+
+impl FrameDropReason {
+    pub const ALL: [Self; 5] = [
+        Self::NetworkLoss,
+        Self::DecoderSaturation,
+        Self::WaitingForIdr,
+        Self::PacingDiscard,
+        Self::CompositorLate,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::NetworkLoss => "Network loss",
+            Self::DecoderSaturation => "Decoder saturation",
+            Self::WaitingForIdr => "Waiting for IDR",
+            Self::PacingDiscard => "Pacing discard",
+            Self::CompositorLate => "Compositor late",
+        }
+    }
+}
+
+// Periodic and log packets sent by the client control send thread
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ControlPacketKind {
+    KeepAlive,
+    ClockSync,
+    Journal,
+    Battery,
+    Log,
+}
+
+impl ControlPacketKind {
+    pub const ALL: [Self; 5] = [
+        Self::KeepAlive,
+        Self::ClockSync,
+        Self::Journal,
+        Self::Battery,
+        Self::Log,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::KeepAlive => "Keepalive",
+            Self::ClockSync => "Clock sync",
+            Self::Journal => "Journal",
+            Self::Battery => "Battery",
+            Self::Log => "Log",
+        }
+    }
+}
+
+// Traffic categories of a connection, used for the per stream bandwidth accounting
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TrafficStream {
+    Video,
+    Audio,
+    Microphone,
+    Tracking,
+    Haptics,
+    Statistics,
+    Control,
+}
+
+impl TrafficStream {
+    pub const ALL: [Self; 7] = [
+        Self::Video,
+        Self::Audio,
+        Self::Microphone,
+        Self::Tracking,
+        Self::Haptics,
+        Self::Statistics,
+        Self::Control,
+    ];
+
+    pub fn from_stream_id(stream_id: u16) -> Option<Self> {
+        match stream_id {
+            TRACKING => Some(Self::Tracking),
+            HAPTICS => Some(Self::Haptics),
+            AUDIO => Some(Self::Audio),
+            MICROPHONE => Some(Self::Microphone),
+            VIDEO => Some(Self::Video),
+            STATISTICS => Some(Self::Statistics),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Video => "Video",
+            Self::Audio => "Audio",
+            Self::Microphone => "Microphone",
+            Self::Tracking => "Tracking",
+            Self::Haptics => "Haptics",
+            Self::Statistics => "Statistics",
+            Self::Control => "Control",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct ClientStatistics {
     pub target_timestamp: Duration, // identifies the frame
     pub frame_interval: Duration,
     pub video_decode: Duration,
     pub video_decoder_queue: Duration,
+    pub video_decode_concurrency: u32, // frames inside the decoder when this frame was submitted
     pub rendering: Duration,
     pub vsync_queue: Duration,
     pub total_pipeline_latency: Duration,
+    // Non-video statistics, not tied to this frame
+    pub audio_underruns: u32,    // since the previous report
+    pub audio_overruns: u32,     // since the previous report
+    pub reprojected_frames: u32, // since the previous report, displayed again for missing a new frame
+    pub wifi_link_events: u32,   // since the previous report, reassociations, roaming or power save
+    pub dropped_packets: u32,    // since the previous report, discarded because of backpressure
+    pub reordered_packets: u32,  // since the previous report, out of order within the window
+    pub frame_drops: Vec<(FrameDropReason, u32)>, // since the previous report, non zero counts
+    pub control_packets_sent: Vec<(ControlPacketKind, u32)>, // since the previous report
+    // Chosen by the audio buffering auto-tuner, None if disabled
+    pub audio_buffering: Option<Duration>,
+    pub tracking_send_jitter: Duration,
+    pub haptics_delivery_latency: Duration, // from reception to the frontend
+    pub thermal_status: ThermalStatus,
+    pub cpu_frequency_ratio: Option<f32>, // current over max frequency, lower when throttling
+    pub decoder_utilization: f32,         // range [0, 1]
+    pub motion_to_photon_latency: Option<Duration>, // None until controller motion is detected
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -234,10 +583,112 @@ pub enum ServerRequest {
     InsertIdr,
     StartRecording,
     StopRecording,
+    StartAbComparison,
+    StopAbComparison,
+    StartEncoderBenchmark,
+    StartTrackingRecording,
+    StopTrackingRecording,
+    ReplayTracking(PathBuf),
+    StopTrackingReplay,
     FirewallRules(FirewallRulesAction),
     RegisterAlvrDriver,
     UnregisterDriver(PathBuf),
     GetDriverList,
+    GetStreamHistory,
     RestartSteamvr,
     ShutdownSteamvr,
+    CreatePairingToken,
+    // Sent when the QR code is not shown anymore
+    ClearPairingToken,
+    CaptureColocationMarker,
+    CalibrateFloorHeight,
+    NudgeFloorHeight(f32), // meters
+    ToggleSeatedMode,
+    PauseStream,
+    ResumeStream,
+    ResetSessionTimer(String), // hostname
+    ScanClientWifi,
+    TestHaptics {
+        device_id: u64,
+        duration: Duration,
+        frequency: f32,
+        amplitude: f32,
+    },
+    SetMicrophoneTest(bool),
+    StartMicrophoneLoopback,
+}
+
+// Versioned web API types, served under /api/v1. Within a version, fields can only be added and
+// must be optional for requests. Breaking changes require a new version prefix, while the old one
+// keeps being served.
+pub const WEB_API_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApiVersion {
+    pub api_version: u32,
+    pub server_version: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApiClient {
+    pub hostname: String,
+    pub display_name: String,
+    pub ips: Vec<IpAddr>,
+    pub trusted: bool,
+    pub connected: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiClientAction {
+    Trust,
+    Remove,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApiClientRequest {
+    pub hostname: String,
+    pub action: ApiClientAction,
+}
+
+// Sent by speech-to-text tools. Without a duration, the default from the captions settings is used
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApiCaption {
+    pub text: String,
+    #[serde(default)]
+    pub duration_s: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApiError {
+    pub message: String,
+}
+
+// Local IPC types, exchanged as JSON lines over the Unix socket or Windows named pipe. Same
+// compatibility rules as the web API
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiStreamingState {
+    Disconnected,
+    Connecting,
+    Connected,
+    Streaming,
+    Paused,
+    Disconnecting,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ApiLocalIpcState {
+    pub state: ApiStreamingState,
+    pub client_hostname: Option<String>,
+    pub client_display_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ApiLocalIpcCommand {
+    Disconnect,
+    RestartSteamvr,
+    PauseStream,
+    ResumeStream,
 }
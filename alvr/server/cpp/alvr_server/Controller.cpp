@@ -10,12 +10,13 @@
 
 vr::ETrackedDeviceClass Controller::getControllerDeviceClass() {
     // index == 8/9 == "HTCViveTracker.json"
-    if (Settings::Instance().m_controllerMode == 8 || Settings::Instance().m_controllerMode == 9)
+    if (m_controllerMode == 8 || m_controllerMode == 9)
         return vr::TrackedDeviceClass_GenericTracker;
     return vr::TrackedDeviceClass_Controller;
 }
 
-Controller::Controller(uint64_t deviceID) : TrackedDevice(deviceID) {
+Controller::Controller(uint64_t deviceID)
+    : TrackedDevice(deviceID), m_controllerMode(Settings::Instance().m_controllerMode) {
     m_pose = vr::DriverPose_t{};
     m_pose.poseIsValid = false;
     m_pose.deviceIsConnected = false;
@@ -38,221 +39,126 @@ Controller::Controller(uint64_t deviceID) : TrackedDevice(deviceID) {
 vr::EVRInitError Controller::Activate(vr::TrackedDeviceIndex_t unObjectId) {
     Debug("RemoteController::Activate. objectId=%d\n", unObjectId);
 
+    std::lock_guard<std::mutex> lock(m_inputMutex);
+
     auto vr_properties = vr::VRProperties();
     auto vr_driver_input = vr::VRDriverInput();
 
-    const bool isViveTracker =
-        Settings::Instance().m_controllerMode == 8 || Settings::Instance().m_controllerMode == 9;
+    const bool isViveTracker = m_controllerMode == 8 || m_controllerMode == 9;
     this->object_id = unObjectId;
     this->prop_container = vr_properties->TrackedDeviceToPropertyContainer(this->object_id);
 
     SetOpenvrProps(this->device_id);
 
-    switch (Settings::Instance().m_controllerMode) {
+    CreateInputComponents();
+
+    // The haptic component is the same for all profiles and is kept across profile changes
+    vr_driver_input->CreateHapticComponent(this->prop_container, "/output/haptic", &m_compHaptic);
+
+    // NB: here we set some initial values for the hand skeleton to fix the frozen hand bug
+    {
+        vr::VRBoneTransform_t boneTransforms[SKELETON_BONE_COUNT];
+        GetBoneTransform(false, this->device_id == LEFT_HAND_ID, 0.0, 0.0, 0, boneTransforms);
+
+        vr_driver_input->UpdateSkeletonComponent(m_compSkeleton,
+                                                 vr::VRSkeletalMotionRange_WithController,
+                                                 boneTransforms,
+                                                 SKELETON_BONE_COUNT);
+        vr_driver_input->UpdateSkeletonComponent(m_compSkeleton,
+                                                 vr::VRSkeletalMotionRange_WithoutController,
+                                                 boneTransforms,
+                                                 SKELETON_BONE_COUNT);
+    }
+
+    return vr::VRInitError_None;
+}
+
+void Controller::CreateInputComponents() {
+    switch (m_controllerMode) {
     case 1: // Oculus Rift
     case 7: // Oculus Quest
 
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/system/click", &m_handles[ALVR_INPUT_SYSTEM_CLICK]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/system/touch", &m_handles[ALVR_INPUT_THUMB_REST_TOUCH]);
-        vr_driver_input->CreateBooleanComponent(this->prop_container,
-                                                "/input/application_menu/click",
-                                                &m_handles[ALVR_INPUT_APPLICATION_MENU_CLICK]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/grip/click", &m_handles[ALVR_INPUT_GRIP_CLICK]);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/grip/value",
-                                               &m_handles[ALVR_INPUT_GRIP_VALUE],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedOneSided);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/grip/touch", &m_handles[ALVR_INPUT_GRIP_TOUCH]);
+        CreateBooleanComponent("/input/system/click", ALVR_INPUT_SYSTEM_CLICK);
+        CreateBooleanComponent("/input/system/touch", ALVR_INPUT_THUMB_REST_TOUCH);
+        CreateBooleanComponent("/input/application_menu/click", ALVR_INPUT_APPLICATION_MENU_CLICK);
+        CreateBooleanComponent("/input/grip/click", ALVR_INPUT_GRIP_CLICK);
+        CreateScalarComponent(
+            "/input/grip/value", ALVR_INPUT_GRIP_VALUE, vr::VRScalarUnits_NormalizedOneSided);
+        CreateBooleanComponent("/input/grip/touch", ALVR_INPUT_GRIP_TOUCH);
 
         if (this->device_id == RIGHT_HAND_ID) {
             // A,B for right hand.
-            vr_driver_input->CreateBooleanComponent(
-                this->prop_container, "/input/a/click", &m_handles[ALVR_INPUT_A_CLICK]);
-            vr_driver_input->CreateBooleanComponent(
-                this->prop_container, "/input/a/touch", &m_handles[ALVR_INPUT_A_TOUCH]);
-            vr_driver_input->CreateBooleanComponent(
-                this->prop_container, "/input/b/click", &m_handles[ALVR_INPUT_B_CLICK]);
-            vr_driver_input->CreateBooleanComponent(
-                this->prop_container, "/input/b/touch", &m_handles[ALVR_INPUT_B_TOUCH]);
-
-            vr_driver_input->CreateSkeletonComponent(
-                this->prop_container,
-                "/input/skeleton/right",
-                "/skeleton/hand/right",
-                "/pose/raw",
-                vr::EVRSkeletalTrackingLevel::VRSkeletalTracking_Partial,
-                nullptr,
-                SKELETON_BONE_COUNT,
-                &m_compSkeleton);
+            CreateBooleanComponent("/input/a/click", ALVR_INPUT_A_CLICK);
+            CreateBooleanComponent("/input/a/touch", ALVR_INPUT_A_TOUCH);
+            CreateBooleanComponent("/input/b/click", ALVR_INPUT_B_CLICK);
+            CreateBooleanComponent("/input/b/touch", ALVR_INPUT_B_TOUCH);
+
+            CreateSkeletonComponent(SKELETON_BONE_COUNT);
 
         } else {
             // X,Y for left hand.
-            vr_driver_input->CreateBooleanComponent(
-                this->prop_container, "/input/x/click", &m_handles[ALVR_INPUT_X_CLICK]);
-            vr_driver_input->CreateBooleanComponent(
-                this->prop_container, "/input/x/touch", &m_handles[ALVR_INPUT_X_TOUCH]);
-            vr_driver_input->CreateBooleanComponent(
-                this->prop_container, "/input/y/click", &m_handles[ALVR_INPUT_Y_CLICK]);
-            vr_driver_input->CreateBooleanComponent(
-                this->prop_container, "/input/y/touch", &m_handles[ALVR_INPUT_Y_TOUCH]);
-
-            vr_driver_input->CreateSkeletonComponent(
-                this->prop_container,
-                "/input/skeleton/left",
-                "/skeleton/hand/left",
-                "/pose/raw",
-                vr::EVRSkeletalTrackingLevel::VRSkeletalTracking_Partial,
-                nullptr,
-                SKELETON_BONE_COUNT,
-                &m_compSkeleton);
+            CreateBooleanComponent("/input/x/click", ALVR_INPUT_X_CLICK);
+            CreateBooleanComponent("/input/x/touch", ALVR_INPUT_X_TOUCH);
+            CreateBooleanComponent("/input/y/click", ALVR_INPUT_Y_CLICK);
+            CreateBooleanComponent("/input/y/touch", ALVR_INPUT_Y_TOUCH);
+
+            CreateSkeletonComponent(SKELETON_BONE_COUNT);
         }
 
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/joystick/click", &m_handles[ALVR_INPUT_JOYSTICK_CLICK]);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/joystick/x",
-                                               &m_handles[ALVR_INPUT_JOYSTICK_X],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedTwoSided);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/joystick/y",
-                                               &m_handles[ALVR_INPUT_JOYSTICK_Y],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedTwoSided);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/joystick/touch", &m_handles[ALVR_INPUT_JOYSTICK_TOUCH]);
-
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/back/click", &m_handles[ALVR_INPUT_BACK_CLICK]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/guide/click", &m_handles[ALVR_INPUT_GUIDE_CLICK]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/start/click", &m_handles[ALVR_INPUT_START_CLICK]);
-
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/trigger/click", &m_handles[ALVR_INPUT_TRIGGER_CLICK]);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/trigger/value",
-                                               &m_handles[ALVR_INPUT_TRIGGER_VALUE],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedOneSided);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/trigger/touch", &m_handles[ALVR_INPUT_TRIGGER_TOUCH]);
-
-        vr_driver_input->CreateHapticComponent(
-            this->prop_container, "/output/haptic", &m_compHaptic);
+        CreateBooleanComponent("/input/joystick/click", ALVR_INPUT_JOYSTICK_CLICK);
+        CreateScalarComponent(
+            "/input/joystick/x", ALVR_INPUT_JOYSTICK_X, vr::VRScalarUnits_NormalizedTwoSided);
+        CreateScalarComponent(
+            "/input/joystick/y", ALVR_INPUT_JOYSTICK_Y, vr::VRScalarUnits_NormalizedTwoSided);
+        CreateBooleanComponent("/input/joystick/touch", ALVR_INPUT_JOYSTICK_TOUCH);
+
+        CreateBooleanComponent("/input/back/click", ALVR_INPUT_BACK_CLICK);
+        CreateBooleanComponent("/input/guide/click", ALVR_INPUT_GUIDE_CLICK);
+        CreateBooleanComponent("/input/start/click", ALVR_INPUT_START_CLICK);
+
+        CreateBooleanComponent("/input/trigger/click", ALVR_INPUT_TRIGGER_CLICK);
+        CreateScalarComponent(
+            "/input/trigger/value", ALVR_INPUT_TRIGGER_VALUE, vr::VRScalarUnits_NormalizedOneSided);
+        CreateBooleanComponent("/input/trigger/touch", ALVR_INPUT_TRIGGER_TOUCH);
         break;
     case 3: // Index
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/system/click", &m_handles[ALVR_INPUT_SYSTEM_CLICK]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/a/click", &m_handles[ALVR_INPUT_A_CLICK]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/a/touch", &m_handles[ALVR_INPUT_A_TOUCH]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/b/click", &m_handles[ALVR_INPUT_B_CLICK]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/b/touch", &m_handles[ALVR_INPUT_B_TOUCH]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/trigger/click", &m_handles[ALVR_INPUT_TRIGGER_CLICK]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/trigger/touch", &m_handles[ALVR_INPUT_TRIGGER_TOUCH]);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/trigger/value",
-                                               &m_handles[ALVR_INPUT_TRIGGER_VALUE],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedOneSided);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/trackpad/x",
-                                               &m_handles[ALVR_INPUT_TRACKPAD_X],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedTwoSided);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/trackpad/y",
-                                               &m_handles[ALVR_INPUT_TRACKPAD_Y],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedTwoSided);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/trackpad/force",
-                                               &m_handles[ALVR_INPUT_TRACKPAD_FORCE],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedOneSided);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/trackpad/touch", &m_handles[ALVR_INPUT_TRACKPAD_TOUCH]);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/grip/force",
-                                               &m_handles[ALVR_INPUT_GRIP_FORCE],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedOneSided);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/grip/value",
-                                               &m_handles[ALVR_INPUT_GRIP_VALUE],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedOneSided);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/grip/touch", &m_handles[ALVR_INPUT_GRIP_TOUCH]);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/thumbstick/x",
-                                               &m_handles[ALVR_INPUT_JOYSTICK_X],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedTwoSided);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/thumbstick/y",
-                                               &m_handles[ALVR_INPUT_JOYSTICK_Y],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedTwoSided);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/thumbstick/click", &m_handles[ALVR_INPUT_JOYSTICK_CLICK]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/thumbstick/touch", &m_handles[ALVR_INPUT_JOYSTICK_TOUCH]);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/finger/index",
-                                               &m_handles[ALVR_INPUT_FINGER_INDEX],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedOneSided);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/finger/middle",
-                                               &m_handles[ALVR_INPUT_FINGER_MIDDLE],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedOneSided);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/finger/ring",
-                                               &m_handles[ALVR_INPUT_FINGER_RING],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedOneSided);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/finger/pinky",
-                                               &m_handles[ALVR_INPUT_FINGER_PINKY],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedOneSided);
-        if (this->device_id == LEFT_HAND_ID) {
-            vr_driver_input->CreateSkeletonComponent(
-                this->prop_container,
-                "/input/skeleton/left",
-                "/skeleton/hand/left",
-                "/pose/raw",
-                vr::EVRSkeletalTrackingLevel::VRSkeletalTracking_Partial,
-                nullptr,
-                0U,
-                &m_compSkeleton);
-        } else {
-            vr_driver_input->CreateSkeletonComponent(
-                this->prop_container,
-                "/input/skeleton/right",
-                "/skeleton/hand/right",
-                "/pose/raw",
-                vr::EVRSkeletalTrackingLevel::VRSkeletalTracking_Partial,
-                nullptr,
-                0U,
-                &m_compSkeleton);
-        }
-        vr_driver_input->CreateHapticComponent(
-            this->prop_container, "/output/haptic", &m_compHaptic);
+        CreateBooleanComponent("/input/system/click", ALVR_INPUT_SYSTEM_CLICK);
+        CreateBooleanComponent("/input/a/click", ALVR_INPUT_A_CLICK);
+        CreateBooleanComponent("/input/a/touch", ALVR_INPUT_A_TOUCH);
+        CreateBooleanComponent("/input/b/click", ALVR_INPUT_B_CLICK);
+        CreateBooleanComponent("/input/b/touch", ALVR_INPUT_B_TOUCH);
+        CreateBooleanComponent("/input/trigger/click", ALVR_INPUT_TRIGGER_CLICK);
+        CreateBooleanComponent("/input/trigger/touch", ALVR_INPUT_TRIGGER_TOUCH);
+        CreateScalarComponent(
+            "/input/trigger/value", ALVR_INPUT_TRIGGER_VALUE, vr::VRScalarUnits_NormalizedOneSided);
+        CreateScalarComponent(
+            "/input/trackpad/x", ALVR_INPUT_TRACKPAD_X, vr::VRScalarUnits_NormalizedTwoSided);
+        CreateScalarComponent(
+            "/input/trackpad/y", ALVR_INPUT_TRACKPAD_Y, vr::VRScalarUnits_NormalizedTwoSided);
+        CreateScalarComponent("/input/trackpad/force",
+                              ALVR_INPUT_TRACKPAD_FORCE,
+                              vr::VRScalarUnits_NormalizedOneSided);
+        CreateBooleanComponent("/input/trackpad/touch", ALVR_INPUT_TRACKPAD_TOUCH);
+        CreateScalarComponent(
+            "/input/grip/force", ALVR_INPUT_GRIP_FORCE, vr::VRScalarUnits_NormalizedOneSided);
+        CreateScalarComponent(
+            "/input/grip/value", ALVR_INPUT_GRIP_VALUE, vr::VRScalarUnits_NormalizedOneSided);
+        CreateBooleanComponent("/input/grip/touch", ALVR_INPUT_GRIP_TOUCH);
+        CreateScalarComponent(
+            "/input/thumbstick/x", ALVR_INPUT_JOYSTICK_X, vr::VRScalarUnits_NormalizedTwoSided);
+        CreateScalarComponent(
+            "/input/thumbstick/y", ALVR_INPUT_JOYSTICK_Y, vr::VRScalarUnits_NormalizedTwoSided);
+        CreateBooleanComponent("/input/thumbstick/click", ALVR_INPUT_JOYSTICK_CLICK);
+        CreateBooleanComponent("/input/thumbstick/touch", ALVR_INPUT_JOYSTICK_TOUCH);
+        CreateScalarComponent(
+            "/input/finger/index", ALVR_INPUT_FINGER_INDEX, vr::VRScalarUnits_NormalizedOneSided);
+        CreateScalarComponent(
+            "/input/finger/middle", ALVR_INPUT_FINGER_MIDDLE, vr::VRScalarUnits_NormalizedOneSided);
+        CreateScalarComponent(
+            "/input/finger/ring", ALVR_INPUT_FINGER_RING, vr::VRScalarUnits_NormalizedOneSided);
+        CreateScalarComponent(
+            "/input/finger/pinky", ALVR_INPUT_FINGER_PINKY, vr::VRScalarUnits_NormalizedOneSided);
+        CreateSkeletonComponent(0U);
         break;
     case 9: { // Vive Tracker
         // yes we want to explicitly fallthrough to vive case!, vive trackers can have input when
@@ -261,76 +167,87 @@ vr::EVRInitError Controller::Activate(vr::TrackedDeviceIndex_t unObjectId) {
         [[fallthrough]];
     }
     case 5: // Vive
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/trackpad/touch", &m_handles[ALVR_INPUT_TRACKPAD_TOUCH]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/trackpad/click", &m_handles[ALVR_INPUT_TRACKPAD_CLICK]);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/trackpad/x",
-                                               &m_handles[ALVR_INPUT_TRACKPAD_X],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedTwoSided);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/trackpad/y",
-                                               &m_handles[ALVR_INPUT_TRACKPAD_Y],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedTwoSided);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/trigger/click", &m_handles[ALVR_INPUT_TRIGGER_CLICK]);
-        vr_driver_input->CreateScalarComponent(this->prop_container,
-                                               "/input/trigger/value",
-                                               &m_handles[ALVR_INPUT_TRIGGER_VALUE],
-                                               vr::VRScalarType_Absolute,
-                                               vr::VRScalarUnits_NormalizedOneSided);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/grip/click", &m_handles[ALVR_INPUT_GRIP_CLICK]);
-        vr_driver_input->CreateBooleanComponent(this->prop_container,
-                                                "/input/application_menu/click",
-                                                &m_handles[ALVR_INPUT_APPLICATION_MENU_CLICK]);
-        vr_driver_input->CreateBooleanComponent(
-            this->prop_container, "/input/system/click", &m_handles[ALVR_INPUT_SYSTEM_CLICK]);
-        if (this->device_id == LEFT_HAND_ID) {
-            vr_driver_input->CreateSkeletonComponent(
-                this->prop_container,
-                "/input/skeleton/left",
-                "/skeleton/hand/left",
-                "/pose/raw",
-                vr::EVRSkeletalTrackingLevel::VRSkeletalTracking_Partial,
-                nullptr,
-                0U,
-                &m_compSkeleton);
-        } else {
-            vr_driver_input->CreateSkeletonComponent(
-                this->prop_container,
-                "/input/skeleton/right",
-                "/skeleton/hand/right",
-                "/pose/raw",
-                vr::EVRSkeletalTrackingLevel::VRSkeletalTracking_Partial,
-                nullptr,
-                0U,
-                &m_compSkeleton);
-        }
-        vr_driver_input->CreateHapticComponent(
-            this->prop_container, "/output/haptic", &m_compHaptic);
+        CreateBooleanComponent("/input/trackpad/touch", ALVR_INPUT_TRACKPAD_TOUCH);
+        CreateBooleanComponent("/input/trackpad/click", ALVR_INPUT_TRACKPAD_CLICK);
+        CreateScalarComponent(
+            "/input/trackpad/x", ALVR_INPUT_TRACKPAD_X, vr::VRScalarUnits_NormalizedTwoSided);
+        CreateScalarComponent(
+            "/input/trackpad/y", ALVR_INPUT_TRACKPAD_Y, vr::VRScalarUnits_NormalizedTwoSided);
+        CreateBooleanComponent("/input/trigger/click", ALVR_INPUT_TRIGGER_CLICK);
+        CreateScalarComponent(
+            "/input/trigger/value", ALVR_INPUT_TRIGGER_VALUE, vr::VRScalarUnits_NormalizedOneSided);
+        CreateBooleanComponent("/input/grip/click", ALVR_INPUT_GRIP_CLICK);
+        CreateBooleanComponent("/input/application_menu/click", ALVR_INPUT_APPLICATION_MENU_CLICK);
+        CreateBooleanComponent("/input/system/click", ALVR_INPUT_SYSTEM_CLICK);
+        CreateSkeletonComponent(0U);
         break;
     }
+}
 
-    // NB: here we set some initial values for the hand skeleton to fix the frozen hand bug
-    {
-        vr::VRBoneTransform_t boneTransforms[SKELETON_BONE_COUNT];
-        GetBoneTransform(false, this->device_id == LEFT_HAND_ID, 0.0, 0.0, 0, boneTransforms);
+void Controller::CreateSkeletonComponent(uint32_t gripLimitTransformCount) {
+    // Only one skeleton can exist per device, the first one is kept across profile changes
+    if (m_compSkeleton != vr::k_ulInvalidInputComponentHandle) {
+        return;
+    }
 
-        vr_driver_input->UpdateSkeletonComponent(m_compSkeleton,
-                                                 vr::VRSkeletalMotionRange_WithController,
-                                                 boneTransforms,
-                                                 SKELETON_BONE_COUNT);
-        vr_driver_input->UpdateSkeletonComponent(m_compSkeleton,
-                                                 vr::VRSkeletalMotionRange_WithoutController,
-                                                 boneTransforms,
-                                                 SKELETON_BONE_COUNT);
+    bool isLeftHand = this->device_id == LEFT_HAND_ID;
+    vr::VRDriverInput()->CreateSkeletonComponent(
+        this->prop_container,
+        isLeftHand ? "/input/skeleton/left" : "/input/skeleton/right",
+        isLeftHand ? "/skeleton/hand/left" : "/skeleton/hand/right",
+        "/pose/raw",
+        vr::EVRSkeletalTrackingLevel::VRSkeletalTracking_Partial,
+        nullptr,
+        gripLimitTransformCount,
+        &m_compSkeleton);
+}
+
+void Controller::CreateBooleanComponent(const char *path, int inputIdx) {
+    auto component = m_components.find(path);
+    if (component == m_components.end()) {
+        vr::VRDriverInput()->CreateBooleanComponent(
+            this->prop_container, path, &m_handles[inputIdx]);
+        m_components[path] = m_handles[inputIdx];
+    } else {
+        m_handles[inputIdx] = component->second;
     }
+}
 
-    return vr::VRInitError_None;
+void Controller::CreateScalarComponent(const char *path, int inputIdx, vr::EVRScalarUnits units) {
+    auto component = m_components.find(path);
+    if (component == m_components.end()) {
+        vr::VRDriverInput()->CreateScalarComponent(this->prop_container,
+                                                   path,
+                                                   &m_handles[inputIdx],
+                                                   vr::VRScalarType_Absolute,
+                                                   units);
+        m_components[path] = m_handles[inputIdx];
+    } else {
+        m_handles[inputIdx] = component->second;
+    }
+}
+
+void Controller::ReloadProfile(int controllerMode) {
+    std::lock_guard<std::mutex> lock(m_inputMutex);
+
+    m_controllerMode = controllerMode;
+
+    if (this->object_id == vr::k_unTrackedDeviceIndexInvalid) {
+        return;
+    }
+
+    SetOpenvrProps(this->device_id);
+
+    // OpenVR cannot remove input components. Components are reused when a profile declares a path
+    // that was already created, the others stay registered but are not updated anymore
+    for (int i = 0; i < ALVR_INPUT_COUNT; i++) {
+        m_handles[i] = vr::k_ulInvalidInputComponentHandle;
+    }
+    CreateInputComponents();
+
+    // Let SteamVR reload the bindings for the new input profile
+    vr::VRServerDriverHost()->VendorSpecificEvent(
+        this->object_id, vr::VREvent_TrackedDeviceUpdated, {}, 0);
 }
 
 void Controller::Deactivate() {
@@ -361,6 +278,8 @@ vr::DriverPose_t Controller::GetPose() { return m_pose; }
 vr::VRInputComponentHandle_t Controller::getHapticComponent() { return m_compHaptic; }
 
 void Controller::SetButton(uint64_t id, FfiButtonValue value) {
+    std::lock_guard<std::mutex> lock(m_inputMutex);
+
     if (value.type == BUTTON_TYPE_BINARY) {
         uint32_t flag;
         if (id == MENU_CLICK_ID) {
@@ -407,7 +326,7 @@ void Controller::SetButton(uint64_t id, FfiButtonValue value) {
 
     auto vr_driver_input = vr::VRDriverInput();
 
-    switch (Settings::Instance().m_controllerMode) {
+    switch (m_controllerMode) {
     case 3: // Valve Index
         vr_driver_input->UpdateBooleanComponent(
             m_handles[ALVR_INPUT_SYSTEM_CLICK],
@@ -636,6 +555,8 @@ bool Controller::onPoseUpdate(float predictionS,
                               FfiDeviceMotion motion,
                               const FfiHandSkeleton *handSkeleton,
                               unsigned int controllersTracked) {
+    std::lock_guard<std::mutex> lock(m_inputMutex);
+
     if (this->object_id == vr::k_unTrackedDeviceIndexInvalid) {
         return false;
     }
@@ -674,7 +595,7 @@ bool Controller::onPoseUpdate(float predictionS,
 
     if (handSkeleton != nullptr) {
         vr::VRBoneTransform_t boneTransform[SKELETON_BONE_COUNT];
-        for (int j = 0; j < 26; j++) {
+        for (int j = 0; j < SKELETON_BONE_COUNT; j++) {
             boneTransform[j].orientation.w = handSkeleton->jointRotations[j].w;
             boneTransform[j].orientation.x = handSkeleton->jointRotations[j].x;
             boneTransform[j].orientation.y = handSkeleton->jointRotations[j].y;
@@ -694,24 +615,13 @@ bool Controller::onPoseUpdate(float predictionS,
                                                  boneTransform,
                                                  SKELETON_BONE_COUNT);
 
-        float rotThumb = (handSkeleton->jointRotations[2].z + handSkeleton->jointRotations[2].y +
-                          handSkeleton->jointRotations[3].z + handSkeleton->jointRotations[3].y +
-                          handSkeleton->jointRotations[4].z + handSkeleton->jointRotations[4].y) *
-                         0.67f;
-        float rotIndex = (handSkeleton->jointRotations[7].z + handSkeleton->jointRotations[8].z +
-                          handSkeleton->jointRotations[9].z) *
-                         0.67f;
-        float rotMiddle = (handSkeleton->jointRotations[12].z + handSkeleton->jointRotations[13].z +
-                           handSkeleton->jointRotations[14].z) *
-                          0.67f;
-        float rotRing = (handSkeleton->jointRotations[17].z + handSkeleton->jointRotations[18].z +
-                         handSkeleton->jointRotations[19].z) *
-                        0.67f;
-        float rotPinky = (handSkeleton->jointRotations[22].z + handSkeleton->jointRotations[23].z +
-                          handSkeleton->jointRotations[24].z) *
-                         0.67f;
-
-        switch (Settings::Instance().m_controllerMode) {
+        float rotThumb = handSkeleton->fingerCurls[0];
+        float rotIndex = handSkeleton->fingerCurls[1];
+        float rotMiddle = handSkeleton->fingerCurls[2];
+        float rotRing = handSkeleton->fingerCurls[3];
+        float rotPinky = handSkeleton->fingerCurls[4];
+
+        switch (m_controllerMode) {
         case 1:
         case 3:
         case 7:
@@ -753,7 +663,7 @@ bool Controller::onPoseUpdate(float predictionS,
         }
 
         // Valve Index
-        if (Settings::Instance().m_controllerMode == 3) {
+        if (m_controllerMode == 3) {
             float indexCurl = 0.0;
             if (m_triggerValue > 0.0) {
                 indexCurl = 0.5 + m_triggerValue * 0.5;
@@ -4,6 +4,8 @@
 #include "TrackedDevice.h"
 #include "openvr_driver.h"
 #include <map>
+#include <mutex>
+#include <string>
 
 class Controller : public TrackedDevice, public vr::ITrackedDeviceServerDriver {
   public:
@@ -49,16 +51,34 @@ class Controller : public TrackedDevice, public vr::ITrackedDeviceServerDriver {
 
     vr::ETrackedDeviceClass getControllerDeviceClass();
 
+    // Switches the emulated controller profile of the running device
+    void ReloadProfile(int controllerMode);
+
+    void SetPresence(FfiDevicePresence presence) { set_presence(m_pose, presence); }
+
   private:
     static const int SKELETON_BONE_COUNT = 31;
     static const int ANIMATION_FRAME_COUNT = 15;
 
     vr::VRInputComponentHandle_t m_handles[ALVR_INPUT_COUNT];
-    vr::VRInputComponentHandle_t m_compHaptic;
+    vr::VRInputComponentHandle_t m_compHaptic = vr::k_ulInvalidInputComponentHandle;
     vr::VRInputComponentHandle_t m_compSkeleton = vr::k_ulInvalidInputComponentHandle;
 
+    // Input components created so far by path, they are shared by all profiles
+    std::map<std::string, vr::VRInputComponentHandle_t> m_components;
+
     vr::DriverPose_t m_pose;
 
+    // Guards the profile and the input handles, which are changed from the server thread while
+    // the tracking thread updates the inputs
+    std::mutex m_inputMutex;
+    int m_controllerMode;
+
+    void CreateInputComponents();
+    void CreateBooleanComponent(const char *path, int inputIdx);
+    void CreateScalarComponent(const char *path, int inputIdx, vr::EVRScalarUnits units);
+    void CreateSkeletonComponent(uint32_t gripLimitTransformCount);
+
     float m_thumbTouchAnimationProgress = 0;
     float m_indexTouchAnimationProgress = 0;
     uint64_t m_lastThumbTouch = 0;
@@ -0,0 +1,80 @@
+#include "EncoderQp.h"
+
+#include "Settings.h"
+#include <math.h>
+
+namespace {
+
+struct CenterBounds {
+    float lo;
+    float hi;
+};
+
+// Bounds of the center region along one axis of an eye, in encoded eye coordinates. Mirrors the
+// alignment done in the foveated encoding setup and the mapping of CompressAxisAlignedPixelShader
+CenterBounds GetCenterBounds(float targetSize, float centerSize, float centerShift, float edgeRatio) {
+    if (!Settings::Instance().m_enableFoveatedRendering) {
+        float edge = (1.f - centerSize) / 2.f;
+        return {edge * (centerShift + 1.f), edge * (centerShift + 1.f) + centerSize};
+    }
+
+    float edgeSize = targetSize - centerSize * targetSize;
+    float centerSizeAligned = 1.f - ceil(edgeSize / (edgeRatio * 2.f)) * (edgeRatio * 2.f) / targetSize;
+    float edgeSizeAligned = targetSize - centerSizeAligned * targetSize;
+    float centerShiftAligned = edgeSizeAligned > 0.f
+        ? ceil(centerShift * edgeSizeAligned / (edgeRatio * 2.f)) * (edgeRatio * 2.f) / edgeSizeAligned
+        : 0.f;
+
+    float optimizedSize = (centerSizeAligned + (1.f - centerSizeAligned) / edgeRatio) * targetSize;
+    float sizeRatio = optimizedSize / (ceil(optimizedSize / 32.f) * 32.f);
+
+    float c0 = (1.f - centerSizeAligned) / 2.f;
+    float c2 = (edgeRatio - 1.f) * centerSizeAligned + 1.f;
+
+    return {c0 * (centerShiftAligned + 1.f) / c2 * sizeRatio, (c0 * (centerShiftAligned - 1.f) / c2 + 1.f) * sizeRatio};
+}
+
+} // namespace
+
+FfiEncoderQp ComputeRegionQp(float averageQp, const uint32_t *blockQp, uint32_t blocksX, uint32_t blocksY, size_t rowPitch) {
+    FfiEncoderQp qp = {averageQp, -1.f, -1.f};
+    if (blockQp == nullptr || blocksX == 0 || blocksY == 0) {
+        return qp;
+    }
+
+    auto &settings = Settings::Instance();
+    CenterBounds boundsX = GetCenterBounds((float)settings.m_renderWidth / 2, settings.m_foveationCenterSizeX,
+                                           settings.m_foveationCenterShiftX, settings.m_foveationEdgeRatioX);
+    CenterBounds boundsY = GetCenterBounds((float)settings.m_renderHeight, settings.m_foveationCenterSizeY,
+                                           settings.m_foveationCenterShiftY, settings.m_foveationEdgeRatioY);
+
+    double centerSum = 0, edgesSum = 0;
+    uint32_t centerCount = 0, edgesCount = 0;
+    for (uint32_t y = 0; y < blocksY; y++) {
+        const uint32_t *row = (const uint32_t *)((const uint8_t *)blockQp + y * rowPitch);
+        float v = (y + 0.5f) / blocksY;
+
+        for (uint32_t x = 0; x < blocksX; x++) {
+            // The right eye is mirrored horizontally
+            float u = (x + 0.5f) / blocksX;
+            float eyeU = u < 0.5f ? u * 2.f : (1.f - u) * 2.f;
+
+            if (eyeU > boundsX.lo && eyeU < boundsX.hi && v > boundsY.lo && v < boundsY.hi) {
+                centerSum += row[x];
+                centerCount++;
+            } else {
+                edgesSum += row[x];
+                edgesCount++;
+            }
+        }
+    }
+
+    if (centerCount > 0) {
+        qp.foveationCenter = (float)(centerSum / centerCount);
+    }
+    if (edgesCount > 0) {
+        qp.foveationEdges = (float)(edgesSum / edgesCount);
+    }
+
+    return qp;
+}
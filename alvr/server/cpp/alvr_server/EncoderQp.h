@@ -0,0 +1,10 @@
+#pragma once
+
+#include <stddef.h>
+#include <stdint.h>
+#include "bindings.h"
+
+// Averages a block level QP map of the encoded frame, both eyes side by side in raster order,
+// separately for the blocks inside the foveation center region and for the compressed edges.
+// The center region from the foveation settings is used even if foveated encoding is disabled.
+FfiEncoderQp ComputeRegionQp(float averageQp, const uint32_t *blockQp, uint32_t blocksX, uint32_t blocksY, size_t rowPitch);
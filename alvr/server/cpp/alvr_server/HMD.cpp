@@ -40,6 +40,8 @@ Hmd::Hmd()
     this->views_config.ipd_m = 0.063;
     this->views_config.fov[0] = dummy_fov;
     this->views_config.fov[1] = dummy_fov;
+    this->views_config.orientations[0] = FfiQuat{0.0, 0.0, 0.0, 1.0};
+    this->views_config.orientations[1] = FfiQuat{0.0, 0.0, 0.0, 1.0};
 
     m_pose = vr::DriverPose_t{};
     m_pose.poseIsValid = true;
@@ -267,14 +269,51 @@ void Hmd::StartStreaming() {
     m_streamComponentsInitialized = true;
 }
 
+#ifdef _WIN32
+void Hmd::SetOverlayLayersMaxFps(float maxFps) {
+    if (m_directModeComponent) {
+        m_directModeComponent->SetOverlayLayersMaxFps(maxFps);
+    }
+}
+
+void Hmd::SetMirrorCapture(unsigned int maxWidth, float maxFps, bool bothEyes) {
+    if (m_directModeComponent) {
+        m_directModeComponent->SetMirrorCapture(maxWidth, maxFps, bothEyes);
+    }
+}
+#endif
+
+void Hmd::SetRefreshRate(float refreshRate) {
+    if (this->object_id == vr::k_unTrackedDeviceIndexInvalid) {
+        return;
+    }
+
+    // SteamVR paces the frames using the display frequency
+    vr::VRProperties()->SetFloatProperty(
+        this->prop_container, vr::Prop_DisplayFrequency_Float, refreshRate);
+}
+
 void Hmd::StopStreaming() { vr::VRDriverInput()->UpdateBooleanComponent(m_proximity, false, 0.0); }
 
 void Hmd::SetViewsConfig(FfiViewsConfig config) {
     this->views_config = config;
 
+    // Canted displays have a rotation between the views and the head
     auto left_transform = MATRIX_IDENTITY;
+    auto left_orientation = config.orientations[0];
+    HmdMatrix_QuatToMat(left_orientation.w,
+                        left_orientation.x,
+                        left_orientation.y,
+                        left_orientation.z,
+                        &left_transform);
     left_transform.m[0][3] = -config.ipd_m / 2.0;
     auto right_transform = MATRIX_IDENTITY;
+    auto right_orientation = config.orientations[1];
+    HmdMatrix_QuatToMat(right_orientation.w,
+                        right_orientation.x,
+                        right_orientation.y,
+                        right_orientation.z,
+                        &right_transform);
     right_transform.m[0][3] = config.ipd_m / 2.0;
     vr::VRServerDriverHost()->SetDisplayEyeToHead(object_id, left_transform, right_transform);
 
@@ -37,8 +37,12 @@ class Hmd : public TrackedDevice, public vr::ITrackedDeviceServerDriver, vr::IVR
 
     void StopStreaming();
 
+    void SetPresence(FfiDevicePresence presence) { set_presence(m_pose, presence); }
+
     void SetViewsConfig(FfiViewsConfig config);
 
+    void SetRefreshRate(float refreshRate);
+
     vr::ETrackedDeviceClass GetDeviceClass() const { return m_deviceClass; }
     bool IsTrackingRef() const { return m_deviceClass == vr::TrackedDeviceClass_TrackingReference; }
     bool IsHMD() const { return m_deviceClass == vr::TrackedDeviceClass_HMD; }
@@ -60,6 +64,12 @@ class Hmd : public TrackedDevice, public vr::ITrackedDeviceServerDriver, vr::IVR
     std::shared_ptr<CEncoder> m_encoder;
     std::shared_ptr<PoseHistory> m_poseHistory;
 
+#ifdef _WIN32
+    std::shared_ptr<CD3DRender> GetD3DRender() { return m_D3DRender; }
+    void SetOverlayLayersMaxFps(float maxFps);
+    void SetMirrorCapture(unsigned int maxWidth, float maxFps, bool bothEyes);
+#endif
+
   private:
     FfiViewsConfig views_config;
 
@@ -52,6 +52,10 @@ void Settings::Load() {
         m_foveationEdgeRatioX = (float)config.get("foveation_edge_ratio_x").get<double>();
         m_foveationEdgeRatioY = (float)config.get("foveation_edge_ratio_y").get<double>();
 
+        m_enableGazeRoi = config.get("enable_gaze_roi").get<bool>();
+        m_gazeRoiRadius = (float)config.get("gaze_roi_radius").get<double>();
+        m_gazeRoiQualityBoost = (float)config.get("gaze_roi_quality_boost").get<double>();
+
         m_enableColorCorrection = config.get("enable_color_correction").get<bool>();
         m_brightness = (float)config.get("brightness").get<double>();
         m_contrast = (float)config.get("contrast").get<double>();
@@ -64,6 +68,9 @@ void Settings::Load() {
         m_fillerData = config.get("filler_data").get<bool>();
         m_entropyCoding = (uint32_t)config.get("entropy_coding").get<int64_t>();
         m_use10bitEncoder = config.get("use_10bit_encoder").get<bool>();
+        m_sliceCount = (uint32_t)config.get("slice_count").get<int64_t>();
+        m_periodicIntraRefreshFrames =
+            (uint32_t)config.get("periodic_intra_refresh_frames").get<int64_t>();
         m_enableVbaq = config.get("enable_vbaq").get<bool>();
         m_usePreproc = config.get("use_preproc").get<bool>();
         m_preProcSigma = (uint32_t)config.get("preproc_sigma").get<int64_t>();
@@ -115,7 +122,7 @@ void Settings::Load() {
                              .get("variant").get<std::string>() == "ConstantMbps";
 
         Info("Render Target: %d %d\n", m_renderWidth, m_renderHeight);
-        Info("Refresh Rate: %d\n", m_refreshRate);
+        Info("Refresh Rate: %d\n", m_refreshRate.load());
         m_loaded = true;
     } catch (std::exception &e) {
         Error("Exception on parsing json: %hs\n", e.what());
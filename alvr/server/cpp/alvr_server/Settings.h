@@ -1,6 +1,7 @@
 #pragma once
 
 #include "ALVR-common/packet_types.h"
+#include <atomic>
 #include <string>
 
 class Settings {
@@ -16,7 +17,8 @@ class Settings {
 
     bool IsLoaded() { return m_loaded; }
 
-    int m_refreshRate;
+    // Can be changed by the client while streaming
+    std::atomic<int> m_refreshRate;
     uint32_t m_renderWidth;
     uint32_t m_renderHeight;
     int32_t m_recommendedTargetWidth;
@@ -32,6 +34,10 @@ class Settings {
     float m_foveationEdgeRatioX;
     float m_foveationEdgeRatioY;
 
+    bool m_enableGazeRoi;
+    float m_gazeRoiRadius;
+    float m_gazeRoiQualityBoost;
+
     bool m_enableColorCorrection;
     float m_brightness;
     float m_contrast;
@@ -41,6 +47,8 @@ class Settings {
 
     int m_codec;
     bool m_use10bitEncoder;
+    uint32_t m_sliceCount;
+    uint32_t m_periodicIntraRefreshFrames;
     bool m_enableVbaq;
     bool m_usePreproc;
     uint32_t m_preProcSigma;
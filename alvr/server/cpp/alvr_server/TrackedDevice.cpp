@@ -56,3 +56,35 @@ void TrackedDevice::set_prop(FfiOpenvrProperty prop) {
     vr::VRServerDriverHost()->VendorSpecificEvent(
         this->object_id, vr::VREvent_PropertyChanged, event_data, 0.);
 }
+
+void TrackedDevice::set_presence(vr::DriverPose_t &pose, FfiDevicePresence presence) {
+    if (this->object_id == vr::k_unTrackedDeviceIndexInvalid ||
+        presence == DEVICE_PRESENCE_TRACKING) {
+        return;
+    }
+
+    if (presence == DEVICE_PRESENCE_FROZEN) {
+        for (int i = 0; i < 3; i++) {
+            pose.vecVelocity[i] = 0;
+            pose.vecAngularVelocity[i] = 0;
+            pose.vecAcceleration[i] = 0;
+            pose.vecAngularAcceleration[i] = 0;
+        }
+        vr::VRServerDriverHost()->TrackedDevicePoseUpdated(
+            this->object_id, pose, sizeof(vr::DriverPose_t));
+
+        return;
+    }
+
+    pose.poseIsValid = false;
+    if (presence == DEVICE_PRESENCE_NOT_TRACKING) {
+        pose.result = vr::TrackingResult_Running_OutOfRange;
+        pose.deviceIsConnected = true;
+    } else {
+        pose.result = vr::TrackingResult_Uninitialized;
+        pose.deviceIsConnected = false;
+    }
+
+    vr::VRServerDriverHost()->TrackedDevicePoseUpdated(
+        this->object_id, pose, sizeof(vr::DriverPose_t));
+}
@@ -15,4 +15,8 @@ class TrackedDevice {
     std::string get_serial_number();
 
     void set_prop(FfiOpenvrProperty prop);
+
+    // Updates the validity of the last pose and submits it. A tracking presence is restored by
+    // the next pose update
+    void set_presence(vr::DriverPose_t &pose, FfiDevicePresence presence);
 };
\ No newline at end of file
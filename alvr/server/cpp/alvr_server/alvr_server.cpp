@@ -1,5 +1,6 @@
 #ifdef _WIN32
 #include "platform/win32/CEncoder.h"
+#include "platform/win32/EncoderBenchmark.h"
 #include <windows.h>
 #elif __APPLE__
 #include "platform/macos/CEncoder.h"
@@ -136,6 +137,8 @@ class DriverProvider : public vr::IServerTrackedDeviceProvider {
                 }
 
                 HapticsSend(id, haptics.fDurationSeconds, haptics.fFrequency, haptics.fAmplitude);
+            } else if (event.eventType == vr::VREvent_SceneApplicationChanged) {
+                ReportSceneApplication(event.data.process.pid);
             }
 #ifdef __linux__
             else if (event.eventType == vr::VREvent_ChaperoneUniverseHasChanged) {
@@ -190,9 +193,19 @@ void (*ShutdownRuntime)();
 unsigned long long (*PathStringToHash)(const char *path);
 void (*ReportPresent)(unsigned long long timestamp_ns, unsigned long long offset_ns);
 void (*ReportComposed)(unsigned long long timestamp_ns, unsigned long long offset_ns);
+void (*ReportEncoderQp)(unsigned long long timestamp_ns, FfiEncoderQp qp);
+void (*ReportSoftwareEncoderFallback)(const char *hardwareErrors);
+void (*ReportIntraRefresh)(bool enabled);
+void (*ReportOverlayLayer)(unsigned int layerIndex,
+                           unsigned int eyeIndex,
+                           unsigned int width,
+                           unsigned int height,
+                           const unsigned char *rgba);
+void (*ReportMirrorFrame)(unsigned int width, unsigned int height, const unsigned char *rgba);
 FfiDynamicEncoderParams (*GetDynamicEncoderParams)();
 unsigned long long (*GetSerialNumber)(unsigned long long deviceID, char *outString);
 void (*SetOpenvrProps)(unsigned long long deviceID);
+void (*ReportSceneApplication)(unsigned int pid);
 void (*WaitForVSync)();
 
 void *CppEntryPoint(const char *interface_name, int *return_code) {
@@ -236,6 +249,12 @@ void RequestIDR() {
     }
 }
 
+void SetEncoderCodec(int codec) {
+    if (g_driver_provider.hmd && g_driver_provider.hmd->m_encoder) {
+        g_driver_provider.hmd->m_encoder->SetCodec(codec);
+    }
+}
+
 void SetTracking(unsigned long long targetTimestampNs,
                  float controllerPoseTimeOffsetS,
                  const FfiDeviceMotion *deviceMotions,
@@ -310,6 +329,35 @@ void SetButton(unsigned long long path, FfiButtonValue value) {
     }
 }
 
+void SetControllerProfile(int modeIdx) {
+    if (g_driver_provider.left_controller) {
+        g_driver_provider.left_controller->ReloadProfile(modeIdx);
+    }
+    if (g_driver_provider.right_controller) {
+        g_driver_provider.right_controller->ReloadProfile(modeIdx);
+    }
+}
+
+void SetDevicesPresence(FfiDevicePresence presence) {
+    if (g_driver_provider.hmd) {
+        g_driver_provider.hmd->SetPresence(presence);
+    }
+    if (g_driver_provider.left_controller) {
+        g_driver_provider.left_controller->SetPresence(presence);
+    }
+    if (g_driver_provider.right_controller) {
+        g_driver_provider.right_controller->SetPresence(presence);
+    }
+}
+
+void SetControllerPresence(unsigned long long deviceID, FfiDevicePresence presence) {
+    if (g_driver_provider.left_controller && deviceID == LEFT_HAND_ID) {
+        g_driver_provider.left_controller->SetPresence(presence);
+    } else if (g_driver_provider.right_controller && deviceID == RIGHT_HAND_ID) {
+        g_driver_provider.right_controller->SetPresence(presence);
+    }
+}
+
 void CaptureFrame() {
 #ifndef __APPLE__
     if (g_driver_provider.hmd && g_driver_provider.hmd->m_encoder) {
@@ -317,3 +365,79 @@ void CaptureFrame() {
     }
 #endif
 }
+
+void SetGazeRoi(FfiGazeRoi roi) {
+#ifdef __linux__
+    if (g_driver_provider.hmd && g_driver_provider.hmd->m_encoder) {
+        g_driver_provider.hmd->m_encoder->SetGazeRoi(roi);
+    }
+#endif
+}
+
+void SetRefreshRate(float refreshRate) {
+    // The encoders pick up the new framerate with the next dynamic encoder parameters
+    Settings::Instance().m_refreshRate = (int)refreshRate;
+
+    if (g_driver_provider.hmd) {
+        g_driver_provider.hmd->SetRefreshRate(refreshRate);
+    }
+}
+
+void SetOverlayLayersMaxFps(float maxFps) {
+#ifdef _WIN32
+    if (g_driver_provider.hmd) {
+        g_driver_provider.hmd->SetOverlayLayersMaxFps(maxFps);
+    }
+#endif
+}
+
+void SetMirrorCapture(unsigned int maxWidth, float maxFps, bool bothEyes) {
+#ifdef _WIN32
+    if (g_driver_provider.hmd) {
+        g_driver_provider.hmd->SetMirrorCapture(maxWidth, maxFps, bothEyes);
+    }
+#elif __linux__
+    if (g_driver_provider.hmd && g_driver_provider.hmd->m_encoder) {
+        g_driver_provider.hmd->m_encoder->SetMirrorCapture(maxWidth, maxFps, bothEyes);
+    }
+#endif
+}
+
+#ifdef _WIN32
+std::unique_ptr<EncoderBenchmark> g_encoderBenchmark;
+#endif
+
+bool InitializeEncoderBenchmark(unsigned int width, unsigned int height) {
+#ifdef _WIN32
+    if (!g_driver_provider.hmd || !g_driver_provider.hmd->GetD3DRender()) {
+        return false;
+    }
+
+    Settings::Instance().Load();
+
+    try {
+        g_encoderBenchmark = std::make_unique<EncoderBenchmark>(
+            g_driver_provider.hmd->GetD3DRender(), width, height);
+        return true;
+    } catch (Exception e) {
+        Error("Failed to initialize the encoder benchmark: %s\n", e.what());
+        return false;
+    }
+#else
+    return false;
+#endif
+}
+
+void EncodeBenchmarkFrame(unsigned long long targetTimestampNs) {
+#ifdef _WIN32
+    if (g_encoderBenchmark) {
+        g_encoderBenchmark->EncodeFrame(targetTimestampNs);
+    }
+#endif
+}
+
+void DeinitializeEncoderBenchmark() {
+#ifdef _WIN32
+    g_encoderBenchmark.reset();
+#endif
+}
@@ -14,9 +14,13 @@ struct FfiQuat {
     float w;
 };
 
+// Bones in the OpenVR skeleton order, relative to their parent. The last 5 are the auxiliary
+// bones, relative to the root
 struct FfiHandSkeleton {
-    float jointPositions[26][3];
-    FfiQuat jointRotations[26];
+    float jointPositions[31][3];
+    FfiQuat jointRotations[31];
+    // From the thumb to the pinky, range [0, 1]
+    float fingerCurls[5];
 };
 
 struct FfiDeviceMotion {
@@ -56,6 +60,7 @@ struct FfiOpenvrProperty {
 struct FfiViewsConfig {
     FfiFov fov[2];
     float ipd_m;
+    FfiQuat orientations[2];
 };
 
 enum FfiButtonType {
@@ -71,6 +76,27 @@ struct FfiButtonValue {
     };
 };
 
+// Negative values are not reported by the encoder
+struct FfiEncoderQp {
+    float average;
+    // Averages of the block level QP inside and outside of the foveation center region
+    float foveationCenter;
+    float foveationEdges;
+};
+
+// Normalized coordinates, relative to each eye view
+struct FfiGazeRoi {
+    float leftEyeCenter[2];
+    float rightEyeCenter[2];
+};
+
+enum FfiDevicePresence {
+    DEVICE_PRESENCE_TRACKING,
+    DEVICE_PRESENCE_NOT_TRACKING, // the client disconnected, waiting for a reconnection
+    DEVICE_PRESENCE_DISCONNECTED,
+    DEVICE_PRESENCE_FROZEN, // tracking lost, the last pose is kept without motion
+};
+
 struct FfiDynamicEncoderParams {
     unsigned int updated;
     unsigned long long bitrate_bps;
@@ -119,9 +145,24 @@ extern "C" void (*ShutdownRuntime)();
 extern "C" unsigned long long (*PathStringToHash)(const char *path);
 extern "C" void (*ReportPresent)(unsigned long long timestamp_ns, unsigned long long offset_ns);
 extern "C" void (*ReportComposed)(unsigned long long timestamp_ns, unsigned long long offset_ns);
+extern "C" void (*ReportEncoderQp)(unsigned long long timestamp_ns, FfiEncoderQp qp);
+extern "C" void (*ReportSoftwareEncoderFallback)(const char *hardwareErrors);
+// Called when an encoder is created, true if it was configured with periodic intra refresh
+extern "C" void (*ReportIntraRefresh)(bool enabled);
+// rgba is tightly packed. Null if the layer is not submitted anymore
+extern "C" void (*ReportOverlayLayer)(unsigned int layerIndex,
+                                      unsigned int eyeIndex,
+                                      unsigned int width,
+                                      unsigned int height,
+                                      const unsigned char *rgba);
+// rgba is tightly packed
+extern "C" void (*ReportMirrorFrame)(unsigned int width,
+                                     unsigned int height,
+                                     const unsigned char *rgba);
 extern "C" FfiDynamicEncoderParams (*GetDynamicEncoderParams)();
 extern "C" unsigned long long (*GetSerialNumber)(unsigned long long deviceID, char *outString);
 extern "C" void (*SetOpenvrProps)(unsigned long long deviceID);
+extern "C" void (*ReportSceneApplication)(unsigned int pid);
 extern "C" void (*WaitForVSync)();
 
 extern "C" void *CppEntryPoint(const char *pInterfaceName, int *pReturnCode);
@@ -129,6 +170,9 @@ extern "C" void InitializeStreaming();
 extern "C" void DeinitializeStreaming();
 extern "C" void SendVSync();
 extern "C" void RequestIDR();
+// Recreates the encoder with another codec (ALVR_CODEC_H264 or ALVR_CODEC_H265) before the next
+// frame. The codec of the settings is used again at the next stream
+extern "C" void SetEncoderCodec(int codec);
 extern "C" void SetTracking(unsigned long long targetTimestampNs,
                             float controllerPoseTimeOffsetS,
                             const FfiDeviceMotion *deviceMotions,
@@ -144,8 +188,22 @@ extern "C" void SetChaperone(float areaWidth, float areaHeight);
 extern "C" void SetViewsConfig(FfiViewsConfig config);
 extern "C" void SetBattery(unsigned long long deviceID, float gauge_value, bool is_plugged);
 extern "C" void SetButton(unsigned long long path, FfiButtonValue value);
+extern "C" void SetControllerProfile(int modeIdx);
+extern "C" void SetRefreshRate(float refreshRate);
+extern "C" void SetDevicesPresence(FfiDevicePresence presence);
+extern "C" void SetControllerPresence(unsigned long long deviceID, FfiDevicePresence presence);
 
 extern "C" void CaptureFrame();
+extern "C" void SetGazeRoi(FfiGazeRoi roi);
+// Only supported on Windows. 0 composites the overlays into the video stream
+extern "C" void SetOverlayLayersMaxFps(float maxFps);
+// 0 maxFps stops the capture of the mirror page images
+extern "C" void SetMirrorCapture(unsigned int maxWidth, float maxFps, bool bothEyes);
+
+// Only supported on Windows. Must not be used while streaming
+extern "C" bool InitializeEncoderBenchmark(unsigned int width, unsigned int height);
+extern "C" void EncodeBenchmarkFrame(unsigned long long targetTimestampNs);
+extern "C" void DeinitializeEncoderBenchmark();
 
 // NalParsing.cpp
 void ParseFrameNals(
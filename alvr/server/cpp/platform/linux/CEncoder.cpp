@@ -20,6 +20,7 @@
 #include "alvr_server/Logger.h"
 #include "alvr_server/PoseHistory.h"
 #include "alvr_server/Settings.h"
+#include "alvr_server/Utils.h"
 #include "protocol.h"
 #include "ffmpeg_helper.h"
 #include "EncodePipeline.h"
@@ -225,6 +226,27 @@ void CEncoder::Run() {
       while (not m_exiting) {
         read_latest(client, (char *)&frame_info, sizeof(frame_info), m_exiting);
 
+        int codec = m_pendingCodec.exchange(-1);
+        if (codec >= 0 && codec != Settings::Instance().m_codec) {
+            // The pipelines read the codec from the settings. The old pipeline is released
+            // first, some GPUs can't run two encoding sessions
+            int previousCodec = Settings::Instance().m_codec;
+            encode_pipeline.reset();
+            try {
+                Settings::Instance().m_codec = codec;
+                encode_pipeline = alvr::EncodePipeline::Create(
+                    &render, vk_ctx, frame, vk_frame_ctx,
+                    render.GetEncodingWidth(), render.GetEncodingHeight());
+            } catch (std::exception &e) {
+                Error("Failed to switch the encoder codec: %s\n", e.what());
+                Settings::Instance().m_codec = previousCodec;
+                encode_pipeline = alvr::EncodePipeline::Create(
+                    &render, vk_ctx, frame, vk_frame_ctx,
+                    render.GetEncodingWidth(), render.GetEncodingHeight());
+            }
+            m_scheduler.InsertIDR();
+        }
+
         encode_pipeline->SetParams(GetDynamicEncoderParams());
 
         auto pose = m_poseHistory->GetBestPoseMatch((const vr::HmdMatrix34_t&)frame_info.pose);
@@ -239,8 +261,24 @@ void CEncoder::Run() {
           render.CaptureOutputFrame(Settings::Instance().m_captureFrameDir + "/alvr_frame_output.ppm");
         }
 
+        {
+            std::lock_guard<std::mutex> lock(m_mirrorMutex);
+            uint64_t now = GetTimestampUs();
+            if (m_mirrorMaxFps > 0.0f && now - m_lastMirrorCaptureUs >= (uint64_t)(1e6 / m_mirrorMaxFps)) {
+                m_lastMirrorCaptureUs = now;
+                render.CaptureMirrorFrame(m_mirrorMaxWidth, m_mirrorBothEyes, ReportMirrorFrame);
+            }
+        }
+
         render.Render(frame_info.image, frame_info.semaphore_value);
 
+        {
+            std::lock_guard<std::mutex> lock(m_gazeRoiMutex);
+            if (m_gazeRoi) {
+                encode_pipeline->SetGazeRoi(*m_gazeRoi);
+            }
+        }
+
         encode_pipeline->PushFrame(pose->targetTimestampNs, m_scheduler.CheckIDRInsertion());
 
         static_assert(sizeof(frame_info.pose) == sizeof(vr::HmdMatrix34_t&));
@@ -272,6 +310,9 @@ void CEncoder::Run() {
 
         ReportPresent(pose->targetTimestampNs, present_offset);
         ReportComposed(pose->targetTimestampNs, composed_offset);
+        if (packet.qp.average >= 0) {
+          ReportEncoderQp(pose->targetTimestampNs, packet.qp);
+        }
 
         ParseFrameNals(encode_pipeline->GetCodec(), packet.data, packet.size, packet.pts, packet.isIDR);
       }
@@ -298,3 +339,17 @@ void CEncoder::OnPacketLoss() { m_scheduler.OnPacketLoss(); }
 void CEncoder::InsertIDR() { m_scheduler.InsertIDR(); }
 
 void CEncoder::CaptureFrame() { m_captureFrame = true; }
+
+void CEncoder::SetGazeRoi(FfiGazeRoi roi) {
+    std::lock_guard<std::mutex> lock(m_gazeRoiMutex);
+    m_gazeRoi = roi;
+}
+
+void CEncoder::SetCodec(int codec) { m_pendingCodec = codec; }
+
+void CEncoder::SetMirrorCapture(unsigned int maxWidth, float maxFps, bool bothEyes) {
+    std::lock_guard<std::mutex> lock(m_mirrorMutex);
+    m_mirrorMaxWidth = maxWidth;
+    m_mirrorMaxFps = maxFps;
+    m_mirrorBothEyes = bothEyes;
+}
@@ -1,9 +1,12 @@
 #pragma once
 
 #include "alvr_server/IDRScheduler.h"
+#include "alvr_server/bindings.h"
 #include "shared/threadtools.h"
 #include <atomic>
 #include <memory>
+#include <mutex>
+#include <optional>
 #include <poll.h>
 #include <sys/types.h>
 
@@ -21,6 +24,11 @@ class CEncoder : public CThread {
     void InsertIDR();
     bool IsConnected() { return m_connected; }
     void CaptureFrame();
+    void SetGazeRoi(FfiGazeRoi roi);
+    // maxFps 0 stops the capture of the mirror page images
+    void SetMirrorCapture(unsigned int maxWidth, float maxFps, bool bothEyes);
+    // Applied by the encoder thread before the next frame
+    void SetCodec(int codec);
 
   private:
     void GetFds(int client, int (*fds)[6]);
@@ -32,4 +40,12 @@ class CEncoder : public CThread {
     int m_fds[6];
     bool m_connected = false;
     std::atomic_bool m_captureFrame = false;
+    std::mutex m_gazeRoiMutex;
+    std::optional<FfiGazeRoi> m_gazeRoi;
+    std::mutex m_mirrorMutex;
+    unsigned int m_mirrorMaxWidth = 0;
+    float m_mirrorMaxFps = 0.0f;
+    bool m_mirrorBothEyes = false;
+    uint64_t m_lastMirrorCaptureUs = 0;
+    std::atomic_int m_pendingCodec{-1};
 };
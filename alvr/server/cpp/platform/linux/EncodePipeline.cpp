@@ -10,8 +10,13 @@
 
 extern "C" {
 #include <libavcodec/avcodec.h>
+#include <libavutil/frame.h>
+#include <libavutil/intreadwrite.h>
 }
 
+#include <algorithm>
+#include <string>
+
 void alvr::EncodePipeline::SetParams(FfiDynamicEncoderParams params) {
   if (params.updated) {
     encoder_ctx->bit_rate = params.bitrate_bps / params.framerate * 60.0;
@@ -22,41 +27,88 @@ void alvr::EncodePipeline::SetParams(FfiDynamicEncoderParams params) {
   }
 }
 
+void alvr::EncodePipeline::AttachGazeRoi(AVFrame *frame) {
+  av_frame_remove_side_data(frame, AV_FRAME_DATA_REGIONS_OF_INTEREST);
+
+  auto &settings = Settings::Instance();
+  if (!settings.m_enableGazeRoi || !has_gaze_roi) {
+    return;
+  }
+
+  AVFrameSideData *side_data = av_frame_new_side_data(frame, AV_FRAME_DATA_REGIONS_OF_INTEREST, 2 * sizeof(AVRegionOfInterest));
+  if (!side_data) {
+    return;
+  }
+
+  // The two eye views are laid side by side in the encoded frame
+  int eye_width = encoder_ctx->width / 2;
+  int height = encoder_ctx->height;
+  int radius = settings.m_gazeRoiRadius * eye_width;
+  const float *centers[2] = {gaze_roi.leftEyeCenter, gaze_roi.rightEyeCenter};
+
+  auto rois = (AVRegionOfInterest *)side_data->data;
+  for (int eye = 0; eye < 2; eye++) {
+    int center_x = eye * eye_width + centers[eye][0] * eye_width;
+    int center_y = centers[eye][1] * height;
+
+    rois[eye].self_size = sizeof(AVRegionOfInterest);
+    rois[eye].left = std::clamp(center_x - radius, eye * eye_width, (eye + 1) * eye_width);
+    rois[eye].right = std::clamp(center_x + radius, eye * eye_width, (eye + 1) * eye_width);
+    rois[eye].top = std::clamp(center_y - radius, 0, height);
+    rois[eye].bottom = std::clamp(center_y + radius, 0, height);
+    // Negative offsets lower the quantizer, increasing quality
+    rois[eye].qoffset = AVRational{-int(settings.m_gazeRoiQualityBoost * 100), 100};
+  }
+}
+
 std::unique_ptr<alvr::EncodePipeline> alvr::EncodePipeline::Create(Renderer *render, VkContext &vk_ctx, VkFrame &input_frame, VkFrameCtx &vk_frame_ctx, uint32_t width, uint32_t height)
 {
-  if(Settings::Instance().m_force_sw_encoding == false) {
+  const bool force_sw = Settings::Instance().m_force_sw_encoding;
+  std::string hardware_errors;
+  if(force_sw == false) {
     if (vk_ctx.nvidia) {
       try {
         auto nvenc = std::make_unique<alvr::EncodePipelineNvEnc>(render, input_frame, vk_frame_ctx, width, height);
         Info("using NvEnc encoder");
+        ReportIntraRefresh(Settings::Instance().m_periodicIntraRefreshFrames > 0);
         return nvenc;
       } catch (std::exception &e)
       {
         Info("failed to create NvEnc encoder: %s", e.what());
+        hardware_errors += std::string("NvEnc: ") + e.what() + "; ";
       }
     } else {
       if (vk_ctx.amd) {
         try {
           auto amf = std::make_unique<alvr::EncodePipelineAMF>(render, width, height);
           Info("using AMF encoder");
+          ReportIntraRefresh(Settings::Instance().m_periodicIntraRefreshFrames > 0);
           return amf;
         } catch (std::exception &e)
         {
           Info("failed to create AMF encoder: %s", e.what());
+          hardware_errors += std::string("AMF: ") + e.what() + "; ";
         }
       }
       try {
         auto vaapi = std::make_unique<alvr::EncodePipelineVAAPI>(render, vk_ctx, input_frame, width, height);
         Info("using VAAPI encoder");
+        // VAAPI has no intra refresh support, the client keeps requesting IDR frames on loss
+        ReportIntraRefresh(false);
         return vaapi;
       } catch (std::exception &e)
       {
         Info("failed to create VAAPI encoder: %s", e.what());
+        hardware_errors += std::string("VAAPI: ") + e.what();
       }
     }
   }
   auto sw = std::make_unique<alvr::EncodePipelineSW>(render, width, height);
   Info("using SW encoder");
+  ReportIntraRefresh(Settings::Instance().m_periodicIntraRefreshFrames > 0);
+  if (!force_sw) {
+    ReportSoftwareEncoderFallback(hardware_errors.c_str());
+  }
   return sw;
 }
 
@@ -81,6 +133,11 @@ bool alvr::EncodePipeline::GetEncoded(FramePacket &packet)
   packet.size = encoder_packet->size;
   packet.pts = encoder_packet->pts;
   packet.isIDR = (encoder_packet->flags & AV_PKT_FLAG_KEY) != 0;
+  // The first field of the quality stats is the frame quality in lambda units
+  uint8_t *quality_stats = av_packet_get_side_data(encoder_packet, AV_PKT_DATA_QUALITY_STATS, nullptr);
+  if (quality_stats) {
+    packet.qp.average = (float)AV_RL32(quality_stats) / FF_QP2LAMBDA;
+  }
   return true;
 }
 
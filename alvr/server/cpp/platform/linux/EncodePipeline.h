@@ -6,6 +6,7 @@
 
 extern "C" struct AVCodecContext;
 extern "C" struct AVPacket;
+extern "C" struct AVFrame;
 
 class Renderer;
 
@@ -21,6 +22,8 @@ struct FramePacket {
   int size;
   uint64_t pts;
   bool isIDR;
+  // Negative values if the encoder does not report them
+  FfiEncoderQp qp = {-1, -1, -1};
 };
 
 class EncodePipeline
@@ -39,11 +42,17 @@ public:
   virtual int GetCodec();
 
   virtual void SetParams(FfiDynamicEncoderParams params);
+  void SetGazeRoi(FfiGazeRoi roi) { gaze_roi = roi; has_gaze_roi = true; }
   static std::unique_ptr<EncodePipeline> Create(Renderer *render, VkContext &vk_ctx, VkFrame &input_frame, VkFrameCtx &vk_frame_ctx, uint32_t width, uint32_t height);
 protected:
+  // Attach the gaze region of interest to the frame, for encoders that support ROI side data
+  void AttachGazeRoi(AVFrame *frame);
+
   AVCodecContext *encoder_ctx = nullptr; //shall be initialized by child class
   AVPacket *encoder_packet = NULL;
   Timestamp timestamp = {};
+  FfiGazeRoi gaze_roi = {};
+  bool has_gaze_roi = false;
 };
 
 }
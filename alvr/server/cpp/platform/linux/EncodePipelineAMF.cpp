@@ -1,6 +1,7 @@
 #include "EncodePipelineAMF.h"
 #include "amf_helper.h"
 
+#include "alvr_server/EncoderQp.h"
 #include "alvr_server/Logger.h"
 #include "alvr_server/Settings.h"
 
@@ -21,6 +22,31 @@ static amf::AMF_SURFACE_FORMAT fromVkFormat(VkFormat format)
     }
 }
 
+// The statistics are requested with each frame. Drivers that don't support them leave the
+// properties unset
+static FfiEncoderQp readEncoderQp(const amf::AMFBufferPtr &buffer, int codec)
+{
+    amf_int64 averageQp = -1;
+    amf::AMFInterfacePtr blockQpMap;
+    if (codec == ALVR_CODEC_H264) {
+        buffer->GetProperty(AMF_VIDEO_ENCODER_STATISTIC_AVERAGE_QP, &averageQp);
+        buffer->GetProperty(AMF_VIDEO_ENCODER_BLOCK_QP_MAP, &blockQpMap);
+    } else {
+        buffer->GetProperty(AMF_VIDEO_ENCODER_HEVC_STATISTIC_AVERAGE_QP, &averageQp);
+        buffer->GetProperty(AMF_VIDEO_ENCODER_HEVC_BLOCK_QP_MAP, &blockQpMap);
+    }
+
+    amf::AMFSurfacePtr blockQpSurface(blockQpMap);
+    if (blockQpSurface == nullptr || blockQpSurface->Convert(amf::AMF_MEMORY_HOST) != AMF_OK) {
+        return ComputeRegionQp((float)averageQp, nullptr, 0, 0, 0);
+    }
+
+    // One 32 bit value per macroblock or coding tree block
+    amf::AMFPlanePtr plane = blockQpSurface->GetPlaneAt(0);
+    return ComputeRegionQp((float)averageQp, (const uint32_t *)plane->GetNative(), plane->GetWidth(),
+                           plane->GetHeight(), plane->GetHPitch());
+}
+
 AMFPipe::AMFPipe(amf::AMFComponentPtr src, AMFDataReceiver receiver)
     : m_amfComponentSrc(src)
     , m_receiver(receiver)
@@ -189,6 +215,7 @@ amf::AMFComponentPtr EncodePipelineAMF::MakeEncoder(amf::AMF_SURFACE_FORMAT inpu
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_USAGE, AMF_VIDEO_ENCODER_USAGE_ULTRA_LOW_LATENCY);
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_PROFILE, AMF_VIDEO_ENCODER_PROFILE_HIGH);
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_PROFILE_LEVEL, 42);
+        amfEncoder->SetProperty(AMF_VIDEO_ENCODER_SLICES_PER_FRAME, Settings::Instance().m_sliceCount);
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_FRAMESIZE, ::AMFConstructSize(width, height));
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_FRAMERATE, ::AMFConstructRate(frameRateIn, 1));
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_B_PIC_PATTERN, 0);
@@ -232,6 +259,13 @@ amf::AMFComponentPtr EncodePipelineAMF::MakeEncoder(amf::AMF_SURFACE_FORMAT inpu
         // Turns Off IDR/I Frames
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_IDR_PERIOD, 0);
 
+        if (Settings::Instance().m_periodicIntraRefreshFrames > 0) {
+            // Refresh the whole frame (in 16x16 macroblocks) over the period
+            int mbCount = ((width + 15) / 16) * ((height + 15) / 16);
+            amfEncoder->SetProperty(AMF_VIDEO_ENCODER_INTRA_REFRESH_NUM_MBS_PER_SLOT,
+                (mbCount + Settings::Instance().m_periodicIntraRefreshFrames - 1) / Settings::Instance().m_periodicIntraRefreshFrames);
+        }
+
         // Disable AUD to produce the same stream format as VideoEncoderNVENC.
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_INSERT_AUD, false);
 
@@ -244,6 +278,7 @@ amf::AMFComponentPtr EncodePipelineAMF::MakeEncoder(amf::AMF_SURFACE_FORMAT inpu
         }
     } else {
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_USAGE, AMF_VIDEO_ENCODER_HEVC_USAGE_ULTRA_LOW_LATENCY);
+        amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_SLICES_PER_FRAME, Settings::Instance().m_sliceCount);
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_FRAMESIZE, ::AMFConstructSize(width, height));
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_FRAMERATE, ::AMFConstructRate(frameRateIn, 1));
 
@@ -289,6 +324,13 @@ amf::AMFComponentPtr EncodePipelineAMF::MakeEncoder(amf::AMF_SURFACE_FORMAT inpu
         // Set infinite GOP length
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_GOP_SIZE, 0);
 
+        if (Settings::Instance().m_periodicIntraRefreshFrames > 0) {
+            // Refresh the whole frame (in 64x64 CTBs) over the period
+            int ctbCount = ((width + 63) / 64) * ((height + 63) / 64);
+            amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_INTRA_REFRESH_NUM_CTBS_PER_SLOT,
+                (ctbCount + Settings::Instance().m_periodicIntraRefreshFrames - 1) / Settings::Instance().m_periodicIntraRefreshFrames);
+        }
+
         // Disable AUD to produce the same stream format as VideoEncoderNVENC.
         amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_INSERT_AUD, false);
 
@@ -400,6 +442,7 @@ bool EncodePipelineAMF::GetEncoded(FramePacket &packet)
         m_frameBuffer->GetProperty(AMF_VIDEO_ENCODER_HEVC_OUTPUT_DATA_TYPE, &type);
         packet.isIDR = type == AMF_VIDEO_ENCODER_HEVC_OUTPUT_DATA_TYPE_IDR;
     }
+    packet.qp = readEncoderQp(m_frameBuffer, m_codec);
 
     return true;
 }
@@ -409,6 +452,17 @@ void EncodePipelineAMF::SetParams(FfiDynamicEncoderParams params)
     if (!params.updated) {
         return;
     }
+
+    int refreshRate = Settings::Instance().m_refreshRate;
+    if (refreshRate != m_refreshRate) {
+        m_refreshRate = refreshRate;
+        if (m_codec == ALVR_CODEC_H264) {
+            m_amfComponents.back()->SetProperty(AMF_VIDEO_ENCODER_FRAMERATE, ::AMFConstructRate(m_refreshRate, 1));
+        } else {
+            m_amfComponents.back()->SetProperty(AMF_VIDEO_ENCODER_HEVC_FRAMERATE, ::AMFConstructRate(m_refreshRate, 1));
+        }
+    }
+
     amf_int64 bitRateIn = params.bitrate_bps / params.framerate * m_refreshRate;
     if (m_codec == ALVR_CODEC_H264) {
         m_amfComponents.back()->SetProperty(AMF_VIDEO_ENCODER_TARGET_BITRATE, bitRateIn);
@@ -435,6 +489,8 @@ void EncodePipelineAMF::ApplyFrameProperties(const amf::AMFSurfacePtr &surface,
     switch (m_codec) {
     case ALVR_CODEC_H264:
         surface->SetProperty(AMF_VIDEO_ENCODER_INSERT_AUD, false);
+        surface->SetProperty(AMF_VIDEO_ENCODER_STATISTICS_FEEDBACK, true);
+        surface->SetProperty(AMF_VIDEO_ENCODER_BLOCK_QP_FEEDBACK, true);
         if (insertIDR) {
             Debug("Inserting IDR frame for H.264.\n");
             surface->SetProperty(AMF_VIDEO_ENCODER_INSERT_SPS, true);
@@ -444,6 +500,8 @@ void EncodePipelineAMF::ApplyFrameProperties(const amf::AMFSurfacePtr &surface,
         break;
     case ALVR_CODEC_H265:
         surface->SetProperty(AMF_VIDEO_ENCODER_HEVC_INSERT_AUD, false);
+        surface->SetProperty(AMF_VIDEO_ENCODER_HEVC_STATISTICS_FEEDBACK, true);
+        surface->SetProperty(AMF_VIDEO_ENCODER_HEVC_BLOCK_QP_FEEDBACK, true);
         if (insertIDR) {
             Debug("Inserting IDR frame for H.265.\n");
             // Insert VPS,SPS,PPS
@@ -104,12 +104,18 @@ alvr::EncodePipelineNvEnc::EncodePipelineNvEnc(Renderer *render,
      */
     encoder_ctx->pix_fmt = AV_PIX_FMT_BGR0;
     encoder_ctx->width = width;
+    encoder_ctx->slices = settings.m_sliceCount;
     encoder_ctx->height = height;
     encoder_ctx->time_base = {1, (int)1e9};
     encoder_ctx->framerate = AVRational{settings.m_refreshRate, 1};
     encoder_ctx->sample_aspect_ratio = AVRational{1, 1};
     encoder_ctx->max_b_frames = 0;
     encoder_ctx->gop_size = INT16_MAX;
+    if (settings.m_periodicIntraRefreshFrames > 0) {
+        // With intra refresh, the GOP size is used as the refresh period
+        av_opt_set_int(encoder_ctx->priv_data, "intra-refresh", 1, 0);
+        encoder_ctx->gop_size = settings.m_periodicIntraRefreshFrames;
+    }
     auto params = FfiDynamicEncoderParams {};
     params.updated = true;
     params.bitrate_bps = 30'000'000;
@@ -139,6 +145,7 @@ void alvr::EncodePipelineNvEnc::PushFrame(uint64_t targetTimestampNs, bool idr)
 
     hw_frame->pict_type = idr ? AV_PICTURE_TYPE_I : AV_PICTURE_TYPE_NONE;
     hw_frame->pts = targetTimestampNs;
+    AttachGazeRoi(hw_frame);
 
     if ((err = avcodec_send_frame(encoder_ctx, hw_frame)) < 0) {
         throw alvr::AvException("avcodec_send_frame failed:", err);
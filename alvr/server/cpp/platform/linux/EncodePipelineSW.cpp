@@ -47,9 +47,14 @@ alvr::EncodePipelineSW::EncodePipelineSW(Renderer *render, uint32_t width, uint3
   param.b_cabac = settings.m_entropyCoding == ALVR_CABAC;
   param.b_sliced_threads = true;
   param.i_threads = settings.m_swThreadCount;
+  param.i_slice_count = settings.m_sliceCount;
   param.i_width = width;
   param.i_height = height;
   param.rc.i_rc_method = X264_RC_ABR;
+  if (settings.m_periodicIntraRefreshFrames > 0) {
+    param.b_intra_refresh = 1;
+    param.i_keyint_max = settings.m_periodicIntraRefreshFrames;
+  }
 
   auto params = FfiDynamicEncoderParams {};
   params.updated = true;
@@ -193,6 +193,7 @@ alvr::EncodePipelineVAAPI::EncodePipelineVAAPI(Renderer *render, VkContext &vk_c
   av_opt_set_int(encoder_ctx->priv_data, "filler_data", settings.m_fillerData, 0);
 
   encoder_ctx->width = width;
+  encoder_ctx->slices = settings.m_sliceCount;
   encoder_ctx->height = height;
   encoder_ctx->time_base = {1, (int)1e9};
   encoder_ctx->sample_aspect_ratio = AVRational{1, 1};
@@ -333,6 +334,7 @@ void alvr::EncodePipelineVAAPI::PushFrame(uint64_t targetTimestampNs, bool idr)
 
   encoder_frame->pict_type = idr ? AV_PICTURE_TYPE_I : AV_PICTURE_TYPE_NONE;
   encoder_frame->pts = targetTimestampNs;
+  AttachGazeRoi(encoder_frame);
 
   if ((err = avcodec_send_frame(encoder_ctx, encoder_frame)) < 0) {
     throw alvr::AvException("avcodec_send_frame failed: ", err);
@@ -460,18 +460,44 @@ void Renderer::CreateOutput(uint32_t width, uint32_t height)
 
 void Renderer::Render(uint32_t index, uint64_t waitValue)
 {
-    if (!m_inputImageCapture.empty()) {
+    if (!m_inputImageCapture.empty() || m_mirrorMaxWidth > 0) {
         VkSemaphoreWaitInfo waitInfo = {};
         waitInfo.sType = VK_STRUCTURE_TYPE_SEMAPHORE_WAIT_INFO;
         waitInfo.semaphoreCount = 1;
         waitInfo.pSemaphores = &m_images[index].semaphore;
         waitInfo.pValues = &waitValue;
         VK_CHECK(vkWaitSemaphores(m_dev, &waitInfo, UINT64_MAX));
+    }
 
+    if (!m_inputImageCapture.empty()) {
         dumpImage(m_images[index].image, m_images[index].layout, m_imageSize.width, m_imageSize.height, m_inputImageCapture);
         m_inputImageCapture.clear();
     }
 
+    if (m_mirrorMaxWidth > 0) {
+        // The eyes are side by side in the input image, the left one comes first
+        uint32_t srcWidth = m_mirrorBothEyes ? m_imageSize.width : m_imageSize.width / 2;
+        float scale = std::min(1.0f, (float)m_mirrorMaxWidth / srcWidth);
+        uint32_t width = std::max((uint32_t)(srcWidth * scale), 1u);
+        uint32_t height = std::max((uint32_t)(m_imageSize.height * scale), 1u);
+
+        readImage(m_images[index].image, m_images[index].layout, srcWidth, m_imageSize.height, width, height, [&](const char *imageData, uint32_t rowPitch) {
+            m_mirrorPixels.resize(width * height * 4);
+            for (uint32_t y = 0; y < height; y++) {
+                memcpy(&m_mirrorPixels[y * width * 4], imageData, width * 4);
+                imageData += rowPitch;
+            }
+        });
+        // The scene alpha is not meaningful
+        for (size_t i = 3; i < m_mirrorPixels.size(); i += 4) {
+            m_mirrorPixels[i] = 255;
+        }
+
+        m_mirrorCallback(width, height, m_mirrorPixels.data());
+        m_mirrorMaxWidth = 0;
+        m_mirrorCallback = nullptr;
+    }
+
     VkCommandBufferBeginInfo commandBufferBegin = {};
     commandBufferBegin.sType = VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO;
     VK_CHECK(vkBeginCommandBuffer(m_commandBuffer, &commandBufferBegin));
@@ -618,6 +644,13 @@ void Renderer::CaptureOutputFrame(const std::string &filename)
     m_outputImageCapture = filename;
 }
 
+void Renderer::CaptureMirrorFrame(uint32_t maxWidth, bool bothEyes, std::function<void(uint32_t width, uint32_t height, const uint8_t *rgba)> callback)
+{
+    m_mirrorMaxWidth = maxWidth;
+    m_mirrorBothEyes = bothEyes;
+    m_mirrorCallback = callback;
+}
+
 std::string Renderer::result_to_str(VkResult result)
 {
     switch (result) {
@@ -733,13 +766,35 @@ void Renderer::addStagingImage(uint32_t width, uint32_t height)
 }
 
 void Renderer::dumpImage(VkImage image, VkImageLayout imageLayout, uint32_t width, uint32_t height, const std::string &filename)
+{
+    readImage(image, imageLayout, width, height, width, height, [&](const char *imageData, uint32_t rowPitch) {
+        std::ofstream file(filename, std::ios::out | std::ios::binary);
+
+        // PPM header
+        file << "P6\n" << width << "\n" << height << "\n" << 255 << "\n";
+
+        // PPM binary pixel data
+        for (uint32_t y = 0; y < height; y++) {
+            uint32_t *row = (uint32_t*)imageData;
+            for (uint32_t x = 0; x < width; x++) {
+                file.write((char*)row++, 3);
+            }
+            imageData += rowPitch;
+        }
+        file.close();
+
+        std::cout << "Image saved to \"" << filename << "\"" << std::endl;
+    });
+}
+
+void Renderer::readImage(VkImage image, VkImageLayout imageLayout, uint32_t srcWidth, uint32_t srcHeight, uint32_t dstWidth, uint32_t dstHeight, const std::function<void(const char *data, uint32_t rowPitch)> &callback)
 {
     VkImageCreateInfo imageInfo = {};
     imageInfo.sType = VK_STRUCTURE_TYPE_IMAGE_CREATE_INFO;
     imageInfo.imageType = VK_IMAGE_TYPE_2D;
     imageInfo.format = VK_FORMAT_R8G8B8A8_UNORM;
-    imageInfo.extent.width = width;
-    imageInfo.extent.height = height;
+    imageInfo.extent.width = dstWidth;
+    imageInfo.extent.height = dstHeight;
     imageInfo.extent.depth = 1;
     imageInfo.arrayLayers = 1;
     imageInfo.mipLevels = 1;
@@ -790,8 +845,8 @@ void Renderer::dumpImage(VkImage image, VkImageLayout imageLayout, uint32_t widt
     imageBlit.srcOffsets[0].x = 0;
     imageBlit.srcOffsets[0].y = 0;
     imageBlit.srcOffsets[0].z = 0;
-    imageBlit.srcOffsets[1].x = width;
-    imageBlit.srcOffsets[1].y = height;
+    imageBlit.srcOffsets[1].x = srcWidth;
+    imageBlit.srcOffsets[1].y = srcHeight;
     imageBlit.srcOffsets[1].z = 1;
     imageBlit.dstSubresource.aspectMask = VK_IMAGE_ASPECT_COLOR_BIT;
     imageBlit.dstSubresource.mipLevel = 0;
@@ -800,8 +855,8 @@ void Renderer::dumpImage(VkImage image, VkImageLayout imageLayout, uint32_t widt
     imageBlit.dstOffsets[0].x = 0;
     imageBlit.dstOffsets[0].y = 0;
     imageBlit.dstOffsets[0].z = 0;
-    imageBlit.dstOffsets[1].x = width;
-    imageBlit.dstOffsets[1].y = height;
+    imageBlit.dstOffsets[1].x = dstWidth;
+    imageBlit.dstOffsets[1].y = dstHeight;
     imageBlit.dstOffsets[1].z = 1;
 
     std::array<VkImageMemoryBarrier, 2> imageBarrierOut;
@@ -828,7 +883,7 @@ void Renderer::dumpImage(VkImage image, VkImageLayout imageLayout, uint32_t widt
 
     commandBufferBegin();
     vkCmdPipelineBarrier(m_commandBuffer, VK_PIPELINE_STAGE_TRANSFER_BIT, VK_PIPELINE_STAGE_TRANSFER_BIT, 0, 0, nullptr, 0, nullptr, imageBarrierIn.size(), imageBarrierIn.data());
-    vkCmdBlitImage(m_commandBuffer, image, VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL, dstImage, VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL, 1, &imageBlit, VK_FILTER_NEAREST);
+    vkCmdBlitImage(m_commandBuffer, image, VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL, dstImage, VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL, 1, &imageBlit, srcWidth == dstWidth && srcHeight == dstHeight ? VK_FILTER_NEAREST : VK_FILTER_LINEAR);
     vkCmdPipelineBarrier(m_commandBuffer, VK_PIPELINE_STAGE_TRANSFER_BIT, VK_PIPELINE_STAGE_TRANSFER_BIT, 0, 0, nullptr, 0, nullptr, imageBarrierOut.size(), imageBarrierOut.data());
     commandBufferSubmit();
 
@@ -841,22 +896,7 @@ void Renderer::dumpImage(VkImage image, VkImageLayout imageLayout, uint32_t widt
     VK_CHECK(vkMapMemory(m_dev, dstMemory, 0, VK_WHOLE_SIZE, 0, (void**)&imageData));
     imageData += layout.offset;
 
-    std::ofstream file(filename, std::ios::out | std::ios::binary);
-
-    // PPM header
-    file << "P6\n" << width << "\n" << height << "\n" << 255 << "\n";
-
-    // PPM binary pixel data
-    for (uint32_t y = 0; y < height; y++) {
-        uint32_t *row = (uint32_t*)imageData;
-        for (uint32_t x = 0; x < width; x++) {
-            file.write((char*)row++, 3);
-        }
-        imageData += layout.rowPitch;
-    }
-    file.close();
-
-    std::cout << "Image saved to \"" << filename << "\"" << std::endl;
+    callback(imageData, layout.rowPitch);
 
     vkUnmapMemory(m_dev, dstMemory);
     vkFreeMemory(m_dev, dstMemory, nullptr);
@@ -4,6 +4,7 @@
 #include <string>
 #include <array>
 #include <iostream>
+#include <functional>
 #include <vulkan/vulkan.h>
 
 #define VK_CHECK(f) \
@@ -68,6 +69,9 @@ public:
 
     void CaptureInputFrame(const std::string &filename);
     void CaptureOutputFrame(const std::string &filename);
+    // The input frame is read back at the next render, downscaled to at most maxWidth. The
+    // callback receives tightly packed RGBA pixels
+    void CaptureMirrorFrame(uint32_t maxWidth, bool bothEyes, std::function<void(uint32_t width, uint32_t height, const uint8_t *rgba)> callback);
 
     static std::string result_to_str(VkResult result);
 
@@ -91,6 +95,7 @@ public:
     void commandBufferSubmit();
     void addStagingImage(uint32_t width, uint32_t height);
     void dumpImage(VkImage image, VkImageLayout imageLayout, uint32_t width, uint32_t height, const std::string &filename);
+    void readImage(VkImage image, VkImageLayout imageLayout, uint32_t srcWidth, uint32_t srcHeight, uint32_t dstWidth, uint32_t dstHeight, const std::function<void(const char *data, uint32_t rowPitch)> &callback);
     uint32_t memoryTypeIndex(VkMemoryPropertyFlags properties, uint32_t typeBits) const;
 
     struct {
@@ -125,6 +130,10 @@ public:
 
     std::string m_inputImageCapture;
     std::string m_outputImageCapture;
+    uint32_t m_mirrorMaxWidth = 0;
+    bool m_mirrorBothEyes = false;
+    std::function<void(uint32_t width, uint32_t height, const uint8_t *rgba)> m_mirrorCallback;
+    std::vector<uint8_t> m_mirrorPixels;
 };
 
 class RenderPipeline
@@ -12,4 +12,5 @@ class CEncoder : public CThread {
     void Stop() {}
     void OnPacketLoss() {}
     void InsertIDR() {}
+    void SetCodec(int) {}
 };
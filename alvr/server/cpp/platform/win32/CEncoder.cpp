@@ -1,9 +1,11 @@
 #include "CEncoder.h"
+#include "alvr_server/bindings.h"
 
 
 		CEncoder::CEncoder()
 			: m_bExiting(false)
 			, m_targetTimestampNs(0)
+			, m_pendingCodec(-1)
 		{
 			m_encodeFinished.Set();
 		}
@@ -18,11 +20,8 @@
 			}
 		}
 
-		void CEncoder::Initialize(std::shared_ptr<CD3DRender> d3dRender) {
-			m_FrameRender = std::make_shared<FrameRender>(d3dRender);
-			m_FrameRender->Startup();
-			uint32_t encoderWidth, encoderHeight;
-			m_FrameRender->GetEncodingResolution(&encoderWidth, &encoderHeight);
+		std::shared_ptr<VideoEncoder> CreateVideoEncoder(std::shared_ptr<CD3DRender> d3dRender, uint32_t encoderWidth, uint32_t encoderHeight) {
+			std::shared_ptr<VideoEncoder> videoEncoder;
 
 			Exception vceException;
 			Exception nvencException;
@@ -31,9 +30,10 @@
 			if (Settings::Instance().m_force_sw_encoding) {
 				try {
 					Debug("Try to use VideoEncoderSW.\n");
-					m_videoEncoder = std::make_shared<VideoEncoderSW>(d3dRender, encoderWidth, encoderHeight);
-					m_videoEncoder->Initialize();
-					return;
+					videoEncoder = std::make_shared<VideoEncoderSW>(d3dRender, encoderWidth, encoderHeight);
+					videoEncoder->Initialize();
+					ReportIntraRefresh(false);
+					return videoEncoder;
 				}
 				catch (Exception e) {
 					swException = e;
@@ -43,18 +43,20 @@
 			
 			try {
 				Debug("Try to use VideoEncoderAMF.\n");
-				m_videoEncoder = std::make_shared<VideoEncoderAMF>(d3dRender, encoderWidth, encoderHeight);
-				m_videoEncoder->Initialize();
-				return;
+				videoEncoder = std::make_shared<VideoEncoderAMF>(d3dRender, encoderWidth, encoderHeight);
+				videoEncoder->Initialize();
+				ReportIntraRefresh(Settings::Instance().m_periodicIntraRefreshFrames > 0);
+				return videoEncoder;
 			}
 			catch (Exception e) {
 				vceException = e;
 			}
 			try {
 				Debug("Try to use VideoEncoderNVENC.\n");
-				m_videoEncoder = std::make_shared<VideoEncoderNVENC>(d3dRender, encoderWidth, encoderHeight);
-				m_videoEncoder->Initialize();
-				return;
+				videoEncoder = std::make_shared<VideoEncoderNVENC>(d3dRender, encoderWidth, encoderHeight);
+				videoEncoder->Initialize();
+				ReportIntraRefresh(Settings::Instance().m_periodicIntraRefreshFrames > 0);
+				return videoEncoder;
 			}
 			catch (Exception e) {
 				nvencException = e;
@@ -62,9 +64,14 @@
 #ifdef ALVR_GPL
 			try {
 				Debug("Try to use VideoEncoderSW.\n");
-				m_videoEncoder = std::make_shared<VideoEncoderSW>(d3dRender, encoderWidth, encoderHeight);
-				m_videoEncoder->Initialize();
-				return;
+				videoEncoder = std::make_shared<VideoEncoderSW>(d3dRender, encoderWidth, encoderHeight);
+				videoEncoder->Initialize();
+
+				std::string hardwareErrors = std::string("VCE: ") + vceException.what() + ", NVENC: " + nvencException.what();
+				ReportSoftwareEncoderFallback(hardwareErrors.c_str());
+				ReportIntraRefresh(false);
+
+				return videoEncoder;
 			}
 			catch (Exception e) {
 				swException = e;
@@ -75,6 +82,16 @@
 #endif
 		}
 
+		void CEncoder::Initialize(std::shared_ptr<CD3DRender> d3dRender) {
+			m_d3dRender = d3dRender;
+			m_FrameRender = std::make_shared<FrameRender>(d3dRender);
+			m_FrameRender->Startup();
+			uint32_t encoderWidth, encoderHeight;
+			m_FrameRender->GetEncodingResolution(&encoderWidth, &encoderHeight);
+
+			m_videoEncoder = CreateVideoEncoder(d3dRender, encoderWidth, encoderHeight);
+		}
+
 		bool CEncoder::CopyToStaging(ID3D11Texture2D *pTexture[][2], vr::VRTextureBounds_t bounds[][2], int layerCount, bool recentering
 			, uint64_t presentationTime, uint64_t targetTimestampNs, const std::string& message, const std::string& debugText)
 		{
@@ -97,6 +114,8 @@
 				if (m_bExiting)
 					break;
 
+				ApplyPendingCodec();
+
 				if (m_FrameRender->GetTexture())
 				{
 					m_videoEncoder->Transmit(m_FrameRender->GetTexture().Get(), m_presentationTime, m_targetTimestampNs, m_scheduler.CheckIDRInsertion());
@@ -139,3 +158,33 @@
 
 		void CEncoder::CaptureFrame() {
 		}
+
+		void CEncoder::SetCodec(int codec) {
+			m_pendingCodec = codec;
+		}
+
+		void CEncoder::ApplyPendingCodec() {
+			int codec = m_pendingCodec.exchange(-1);
+			if (codec < 0 || codec == Settings::Instance().m_codec) {
+				return;
+			}
+
+			uint32_t encoderWidth, encoderHeight;
+			m_FrameRender->GetEncodingResolution(&encoderWidth, &encoderHeight);
+
+			// The encoders read the codec from the settings. The old encoder is released first, some
+			// GPUs can't run two encoding sessions
+			int previousCodec = Settings::Instance().m_codec;
+			m_videoEncoder->Shutdown();
+			try {
+				Settings::Instance().m_codec = codec;
+				m_videoEncoder = CreateVideoEncoder(m_d3dRender, encoderWidth, encoderHeight);
+			}
+			catch (Exception e) {
+				Error("Failed to switch the encoder codec: %s\n", e.what());
+				Settings::Instance().m_codec = previousCodec;
+				m_videoEncoder = CreateVideoEncoder(m_d3dRender, encoderWidth, encoderHeight);
+			}
+
+			m_scheduler.InsertIDR();
+		}
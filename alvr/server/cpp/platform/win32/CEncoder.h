@@ -6,6 +6,7 @@
 #include <d3d11.h>
 #include <wrl.h>
 #include <map>
+#include <atomic>
 #include <d3d11_1.h>
 #include <wincodec.h>
 #include <wincodecsdk.h>
@@ -22,6 +23,9 @@
 
 	using Microsoft::WRL::ComPtr;
 
+	// Tries the available encoders in order of preference. Throws if none can be initialized
+	std::shared_ptr<VideoEncoder> CreateVideoEncoder(std::shared_ptr<CD3DRender> d3dRender, uint32_t encoderWidth, uint32_t encoderHeight);
+
 	//----------------------------------------------------------------------------
 	// Blocks on reading backbuffer from gpu, so WaitForPresent can return
 	// as soon as we know rendering made it this frame.  This step of the pipeline
@@ -54,9 +58,16 @@
 
 		void CaptureFrame();
 
+		// Applied by the encoder thread before the next frame
+		void SetCodec(int codec);
+
 	private:
+		void ApplyPendingCodec();
+
 		CThreadEvent m_newFrameReady, m_encodeFinished;
+		std::shared_ptr<CD3DRender> m_d3dRender;
 		std::shared_ptr<VideoEncoder> m_videoEncoder;
+		std::atomic<int> m_pendingCodec;
 		bool m_bExiting;
 		uint64_t m_presentationTime;
 		uint64_t m_targetTimestampNs;
@@ -0,0 +1,75 @@
+#include "EncoderBenchmark.h"
+#include "CEncoder.h"
+#include "alvr_server/Logger.h"
+#include "alvr_server/Utils.h"
+
+// Rows of noise regenerated every frame, so that the encoder always has new detail to compress
+const uint32_t NOISE_BAND_HEIGHT = 64;
+
+EncoderBenchmark::EncoderBenchmark(std::shared_ptr<CD3DRender> d3dRender,
+                                   uint32_t width,
+                                   uint32_t height)
+    : m_d3dRender(d3dRender)
+    , m_width(width)
+    , m_height(height)
+    , m_pixels(width * height)
+    , m_frameIndex(0)
+    , m_noiseState(0x12345678) {
+    // Same format as the FrameRender output
+    D3D11_TEXTURE2D_DESC desc = {};
+    desc.Width = width;
+    desc.Height = height;
+    desc.Format = DXGI_FORMAT_R8G8B8A8_UNORM_SRGB;
+    desc.MipLevels = 1;
+    desc.ArraySize = 1;
+    desc.SampleDesc.Count = 1;
+    desc.Usage = D3D11_USAGE_DEFAULT;
+    desc.BindFlags = D3D11_BIND_SHADER_RESOURCE | D3D11_BIND_RENDER_TARGET;
+
+    HRESULT hr = m_d3dRender->GetDevice()->CreateTexture2D(&desc, NULL, &m_texture);
+    if (FAILED(hr)) {
+        throw MakeException("Failed to create the benchmark texture: %p", hr);
+    }
+
+    m_videoEncoder = CreateVideoEncoder(d3dRender, width, height);
+}
+
+EncoderBenchmark::~EncoderBenchmark() {
+    if (m_videoEncoder) {
+        m_videoEncoder->Shutdown();
+    }
+}
+
+// A gradient scrolling horizontally, with a band of noise moving vertically
+void EncoderBenchmark::UpdatePixels() {
+    uint32_t bandStart = (uint32_t)(m_frameIndex * 8 % m_height);
+
+    for (uint32_t y = 0; y < m_height; y++) {
+        uint32_t *row = &m_pixels[y * m_width];
+        bool isNoise = y >= bandStart && y < bandStart + NOISE_BAND_HEIGHT;
+
+        for (uint32_t x = 0; x < m_width; x++) {
+            if (isNoise) {
+                // xorshift32
+                m_noiseState ^= m_noiseState << 13;
+                m_noiseState ^= m_noiseState >> 17;
+                m_noiseState ^= m_noiseState << 5;
+                row[x] = m_noiseState | 0xFF000000;
+            } else {
+                uint32_t value = (uint32_t)((x + m_frameIndex * 4) * 255 / m_width) & 0xFF;
+                row[x] = 0xFF000000 | (value << 16) | ((y * 255 / m_height) << 8) | (255 - value);
+            }
+        }
+    }
+
+    m_d3dRender->GetContext()->UpdateSubresource(
+        m_texture.Get(), 0, NULL, m_pixels.data(), m_width * sizeof(uint32_t), 0);
+}
+
+void EncoderBenchmark::EncodeFrame(uint64_t targetTimestampNs) {
+    UpdatePixels();
+
+    m_videoEncoder->Transmit(m_texture.Get(), targetTimestampNs, targetTimestampNs, m_frameIndex == 0);
+
+    m_frameIndex++;
+}
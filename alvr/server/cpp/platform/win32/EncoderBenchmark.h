@@ -0,0 +1,33 @@
+#pragma once
+
+#include "VideoEncoder.h"
+#include "shared/d3drender.h"
+#include <d3d11.h>
+#include <memory>
+#include <vector>
+#include <wrl.h>
+
+using Microsoft::WRL::ComPtr;
+
+// Encodes synthetic frames without SteamVR rendering anything. The encoded frames are sent through
+// VideoSend like the streamed ones, the streamer tells them apart.
+class EncoderBenchmark {
+  public:
+    EncoderBenchmark(std::shared_ptr<CD3DRender> d3dRender, uint32_t width, uint32_t height);
+    ~EncoderBenchmark();
+
+    void EncodeFrame(uint64_t targetTimestampNs);
+
+  private:
+    void UpdatePixels();
+
+    std::shared_ptr<CD3DRender> m_d3dRender;
+    std::shared_ptr<VideoEncoder> m_videoEncoder;
+    ComPtr<ID3D11Texture2D> m_texture;
+
+    uint32_t m_width;
+    uint32_t m_height;
+    std::vector<uint32_t> m_pixels;
+    uint64_t m_frameIndex;
+    uint32_t m_noiseState;
+};
@@ -0,0 +1,283 @@
+#include "MirrorCapture.h"
+#include "alvr_server/Utils.h"
+#include "alvr_server/Logger.h"
+#include "alvr_server/bindings.h"
+
+#include <algorithm>
+
+namespace {
+	bool GetChannelOrder(DXGI_FORMAT format, bool *isBgra) {
+		switch (format) {
+		case DXGI_FORMAT_R8G8B8A8_TYPELESS:
+		case DXGI_FORMAT_R8G8B8A8_UNORM:
+		case DXGI_FORMAT_R8G8B8A8_UNORM_SRGB:
+			*isBgra = false;
+			return true;
+		case DXGI_FORMAT_B8G8R8A8_TYPELESS:
+		case DXGI_FORMAT_B8G8R8A8_UNORM:
+		case DXGI_FORMAT_B8G8R8A8_UNORM_SRGB:
+			*isBgra = true;
+			return true;
+		default:
+			return false;
+		}
+	}
+
+	// Typeless textures need a typed view for the mip generation
+	DXGI_FORMAT GetViewFormat(DXGI_FORMAT format) {
+		switch (format) {
+		case DXGI_FORMAT_R8G8B8A8_TYPELESS:
+			return DXGI_FORMAT_R8G8B8A8_UNORM;
+		case DXGI_FORMAT_B8G8R8A8_TYPELESS:
+			return DXGI_FORMAT_B8G8R8A8_UNORM;
+		default:
+			return format;
+		}
+	}
+}
+
+MirrorCapture::MirrorCapture(std::shared_ptr<CD3DRender> pD3DRender)
+	: m_pD3DRender(pD3DRender)
+	, m_maxWidth(0)
+	, m_maxFps(0.0f)
+	, m_bothEyes(false)
+	, m_lastCaptureUs(0)
+	, m_stagingIndex(0)
+{
+}
+
+void MirrorCapture::Configure(uint32_t maxWidth, float maxFps, bool bothEyes) {
+	std::lock_guard<std::mutex> lock(m_configMutex);
+	m_maxWidth = maxWidth;
+	m_maxFps = maxFps;
+	m_bothEyes = bothEyes;
+}
+
+bool MirrorCapture::IsEnabled() {
+	std::lock_guard<std::mutex> lock(m_configMutex);
+	return m_maxFps > 0.0f;
+}
+
+void MirrorCapture::Capture(ID3D11Texture2D *pTexture[2], const vr::VRTextureBounds_t bounds[2]) {
+	uint32_t maxWidth;
+	float maxFps;
+	bool bothEyes;
+	{
+		std::lock_guard<std::mutex> lock(m_configMutex);
+		maxWidth = m_maxWidth;
+		maxFps = m_maxFps;
+		bothEyes = m_bothEyes;
+	}
+	if (maxFps <= 0.0f || pTexture[0] == nullptr) {
+		return;
+	}
+
+	uint64_t now = GetTimestampUs();
+	if (now - m_lastCaptureUs < (uint64_t)(1e6 / maxFps)) {
+		return;
+	}
+	m_lastCaptureUs = now;
+
+	ReportPending(m_stagingTextures[1 - m_stagingIndex]);
+
+	uint32_t eyeCount = bothEyes && pTexture[1] != nullptr ? 2 : 1;
+
+	D3D11_TEXTURE2D_DESC desc;
+	pTexture[0]->GetDesc(&desc);
+
+	bool isBgra;
+	if (!GetChannelOrder(desc.Format, &isBgra)) {
+		Debug("Mirror scene layer format %d is not supported\n", desc.Format);
+		return;
+	}
+	if (desc.SampleDesc.Count > 1) {
+		Debug("Multisampled scene layers are not supported by the mirror\n");
+		return;
+	}
+
+	// Both eyes use the size of the left eye region
+	const vr::VRTextureBounds_t &leftBounds = bounds[0];
+	uint32_t width = (uint32_t)(std::abs(leftBounds.uMax - leftBounds.uMin) * desc.Width);
+	uint32_t height = (uint32_t)(std::abs(leftBounds.vMax - leftBounds.vMin) * desc.Height);
+	if (width == 0 || height == 0) {
+		return;
+	}
+
+	// The mip chain halves the size at each level, the first level that fits is used
+	uint32_t mipLevels = 1;
+	while ((std::max(desc.Width, desc.Height) >> mipLevels) > 0) {
+		mipLevels++;
+	}
+	uint32_t level = 0;
+	while (level + 1 < mipLevels && (width >> level) * eyeCount > maxWidth) {
+		level++;
+	}
+	uint32_t levelWidth = std::max(width >> level, 1u);
+	uint32_t levelHeight = std::max(height >> level, 1u);
+
+	StagingTexture &staging = m_stagingTextures[m_stagingIndex];
+	if (!PrepareStagingTexture(staging, levelWidth * eyeCount, levelHeight, desc.Format)) {
+		return;
+	}
+
+	auto context = m_pD3DRender->GetContext();
+	for (uint32_t eye = 0; eye < eyeCount; eye++) {
+		D3D11_TEXTURE2D_DESC eyeDesc;
+		pTexture[eye]->GetDesc(&eyeDesc);
+		if (eyeDesc.Format != desc.Format || eyeDesc.Width != desc.Width || eyeDesc.Height != desc.Height
+			|| eyeDesc.SampleDesc.Count > 1) {
+			return;
+		}
+
+		uint32_t left = (uint32_t)(std::min(bounds[eye].uMin, bounds[eye].uMax) * desc.Width);
+		uint32_t top = (uint32_t)(std::min(bounds[eye].vMin, bounds[eye].vMax) * desc.Height);
+		left = std::min(left, desc.Width - width);
+		top = std::min(top, desc.Height - height);
+
+		ID3D11Texture2D *source = pTexture[eye];
+		if (level > 0 && eye > 0 && pTexture[eye] == pTexture[0]) {
+			// Both eyes are in the same texture, its mips are already generated
+			source = m_mipTextures[0].texture.Get();
+		}
+		else if (level > 0) {
+			MipTexture &mip = m_mipTextures[eye];
+			if (!PrepareMipTexture(mip, desc, level + 1)) {
+				return;
+			}
+			context->CopySubresourceRegion(mip.texture.Get(), 0, 0, 0, 0, pTexture[eye], 0, nullptr);
+			context->GenerateMips(mip.view.Get());
+			source = mip.texture.Get();
+		}
+
+		uint32_t levelLeft = std::min(left >> level, std::max(desc.Width >> level, 1u) - levelWidth);
+		uint32_t levelTop = std::min(top >> level, std::max(desc.Height >> level, 1u) - levelHeight);
+		D3D11_BOX box = { levelLeft, levelTop, 0, levelLeft + levelWidth, levelTop + levelHeight, 1 };
+		context->CopySubresourceRegion(staging.texture.Get(), 0, eye * levelWidth, 0, 0, source, level, &box);
+	}
+
+	staging.isBgra = isBgra;
+	staging.flipVertically = leftBounds.vMin > leftBounds.vMax;
+	staging.pending = true;
+	m_stagingIndex = 1 - m_stagingIndex;
+}
+
+bool MirrorCapture::PrepareMipTexture(MipTexture &mip, const D3D11_TEXTURE2D_DESC &desc, uint32_t mipLevels) {
+	D3D11_TEXTURE2D_DESC mipDesc = {};
+	if (mip.texture) {
+		mip.texture->GetDesc(&mipDesc);
+	}
+	if (mip.texture && mipDesc.Width == desc.Width && mipDesc.Height == desc.Height && mipDesc.Format == desc.Format
+		&& mipDesc.MipLevels == mipLevels) {
+		return true;
+	}
+
+	mipDesc = {};
+	mipDesc.Width = desc.Width;
+	mipDesc.Height = desc.Height;
+	mipDesc.MipLevels = mipLevels;
+	mipDesc.ArraySize = 1;
+	mipDesc.Format = desc.Format;
+	mipDesc.SampleDesc.Count = 1;
+	mipDesc.Usage = D3D11_USAGE_DEFAULT;
+	mipDesc.BindFlags = D3D11_BIND_SHADER_RESOURCE | D3D11_BIND_RENDER_TARGET;
+	mipDesc.MiscFlags = D3D11_RESOURCE_MISC_GENERATE_MIPS;
+
+	mip.texture.Reset();
+	mip.view.Reset();
+	HRESULT hr = m_pD3DRender->GetDevice()->CreateTexture2D(&mipDesc, NULL, &mip.texture);
+	if (FAILED(hr)) {
+		Error("Failed to create the mirror mip texture %p %ls\n", hr, GetErrorStr(hr).c_str());
+		return false;
+	}
+
+	D3D11_SHADER_RESOURCE_VIEW_DESC viewDesc = {};
+	viewDesc.Format = GetViewFormat(desc.Format);
+	viewDesc.ViewDimension = D3D11_SRV_DIMENSION_TEXTURE2D;
+	viewDesc.Texture2D.MipLevels = mipLevels;
+	hr = m_pD3DRender->GetDevice()->CreateShaderResourceView(mip.texture.Get(), &viewDesc, &mip.view);
+	if (FAILED(hr)) {
+		Error("Failed to create the mirror mip view %p %ls\n", hr, GetErrorStr(hr).c_str());
+		mip.texture.Reset();
+		return false;
+	}
+
+	return true;
+}
+
+bool MirrorCapture::PrepareStagingTexture(StagingTexture &staging, uint32_t width, uint32_t height, DXGI_FORMAT format) {
+	D3D11_TEXTURE2D_DESC stagingDesc = {};
+	if (staging.texture) {
+		staging.texture->GetDesc(&stagingDesc);
+	}
+	if (staging.texture && stagingDesc.Width == width && stagingDesc.Height == height && stagingDesc.Format == format) {
+		return true;
+	}
+
+	stagingDesc = {};
+	stagingDesc.Width = width;
+	stagingDesc.Height = height;
+	stagingDesc.MipLevels = 1;
+	stagingDesc.ArraySize = 1;
+	stagingDesc.Format = format;
+	stagingDesc.SampleDesc.Count = 1;
+	stagingDesc.Usage = D3D11_USAGE_STAGING;
+	stagingDesc.CPUAccessFlags = D3D11_CPU_ACCESS_READ;
+
+	staging.texture.Reset();
+	staging.pending = false;
+	HRESULT hr = m_pD3DRender->GetDevice()->CreateTexture2D(&stagingDesc, NULL, &staging.texture);
+	if (FAILED(hr)) {
+		Error("Failed to create the mirror staging texture %p %ls\n", hr, GetErrorStr(hr).c_str());
+		return false;
+	}
+	staging.width = width;
+	staging.height = height;
+
+	return true;
+}
+
+void MirrorCapture::ReportPending(StagingTexture &staging) {
+	if (!staging.pending) {
+		return;
+	}
+	staging.pending = false;
+
+	// The copy was queued one capture ago. If the GPU is still busy with it, the image is dropped
+	// instead of stalling the present thread
+	D3D11_MAPPED_SUBRESOURCE mapped;
+	HRESULT hr = m_pD3DRender->GetContext()->Map(staging.texture.Get(), 0, D3D11_MAP_READ, D3D11_MAP_FLAG_DO_NOT_WAIT, &mapped);
+	if (hr == DXGI_ERROR_WAS_STILL_DRAWING) {
+		return;
+	}
+	if (FAILED(hr)) {
+		Error("Failed to map the mirror staging texture %p %ls\n", hr, GetErrorStr(hr).c_str());
+		return;
+	}
+
+	uint32_t width = staging.width;
+	uint32_t height = staging.height;
+	m_pixels.resize(width * height * 4);
+	for (uint32_t y = 0; y < height; y++) {
+		const uint8_t *srcRow = (const uint8_t *)mapped.pData + (staging.flipVertically ? height - 1 - y : y) * mapped.RowPitch;
+		uint8_t *dstRow = &m_pixels[y * width * 4];
+
+		if (staging.isBgra) {
+			for (uint32_t x = 0; x < width; x++) {
+				dstRow[x * 4 + 0] = srcRow[x * 4 + 2];
+				dstRow[x * 4 + 1] = srcRow[x * 4 + 1];
+				dstRow[x * 4 + 2] = srcRow[x * 4 + 0];
+				dstRow[x * 4 + 3] = 255;
+			}
+		}
+		else {
+			for (uint32_t x = 0; x < width; x++) {
+				memcpy(&dstRow[x * 4], &srcRow[x * 4], 3);
+				dstRow[x * 4 + 3] = 255;
+			}
+		}
+	}
+
+	m_pD3DRender->GetContext()->Unmap(staging.texture.Get(), 0);
+
+	ReportMirrorFrame(width, height, m_pixels.data());
+}
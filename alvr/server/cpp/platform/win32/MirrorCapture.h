@@ -0,0 +1,60 @@
+#pragma once
+#include "shared/d3drender.h"
+#include "openvr_driver.h"
+
+#include <d3d11.h>
+#include <wrl.h>
+#include <memory>
+#include <mutex>
+#include <vector>
+
+using Microsoft::WRL::ComPtr;
+
+// Reads back the scene layer, downscaled and at a limited frame rate, for the mirror page. The
+// images don't go through the video encoder, so the page works whatever the codec.
+class MirrorCapture
+{
+public:
+	MirrorCapture(std::shared_ptr<CD3DRender> pD3DRender);
+
+	// maxFps 0 disables the capture
+	void Configure(uint32_t maxWidth, float maxFps, bool bothEyes);
+	bool IsEnabled();
+
+	// Must be called while the encoder does not use the D3D context. Each image is reported at the
+	// next capture, so the GPU copy is never waited on.
+	void Capture(ID3D11Texture2D *pTexture[2], const vr::VRTextureBounds_t bounds[2]);
+
+private:
+	struct MipTexture {
+		ComPtr<ID3D11Texture2D> texture;
+		ComPtr<ID3D11ShaderResourceView> view;
+	};
+
+	struct StagingTexture {
+		ComPtr<ID3D11Texture2D> texture;
+		uint32_t width = 0;
+		uint32_t height = 0;
+		bool isBgra = false;
+		bool flipVertically = false;
+		bool pending = false;
+	};
+
+	bool PrepareMipTexture(MipTexture &mip, const D3D11_TEXTURE2D_DESC &desc, uint32_t mipLevels);
+	bool PrepareStagingTexture(StagingTexture &staging, uint32_t width, uint32_t height, DXGI_FORMAT format);
+	void ReportPending(StagingTexture &staging);
+
+	std::shared_ptr<CD3DRender> m_pD3DRender;
+
+	std::mutex m_configMutex;
+	uint32_t m_maxWidth;
+	float m_maxFps;
+	bool m_bothEyes;
+
+	uint64_t m_lastCaptureUs;
+	MipTexture m_mipTextures[2];
+	// Written alternately, the one of the previous capture is read back
+	StagingTexture m_stagingTextures[2];
+	uint32_t m_stagingIndex;
+	std::vector<uint8_t> m_pixels;
+};
@@ -594,6 +594,7 @@ void NvEncoder::GetEncodedPacket(std::vector<NV_ENC_OUTPUT_PTR> &vOutputBuffer,
         NVENC_API_CALL(m_nvenc.nvEncLockBitstream(m_hEncoder, &lockBitstreamData));
   
         uint8_t *pData = (uint8_t *)lockBitstreamData.bitstreamBufferPtr;
+        m_nLastFrameAverageQP = lockBitstreamData.frameAvgQP;
         if (vPacket.size() < i + 1)
         {
             vPacket.push_back(std::vector<uint8_t>());
@@ -254,6 +254,11 @@ public:
     *  @brief This function returns the number of allocated buffers.
     */
     uint32_t GetEncoderBufferCount() const { return m_nEncoderBuffer; }
+
+    /**
+    *  @brief This function returns the average QP of the last frame retrieved from the encoder.
+    */
+    uint32_t GetLastFrameAverageQP() const { return m_nLastFrameAverageQP; }
 protected:
 
     /**
@@ -430,6 +435,7 @@ protected:
     IVFUtils m_IVFUtils;
     bool m_bWriteIVFFileHeader = true;
     bool m_bUseIVFContainer = true;
+    uint32_t m_nLastFrameAverageQP = 0;
 
 private:
     uint32_t m_nWidth;
@@ -0,0 +1,146 @@
+#include "OverlayCapture.h"
+#include "alvr_server/Utils.h"
+#include "alvr_server/Logger.h"
+#include "alvr_server/bindings.h"
+
+#include <algorithm>
+
+OverlayCapture::OverlayCapture(std::shared_ptr<CD3DRender> pD3DRender)
+	: m_pD3DRender(pD3DRender)
+	, m_maxFps(0.0f)
+	, m_lastCaptureUs(0)
+{
+}
+
+void OverlayCapture::SetMaxFps(float maxFps) {
+	m_maxFps = maxFps;
+}
+
+bool OverlayCapture::IsEnabled() {
+	return m_maxFps > 0.0f;
+}
+
+void OverlayCapture::Capture(ID3D11Texture2D *pTexture[][2], vr::VRTextureBounds_t bounds[][2], uint32_t layerCount) {
+	float maxFps = m_maxFps;
+	if (maxFps <= 0.0f) {
+		return;
+	}
+
+	uint64_t now = GetTimestampUs();
+	if (now - m_lastCaptureUs < (uint64_t)(1e6 / maxFps)) {
+		return;
+	}
+	m_lastCaptureUs = now;
+
+	uint32_t eyeCount = std::max(layerCount * 2, (uint32_t)m_eyeVisible.size());
+	m_eyeVisible.resize(eyeCount, false);
+
+	for (uint32_t i = 0; i < eyeCount; i++) {
+		uint32_t layerIndex = i / 2;
+		uint32_t eyeIndex = i % 2;
+
+		bool visible = layerIndex < layerCount && pTexture[layerIndex][eyeIndex] != nullptr
+			&& CaptureEye(layerIndex, eyeIndex, pTexture[layerIndex][eyeIndex], bounds[layerIndex][eyeIndex]);
+
+		// Hidden layers are reported once
+		if (!visible && m_eyeVisible[i]) {
+			ReportOverlayLayer(layerIndex, eyeIndex, 0, 0, nullptr);
+		}
+		m_eyeVisible[i] = visible;
+	}
+}
+
+bool OverlayCapture::CaptureEye(uint32_t layerIndex, uint32_t eyeIndex, ID3D11Texture2D *pTexture, const vr::VRTextureBounds_t &bounds) {
+	D3D11_TEXTURE2D_DESC desc;
+	pTexture->GetDesc(&desc);
+
+	bool isBgra;
+	switch (desc.Format) {
+	case DXGI_FORMAT_R8G8B8A8_TYPELESS:
+	case DXGI_FORMAT_R8G8B8A8_UNORM:
+	case DXGI_FORMAT_R8G8B8A8_UNORM_SRGB:
+		isBgra = false;
+		break;
+	case DXGI_FORMAT_B8G8R8A8_TYPELESS:
+	case DXGI_FORMAT_B8G8R8A8_UNORM:
+	case DXGI_FORMAT_B8G8R8A8_UNORM_SRGB:
+		isBgra = true;
+		break;
+	default:
+		Debug("Overlay layer format %d is not supported\n", desc.Format);
+		return false;
+	}
+	if (desc.SampleDesc.Count > 1) {
+		Debug("Multisampled overlay layers are not supported\n");
+		return false;
+	}
+
+	uint32_t left = (uint32_t)(std::min(bounds.uMin, bounds.uMax) * desc.Width);
+	uint32_t right = std::min((uint32_t)(std::max(bounds.uMin, bounds.uMax) * desc.Width), desc.Width);
+	uint32_t top = (uint32_t)(std::min(bounds.vMin, bounds.vMax) * desc.Height);
+	uint32_t bottom = std::min((uint32_t)(std::max(bounds.vMin, bounds.vMax) * desc.Height), desc.Height);
+	bool flipVertically = bounds.vMin > bounds.vMax;
+	if (right <= left || bottom <= top) {
+		return false;
+	}
+	uint32_t width = right - left;
+	uint32_t height = bottom - top;
+
+	D3D11_TEXTURE2D_DESC stagingDesc = {};
+	if (m_stagingTexture) {
+		m_stagingTexture->GetDesc(&stagingDesc);
+	}
+	if (!m_stagingTexture || stagingDesc.Width != width || stagingDesc.Height != height || stagingDesc.Format != desc.Format) {
+		stagingDesc = {};
+		stagingDesc.Width = width;
+		stagingDesc.Height = height;
+		stagingDesc.MipLevels = 1;
+		stagingDesc.ArraySize = 1;
+		stagingDesc.Format = desc.Format;
+		stagingDesc.SampleDesc.Count = 1;
+		stagingDesc.Usage = D3D11_USAGE_STAGING;
+		stagingDesc.CPUAccessFlags = D3D11_CPU_ACCESS_READ;
+
+		m_stagingTexture.Reset();
+		HRESULT hr = m_pD3DRender->GetDevice()->CreateTexture2D(&stagingDesc, NULL, &m_stagingTexture);
+		if (FAILED(hr)) {
+			Error("Failed to create the overlay staging texture %p %ls\n", hr, GetErrorStr(hr).c_str());
+			return false;
+		}
+	}
+
+	D3D11_BOX box = { left, top, 0, right, bottom, 1 };
+	m_pD3DRender->GetContext()->CopySubresourceRegion(m_stagingTexture.Get(), 0, 0, 0, 0, pTexture, 0, &box);
+
+	// Overlays are captured at a low rate, stalling the pipeline here is acceptable
+	D3D11_MAPPED_SUBRESOURCE mapped;
+	HRESULT hr = m_pD3DRender->GetContext()->Map(m_stagingTexture.Get(), 0, D3D11_MAP_READ, 0, &mapped);
+	if (FAILED(hr)) {
+		Error("Failed to map the overlay staging texture %p %ls\n", hr, GetErrorStr(hr).c_str());
+		return false;
+	}
+
+	m_pixels.resize(width * height * 4);
+	for (uint32_t y = 0; y < height; y++) {
+		const uint8_t *srcRow = (const uint8_t *)mapped.pData + (flipVertically ? height - 1 - y : y) * mapped.RowPitch;
+		uint8_t *dstRow = &m_pixels[y * width * 4];
+
+		if (isBgra) {
+			for (uint32_t x = 0; x < width; x++) {
+				dstRow[x * 4 + 0] = srcRow[x * 4 + 2];
+				dstRow[x * 4 + 1] = srcRow[x * 4 + 1];
+				dstRow[x * 4 + 2] = srcRow[x * 4 + 0];
+				dstRow[x * 4 + 3] = srcRow[x * 4 + 3];
+			}
+		}
+		else {
+			memcpy(dstRow, srcRow, width * 4);
+		}
+	}
+
+	m_pD3DRender->GetContext()->Unmap(m_stagingTexture.Get(), 0);
+
+	ReportOverlayLayer(layerIndex, eyeIndex, width, height, m_pixels.data());
+
+	return true;
+}
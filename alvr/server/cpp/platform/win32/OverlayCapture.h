@@ -0,0 +1,40 @@
+#pragma once
+#include "shared/d3drender.h"
+#include "openvr_driver.h"
+
+#include <d3d11.h>
+#include <wrl.h>
+#include <atomic>
+#include <memory>
+#include <vector>
+
+using Microsoft::WRL::ComPtr;
+
+// Reads back the layers submitted on top of the scene layer (SteamVR dashboard, overlays) at a low
+// frame rate. They are streamed as images separate from the video, so the client can show them at
+// full resolution.
+class OverlayCapture
+{
+public:
+	OverlayCapture(std::shared_ptr<CD3DRender> pD3DRender);
+
+	// 0 disables the capture
+	void SetMaxFps(float maxFps);
+	bool IsEnabled();
+
+	// Must be called while the encoder does not use the D3D context. Layers that were captured
+	// before and are not submitted anymore are reported as hidden.
+	void Capture(ID3D11Texture2D *pTexture[][2], vr::VRTextureBounds_t bounds[][2], uint32_t layerCount);
+
+private:
+	bool CaptureEye(uint32_t layerIndex, uint32_t eyeIndex, ID3D11Texture2D *pTexture, const vr::VRTextureBounds_t &bounds);
+
+	std::shared_ptr<CD3DRender> m_pD3DRender;
+	std::atomic<float> m_maxFps;
+	uint64_t m_lastCaptureUs;
+	// Indexed by layer * 2 + eye
+	std::vector<bool> m_eyeVisible;
+
+	ComPtr<ID3D11Texture2D> m_stagingTexture;
+	std::vector<uint8_t> m_pixels;
+};
@@ -1,8 +1,12 @@
 #include "OvrDirectModeComponent.h"
 
+#include <algorithm>
+
 OvrDirectModeComponent::OvrDirectModeComponent(std::shared_ptr<CD3DRender> pD3DRender, std::shared_ptr<PoseHistory> poseHistory)
 	: m_pD3DRender(pD3DRender)
 	, m_poseHistory(poseHistory)
+	, m_overlayCapture(pD3DRender)
+	, m_mirrorCapture(pD3DRender)
 	, m_submitLayer(0)
 {
 }
@@ -11,6 +15,14 @@ void OvrDirectModeComponent::SetEncoder(std::shared_ptr<CEncoder> pEncoder) {
 	m_pEncoder = pEncoder;
 }
 
+void OvrDirectModeComponent::SetOverlayLayersMaxFps(float maxFps) {
+	m_overlayCapture.SetMaxFps(maxFps);
+}
+
+void OvrDirectModeComponent::SetMirrorCapture(uint32_t maxWidth, float maxFps, bool bothEyes) {
+	m_mirrorCapture.Configure(maxWidth, maxFps, bothEyes);
+}
+
 /** Specific to Oculus compositor support, textures supplied must be created using this method. */
 void OvrDirectModeComponent::CreateSwapTextureSet(uint32_t unPid, const SwapTextureSetDesc_t *pSwapTextureSetDesc, SwapTextureSet_t *pOutSwapTextureSet)
 {
@@ -291,8 +303,24 @@ void OvrDirectModeComponent::CopyTexture(uint32_t layerCount) {
 
 		uint64_t submitFrameIndex = m_targetTimestampNs;
 
+		// The first layer is the scene, the others are the dashboard and overlays. When they are
+		// streamed separately, only the scene goes in the video stream
+		uint32_t encodedLayerCount = layerCount;
+		if (m_overlayCapture.IsEnabled()) {
+			encodedLayerCount = std::min(layerCount, 1u);
+		}
+
 		// Copy entire texture to staging so we can read the pixels to send to remote device.
-		m_pEncoder->CopyToStaging(pTexture, bounds, layerCount,false, presentationTime, submitFrameIndex,"", debugText);
+		m_pEncoder->CopyToStaging(pTexture, bounds, encodedLayerCount,false, presentationTime, submitFrameIndex,"", debugText);
+
+		if (m_overlayCapture.IsEnabled()) {
+			m_overlayCapture.Capture(pTexture + encodedLayerCount, bounds + encodedLayerCount, layerCount - encodedLayerCount);
+		}
+
+		// The scene layer is captured before the foveated encoding and without the overlays
+		if (layerCount > 0 && m_mirrorCapture.IsEnabled()) {
+			m_mirrorCapture.Capture(pTexture[0], bounds[0]);
+		}
 
 		m_pD3DRender->GetContext()->Flush();
 	}
@@ -2,6 +2,8 @@
 #include "openvr_driver.h"
 #include "alvr_server/Utils.h"
 #include "CEncoder.h"
+#include "OverlayCapture.h"
+#include "MirrorCapture.h"
 #include "alvr_server/PoseHistory.h"
 
 #include "alvr_server/Settings.h"
@@ -16,6 +18,12 @@ public:
 
 	void SetEncoder(std::shared_ptr<CEncoder> pEncoder);
 
+	// 0 composites the overlay layers into the video stream
+	void SetOverlayLayersMaxFps(float maxFps);
+
+	// maxFps 0 stops the capture of the mirror page images
+	void SetMirrorCapture(uint32_t maxWidth, float maxFps, bool bothEyes);
+
 	/** Specific to Oculus compositor support, textures supplied must be created using this method. */
 	virtual void CreateSwapTextureSet( uint32_t unPid, const SwapTextureSetDesc_t *pSwapTextureSetDesc, SwapTextureSet_t *pOutSwapTextureSet );
 
@@ -44,6 +52,8 @@ private:
 	std::shared_ptr<CD3DRender> m_pD3DRender;
 	std::shared_ptr<CEncoder> m_pEncoder;
 	std::shared_ptr<PoseHistory> m_poseHistory;
+	OverlayCapture m_overlayCapture;
+	MirrorCapture m_mirrorCapture;
 
 	// Resource for each process
 	struct ProcessResource {
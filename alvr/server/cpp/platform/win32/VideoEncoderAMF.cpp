@@ -1,5 +1,6 @@
 #include "VideoEncoderAMF.h"
 
+#include "alvr_server/EncoderQp.h"
 #include "alvr_server/Logger.h"
 #include "alvr_server/Settings.h"
 
@@ -9,6 +10,30 @@ if(res != AMF_OK){throw MakeException("AMF Error %d. %s", res, L#expr);}}
 const wchar_t *VideoEncoderAMF::START_TIME_PROPERTY = L"StartTimeProperty";
 const wchar_t *VideoEncoderAMF::FRAME_INDEX_PROPERTY = L"FrameIndexProperty";
 
+// The statistics are requested with each frame. Drivers that don't support them leave the
+// properties unset
+static FfiEncoderQp ReadEncoderQp(const amf::AMFDataPtr &data, int codec) {
+	amf_int64 averageQp = -1;
+	amf::AMFInterfacePtr blockQpMap;
+	if (codec == ALVR_CODEC_H264) {
+		data->GetProperty(AMF_VIDEO_ENCODER_STATISTIC_AVERAGE_QP, &averageQp);
+		data->GetProperty(AMF_VIDEO_ENCODER_BLOCK_QP_MAP, &blockQpMap);
+	} else {
+		data->GetProperty(AMF_VIDEO_ENCODER_HEVC_STATISTIC_AVERAGE_QP, &averageQp);
+		data->GetProperty(AMF_VIDEO_ENCODER_HEVC_BLOCK_QP_MAP, &blockQpMap);
+	}
+
+	amf::AMFSurfacePtr blockQpSurface(blockQpMap);
+	if (blockQpSurface == nullptr || blockQpSurface->Convert(amf::AMF_MEMORY_HOST) != AMF_OK) {
+		return ComputeRegionQp((float)averageQp, nullptr, 0, 0, 0);
+	}
+
+	// One 32 bit value per macroblock or coding tree block
+	amf::AMFPlanePtr plane = blockQpSurface->GetPlaneAt(0);
+	return ComputeRegionQp((float)averageQp, (const uint32_t *)plane->GetNative(), plane->GetWidth(),
+		plane->GetHeight(), plane->GetHPitch());
+}
+
 AMFPipe::AMFPipe(amf::AMFComponentPtr src, AMFDataReceiver receiver) 
 	: m_amfComponentSrc(src)
 	, m_receiver(receiver) 
@@ -151,6 +176,7 @@ amf::AMFComponentPtr VideoEncoderAMF::MakeEncoder(
 		amfEncoder->SetProperty(AMF_VIDEO_ENCODER_USAGE, AMF_VIDEO_ENCODER_USAGE_ULTRA_LOW_LATENCY);
 		amfEncoder->SetProperty(AMF_VIDEO_ENCODER_PROFILE, AMF_VIDEO_ENCODER_PROFILE_HIGH);
 		amfEncoder->SetProperty(AMF_VIDEO_ENCODER_PROFILE_LEVEL, 42);
+		amfEncoder->SetProperty(AMF_VIDEO_ENCODER_SLICES_PER_FRAME, Settings::Instance().m_sliceCount);
 		switch (Settings::Instance().m_rateControlMode) {
 			case ALVR_CBR:
 				amfEncoder->SetProperty(AMF_VIDEO_ENCODER_RATE_CONTROL_METHOD, AMF_VIDEO_ENCODER_RATE_CONTROL_METHOD_CBR);
@@ -196,6 +222,13 @@ amf::AMFComponentPtr VideoEncoderAMF::MakeEncoder(
 		//Turns Off IDR/I Frames
 		amfEncoder->SetProperty(AMF_VIDEO_ENCODER_IDR_PERIOD, 0);
 
+		if (Settings::Instance().m_periodicIntraRefreshFrames > 0) {
+			// Refresh the whole frame (in 16x16 macroblocks) over the period
+			int mbCount = ((width + 15) / 16) * ((height + 15) / 16);
+			amfEncoder->SetProperty(AMF_VIDEO_ENCODER_INTRA_REFRESH_NUM_MBS_PER_SLOT,
+				(mbCount + Settings::Instance().m_periodicIntraRefreshFrames - 1) / Settings::Instance().m_periodicIntraRefreshFrames);
+		}
+
 		// Disable AUD to produce the same stream format as VideoEncoderNVENC.
 		// FIXME: This option doesn't work in 22.10.3, but works in versions prior 22.5.1
 		amfEncoder->SetProperty(AMF_VIDEO_ENCODER_INSERT_AUD, false);
@@ -215,6 +248,7 @@ amf::AMFComponentPtr VideoEncoderAMF::MakeEncoder(
 	else
 	{
 		amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_USAGE, AMF_VIDEO_ENCODER_HEVC_USAGE_ULTRA_LOW_LATENCY);
+		amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_SLICES_PER_FRAME, Settings::Instance().m_sliceCount);
 		switch (Settings::Instance().m_rateControlMode) {
 			case ALVR_CBR:
 				amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_RATE_CONTROL_METHOD, AMF_VIDEO_ENCODER_HEVC_RATE_CONTROL_METHOD_CBR);
@@ -258,6 +292,13 @@ amf::AMFComponentPtr VideoEncoderAMF::MakeEncoder(
 		amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_NUM_GOPS_PER_IDR, 0);
 		//Set infinite GOP length
 		amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_GOP_SIZE, 0);
+
+		if (Settings::Instance().m_periodicIntraRefreshFrames > 0) {
+			// Refresh the whole frame (in 64x64 CTBs) over the period
+			int ctbCount = ((width + 63) / 64) * ((height + 63) / 64);
+			amfEncoder->SetProperty(AMF_VIDEO_ENCODER_HEVC_INTRA_REFRESH_NUM_CTBS_PER_SLOT,
+				(ctbCount + Settings::Instance().m_periodicIntraRefreshFrames - 1) / Settings::Instance().m_periodicIntraRefreshFrames);
+		}
 		
 		// Disable AUD to produce the same stream format as VideoEncoderNVENC.
 		// FIXME: This option doesn't work in 22.10.3, but works in versions prior 22.5.1
@@ -388,6 +429,16 @@ void VideoEncoderAMF::Transmit(ID3D11Texture2D *pTexture, uint64_t presentationT
 
 	auto params = GetDynamicEncoderParams();
 	if (params.updated) {
+		int refreshRate = Settings::Instance().m_refreshRate;
+		if (refreshRate != m_refreshRate) {
+			m_refreshRate = refreshRate;
+			if (m_codec == ALVR_CODEC_H264) {
+				m_amfComponents.back()->SetProperty(AMF_VIDEO_ENCODER_FRAMERATE, ::AMFConstructRate(m_refreshRate, 1));
+			} else {
+				m_amfComponents.back()->SetProperty(AMF_VIDEO_ENCODER_HEVC_FRAMERATE, ::AMFConstructRate(m_refreshRate, 1));
+			}
+		}
+
 		amf_int64 bitRateIn = params.bitrate_bps / params.framerate * m_refreshRate; // in bits
 		if (m_codec == ALVR_CODEC_H264)
 		{
@@ -451,6 +502,11 @@ void VideoEncoderAMF::Receive(AMFDataPtr data)
 		isIdr = type == AMF_VIDEO_ENCODER_HEVC_OUTPUT_DATA_TYPE_IDR;
 	}
 
+	FfiEncoderQp qp = ReadEncoderQp(data, m_codec);
+	if (qp.average >= 0) {
+		ReportEncoderQp(targetTimestampNs, qp);
+	}
+
 	ParseFrameNals(m_codec, reinterpret_cast<uint8_t *>(p), length, targetTimestampNs, isIdr);
 }
 
@@ -459,6 +515,8 @@ void VideoEncoderAMF::ApplyFrameProperties(const amf::AMFSurfacePtr &surface, bo
 	case ALVR_CODEC_H264:
 		// FIXME: This option doesn't work in drivers 22.3.1 - 22.5.1, but works in 22.10.3
 		surface->SetProperty(AMF_VIDEO_ENCODER_INSERT_AUD, false);
+		surface->SetProperty(AMF_VIDEO_ENCODER_STATISTICS_FEEDBACK, true);
+		surface->SetProperty(AMF_VIDEO_ENCODER_BLOCK_QP_FEEDBACK, true);
 		if (insertIDR) {
 			Debug("Inserting IDR frame for H.264.\n");
 			surface->SetProperty(AMF_VIDEO_ENCODER_INSERT_SPS, true);
@@ -469,6 +527,8 @@ void VideoEncoderAMF::ApplyFrameProperties(const amf::AMFSurfacePtr &surface, bo
 	case ALVR_CODEC_H265:
 		// FIXME: This option works with 22.10.3, but may not work with older drivers
 		surface->SetProperty(AMF_VIDEO_ENCODER_HEVC_INSERT_AUD, false);
+		surface->SetProperty(AMF_VIDEO_ENCODER_HEVC_STATISTICS_FEEDBACK, true);
+		surface->SetProperty(AMF_VIDEO_ENCODER_HEVC_BLOCK_QP_FEEDBACK, true);
 		if (insertIDR) {
 			Debug("Inserting IDR frame for H.265.\n");
 			// Insert VPS,SPS,PPS
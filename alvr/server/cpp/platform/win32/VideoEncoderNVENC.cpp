@@ -112,6 +112,11 @@ void VideoEncoderNVENC::Transmit(ID3D11Texture2D *pTexture, uint64_t presentatio
 	}
 	m_NvNecoder->EncodeFrame(vPacket, &picParams);
 
+	if (!vPacket.empty()) {
+		// NVENC only reports the frame average
+		ReportEncoderQp(targetTimestampNs, { (float)m_NvNecoder->GetLastFrameAverageQP(), -1.f, -1.f });
+	}
+
 	for (std::vector<uint8_t> &packet : vPacket)
 	{
 		if (fpOut) {
@@ -185,6 +190,9 @@ void VideoEncoderNVENC::FillEncodeConfig(NV_ENC_INITIALIZE_PARAMS &initializePar
 	if (m_codec == ALVR_CODEC_H264) {
 		auto &config = encodeConfig.encodeCodecConfig.h264Config;
 		config.repeatSPSPPS = 1;
+		// Mode 3: fixed number of slices per picture
+		config.sliceMode = 3;
+		config.sliceModeData = Settings::Instance().m_sliceCount;
 		config.enableIntraRefresh = Settings::Instance().m_nvencEnableIntraRefresh;
 		
 		if (Settings::Instance().m_nvencIntraRefreshPeriod != -1) {
@@ -193,6 +201,11 @@ void VideoEncoderNVENC::FillEncodeConfig(NV_ENC_INITIALIZE_PARAMS &initializePar
 		if (Settings::Instance().m_nvencIntraRefreshCount != -1) {
 			config.intraRefreshCnt = Settings::Instance().m_nvencIntraRefreshCount;
 		}
+		if (Settings::Instance().m_periodicIntraRefreshFrames > 0) {
+			config.enableIntraRefresh = 1;
+			config.intraRefreshPeriod = Settings::Instance().m_periodicIntraRefreshFrames;
+			config.intraRefreshCnt = Settings::Instance().m_periodicIntraRefreshFrames - 1;
+		}
 
 		switch (Settings::Instance().m_entropyCoding) {
 			case ALVR_CABAC:
@@ -213,6 +226,9 @@ void VideoEncoderNVENC::FillEncodeConfig(NV_ENC_INITIALIZE_PARAMS &initializePar
 	else {
 		auto &config = encodeConfig.encodeCodecConfig.hevcConfig;
 		config.repeatSPSPPS = 1;
+		// Mode 3: fixed number of slices per picture
+		config.sliceMode = 3;
+		config.sliceModeData = Settings::Instance().m_sliceCount;
 		config.enableIntraRefresh = Settings::Instance().m_nvencEnableIntraRefresh;
 
 		if (Settings::Instance().m_nvencIntraRefreshPeriod != -1) {
@@ -221,6 +237,11 @@ void VideoEncoderNVENC::FillEncodeConfig(NV_ENC_INITIALIZE_PARAMS &initializePar
 		if (Settings::Instance().m_nvencIntraRefreshCount != -1) {
 			config.intraRefreshCnt = Settings::Instance().m_nvencIntraRefreshCount;
 		}
+		if (Settings::Instance().m_periodicIntraRefreshFrames > 0) {
+			config.enableIntraRefresh = 1;
+			config.intraRefreshPeriod = Settings::Instance().m_periodicIntraRefreshFrames;
+			config.intraRefreshCnt = Settings::Instance().m_periodicIntraRefreshFrames - 1;
+		}
 
 		config.maxNumRefFramesInDPB = maxNumRefFrames;
 		config.idrPeriod = gopLength;
@@ -89,6 +89,7 @@ void VideoEncoderSW::Initialize() {
 	}
 	m_codecContext->rc_max_rate = m_codecContext->bit_rate;
 	m_codecContext->thread_count = settings.m_swThreadCount;
+	m_codecContext->slices = settings.m_sliceCount;
 
 	if((err = avcodec_open2(m_codecContext, codec, &opt))) throw MakeException("Cannot open video encoder codec: %d", err);
 
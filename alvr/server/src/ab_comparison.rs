@@ -0,0 +1,323 @@
+// Objective comparison of two encoder configurations. The bitrate and optionally the codec
+// alternate between variant A and variant B for the configured number of rounds while the frame
+// statistics are accumulated separately for each variant. The encoder is recreated when the codec
+// changes, the first seconds of every phase are discarded to let it settle. The codec of the stream
+// is restored at the end.
+
+use crate::{connection::IS_STREAMING, DECODER_CONFIG, FILESYSTEM_LAYOUT, SERVER_DATA_MANAGER};
+use alvr_common::{info, once_cell::sync::Lazy, parking_lot::Mutex, warn};
+use alvr_events::{AbComparisonReport, AbVariantResult, EventType, GraphStatistics};
+use alvr_session::{settings_schema::Switch, AbComparisonConfig, CodecType};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const SETTLE_DURATION: Duration = Duration::from_secs(2);
+const PHASE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const VARIANT_NAMES: [&str; 2] = ["A", "B"];
+
+#[derive(Default)]
+struct VariantAccumulator {
+    // Codec of the frames actually received, not the requested one
+    codec: Option<CodecType>,
+    frames: usize,
+    total_latency_sum_s: f32,
+    encoder_latency_sum_s: f32,
+    network_latency_sum_s: f32,
+    decoder_latency_sum_s: f32,
+    actual_bitrate_sum_bps: f32,
+    encoder_qp_sum: f32,
+    encoder_qp_count: usize,
+    frame_drops: usize,
+    screenshots: Vec<String>,
+}
+
+impl VariantAccumulator {
+    fn result(&self, name: &str, bitrate_mbps: u64) -> AbVariantResult {
+        let frames = usize::max(self.frames, 1) as f32;
+
+        AbVariantResult {
+            name: name.into(),
+            bitrate_mbps,
+            codec: self
+                .codec
+                .map(|codec| format!("{codec:?}"))
+                .unwrap_or_default(),
+            frames: self.frames,
+            average_total_latency_ms: self.total_latency_sum_s / frames * 1000.0,
+            average_encoder_latency_ms: self.encoder_latency_sum_s / frames * 1000.0,
+            average_network_latency_ms: self.network_latency_sum_s / frames * 1000.0,
+            average_decoder_latency_ms: self.decoder_latency_sum_s / frames * 1000.0,
+            average_actual_bitrate_mbps: self.actual_bitrate_sum_bps / frames / 1e6,
+            average_encoder_qp: (self.encoder_qp_count > 0)
+                .then(|| self.encoder_qp_sum / self.encoder_qp_count as f32),
+            frame_drops: self.frame_drops,
+            screenshots: self.screenshots.clone(),
+        }
+    }
+}
+
+struct Comparison {
+    config: AbComparisonConfig,
+    stream_codec: CodecType,
+    start_unix_secs: u64,
+    // Phases alternate between variant A (even) and variant B (odd)
+    phase_index: usize,
+    phase_start: Instant,
+    screenshot_requested: bool,
+    variants: [VariantAccumulator; 2],
+}
+
+impl Comparison {
+    fn phase_count(&self) -> usize {
+        self.config.rounds as usize * 2
+    }
+
+    fn variant_index(&self) -> usize {
+        self.phase_index % 2
+    }
+
+    fn variant_bitrate_mbps(&self, index: usize) -> u64 {
+        if index == 0 {
+            self.config.variant_a_bitrate_mbps
+        } else {
+            self.config.variant_b_bitrate_mbps
+        }
+    }
+
+    fn variant_codec(&self, index: usize) -> CodecType {
+        let codec = if index == 0 {
+            &self.config.variant_a_codec
+        } else {
+            &self.config.variant_b_codec
+        };
+
+        match codec {
+            Switch::Enabled(codec) => *codec,
+            Switch::Disabled => self.stream_codec,
+        }
+    }
+
+    fn apply_variant_codec(&self) {
+        let codec = self.variant_codec(self.variant_index());
+        unsafe { crate::SetEncoderCodec(codec as i32) };
+    }
+
+    fn is_settling(&self) -> bool {
+        self.phase_start.elapsed() < SETTLE_DURATION
+    }
+
+    fn progress(&self) -> String {
+        format!(
+            "Round {}/{}, variant {} ({} Mbps, {:?})",
+            self.phase_index / 2 + 1,
+            self.config.rounds,
+            VARIANT_NAMES[self.variant_index()],
+            self.variant_bitrate_mbps(self.variant_index()),
+            self.variant_codec(self.variant_index())
+        )
+    }
+
+    fn report(&self, completed: bool) -> AbComparisonReport {
+        let mut codecs = self
+            .variants
+            .iter()
+            .filter_map(|variant| variant.codec)
+            .map(|codec| format!("{codec:?}"))
+            .collect::<Vec<_>>();
+        codecs.dedup();
+
+        AbComparisonReport {
+            start_unix_secs: self.start_unix_secs,
+            codec: codecs.join(" / "),
+            phase_duration_s: self.config.phase_duration_s,
+            rounds: self.config.rounds,
+            completed,
+            results: self
+                .variants
+                .iter()
+                .enumerate()
+                .map(|(index, variant)| {
+                    variant.result(VARIANT_NAMES[index], self.variant_bitrate_mbps(index))
+                })
+                .collect(),
+        }
+    }
+}
+
+static COMPARISON: Lazy<Mutex<Option<Comparison>>> = Lazy::new(|| Mutex::new(None));
+
+// Incremented at every start and stop, so that the phase thread of a previous comparison exits
+static COMPARISON_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+fn finish(comparison: Comparison, completed: bool) {
+    alvr_events::send_event(EventType::AbComparisonProgress(None));
+
+    unsafe { crate::SetEncoderCodec(comparison.stream_codec as i32) };
+
+    match alvr_server_io::append_ab_comparison_report(
+        &FILESYSTEM_LAYOUT.ab_comparison_reports(),
+        comparison.report(completed),
+    ) {
+        Ok(reports) => alvr_events::send_event(EventType::AbComparisonReports(reports)),
+        Err(e) => warn!("Failed to save A/B comparison report: {e}"),
+    }
+}
+
+pub fn start() {
+    if !IS_STREAMING.value() {
+        warn!("Cannot start the A/B comparison, no client is streaming");
+        return;
+    }
+
+    let (mut config, preferred_codec) = {
+        let data_manager_lock = SERVER_DATA_MANAGER.read();
+        let settings = data_manager_lock.settings();
+
+        (
+            settings.capture.ab_comparison.clone(),
+            settings.video.preferred_codec,
+        )
+    };
+    // A session edited by hand could contain 0 rounds, the comparison would end without phases
+    config.rounds = config.rounds.max(1);
+    let phase_duration = Duration::from_secs(config.phase_duration_s);
+
+    let comparison = Comparison {
+        config,
+        stream_codec: DECODER_CONFIG
+            .lock()
+            .as_ref()
+            .map(|config| config.codec)
+            .unwrap_or(preferred_codec),
+        start_unix_secs: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default(),
+        phase_index: 0,
+        phase_start: Instant::now(),
+        screenshot_requested: false,
+        variants: Default::default(),
+    };
+    info!("A/B comparison started: {}", comparison.progress());
+    comparison.apply_variant_codec();
+    alvr_events::send_event(EventType::AbComparisonProgress(Some(comparison.progress())));
+
+    let generation = {
+        let mut comparison_lock = COMPARISON.lock();
+        if let Some(previous) = comparison_lock.take() {
+            finish(previous, false);
+        }
+        *comparison_lock = Some(comparison);
+
+        COMPARISON_GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+    };
+
+    thread::spawn(move || loop {
+        thread::sleep(PHASE_POLL_INTERVAL);
+
+        if COMPARISON_GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+
+        let mut comparison_lock = COMPARISON.lock();
+        let Some(comparison) = &mut *comparison_lock else {
+            return;
+        };
+
+        if !IS_STREAMING.value() {
+            info!("A/B comparison interrupted by the client disconnection");
+            finish(comparison_lock.take().unwrap(), false);
+            return;
+        }
+
+        let elapsed = comparison.phase_start.elapsed();
+
+        if comparison.config.capture_client_screenshots
+            && !comparison.screenshot_requested
+            && elapsed > phase_duration / 2
+        {
+            let label = format!(
+                "ab_{}_round{}_{}",
+                comparison.start_unix_secs,
+                comparison.phase_index / 2 + 1,
+                VARIANT_NAMES[comparison.variant_index()]
+            );
+            crate::connection::request_client_screenshot(label.clone());
+
+            let variant_index = comparison.variant_index();
+            comparison.variants[variant_index].screenshots.push(label);
+            comparison.screenshot_requested = true;
+        }
+
+        if elapsed >= phase_duration {
+            comparison.phase_index += 1;
+            comparison.phase_start = Instant::now();
+            comparison.screenshot_requested = false;
+
+            if comparison.phase_index == comparison.phase_count() {
+                info!("A/B comparison completed");
+                finish(comparison_lock.take().unwrap(), true);
+                return;
+            }
+
+            comparison.apply_variant_codec();
+            alvr_events::send_event(EventType::AbComparisonProgress(Some(comparison.progress())));
+        }
+    });
+}
+
+pub fn stop() {
+    COMPARISON_GENERATION.fetch_add(1, Ordering::SeqCst);
+
+    if let Some(comparison) = COMPARISON.lock().take() {
+        info!("A/B comparison stopped");
+        finish(comparison, false);
+    }
+}
+
+// Bitrate of the current variant, overriding the bitrate mode setting
+pub fn bitrate_override_mbps() -> Option<u64> {
+    COMPARISON
+        .lock()
+        .as_ref()
+        .map(|comparison| comparison.variant_bitrate_mbps(comparison.variant_index()))
+}
+
+pub fn report_frame(statistics: &GraphStatistics) {
+    if let Some(comparison) = &mut *COMPARISON.lock() {
+        if comparison.is_settling() {
+            return;
+        }
+
+        let variant_index = comparison.variant_index();
+        let variant = &mut comparison.variants[variant_index];
+        if let Some(config) = &*DECODER_CONFIG.lock() {
+            variant.codec = Some(config.codec);
+        }
+        variant.frames += 1;
+        variant.total_latency_sum_s += statistics.total_pipeline_latency_s;
+        variant.encoder_latency_sum_s += statistics.encoder_s;
+        variant.network_latency_sum_s += statistics.network_s;
+        variant.decoder_latency_sum_s += statistics.decoder_s;
+        variant.actual_bitrate_sum_bps += statistics.actual_bitrate_bps;
+        if let Some(qp) = statistics.encoder_qp {
+            variant.encoder_qp_sum += qp;
+            variant.encoder_qp_count += 1;
+        }
+    }
+}
+
+pub fn report_frame_drops(count: usize) {
+    if let Some(comparison) = &mut *COMPARISON.lock() {
+        if comparison.is_settling() {
+            return;
+        }
+
+        let variant_index = comparison.variant_index();
+        comparison.variants[variant_index].frame_drops += count;
+    }
+}
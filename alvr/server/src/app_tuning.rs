@@ -0,0 +1,133 @@
+// Per-game tuning of controller prediction, thumbstick deadzone and haptics intensity, applied
+// when the scene application changes. The database shipped with the streamer can be extended
+// with app_tuning.json in the config directory, whose entries take precedence.
+
+use crate::{FILESYSTEM_LAYOUT, SERVER_DATA_MANAGER, STATISTICS_MANAGER};
+use alvr_common::{
+    info, once_cell::sync::Lazy, parking_lot::Mutex, settings_schema::Switch, warn,
+    LEFT_THUMBSTICK_X_ID, LEFT_THUMBSTICK_Y_ID, RIGHT_THUMBSTICK_X_ID, RIGHT_THUMBSTICK_Y_ID,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+const SHIPPED_DATABASE: &str = include_str!("../resources/app_tuning.json");
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct AppTuning {
+    // Only used to make the database readable
+    pub name: String,
+    // An entry matches if either the Steam app ID or the executable name match
+    pub steam_app_id: Option<u32>,
+    pub executable_name: Option<String>,
+    // Overrides the controllers prediction setting
+    pub steamvr_pipeline_frames: Option<f32>,
+    // Fraction of the thumbstick range ignored around the center, per axis
+    pub thumbstick_deadzone: Option<f32>,
+    // Multiplies the intensity set in the haptics settings
+    pub haptics_intensity_multiplier: Option<f32>,
+}
+
+static ACTIVE_TUNING: Lazy<Mutex<Option<AppTuning>>> = Lazy::new(|| Mutex::new(None));
+
+fn load_database() -> Vec<AppTuning> {
+    let mut database = vec![];
+
+    if let Ok(user_database) = fs::read_to_string(FILESYSTEM_LAYOUT.app_tuning()) {
+        match serde_json::from_str::<Vec<AppTuning>>(&user_database) {
+            Ok(entries) => database.extend(entries),
+            Err(e) => warn!("Failed to parse the user app tuning database: {e}"),
+        }
+    }
+
+    database.extend(serde_json::from_str::<Vec<AppTuning>>(SHIPPED_DATABASE).unwrap_or_default());
+
+    database
+}
+
+// Called when the scene application changes
+pub fn select_app(steam_app_id: Option<u32>, executable_name: Option<&str>) {
+    let Switch::Enabled(config) = SERVER_DATA_MANAGER
+        .read()
+        .settings()
+        .headset
+        .controllers
+        .clone()
+    else {
+        *ACTIVE_TUNING.lock() = None;
+        return;
+    };
+
+    let tuning = config
+        .per_game_tuning
+        .then(|| {
+            load_database().into_iter().find(|entry| {
+                (entry.steam_app_id.is_some() && entry.steam_app_id == steam_app_id)
+                    || entry
+                        .executable_name
+                        .as_ref()
+                        .zip(executable_name)
+                        .map_or(false, |(a, b)| a.eq_ignore_ascii_case(b))
+            })
+        })
+        .flatten();
+
+    if let Some(tuning) = &tuning {
+        info!("Applying app tuning for {}", tuning.name);
+    }
+
+    let frames = tuning
+        .as_ref()
+        .and_then(|t| t.steamvr_pipeline_frames)
+        .unwrap_or(config.steamvr_pipeline_frames);
+    *ACTIVE_TUNING.lock() = tuning;
+
+    if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+        stats.set_steamvr_pipeline_frames(frames);
+    }
+}
+
+pub fn steamvr_pipeline_frames(default: f32) -> f32 {
+    ACTIVE_TUNING
+        .lock()
+        .as_ref()
+        .and_then(|t| t.steamvr_pipeline_frames)
+        .unwrap_or(default)
+}
+
+pub fn haptics_intensity_multiplier() -> f32 {
+    ACTIVE_TUNING
+        .lock()
+        .as_ref()
+        .and_then(|t| t.haptics_intensity_multiplier)
+        .unwrap_or(1.0)
+}
+
+// Values inside the deadzone are zeroed and the rest of the range is rescaled
+pub fn map_thumbstick_value(path_id: u64, value: f32) -> f32 {
+    let Some(deadzone) = ACTIVE_TUNING
+        .lock()
+        .as_ref()
+        .and_then(|t| t.thumbstick_deadzone)
+    else {
+        return value;
+    };
+
+    if ![
+        *LEFT_THUMBSTICK_X_ID,
+        *LEFT_THUMBSTICK_Y_ID,
+        *RIGHT_THUMBSTICK_X_ID,
+        *RIGHT_THUMBSTICK_Y_ID,
+    ]
+    .contains(&path_id)
+        || deadzone >= 1.0
+    {
+        return value;
+    }
+
+    if value.abs() <= deadzone {
+        0.0
+    } else {
+        value.signum() * (value.abs() - deadzone) / (1.0 - deadzone)
+    }
+}
@@ -26,6 +26,7 @@ pub struct BitrateManager {
     last_update_instant: Instant,
     dynamic_max_bitrate: f32,
     previous_config: Option<BitrateConfig>,
+    previous_client_max_bitrate_mbps: Option<u64>,
     update_needed: bool,
 }
 
@@ -53,6 +54,7 @@ impl BitrateManager {
             last_update_instant: Instant::now(),
             dynamic_max_bitrate: f32::MAX,
             previous_config: None,
+            previous_client_max_bitrate_mbps: None,
             update_needed: true,
         }
     }
@@ -144,9 +146,36 @@ impl BitrateManager {
         }
     }
 
+    pub fn set_nominal_framerate(&mut self, framerate: f32) {
+        self.nominal_frame_interval = Duration::from_secs_f32(1. / framerate);
+        self.frame_interval_average =
+            SlidingWindowAverage::new(self.nominal_frame_interval, self.max_history_size);
+
+        self.update_needed = true;
+    }
+
+    // Unlike the decoder latency limiter, this reacts to frames actually dropped by the client
+    pub fn report_decoder_overload(&mut self, config: &BitrateMode) {
+        if let BitrateMode::Adaptive {
+            decoder_overload_limiter: Switch::Enabled(config),
+            ..
+        } = &config
+        {
+            self.dynamic_max_bitrate =
+                f32::min(self.bitrate_average.get_average(), self.dynamic_max_bitrate)
+                    * config.overload_multiplier;
+
+            self.update_needed = true;
+        }
+    }
+
+    // client_max_bitrate_mbps is the cap set in the dashboard for the connected client
     pub fn get_encoder_params(
         &mut self,
         config: &BitrateConfig,
+        client_max_bitrate_mbps: Option<u64>,
+        // Set by the latency budget, takes precedence over the encoder latency limiter setting
+        encoder_max_saturation: Option<f32>,
     ) -> (FfiDynamicEncoderParams, Option<NominalBitrateStats>) {
         let now = Instant::now();
 
@@ -155,8 +184,10 @@ impl BitrateManager {
             .as_ref()
             .map(|prev| config != prev)
             .unwrap_or(true)
+            || client_max_bitrate_mbps != self.previous_client_max_bitrate_mbps
         {
             self.previous_config = Some(config.clone());
+            self.previous_client_max_bitrate_mbps = client_max_bitrate_mbps;
             // Continue method. Always update bitrate in this case
         } else if !self.update_needed
             && (now < self.last_update_instant + UPDATE_INTERVAL
@@ -177,7 +208,7 @@ impl BitrateManager {
 
         let mut stats = NominalBitrateStats::default();
 
-        let bitrate_bps = match &config.mode {
+        let mut bitrate_bps = match &config.mode {
             BitrateMode::ConstantMbps(bitrate_mbps) => *bitrate_mbps as f32 * 1e6,
             BitrateMode::Adaptive {
                 saturation_multiplier,
@@ -203,14 +234,17 @@ impl BitrateManager {
                     stats.network_latency_limiter_bps = Some(max);
                 }
 
-                if let Switch::Enabled(config) = encoder_latency_limiter {
+                if let Some(max_saturation_multiplier) =
+                    encoder_max_saturation.or(encoder_latency_limiter
+                        .as_option()
+                        .map(|config| config.max_saturation_multiplier))
+                {
                     let saturation = self.encoder_latency_average.get_average().as_secs_f32()
                         / self.nominal_frame_interval.as_secs_f32();
-                    let max =
-                        initial_bitrate_average_bps * config.max_saturation_multiplier / saturation;
+                    let max = initial_bitrate_average_bps * max_saturation_multiplier / saturation;
                     stats.encoder_latency_limiter_bps = Some(max);
 
-                    if saturation > config.max_saturation_multiplier {
+                    if saturation > max_saturation_multiplier {
                         // Note: this assumes linear relationship between bitrate and encoder
                         // latency but this may not be the case
                         bitrate_bps = f32::min(bitrate_bps, max);
@@ -234,6 +268,14 @@ impl BitrateManager {
             }
         };
 
+        // Applies to the constant bitrate too, and takes precedence over the manual minimum
+        if let Some(max) = client_max_bitrate_mbps {
+            let max = max as f32 * 1e6;
+            bitrate_bps = f32::min(bitrate_bps, max);
+
+            stats.client_max_bps = Some(max);
+        }
+
         stats.requested_bps = bitrate_bps;
 
         let frame_interval = if config.adapt_to_framerate.enabled() {
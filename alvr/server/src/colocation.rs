@@ -0,0 +1,220 @@
+// Playspace alignment between streamers on the same LAN, for colocated multiplayer. Each user
+// places the left controller on the same physical marker and captures its pose. The first streamer
+// of a group to capture becomes the host: it keeps its playspace and announces the marker pose in
+// playspace coordinates (the shared anchor). The other streamers move their origin so that their
+// own capture of the marker lands on the same coordinates.
+
+use crate::{
+    connection::SHOULD_CONNECT_TO_CLIENTS, tracking::TrackingManager, SERVER_DATA_MANAGER,
+};
+use alvr_common::{
+    glam::Quat, info, once_cell::sync::Lazy, parking_lot::Mutex, settings_schema::Switch, warn,
+    Pose,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    net::{Ipv4Addr, UdpSocket},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const PROTOCOL_NAME: &str = "ALVR colocation";
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_PACKET_SIZE: usize = 1024;
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct SharedAnchor {
+    // Capture time in ms since the UNIX epoch. When two streamers capture before hearing from each
+    // other, the oldest anchor wins
+    timestamp_ms: u64,
+    host_id: u64,
+    pose: Pose, // marker pose in the shared playspace
+}
+
+impl SharedAnchor {
+    fn is_older_than(&self, other: &SharedAnchor) -> bool {
+        (self.timestamp_ms, self.host_id) < (other.timestamp_ms, other.host_id)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PeerPacket {
+    protocol: String,
+    group_name: String,
+    streamer_id: u64,
+    anchor: Option<SharedAnchor>,
+}
+
+struct ColocationState {
+    streamer_id: u64,
+    shared_anchor: Option<SharedAnchor>,
+    local_marker: Option<Pose>, // client's reference space
+    capture_requested: bool,
+}
+
+static STATE: Lazy<Mutex<ColocationState>> = Lazy::new(|| {
+    Mutex::new(ColocationState {
+        streamer_id: rand::random(),
+        shared_anchor: None,
+        local_marker: None,
+        capture_requested: false,
+    })
+});
+
+fn yaw_only(pose: Pose) -> Pose {
+    let mut orientation = pose.orientation;
+    orientation.x = 0.0;
+    orientation.z = 0.0;
+
+    Pose {
+        orientation: if orientation.length_squared() > 0.0 {
+            orientation.normalize()
+        } else {
+            Quat::IDENTITY
+        },
+        position: pose.position,
+    }
+}
+
+// The capture happens on the next tracking update
+pub fn request_capture() {
+    STATE.lock().capture_requested = true;
+}
+
+// Restores the marker captured in a previous session of the same client
+pub fn restore_marker(marker_pose: Option<Pose>) {
+    STATE.lock().local_marker = marker_pose;
+}
+
+// Called for every tracking update, before the motions are transformed. Returns the marker pose
+// if it has just been captured
+pub fn update_tracking(tracking_manager: &mut TrackingManager, enabled: bool) -> Option<Pose> {
+    let mut state = STATE.lock();
+
+    if !enabled {
+        state.shared_anchor = None;
+        state.local_marker = None;
+        state.capture_requested = false;
+        tracking_manager.set_colocation_origin(None);
+
+        return None;
+    }
+
+    let mut captured_marker = None;
+    if state.capture_requested {
+        state.capture_requested = false;
+
+        if let Some(marker_pose) = tracking_manager.last_left_hand_pose() {
+            let marker_pose = yaw_only(marker_pose);
+            state.local_marker = Some(marker_pose);
+            captured_marker = Some(marker_pose);
+
+            if state.shared_anchor.is_none() {
+                state.shared_anchor = Some(SharedAnchor {
+                    timestamp_ms: SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis() as u64,
+                    host_id: state.streamer_id,
+                    pose: tracking_manager.origin().inverse() * marker_pose,
+                });
+
+                info!("Colocation: marker captured, the other streamers will align to this one");
+            } else {
+                info!("Colocation: marker captured, aligned to the shared anchor");
+            }
+        } else {
+            warn!("Colocation: cannot capture the marker, the left controller is not tracked");
+        }
+    }
+
+    // For the host this is equal to the origin at the time of the capture
+    let origin = state
+        .local_marker
+        .zip(state.shared_anchor)
+        .map(|(marker_pose, anchor)| marker_pose * anchor.pose.inverse());
+    tracking_manager.set_colocation_origin(origin);
+
+    captured_marker
+}
+
+// Announces the shared anchor to the other streamers of the group and listens for theirs
+pub fn peer_loop() {
+    while SHOULD_CONNECT_TO_CLIENTS.value() {
+        let Switch::Enabled(config) = SERVER_DATA_MANAGER
+            .read()
+            .settings()
+            .headset
+            .colocation
+            .clone()
+        else {
+            thread::sleep(ANNOUNCE_INTERVAL);
+            continue;
+        };
+
+        let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, config.peer_port)) {
+            Ok(socket) => socket,
+            Err(e) => {
+                warn!("Colocation: failed to bind peer socket: {e}");
+                thread::sleep(ANNOUNCE_INTERVAL);
+                continue;
+            }
+        };
+        socket.set_broadcast(true).ok();
+        socket.set_read_timeout(Some(ANNOUNCE_INTERVAL)).ok();
+
+        let mut last_announce_instant = None::<Instant>;
+        let mut buffer = [0; MAX_PACKET_SIZE];
+        while SHOULD_CONNECT_TO_CLIENTS.value()
+            && matches!(
+                &SERVER_DATA_MANAGER.read().settings().headset.colocation,
+                Switch::Enabled(current) if *current == config
+            )
+        {
+            if last_announce_instant.map_or(true, |instant| instant.elapsed() >= ANNOUNCE_INTERVAL)
+            {
+                let packet = {
+                    let state = STATE.lock();
+                    PeerPacket {
+                        protocol: PROTOCOL_NAME.into(),
+                        group_name: config.group_name.clone(),
+                        streamer_id: state.streamer_id,
+                        anchor: state.shared_anchor,
+                    }
+                };
+                if let Ok(packet) = serde_json::to_vec(&packet) {
+                    socket
+                        .send_to(&packet, (Ipv4Addr::BROADCAST, config.peer_port))
+                        .ok();
+                }
+
+                last_announce_instant = Some(Instant::now());
+            }
+
+            let Ok(size) = socket.recv(&mut buffer) else {
+                continue;
+            };
+            let Ok(packet) = serde_json::from_slice::<PeerPacket>(&buffer[..size]) else {
+                continue;
+            };
+
+            let mut state = STATE.lock();
+            if packet.protocol != PROTOCOL_NAME
+                || packet.group_name != config.group_name
+                || packet.streamer_id == state.streamer_id
+            {
+                continue;
+            }
+
+            if let Some(peer_anchor) = packet.anchor {
+                if state
+                    .shared_anchor
+                    .map_or(true, |anchor| peer_anchor.is_older_than(&anchor))
+                {
+                    info!("Colocation: received the shared anchor from another streamer");
+                    state.shared_anchor = Some(peer_anchor);
+                }
+            }
+        }
+    }
+}
@@ -1,39 +1,57 @@
 use crate::{
+    app_tuning,
     bitrate::BitrateManager,
     buttons::BUTTON_PATH_FROM_ID,
+    colocation, controller_profiles,
+    controller_tracking::TrackingLossMonitor,
     create_recording_file,
+    desktop_pointer::DesktopPointer,
+    device_presence, encoder_benchmark,
     face_tracking::FaceTrackingSink,
-    haptics,
-    sockets::WelcomeSocket,
-    statistics::StatisticsManager,
+    floor_calibration,
+    haptics::{self, HapticsPcmEncoder},
+    journal, latency_planner, microphone_test, mirror, resource_usage, seated_mode, session_timer,
+    sockets::{self, WelcomeSocket},
+    statistics::{self, StatisticsManager},
+    statistics_sinks,
     tracking::{self, TrackingManager},
-    FfiButtonValue, FfiFov, FfiViewsConfig, VideoPacket, BITRATE_MANAGER, DECODER_CONFIG,
-    SERVER_DATA_MANAGER, STATISTICS_MANAGER, VIDEO_MIRROR_SENDER, VIDEO_RECORDING_FILE,
+    tracking_recording, FfiButtonValue, FfiFov, FfiGazeRoi, FfiViewsConfig, VideoPacket,
+    BITRATE_MANAGER, DECODER_CONFIG, FILESYSTEM_LAYOUT, SERVER_DATA_MANAGER, STATISTICS_MANAGER,
+    VIDEO_MIRROR_SENDER, VIDEO_RECORDING_FILE,
 };
 use alvr_audio::AudioDevice;
 use alvr_common::{
+    anyhow::{bail, Result},
     con_bail, debug, error,
     glam::{UVec2, Vec2},
     info,
     once_cell::sync::Lazy,
     parking_lot::Mutex,
     settings_schema::Switch,
-    warn, AnyhowToCon, ConResult, ConnectionError, RelaxedAtomic, ToCon, DEVICE_ID_TO_PATH,
-    HEAD_ID, LEFT_HAND_ID, RIGHT_HAND_ID,
+    warn, AnyhowToCon, ConResult, ConnectionError, Fov, RelaxedAtomic, ToCon, ALVR_VERSION,
+    DEVICE_ID_TO_PATH, HEAD_ID, LEFT_HAND_ID, RIGHT_HAND_ID,
 };
-use alvr_events::{ButtonEvent, EventType, HapticsEvent, TrackingEvent};
+use alvr_events::{ButtonEvent, EventType, HapticsEvent, StreamSummary, TrackingEvent};
 use alvr_packets::{
-    ButtonValue, ClientConnectionResult, ClientControlPacket, ClientListAction, ClientStatistics,
-    Haptics, ServerControlPacket, StreamConfigPacket, Tracking, VideoPacketHeader, AUDIO, HAPTICS,
-    STATISTICS, TRACKING, VIDEO,
+    simulated_network_conditions, ButtonValue, ClientConnectionResult, ClientControlPacket,
+    ClientListAction, ClientStatistics, DecoderInitializationConfig, HandshakeWarning,
+    HandshakeWarningKind, Haptics, JournalCategory, OverlayLayerHeader, ServerControlPacket,
+    ServerHandshakeInfo, StreamConfigPacket, Tracking, TrafficStream, VideoPacketHeader,
+    ViewsConfig, AUDIO, HAPTICS, MICROPHONE, OVERLAY_LAYERS, STATISTICS, TRACKING, VIDEO,
+};
+use alvr_session::{
+    experiments, BinauralDownmixLocation, BitrateMode, ClientCalibration, CodecType,
+    ColorBlindnessFilterConfig, ConnectionState, ExtraLatencyMode, FrameSize, KeyframeInterval,
+    MultichannelAudioConfig, OpenvrConfig, SocketBufferSize, SocketProtocol,
 };
-use alvr_session::{CodecType, ConnectionState, ControllersEmulationMode, FrameSize, OpenvrConfig};
 use alvr_sockets::{
-    PeerType, ProtoControlSocket, StreamSender, StreamSocketBuilder, KEEPALIVE_INTERVAL,
+    BackpressurePolicy, ControlChannelSender, PeerType, ProtoControlSocket, StreamChannelSender,
+    StreamSocketBuilder, KEEPALIVE_INTERVAL,
 };
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     io::Write,
+    iter,
     net::IpAddr,
     process::Command,
     ptr,
@@ -45,30 +63,245 @@ use std::{
     thread,
     time::{Duration, Instant},
 };
+use sysinfo::{System, SystemExt};
 
 const RETRY_CONNECT_MIN_INTERVAL: Duration = Duration::from_secs(1);
-const HANDSHAKE_ACTION_TIMEOUT: Duration = Duration::from_secs(2);
 const STREAMING_RECV_TIMEOUT: Duration = Duration::from_millis(500);
 
 const MAX_UNREAD_PACKETS: usize = 10; // Applies per stream
+const AUTOMATIC_KEYFRAME_INTERVAL: Duration = Duration::from_secs(2);
+const TRAFFIC_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_QUEUED_OVERLAY_IMAGES: usize = 8;
+const MAX_CAPTION_LENGTH: usize = 500;
+const RESTART_NOTIFICATION_TIMEOUT: Duration = Duration::from_millis(500);
+const PAIRING_TOKEN_LIFETIME: Duration = Duration::from_secs(5 * 60);
+// Wi-Fi throughput is usually around half of the link speed reported by the headset
+const USABLE_LINK_SPEED_FRACTION: f32 = 0.5;
 
 pub static SHOULD_CONNECT_TO_CLIENTS: Lazy<Arc<RelaxedAtomic>> =
     Lazy::new(|| Arc::new(RelaxedAtomic::new(false)));
 pub static IS_STREAMING: Lazy<Arc<RelaxedAtomic>> =
     Lazy::new(|| Arc::new(RelaxedAtomic::new(false)));
+// While paused, the connection is kept but video and game audio are not sent
+pub static IS_STREAM_PAUSED: Lazy<RelaxedAtomic> = Lazy::new(|| RelaxedAtomic::new(false));
+static IS_GAME_AUDIO_RUNNING: Lazy<Arc<RelaxedAtomic>> =
+    Lazy::new(|| Arc::new(RelaxedAtomic::new(false)));
+static CONTROL_SENDER: Lazy<Mutex<Option<ControlChannelSender<ServerControlPacket>>>> =
+    Lazy::new(|| Mutex::new(None));
 static VIDEO_CHANNEL_SENDER: Lazy<Mutex<Option<SyncSender<VideoPacket>>>> =
     Lazy::new(|| Mutex::new(None));
-static HAPTICS_SENDER: Lazy<Mutex<Option<StreamSender<Haptics>>>> = Lazy::new(|| Mutex::new(None));
+static HAPTICS_SENDER: Lazy<Mutex<Option<StreamChannelSender<Haptics>>>> =
+    Lazy::new(|| Mutex::new(None));
+static HAPTICS_PCM_ENCODER: Lazy<Mutex<Option<HapticsPcmEncoder>>> = Lazy::new(|| Mutex::new(None));
+static OVERLAY_LAYERS_CHANNEL_SENDER: Lazy<Mutex<Option<SyncSender<OverlayLayerImage>>>> =
+    Lazy::new(|| Mutex::new(None));
+// Filter applied by the streaming client, used to send only the changes
+static CLIENT_COLOR_BLINDNESS_FILTER: Lazy<Mutex<Option<ColorBlindnessFilterConfig>>> =
+    Lazy::new(|| Mutex::new(None));
+
+struct OverlayLayerImage {
+    header: OverlayLayerHeader,
+    resolution: UVec2,
+    // None hides the layer
+    rgba: Option<Vec<u8>>,
+}
 
 pub enum ClientDisconnectRequest {
-    Disconnect,
+    Disconnect(String), // reason
     ServerShutdown,
     ServerRestart,
 }
 
+static VIEWS_FOV: Lazy<Mutex<Option<[Fov; 2]>>> = Lazy::new(|| Mutex::new(None));
+// Last views config from the client, with the overscan applied, and the world scale used for it
+static VIEWS_CONFIG: Lazy<Mutex<Option<(ViewsConfig, f32)>>> = Lazy::new(|| Mutex::new(None));
+
+// One-time token shown by the dashboard as QR code, with its creation time. Clients presenting it
+// before it expires are trusted right away
+pub static PAIRING_TOKEN: Lazy<Mutex<Option<(u64, Instant)>>> = Lazy::new(|| Mutex::new(None));
+
 pub static DISCONNECT_CLIENT_NOTIFIER: Lazy<Mutex<Option<mpsc::Sender<ClientDisconnectRequest>>>> =
     Lazy::new(|| Mutex::new(None));
 
+// Can be requested by both the dashboard and the client
+pub fn set_stream_paused(paused: bool) {
+    if !IS_STREAMING.value() || IS_STREAM_PAUSED.value() == paused {
+        return;
+    }
+
+    IS_STREAM_PAUSED.set(paused);
+    if paused {
+        IS_GAME_AUDIO_RUNNING.set(false);
+    } else {
+        // The client decoder needs a new IDR after the gap in the video stream
+        unsafe { crate::RequestIDR() };
+    }
+
+    if let Some(sender) = &*CONTROL_SENDER.lock() {
+        let packet = if paused {
+            ServerControlPacket::StreamPaused
+        } else {
+            ServerControlPacket::StreamResumed
+        };
+        sender.send(packet).ok();
+    }
+
+    info!("Stream {}", if paused { "paused" } else { "resumed" });
+    journal::record(
+        JournalCategory::Stream,
+        if paused {
+            "Stream paused"
+        } else {
+            "Stream resumed"
+        },
+    );
+    alvr_events::send_event(EventType::StreamPaused(paused));
+}
+
+// The result is sent to the dashboard as an event
+pub fn request_wifi_scan() {
+    if let Some(sender) = &*CONTROL_SENDER.lock() {
+        sender.send(ServerControlPacket::RequestWifiScan).ok();
+    } else {
+        alvr_events::send_event(EventType::WifiScan(Err("No client is streaming".into())));
+    }
+}
+
+pub fn request_client_screenshot(label: String) {
+    if let Some(sender) = &*CONTROL_SENDER.lock() {
+        sender
+            .send(ServerControlPacket::CaptureScreenshot(label))
+            .ok();
+    }
+}
+
+// Only the encoder knows if intra refresh could be configured. Without it, the client must keep
+// requesting IDR frames after packet loss
+pub fn notify_intra_refresh(enabled: bool) {
+    if let Some(sender) = &*CONTROL_SENDER.lock() {
+        sender
+            .send(ServerControlPacket::IntraRefreshChanged(enabled))
+            .ok();
+    }
+}
+
+// The decoder of the client is reconfigured before the first frame of the new codec arrives
+pub fn notify_decoder_config(config: DecoderInitializationConfig) {
+    if let Some(sender) = &*CONTROL_SENDER.lock() {
+        sender
+            .send_urgent(ServerControlPacket::InitializeDecoder(config))
+            .ok();
+    }
+}
+
+// Called when the session changes. The filter is applied by the client when displaying the frames,
+// so it can be changed without restarting the stream
+pub fn update_color_blindness_filter() {
+    let filter = SERVER_DATA_MANAGER
+        .read()
+        .settings()
+        .video
+        .color_blindness_filter
+        .clone()
+        .into_option();
+
+    let mut client_filter = CLIENT_COLOR_BLINDNESS_FILTER.lock();
+    if *client_filter == filter {
+        return;
+    }
+
+    if let Some(sender) = &*CONTROL_SENDER.lock() {
+        sender
+            .send(ServerControlPacket::ColorBlindnessFilterChanged(
+                filter.clone(),
+            ))
+            .ok();
+        *client_filter = filter;
+    }
+}
+
+fn set_views_config(config: ViewsConfig) {
+    let world_scale = SERVER_DATA_MANAGER.read().settings().headset.world_scale;
+
+    unsafe {
+        crate::SetViewsConfig(FfiViewsConfig {
+            fov: [
+                FfiFov {
+                    left: config.fov[0].left,
+                    right: config.fov[0].right,
+                    up: config.fov[0].up,
+                    down: config.fov[0].down,
+                },
+                FfiFov {
+                    left: config.fov[1].left,
+                    right: config.fov[1].right,
+                    up: config.fov[1].up,
+                    down: config.fov[1].down,
+                },
+            ],
+            // Scaled like the tracked positions
+            ipd_m: config.ipd_m / world_scale,
+            orientations: config.orientations.map(crate::to_ffi_quat),
+        })
+    };
+
+    *VIEWS_CONFIG.lock() = Some((config, world_scale));
+}
+
+// Called when the session changes. Tracked positions are scaled at every update, while the IPD
+// needs to be sent to SteamVR again
+pub fn update_world_scale() {
+    let world_scale = SERVER_DATA_MANAGER.read().settings().headset.world_scale;
+
+    let views_config = VIEWS_CONFIG.lock().clone();
+    if let Some((config, applied_world_scale)) = views_config {
+        if applied_world_scale != world_scale {
+            set_views_config(config);
+        }
+    }
+}
+
+// Captions come from speech-to-text tools through the web API
+pub fn send_caption(text: String, duration: Option<Duration>) -> Result<()> {
+    let Switch::Enabled(config) = SERVER_DATA_MANAGER
+        .read()
+        .settings()
+        .headset
+        .captions
+        .clone()
+    else {
+        bail!("Captions are disabled in the settings");
+    };
+
+    let Some(sender) = &*CONTROL_SENDER.lock() else {
+        bail!("No client is streaming");
+    };
+
+    // Tools that stream partial transcriptions can send long texts, keep the most recent words
+    let text = if text.chars().count() > MAX_CAPTION_LENGTH {
+        let skip = text.chars().count() - MAX_CAPTION_LENGTH;
+        text.chars().skip(skip).collect()
+    } else {
+        text
+    };
+    let duration = duration.unwrap_or_else(|| Duration::from_secs_f32(config.default_duration_s));
+
+    sender.send(ServerControlPacket::Caption { text, duration })
+}
+
+fn update_client_calibration(hostname: &str, update: impl FnOnce(&mut ClientCalibration)) {
+    let mut data_manager_lock = SERVER_DATA_MANAGER.write();
+    if let Some(entry) = data_manager_lock.client_list().get(hostname) {
+        let mut calibration = entry.calibration.clone();
+        update(&mut calibration);
+
+        data_manager_lock.update_client_list(
+            hostname.to_owned(),
+            ClientListAction::SetCalibration(calibration),
+        );
+    }
+}
+
 fn align32(value: f32) -> u32 {
     ((value / 32.).floor() * 32.) as u32
 }
@@ -84,13 +317,8 @@ pub fn contruct_openvr_config() -> OpenvrConfig {
     let mut override_grip_threshold = false;
     let mut grip_threshold = 0.0;
     let controllers_enabled = if let Switch::Enabled(config) = settings.headset.controllers {
-        controllers_mode_idx = match config.emulation_mode {
-            ControllersEmulationMode::RiftSTouch => 1,
-            ControllersEmulationMode::ValveIndex => 3,
-            ControllersEmulationMode::ViveWand => 5,
-            ControllersEmulationMode::Quest2Touch => 7,
-            ControllersEmulationMode::ViveTracker => 9,
-        };
+        controllers_mode_idx =
+            controller_profiles::mode_idx(&controller_profiles::active_emulation_mode(&config));
         override_trigger_threshold =
             if let Switch::Enabled(value) = config.trigger_threshold_override {
                 trigger_threshold = value;
@@ -129,6 +357,17 @@ pub fn contruct_openvr_config() -> OpenvrConfig {
             false
         };
 
+    let mut gaze_roi_radius = 0.0;
+    let mut gaze_roi_quality_boost = 0.0;
+    let enable_gaze_roi = if let Switch::Enabled(config) = settings.video.gaze_roi {
+        gaze_roi_radius = config.radius;
+        gaze_roi_quality_boost = config.quality_boost;
+
+        cfg!(target_os = "linux")
+    } else {
+        false
+    };
+
     let mut brightness = 0.0;
     let mut contrast = 0.0;
     let mut saturation = 0.0;
@@ -158,6 +397,14 @@ pub fn contruct_openvr_config() -> OpenvrConfig {
         filler_data: settings.video.encoder_config.filler_data,
         entropy_coding: settings.video.encoder_config.entropy_coding as u32,
         use_10bit_encoder: settings.video.encoder_config.use_10bit,
+        slice_count: settings.video.encoder_config.slice_count,
+        periodic_intra_refresh_frames: settings
+            .video
+            .encoder_config
+            .intra_refresh
+            .as_option()
+            .map(|config| config.period_frames)
+            .unwrap_or(0),
         enable_vbaq: amf_controls.enable_vbaq,
         use_preproc: amf_controls.use_preproc,
         preproc_sigma: amf_controls.preproc_sigma,
@@ -183,6 +430,9 @@ pub fn contruct_openvr_config() -> OpenvrConfig {
         foveation_center_shift_y,
         foveation_edge_ratio_x,
         foveation_edge_ratio_y,
+        enable_gaze_roi,
+        gaze_roi_radius,
+        gaze_roi_quality_boost,
         enable_color_correction,
         brightness,
         contrast,
@@ -223,14 +473,22 @@ pub fn handshake_loop() {
         }
     };
 
+    // Clients already checked for a route through a virtual network adapter
+    let mut route_checked_client_ips = HashSet::new();
+
     while SHOULD_CONNECT_TO_CLIENTS.value() {
         let available_manual_client_ips = {
+            let expired_clients = session_timer::expired_clients();
+
             let mut manual_client_ips = HashMap::new();
             for (hostname, connection_info) in SERVER_DATA_MANAGER
                 .read()
                 .client_list()
                 .iter()
-                .filter(|(_, info)| info.connection_state == ConnectionState::Disconnected)
+                .filter(|(hostname, info)| {
+                    info.connection_state == ConnectionState::Disconnected
+                        && !expired_clients.contains(*hostname)
+                })
             {
                 for ip in &connection_info.manual_ips {
                     manual_client_ips.insert(*ip, hostname.clone());
@@ -252,18 +510,54 @@ pub fn handshake_loop() {
             .connection
             .client_discovery
             .clone();
-        if let Switch::Enabled(config) = discovery_config {
-            let (client_hostname, client_ip) = match welcome_socket.recv() {
-                Ok(pair) => pair,
-                Err(e) => {
-                    if let ConnectionError::Other(e) = e {
-                        warn!("UDP handshake listening error: {e:?}");
-                    }
 
-                    continue;
+        // Paired clients contact the server directly, so they are accepted even when discovery
+        // is disabled
+        let (client_hostname, client_ip, pairing_token) = match welcome_socket.recv() {
+            Ok(packet) => packet,
+            Err(e) => {
+                if let ConnectionError::Other(e) = e {
+                    warn!("UDP handshake listening error: {e:?}");
                 }
-            };
 
+                continue;
+            }
+        };
+
+        // VPNs and virtual adapters can capture the traffic to the local network, then the client
+        // is found but the handshake never reaches it
+        if route_checked_client_ips.insert(client_ip) {
+            if let Some(adapter) = sockets::virtual_adapter_to(client_ip) {
+                let message = format!(
+                    "{client_hostname} ({client_ip}) is reached through the virtual network adapter \"{adapter}\". If the client is stuck searching for the streamer, disable the VPN or virtual adapter, or exclude the local network from it"
+                );
+                warn!("{message}");
+                journal::record(JournalCategory::Connection, message);
+            }
+        }
+
+        let paired = pairing_token.is_some() && {
+            let mut token_lock = PAIRING_TOKEN.lock();
+            match *token_lock {
+                // The token is sent in the discovery broadcast, so it must not stay valid
+                Some((_, created)) if created.elapsed() > PAIRING_TOKEN_LIFETIME => {
+                    *token_lock = None;
+                    false
+                }
+                Some((token, _)) if Some(token) == pairing_token => {
+                    *token_lock = None;
+                    true
+                }
+                _ => false,
+            }
+        };
+
+        let auto_trust = matches!(
+            &discovery_config,
+            Switch::Enabled(config) if config.auto_trust_clients
+        );
+
+        if paired || matches!(discovery_config, Switch::Enabled(_)) {
             let trusted = {
                 let mut data_manager = SERVER_DATA_MANAGER.write();
 
@@ -275,7 +569,29 @@ pub fn handshake_loop() {
                     },
                 );
 
-                if config.auto_trust_clients {
+                if paired {
+                    info!("Client {client_hostname} paired with QR code");
+
+                    // The manual IP lets the server reach the client even if broadcasts are lost
+                    let mut manual_ips = data_manager
+                        .client_list()
+                        .get(&client_hostname)
+                        .unwrap()
+                        .manual_ips
+                        .iter()
+                        .copied()
+                        .collect::<Vec<_>>();
+                    if !manual_ips.contains(&client_ip) {
+                        manual_ips.push(client_ip);
+
+                        data_manager.update_client_list(
+                            client_hostname.clone(),
+                            ClientListAction::SetManualIps(manual_ips),
+                        );
+                    }
+                }
+
+                if paired || auto_trust {
                     data_manager
                         .update_client_list(client_hostname.clone(), ClientListAction::Trust);
                 }
@@ -289,6 +605,7 @@ pub fn handshake_loop() {
 
             // do not attempt connection if the client is already connected
             if trusted
+                && !session_timer::is_expired(&client_hostname)
                 && SERVER_DATA_MANAGER
                     .read()
                     .client_list()
@@ -301,6 +618,10 @@ pub fn handshake_loop() {
                     try_connect([(client_ip, client_hostname.clone())].into_iter().collect())
                 {
                     error!("Handshake error for {client_hostname}: {e}");
+                    journal::record(
+                        JournalCategory::Error,
+                        format!("Handshake error for {client_hostname}: {e}"),
+                    );
                 }
             }
         }
@@ -315,6 +636,31 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
         PeerType::AnyClient(client_ips.keys().cloned().collect()),
     )?;
 
+    let handshake_timeout = Duration::from_millis(
+        SERVER_DATA_MANAGER
+            .read()
+            .settings()
+            .connection
+            .handshake_timeout_ms,
+    );
+
+    let (gpu_names, codec) = {
+        let data_manager_lock = SERVER_DATA_MANAGER.read();
+        (
+            data_manager_lock.get_gpu_names(),
+            data_manager_lock.settings().video.preferred_codec,
+        )
+    };
+    proto_socket
+        .send(&ServerHandshakeInfo {
+            hostname: System::new().host_name().unwrap_or_default(),
+            version: ALVR_VERSION.to_string(),
+            protocol_id: alvr_common::protocol_id(),
+            gpu_names,
+            codec,
+        })
+        .to_con()?;
+
     let (disconnect_sender, disconnect_receiver) = mpsc::channel();
     *DISCONNECT_CLIENT_NOTIFIER.lock() = Some(disconnect_sender);
 
@@ -362,18 +708,45 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
         ClientListAction::UpdateCurrentIp(Some(client_ip)),
     );
 
+    // Empty if the client does not report its decoders
+    let decoder_codecs;
     let maybe_streaming_caps = if let ClientConnectionResult::ConnectionAccepted {
         client_protocol_id,
         display_name,
         streaming_capabilities,
+        hardware_info,
         ..
-    } = proto_socket.recv(HANDSHAKE_ACTION_TIMEOUT)?
+    } = proto_socket.recv(handshake_timeout)?
     {
         SERVER_DATA_MANAGER.write().update_client_list(
             client_hostname.clone(),
             ClientListAction::SetDisplayName(display_name),
         );
 
+        // Logged so that it ends up in the session log attached to bug reports
+        info!(
+            "Client hardware: SoC {}, {}, runtime {}, {} MB RAM available, decoders: {}",
+            hardware_info.soc_model,
+            hardware_info.os_version,
+            hardware_info.runtime_name,
+            hardware_info.available_ram_mb,
+            hardware_info
+                .decoders
+                .iter()
+                .map(|decoder| decoder.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        decoder_codecs = hardware_info
+            .decoders
+            .iter()
+            .flat_map(|decoder| decoder.codecs.iter().copied())
+            .collect::<Vec<_>>();
+        SERVER_DATA_MANAGER.write().update_client_list(
+            client_hostname.clone(),
+            ClientListAction::SetHardwareInfo(hardware_info),
+        );
+
         if client_protocol_id != alvr_common::protocol_id() {
             warn!(
                 "Trusted client is incompatible! Expected protocol ID: {}, found: {}",
@@ -396,8 +769,94 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
         con_bail!("Only streaming clients are supported for now");
     };
 
+    // The benchmark uses the encoder
+    if encoder_benchmark::is_running() {
+        con_bail!("Cannot start streaming while the encoder benchmark is running");
+    }
+
     let settings = SERVER_DATA_MANAGER.read().settings().clone();
 
+    let mut handshake_warnings = vec![];
+
+    if let (Switch::Enabled(warning_threshold_ms), Some(round_trip_time)) = (
+        &settings.connection.high_latency_warning_ms,
+        proto_socket.connect_round_trip_time(),
+    ) {
+        if round_trip_time > Duration::from_millis(*warning_threshold_ms) {
+            handshake_warnings.push(HandshakeWarning {
+                kind: HandshakeWarningKind::HighLatencyLink,
+                message: format!(
+                    "High latency link detected ({} ms round trip). Consider selecting the \"Constrained\" network profile preset in the Settings tab",
+                    round_trip_time.as_millis()
+                ),
+            });
+        }
+    }
+
+    if let Some(wifi_link) = &streaming_caps.wifi_link {
+        if (2400..2500).contains(&wifi_link.frequency_mhz) {
+            handshake_warnings.push(HandshakeWarning {
+                kind: HandshakeWarningKind::Wifi24Ghz,
+                message: format!(
+                    "The headset is connected to a 2.4 GHz Wi-Fi network ({} MHz). Use a 5 GHz or 6 GHz network to avoid stutters",
+                    wifi_link.frequency_mhz
+                ),
+            });
+        }
+
+        let mode_max_bitrate_mbps = match &settings.video.bitrate.mode {
+            BitrateMode::ConstantMbps(bitrate_mbps) => Some(*bitrate_mbps),
+            BitrateMode::Adaptive {
+                max_bitrate_mbps, ..
+            } => max_bitrate_mbps.as_option().copied(),
+        };
+        let client_max_bitrate_mbps = SERVER_DATA_MANAGER
+            .read()
+            .client_list()
+            .get(&client_hostname)
+            .and_then(|entry| entry.max_bitrate_mbps);
+        if let Some(max_bitrate_mbps) = [mode_max_bitrate_mbps, client_max_bitrate_mbps]
+            .into_iter()
+            .flatten()
+            .min()
+        {
+            if max_bitrate_mbps as f32
+                > wifi_link.link_speed_mbps as f32 * USABLE_LINK_SPEED_FRACTION
+            {
+                handshake_warnings.push(HandshakeWarning {
+                    kind: HandshakeWarningKind::BitrateAboveLinkSpeed,
+                    message: format!(
+                        "The video bitrate ({max_bitrate_mbps} Mbps) is too high for the Wi-Fi link speed of the headset ({} Mbps). Lower the bitrate or move the headset closer to the access point",
+                        wifi_link.link_speed_mbps
+                    ),
+                });
+            }
+        }
+    }
+
+    // The preferred codec is kept, the decoder list reported by some headsets is incomplete
+    if !decoder_codecs.is_empty() && !decoder_codecs.contains(&settings.video.preferred_codec) {
+        handshake_warnings.push(HandshakeWarning {
+            kind: HandshakeWarningKind::UnsupportedCodec,
+            message: format!(
+                "The headset reports no {:?} decoder. If the stream does not start, select another codec in the Settings tab",
+                settings.video.preferred_codec
+            ),
+        });
+    }
+
+    // Logged warnings are also shown in the dashboard notification bar
+    for warning in &handshake_warnings {
+        warn!("{}", warning.message);
+    }
+
+    let calibration = SERVER_DATA_MANAGER
+        .read()
+        .client_list()
+        .get(&client_hostname)
+        .map(|entry| entry.calibration.clone())
+        .unwrap_or_default();
+
     fn get_view_res(config: FrameSize, default_res: UVec2) -> UVec2 {
         let res = match config {
             FrameSize::Scale(scale) => default_res.as_vec2() * scale,
@@ -416,21 +875,94 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
         UVec2::new(align32(res.x), align32(res.y))
     }
 
-    let stream_view_resolution = get_view_res(
-        settings.video.transcoding_view_resolution,
-        streaming_caps.default_view_resolution,
-    );
+    // With overscan the views cover a larger area, the resolution grows to keep the pixel density
+    let reprojection_overscan = settings
+        .video
+        .reprojection_overscan
+        .as_option()
+        .map(|percentage| *percentage / 100.0);
+    let default_view_resolution = (streaming_caps.default_view_resolution.as_vec2()
+        * (1.0 + reprojection_overscan.unwrap_or(0.0)))
+    .as_uvec2();
+
+    let recommended_view_resolutions = streaming_caps
+        .recommended_view_resolutions
+        .as_ref()
+        .filter(|_| settings.video.use_headset_recommended_resolution)
+        .map(|resolutions| {
+            let scale_res = |res: UVec2| {
+                let res = res.as_vec2() * (1.0 + reprojection_overscan.unwrap_or(0.0));
+                UVec2::new(align32(res.x), align32(res.y))
+            };
 
-    let target_view_resolution = get_view_res(
-        settings.video.emulated_headset_view_resolution,
-        streaming_caps.default_view_resolution,
-    );
+            (
+                scale_res(resolutions.transcoding),
+                scale_res(resolutions.emulated_headset),
+            )
+        });
+    if let Some((transcoding, emulated_headset)) = recommended_view_resolutions {
+        info!(
+            "Using the headset recommended resolutions: {}x{} encoded, {}x{} rendered",
+            transcoding.x, transcoding.y, emulated_headset.x, emulated_headset.y
+        );
+    }
+
+    let mut stream_view_resolution = if let Some((transcoding, _)) = recommended_view_resolutions {
+        transcoding
+    } else {
+        get_view_res(
+            settings.video.transcoding_view_resolution,
+            default_view_resolution,
+        )
+    };
+
+    // The two views are encoded side by side. A frame larger than what the decoder supports would
+    // be silently rejected by the client
+    if let Some((_, max_resolution)) = streaming_caps
+        .max_decoder_resolutions
+        .iter()
+        .find(|(codec, _)| *codec == settings.video.preferred_codec)
+    {
+        let frame_resolution = UVec2::new(stream_view_resolution.x * 2, stream_view_resolution.y);
+        if frame_resolution.x > max_resolution.x || frame_resolution.y > max_resolution.y {
+            let scale = f32::min(
+                max_resolution.x as f32 / frame_resolution.x as f32,
+                max_resolution.y as f32 / frame_resolution.y as f32,
+            );
+            stream_view_resolution = UVec2::new(
+                align32(stream_view_resolution.x as f32 * scale),
+                align32(stream_view_resolution.y as f32 * scale),
+            );
+
+            warn!(
+                "Encoded resolution {}x{} is not supported by the headset decoder (max {}x{}). Using {}x{} per eye",
+                frame_resolution.x,
+                frame_resolution.y,
+                max_resolution.x,
+                max_resolution.y,
+                stream_view_resolution.x,
+                stream_view_resolution.y,
+            );
+        }
+    }
 
+    let target_view_resolution = if let Some((_, emulated_headset)) = recommended_view_resolutions {
+        emulated_headset
+    } else {
+        get_view_res(
+            settings.video.emulated_headset_view_resolution,
+            default_view_resolution,
+        )
+    };
+
+    let preferred_fps = calibration
+        .preferred_refresh_rate
+        .unwrap_or(settings.video.preferred_fps);
     let fps = {
         let mut best_match = 0_f32;
         let mut min_diff = f32::MAX;
         for rr in &streaming_caps.supported_refresh_rates {
-            let diff = (*rr - settings.video.preferred_fps).abs();
+            let diff = (*rr - preferred_fps).abs();
             if diff < min_diff {
                 best_match = *rr;
                 min_diff = diff;
@@ -441,12 +973,12 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
 
     if !streaming_caps
         .supported_refresh_rates
-        .contains(&settings.video.preferred_fps)
+        .contains(&preferred_fps)
     {
         warn!("Chosen refresh rate not supported. Using {fps}Hz");
     }
 
-    let game_audio_sample_rate =
+    let (game_audio_sample_rate, game_audio_channels) =
         if let Switch::Enabled(game_audio_config) = &settings.audio.game_audio {
             let game_audio_device = AudioDevice::new_output(
                 Some(settings.audio.linux_backend),
@@ -468,11 +1000,63 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                 }
             }
 
-            game_audio_device.input_sample_rate().to_con()?
+            // Surround audio is sent as is only if the client does the binaural downmix
+            let device_channels = game_audio_device.input_channels_count().to_con()?;
+            let channels = if device_channels > 2
+                && streaming_caps.supports_binaural_downmix
+                && matches!(
+                    game_audio_config.multichannel.as_option(),
+                    Some(MultichannelAudioConfig {
+                        binaural_downmix_location: BinauralDownmixLocation::Client
+                    })
+                ) {
+                device_channels
+            } else {
+                2
+            };
+
+            (game_audio_device.input_sample_rate().to_con()?, channels)
         } else {
-            0
+            (0, 2)
         };
 
+    let latency_plan = latency_planner::current_plan(settings.video.latency_budget.as_option());
+
+    // Options the client does not support are left to the client runtime
+    let present_config = &settings.video.client_present;
+    let extra_latency_mode = if streaming_caps.supports_extra_latency_mode {
+        match present_config.extra_latency_mode {
+            ExtraLatencyMode::Default => None,
+            ExtraLatencyMode::Enabled => Some(true),
+            ExtraLatencyMode::Disabled => Some(false),
+        }
+    } else {
+        None
+    };
+    let front_buffer_rendering =
+        present_config.front_buffer_rendering && streaming_caps.supports_front_buffer_rendering;
+
+    // Otherwise the overlays stay composited into the video stream
+    let overlay_layers = settings
+        .video
+        .overlay_layers
+        .as_option()
+        .filter(|_| streaming_caps.supports_overlay_layers)
+        .cloned();
+
+    // Probing needs direct access to the UDP socket
+    let packet_size_probe = settings.connection.jumbo_frames.as_option().is_some()
+        && matches!(settings.connection.stream_protocol, SocketProtocol::Udp)
+        && settings.connection.network_simulation.as_option().is_none();
+
+    let enabled_experiments =
+        experiments::negotiate(&settings.experiments, &streaming_caps.supported_experiments);
+    experiments::set_enabled(&enabled_experiments);
+    if !enabled_experiments.is_empty() {
+        // Logged so that it ends up in the session log attached to bug reports
+        info!("Enabled experiments: {}", enabled_experiments.join(", "));
+    }
+
     let client_config = StreamConfigPacket {
         session: {
             let session = SERVER_DATA_MANAGER.read().session().clone();
@@ -482,6 +1066,16 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
             "view_resolution": stream_view_resolution,
             "refresh_rate_hint": fps,
             "game_audio_sample_rate": game_audio_sample_rate,
+            "game_audio_channels": game_audio_channels,
+            "slice_count": settings.video.encoder_config.slice_count,
+            "server_hostname": System::new().host_name().unwrap_or_default(),
+            "max_buffering_frames": latency_plan.as_ref().map(|plan| plan.client_max_buffering_frames),
+            "extra_latency_mode": extra_latency_mode,
+            "front_buffer_rendering": front_buffer_rendering,
+            "experiments": enabled_experiments,
+            "packet_size_probe": packet_size_probe,
+            "overlay_layers": overlay_layers.is_some(),
+            "handshake_warnings": handshake_warnings,
         })
         .to_string(),
     };
@@ -497,62 +1091,159 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
     new_openvr_config.target_eye_resolution_height = target_view_resolution.y;
     new_openvr_config.refresh_rate = fps as _;
 
-    if SERVER_DATA_MANAGER.read().session().openvr_config != new_openvr_config {
-        SERVER_DATA_MANAGER.write().session_mut().openvr_config = new_openvr_config;
+    // Clients that cannot decode foveated frames fall back to the gaze region of interest
+    if !streaming_caps.supports_foveated_encoding {
+        new_openvr_config.enable_foveated_rendering = false;
+    }
+    if new_openvr_config.enable_foveated_rendering {
+        new_openvr_config.enable_gaze_roi = false;
+    }
+    let gaze_roi_enabled = new_openvr_config.enable_gaze_roi;
+
+    let old_openvr_config = SERVER_DATA_MANAGER.read().session().openvr_config.clone();
+    if old_openvr_config != new_openvr_config {
+        // Controller profiles of the same device class are switched without restarting SteamVR
+        let profile_only_change = OpenvrConfig {
+            controllers_mode_idx: new_openvr_config.controllers_mode_idx,
+            ..old_openvr_config.clone()
+        } == new_openvr_config
+            && controller_profiles::is_same_device_class(
+                old_openvr_config.controllers_mode_idx,
+                new_openvr_config.controllers_mode_idx,
+            );
+
+        SERVER_DATA_MANAGER.write().session_mut().openvr_config = new_openvr_config.clone();
 
-        control_sender.send(&ServerControlPacket::Restarting).ok();
+        if profile_only_change {
+            unsafe { crate::SetControllerProfile(new_openvr_config.controllers_mode_idx) };
+        } else {
+            control_sender.send(&ServerControlPacket::Restarting).ok();
 
-        crate::notify_restart_driver();
+            crate::notify_restart_driver();
+        }
     }
 
     control_sender
         .send(&ServerControlPacket::StartStream)
         .to_con()?;
 
-    let signal = control_receiver.recv(HANDSHAKE_ACTION_TIMEOUT)?;
+    let signal = control_receiver.recv(handshake_timeout)?;
     if !matches!(signal, ClientControlPacket::StreamReady) {
         con_bail!("Got unexpected packet waiting for stream ack");
     }
 
-    *STATISTICS_MANAGER.lock() = Some(StatisticsManager::new(
+    let mut stats = StatisticsManager::new(
         settings.connection.statistics_history_size,
         Duration::from_secs_f32(1.0 / fps),
         if let Switch::Enabled(config) = &settings.headset.controllers {
-            config.steamvr_pipeline_frames
+            app_tuning::steamvr_pipeline_frames(config.steamvr_pipeline_frames)
         } else {
             0.0
         },
-    ));
+    );
+    for sink in statistics_sinks::create_sinks(&settings.logging.statistics_sinks, &client_hostname)
+    {
+        stats.register_sink(sink);
+    }
+    *STATISTICS_MANAGER.lock() = Some(stats);
 
     *BITRATE_MANAGER.lock() = BitrateManager::new(settings.video.bitrate.history_size, fps);
 
     let mut stream_socket = StreamSocketBuilder::connect_to_client(
-        HANDSHAKE_ACTION_TIMEOUT,
+        handshake_timeout,
         client_ip,
         settings.connection.stream_port,
         settings.connection.stream_protocol,
         settings.connection.server_send_buffer_bytes,
         settings.connection.server_recv_buffer_bytes,
         settings.connection.packet_size as _,
+        settings
+            .connection
+            .network_simulation
+            .as_option()
+            .map(simulated_network_conditions)
+            .unwrap_or_default(),
     )?;
 
+    if matches!(
+        settings.connection.server_recv_buffer_bytes,
+        SocketBufferSize::Autotune
+    ) {
+        stream_socket.enable_recv_buffer_autotune();
+    }
+
+    if let Some(config) = settings
+        .connection
+        .jumbo_frames
+        .as_option()
+        .filter(|_| packet_size_probe)
+    {
+        // Largest first, halving down to the standard packet size
+        let sizes = iter::successors(Some(config.max_packet_size), |size| Some(size / 2))
+            .take_while(|size| *size > settings.connection.packet_size as usize)
+            .collect::<Vec<_>>();
+        if let Err(e) = stream_socket.send_packet_size_probes(&sizes) {
+            warn!("Failed to send packet size probes: {e}");
+        }
+
+        // The client replies even if no probe was received
+        match control_receiver.recv(handshake_timeout)? {
+            ClientControlPacket::PacketSizeProbeReport(Some(size)) => {
+                info!("Jumbo frames enabled, packet size: {size}B");
+                stream_socket.set_max_packet_size(size as usize);
+            }
+            ClientControlPacket::PacketSizeProbeReport(None) => {
+                warn!("The network path does not support jumbo frames, using the standard packet size");
+            }
+            _ => con_bail!("Got unexpected packet waiting for the packet size probe report"),
+        }
+    }
+
     let mut video_sender = stream_socket.request_stream(VIDEO);
     let game_audio_sender = stream_socket.request_stream(AUDIO);
-    let microphone_receiver = stream_socket.subscribe_to_stream(AUDIO, MAX_UNREAD_PACKETS);
-    let mut tracking_receiver =
-        stream_socket.subscribe_to_stream::<Tracking>(TRACKING, MAX_UNREAD_PACKETS);
-    let haptics_sender = stream_socket.request_stream(HAPTICS);
-    let mut statics_receiver =
-        stream_socket.subscribe_to_stream::<ClientStatistics>(STATISTICS, MAX_UNREAD_PACKETS);
+    let microphone_receiver = stream_socket.subscribe_to_stream(
+        MICROPHONE,
+        settings
+            .audio
+            .microphone
+            .as_option()
+            .map(|config| config.max_queued_packets)
+            .unwrap_or(MAX_UNREAD_PACKETS),
+        BackpressurePolicy::DropOldest,
+    );
+    // Only the latest tracking sample is useful, older ones are discarded if they pile up
+    let mut tracking_receiver = stream_socket.subscribe_to_stream::<Tracking>(
+        TRACKING,
+        MAX_UNREAD_PACKETS,
+        BackpressurePolicy::Coalesce,
+    );
+    let mut haptics_sender = stream_socket.request_stream(HAPTICS);
+    haptics_sender.set_redundancy(
+        settings
+            .connection
+            .input_packet_redundancy
+            .as_option()
+            .map(|config| Duration::from_millis(config.max_duplicate_delay_ms)),
+    );
+    let mut statics_receiver = stream_socket.subscribe_to_stream::<ClientStatistics>(
+        STATISTICS,
+        MAX_UNREAD_PACKETS,
+        BackpressurePolicy::DropNewest,
+    );
+    let mut overlay_layers_sender = stream_socket.request_stream(OVERLAY_LAYERS);
 
     // Note: from here on, the function MUST be infallible. Failure to respect this might leave
     // lingering objects that prevent reconnection.
     IS_STREAMING.set(true);
+    IS_STREAM_PAUSED.set(false);
 
     let (video_channel_sender, video_channel_receiver) =
         std::sync::mpsc::sync_channel(settings.connection.max_queued_server_video_frames);
     *VIDEO_CHANNEL_SENDER.lock() = Some(video_channel_sender);
-    *HAPTICS_SENDER.lock() = Some(haptics_sender);
+    *HAPTICS_SENDER.lock() = Some(haptics_sender.into_channel());
+    *HAPTICS_PCM_ENCODER.lock() = streaming_caps
+        .pcm_haptics_sample_rate
+        .map(HapticsPcmEncoder::new);
 
     let video_send_thread = thread::spawn(move || {
         while IS_STREAMING.value() {
@@ -563,6 +1254,23 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                     Err(RecvTimeoutError::Disconnected) => return,
                 };
 
+            let pacing_fraction = if let Switch::Enabled(config) = &SERVER_DATA_MANAGER
+                .read()
+                .settings()
+                .connection
+                .video_pacing
+            {
+                Some(config.frame_interval_percentage / 100.0)
+            } else {
+                None
+            };
+            video_sender.set_pacing(pacing_fraction.and_then(|fraction| {
+                STATISTICS_MANAGER
+                    .lock()
+                    .as_ref()
+                    .map(|stats| stats.nominal_server_frame_interval().mul_f32(fraction))
+            }));
+
             let mut buffer = video_sender.get_buffer(&header).unwrap();
             // todo: make encoder write to socket buffers directly to avoid copy
             buffer
@@ -572,9 +1280,52 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
         }
     });
 
+    if overlay_layers.is_some() {
+        let (overlay_channel_sender, overlay_channel_receiver) =
+            mpsc::sync_channel(MAX_QUEUED_OVERLAY_IMAGES);
+        *OVERLAY_LAYERS_CHANNEL_SENDER.lock() = Some(overlay_channel_sender);
+
+        thread::spawn(move || {
+            while IS_STREAMING.value() {
+                let OverlayLayerImage {
+                    header,
+                    resolution,
+                    rgba,
+                } = match overlay_channel_receiver.recv_timeout(STREAMING_RECV_TIMEOUT) {
+                    Ok(image) => image,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                };
+
+                let payload = if let Some(rgba) = rgba {
+                    match encode_png(resolution, &rgba) {
+                        Ok(png) => png,
+                        Err(e) => {
+                            warn!("Failed to encode overlay layer: {e}");
+                            continue;
+                        }
+                    }
+                } else {
+                    vec![]
+                };
+
+                let mut buffer = overlay_layers_sender.get_buffer(&header).unwrap();
+                buffer
+                    .get_range_mut(0, payload.len())
+                    .copy_from_slice(&payload);
+                overlay_layers_sender.send(buffer).ok();
+            }
+        });
+    }
+
     let game_audio_thread = if let Switch::Enabled(config) = settings.audio.game_audio {
         thread::spawn(move || {
             while IS_STREAMING.value() {
+                if IS_STREAM_PAUSED.value() {
+                    thread::sleep(STREAMING_RECV_TIMEOUT);
+                    continue;
+                }
+
                 let device = match AudioDevice::new_output(
                     Some(settings.audio.linux_backend),
                     config.device.as_ref(),
@@ -602,11 +1353,14 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                     continue;
                 };
 
+                // Stopped when the stream is paused or ends
+                IS_GAME_AUDIO_RUNNING.set(true);
                 if let Err(e) = alvr_audio::record_audio_blocking(
-                    Arc::clone(&IS_STREAMING),
+                    Arc::clone(&IS_GAME_AUDIO_RUNNING),
                     game_audio_sender.clone(),
                     &device,
-                    2,
+                    game_audio_channels,
+                    config.multichannel.as_option().is_some(),
                     config.mute_when_streaming,
                 ) {
                     error!("Audio record error: {e:?}");
@@ -661,16 +1415,33 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                 streaming_caps.microphone_sample_rate,
                 config.buffering,
                 microphone_receiver,
+                |_| (),
+                |samples| {
+                    microphone_test::report_samples(samples, streaming_caps.microphone_sample_rate)
+                },
             ));
         })
     } else {
         thread::spawn(|| ())
     };
 
-    let tracking_manager = Arc::new(Mutex::new(TrackingManager::new()));
+    let mut tracking_manager = TrackingManager::new();
+    colocation::restore_marker(calibration.playspace_alignment);
+    floor_calibration::restore_offset(calibration.floor_height_offset);
+
+    let tracking_manager = Arc::new(Mutex::new(tracking_manager));
+
+    let desktop_pointer = settings
+        .headset
+        .controllers
+        .as_option()
+        .and_then(|config| config.desktop_pointer.as_option())
+        .map(|config| Arc::new(Mutex::new(DesktopPointer::new(config.clone()))));
 
     let tracking_receive_thread = thread::spawn({
         let tracking_manager = Arc::clone(&tracking_manager);
+        let desktop_pointer = desktop_pointer.clone();
+        let client_hostname = client_hostname.clone();
         move || {
             let mut face_tracking_sink =
                 settings
@@ -681,10 +1452,11 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                         FaceTrackingSink::new(config.sink, settings.connection.osc_local_port).ok()
                     });
 
-            let mut track_controllers = 0u32;
-            if let Switch::Enabled(config) = settings.headset.controllers {
-                track_controllers = config.tracked.into();
-            }
+            let track_controllers = matches!(
+                settings.headset.controllers,
+                Switch::Enabled(ref config) if config.tracked
+            );
+            let mut tracking_loss_monitor = TrackingLossMonitor::new();
 
             while IS_STREAMING.value() {
                 let data = match tracking_receiver.recv(STREAMING_RECV_TIMEOUT) {
@@ -696,27 +1468,62 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                     return;
                 };
 
+                tracking_recording::record(&tracking);
+
+                if let Some(pointer) = &desktop_pointer {
+                    let mut pointer_lock = pointer.lock();
+                    if let Some((_, motion)) = tracking
+                        .device_motions
+                        .iter()
+                        .find(|(id, _)| pointer_lock.is_pointer_hand(*id))
+                    {
+                        pointer_lock.update_orientation(motion.pose.orientation);
+                    }
+                }
+
                 let mut tracking_manager_lock = tracking_manager.lock();
 
                 let motions;
                 let left_hand_skeleton;
                 let right_hand_skeleton;
+                let captured_marker;
+                let floor_height_offset;
+                let mut controller_tracking_gaps = [None; 2];
                 {
                     let data_manager_lock = SERVER_DATA_MANAGER.read();
                     let config = &data_manager_lock.settings().headset;
-                    motions = tracking_manager_lock.transform_motions(
-                        config,
-                        &tracking.device_motions,
-                        [
-                            tracking.hand_skeletons[0].is_some(),
-                            tracking.hand_skeletons[1].is_some(),
-                        ],
+                    captured_marker = colocation::update_tracking(
+                        &mut tracking_manager_lock,
+                        matches!(config.colocation, Switch::Enabled(_)),
+                    );
+                    floor_height_offset =
+                        floor_calibration::update_tracking(&mut tracking_manager_lock);
+                    tracking_manager_lock.set_seated_height_offset(
+                        seated_mode::is_active().then_some(config.seated_mode_height_offset),
                     );
+                    (motions, [left_hand_skeleton, right_hand_skeleton]) =
+                        tracking_manager_lock.transform_tracking(config, &tracking);
+
+                    if let Switch::Enabled(controllers_config) = &config.controllers {
+                        if controllers_config.tracked {
+                            controller_tracking_gaps = tracking_loss_monitor.update(
+                                &controllers_config.tracking_loss,
+                                [*LEFT_HAND_ID, *RIGHT_HAND_ID]
+                                    .map(|hand_id| motions.iter().any(|(id, _)| *id == hand_id)),
+                            );
+                        }
+                    }
+                }
 
-                    left_hand_skeleton = tracking.hand_skeletons[0]
-                        .map(|s| tracking::to_openvr_hand_skeleton(config, *LEFT_HAND_ID, s));
-                    right_hand_skeleton = tracking.hand_skeletons[1]
-                        .map(|s| tracking::to_openvr_hand_skeleton(config, *RIGHT_HAND_ID, s));
+                if let Some(marker_pose) = captured_marker {
+                    update_client_calibration(&client_hostname, |calibration| {
+                        calibration.playspace_alignment = Some(marker_pose)
+                    });
+                }
+                if let Some(offset) = floor_height_offset {
+                    update_client_calibration(&client_hostname, |calibration| {
+                        calibration.floor_height_offset = offset
+                    });
                 }
 
                 // Note: using the raw unrecentered head
@@ -754,6 +1561,25 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                     }
                 }
 
+                if gaze_roi_enabled {
+                    if let Some(fov) = *VIEWS_FOV.lock() {
+                        let left =
+                            local_eye_gazes[0].and_then(|g| tracking::to_view_coords(g, fov[0]));
+                        let right =
+                            local_eye_gazes[1].and_then(|g| tracking::to_view_coords(g, fov[1]));
+
+                        // Without gaze the encoder keeps the default uniform quality
+                        if let (Some(left), Some(right)) = (left, right) {
+                            unsafe {
+                                crate::SetGazeRoi(FfiGazeRoi {
+                                    leftEyeCenter: left.to_array(),
+                                    rightEyeCenter: right.to_array(),
+                                })
+                            };
+                        }
+                    }
+                }
+
                 if let Some(sink) = &mut face_tracking_sink {
                     let mut face_data = tracking.face_data;
                     face_data.eye_gazes = local_eye_gazes;
@@ -761,37 +1587,23 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                     sink.send_tracking(face_data);
                 }
 
-                let ffi_motions = motions
-                    .into_iter()
-                    .map(|(id, motion)| tracking::to_ffi_motion(id, motion))
-                    .collect::<Vec<_>>();
-                let ffi_left_hand_skeleton = left_hand_skeleton.map(tracking::to_ffi_skeleton);
-                let ffi_right_hand_skeleton = right_hand_skeleton.map(tracking::to_ffi_skeleton);
-
                 drop(tracking_manager_lock);
 
                 if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
                     stats.report_tracking_received(tracking.target_timestamp);
+                    for (hand_index, gap) in controller_tracking_gaps.into_iter().enumerate() {
+                        if let Some(gap) = gap {
+                            stats.report_controller_tracking_gap(hand_index, gap);
+                        }
+                    }
 
-                    unsafe {
-                        crate::SetTracking(
-                            tracking.target_timestamp.as_nanos() as _,
-                            stats.tracker_pose_time_offset().as_secs_f32(),
-                            ffi_motions.as_ptr(),
-                            ffi_motions.len() as _,
-                            if let Some(skeleton) = &ffi_left_hand_skeleton {
-                                skeleton
-                            } else {
-                                ptr::null()
-                            },
-                            if let Some(skeleton) = &ffi_right_hand_skeleton {
-                                skeleton
-                            } else {
-                                ptr::null()
-                            },
-                            track_controllers,
-                        )
-                    };
+                    tracking::set_driver_tracking(
+                        tracking.target_timestamp,
+                        stats.tracker_pose_time_offset(),
+                        motions,
+                        [left_hand_skeleton, right_hand_skeleton],
+                        track_controllers,
+                    );
                 }
             }
         }
@@ -811,8 +1623,25 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
             if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
                 let timestamp = client_stats.target_timestamp;
                 let decoder_latency = client_stats.video_decode;
+                let motion_to_photon_latency = client_stats
+                    .motion_to_photon_latency
+                    .unwrap_or(client_stats.total_pipeline_latency);
                 let network_latency = stats.report_statistics(client_stats);
 
+                let latency_budget = SERVER_DATA_MANAGER
+                    .read()
+                    .settings()
+                    .video
+                    .latency_budget
+                    .as_option()
+                    .cloned();
+                if let Some(plan) = latency_planner::report_latency(
+                    latency_budget.as_ref(),
+                    motion_to_photon_latency,
+                ) {
+                    alvr_events::send_event(EventType::LatencyPlan(plan));
+                }
+
                 BITRATE_MANAGER.lock().report_frame_latencies(
                     &SERVER_DATA_MANAGER.read().settings().video.bitrate.mode,
                     timestamp,
@@ -823,15 +1652,28 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
         }
     });
 
-    let control_sender = Arc::new(Mutex::new(control_sender));
+    // From now on packets are sent from a dedicated thread, in order of priority
+    let control_sender = control_sender.into_channel();
+    *CONTROL_SENDER.lock() = Some(control_sender.clone());
+    *CLIENT_COLOR_BLINDNESS_FILTER.lock() =
+        settings.video.color_blindness_filter.clone().into_option();
+
+    let session_timer_thread = thread::spawn({
+        let control_sender = control_sender.clone();
+        let client_hostname = client_hostname.clone();
+        move || session_timer::session_timer_loop(client_hostname, control_sender)
+    });
+
+    let resource_usage_thread = thread::spawn(resource_usage::sampling_loop);
 
     let keepalive_thread = thread::spawn({
-        let control_sender = Arc::clone(&control_sender);
+        let control_sender = control_sender.clone();
         let client_hostname = client_hostname.clone();
         move || {
             while IS_STREAMING.value() {
-                if let Err(e) = control_sender.lock().send(&ServerControlPacket::KeepAlive) {
+                if let Err(e) = control_sender.send(ServerControlPacket::KeepAlive) {
                     info!("Client disconnected. Cause: {e:?}");
+                    let reason = format!("Keepalive failed: {e}");
 
                     SERVER_DATA_MANAGER.write().update_client_list(
                         client_hostname,
@@ -840,7 +1682,9 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                         }),
                     );
                     if let Some(notifier) = &*DISCONNECT_CLIENT_NOTIFIER.lock() {
-                        notifier.send(ClientDisconnectRequest::Disconnect).ok();
+                        notifier
+                            .send(ClientDisconnectRequest::Disconnect(reason))
+                            .ok();
                     }
 
                     return;
@@ -852,15 +1696,32 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
     });
 
     let control_receive_thread = thread::spawn({
-        let control_sender = Arc::clone(&control_sender);
+        let control_sender = control_sender.clone();
         let client_hostname = client_hostname.clone();
+        let supported_refresh_rates = streaming_caps.supported_refresh_rates.clone();
         move || {
+            let mut last_traffic_report = Instant::now();
             while IS_STREAMING.value() {
+                if last_traffic_report.elapsed() > TRAFFIC_REPORT_INTERVAL {
+                    let sent_bytes = control_sender.take_sent_bytes();
+                    let received_bytes = control_receiver.take_received_bytes();
+                    if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+                        stats.report_stream_traffic(
+                            TrafficStream::Control,
+                            sent_bytes,
+                            received_bytes,
+                        );
+                    }
+
+                    last_traffic_report = Instant::now();
+                }
+
                 let packet = match control_receiver.recv(STREAMING_RECV_TIMEOUT) {
                     Ok(packet) => packet,
                     Err(ConnectionError::TryAgain(_)) => continue,
                     Err(e) => {
                         info!("Client disconnected. Cause: {e}");
+                        let reason = format!("Connection lost: {e}");
 
                         SERVER_DATA_MANAGER.write().update_client_list(
                             client_hostname,
@@ -869,7 +1730,9 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                             }),
                         );
                         if let Some(notifier) = &*DISCONNECT_CLIENT_NOTIFIER.lock() {
-                            notifier.send(ClientDisconnectRequest::Disconnect).ok();
+                            notifier
+                                .send(ClientDisconnectRequest::Disconnect(reason))
+                                .ok();
                         }
 
                         return;
@@ -890,40 +1753,120 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                             );
                         }
                     }
+                    ClientControlPacket::Recenter => {
+                        let data_manager_lock = SERVER_DATA_MANAGER.read();
+                        let config = &data_manager_lock.settings().headset;
+                        tracking_manager.lock().recenter(
+                            config.position_recentering_mode,
+                            config.rotation_recentering_mode,
+                        );
+                    }
+                    ClientControlPacket::ToggleSeatedMode => seated_mode::toggle(),
                     ClientControlPacket::RequestIdr => {
                         if let Some(config) = DECODER_CONFIG.lock().clone() {
                             control_sender
-                                .lock()
-                                .send(&ServerControlPacket::InitializeDecoder(config))
+                                .send_urgent(ServerControlPacket::InitializeDecoder(config))
                                 .ok();
                         }
+                        statistics::report_marker("IDR requested by client");
                         unsafe { crate::RequestIDR() }
                     }
+                    ClientControlPacket::CachedDecoderConfig(cached_config) => {
+                        if let Some(config) = DECODER_CONFIG.lock().clone() {
+                            let packet = if config.config_buffer == cached_config.config_buffer {
+                                ServerControlPacket::DecoderConfigUnchanged
+                            } else {
+                                ServerControlPacket::InitializeDecoder(config)
+                            };
+                            control_sender.send(packet).ok();
+                        }
+                    }
+                    ClientControlPacket::DecoderOverload(report) => {
+                        warn!(
+                            "Client decoder overloaded ({:.1} dropped frames/s, {} queued frames). Reducing bitrate",
+                            report.dropped_frames_per_sec, report.decoder_queue_depth
+                        );
+                        journal::record(JournalCategory::Error, "Client decoder overloaded");
+
+                        BITRATE_MANAGER.lock().report_decoder_overload(
+                            &SERVER_DATA_MANAGER.read().settings().video.bitrate.mode,
+                        );
+
+                        statistics::report_marker("IDR requested: decoder overload");
+                        unsafe { crate::RequestIDR() }
+                    }
+                    ClientControlPacket::DecoderOptionsReport {
+                        accepted_option_keys,
+                        rejected_option_keys,
+                    } => {
+                        if rejected_option_keys.is_empty() {
+                            info!("Client decoder accepted the extra options {accepted_option_keys:?}");
+                        } else {
+                            warn!(
+                                "Client decoder rejected the extra options {rejected_option_keys:?}, \
+                                using only {accepted_option_keys:?}"
+                            );
+                            journal::record(
+                                JournalCategory::Error,
+                                format!(
+                                    "Client decoder rejected the options {}",
+                                    rejected_option_keys.join(", ")
+                                ),
+                            );
+                        }
+
+                        SERVER_DATA_MANAGER.write().update_client_list(
+                            client_hostname.clone(),
+                            ClientListAction::SetRejectedDecoderOptions(rejected_option_keys),
+                        );
+                    }
+                    ClientControlPacket::RequestRefreshRate(refresh_rate) => {
+                        if !supported_refresh_rates.contains(&refresh_rate) {
+                            warn!("Client requested unsupported refresh rate {refresh_rate}Hz");
+                            continue;
+                        }
+
+                        info!("Switching refresh rate to {refresh_rate}Hz");
+
+                        let frame_interval = Duration::from_secs_f32(1.0 / refresh_rate);
+                        if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+                            stats.set_nominal_server_frame_interval(frame_interval);
+                        }
+                        BITRATE_MANAGER.lock().set_nominal_framerate(refresh_rate);
+
+                        // The encoder framerate follows the dynamic encoder parameters, which
+                        // are updated by set_nominal_framerate()
+                        unsafe { crate::SetRefreshRate(refresh_rate) };
+
+                        control_sender
+                            .send(ServerControlPacket::RefreshRateChanged(refresh_rate))
+                            .ok();
+
+                        // Going back to the preferred FPS clears the override
+                        let preferred_fps =
+                            SERVER_DATA_MANAGER.read().settings().video.preferred_fps;
+                        update_client_calibration(&client_hostname, |calibration| {
+                            calibration.preferred_refresh_rate =
+                                (refresh_rate != preferred_fps).then_some(refresh_rate)
+                        });
+                    }
+                    ClientControlPacket::PauseStream => set_stream_paused(true),
+                    ClientControlPacket::ResumeStream => set_stream_paused(false),
                     ClientControlPacket::VideoErrorReport => {
                         if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
                             stats.report_packet_loss();
                         }
                         unsafe { crate::VideoErrorReportReceive() };
                     }
-                    ClientControlPacket::ViewsConfig(config) => unsafe {
-                        crate::SetViewsConfig(FfiViewsConfig {
-                            fov: [
-                                FfiFov {
-                                    left: config.fov[0].left,
-                                    right: config.fov[0].right,
-                                    up: config.fov[0].up,
-                                    down: config.fov[0].down,
-                                },
-                                FfiFov {
-                                    left: config.fov[1].left,
-                                    right: config.fov[1].right,
-                                    up: config.fov[1].up,
-                                    down: config.fov[1].down,
-                                },
-                            ],
-                            ipd_m: config.ipd_m,
-                        });
-                    },
+                    ClientControlPacket::ViewsConfig(mut config) => {
+                        if let Some(overscan) = reprojection_overscan {
+                            config.fov = config.fov.map(|fov| fov.with_overscan(overscan));
+                        }
+
+                        *VIEWS_FOV.lock() = Some(config.fov);
+
+                        set_views_config(config);
+                    }
                     ClientControlPacket::Battery(packet) => unsafe {
                         crate::SetBattery(packet.device_id, packet.gauge_value, packet.is_plugged);
 
@@ -956,6 +1899,12 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                             }
                         }
 
+                        let entries = if let Some(pointer) = &desktop_pointer {
+                            pointer.lock().filter_buttons(entries)
+                        } else {
+                            entries
+                        };
+
                         for entry in entries {
                             let value = match entry.value {
                                 ButtonValue::Binary(value) => FfiButtonValue {
@@ -968,7 +1917,10 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                                 ButtonValue::Scalar(value) => FfiButtonValue {
                                     type_: crate::FfiButtonType_BUTTON_TYPE_SCALAR,
                                     __bindgen_anon_1: crate::FfiButtonValue__bindgen_ty_1 {
-                                        scalar: value,
+                                        scalar: app_tuning::map_thumbstick_value(
+                                            entry.path_id,
+                                            value,
+                                        ),
                                     },
                                 },
                             };
@@ -979,6 +1931,20 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                     ClientControlPacket::Log { level, message } => {
                         info!("Client {client_hostname}: [{level:?}] {message}")
                     }
+                    ClientControlPacket::ClockSyncRequest(client_time) => {
+                        control_sender
+                            .send_urgent(ServerControlPacket::ClockSyncResponse {
+                                client_time,
+                                server_time: journal::unix_time(),
+                            })
+                            .ok();
+                    }
+                    ClientControlPacket::JournalEntry(entry) => {
+                        journal::forward_client_entry(entry)
+                    }
+                    ClientControlPacket::WifiScanResult(result) => {
+                        alvr_events::send_event(EventType::WifiScan(result))
+                    }
                     _ => (),
                 }
             }
@@ -988,12 +1954,38 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
     let stream_receive_thread = thread::spawn({
         let client_hostname = client_hostname.clone();
         move || {
+            let mut last_traffic_report = Instant::now();
             while IS_STREAMING.value() {
+                if last_traffic_report.elapsed() > TRAFFIC_REPORT_INTERVAL {
+                    let traffic = stream_socket.take_traffic();
+                    if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+                        for (stream_id, traffic) in traffic {
+                            if let Some(stream) = TrafficStream::from_stream_id(stream_id) {
+                                stats.report_stream_traffic(
+                                    stream,
+                                    traffic.sent_bytes,
+                                    traffic.received_bytes,
+                                );
+                            }
+                        }
+                    }
+
+                    last_traffic_report = Instant::now();
+                }
+
                 match stream_socket.recv() {
-                    Ok(()) => (),
+                    Ok(()) => {
+                        let dropped_packets = stream_socket.take_dropped_packets();
+                        if dropped_packets > 0 {
+                            if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+                                stats.report_dropped_packets(dropped_packets);
+                            }
+                        }
+                    }
                     Err(ConnectionError::TryAgain(_)) => continue,
                     Err(e) => {
                         info!("Client disconnected. Cause: {e}");
+                        let reason = format!("Connection lost: {e}");
 
                         SERVER_DATA_MANAGER.write().update_client_list(
                             client_hostname,
@@ -1003,7 +1995,9 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
                         );
 
                         if let Some(notifier) = &*DISCONNECT_CLIENT_NOTIFIER.lock() {
-                            notifier.send(ClientDisconnectRequest::Disconnect).ok();
+                            notifier
+                                .send(ClientDisconnectRequest::Disconnect(reason))
+                                .ok();
                         }
 
                         return;
@@ -1019,7 +2013,11 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
         }
 
         if let Some(notifier) = &*DISCONNECT_CLIENT_NOTIFIER.lock() {
-            notifier.send(ClientDisconnectRequest::Disconnect).ok();
+            notifier
+                .send(ClientDisconnectRequest::Disconnect(
+                    "Client connections disabled".into(),
+                ))
+                .ok();
         }
     });
 
@@ -1041,32 +2039,94 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
         crate::create_recording_file();
     }
 
-    unsafe { crate::InitializeStreaming() };
+    unsafe {
+        crate::InitializeStreaming();
+        crate::SetOverlayLayersMaxFps(overlay_layers.map(|config| config.max_fps).unwrap_or(0.0));
+    }
+    mirror::stream_started();
+    device_presence::stream_started();
 
     SERVER_DATA_MANAGER.write().update_client_list(
-        client_hostname,
+        client_hostname.clone(),
         ClientListAction::SetConnectionState(ConnectionState::Streaming),
     );
+    journal::record(
+        JournalCategory::Connection,
+        format!("Streaming started with {client_hostname}"),
+    );
+
+    let codec = format!("{:?}", settings.video.preferred_codec);
 
     thread::spawn(move || {
         let _connection_drop_guard = _connection_drop_guard;
 
         let res = disconnect_receiver.recv();
+        let disconnect_reason = match &res {
+            Ok(ClientDisconnectRequest::Disconnect(reason)) => reason.clone(),
+            Ok(ClientDisconnectRequest::ServerShutdown) => "Server shutdown".into(),
+            Ok(ClientDisconnectRequest::ServerRestart) => "Server restart".into(),
+            Err(_) => "Unknown".into(),
+        };
+        journal::record(
+            JournalCategory::Connection,
+            format!("Streaming with {client_hostname} stopped: {disconnect_reason}"),
+        );
+        // The driver is shut down right after this thread exits
         if matches!(res, Ok(ClientDisconnectRequest::ServerRestart)) {
-            control_sender
-                .lock()
-                .send(&ServerControlPacket::Restarting)
-                .ok();
+            control_sender.send(ServerControlPacket::Restarting).ok();
+            control_sender.flush(RESTART_NOTIFICATION_TIMEOUT).ok();
         }
 
         // This requests shutdown from threads
         IS_STREAMING.set(false);
+        IS_STREAM_PAUSED.set(false);
+        IS_GAME_AUDIO_RUNNING.set(false);
+        *CONTROL_SENDER.lock() = None;
         *VIDEO_CHANNEL_SENDER.lock() = None;
         *HAPTICS_SENDER.lock() = None;
+        *HAPTICS_PCM_ENCODER.lock() = None;
+        *OVERLAY_LAYERS_CHANNEL_SENDER.lock() = None;
+        *VIEWS_CONFIG.lock() = None;
+        seated_mode::set_active(false);
 
         *VIDEO_RECORDING_FILE.lock() = None;
 
-        unsafe { crate::DeinitializeStreaming() };
+        unsafe {
+            crate::SetOverlayLayersMaxFps(0.0);
+            crate::DeinitializeStreaming();
+        }
+
+        // SteamVR is closing anyway when the server shuts down or restarts
+        if matches!(res, Ok(ClientDisconnectRequest::Disconnect(_)) | Err(_)) {
+            device_presence::stream_stopped(Duration::from_secs(
+                SERVER_DATA_MANAGER
+                    .read()
+                    .settings()
+                    .connection
+                    .device_presence_grace_period_s,
+            ));
+        }
+
+        if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+            stats.unregister_sinks();
+
+            let summary = StreamSummary {
+                client_hostname,
+                view_resolution: (stream_view_resolution.x, stream_view_resolution.y),
+                fps,
+                codec,
+                experiments: experiments::enabled_experiments(),
+                ..stats.stream_summary(disconnect_reason)
+            };
+
+            match alvr_server_io::append_stream_summary(
+                &FILESYSTEM_LAYOUT.stream_history(),
+                summary,
+            ) {
+                Ok(history) => alvr_events::send_event(EventType::StreamHistory(history)),
+                Err(e) => warn!("Failed to save stream summary: {e}"),
+            }
+        }
 
         let on_disconnect_script = SERVER_DATA_MANAGER
             .read()
@@ -1093,22 +2153,66 @@ fn try_connect(mut client_ips: HashMap<IpAddr, String>) -> ConResult {
         control_receive_thread.join().ok();
         stream_receive_thread.join().ok();
         keepalive_thread.join().ok();
+        session_timer_thread.join().ok();
+        resource_usage_thread.join().ok();
         lifecycle_check_thread.join().ok();
     });
 
     Ok(())
 }
 
+// Periodic keyframes make recorded and mirrored streams seekable. They are sent in addition to the
+// keyframes requested on demand
+fn periodic_keyframe_interval(config: &KeyframeInterval) -> Option<Duration> {
+    match config {
+        KeyframeInterval::OnDemand => None,
+        KeyframeInterval::Automatic => {
+            let mirrored = VIDEO_MIRROR_SENDER
+                .lock()
+                .as_ref()
+                .map(|sender| sender.receiver_count() > 0)
+                .unwrap_or(false);
+
+            (mirrored || VIDEO_RECORDING_FILE.lock().is_some())
+                .then_some(AUTOMATIC_KEYFRAME_INTERVAL)
+        }
+        KeyframeInterval::Manual(interval_s) => Some(Duration::from_secs_f32(*interval_s)),
+    }
+}
+
 pub extern "C" fn send_video(timestamp_ns: u64, buffer_ptr: *mut u8, len: i32, is_idr: bool) {
+    if encoder_benchmark::report_encoded_frame(timestamp_ns, len as usize) {
+        return;
+    }
+
     // start in the corrupts state, the client didn't receive the initial IDR yet.
     static STREAM_CORRUPTED: AtomicBool = AtomicBool::new(true);
     static LAST_IDR_INSTANT: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
+    static LAST_KEYFRAME_INSTANT: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
 
     if let Some(sender) = &*VIDEO_CHANNEL_SENDER.lock() {
+        if IS_STREAM_PAUSED.value() {
+            // Drop P-frames after resuming until the requested IDR arrives
+            STREAM_CORRUPTED.store(true, Ordering::SeqCst);
+            return;
+        }
+
         let buffer_size = len as usize;
 
         if is_idr {
             STREAM_CORRUPTED.store(false, Ordering::SeqCst);
+            *LAST_KEYFRAME_INSTANT.lock() = Instant::now();
+        } else if let Some(interval) = periodic_keyframe_interval(
+            &SERVER_DATA_MANAGER
+                .read()
+                .settings()
+                .video
+                .encoder_config
+                .keyframe_interval,
+        ) {
+            if LAST_KEYFRAME_INSTANT.lock().elapsed() > interval {
+                unsafe { crate::RequestIDR() };
+            }
         }
 
         if let Switch::Enabled(config) = &SERVER_DATA_MANAGER
@@ -1159,6 +2263,7 @@ pub extern "C" fn send_video(timestamp_ns: u64, buffer_ptr: *mut u8, len: i32, i
                 Err(TrySendError::Full(_))
             ) {
                 STREAM_CORRUPTED.store(true, Ordering::SeqCst);
+                statistics::report_marker("IDR requested: network congestion");
                 unsafe { crate::RequestIDR() };
                 warn!("Dropping video packet. Reason: Can't push to network");
             }
@@ -1177,12 +2282,27 @@ pub extern "C" fn send_video(timestamp_ns: u64, buffer_ptr: *mut u8, len: i32, i
     }
 }
 
+// Goes through the same path as the haptics requested by SteamVR, scaling settings included
+pub fn test_haptics(device_id: u64, duration: Duration, frequency: f32, amplitude: f32) {
+    if HAPTICS_SENDER.lock().is_none() {
+        warn!("Cannot test haptics, no client is streaming");
+        return;
+    }
+
+    info!(
+        "Testing haptics: {} ms, {frequency} Hz, amplitude {amplitude}",
+        duration.as_millis()
+    );
+    send_haptics(device_id, duration.as_secs_f32(), frequency, amplitude);
+}
+
 pub extern "C" fn send_haptics(device_id: u64, duration_s: f32, frequency: f32, amplitude: f32) {
     let haptics = Haptics {
         device_id,
         duration: Duration::from_secs_f32(f32::max(duration_s, 0.0)),
         frequency,
         amplitude,
+        pcm: None,
     };
 
     let haptics_config = {
@@ -1208,9 +2328,57 @@ pub extern "C" fn send_haptics(device_id: u64, duration_s: f32, frequency: f32,
             .and_then(|c| c.haptics.as_option().cloned())
     };
 
-    if let (Some(config), Some(sender)) = (haptics_config, &mut *HAPTICS_SENDER.lock()) {
-        sender
-            .send_header(&haptics::map_haptics(&config, haptics))
-            .ok();
+    if let (Some(config), Some(sender)) = (haptics_config, &*HAPTICS_SENDER.lock()) {
+        let mut haptics = haptics::map_haptics(&config, haptics);
+        if let Some(encoder) = &mut *HAPTICS_PCM_ENCODER.lock() {
+            haptics.pcm = encoder.encode(&haptics);
+        }
+
+        sender.send_header(haptics).ok();
+    }
+}
+
+// rgba is None when the layer is not submitted anymore
+pub fn send_overlay_layer(
+    layer_index: u32,
+    eye_index: u32,
+    resolution: UVec2,
+    rgba: Option<Vec<u8>>,
+) {
+    let Some(sender) = OVERLAY_LAYERS_CHANNEL_SENDER.lock().clone() else {
+        return;
+    };
+
+    let image = OverlayLayerImage {
+        header: OverlayLayerHeader {
+            layer_index,
+            eye_index,
+        },
+        resolution,
+        rgba,
+    };
+
+    // A dropped image is replaced at the next capture, but hiding a layer must not be lost
+    if image.rgba.is_some() {
+        sender.try_send(image).ok();
+    } else {
+        sender.send(image).ok();
     }
 }
+
+pub fn encode_png(resolution: UVec2, rgba: &[u8]) -> Result<Vec<u8>> {
+    let mut png_buffer = vec![];
+
+    let mut encoder = png::Encoder::new(&mut png_buffer, resolution.x, resolution.y);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // Overlays are mostly transparent or flat and mirror frames are downscaled, the fast setting
+    // compresses them well enough
+    encoder.set_compression(png::Compression::Fast);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+
+    Ok(png_buffer)
+}
@@ -0,0 +1,439 @@
+// Emulated controller profiles. Profiles sharing the same OpenVR device class can be switched
+// while SteamVR is running: the props are set again and the driver recreates the input
+// components. Switching to or from Vive Tracker emulation still requires a SteamVR restart.
+
+use crate::{app_tuning, statistics, SERVER_DATA_MANAGER};
+use alvr_common::{
+    info, once_cell::sync::Lazy, parking_lot::Mutex, settings_schema::Switch, warn, LEFT_HAND_ID,
+    RIGHT_HAND_ID,
+};
+use alvr_session::{
+    ControllersConfig, ControllersEmulationMode, OpenvrPropValue,
+    OpenvrPropertyKey::{self, *},
+};
+use sysinfo::{Pid, PidExt, ProcessExt, ProcessRefreshKind, System, SystemExt};
+
+// Executable name of the application currently rendering the scene
+static SCENE_APPLICATION: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+// Must match the indices used by the driver
+pub fn mode_idx(mode: &ControllersEmulationMode) -> i32 {
+    match mode {
+        ControllersEmulationMode::RiftSTouch => 1,
+        ControllersEmulationMode::ValveIndex => 3,
+        ControllersEmulationMode::ViveWand => 5,
+        ControllersEmulationMode::Quest2Touch => 7,
+        ControllersEmulationMode::ViveTracker => 9,
+    }
+}
+
+pub fn mode_from_idx(idx: i32) -> Option<ControllersEmulationMode> {
+    match idx {
+        1 => Some(ControllersEmulationMode::RiftSTouch),
+        3 => Some(ControllersEmulationMode::ValveIndex),
+        5 => Some(ControllersEmulationMode::ViveWand),
+        7 => Some(ControllersEmulationMode::Quest2Touch),
+        8 | 9 => Some(ControllersEmulationMode::ViveTracker),
+        _ => None,
+    }
+}
+
+// The device class is chosen when the device is registered and cannot change afterwards
+pub fn is_same_device_class(mode_idx1: i32, mode_idx2: i32) -> bool {
+    let is_tracker = |idx| {
+        matches!(
+            mode_from_idx(idx),
+            Some(ControllersEmulationMode::ViveTracker)
+        )
+    };
+
+    is_tracker(mode_idx1) == is_tracker(mode_idx2)
+}
+
+// Per-game overrides take precedence over the global emulation mode
+pub fn active_emulation_mode(config: &ControllersConfig) -> ControllersEmulationMode {
+    SCENE_APPLICATION
+        .lock()
+        .as_ref()
+        .and_then(|name| {
+            config
+                .per_game_emulation_mode
+                .iter()
+                .find(|entry| entry.executable_name.eq_ignore_ascii_case(name))
+        })
+        .map(|entry| entry.emulation_mode.clone())
+        .unwrap_or_else(|| config.emulation_mode.clone())
+}
+
+pub fn serial_number_prefix(mode: &ControllersEmulationMode) -> &'static str {
+    match mode {
+        ControllersEmulationMode::RiftSTouch => "1WMGH000XX0000_Controller",
+        ControllersEmulationMode::ValveIndex => "ALVR Remote Controller",
+        ControllersEmulationMode::ViveWand => "ALVR Remote Controller",
+        ControllersEmulationMode::Quest2Touch => "1WMHH000X00000_Controller",
+        ControllersEmulationMode::ViveTracker => "ALVR Remote Controller",
+    }
+}
+
+pub fn set_profile_props(
+    device_id: u64,
+    mode: ControllersEmulationMode,
+    set_prop: &dyn Fn(OpenvrPropertyKey, OpenvrPropValue),
+) {
+    let set_bool = |key, value| {
+        set_prop(key, OpenvrPropValue::Bool(value));
+    };
+    let set_int32 = |key, value| {
+        set_prop(key, OpenvrPropValue::Int32(value));
+    };
+    let set_uint64 = |key, value| {
+        set_prop(key, OpenvrPropValue::Uint64(value));
+    };
+    let set_string = |key, value: &str| {
+        set_prop(key, OpenvrPropValue::String(value.into()));
+    };
+
+    match mode {
+        ControllersEmulationMode::RiftSTouch => {
+            set_string(TrackingSystemName, "oculus");
+            set_string(ManufacturerName, "Oculus");
+            if device_id == *LEFT_HAND_ID {
+                set_string(ModelNumber, "Oculus Rift S (Left Controller)");
+                set_string(RenderModelName, "oculus_rifts_controller_left");
+                set_string(
+                    RegisteredDeviceType,
+                    "oculus/1WMGH000XX0000_Controller_Left",
+                );
+            } else if device_id == *RIGHT_HAND_ID {
+                set_string(ModelNumber, "Oculus Rift S (Right Controller)");
+                set_string(RenderModelName, "oculus_rifts_controller_right");
+                set_string(
+                    RegisteredDeviceType,
+                    "oculus/1WMGH000XX0000_Controller_Right",
+                );
+            }
+            set_string(ControllerType, "oculus_touch");
+            set_string(InputProfilePath, "{oculus}/input/touch_profile.json");
+
+            if device_id == *LEFT_HAND_ID {
+                set_string(
+                    NamedIconPathDeviceOff,
+                    "{oculus}/icons/rifts_left_controller_off.png",
+                );
+                set_string(
+                    NamedIconPathDeviceSearching,
+                    "{oculus}/icons/rifts_left_controller_searching.gif",
+                );
+                set_string(
+                    NamedIconPathDeviceSearchingAlert,
+                    "{oculus}/icons/rifts_left_controller_searching_alert.gif",
+                );
+                set_string(
+                    NamedIconPathDeviceReady,
+                    "{oculus}/icons/rifts_left_controller_ready.png",
+                );
+                set_string(
+                    NamedIconPathDeviceReadyAlert,
+                    "{oculus}/icons/rifts_left_controller_ready_alert.png",
+                );
+                set_string(
+                    NamedIconPathDeviceAlertLow,
+                    "{oculus}/icons/rifts_left_controller_ready_low.png",
+                );
+            } else if device_id == *RIGHT_HAND_ID {
+                set_string(
+                    NamedIconPathDeviceOff,
+                    "{oculus}/icons/rifts_right_controller_off.png",
+                );
+                set_string(
+                    NamedIconPathDeviceSearching,
+                    "{oculus}/icons/rifts_right_controller_searching.gif",
+                );
+                set_string(
+                    NamedIconPathDeviceSearchingAlert,
+                    "{oculus}/icons/rifts_right_controller_searching_alert.gif",
+                );
+                set_string(
+                    NamedIconPathDeviceReady,
+                    "{oculus}/icons/rifts_right_controller_ready.png",
+                );
+                set_string(
+                    NamedIconPathDeviceReadyAlert,
+                    "{oculus}/icons/rifts_right_controller_ready_alert.png",
+                );
+                set_string(
+                    NamedIconPathDeviceAlertLow,
+                    "{oculus}/icons/rifts_right_controller_ready_low.png",
+                );
+            }
+        }
+        ControllersEmulationMode::ValveIndex => {
+            set_string(TrackingSystemName, "indexcontroller");
+            set_string(ManufacturerName, "Valve");
+            if device_id == *LEFT_HAND_ID {
+                set_string(ModelNumber, "Knuckles (Left Controller)");
+                set_string(
+                    RenderModelName,
+                    "{indexcontroller}valve_controller_knu_1_0_left",
+                );
+                set_string(
+                    RegisteredDeviceType,
+                    "valve/index_controllerLHR-E217CD00_Left",
+                );
+            } else if device_id == *RIGHT_HAND_ID {
+                set_string(ModelNumber, "Knuckles (Right Controller)");
+                set_string(
+                    RenderModelName,
+                    "{indexcontroller}valve_controller_knu_1_0_right",
+                );
+                set_string(
+                    RegisteredDeviceType,
+                    "valve/index_controllerLHR-E217CD00_Right",
+                );
+            }
+            set_string(ControllerType, "knuckles");
+            set_string(
+                InputProfilePath,
+                "{indexcontroller}/input/index_controller_profile.json",
+            );
+        }
+        ControllersEmulationMode::ViveWand => {
+            set_string(TrackingSystemName, "htc");
+            set_string(ManufacturerName, "HTC");
+            set_string(RenderModelName, "vr_controller_vive_1_5");
+            if device_id == *LEFT_HAND_ID {
+                set_string(ModelNumber, "ALVR Remote Controller (Left Controller)");
+                set_string(RegisteredDeviceType, "vive_controller_Left");
+            } else if device_id == *RIGHT_HAND_ID {
+                set_string(ModelNumber, "ALVR Remote Controller (Right Controller)");
+                set_string(RegisteredDeviceType, "oculus/vive_controller_Right");
+            }
+            set_string(ControllerType, "vive_controller");
+            set_string(InputProfilePath, "{oculus}/input/touch_profile.json");
+        }
+        ControllersEmulationMode::Quest2Touch => {
+            set_string(TrackingSystemName, "oculus");
+            set_string(ManufacturerName, "Oculus");
+            if device_id == *LEFT_HAND_ID {
+                set_string(ModelNumber, "Miramar (Left Controller)");
+                set_string(RenderModelName, "oculus_quest2_controller_left");
+                set_string(
+                    RegisteredDeviceType,
+                    "oculus/1WMHH000X00000_Controller_Left",
+                );
+            } else if device_id == *RIGHT_HAND_ID {
+                set_string(ModelNumber, "Miramar (Right Controller)");
+                set_string(RenderModelName, "oculus_quest2_controller_right");
+                set_string(
+                    RegisteredDeviceType,
+                    "oculus/1WMHH000X00000_Controller_Right",
+                );
+            }
+            set_string(ControllerType, "oculus_touch");
+            set_string(InputProfilePath, "{oculus}/input/touch_profile.json");
+
+            if device_id == *LEFT_HAND_ID {
+                set_string(
+                    NamedIconPathDeviceOff,
+                    "{oculus}/icons/rifts_left_controller_off.png",
+                );
+                set_string(
+                    NamedIconPathDeviceSearching,
+                    "{oculus}/icons/rifts_left_controller_searching.gif",
+                );
+                set_string(
+                    NamedIconPathDeviceSearchingAlert,
+                    "{oculus}/icons/rifts_left_controller_searching_alert.gif",
+                );
+                set_string(
+                    NamedIconPathDeviceReady,
+                    "{oculus}/icons/rifts_left_controller_ready.png",
+                );
+                set_string(
+                    NamedIconPathDeviceReadyAlert,
+                    "{oculus}/icons/rifts_left_controller_ready_alert.png",
+                );
+                set_string(
+                    NamedIconPathDeviceAlertLow,
+                    "{oculus}/icons/rifts_left_controller_ready_low.png",
+                );
+            } else if device_id == *RIGHT_HAND_ID {
+                set_string(
+                    NamedIconPathDeviceOff,
+                    "{oculus}/icons/rifts_right_controller_off.png",
+                );
+                set_string(
+                    NamedIconPathDeviceSearching,
+                    "{oculus}/icons/rifts_right_controller_searching.gif",
+                );
+                set_string(
+                    NamedIconPathDeviceSearchingAlert,
+                    "{oculus}/icons/rifts_right_controller_searching_alert.gif",
+                );
+                set_string(
+                    NamedIconPathDeviceReady,
+                    "{oculus}/icons/rifts_right_controller_ready.png",
+                );
+                set_string(
+                    NamedIconPathDeviceReadyAlert,
+                    "{oculus}/icons/rifts_right_controller_ready_alert.png",
+                );
+                set_string(
+                    NamedIconPathDeviceAlertLow,
+                    "{oculus}/icons/rifts_right_controller_ready_low.png",
+                );
+            }
+        }
+        ControllersEmulationMode::ViveTracker => {
+            set_string(TrackingSystemName, "lighthouse");
+            set_string(RenderModelName, "{htc}vr_tracker_vive_1_0");
+            if device_id == *LEFT_HAND_ID {
+                set_string(ModelNumber, "Vive Tracker Pro MV (Left Controller)");
+                set_string(RegisteredDeviceType, "ALVR/tracker/left_foot");
+                set_string(ControllerType, "vive_tracker_left_foot");
+            } else if device_id == *RIGHT_HAND_ID {
+                set_string(ModelNumber, "Vive Tracker Pro MV (Right Controller)");
+                set_string(RegisteredDeviceType, "ALVR/tracker/right_foot");
+                set_string(ControllerType, "vive_tracker_right_foot");
+            }
+            set_string(InputProfilePath, "{htc}/input/vive_tracker_profile.json");
+
+            // All of these property values were dumped from real a vive tracker via
+            // https://github.com/SDraw/openvr_dumper and were copied from
+            // https://github.com/SDraw/driver_kinectV2
+            set_string(ResourceRoot, "htc");
+            set_bool(WillDriftInYaw, false);
+            set_string(TrackingFirmwareVersion, "1541800000 RUNNER-WATCHMAN$runner-watchman@runner-watchman 2018-01-01 FPGA 512(2.56/0/0) BL 0 VRC 1541800000 Radio 1518800000");
+            set_string(
+                HardwareRevisionString,
+                "product 128 rev 2.5.6 lot 2000/0/0 0",
+            );
+            set_string(ConnectedWirelessDongle, "D0000BE000");
+            set_bool(DeviceIsWireless, true);
+            set_bool(DeviceIsCharging, false);
+            set_int32(ControllerHandSelectionPriority, -1);
+            // vr::HmdMatrix34_t l_transform = {
+            //     {{-1.f, 0.f, 0.f, 0.f}, {0.f, 0.f, -1.f, 0.f}, {0.f, -1.f, 0.f, 0.f}}};
+            // vr_properties->SetProperty(this->prop_container,
+            //                            vr::Prop_StatusDisplayTransform_Matrix34,
+            //                            &l_transform,
+            //                            sizeof(vr::HmdMatrix34_t),
+            //                            vr::k_unHmdMatrix34PropertyTag);
+            set_bool(FirmwareUpdateAvailable, false);
+            set_bool(FirmwareManualUpdate, false);
+            set_string(
+                FirmwareManualUpdateURL,
+                "https://developer.valvesoftware.com/wiki/SteamVR/HowTo_Update_Firmware",
+            );
+            set_uint64(HardwareRevisionUint64, 2214720000);
+            set_uint64(FirmwareVersion, 1541800000);
+            set_uint64(FPGAVersion, 512);
+            set_uint64(VRCVersion, 1514800000);
+            set_uint64(RadioVersion, 1518800000);
+            set_uint64(DongleVersion, 8933539758);
+            set_bool(DeviceCanPowerOff, true);
+            // vr_properties->SetStringProperty(this->prop_container,
+            //                                  vr::Prop_Firmware_ProgrammingTarget_String,
+            //                                  GetSerialNumber().c_str());
+            set_bool(FirmwareForceUpdateRequired, false);
+            set_bool(FirmwareRemindUpdate, false);
+            set_bool(HasDisplayComponent, false);
+            set_bool(HasCameraComponent, false);
+            set_bool(HasDriverDirectModeComponent, false);
+            set_bool(HasVirtualDisplayComponent, false);
+
+            // icons
+            set_string(NamedIconPathDeviceOff, "{htc}/icons/tracker_status_off.png");
+            set_string(
+                NamedIconPathDeviceSearching,
+                "{htc}/icons/tracker_status_searching.gif",
+            );
+            set_string(
+                NamedIconPathDeviceSearchingAlert,
+                "{htc}/icons/tracker_status_searching_alert.gif",
+            );
+            set_string(
+                NamedIconPathDeviceReady,
+                "{htc}/icons/tracker_status_ready.png",
+            );
+            set_string(
+                NamedIconPathDeviceReadyAlert,
+                "{htc}/icons/tracker_status_ready_alert.png",
+            );
+            set_string(
+                NamedIconPathDeviceNotReady,
+                "{htc}/icons/tracker_status_error.png",
+            );
+            set_string(
+                NamedIconPathDeviceStandby,
+                "{htc}/icons/tracker_status_standby.png",
+            );
+            set_string(
+                NamedIconPathDeviceAlertLow,
+                "{htc}/icons/tracker_status_ready_low.png",
+            );
+        }
+    }
+}
+
+// Applies the emulation mode to the running controllers if it changed since the last time
+pub fn update_emulation_mode() {
+    let mut data_manager_lock = SERVER_DATA_MANAGER.write();
+
+    let Switch::Enabled(config) = data_manager_lock.settings().headset.controllers.clone() else {
+        return;
+    };
+
+    let old_idx = data_manager_lock
+        .session()
+        .openvr_config
+        .controllers_mode_idx;
+    let new_idx = mode_idx(&active_emulation_mode(&config));
+    if new_idx == old_idx {
+        return;
+    }
+
+    if !is_same_device_class(old_idx, new_idx) {
+        warn!("Switching controllers to or from Vive Tracker emulation requires a SteamVR restart");
+        return;
+    }
+
+    data_manager_lock
+        .session_mut()
+        .openvr_config
+        .controllers_mode_idx = new_idx;
+
+    // The driver calls back into set_device_openvr_props(), which needs the lock
+    drop(data_manager_lock);
+
+    info!("Switching controllers emulation mode");
+    unsafe { crate::SetControllerProfile(new_idx) };
+}
+
+pub fn scene_application() -> Option<String> {
+    SCENE_APPLICATION.lock().clone()
+}
+
+pub extern "C" fn report_scene_application(pid: u32) {
+    let mut system = System::new();
+    let pid = Pid::from_u32(pid);
+    system.refresh_process_specifics(pid, ProcessRefreshKind::new());
+
+    let name = system.process(pid).map(|process| process.name().to_owned());
+    // Set by Steam for the games it launches
+    let steam_app_id = system.process(pid).and_then(|process| {
+        process
+            .environ()
+            .iter()
+            .find_map(|var| var.strip_prefix("SteamAppId="))
+            .and_then(|id| id.parse().ok())
+    });
+    info!("Scene application changed: {name:?} (Steam app ID: {steam_app_id:?})");
+    *SCENE_APPLICATION.lock() = name.clone();
+    if let Some(name) = &name {
+        statistics::report_marker(format!("Game launched: {name}"));
+    }
+
+    update_emulation_mode();
+    app_tuning::select_app(steam_app_id, name.as_deref());
+}
@@ -0,0 +1,79 @@
+// Detection of controllers whose poses stopped arriving, for example because they are asleep or
+// out of view of the headset cameras. SteamVR would keep showing them at their last pose, so once
+// the timeout expires the configured action is applied. The controller is restored by the next
+// pose it receives.
+
+use alvr_common::{LEFT_HAND_ID, RIGHT_HAND_ID};
+use alvr_session::{ControllerTrackingLossConfig, TrackingLossAction};
+use std::time::{Duration, Instant};
+
+// Shorter interruptions are not counted as tracking gaps
+const MIN_TRACKING_GAP: Duration = Duration::from_millis(100);
+
+struct HandState {
+    last_tracked: Instant,
+    lost: bool,
+}
+
+pub struct TrackingLossMonitor {
+    hands: [HandState; 2],
+}
+
+impl TrackingLossMonitor {
+    pub fn new() -> Self {
+        let now = Instant::now();
+
+        Self {
+            hands: [
+                HandState {
+                    last_tracked: now,
+                    lost: false,
+                },
+                HandState {
+                    last_tracked: now,
+                    lost: false,
+                },
+            ],
+        }
+    }
+
+    // Returns the tracking gaps that ended with this update, per hand
+    pub fn update(
+        &mut self,
+        config: &ControllerTrackingLossConfig,
+        tracked: [bool; 2],
+    ) -> [Option<Duration>; 2] {
+        let timeout = Duration::from_millis(config.timeout_ms);
+        let presence = match config.action {
+            TrackingLossAction::Freeze => crate::FfiDevicePresence_DEVICE_PRESENCE_FROZEN,
+            TrackingLossAction::NotTracking => {
+                crate::FfiDevicePresence_DEVICE_PRESENCE_NOT_TRACKING
+            }
+            TrackingLossAction::Hide => crate::FfiDevicePresence_DEVICE_PRESENCE_DISCONNECTED,
+        };
+
+        let mut gaps = [None; 2];
+        for (index, (hand, device_id)) in self
+            .hands
+            .iter_mut()
+            .zip([*LEFT_HAND_ID, *RIGHT_HAND_ID])
+            .enumerate()
+        {
+            let untracked_duration = hand.last_tracked.elapsed();
+
+            if tracked[index] {
+                if untracked_duration > MIN_TRACKING_GAP {
+                    gaps[index] = Some(untracked_duration);
+                }
+                hand.last_tracked = Instant::now();
+                hand.lost = false;
+            } else if !hand.lost && untracked_duration > timeout {
+                hand.lost = true;
+
+                unsafe { crate::SetControllerPresence(device_id, presence) };
+            }
+        }
+
+        gaps
+    }
+}
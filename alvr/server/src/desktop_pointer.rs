@@ -0,0 +1,249 @@
+// Desktop pointer mode: one controller is used as a laser pointer for the desktop mouse cursor.
+// The cursor position is the controller direction relative to the direction it had when the mode
+// was activated, so the cursor starts at the center of the desktop and never drifts.
+
+use alvr_common::{
+    glam::{Quat, Vec3},
+    info, warn, LEFT_HAND_ID, LEFT_SQUEEZE_CLICK_ID, LEFT_SQUEEZE_VALUE_ID,
+    LEFT_THUMBSTICK_CLICK_ID, LEFT_TRIGGER_CLICK_ID, LEFT_TRIGGER_VALUE_ID, RIGHT_HAND_ID,
+    RIGHT_SQUEEZE_CLICK_ID, RIGHT_SQUEEZE_VALUE_ID, RIGHT_THUMBSTICK_CLICK_ID,
+    RIGHT_TRIGGER_CLICK_ID, RIGHT_TRIGGER_VALUE_ID,
+};
+use alvr_packets::{ButtonEntry, ButtonValue};
+use alvr_session::{DesktopPointerConfig, DesktopPointerHand};
+
+// Cursor movements smaller than this (normalized desktop coordinates) are not sent
+const MIN_CURSOR_MOVEMENT: f32 = 0.0005;
+
+#[derive(Clone, Copy)]
+enum MouseButton {
+    Left,
+    Right,
+}
+
+#[cfg(windows)]
+mod os {
+    use super::MouseButton;
+    use std::mem;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_MOUSE, MOUSEEVENTF_ABSOLUTE, MOUSEEVENTF_LEFTDOWN,
+        MOUSEEVENTF_LEFTUP, MOUSEEVENTF_MOVE, MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP,
+        MOUSEINPUT, MOUSE_EVENT_FLAGS,
+    };
+
+    const ABSOLUTE_COORDINATES_MAX: f32 = 65535.0;
+
+    fn send(dx: i32, dy: i32, flags: MOUSE_EVENT_FLAGS) {
+        let input = INPUT {
+            r#type: INPUT_MOUSE,
+            Anonymous: INPUT_0 {
+                mi: MOUSEINPUT {
+                    dx,
+                    dy,
+                    dwFlags: flags,
+                    ..Default::default()
+                },
+            },
+        };
+
+        unsafe { SendInput(&[input], mem::size_of::<INPUT>() as i32) };
+    }
+
+    pub fn is_supported() -> bool {
+        true
+    }
+
+    // x and y are normalized coordinates of the primary monitor
+    pub fn move_cursor(x: f32, y: f32) {
+        send(
+            (x * ABSOLUTE_COORDINATES_MAX) as i32,
+            (y * ABSOLUTE_COORDINATES_MAX) as i32,
+            MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE,
+        );
+    }
+
+    pub fn set_button(button: MouseButton, pressed: bool) {
+        let flags = match (button, pressed) {
+            (MouseButton::Left, true) => MOUSEEVENTF_LEFTDOWN,
+            (MouseButton::Left, false) => MOUSEEVENTF_LEFTUP,
+            (MouseButton::Right, true) => MOUSEEVENTF_RIGHTDOWN,
+            (MouseButton::Right, false) => MOUSEEVENTF_RIGHTUP,
+        };
+
+        send(0, 0, flags);
+    }
+}
+
+#[cfg(not(windows))]
+mod os {
+    use super::MouseButton;
+
+    pub fn is_supported() -> bool {
+        false
+    }
+
+    pub fn move_cursor(_: f32, _: f32) {}
+
+    pub fn set_button(_: MouseButton, _: bool) {}
+}
+
+struct HandButtons {
+    trigger_click: u64,
+    trigger_value: u64,
+    squeeze_click: u64,
+    squeeze_value: u64,
+}
+
+pub struct DesktopPointer {
+    config: DesktopPointerConfig,
+    buttons: HandButtons,
+    thumbstick_clicks: [bool; 2],
+    active: bool,
+    // Set when the mode is activated, replaced by the first pose received afterwards
+    reference_orientation: Option<Quat>,
+    last_cursor: Option<(f32, f32)>,
+    pressed_buttons: [bool; 2],
+}
+
+impl DesktopPointer {
+    pub fn new(config: DesktopPointerConfig) -> Self {
+        let buttons = match config.hand {
+            DesktopPointerHand::Left => HandButtons {
+                trigger_click: *LEFT_TRIGGER_CLICK_ID,
+                trigger_value: *LEFT_TRIGGER_VALUE_ID,
+                squeeze_click: *LEFT_SQUEEZE_CLICK_ID,
+                squeeze_value: *LEFT_SQUEEZE_VALUE_ID,
+            },
+            DesktopPointerHand::Right => HandButtons {
+                trigger_click: *RIGHT_TRIGGER_CLICK_ID,
+                trigger_value: *RIGHT_TRIGGER_VALUE_ID,
+                squeeze_click: *RIGHT_SQUEEZE_CLICK_ID,
+                squeeze_value: *RIGHT_SQUEEZE_VALUE_ID,
+            },
+        };
+
+        Self {
+            config,
+            buttons,
+            thumbstick_clicks: [false; 2],
+            active: false,
+            reference_orientation: None,
+            last_cursor: None,
+            pressed_buttons: [false; 2],
+        }
+    }
+
+    pub fn is_pointer_hand(&self, device_id: u64) -> bool {
+        device_id
+            == match self.config.hand {
+                DesktopPointerHand::Left => *LEFT_HAND_ID,
+                DesktopPointerHand::Right => *RIGHT_HAND_ID,
+            }
+    }
+
+    fn set_active(&mut self, active: bool) {
+        if active && !os::is_supported() {
+            warn!("Desktop pointer mode is not supported on this platform");
+            return;
+        }
+
+        self.active = active;
+        self.reference_orientation = None;
+        self.last_cursor = None;
+        self.release_mouse_buttons();
+
+        info!(
+            "Desktop pointer mode {}",
+            if active { "activated" } else { "deactivated" }
+        );
+    }
+
+    // Don't leave mouse buttons stuck when deactivating with a button held
+    fn release_mouse_buttons(&mut self) {
+        for button in [MouseButton::Left, MouseButton::Right] {
+            self.set_mouse_button(button, false);
+        }
+    }
+
+    fn set_mouse_button(&mut self, button: MouseButton, pressed: bool) {
+        let index = button as usize;
+        if self.pressed_buttons[index] != pressed {
+            os::set_button(button, pressed);
+            self.pressed_buttons[index] = pressed;
+        }
+    }
+
+    // Returns the entries that should still be forwarded to SteamVR
+    pub fn filter_buttons(&mut self, entries: Vec<ButtonEntry>) -> Vec<ButtonEntry> {
+        let mut forwarded = Vec::with_capacity(entries.len());
+
+        for entry in entries {
+            if let ButtonValue::Binary(value) = entry.value {
+                let chord_was_held = self.thumbstick_clicks == [true; 2];
+                if entry.path_id == *LEFT_THUMBSTICK_CLICK_ID {
+                    self.thumbstick_clicks[0] = value;
+                } else if entry.path_id == *RIGHT_THUMBSTICK_CLICK_ID {
+                    self.thumbstick_clicks[1] = value;
+                }
+
+                if !chord_was_held && self.thumbstick_clicks == [true; 2] {
+                    self.set_active(!self.active);
+                }
+            }
+
+            if !self.active {
+                forwarded.push(entry);
+                continue;
+            }
+
+            if entry.path_id == self.buttons.trigger_click {
+                if let ButtonValue::Binary(value) = entry.value {
+                    self.set_mouse_button(MouseButton::Left, value);
+                }
+            } else if entry.path_id == self.buttons.squeeze_click {
+                if let ButtonValue::Binary(value) = entry.value {
+                    self.set_mouse_button(MouseButton::Right, value);
+                }
+            } else if entry.path_id != self.buttons.trigger_value
+                && entry.path_id != self.buttons.squeeze_value
+            {
+                forwarded.push(entry);
+            }
+        }
+
+        forwarded
+    }
+
+    // The orientation can be in any reference space, only the rotation relative to the activation
+    // orientation is used
+    pub fn update_orientation(&mut self, orientation: Quat) {
+        if !self.active {
+            return;
+        }
+
+        let reference = *self.reference_orientation.get_or_insert(orientation);
+        let direction = (reference.inverse() * orientation) * Vec3::NEG_Z;
+
+        let yaw = f32::atan2(direction.x, -direction.z);
+        let pitch = direction.y.clamp(-1.0, 1.0).asin();
+
+        let x = (0.5 + yaw / self.config.horizontal_range_deg.to_radians()).clamp(0.0, 1.0);
+        let y = (0.5 - pitch / self.config.vertical_range_deg.to_radians()).clamp(0.0, 1.0);
+
+        let moved = self.last_cursor.map_or(true, |(last_x, last_y)| {
+            (x - last_x).abs() > MIN_CURSOR_MOVEMENT || (y - last_y).abs() > MIN_CURSOR_MOVEMENT
+        });
+        if moved {
+            os::move_cursor(x, y);
+            self.last_cursor = Some((x, y));
+        }
+    }
+}
+
+// The pointer is dropped when the client disconnects or the settings change, possibly while a
+// button is held
+impl Drop for DesktopPointer {
+    fn drop(&mut self) {
+        self.release_mouse_buttons();
+    }
+}
@@ -0,0 +1,51 @@
+// Presence of the headset and controllers in SteamVR across client disconnections. After a
+// disconnection the devices stay present but not tracking for a grace period, so a brief headset
+// standby does not end the SteamVR session. If no client reconnects in time they are reported as
+// disconnected.
+
+use crate::connection::IS_STREAMING;
+use alvr_common::info;
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+const GRACE_PERIOD_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Incremented at every stream start and stop, so that the grace period timer of a previous
+// disconnection can tell it has been superseded
+static PRESENCE_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+pub fn stream_started() {
+    PRESENCE_GENERATION.fetch_add(1, Ordering::SeqCst);
+
+    unsafe { crate::SetDevicesPresence(crate::FfiDevicePresence_DEVICE_PRESENCE_TRACKING) };
+}
+
+pub fn stream_stopped(grace_period: Duration) {
+    let generation = PRESENCE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    unsafe { crate::SetDevicesPresence(crate::FfiDevicePresence_DEVICE_PRESENCE_NOT_TRACKING) };
+
+    let deadline = Instant::now() + grace_period;
+    thread::spawn(move || {
+        while Instant::now() < deadline {
+            if PRESENCE_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+
+            thread::sleep(GRACE_PERIOD_POLL_INTERVAL);
+        }
+
+        if PRESENCE_GENERATION.load(Ordering::SeqCst) == generation && !IS_STREAMING.value() {
+            info!(
+                "No client reconnected within the grace period, devices reported as disconnected"
+            );
+
+            unsafe {
+                crate::SetDevicesPresence(crate::FfiDevicePresence_DEVICE_PRESENCE_DISCONNECTED)
+            };
+        }
+    });
+}
@@ -0,0 +1,281 @@
+// Micro-benchmark of the encoder on the current GPU. While no client is streaming, synthetic frames
+// are encoded at several resolutions and bitrates, measuring the encoder throughput and the latency
+// from the submission of a frame to its output. The encoded frames come back through send_video()
+// and are discarded. The last report is stored, and the case matching the current settings guides
+// the latency planner.
+
+use crate::{
+    connection::IS_STREAMING, FfiDynamicEncoderParams, FILESYSTEM_LAYOUT, SERVER_DATA_MANAGER,
+};
+use alvr_common::{info, once_cell::sync::Lazy, parking_lot::Mutex, warn};
+use alvr_events::{EncoderBenchmarkReport, EncoderBenchmarkResult, EventType};
+use alvr_session::BitrateMode;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const FRAMES_PER_CASE: u64 = 300;
+// Excluded from the measurements, while the encoder warms up
+const WARMUP_FRAMES: u64 = 30;
+// Asynchronous encoders can output the last frames after the submission loop ended
+const OUTPUT_TIMEOUT: Duration = Duration::from_secs(1);
+const OUTPUT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+const NOMINAL_FRAMERATE: f32 = 90.0;
+
+// Both views side by side: (width, height, bitrate Mbps)
+const CASES: [(u32, u32, u64); 6] = [
+    (2880, 1600, 50),
+    (2880, 1600, 150),
+    (3664, 1920, 50),
+    (3664, 1920, 150),
+    (4128, 2208, 100),
+    (4128, 2208, 300),
+];
+// Used if the bitrate of the current settings is not fixed
+const DEFAULT_ADAPTIVE_BITRATE_MBPS: u64 = 100;
+
+struct CaseState {
+    bitrate_mbps: u64,
+    bitrate_applied: bool,
+    // By target timestamp
+    submit_instants: HashMap<u64, Instant>,
+    latencies: Vec<Duration>,
+    encoded_bytes: usize,
+    last_output_instant: Option<Instant>,
+}
+
+static CASE: Lazy<Mutex<Option<CaseState>>> = Lazy::new(|| Mutex::new(None));
+static IS_RUNNING: AtomicBool = AtomicBool::new(false);
+
+static LAST_REPORT: Lazy<Mutex<Option<EncoderBenchmarkReport>>> = Lazy::new(|| {
+    Mutex::new(alvr_server_io::load_encoder_benchmark_report(
+        &FILESYSTEM_LAYOUT.encoder_benchmark(),
+    ))
+});
+
+fn current_settings_case() -> (u32, u32, u64) {
+    let data_manager_lock = SERVER_DATA_MANAGER.read();
+    let openvr_config = &data_manager_lock.session().openvr_config;
+
+    let bitrate_mbps = match &data_manager_lock.settings().video.bitrate.mode {
+        BitrateMode::ConstantMbps(bitrate_mbps) => *bitrate_mbps,
+        BitrateMode::Adaptive {
+            max_bitrate_mbps, ..
+        } => max_bitrate_mbps
+            .as_option()
+            .copied()
+            .unwrap_or(DEFAULT_ADAPTIVE_BITRATE_MBPS),
+    };
+
+    (
+        openvr_config.target_eye_resolution_width * 2,
+        openvr_config.target_eye_resolution_height,
+        bitrate_mbps,
+    )
+}
+
+// Returns None if the encoder could not be initialized
+fn run_case(width: u32, height: u32, bitrate_mbps: u64) -> Option<EncoderBenchmarkResult> {
+    *CASE.lock() = Some(CaseState {
+        bitrate_mbps,
+        bitrate_applied: false,
+        submit_instants: HashMap::new(),
+        latencies: vec![],
+        encoded_bytes: 0,
+        last_output_instant: None,
+    });
+
+    if !unsafe { crate::InitializeEncoderBenchmark(width, height) } {
+        *CASE.lock() = None;
+        return None;
+    }
+
+    let mut measurement_start = Instant::now();
+    for frame_index in 0..FRAMES_PER_CASE {
+        // Zero is avoided, timestamps start from 1
+        let timestamp_ns = frame_index + 1;
+
+        let now = Instant::now();
+        if frame_index == WARMUP_FRAMES {
+            measurement_start = now;
+        }
+        if let Some(case) = &mut *CASE.lock() {
+            case.submit_instants.insert(timestamp_ns, now);
+        }
+
+        // NB: CASE must not be locked here, synchronous encoders call send_video() from this call
+        unsafe { crate::EncodeBenchmarkFrame(timestamp_ns) };
+    }
+
+    let deadline = Instant::now() + OUTPUT_TIMEOUT;
+    while Instant::now() < deadline
+        && CASE
+            .lock()
+            .as_ref()
+            .map_or(false, |case| !case.submit_instants.is_empty())
+    {
+        thread::sleep(OUTPUT_POLL_INTERVAL);
+    }
+
+    unsafe { crate::DeinitializeEncoderBenchmark() };
+
+    let case = CASE.lock().take()?;
+
+    let frames = case.latencies.len();
+    let measured_duration = case
+        .last_output_instant
+        .map(|instant| instant.saturating_duration_since(measurement_start))
+        .unwrap_or_default();
+    let frames_count = usize::max(frames, 1) as f32;
+
+    Some(EncoderBenchmarkResult {
+        width,
+        height,
+        bitrate_mbps,
+        current_settings: false,
+        frames_per_sec: frames as f32 / measured_duration.as_secs_f32().max(f32::EPSILON),
+        average_latency_ms: case.latencies.iter().sum::<Duration>().as_secs_f32() * 1000.0
+            / frames_count,
+        max_latency_ms: case
+            .latencies
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or_default()
+            .as_secs_f32()
+            * 1000.0,
+        average_frame_size_kb: case.encoded_bytes as f32 / 1000.0 / frames_count,
+    })
+}
+
+fn run() {
+    let current_case = current_settings_case();
+    let mut cases = CASES.iter().map(|case| (*case, false)).collect::<Vec<_>>();
+    cases.push((current_case, true));
+
+    let mut report = EncoderBenchmarkReport {
+        start_unix_secs: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default(),
+        codec: format!(
+            "{:?}",
+            SERVER_DATA_MANAGER.read().settings().video.preferred_codec
+        ),
+        completed: false,
+        results: vec![],
+    };
+
+    for (index, ((width, height, bitrate_mbps), current_settings)) in cases.iter().enumerate() {
+        if IS_STREAMING.value() {
+            info!("Encoder benchmark interrupted by a client connection");
+            break;
+        }
+
+        alvr_events::send_event(EventType::EncoderBenchmarkProgress(Some(format!(
+            "Case {}/{}: {width}x{height} at {bitrate_mbps} Mbps",
+            index + 1,
+            cases.len()
+        ))));
+
+        let Some(mut result) = run_case(*width, *height, *bitrate_mbps) else {
+            warn!("Encoder benchmark: cannot initialize the encoder at {width}x{height}");
+            break;
+        };
+        result.current_settings = *current_settings;
+
+        info!(
+            "Encoder benchmark: {width}x{height} at {bitrate_mbps} Mbps: {:.0} FPS, {:.2} ms",
+            result.frames_per_sec, result.average_latency_ms
+        );
+        report.results.push(result);
+    }
+    report.completed = report.results.len() == cases.len();
+
+    alvr_events::send_event(EventType::EncoderBenchmarkProgress(None));
+
+    if let Err(e) = alvr_server_io::save_encoder_benchmark_report(
+        &FILESYSTEM_LAYOUT.encoder_benchmark(),
+        &report,
+    ) {
+        warn!("Failed to save the encoder benchmark report: {e}");
+    }
+    alvr_events::send_event(EventType::EncoderBenchmarkReport(Some(report.clone())));
+    *LAST_REPORT.lock() = Some(report);
+
+    IS_RUNNING.store(false, Ordering::SeqCst);
+}
+
+pub fn start() {
+    if IS_STREAMING.value() {
+        warn!("Cannot run the encoder benchmark while a client is streaming");
+        return;
+    }
+    if cfg!(not(windows)) {
+        warn!("The encoder benchmark is supported only on Windows");
+        return;
+    }
+    if IS_RUNNING.swap(true, Ordering::SeqCst) {
+        warn!("The encoder benchmark is already running");
+        return;
+    }
+
+    info!("Encoder benchmark started");
+    thread::spawn(run);
+}
+
+pub fn last_report() -> Option<EncoderBenchmarkReport> {
+    LAST_REPORT.lock().clone()
+}
+
+// Encoder latency measured with the current settings, if a benchmark was run
+pub fn current_settings_latency() -> Option<Duration> {
+    LAST_REPORT
+        .lock()
+        .as_ref()?
+        .results
+        .iter()
+        .find(|result| result.current_settings)
+        .map(|result| Duration::from_secs_f32(result.average_latency_ms / 1000.0))
+}
+
+// Returns false if the frame does not belong to the benchmark and must be streamed
+pub fn report_encoded_frame(timestamp_ns: u64, size: usize) -> bool {
+    let mut case_lock = CASE.lock();
+    let Some(case) = &mut *case_lock else {
+        return false;
+    };
+
+    // Only the first output of each frame is measured
+    if let Some(submit_instant) = case.submit_instants.remove(&timestamp_ns) {
+        if timestamp_ns > WARMUP_FRAMES {
+            case.latencies.push(submit_instant.elapsed());
+            case.encoded_bytes += size;
+            case.last_output_instant = Some(Instant::now());
+        }
+    }
+
+    true
+}
+
+pub fn is_running() -> bool {
+    IS_RUNNING.load(Ordering::SeqCst)
+}
+
+// Overrides the bitrate while a benchmark case is running
+pub fn dynamic_encoder_params() -> Option<FfiDynamicEncoderParams> {
+    let mut case_lock = CASE.lock();
+    let case = case_lock.as_mut()?;
+
+    let updated = !case.bitrate_applied;
+    case.bitrate_applied = true;
+
+    Some(FfiDynamicEncoderParams {
+        updated: updated as u32,
+        bitrate_bps: case.bitrate_mbps * 1_000_000,
+        framerate: NOMINAL_FRAMERATE,
+    })
+}
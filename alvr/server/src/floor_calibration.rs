@@ -0,0 +1,76 @@
+// Vertical alignment between the floor of the headset and the floor of SteamVR. The user places the
+// left controller flat on the floor and captures its height: the difference with the expected
+// resting height becomes a vertical offset applied to all tracked devices. The offset can then be
+// nudged manually from the dashboard and is stored per client.
+
+use crate::tracking::TrackingManager;
+use alvr_common::{info, once_cell::sync::Lazy, parking_lot::Mutex, warn};
+
+// Height of the tracked point of a controller lying on the floor
+const CONTROLLER_RESTING_HEIGHT: f32 = 0.04;
+
+struct FloorCalibrationState {
+    height_offset: f32,
+    capture_requested: bool,
+    pending_nudge: f32,
+}
+
+static STATE: Lazy<Mutex<FloorCalibrationState>> = Lazy::new(|| {
+    Mutex::new(FloorCalibrationState {
+        height_offset: 0.0,
+        capture_requested: false,
+        pending_nudge: 0.0,
+    })
+});
+
+// The capture happens on the next tracking update
+pub fn request_capture() {
+    STATE.lock().capture_requested = true;
+}
+
+pub fn nudge(delta: f32) {
+    STATE.lock().pending_nudge += delta;
+}
+
+// Restores the offset of a previous session of the same client
+pub fn restore_offset(height_offset: f32) {
+    let mut state = STATE.lock();
+    state.height_offset = height_offset;
+    state.capture_requested = false;
+    state.pending_nudge = 0.0;
+}
+
+// Called for every tracking update, before the motions are transformed. Returns the new offset if
+// it has just changed
+pub fn update_tracking(tracking_manager: &mut TrackingManager) -> Option<f32> {
+    let mut state = STATE.lock();
+
+    let mut changed = false;
+    if state.capture_requested {
+        state.capture_requested = false;
+
+        if let Some(controller_pose) = tracking_manager.last_left_hand_pose() {
+            let floor_height = tracking_manager.recenter_pose(controller_pose).position.y
+                - CONTROLLER_RESTING_HEIGHT;
+            state.height_offset = -floor_height;
+            changed = true;
+
+            info!(
+                "Floor calibration: the headset floor is {:.1} cm from the SteamVR floor",
+                floor_height * 100.0
+            );
+        } else {
+            warn!("Floor calibration: cannot capture, the left controller is not tracked");
+        }
+    }
+
+    if state.pending_nudge != 0.0 {
+        state.height_offset += state.pending_nudge;
+        state.pending_nudge = 0.0;
+        changed = true;
+    }
+
+    tracking_manager.set_height_offset(state.height_offset);
+
+    changed.then_some(state.height_offset)
+}
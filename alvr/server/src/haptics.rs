@@ -1,6 +1,14 @@
-use alvr_packets::Haptics;
+use crate::app_tuning;
+use alvr_packets::{Haptics, HapticsPcm};
 use alvr_session::HapticsConfig;
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    f32::consts::PI,
+    time::{Duration, Instant},
+};
+
+// Vibrations longer than this are sent only as parameters
+const MAX_PCM_DURATION: Duration = Duration::from_secs(1);
 
 pub fn map_haptics(config: &HapticsConfig, haptics: Haptics) -> Haptics {
     Haptics {
@@ -9,7 +17,78 @@ pub fn map_haptics(config: &HapticsConfig, haptics: Haptics) -> Haptics {
             Duration::from_secs_f32(config.min_duration_s),
         ),
         amplitude: config.intensity_multiplier
+            * app_tuning::haptics_intensity_multiplier()
             * f32::powf(haptics.amplitude, config.amplitude_curve),
         ..haptics
     }
 }
+
+struct DeviceWaveform {
+    end_time: Instant,
+    phase: f32,
+}
+
+// Converts vibrations into sampled waveforms. SteamVR apps often emit a stream of short pulses,
+// consecutive pulses are appended to the playing waveform keeping the phase continuous.
+pub struct HapticsPcmEncoder {
+    sample_rate: f32,
+    devices: HashMap<u64, DeviceWaveform>,
+}
+
+impl HapticsPcmEncoder {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            devices: HashMap::new(),
+        }
+    }
+
+    pub fn encode(&mut self, haptics: &Haptics) -> Option<HapticsPcm> {
+        if haptics.duration > MAX_PCM_DURATION {
+            self.devices.remove(&haptics.device_id);
+
+            return None;
+        }
+
+        let now = Instant::now();
+        let waveform = self
+            .devices
+            .entry(haptics.device_id)
+            .or_insert(DeviceWaveform {
+                end_time: now,
+                phase: 0.0,
+            });
+
+        let append = waveform.end_time > now;
+        if append {
+            waveform.end_time += haptics.duration;
+        } else {
+            waveform.end_time = now + haptics.duration;
+            waveform.phase = 0.0;
+        }
+
+        // Frequency 0 means the default frequency of the device, use a constant amplitude
+        let frequency = f32::min(haptics.frequency, self.sample_rate / 2.0);
+        let phase_step = 2.0 * PI * frequency / self.sample_rate;
+        let samples_count = (haptics.duration.as_secs_f32() * self.sample_rate).round() as usize;
+
+        let samples = (0..samples_count)
+            .map(|_| {
+                let sample = if frequency > 0.0 {
+                    haptics.amplitude * waveform.phase.sin()
+                } else {
+                    haptics.amplitude
+                };
+                waveform.phase = (waveform.phase + phase_step) % (2.0 * PI);
+
+                sample.clamp(-1.0, 1.0)
+            })
+            .collect();
+
+        Some(HapticsPcm {
+            sample_rate: self.sample_rate,
+            samples,
+            append,
+        })
+    }
+}
@@ -0,0 +1,316 @@
+// Generated input profiles and default bindings for the emulated controllers. The bindings target
+// the legacy action set, so they apply only to apps that don't ship their own action manifest.
+
+use crate::FILESYSTEM_LAYOUT;
+use alvr_common::anyhow::Result;
+use alvr_session::{ControllerBindingsConfig, ControllersEmulationMode};
+use serde_json::{self as json, json, Value};
+use std::fs;
+
+// Distance between the click activation and deactivation thresholds
+const THRESHOLD_HYSTERESIS: f32 = 0.05;
+
+#[derive(Clone, Copy)]
+enum SourceType {
+    Button,
+    Trigger,
+    Joystick,
+    Trackpad,
+    Scalar,
+}
+
+struct InputSource {
+    path: &'static str,
+    type_: SourceType,
+    click: bool,
+    touch: bool,
+    force: bool,
+    // None for sources present on both hands
+    left_hand: Option<bool>,
+}
+
+const fn source(path: &'static str, type_: SourceType, click: bool, touch: bool) -> InputSource {
+    InputSource {
+        path,
+        type_,
+        click,
+        touch,
+        force: false,
+        left_hand: None,
+    }
+}
+
+impl InputSource {
+    const fn on_side(self, left_hand: bool) -> Self {
+        Self {
+            left_hand: Some(left_hand),
+            ..self
+        }
+    }
+
+    const fn with_force(self) -> Self {
+        Self {
+            force: true,
+            ..self
+        }
+    }
+}
+
+struct ControllerType {
+    name: &'static str,
+    resource_root: &'static str,
+    sources: &'static [InputSource],
+}
+
+// These must match the components created by the driver for each emulation mode
+const OCULUS_TOUCH: ControllerType = ControllerType {
+    name: "oculus_touch",
+    resource_root: "oculus",
+    sources: &[
+        source("/input/system", SourceType::Button, true, true),
+        source("/input/application_menu", SourceType::Button, true, false),
+        source("/input/grip", SourceType::Trigger, true, true),
+        source("/input/a", SourceType::Button, true, true).on_side(false),
+        source("/input/b", SourceType::Button, true, true).on_side(false),
+        source("/input/x", SourceType::Button, true, true).on_side(true),
+        source("/input/y", SourceType::Button, true, true).on_side(true),
+        source("/input/joystick", SourceType::Joystick, true, true),
+        source("/input/trigger", SourceType::Trigger, true, true),
+    ],
+};
+
+const KNUCKLES: ControllerType = ControllerType {
+    name: "knuckles",
+    resource_root: "indexcontroller",
+    sources: &[
+        source("/input/system", SourceType::Button, true, false),
+        source("/input/a", SourceType::Button, true, true),
+        source("/input/b", SourceType::Button, true, true),
+        source("/input/trigger", SourceType::Trigger, true, true),
+        source("/input/trackpad", SourceType::Trackpad, false, true).with_force(),
+        source("/input/grip", SourceType::Trigger, false, true).with_force(),
+        source("/input/thumbstick", SourceType::Joystick, true, true),
+        source("/input/finger/index", SourceType::Scalar, false, false),
+        source("/input/finger/middle", SourceType::Scalar, false, false),
+        source("/input/finger/ring", SourceType::Scalar, false, false),
+        source("/input/finger/pinky", SourceType::Scalar, false, false),
+    ],
+};
+
+const VIVE_CONTROLLER: ControllerType = ControllerType {
+    name: "vive_controller",
+    resource_root: "htc",
+    sources: &[
+        source("/input/trackpad", SourceType::Trackpad, true, true),
+        source("/input/trigger", SourceType::Trigger, true, false),
+        source("/input/grip", SourceType::Button, true, false),
+        source("/input/application_menu", SourceType::Button, true, false),
+        source("/input/system", SourceType::Button, true, false),
+    ],
+};
+
+fn controller_type(mode: &ControllersEmulationMode) -> Option<&'static ControllerType> {
+    match mode {
+        ControllersEmulationMode::RiftSTouch | ControllersEmulationMode::Quest2Touch => {
+            Some(&OCULUS_TOUCH)
+        }
+        ControllersEmulationMode::ValveIndex => Some(&KNUCKLES),
+        ControllersEmulationMode::ViveWand => Some(&VIVE_CONTROLLER),
+        ControllersEmulationMode::ViveTracker => None,
+    }
+}
+
+fn input_profile(controller_type: &ControllerType, app_keys: &[String]) -> Value {
+    let mut input_source = json::Map::new();
+    for source in controller_type.sources {
+        let type_ = match source.type_ {
+            SourceType::Button => "button",
+            SourceType::Trigger => "trigger",
+            SourceType::Joystick => "joystick",
+            SourceType::Trackpad => "trackpad",
+            SourceType::Scalar => "trigger",
+        };
+        let mut entry = json!({ "type": type_, "click": source.click, "touch": source.touch });
+        if source.force {
+            entry["force"] = json!(true);
+        }
+        if let Some(left_hand) = source.left_hand {
+            entry["side"] = json!(if left_hand { "left" } else { "right" });
+        }
+
+        input_source.insert(source.path.into(), entry);
+    }
+    for side in ["left", "right"] {
+        input_source.insert(
+            format!("/input/skeleton/{side}"),
+            json!({ "type": "skeleton", "skeleton": format!("/skeleton/hand/{side}"), "side": side }),
+        );
+    }
+    input_source.insert("/output/haptic".into(), json!({ "type": "vibration" }));
+    input_source.insert("/pose/raw".into(), json!({ "type": "pose" }));
+
+    let default_bindings = app_keys
+        .iter()
+        .filter(|key| !key.is_empty())
+        .map(|key| {
+            json!({
+                "app_key": key,
+                "binding_url": format!("bindings/alvr_{}_legacy.json", controller_type.name),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "json_id": "input_profile",
+        "controller_type": controller_type.name,
+        "device_class": "TrackedDeviceClass_Controller",
+        "resource_root": controller_type.resource_root,
+        "driver_name": "alvr_server",
+        "input_bindingui_mode": "controller_handed",
+        "should_show_binding_errors": true,
+        "input_source": input_source,
+        "default_bindings": default_bindings,
+    })
+}
+
+fn threshold_parameters(threshold: f32) -> Value {
+    json!({
+        "click_activate_threshold": threshold.to_string(),
+        "click_deactivate_threshold": (threshold - THRESHOLD_HYSTERESIS).max(0.0).to_string(),
+    })
+}
+
+fn legacy_bindings(controller_type: &ControllerType, config: &ControllerBindingsConfig) -> Value {
+    let mut sources = vec![];
+    for side in ["left", "right"] {
+        let action = |name: &str| format!("/actions/legacy/in/{side}_{name}");
+
+        for source in controller_type.sources {
+            if source
+                .left_hand
+                .map_or(false, |left| left != (side == "left"))
+            {
+                continue;
+            }
+
+            // A/X maps to the legacy A button and B/Y to the application menu
+            let button_name = match (source.path, config.swap_a_b) {
+                ("/input/a" | "/input/x", false) | ("/input/b" | "/input/y", true) => "a",
+                ("/input/b" | "/input/y", false) | ("/input/a" | "/input/x", true) => {
+                    "applicationmenu"
+                }
+                ("/input/system", _) => "system",
+                ("/input/application_menu", _) => "applicationmenu",
+                ("/input/grip", _) => "grip",
+                _ => "",
+            };
+
+            let (mode, inputs, parameters) = match (source.type_, source.path) {
+                (SourceType::Button, _) => {
+                    let mut inputs =
+                        json!({ "click": { "output": action(&format!("{button_name}_press")) } });
+                    if source.touch && button_name == "a" {
+                        inputs["touch"] = json!({ "output": action("a_touch") });
+                    }
+                    ("button", inputs, None)
+                }
+                (SourceType::Trigger, "/input/trigger") => (
+                    "trigger",
+                    json!({
+                        "pull": { "output": action("axis1_value") },
+                        "click": { "output": action("axis1_press") },
+                        "touch": { "output": action("axis1_touch") },
+                    }),
+                    Some(threshold_parameters(config.trigger_click_threshold)),
+                ),
+                (SourceType::Trigger, _) => (
+                    "trigger",
+                    json!({
+                        "pull": { "output": action("axis2_value") },
+                        "click": { "output": action("grip_press") },
+                    }),
+                    Some(threshold_parameters(config.grip_click_threshold)),
+                ),
+                // Knuckles already have a thumbstick which uses the primary axis
+                (SourceType::Trackpad, _) if controller_type.name == KNUCKLES.name => continue,
+                (SourceType::Trackpad, _) if !config.trackpad_as_joystick => (
+                    "trackpad",
+                    json!({
+                        "position": { "output": action("axis0_value") },
+                        "click": { "output": action("axis0_press") },
+                        "touch": { "output": action("axis0_touch") },
+                    }),
+                    None,
+                ),
+                (SourceType::Joystick | SourceType::Trackpad, _) => (
+                    "joystick",
+                    json!({
+                        "position": { "output": action("axis0_value") },
+                        "click": { "output": action("axis0_press") },
+                        "touch": { "output": action("axis0_touch") },
+                    }),
+                    None,
+                ),
+                (SourceType::Scalar, _) => continue,
+            };
+
+            let mut entry = json!({
+                "path": format!("/user/hand/{side}{}", source.path),
+                "mode": mode,
+                "inputs": inputs,
+            });
+            if let Some(parameters) = parameters {
+                entry["parameters"] = parameters;
+            }
+
+            sources.push(entry);
+        }
+    }
+
+    json!({
+        "controller_type": controller_type.name,
+        "name": "ALVR generated bindings",
+        "description": "Generated from the ALVR controller bindings settings",
+        "bindings": {
+            "/actions/legacy": {
+                "sources": sources,
+                "poses": [
+                    { "path": "/user/hand/left/pose/raw", "output": "/actions/legacy/in/left_pose" },
+                    { "path": "/user/hand/right/pose/raw", "output": "/actions/legacy/in/right_pose" },
+                ],
+                "haptics": [
+                    { "path": "/user/hand/left/output/haptic", "output": "/actions/legacy/out/left_haptic" },
+                    { "path": "/user/hand/right/output/haptic", "output": "/actions/legacy/out/right_haptic" },
+                ],
+            }
+        },
+    })
+}
+
+// Writes the input profile and bindings for the emulation mode into the driver resources and
+// returns the value for the InputProfilePath property. Returns None for modes without bindings.
+pub fn install_bindings(
+    mode: &ControllersEmulationMode,
+    config: &ControllerBindingsConfig,
+) -> Result<Option<String>> {
+    let Some(controller_type) = controller_type(mode) else {
+        return Ok(None);
+    };
+
+    let input_dir = FILESYSTEM_LAYOUT.resources_dir().join("input");
+    fs::create_dir_all(input_dir.join("bindings"))?;
+
+    let profile_fname = format!("alvr_{}_profile.json", controller_type.name);
+    fs::write(
+        input_dir.join(&profile_fname),
+        json::to_string_pretty(&input_profile(controller_type, &config.app_keys))?,
+    )?;
+    fs::write(
+        input_dir
+            .join("bindings")
+            .join(format!("alvr_{}_legacy.json", controller_type.name)),
+        json::to_string_pretty(&legacy_bindings(controller_type, config))?,
+    )?;
+
+    Ok(Some(format!("{{alvr_server}}/input/{profile_fname}")))
+}
@@ -0,0 +1,28 @@
+// Server side of the event journal. Entries are sent to the dashboard as events, together with the
+// entries received from the client, which are already converted to the server clock.
+
+use alvr_events::EventType;
+use alvr_packets::{JournalCategory, JournalEntry, JournalSource};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub fn unix_time() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+pub fn record(category: JournalCategory, message: impl Into<String>) {
+    alvr_events::send_event(EventType::Journal(JournalEntry {
+        timestamp: unix_time(),
+        source: JournalSource::Server,
+        category,
+        message: message.into(),
+    }));
+}
+
+pub fn forward_client_entry(entry: JournalEntry) {
+    alvr_events::send_event(EventType::Journal(JournalEntry {
+        source: JournalSource::Client,
+        ..entry
+    }));
+}
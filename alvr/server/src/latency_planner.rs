@@ -0,0 +1,217 @@
+// Derives the encoder latency limit and the client buffering from a single motion-to-photon latency
+// target. A "tightness" value between 0 (favor smoothness) and 1 (favor latency) is first estimated
+// from the target, then nudged once per second depending on whether the measured latency is above
+// or comfortably below the target. Every parameter is interpolated between its relaxed and its
+// tight value using the tightness. The controller prediction is not derived from the budget: it
+// must keep matching the measured latency, whatever the target is.
+
+use crate::encoder_benchmark;
+use alvr_common::{once_cell::sync::Lazy, parking_lot::Mutex, SlidingWindowAverage};
+use alvr_events::LatencyPlan;
+use alvr_session::LatencyBudgetConfig;
+use std::time::{Duration, Instant};
+
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+const MEASURED_LATENCY_HISTORY_SIZE: usize = 256;
+
+// Targets at or above the relaxed budget start fully relaxed, at or below the tight budget fully
+// tight
+const RELAXED_BUDGET_MS: f32 = 80.0;
+const TIGHT_BUDGET_MS: f32 = 30.0;
+// Encoder latency the budgets above assume. When the encoder benchmark measured a slower encoder,
+// the initial tightness is estimated from a target reduced by the difference
+const REFERENCE_ENCODER_LATENCY_MS: f32 = 5.0;
+
+// The measured latency must fall below this fraction of the target before relaxing again
+const RELAX_THRESHOLD: f32 = 0.8;
+const TIGHTEN_STEP: f32 = 0.05;
+const RELAX_STEP: f32 = 0.02;
+
+// (relaxed, tight)
+const ENCODER_MAX_SATURATION: (f32, f32) = (1.0, 0.5);
+const CLIENT_MAX_BUFFERING_FRAMES: (f32, f32) = (2.0, 1.0);
+
+struct LatencyPlanner {
+    target: Duration,
+    tightness: f32,
+    measured_latency_average: SlidingWindowAverage<Duration>,
+    last_update: Instant,
+}
+
+impl LatencyPlanner {
+    fn new(target: Duration) -> Self {
+        let encoder_penalty_ms = encoder_benchmark::current_settings_latency()
+            .map(|latency| latency.as_secs_f32() * 1000.0 - REFERENCE_ENCODER_LATENCY_MS)
+            .unwrap_or(0.0);
+        let target_ms = target.as_secs_f32() * 1000.0 - encoder_penalty_ms;
+        let tightness = ((RELAXED_BUDGET_MS - target_ms) / (RELAXED_BUDGET_MS - TIGHT_BUDGET_MS))
+            .clamp(0.0, 1.0);
+
+        Self {
+            target,
+            tightness,
+            measured_latency_average: SlidingWindowAverage::new(
+                target,
+                MEASURED_LATENCY_HISTORY_SIZE,
+            ),
+            last_update: Instant::now(),
+        }
+    }
+
+    fn derive(&self, (relaxed, tight): (f32, f32)) -> f32 {
+        relaxed + (tight - relaxed) * self.tightness
+    }
+
+    fn plan(&self) -> LatencyPlan {
+        LatencyPlan {
+            target_ms: self.target.as_secs_f32() * 1000.0,
+            measured_ms: self.measured_latency_average.get_average().as_secs_f32() * 1000.0,
+            encoder_max_saturation: self.derive(ENCODER_MAX_SATURATION),
+            client_max_buffering_frames: self.derive(CLIENT_MAX_BUFFERING_FRAMES),
+        }
+    }
+
+    fn nudge(&mut self) {
+        let measured = self.measured_latency_average.get_average();
+
+        if measured > self.target {
+            self.tightness = f32::min(self.tightness + TIGHTEN_STEP, 1.0);
+        } else if measured < self.target.mul_f32(RELAX_THRESHOLD) {
+            self.tightness = f32::max(self.tightness - RELAX_STEP, 0.0);
+        }
+    }
+}
+
+// The planner is kept across streams while the target does not change, so the client buffering
+// learned during a stream is used by the next one
+static PLANNER: Lazy<Mutex<Option<LatencyPlanner>>> = Lazy::new(|| Mutex::new(None));
+
+fn with_planner<R>(
+    config: Option<&LatencyBudgetConfig>,
+    f: impl FnOnce(&mut LatencyPlanner) -> R,
+) -> Option<R> {
+    let mut planner_lock = PLANNER.lock();
+
+    let Some(config) = config else {
+        *planner_lock = None;
+        return None;
+    };
+
+    let target = Duration::from_millis(config.target_motion_to_photon_ms);
+    if planner_lock
+        .as_ref()
+        .map_or(true, |planner| planner.target != target)
+    {
+        *planner_lock = None;
+    }
+    let planner = planner_lock.get_or_insert_with(|| LatencyPlanner::new(target));
+
+    Some(f(planner))
+}
+
+// Returns None if the latency budget is disabled
+pub fn current_plan(config: Option<&LatencyBudgetConfig>) -> Option<LatencyPlan> {
+    with_planner(config, |planner| planner.plan())
+}
+
+// Returns the updated plan once per update interval
+pub fn report_latency(
+    config: Option<&LatencyBudgetConfig>,
+    motion_to_photon_latency: Duration,
+) -> Option<LatencyPlan> {
+    with_planner(config, |planner| {
+        planner
+            .measured_latency_average
+            .submit_sample(motion_to_photon_latency);
+
+        if planner.last_update.elapsed() < UPDATE_INTERVAL {
+            return None;
+        }
+        planner.last_update = Instant::now();
+
+        planner.nudge();
+
+        Some(planner.plan())
+    })
+    .flatten()
+}
+
+pub fn encoder_max_saturation() -> Option<f32> {
+    PLANNER
+        .lock()
+        .as_ref()
+        .map(|planner| planner.derive(ENCODER_MAX_SATURATION))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planner(target_ms: u64, tightness: f32) -> LatencyPlanner {
+        let target = Duration::from_millis(target_ms);
+
+        LatencyPlanner {
+            target,
+            tightness,
+            measured_latency_average: SlidingWindowAverage::new(
+                target,
+                MEASURED_LATENCY_HISTORY_SIZE,
+            ),
+            last_update: Instant::now(),
+        }
+    }
+
+    fn set_measured_latency(planner: &mut LatencyPlanner, latency_ms: u64) {
+        for _ in 0..MEASURED_LATENCY_HISTORY_SIZE {
+            planner
+                .measured_latency_average
+                .submit_sample(Duration::from_millis(latency_ms));
+        }
+    }
+
+    #[test]
+    fn test_derive() {
+        assert_eq!(planner(45, 0.0).derive((2.0, 1.0)), 2.0);
+        assert_eq!(planner(45, 1.0).derive((2.0, 1.0)), 1.0);
+        assert_eq!(planner(45, 0.5).derive((2.0, 1.0)), 1.5);
+        assert_eq!(planner(45, 0.25).derive(ENCODER_MAX_SATURATION), 0.875);
+    }
+
+    #[test]
+    fn test_nudge_tightens_above_target() {
+        let mut planner = planner(45, 0.5);
+        set_measured_latency(&mut planner, 50);
+
+        planner.nudge();
+        assert!((planner.tightness - (0.5 + TIGHTEN_STEP)).abs() < 1e-6);
+
+        for _ in 0..100 {
+            planner.nudge();
+        }
+        assert_eq!(planner.tightness, 1.0);
+    }
+
+    #[test]
+    fn test_nudge_relaxes_below_threshold() {
+        let mut planner = planner(45, 0.5);
+        set_measured_latency(&mut planner, 30);
+
+        planner.nudge();
+        assert!((planner.tightness - (0.5 - RELAX_STEP)).abs() < 1e-6);
+
+        for _ in 0..100 {
+            planner.nudge();
+        }
+        assert_eq!(planner.tightness, 0.0);
+    }
+
+    #[test]
+    fn test_nudge_keeps_tightness_near_target() {
+        // Between the relax threshold and the target
+        let mut planner = planner(45, 0.5);
+        set_measured_latency(&mut planner, 40);
+
+        planner.nudge();
+        assert_eq!(planner.tightness, 0.5);
+    }
+}
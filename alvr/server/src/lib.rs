@@ -1,13 +1,33 @@
+mod ab_comparison;
+mod app_tuning;
 mod bitrate;
 mod buttons;
+mod colocation;
 mod connection;
+mod controller_profiles;
+mod controller_tracking;
+mod desktop_pointer;
+mod device_presence;
+mod encoder_benchmark;
 mod face_tracking;
+mod floor_calibration;
 mod haptics;
+mod input_bindings;
+mod journal;
+mod latency_planner;
+mod local_ipc;
 mod logging_backend;
+mod microphone_test;
+mod mirror;
 mod openvr_props;
+mod resource_usage;
+mod seated_mode;
+mod session_timer;
 mod sockets;
 mod statistics;
+mod statistics_sinks;
 mod tracking;
+mod tracking_recording;
 mod web_server;
 
 #[allow(
@@ -24,7 +44,7 @@ use bindings::*;
 
 use alvr_common::{
     error,
-    glam::Quat,
+    glam::{Quat, UVec2},
     log,
     once_cell::sync::Lazy,
     parking_lot::{Mutex, RwLock},
@@ -33,7 +53,7 @@ use alvr_events::EventType;
 use alvr_filesystem::{self as afs, Layout};
 use alvr_packets::{ClientListAction, DecoderInitializationConfig, VideoPacketHeader};
 use alvr_server_io::ServerDataManager;
-use alvr_session::{CodecType, ConnectionState};
+use alvr_session::{BitrateMode, CodecType, ConnectionState};
 use bitrate::BitrateManager;
 use connection::{ClientDisconnectRequest, DISCONNECT_CLIENT_NOTIFIER, SHOULD_CONNECT_TO_CLIENTS};
 use statistics::StatisticsManager;
@@ -110,7 +130,12 @@ fn to_ffi_quat(quat: Quat) -> FfiQuat {
 }
 
 pub fn create_recording_file() {
-    let codec = SERVER_DATA_MANAGER.read().settings().video.preferred_codec;
+    // The stream codec differs from the preferred one during an A/B comparison of codecs
+    let codec = DECODER_CONFIG
+        .lock()
+        .as_ref()
+        .map(|config| config.codec)
+        .unwrap_or_else(|| SERVER_DATA_MANAGER.read().settings().video.preferred_codec);
     let ext = if matches!(codec, CodecType::H264) {
         "h264"
     } else {
@@ -243,6 +268,13 @@ fn init() {
     SERVER_DATA_MANAGER.write().clean_client_list();
 
     if let Some(runtime) = WEBSERVER_RUNTIME.lock().as_mut() {
+        if SERVER_DATA_MANAGER.read().settings().connection.local_ipc {
+            let events_sender = events_sender.clone();
+            runtime.spawn(async {
+                alvr_common::show_err(local_ipc::local_ipc_server(events_sender).await)
+            });
+        }
+
         runtime.spawn(async { alvr_common::show_err(web_server::web_server(events_sender).await) });
     }
 
@@ -330,6 +362,11 @@ pub unsafe extern "C" fn HmdDriverFactory(
     }
 
     extern "C" fn set_video_config_nals(buffer_ptr: *const u8, len: i32, codec: i32) {
+        // The benchmark frames are not streamed
+        if encoder_benchmark::is_running() {
+            return;
+        }
+
         let codec = if codec == 0 {
             CodecType::H264
         } else {
@@ -348,10 +385,20 @@ pub unsafe extern "C" fn HmdDriverFactory(
             file.write_all(&config_buffer).ok();
         }
 
-        *DECODER_CONFIG.lock() = Some(DecoderInitializationConfig {
+        let config = DecoderInitializationConfig {
             codec,
             config_buffer,
-        });
+        };
+
+        // The codec changes during a stream only when the A/B comparison switches variant
+        let codec_changed = DECODER_CONFIG
+            .lock()
+            .replace(config.clone())
+            .map(|previous| previous.codec != codec)
+            .unwrap_or(false);
+        if codec_changed {
+            connection::notify_decoder_config(config);
+        }
     }
 
     pub extern "C" fn driver_ready_idle(set_default_chap: bool) {
@@ -366,6 +413,8 @@ pub unsafe extern "C" fn HmdDriverFactory(
 
             connection::handshake_loop();
         });
+
+        thread::spawn(colocation::peer_loop);
     }
 
     unsafe extern "C" fn path_string_to_hash(path: *const c_char) -> u64 {
@@ -399,10 +448,92 @@ pub unsafe extern "C" fn HmdDriverFactory(
         }
     }
 
+    extern "C" fn report_encoder_qp(timestamp_ns: u64, qp: FfiEncoderQp) {
+        // Negative values are not reported by the encoder
+        let region_qp = |value: f32| (value >= 0.0).then_some(value);
+
+        if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+            stats.report_frame_encoder_qp(
+                Duration::from_nanos(timestamp_ns),
+                qp.average,
+                region_qp(qp.foveationCenter),
+                region_qp(qp.foveationEdges),
+            );
+        }
+    }
+
+    extern "C" fn report_intra_refresh(enabled: bool) {
+        // The benchmark encoders don't stream
+        if encoder_benchmark::is_running() {
+            return;
+        }
+
+        connection::notify_intra_refresh(enabled);
+    }
+
+    // Called when no hardware encoder could be initialized and the CPU encoder is used instead
+    unsafe extern "C" fn report_software_encoder_fallback(hardware_errors: *const c_char) {
+        // The encoder can be recreated many times, for example by the encoder benchmark
+        static WARNED: alvr_common::RelaxedAtomic = alvr_common::RelaxedAtomic::new(false);
+        if WARNED.value() {
+            return;
+        }
+        WARNED.set(true);
+
+        alvr_common::warn!(
+            "No usable hardware video encoder, falling back to software encoding. Expect higher \
+            latency and CPU usage: lower the resolution and the bitrate if the stream stutters. \
+            Hardware encoder errors: {}",
+            CStr::from_ptr(hardware_errors).to_string_lossy()
+        );
+        journal::record(
+            alvr_packets::JournalCategory::Error,
+            "No usable hardware video encoder, using software encoding",
+        );
+    }
+
+    // A null rgba pointer means the layer is not submitted anymore
+    unsafe extern "C" fn report_overlay_layer(
+        layer_index: u32,
+        eye_index: u32,
+        width: u32,
+        height: u32,
+        rgba: *const u8,
+    ) {
+        let rgba = (!rgba.is_null())
+            .then(|| std::slice::from_raw_parts(rgba, (width * height * 4) as usize).to_vec());
+
+        connection::send_overlay_layer(layer_index, eye_index, UVec2::new(width, height), rgba);
+    }
+
+    unsafe extern "C" fn report_mirror_frame(width: u32, height: u32, rgba: *const u8) {
+        let rgba = std::slice::from_raw_parts(rgba, (width * height * 4) as usize);
+
+        mirror::report_frame(UVec2::new(width, height), rgba);
+    }
+
     extern "C" fn get_dynamic_encoder_params() -> FfiDynamicEncoderParams {
-        let (params, stats) = BITRATE_MANAGER
-            .lock()
-            .get_encoder_params(&SERVER_DATA_MANAGER.read().settings().video.bitrate);
+        if let Some(params) = encoder_benchmark::dynamic_encoder_params() {
+            return params;
+        }
+
+        let client_max_bitrate_mbps = SERVER_DATA_MANAGER
+            .read()
+            .client_list()
+            .values()
+            .find(|entry| entry.connection_state == ConnectionState::Streaming)
+            .and_then(|entry| entry.max_bitrate_mbps);
+
+        let mut bitrate_config = SERVER_DATA_MANAGER.read().settings().video.bitrate.clone();
+        if let Some(bitrate_mbps) = ab_comparison::bitrate_override_mbps() {
+            bitrate_config.mode = BitrateMode::ConstantMbps(bitrate_mbps);
+        }
+
+        let (params, stats) = BITRATE_MANAGER.lock().get_encoder_params(
+            &bitrate_config,
+            client_max_bitrate_mbps,
+            latency_planner::encoder_max_saturation(),
+        );
 
         if let Some(stats) = stats {
             if let Some(stats_manager) = &mut *STATISTICS_MANAGER.lock() {
@@ -445,8 +576,14 @@ pub unsafe extern "C" fn HmdDriverFactory(
     PathStringToHash = Some(path_string_to_hash);
     ReportPresent = Some(report_present);
     ReportComposed = Some(report_composed);
+    ReportEncoderQp = Some(report_encoder_qp);
+    ReportSoftwareEncoderFallback = Some(report_software_encoder_fallback);
+    ReportIntraRefresh = Some(report_intra_refresh);
+    ReportOverlayLayer = Some(report_overlay_layer);
+    ReportMirrorFrame = Some(report_mirror_frame);
     GetSerialNumber = Some(openvr_props::get_serial_number);
     SetOpenvrProps = Some(openvr_props::set_device_openvr_props);
+    ReportSceneApplication = Some(controller_profiles::report_scene_application);
     GetDynamicEncoderParams = Some(get_dynamic_encoder_params);
     WaitForVSync = Some(wait_for_vsync);
 
@@ -0,0 +1,172 @@
+// Streaming state and basic controls for local applications (desktop widgets, OBS plugins, window
+// managers), as JSON lines over a Unix socket or a Windows named pipe. The state is pushed on
+// connection and every time it changes, and each line received is a command.
+
+use crate::{
+    connection::{self, ClientDisconnectRequest, IS_STREAM_PAUSED},
+    DISCONNECT_CLIENT_NOTIFIER, SERVER_DATA_MANAGER,
+};
+use alvr_common::{anyhow::Result, info, warn};
+use alvr_events::{Event, EventType};
+use alvr_packets::{ApiLocalIpcCommand, ApiLocalIpcState, ApiStreamingState};
+use alvr_session::ConnectionState;
+use std::thread;
+use tokio::{
+    io::{self, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    sync::broadcast::{self, error::RecvError},
+};
+
+#[cfg(windows)]
+const PIPE_NAME: &str = r"\\.\pipe\alvr";
+
+fn current_state() -> ApiLocalIpcState {
+    let data_manager = SERVER_DATA_MANAGER.read();
+    let maybe_client = data_manager
+        .client_list()
+        .iter()
+        .find(|(_, entry)| entry.connection_state != ConnectionState::Disconnected);
+
+    let state = match maybe_client.map(|(_, entry)| &entry.connection_state) {
+        None | Some(ConnectionState::Disconnected) => ApiStreamingState::Disconnected,
+        Some(ConnectionState::Connecting) => ApiStreamingState::Connecting,
+        Some(ConnectionState::Connected) => ApiStreamingState::Connected,
+        Some(ConnectionState::Streaming) if IS_STREAM_PAUSED.value() => ApiStreamingState::Paused,
+        Some(ConnectionState::Streaming) => ApiStreamingState::Streaming,
+        Some(ConnectionState::Disconnecting { .. }) => ApiStreamingState::Disconnecting,
+    };
+
+    ApiLocalIpcState {
+        state,
+        client_hostname: maybe_client.map(|(hostname, _)| hostname.clone()),
+        client_display_name: maybe_client.map(|(_, entry)| entry.display_name.clone()),
+    }
+}
+
+fn execute_command(command: ApiLocalIpcCommand) {
+    match command {
+        ApiLocalIpcCommand::Disconnect => {
+            if let Some(notifier) = &*DISCONNECT_CLIENT_NOTIFIER.lock() {
+                notifier
+                    .send(ClientDisconnectRequest::Disconnect(
+                        "Requested by local IPC".into(),
+                    ))
+                    .ok();
+            }
+        }
+        ApiLocalIpcCommand::RestartSteamvr => {
+            thread::spawn(crate::restart_driver);
+        }
+        ApiLocalIpcCommand::PauseStream => connection::set_stream_paused(true),
+        ApiLocalIpcCommand::ResumeStream => connection::set_stream_paused(false),
+    }
+}
+
+async fn send_state(
+    writer: &mut (impl AsyncWrite + Unpin),
+    state: &ApiLocalIpcState,
+) -> Result<()> {
+    let mut line = serde_json::to_string(state)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+
+    Ok(())
+}
+
+async fn serve_connection(
+    stream: impl AsyncRead + AsyncWrite,
+    events_sender: broadcast::Sender<Event>,
+) -> Result<()> {
+    let mut events_receiver = events_sender.subscribe();
+    let (reader, mut writer) = io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    let mut last_state = current_state();
+    send_state(&mut writer, &last_state).await?;
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    return Ok(());
+                };
+
+                match serde_json::from_str(&line) {
+                    Ok(command) => execute_command(command),
+                    Err(e) => warn!("Invalid local IPC command: {e}"),
+                }
+            }
+            event = events_receiver.recv() => {
+                match event {
+                    // The state is derived from the session and the stream pause status. If some
+                    // events were missed, check anyway
+                    Ok(Event {
+                        event_type: EventType::Session(_) | EventType::StreamPaused(_),
+                        ..
+                    })
+                    | Err(RecvError::Lagged(_)) => (),
+                    Ok(_) => continue,
+                    Err(RecvError::Closed) => return Ok(()),
+                }
+
+                let state = current_state();
+                if state != last_state {
+                    send_state(&mut writer, &state).await?;
+                    last_state = state;
+                }
+            }
+        }
+    }
+}
+
+fn spawn_connection(
+    stream: impl AsyncRead + AsyncWrite + Send + 'static,
+    events_sender: &broadcast::Sender<Event>,
+) {
+    let events_sender = events_sender.clone();
+    tokio::spawn(async move {
+        if let Err(e) = serve_connection(stream, events_sender).await {
+            info!("Local IPC connection closed: {e}");
+        }
+    });
+}
+
+// The socket is created in $XDG_RUNTIME_DIR, or in the temporary directory, as alvr.sock
+#[cfg(unix)]
+pub async fn local_ipc_server(events_sender: broadcast::Sender<Event>) -> Result<()> {
+    use std::{env, fs, os::unix::fs::PermissionsExt, path::PathBuf};
+
+    let path = env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+        .join("alvr.sock");
+
+    // Left behind if the streamer was not shut down cleanly
+    fs::remove_file(&path).ok();
+
+    let listener = tokio::net::UnixListener::bind(&path)?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        spawn_connection(stream, &events_sender);
+    }
+}
+
+#[cfg(windows)]
+pub async fn local_ipc_server(events_sender: broadcast::Sender<Event>) -> Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(PIPE_NAME)?;
+
+    loop {
+        server.connect().await?;
+
+        // A new instance must exist before the next client tries to connect
+        let connected_server = server;
+        server = ServerOptions::new().create(PIPE_NAME)?;
+
+        spawn_connection(connected_server, &events_sender);
+    }
+}
@@ -4,25 +4,45 @@ use alvr_events::{Event, EventType};
 use chrono::Local;
 use fern::Dispatch;
 use std::fs;
+use sysinfo::{System, SystemExt};
 use tokio::sync::broadcast::Sender;
 
 // todo: don't stringify events immediately, use Sender<Event>
 pub fn init_logging(events_sender: Sender<Event>) {
+    let anonymize = SERVER_DATA_MANAGER.read().settings().logging.anonymize_logs;
+    if anonymize {
+        if let Some(hostname) = System::new().host_name() {
+            alvr_common::register_sensitive_name(&hostname);
+        }
+    }
+
     let mut log_dispatch = Dispatch::new().format(move |out, message, record| {
         let maybe_event = format!("{message}");
         let event_type = if maybe_event.starts_with('{') && maybe_event.ends_with('}') {
             serde_json::from_str(&maybe_event).unwrap()
         } else {
+            let content = message.to_string();
             EventType::Log(LogEntry {
                 severity: LogSeverity::from_log_level(record.level()),
-                content: message.to_string(),
+                content: if anonymize {
+                    alvr_common::anonymize_log(&content)
+                } else {
+                    content
+                },
             })
         };
         let event = Event {
             timestamp: Local::now().format("%H:%M:%S.%f").to_string(),
             event_type,
         };
-        out.finish(format_args!("{}", serde_json::to_string(&event).unwrap()));
+
+        // Events sent to the dashboard are kept intact, the client list is needed to operate
+        let line = serde_json::to_string(&event).unwrap();
+        if anonymize {
+            out.finish(format_args!("{}", alvr_common::anonymize_log(&line)));
+        } else {
+            out.finish(format_args!("{line}"));
+        }
 
         events_sender.send(event).ok();
     });
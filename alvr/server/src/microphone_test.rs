@@ -0,0 +1,125 @@
+// Microphone test from the dashboard. While active, the level of the microphone audio received from
+// the headset is reported a few times per second. The loopback records a few seconds of it and then
+// plays them on the default output device of the PC. Recording first avoids a feedback loop when
+// the headset is close to the speakers.
+
+use crate::SERVER_DATA_MANAGER;
+use alvr_audio::AudioDevice;
+use alvr_common::{info, once_cell::sync::Lazy, parking_lot::Mutex, warn};
+use alvr_events::{EventType, MicrophoneLevel, MicrophoneLoopbackPhase};
+use std::{thread, time::Duration};
+
+const LEVEL_REPORT_INTERVAL: Duration = Duration::from_millis(100);
+const LOOPBACK_DURATION: Duration = Duration::from_secs(5);
+// The microphone is streamed as mono
+const CHANNELS_COUNT: u16 = 1;
+const MIN_DBFS: f32 = -100.0;
+
+struct MicrophoneTestState {
+    active: bool,
+    level_frames_count: usize,
+    level_sum_squares: f32,
+    level_peak: f32,
+    loopback_samples: Option<Vec<f32>>,
+}
+
+static STATE: Lazy<Mutex<MicrophoneTestState>> = Lazy::new(|| {
+    Mutex::new(MicrophoneTestState {
+        active: false,
+        level_frames_count: 0,
+        level_sum_squares: 0.0,
+        level_peak: 0.0,
+        loopback_samples: None,
+    })
+});
+
+fn to_dbfs(amplitude: f32) -> f32 {
+    f32::max(20.0 * amplitude.log10(), MIN_DBFS)
+}
+
+pub fn set_active(active: bool) {
+    let mut state = STATE.lock();
+    state.active = active;
+    state.level_frames_count = 0;
+    state.level_sum_squares = 0.0;
+    state.level_peak = 0.0;
+
+    if !active && state.loopback_samples.take().is_some() {
+        alvr_events::send_event(EventType::MicrophoneLoopback(None));
+    }
+}
+
+pub fn start_loopback() {
+    let mut state = STATE.lock();
+    if !state.active {
+        warn!("Start the microphone test before the loopback");
+        return;
+    }
+    if state.loopback_samples.is_some() {
+        warn!("The microphone loopback is already running");
+        return;
+    }
+
+    info!(
+        "Microphone loopback: recording {} seconds",
+        LOOPBACK_DURATION.as_secs()
+    );
+    state.loopback_samples = Some(vec![]);
+    alvr_events::send_event(EventType::MicrophoneLoopback(Some(
+        MicrophoneLoopbackPhase::Recording,
+    )));
+}
+
+fn play_loopback(samples: Vec<f32>, sample_rate: u32) {
+    alvr_events::send_event(EventType::MicrophoneLoopback(Some(
+        MicrophoneLoopbackPhase::Playing,
+    )));
+
+    let linux_backend = SERVER_DATA_MANAGER.read().settings().audio.linux_backend;
+    let res = AudioDevice::new_output(Some(linux_backend), None).and_then(|device| {
+        alvr_audio::play_samples_blocking(&device, CHANNELS_COUNT, sample_rate, samples)
+    });
+    if let Err(e) = res {
+        warn!("Microphone loopback failed: {e}");
+    }
+
+    alvr_events::send_event(EventType::MicrophoneLoopback(None));
+}
+
+// Called for every microphone packet received from the client
+pub fn report_samples(samples: &[f32], sample_rate: u32) {
+    let mut state = STATE.lock();
+    if !state.active {
+        return;
+    }
+
+    for sample in samples {
+        state.level_sum_squares += sample * sample;
+        state.level_peak = f32::max(state.level_peak, sample.abs());
+    }
+    state.level_frames_count += samples.len() / CHANNELS_COUNT as usize;
+
+    let report_frames_count = (sample_rate as f32 * LEVEL_REPORT_INTERVAL.as_secs_f32()) as usize;
+    if state.level_frames_count >= report_frames_count {
+        let rms = f32::sqrt(state.level_sum_squares / state.level_frames_count as f32);
+        alvr_events::send_event(EventType::MicrophoneLevel(MicrophoneLevel {
+            rms_dbfs: to_dbfs(rms),
+            peak_dbfs: to_dbfs(state.level_peak),
+        }));
+
+        state.level_frames_count = 0;
+        state.level_sum_squares = 0.0;
+        state.level_peak = 0.0;
+    }
+
+    let loopback_samples_count =
+        (sample_rate as f32 * LOOPBACK_DURATION.as_secs_f32()) as usize * CHANNELS_COUNT as usize;
+    if let Some(loopback_samples) = &mut state.loopback_samples {
+        loopback_samples.extend_from_slice(samples);
+
+        if loopback_samples.len() >= loopback_samples_count {
+            let samples = state.loopback_samples.take().unwrap_or_default();
+            thread::spawn(move || play_loopback(samples, sample_rate));
+        }
+    }
+}
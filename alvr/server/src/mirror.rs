@@ -0,0 +1,104 @@
+// Mirror page images. The streamer reads back the scene downscaled to the configured width and at
+// the configured rate, then the images are sent to the page as PNGs, so any browser source can
+// show them whatever the video codec. The capture runs only while a mirror page is connected.
+
+use crate::{connection, web_server::WS_BROADCAST_CAPACITY, SERVER_DATA_MANAGER};
+use alvr_common::{glam::UVec2, once_cell::sync::Lazy, parking_lot::Mutex, warn};
+use std::{
+    sync::mpsc::{self, SyncSender},
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::sync::broadcast;
+
+// The websocket of a page that just connected subscribes after the capture is started
+const SUBSCRIBE_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+struct MirrorState {
+    frames_sender: broadcast::Sender<Vec<u8>>,
+    encoder_sender: SyncSender<(UVec2, Vec<u8>)>,
+    subscribe_instant: Instant,
+}
+
+static STATE: Lazy<Mutex<Option<MirrorState>>> = Lazy::new(|| Mutex::new(None));
+
+fn start_capture() {
+    let config = SERVER_DATA_MANAGER
+        .read()
+        .settings()
+        .connection
+        .mirror_page
+        .clone();
+
+    unsafe { crate::SetMirrorCapture(config.max_width, config.max_fps, config.both_eyes) };
+}
+
+pub fn subscribe() -> broadcast::Sender<Vec<u8>> {
+    let frames_sender = {
+        let mut state_lock = STATE.lock();
+        let state = state_lock.get_or_insert_with(|| {
+            let (frames_sender, _) = broadcast::channel(WS_BROADCAST_CAPACITY);
+            // Frames captured while the previous one is being encoded are dropped
+            let (encoder_sender, encoder_receiver) = mpsc::sync_channel::<(UVec2, Vec<u8>)>(1);
+
+            thread::spawn({
+                let frames_sender = frames_sender.clone();
+                move || {
+                    while let Ok((resolution, rgba)) = encoder_receiver.recv() {
+                        match connection::encode_png(resolution, &rgba) {
+                            Ok(png) => {
+                                frames_sender.send(png).ok();
+                            }
+                            Err(e) => warn!("Failed to encode mirror frame: {e}"),
+                        }
+                    }
+                }
+            });
+
+            MirrorState {
+                frames_sender,
+                encoder_sender,
+                subscribe_instant: Instant::now(),
+            }
+        });
+        state.subscribe_instant = Instant::now();
+
+        state.frames_sender.clone()
+    };
+
+    // The settings are read again so changes apply when the page is reloaded
+    start_capture();
+
+    frames_sender
+}
+
+// The streaming components are recreated with the capture disabled
+pub fn stream_started() {
+    if STATE.lock().is_some() {
+        start_capture();
+    }
+}
+
+pub fn report_frame(resolution: UVec2, rgba: &[u8]) {
+    let mut state_lock = STATE.lock();
+    let Some(state) = &*state_lock else {
+        return;
+    };
+
+    if state.frames_sender.receiver_count() == 0
+        && state.subscribe_instant.elapsed() > SUBSCRIBE_GRACE_PERIOD
+    {
+        // Dropping the encoder sender ends the encoder thread
+        *state_lock = None;
+        drop(state_lock);
+
+        unsafe { crate::SetMirrorCapture(0, 0.0, false) };
+
+        return;
+    }
+
+    state
+        .encoder_sender
+        .try_send((resolution, rgba.to_vec()))
+        .ok();
+}
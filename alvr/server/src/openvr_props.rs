@@ -1,9 +1,12 @@
 // Note: many properties are missing or are stubs.
 // todo: fill out more properties for headset and controllers
-// todo: add more emulation modes
 
-use crate::{FfiOpenvrProperty, FfiOpenvrPropertyValue, SERVER_DATA_MANAGER};
-use alvr_common::{info, settings_schema::Switch, HEAD_ID, LEFT_HAND_ID, RIGHT_HAND_ID};
+use crate::{
+    controller_profiles, input_bindings, FfiOpenvrProperty, FfiOpenvrPropertyValue,
+    SERVER_DATA_MANAGER,
+};
+use alvr_common::{error, info, settings_schema::Switch, HEAD_ID, LEFT_HAND_ID, RIGHT_HAND_ID};
+use alvr_server_io::ServerDataManager;
 use alvr_session::{
     ControllersEmulationMode, HeadsetEmulationMode, OpenvrPropValue,
     OpenvrPropertyKey::{self, *},
@@ -56,6 +59,23 @@ pub fn to_ffi_openvr_prop(key: OpenvrPropertyKey, value: OpenvrPropValue) -> Ffi
     }
 }
 
+// The emulation mode the driver is currently using, which can differ from the settings while a
+// per-game override is active
+fn controllers_emulation_mode(
+    data_manager: &ServerDataManager,
+) -> Option<ControllersEmulationMode> {
+    if let Switch::Enabled(config) = &data_manager.settings().headset.controllers {
+        let mode_idx = data_manager.session().openvr_config.controllers_mode_idx;
+
+        Some(
+            controller_profiles::mode_from_idx(mode_idx)
+                .unwrap_or_else(|| controller_profiles::active_emulation_mode(config)),
+        )
+    } else {
+        None
+    }
+}
+
 fn serial_number(device_id: u64) -> String {
     let data_manager_lock = SERVER_DATA_MANAGER.read();
     let settings = data_manager_lock.settings();
@@ -68,14 +88,8 @@ fn serial_number(device_id: u64) -> String {
             HeadsetEmulationMode::Custom { serial_number, .. } => serial_number.clone(),
         }
     } else if device_id == *LEFT_HAND_ID || device_id == *RIGHT_HAND_ID {
-        if let Switch::Enabled(controllers) = &settings.headset.controllers {
-            let serial_number = match &controllers.emulation_mode {
-                ControllersEmulationMode::RiftSTouch => "1WMGH000XX0000_Controller",
-                ControllersEmulationMode::ValveIndex => "ALVR Remote Controller",
-                ControllersEmulationMode::ViveWand => "ALVR Remote Controller",
-                ControllersEmulationMode::Quest2Touch => "1WMHH000X00000_Controller",
-                ControllersEmulationMode::ViveTracker => "ALVR Remote Controller",
-            };
+        if let Some(mode) = controllers_emulation_mode(&data_manager_lock) {
+            let serial_number = controller_profiles::serial_number_prefix(&mode);
 
             if device_id == *LEFT_HAND_ID {
                 format!("{serial_number}_Left")
@@ -203,7 +217,10 @@ pub extern "C" fn set_device_openvr_props(device_id: u64) {
             set_prop(prop.key, prop.value.clone());
         }
     } else if device_id == *LEFT_HAND_ID || device_id == *RIGHT_HAND_ID {
-        if let Switch::Enabled(config) = &settings.headset.controllers {
+        if let (Switch::Enabled(config), Some(mode)) = (
+            &settings.headset.controllers,
+            controllers_emulation_mode(&data_manager_lock),
+        ) {
             let set_prop = |key, value| {
                 info!(
                     "Setting {} controller OpenVR prop: {key:?} => {value:?}",
@@ -230,285 +247,13 @@ pub extern "C" fn set_device_openvr_props(device_id: u64) {
                 set_prop(key, OpenvrPropValue::String(value.into()));
             };
 
-            match config.emulation_mode {
-                ControllersEmulationMode::RiftSTouch => {
-                    set_string(TrackingSystemName, "oculus");
-                    set_string(ManufacturerName, "Oculus");
-                    if device_id == *LEFT_HAND_ID {
-                        set_string(ModelNumber, "Oculus Rift S (Left Controller)");
-                        set_string(RenderModelName, "oculus_rifts_controller_left");
-                        set_string(
-                            RegisteredDeviceType,
-                            "oculus/1WMGH000XX0000_Controller_Left",
-                        );
-                    } else if device_id == *RIGHT_HAND_ID {
-                        set_string(ModelNumber, "Oculus Rift S (Right Controller)");
-                        set_string(RenderModelName, "oculus_rifts_controller_right");
-                        set_string(
-                            RegisteredDeviceType,
-                            "oculus/1WMGH000XX0000_Controller_Right",
-                        );
-                    }
-                    set_string(ControllerType, "oculus_touch");
-                    set_string(InputProfilePath, "{oculus}/input/touch_profile.json");
+            controller_profiles::set_profile_props(device_id, mode.clone(), &set_prop);
 
-                    if device_id == *LEFT_HAND_ID {
-                        set_string(
-                            NamedIconPathDeviceOff,
-                            "{oculus}/icons/rifts_left_controller_off.png",
-                        );
-                        set_string(
-                            NamedIconPathDeviceSearching,
-                            "{oculus}/icons/rifts_left_controller_searching.gif",
-                        );
-                        set_string(
-                            NamedIconPathDeviceSearchingAlert,
-                            "{oculus}/icons/rifts_left_controller_searching_alert.gif",
-                        );
-                        set_string(
-                            NamedIconPathDeviceReady,
-                            "{oculus}/icons/rifts_left_controller_ready.png",
-                        );
-                        set_string(
-                            NamedIconPathDeviceReadyAlert,
-                            "{oculus}/icons/rifts_left_controller_ready_alert.png",
-                        );
-                        set_string(
-                            NamedIconPathDeviceAlertLow,
-                            "{oculus}/icons/rifts_left_controller_ready_low.png",
-                        );
-                    } else if device_id == *RIGHT_HAND_ID {
-                        set_string(
-                            NamedIconPathDeviceOff,
-                            "{oculus}/icons/rifts_right_controller_off.png",
-                        );
-                        set_string(
-                            NamedIconPathDeviceSearching,
-                            "{oculus}/icons/rifts_right_controller_searching.gif",
-                        );
-                        set_string(
-                            NamedIconPathDeviceSearchingAlert,
-                            "{oculus}/icons/rifts_right_controller_searching_alert.gif",
-                        );
-                        set_string(
-                            NamedIconPathDeviceReady,
-                            "{oculus}/icons/rifts_right_controller_ready.png",
-                        );
-                        set_string(
-                            NamedIconPathDeviceReadyAlert,
-                            "{oculus}/icons/rifts_right_controller_ready_alert.png",
-                        );
-                        set_string(
-                            NamedIconPathDeviceAlertLow,
-                            "{oculus}/icons/rifts_right_controller_ready_low.png",
-                        );
-                    }
-                }
-                ControllersEmulationMode::ValveIndex => {
-                    set_string(TrackingSystemName, "indexcontroller");
-                    set_string(ManufacturerName, "Valve");
-                    if device_id == *LEFT_HAND_ID {
-                        set_string(ModelNumber, "Knuckles (Left Controller)");
-                        set_string(
-                            RenderModelName,
-                            "{indexcontroller}valve_controller_knu_1_0_left",
-                        );
-                        set_string(
-                            RegisteredDeviceType,
-                            "valve/index_controllerLHR-E217CD00_Left",
-                        );
-                    } else if device_id == *RIGHT_HAND_ID {
-                        set_string(ModelNumber, "Knuckles (Right Controller)");
-                        set_string(
-                            RenderModelName,
-                            "{indexcontroller}valve_controller_knu_1_0_right",
-                        );
-                        set_string(
-                            RegisteredDeviceType,
-                            "valve/index_controllerLHR-E217CD00_Right",
-                        );
-                    }
-                    set_string(ControllerType, "knuckles");
-                    set_string(
-                        InputProfilePath,
-                        "{indexcontroller}/input/index_controller_profile.json",
-                    );
-                }
-                ControllersEmulationMode::ViveWand => {
-                    set_string(TrackingSystemName, "htc");
-                    set_string(ManufacturerName, "HTC");
-                    set_string(RenderModelName, "vr_controller_vive_1_5");
-                    if device_id == *LEFT_HAND_ID {
-                        set_string(ModelNumber, "ALVR Remote Controller (Left Controller)");
-                        set_string(RegisteredDeviceType, "vive_controller_Left");
-                    } else if device_id == *RIGHT_HAND_ID {
-                        set_string(ModelNumber, "ALVR Remote Controller (Right Controller)");
-                        set_string(RegisteredDeviceType, "oculus/vive_controller_Right");
-                    }
-                    set_string(ControllerType, "vive_controller");
-                    set_string(InputProfilePath, "{oculus}/input/touch_profile.json");
-                }
-                ControllersEmulationMode::Quest2Touch => {
-                    set_string(TrackingSystemName, "oculus");
-                    set_string(ManufacturerName, "Oculus");
-                    if device_id == *LEFT_HAND_ID {
-                        set_string(ModelNumber, "Miramar (Left Controller)");
-                        set_string(RenderModelName, "oculus_quest2_controller_left");
-                        set_string(
-                            RegisteredDeviceType,
-                            "oculus/1WMHH000X00000_Controller_Left",
-                        );
-                    } else if device_id == *RIGHT_HAND_ID {
-                        set_string(ModelNumber, "Miramar (Right Controller)");
-                        set_string(RenderModelName, "oculus_quest2_controller_right");
-                        set_string(
-                            RegisteredDeviceType,
-                            "oculus/1WMHH000X00000_Controller_Right",
-                        );
-                    }
-                    set_string(ControllerType, "oculus_touch");
-                    set_string(InputProfilePath, "{oculus}/input/touch_profile.json");
-
-                    if device_id == *LEFT_HAND_ID {
-                        set_string(
-                            NamedIconPathDeviceOff,
-                            "{oculus}/icons/rifts_left_controller_off.png",
-                        );
-                        set_string(
-                            NamedIconPathDeviceSearching,
-                            "{oculus}/icons/rifts_left_controller_searching.gif",
-                        );
-                        set_string(
-                            NamedIconPathDeviceSearchingAlert,
-                            "{oculus}/icons/rifts_left_controller_searching_alert.gif",
-                        );
-                        set_string(
-                            NamedIconPathDeviceReady,
-                            "{oculus}/icons/rifts_left_controller_ready.png",
-                        );
-                        set_string(
-                            NamedIconPathDeviceReadyAlert,
-                            "{oculus}/icons/rifts_left_controller_ready_alert.png",
-                        );
-                        set_string(
-                            NamedIconPathDeviceAlertLow,
-                            "{oculus}/icons/rifts_left_controller_ready_low.png",
-                        );
-                    } else if device_id == *RIGHT_HAND_ID {
-                        set_string(
-                            NamedIconPathDeviceOff,
-                            "{oculus}/icons/rifts_right_controller_off.png",
-                        );
-                        set_string(
-                            NamedIconPathDeviceSearching,
-                            "{oculus}/icons/rifts_right_controller_searching.gif",
-                        );
-                        set_string(
-                            NamedIconPathDeviceSearchingAlert,
-                            "{oculus}/icons/rifts_right_controller_searching_alert.gif",
-                        );
-                        set_string(
-                            NamedIconPathDeviceReady,
-                            "{oculus}/icons/rifts_right_controller_ready.png",
-                        );
-                        set_string(
-                            NamedIconPathDeviceReadyAlert,
-                            "{oculus}/icons/rifts_right_controller_ready_alert.png",
-                        );
-                        set_string(
-                            NamedIconPathDeviceAlertLow,
-                            "{oculus}/icons/rifts_right_controller_ready_low.png",
-                        );
-                    }
-                }
-                ControllersEmulationMode::ViveTracker => {
-                    set_string(TrackingSystemName, "lighthouse");
-                    set_string(RenderModelName, "{htc}vr_tracker_vive_1_0");
-                    if device_id == *LEFT_HAND_ID {
-                        set_string(ModelNumber, "Vive Tracker Pro MV (Left Controller)");
-                        set_string(RegisteredDeviceType, "ALVR/tracker/left_foot");
-                        set_string(ControllerType, "vive_tracker_left_foot");
-                    } else if device_id == *RIGHT_HAND_ID {
-                        set_string(ModelNumber, "Vive Tracker Pro MV (Right Controller)");
-                        set_string(RegisteredDeviceType, "ALVR/tracker/right_foot");
-                        set_string(ControllerType, "vive_tracker_right_foot");
-                    }
-                    set_string(InputProfilePath, "{htc}/input/vive_tracker_profile.json");
-
-                    // All of these property values were dumped from real a vive tracker via
-                    // https://github.com/SDraw/openvr_dumper and were copied from
-                    // https://github.com/SDraw/driver_kinectV2
-                    set_string(ResourceRoot, "htc");
-                    set_bool(WillDriftInYaw, false);
-                    set_string(TrackingFirmwareVersion, "1541800000 RUNNER-WATCHMAN$runner-watchman@runner-watchman 2018-01-01 FPGA 512(2.56/0/0) BL 0 VRC 1541800000 Radio 1518800000");
-                    set_string(
-                        HardwareRevisionString,
-                        "product 128 rev 2.5.6 lot 2000/0/0 0",
-                    );
-                    set_string(ConnectedWirelessDongle, "D0000BE000");
-                    set_bool(DeviceIsWireless, true);
-                    set_bool(DeviceIsCharging, false);
-                    set_int32(ControllerHandSelectionPriority, -1);
-                    // vr::HmdMatrix34_t l_transform = {
-                    //     {{-1.f, 0.f, 0.f, 0.f}, {0.f, 0.f, -1.f, 0.f}, {0.f, -1.f, 0.f, 0.f}}};
-                    // vr_properties->SetProperty(this->prop_container,
-                    //                            vr::Prop_StatusDisplayTransform_Matrix34,
-                    //                            &l_transform,
-                    //                            sizeof(vr::HmdMatrix34_t),
-                    //                            vr::k_unHmdMatrix34PropertyTag);
-                    set_bool(FirmwareUpdateAvailable, false);
-                    set_bool(FirmwareManualUpdate, false);
-                    set_string(
-                        FirmwareManualUpdateURL,
-                        "https://developer.valvesoftware.com/wiki/SteamVR/HowTo_Update_Firmware",
-                    );
-                    set_uint64(HardwareRevisionUint64, 2214720000);
-                    set_uint64(FirmwareVersion, 1541800000);
-                    set_uint64(FPGAVersion, 512);
-                    set_uint64(VRCVersion, 1514800000);
-                    set_uint64(RadioVersion, 1518800000);
-                    set_uint64(DongleVersion, 8933539758);
-                    set_bool(DeviceCanPowerOff, true);
-                    // vr_properties->SetStringProperty(this->prop_container,
-                    //                                  vr::Prop_Firmware_ProgrammingTarget_String,
-                    //                                  GetSerialNumber().c_str());
-                    set_bool(FirmwareForceUpdateRequired, false);
-                    set_bool(FirmwareRemindUpdate, false);
-                    set_bool(HasDisplayComponent, false);
-                    set_bool(HasCameraComponent, false);
-                    set_bool(HasDriverDirectModeComponent, false);
-                    set_bool(HasVirtualDisplayComponent, false);
-
-                    // icons
-                    set_string(NamedIconPathDeviceOff, "{htc}/icons/tracker_status_off.png");
-                    set_string(
-                        NamedIconPathDeviceSearching,
-                        "{htc}/icons/tracker_status_searching.gif",
-                    );
-                    set_string(
-                        NamedIconPathDeviceSearchingAlert,
-                        "{htc}/icons/tracker_status_searching_alert.gif",
-                    );
-                    set_string(
-                        NamedIconPathDeviceReady,
-                        "{htc}/icons/tracker_status_ready.png",
-                    );
-                    set_string(
-                        NamedIconPathDeviceReadyAlert,
-                        "{htc}/icons/tracker_status_ready_alert.png",
-                    );
-                    set_string(
-                        NamedIconPathDeviceNotReady,
-                        "{htc}/icons/tracker_status_error.png",
-                    );
-                    set_string(
-                        NamedIconPathDeviceStandby,
-                        "{htc}/icons/tracker_status_standby.png",
-                    );
-                    set_string(
-                        NamedIconPathDeviceAlertLow,
-                        "{htc}/icons/tracker_status_ready_low.png",
-                    );
+            if let Switch::Enabled(bindings_config) = &config.custom_bindings {
+                match input_bindings::install_bindings(&mode, bindings_config) {
+                    Ok(Some(profile_path)) => set_string(InputProfilePath, &profile_path),
+                    Ok(None) => (),
+                    Err(e) => error!("Failed to install controller bindings: {e}"),
                 }
             }
 
@@ -523,7 +268,7 @@ pub extern "C" fn set_device_openvr_props(device_id: u64) {
             // k_eControllerAxis_Joystick = 2
             set_prop(Axis0Type, OpenvrPropValue::Int32(2));
 
-            if matches!(config.emulation_mode, ControllersEmulationMode::ViveTracker) {
+            if matches!(mode, ControllersEmulationMode::ViveTracker) {
                 // TrackedControllerRole_Invalid
                 set_int32(ControllerRoleHint, 0);
             } else if device_id == *LEFT_HAND_ID {
@@ -0,0 +1,108 @@
+// Periodic sampling of the streamer machine load, reported with the graph statistics. This helps
+// to tell whether the bottleneck is the game, the encoder or the network.
+
+use crate::{connection::IS_STREAMING, controller_profiles};
+use alvr_common::{once_cell::sync::Lazy, parking_lot::Mutex};
+use std::{fs, process::Command, thread, time::Duration};
+use sysinfo::{CpuExt, CpuRefreshKind, ProcessExt, ProcessRefreshKind, System, SystemExt};
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+const SAMPLING_INTERVAL: Duration = Duration::from_secs(1);
+const STEAMVR_PROCESSES: [&str; 2] = ["vrserver", "vrcompositor"];
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+// Loads are percentages. CPU loads are relative to the total capacity of the machine
+#[derive(Default, Clone, Copy)]
+pub struct ResourceUsage {
+    pub cpu_load: f32,
+    pub steamvr_cpu_load: f32,
+    pub game_cpu_load: Option<f32>,
+    pub render_gpu_load: Option<f32>,
+    pub encoder_gpu_load: Option<f32>,
+}
+
+static LAST_SAMPLE: Lazy<Mutex<ResourceUsage>> = Lazy::new(|| Mutex::new(ResourceUsage::default()));
+
+pub fn last_sample() -> ResourceUsage {
+    *LAST_SAMPLE.lock()
+}
+
+// Returns the render and encoder loads. Only NVIDIA GPUs report the encoder load
+fn gpu_loads() -> (Option<f32>, Option<f32>) {
+    let mut command = Command::new("nvidia-smi");
+    command.args([
+        "--query-gpu=utilization.gpu,utilization.encoder",
+        "--format=csv,noheader,nounits",
+    ]);
+    #[cfg(windows)]
+    command.creation_flags(CREATE_NO_WINDOW);
+
+    if let Ok(output) = command.output() {
+        let output = String::from_utf8_lossy(&output.stdout);
+        let mut values = output
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .split(',')
+            .map(|value| value.trim().parse::<f32>().ok());
+
+        if let (Some(Some(render)), Some(Some(encoder))) = (values.next(), values.next()) {
+            return (Some(render), Some(encoder));
+        }
+    }
+
+    // Exposed by the amdgpu driver
+    if cfg!(target_os = "linux") {
+        if let Ok(entries) = fs::read_dir("/sys/class/drm") {
+            for entry in entries.flatten() {
+                if let Some(load) = fs::read_to_string(entry.path().join("device/gpu_busy_percent"))
+                    .ok()
+                    .and_then(|value| value.trim().parse().ok())
+                {
+                    return (Some(load), None);
+                }
+            }
+        }
+    }
+
+    (None, None)
+}
+
+pub fn sampling_loop() {
+    let mut system = System::new();
+
+    while IS_STREAMING.value() {
+        system.refresh_cpu_specifics(CpuRefreshKind::new().with_cpu_usage());
+        system.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu());
+
+        // Process usage is relative to a single core
+        let cpu_count = system.cpus().len().max(1) as f32;
+        let processes_load = |name: &str| {
+            system
+                .processes_by_name(name)
+                .map(|process| process.cpu_usage())
+                .sum::<f32>()
+                / cpu_count
+        };
+
+        let (render_gpu_load, encoder_gpu_load) = gpu_loads();
+
+        *LAST_SAMPLE.lock() = ResourceUsage {
+            cpu_load: system.global_cpu_info().cpu_usage(),
+            steamvr_cpu_load: STEAMVR_PROCESSES
+                .iter()
+                .map(|name| processes_load(name))
+                .sum(),
+            game_cpu_load: controller_profiles::scene_application()
+                .map(|name| processes_load(&name)),
+            render_gpu_load,
+            encoder_gpu_load,
+        };
+
+        thread::sleep(SAMPLING_INTERVAL);
+    }
+}
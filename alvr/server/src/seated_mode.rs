@@ -0,0 +1,35 @@
+// Seated mode, for users playing in bed or on a couch. While active, the floor calibration offset is
+// replaced by the fixed vertical offset from the settings, which raises the view to a standing
+// height. It is toggled from the dashboard or with a controller chord and ends with the stream.
+
+use crate::journal;
+use alvr_common::{info, once_cell::sync::Lazy, RelaxedAtomic};
+use alvr_events::EventType;
+use alvr_packets::JournalCategory;
+
+static ACTIVE: Lazy<RelaxedAtomic> = Lazy::new(|| RelaxedAtomic::new(false));
+
+pub fn is_active() -> bool {
+    ACTIVE.value()
+}
+
+pub fn set_active(active: bool) {
+    if ACTIVE.value() == active {
+        return;
+    }
+    ACTIVE.set(active);
+
+    let message = if active {
+        "Seated mode enabled"
+    } else {
+        "Seated mode disabled"
+    };
+    info!("{message}");
+    journal::record(JournalCategory::Stream, message);
+
+    alvr_events::send_event(EventType::SeatedMode(active));
+}
+
+pub fn toggle() {
+    set_active(!ACTIVE.value());
+}
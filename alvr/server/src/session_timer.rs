@@ -0,0 +1,106 @@
+use crate::{
+    connection::{ClientDisconnectRequest, DISCONNECT_CLIENT_NOTIFIER, IS_STREAMING},
+    SERVER_DATA_MANAGER,
+};
+use alvr_common::{info, once_cell::sync::Lazy, parking_lot::Mutex, settings_schema::Switch, warn};
+use alvr_packets::ServerControlPacket;
+use alvr_sockets::ControlChannelSender;
+use std::{
+    collections::HashSet,
+    thread,
+    time::{Duration, Instant},
+};
+
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Clients that used up their time. They are not reconnected until the operator resets the timer
+static EXPIRED_CLIENTS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+// Returns None if session time limits are disabled
+fn time_limits(hostname: &str) -> Option<(Duration, Duration)> {
+    let data_manager = SERVER_DATA_MANAGER.read();
+
+    let Switch::Enabled(config) = &data_manager.settings().connection.session_time_limit else {
+        return None;
+    };
+
+    let limit_min = data_manager
+        .client_list()
+        .get(hostname)
+        .and_then(|entry| entry.session_time_limit_min)
+        .unwrap_or(config.default_limit_min);
+
+    Some((
+        Duration::from_secs(limit_min * 60),
+        Duration::from_secs(config.countdown_s),
+    ))
+}
+
+// Returns an empty list if session time limits are disabled
+pub fn expired_clients() -> HashSet<String> {
+    if matches!(
+        SERVER_DATA_MANAGER
+            .read()
+            .settings()
+            .connection
+            .session_time_limit,
+        Switch::Enabled(_)
+    ) {
+        EXPIRED_CLIENTS.lock().clone()
+    } else {
+        HashSet::new()
+    }
+}
+
+pub fn is_expired(hostname: &str) -> bool {
+    expired_clients().contains(hostname)
+}
+
+pub fn reset(hostname: &str) {
+    if EXPIRED_CLIENTS.lock().remove(hostname) {
+        info!("Session timer reset for {hostname}");
+    }
+}
+
+// Runs for the whole streaming session. Settings are read on every update so the operator can
+// change the limit of a running session.
+pub fn session_timer_loop(
+    hostname: String,
+    control_sender: ControlChannelSender<ServerControlPacket>,
+) {
+    let begin_instant = Instant::now();
+
+    while IS_STREAMING.value() {
+        thread::sleep(UPDATE_INTERVAL);
+
+        let Some((limit, countdown)) = time_limits(&hostname) else {
+            continue;
+        };
+
+        let remaining = limit.saturating_sub(begin_instant.elapsed());
+        if remaining.is_zero() {
+            info!("Session time of {hostname} is over");
+
+            EXPIRED_CLIENTS.lock().insert(hostname);
+
+            control_sender
+                .send(ServerControlPacket::SessionTimeExpired)
+                .ok();
+            if let Some(notifier) = &*DISCONNECT_CLIENT_NOTIFIER.lock() {
+                notifier
+                    .send(ClientDisconnectRequest::Disconnect(
+                        "Session time expired".into(),
+                    ))
+                    .ok();
+            }
+
+            return;
+        } else if remaining <= countdown {
+            if let Err(e) =
+                control_sender.send(ServerControlPacket::SessionTimeRemaining(remaining))
+            {
+                warn!("Failed to send remaining session time: {e}");
+            }
+        }
+    }
+}
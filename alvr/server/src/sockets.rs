@@ -1,13 +1,95 @@
 use alvr_common::{anyhow::Result, con_bail, ConResult, HandleTryAgain, ToCon, ALVR_NAME};
-use alvr_sockets::{CONTROL_PORT, HANDSHAKE_PACKET_SIZE_BYTES, LOCAL_IP};
+use alvr_sockets::{
+    CONTROL_PORT, HANDSHAKE_PACKET_SIZE_BYTES, LOCAL_IP, PAIRING_PACKET_SIZE_BYTES,
+};
 use std::{
-    net::{IpAddr, UdpSocket},
+    net::{IpAddr, Ipv4Addr, UdpSocket},
     time::Duration,
 };
 
+// Lowercase, matched anywhere in the adapter name (Windows friendly names)
+const VIRTUAL_ADAPTER_KEYWORDS: &[&str] = &[
+    "virtualbox",
+    "vmware",
+    "hyper-v",
+    "vethernet",
+    "tailscale",
+    "zerotier",
+    "wireguard",
+    "hamachi",
+    "radmin",
+    "openvpn",
+    "nordlynx",
+    "tap-windows",
+];
+// Lowercase, matched at the start of the interface name (Linux and macOS)
+const VIRTUAL_ADAPTER_PREFIXES: &[&str] = &[
+    "vboxnet", "vmnet", "docker", "br-", "veth", "virbr", "tun", "tap", "wg", "utun", "zt",
+];
+
+// IP of the interface used to reach the given host. No packet is actually sent
+fn route_local_ip(peer_ip: IpAddr) -> Option<IpAddr> {
+    let socket = UdpSocket::bind((LOCAL_IP, 0)).ok()?;
+    socket.connect((peer_ip, 80)).ok()?;
+
+    Some(socket.local_addr().ok()?.ip())
+}
+
+fn is_virtual_adapter(name: &str, ip: IpAddr) -> bool {
+    let name = name.to_lowercase();
+
+    // Tailscale assigns addresses in the carrier-grade NAT range
+    let is_cgnat =
+        matches!(ip, IpAddr::V4(ip) if ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64);
+
+    VIRTUAL_ADAPTER_KEYWORDS
+        .iter()
+        .any(|keyword| name.contains(keyword))
+        || VIRTUAL_ADAPTER_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+        || is_cgnat
+}
+
+// Name of the VPN or virtual adapter used to reach the given host, None if the host is reached
+// through a physical adapter or the adapter is unknown
+pub fn virtual_adapter_to(peer_ip: IpAddr) -> Option<String> {
+    let local_ip = route_local_ip(peer_ip)?;
+
+    local_ip_address::list_afinet_netifas()
+        .ok()?
+        .into_iter()
+        .find(|(name, ip)| *ip == local_ip && is_virtual_adapter(name, *ip))
+        .map(|(name, _)| name)
+}
+
+// IP of the interface used to reach other hosts. The default route can go through a VPN, in which
+// case the first physical adapter with a private address is preferred
+pub fn local_ip() -> Option<IpAddr> {
+    let default_ip = route_local_ip(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)))?;
+
+    let Ok(interfaces) = local_ip_address::list_afinet_netifas() else {
+        return Some(default_ip);
+    };
+    if !interfaces
+        .iter()
+        .any(|(name, ip)| *ip == default_ip && is_virtual_adapter(name, *ip))
+    {
+        return Some(default_ip);
+    }
+
+    interfaces
+        .into_iter()
+        .find_map(|(name, ip)| match ip {
+            IpAddr::V4(ipv4) if ipv4.is_private() && !is_virtual_adapter(&name, ip) => Some(ip),
+            _ => None,
+        })
+        .or(Some(default_ip))
+}
+
 pub struct WelcomeSocket {
     socket: UdpSocket,
-    buffer: [u8; HANDSHAKE_PACKET_SIZE_BYTES],
+    buffer: [u8; PAIRING_PACKET_SIZE_BYTES],
 }
 
 impl WelcomeSocket {
@@ -17,15 +99,15 @@ impl WelcomeSocket {
 
         Ok(Self {
             socket,
-            buffer: [0; HANDSHAKE_PACKET_SIZE_BYTES],
+            buffer: [0; PAIRING_PACKET_SIZE_BYTES],
         })
     }
 
-    // Returns: client IP, client hostname
-    pub fn recv(&mut self) -> ConResult<(String, IpAddr)> {
+    // Returns: client hostname, client IP, trust token (only for pairing packets)
+    pub fn recv(&mut self) -> ConResult<(String, IpAddr, Option<u64>)> {
         let (size, address) = self.socket.recv_from(&mut self.buffer).handle_try_again()?;
 
-        if size == HANDSHAKE_PACKET_SIZE_BYTES
+        if (size == HANDSHAKE_PACKET_SIZE_BYTES || size == PAIRING_PACKET_SIZE_BYTES)
             && &self.buffer[..ALVR_NAME.len()] == ALVR_NAME.as_bytes()
             && self.buffer[ALVR_NAME.len()..16].iter().all(|b| *b == 0)
         {
@@ -45,7 +127,15 @@ impl WelcomeSocket {
                 .trim_end_matches('\x00')
                 .to_owned();
 
-            Ok((hostname, address.ip()))
+            let token = (size == PAIRING_PACKET_SIZE_BYTES).then(|| {
+                let mut token_bytes = [0; 8];
+                token_bytes.copy_from_slice(
+                    &self.buffer[HANDSHAKE_PACKET_SIZE_BYTES..PAIRING_PACKET_SIZE_BYTES],
+                );
+                u64::from_le_bytes(token_bytes)
+            });
+
+            Ok((hostname, address.ip(), token))
         } else if &self.buffer[..16] == b"\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00ALVR"
             || &self.buffer[..5] == b"\x01ALVR"
         {
@@ -1,13 +1,88 @@
+use crate::{ab_comparison, resource_usage, statistics_sinks::StatisticsSink, STATISTICS_MANAGER};
 use alvr_common::{SlidingWindowAverage, HEAD_ID};
-use alvr_events::{EventType, GraphStatistics, NominalBitrateStats, StatisticsSummary};
-use alvr_packets::ClientStatistics;
+use alvr_events::{
+    EventType, GraphStatistics, NominalBitrateStats, StageLatencySummary, StatisticsSummary,
+    StreamSummary,
+};
+use alvr_packets::{ClientStatistics, ControlPacketKind, FrameDropReason, TrafficStream};
 use std::{
     collections::{HashMap, VecDeque},
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 const FULL_REPORT_INTERVAL: Duration = Duration::from_millis(500);
 
+// Latencies above the range are accounted in the last bucket
+const LATENCY_HISTOGRAM_BUCKET: Duration = Duration::from_micros(250);
+const LATENCY_HISTOGRAM_SIZE: usize = 1000;
+const BITRATE_HISTOGRAM_BUCKET_MBPS: f32 = 10.0;
+
+// Markers are flushed with the next graph statistics. Avoid piling up while no frame is reported
+const MAX_PENDING_MARKERS: usize = 16;
+
+const LATENCY_STAGE_NAMES: [&str; 9] = [
+    "Total",
+    "Game time",
+    "Server compositor",
+    "Encoder",
+    "Network",
+    "Decoder",
+    "Decoder queue",
+    "Client compositor",
+    "VSync queue",
+];
+
+// Used to calculate the session percentiles without storing every sample
+struct LatencyHistogram {
+    buckets: Vec<u32>,
+    sum: Duration,
+    count: u32,
+}
+
+impl LatencyHistogram {
+    fn new() -> Self {
+        Self {
+            buckets: vec![0; LATENCY_HISTOGRAM_SIZE],
+            sum: Duration::ZERO,
+            count: 0,
+        }
+    }
+
+    fn add(&mut self, latency: Duration) {
+        let index = (latency.as_nanos() / LATENCY_HISTOGRAM_BUCKET.as_nanos()) as usize;
+        self.buckets[usize::min(index, LATENCY_HISTOGRAM_SIZE - 1)] += 1;
+        self.sum += latency;
+        self.count += 1;
+    }
+
+    fn summary(&self, name: &str) -> StageLatencySummary {
+        if self.count == 0 {
+            return StageLatencySummary {
+                name: name.into(),
+                ..Default::default()
+            };
+        }
+
+        let p99_count = (self.count as f32 * 0.99).ceil() as u32;
+        let mut accumulated = 0;
+        let mut p99_index = LATENCY_HISTOGRAM_SIZE - 1;
+        for (index, count) in self.buckets.iter().enumerate() {
+            accumulated += count;
+            if accumulated >= p99_count {
+                p99_index = index;
+                break;
+            }
+        }
+
+        StageLatencySummary {
+            name: name.into(),
+            average_ms: self.sum.as_secs_f32() * 1000. / self.count as f32,
+            // Upper edge of the bucket
+            p99_ms: (p99_index + 1) as f32 * LATENCY_HISTOGRAM_BUCKET.as_secs_f32() * 1000.,
+        }
+    }
+}
+
 pub struct HistoryFrame {
     target_timestamp: Duration,
     tracking_received: Instant,
@@ -15,6 +90,9 @@ pub struct HistoryFrame {
     frame_composed: Instant,
     frame_encoded: Instant,
     video_packet_bytes: usize,
+    encoder_qp: Option<f32>,
+    encoder_qp_foveation_center: Option<f32>,
+    encoder_qp_foveation_edges: Option<f32>,
     total_pipeline_latency: Duration,
 }
 
@@ -28,6 +106,9 @@ impl Default for HistoryFrame {
             frame_composed: now,
             frame_encoded: now,
             video_packet_bytes: 0,
+            encoder_qp: None,
+            encoder_qp_foveation_center: None,
+            encoder_qp_foveation_edges: None,
             total_pipeline_latency: Duration::ZERO,
         }
     }
@@ -51,12 +132,34 @@ pub struct StatisticsManager {
     video_bytes_partial_sum: usize,
     packets_lost_total: usize,
     packets_lost_partial_sum: usize,
+    // Packets discarded because of backpressure, by both the client and the server
+    packets_dropped_total: usize,
+    packets_reordered_total: usize,
+    packets_reordered_partial_sum: usize,
+    frame_drops_total: HashMap<FrameDropReason, usize>,
+    control_packets_partial_sum: HashMap<ControlPacketKind, usize>,
+    audio_underruns_total: usize,
+    audio_overruns_total: usize,
+    reprojected_frames_total: usize,
+    wifi_link_events_total: usize,
+    // Count and longest duration, per hand
+    controller_tracking_gaps: [(usize, Duration); 2],
+    // Sent and received bytes since the start of the stream
+    stream_traffic_total: HashMap<TrafficStream, [usize; 2]>,
+    encoder_qp_partial: Option<(f32, f32, f32, usize)>, // min, max, sum, count
     battery_gauges: HashMap<u64, BatteryData>,
+    steamvr_pipeline_frames: f32,
     steamvr_pipeline_latency: Duration,
     total_pipeline_latency_average: SlidingWindowAverage<Duration>,
     last_vsync_time: Instant,
     frame_interval: Duration,
     last_nominal_bitrate_stats: NominalBitrateStats,
+    stream_start_time: SystemTime,
+    stream_start_instant: Instant,
+    latency_histograms: Vec<LatencyHistogram>,
+    bitrate_histogram: Vec<u32>,
+    pending_markers: Vec<String>,
+    sinks: Vec<Box<dyn StatisticsSink>>,
 }
 
 impl StatisticsManager {
@@ -78,7 +181,20 @@ impl StatisticsManager {
             video_bytes_partial_sum: 0,
             packets_lost_total: 0,
             packets_lost_partial_sum: 0,
+            packets_dropped_total: 0,
+            packets_reordered_total: 0,
+            packets_reordered_partial_sum: 0,
+            frame_drops_total: HashMap::new(),
+            control_packets_partial_sum: HashMap::new(),
+            audio_underruns_total: 0,
+            audio_overruns_total: 0,
+            reprojected_frames_total: 0,
+            wifi_link_events_total: 0,
+            controller_tracking_gaps: [(0, Duration::ZERO); 2],
+            stream_traffic_total: HashMap::new(),
+            encoder_qp_partial: None,
             battery_gauges: HashMap::new(),
+            steamvr_pipeline_frames,
             steamvr_pipeline_latency: Duration::from_secs_f32(
                 steamvr_pipeline_frames * nominal_server_frame_interval.as_secs_f32(),
             ),
@@ -89,9 +205,75 @@ impl StatisticsManager {
             last_vsync_time: Instant::now(),
             frame_interval: nominal_server_frame_interval,
             last_nominal_bitrate_stats: NominalBitrateStats::default(),
+            stream_start_time: SystemTime::now(),
+            stream_start_instant: Instant::now(),
+            latency_histograms: LATENCY_STAGE_NAMES
+                .iter()
+                .map(|_| LatencyHistogram::new())
+                .collect(),
+            bitrate_histogram: vec![],
+            pending_markers: vec![],
+            sinks: vec![],
         }
     }
 
+    pub fn register_sink(&mut self, sink: Box<dyn StatisticsSink>) {
+        self.sinks.push(sink);
+    }
+
+    // Called when the stream ends, closes files and sockets
+    pub fn unregister_sinks(&mut self) {
+        self.sinks.clear();
+    }
+
+    pub fn nominal_server_frame_interval(&self) -> Duration {
+        self.frame_interval
+    }
+
+    pub fn set_nominal_server_frame_interval(&mut self, interval: Duration) {
+        self.frame_interval = interval;
+        self.steamvr_pipeline_latency =
+            Duration::from_secs_f32(self.steamvr_pipeline_frames * interval.as_secs_f32());
+    }
+
+    pub fn set_steamvr_pipeline_frames(&mut self, frames: f32) {
+        self.steamvr_pipeline_frames = frames;
+        self.steamvr_pipeline_latency =
+            Duration::from_secs_f32(frames * self.frame_interval.as_secs_f32());
+    }
+
+    pub fn report_controller_tracking_gap(&mut self, hand_index: usize, gap: Duration) {
+        let (count, longest) = &mut self.controller_tracking_gaps[hand_index];
+        *count += 1;
+        *longest = Duration::max(*longest, gap);
+    }
+
+    pub fn report_stream_traffic(
+        &mut self,
+        stream: TrafficStream,
+        sent_bytes: usize,
+        received_bytes: usize,
+    ) {
+        let [sent, received] = self.stream_traffic_total.entry(stream).or_default();
+        *sent += sent_bytes;
+        *received += received_bytes;
+    }
+
+    fn stream_traffic_mbytes(&self) -> Vec<(TrafficStream, [f32; 2])> {
+        TrafficStream::ALL
+            .iter()
+            .map(|stream| {
+                let bytes = self
+                    .stream_traffic_total
+                    .get(stream)
+                    .copied()
+                    .unwrap_or_default();
+
+                (*stream, bytes.map(|bytes| bytes as f32 / 1e6))
+            })
+            .collect()
+    }
+
     pub fn report_tracking_received(&mut self, target_timestamp: Duration) {
         if !self
             .history_buffer
@@ -164,11 +346,43 @@ impl StatisticsManager {
         }
     }
 
+    // Not all encoders report the quantization parameter, and only some report it per block.
+    // Encoders don't report the bits spent per region, the QP is the closest proxy
+    pub fn report_frame_encoder_qp(
+        &mut self,
+        target_timestamp: Duration,
+        average_qp: f32,
+        foveation_center_qp: Option<f32>,
+        foveation_edges_qp: Option<f32>,
+    ) {
+        let (min, max, sum, count) =
+            self.encoder_qp_partial
+                .get_or_insert((f32::MAX, f32::MIN, 0.0, 0));
+        *min = f32::min(*min, average_qp);
+        *max = f32::max(*max, average_qp);
+        *sum += average_qp;
+        *count += 1;
+
+        if let Some(frame) = self
+            .history_buffer
+            .iter_mut()
+            .find(|frame| frame.target_timestamp == target_timestamp)
+        {
+            frame.encoder_qp = Some(average_qp);
+            frame.encoder_qp_foveation_center = foveation_center_qp;
+            frame.encoder_qp_foveation_edges = foveation_edges_qp;
+        }
+    }
+
     pub fn report_packet_loss(&mut self) {
         self.packets_lost_total += 1;
         self.packets_lost_partial_sum += 1;
     }
 
+    pub fn report_dropped_packets(&mut self, count: usize) {
+        self.packets_dropped_total += count;
+    }
+
     pub fn report_battery(&mut self, device_id: u64, gauge_value: f32, is_plugged: bool) {
         *self.battery_gauges.entry(device_id).or_default() = BatteryData {
             gauge_value,
@@ -180,9 +394,41 @@ impl StatisticsManager {
         self.last_nominal_bitrate_stats = stats;
     }
 
+    pub fn report_marker(&mut self, label: String) {
+        if self.pending_markers.len() < MAX_PENDING_MARKERS {
+            self.pending_markers.push(label);
+        }
+    }
+
     // Called every frame. Some statistics are reported once every frame
     // Returns network latency
     pub fn report_statistics(&mut self, client_stats: ClientStatistics) -> Duration {
+        // Audio glitches and reprojected frames are reported as increments and must be accounted
+        // even if the frame is not found
+        self.audio_underruns_total += client_stats.audio_underruns as usize;
+        self.audio_overruns_total += client_stats.audio_overruns as usize;
+        self.reprojected_frames_total += client_stats.reprojected_frames as usize;
+        self.wifi_link_events_total += client_stats.wifi_link_events as usize;
+        self.packets_dropped_total += client_stats.dropped_packets as usize;
+        self.packets_reordered_total += client_stats.reordered_packets as usize;
+        self.packets_reordered_partial_sum += client_stats.reordered_packets as usize;
+        for (reason, count) in &client_stats.frame_drops {
+            *self.frame_drops_total.entry(*reason).or_default() += *count as usize;
+        }
+        ab_comparison::report_frame_drops(
+            client_stats
+                .frame_drops
+                .iter()
+                .map(|(_, count)| *count as usize)
+                .sum(),
+        );
+        for (kind, count) in &client_stats.control_packets_sent {
+            *self.control_packets_partial_sum.entry(*kind).or_default() += *count as usize;
+        }
+        if client_stats.wifi_link_events > 0 {
+            self.report_marker("Wi-Fi link event".into());
+        }
+
         if let Some(frame) = self
             .history_buffer
             .iter_mut()
@@ -218,6 +464,20 @@ impl StatisticsManager {
                     + client_stats.vsync_queue,
             );
 
+            for (histogram, latency) in self.latency_histograms.iter_mut().zip([
+                client_stats.total_pipeline_latency,
+                game_time_latency,
+                server_compositor_latency,
+                encoder_latency,
+                network_latency,
+                client_stats.video_decode,
+                client_stats.video_decoder_queue,
+                client_stats.rendering,
+                client_stats.vsync_queue,
+            ]) {
+                histogram.add(latency);
+            }
+
             let client_fps = 1.0
                 / client_stats
                     .frame_interval
@@ -234,21 +494,79 @@ impl StatisticsManager {
 
                 let interval_secs = FULL_REPORT_INTERVAL.as_secs_f32();
 
+                let encoder_qp = self
+                    .encoder_qp_partial
+                    .take()
+                    .map(|(min, max, sum, count)| (min, sum / count as f32, max));
+
+                let video_mbits_per_sec =
+                    self.video_bytes_partial_sum as f32 * 8. / 1e6 / interval_secs;
+                let bitrate_bucket = (video_mbits_per_sec / BITRATE_HISTOGRAM_BUCKET_MBPS) as usize;
+                if self.bitrate_histogram.len() <= bitrate_bucket {
+                    self.bitrate_histogram.resize(bitrate_bucket + 1, 0);
+                }
+                self.bitrate_histogram[bitrate_bucket] += 1;
+
                 alvr_events::send_event(EventType::StatisticsSummary(StatisticsSummary {
                     video_packets_total: self.video_packets_total,
                     video_packets_per_sec: (self.video_packets_partial_sum as f32 / interval_secs)
                         as _,
                     video_mbytes_total: (self.video_bytes_total as f32 / 1e6) as usize,
-                    video_mbits_per_sec: self.video_bytes_partial_sum as f32 * 8.
-                        / 1e6
-                        / interval_secs,
+                    video_mbits_per_sec,
                     total_latency_ms: client_stats.total_pipeline_latency.as_secs_f32() * 1000.,
                     network_latency_ms: network_latency.as_secs_f32() * 1000.,
                     encode_latency_ms: encoder_latency.as_secs_f32() * 1000.,
                     decode_latency_ms: client_stats.video_decode.as_secs_f32() * 1000.,
+                    decode_concurrency: client_stats.video_decode_concurrency,
                     packets_lost_total: self.packets_lost_total,
                     packets_lost_per_sec: (self.packets_lost_partial_sum as f32 / interval_secs)
                         as _,
+                    packets_dropped_total: self.packets_dropped_total,
+                    packets_reordered_total: self.packets_reordered_total,
+                    packets_reordered_per_sec: (self.packets_reordered_partial_sum as f32
+                        / interval_secs) as _,
+                    frame_drops_total: FrameDropReason::ALL
+                        .iter()
+                        .map(|reason| {
+                            (
+                                *reason,
+                                self.frame_drops_total
+                                    .get(reason)
+                                    .copied()
+                                    .unwrap_or_default(),
+                            )
+                        })
+                        .collect(),
+                    control_packets_per_sec: ControlPacketKind::ALL
+                        .iter()
+                        .map(|kind| {
+                            (
+                                *kind,
+                                self.control_packets_partial_sum
+                                    .get(kind)
+                                    .copied()
+                                    .unwrap_or_default() as f32
+                                    / interval_secs,
+                            )
+                        })
+                        .collect(),
+                    audio_underruns_total: self.audio_underruns_total,
+                    audio_overruns_total: self.audio_overruns_total,
+                    client_audio_buffering_ms: client_stats
+                        .audio_buffering
+                        .map(|buffering| buffering.as_secs_f32() * 1000.),
+                    reprojected_frames_total: self.reprojected_frames_total,
+                    wifi_link_events_total: self.wifi_link_events_total,
+                    stream_traffic_mbytes_total: self.stream_traffic_mbytes(),
+                    controller_tracking_gaps_total: self
+                        .controller_tracking_gaps
+                        .map(|(count, _)| count),
+                    controller_longest_tracking_gap_ms: self
+                        .controller_tracking_gaps
+                        .map(|(_, longest)| longest.as_secs_f32() * 1000.),
+                    tracking_send_jitter_ms: client_stats.tracking_send_jitter.as_secs_f32()
+                        * 1000.,
+                    haptics_latency_ms: client_stats.haptics_delivery_latency.as_secs_f32() * 1000.,
                     client_fps: client_fps as _,
                     server_fps: server_fps as _,
                     battery_hmd: (self
@@ -264,11 +582,20 @@ impl StatisticsManager {
                         .cloned()
                         .unwrap_or_default()
                         .is_plugged,
+                    client_thermal_status: client_stats.thermal_status,
+                    motion_to_photon_ms: client_stats
+                        .motion_to_photon_latency
+                        .map(|latency| latency.as_secs_f32() * 1000.),
+                    encoder_qp_min: encoder_qp.map(|(min, _, _)| min),
+                    encoder_qp_avg: encoder_qp.map(|(_, avg, _)| avg),
+                    encoder_qp_max: encoder_qp.map(|(_, _, max)| max),
                 }));
 
                 self.video_packets_partial_sum = 0;
                 self.video_bytes_partial_sum = 0;
                 self.packets_lost_partial_sum = 0;
+                self.packets_reordered_partial_sum = 0;
+                self.control_packets_partial_sum.clear();
             }
 
             // While not accurate, this prevents NaNs and zeros that would cause a crash or pollute
@@ -279,9 +606,11 @@ impl StatisticsManager {
                 0.0
             };
 
+            let resource_usage = resource_usage::last_sample();
+
             // todo: use target timestamp in nanoseconds. the dashboard needs to use the first
             // timestamp as the graph time origin.
-            alvr_events::send_event(EventType::GraphStatistics(GraphStatistics {
+            let graph_statistics = GraphStatistics {
                 total_pipeline_latency_s: client_stats.total_pipeline_latency.as_secs_f32(),
                 game_time_s: game_time_latency.as_secs_f32(),
                 server_compositor_s: server_compositor_latency.as_secs_f32(),
@@ -295,7 +624,32 @@ impl StatisticsManager {
                 server_fps,
                 nominal_bitrate: self.last_nominal_bitrate_stats.clone(),
                 actual_bitrate_bps: bitrate_bps,
-            }));
+                tracking_send_jitter_s: client_stats.tracking_send_jitter.as_secs_f32(),
+                haptics_latency_s: client_stats.haptics_delivery_latency.as_secs_f32(),
+                audio_underruns: client_stats.audio_underruns,
+                audio_overruns: client_stats.audio_overruns,
+                client_reprojected_frames: client_stats.reprojected_frames,
+                client_wifi_link_events: client_stats.wifi_link_events,
+                server_cpu_load: resource_usage.cpu_load,
+                steamvr_cpu_load: resource_usage.steamvr_cpu_load,
+                game_cpu_load: resource_usage.game_cpu_load,
+                render_gpu_load: resource_usage.render_gpu_load,
+                encoder_gpu_load: resource_usage.encoder_gpu_load,
+                client_thermal_status: client_stats.thermal_status,
+                client_cpu_frequency_ratio: client_stats.cpu_frequency_ratio,
+                client_decoder_utilization: client_stats.decoder_utilization,
+                motion_to_photon_s: client_stats
+                    .motion_to_photon_latency
+                    .map(|latency| latency.as_secs_f32()),
+                encoder_qp: frame.encoder_qp,
+                encoder_qp_foveation_center: frame.encoder_qp_foveation_center,
+                encoder_qp_foveation_edges: frame.encoder_qp_foveation_edges,
+                markers: std::mem::take(&mut self.pending_markers),
+            };
+            ab_comparison::report_frame(&graph_statistics);
+            for sink in &mut self.sinks {
+                sink.report(&graph_statistics);
+            }
 
             network_latency
         } else {
@@ -303,6 +657,34 @@ impl StatisticsManager {
         }
     }
 
+    // Stream metadata is filled by the caller
+    pub fn stream_summary(&self, disconnect_reason: String) -> StreamSummary {
+        let duration_s = self.stream_start_instant.elapsed().as_secs_f32();
+
+        StreamSummary {
+            start_unix_secs: self
+                .stream_start_time
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            duration_s,
+            latency_stages: LATENCY_STAGE_NAMES
+                .iter()
+                .zip(&self.latency_histograms)
+                .map(|(name, histogram)| histogram.summary(name))
+                .collect(),
+            video_packets_total: self.video_packets_total,
+            packets_lost_total: self.packets_lost_total,
+            reprojected_frames_total: self.reprojected_frames_total,
+            average_bitrate_mbps: self.video_bytes_total as f32 * 8. / 1e6 / duration_s.max(1.),
+            bitrate_histogram_bucket_mbps: BITRATE_HISTOGRAM_BUCKET_MBPS,
+            bitrate_histogram: self.bitrate_histogram.clone(),
+            traffic_mbytes: self.stream_traffic_mbytes(),
+            disconnect_reason,
+            ..Default::default()
+        }
+    }
+
     pub fn tracker_pose_time_offset(&self) -> Duration {
         // This is the opposite of the client's StatisticsManager::tracker_prediction_offset().
         self.steamvr_pipeline_latency
@@ -321,3 +703,11 @@ impl StatisticsManager {
         (self.last_vsync_time + self.frame_interval).saturating_duration_since(now)
     }
 }
+
+// Annotate the dashboard graphs with an event that may explain a change in the statistics. Must not
+// be called while STATISTICS_MANAGER is locked
+pub fn report_marker(label: impl Into<String>) {
+    if let Some(stats) = &mut *STATISTICS_MANAGER.lock() {
+        stats.report_marker(label.into());
+    }
+}
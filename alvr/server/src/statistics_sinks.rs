@@ -0,0 +1,328 @@
+// Destinations of the statistics computed for every frame by the StatisticsManager. Sinks are
+// created at the start of every stream from the settings and dropped when the stream ends. They
+// are called with the statistics manager locked, so they must not block.
+
+use crate::FILESYSTEM_LAYOUT;
+use alvr_common::{
+    error, info, once_cell::sync::Lazy, parking_lot::Mutex, settings_schema::Switch, warn,
+};
+use alvr_events::{EventType, GraphStatistics};
+use alvr_session::{InfluxDbConfig, StatisticsSinksConfig};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs::File,
+    io::{BufWriter, Write},
+    net::UdpSocket,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+pub trait StatisticsSink: Send {
+    fn report(&mut self, statistics: &GraphStatistics);
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ValueKind {
+    Gauge,
+    // Count of events since the previous frame
+    Increment,
+}
+
+// Numeric values shared by the sinks other than the dashboard. Missing values are reported as None
+fn numeric_values(statistics: &GraphStatistics) -> [(&'static str, ValueKind, Option<f32>); 28] {
+    use ValueKind::*;
+
+    [
+        (
+            "total_pipeline_latency_s",
+            Gauge,
+            Some(statistics.total_pipeline_latency_s),
+        ),
+        ("game_time_s", Gauge, Some(statistics.game_time_s)),
+        (
+            "server_compositor_s",
+            Gauge,
+            Some(statistics.server_compositor_s),
+        ),
+        ("encoder_s", Gauge, Some(statistics.encoder_s)),
+        ("network_s", Gauge, Some(statistics.network_s)),
+        ("decoder_s", Gauge, Some(statistics.decoder_s)),
+        ("decoder_queue_s", Gauge, Some(statistics.decoder_queue_s)),
+        (
+            "client_compositor_s",
+            Gauge,
+            Some(statistics.client_compositor_s),
+        ),
+        ("vsync_queue_s", Gauge, Some(statistics.vsync_queue_s)),
+        ("client_fps", Gauge, Some(statistics.client_fps)),
+        ("server_fps", Gauge, Some(statistics.server_fps)),
+        (
+            "requested_bitrate_bps",
+            Gauge,
+            Some(statistics.nominal_bitrate.requested_bps),
+        ),
+        (
+            "actual_bitrate_bps",
+            Gauge,
+            Some(statistics.actual_bitrate_bps),
+        ),
+        (
+            "tracking_send_jitter_s",
+            Gauge,
+            Some(statistics.tracking_send_jitter_s),
+        ),
+        (
+            "haptics_latency_s",
+            Gauge,
+            Some(statistics.haptics_latency_s),
+        ),
+        (
+            "audio_underruns",
+            Increment,
+            Some(statistics.audio_underruns as f32),
+        ),
+        (
+            "audio_overruns",
+            Increment,
+            Some(statistics.audio_overruns as f32),
+        ),
+        (
+            "client_reprojected_frames",
+            Increment,
+            Some(statistics.client_reprojected_frames as f32),
+        ),
+        (
+            "client_wifi_link_events",
+            Increment,
+            Some(statistics.client_wifi_link_events as f32),
+        ),
+        ("server_cpu_load", Gauge, Some(statistics.server_cpu_load)),
+        ("steamvr_cpu_load", Gauge, Some(statistics.steamvr_cpu_load)),
+        ("game_cpu_load", Gauge, statistics.game_cpu_load),
+        ("render_gpu_load", Gauge, statistics.render_gpu_load),
+        ("encoder_gpu_load", Gauge, statistics.encoder_gpu_load),
+        (
+            "client_cpu_frequency_ratio",
+            Gauge,
+            statistics.client_cpu_frequency_ratio,
+        ),
+        (
+            "client_decoder_utilization",
+            Gauge,
+            Some(statistics.client_decoder_utilization),
+        ),
+        ("motion_to_photon_s", Gauge, statistics.motion_to_photon_s),
+        ("encoder_qp", Gauge, statistics.encoder_qp),
+    ]
+}
+
+// Feeds the graphs of the dashboard and of the web API event stream
+struct DashboardGraphSink;
+
+impl StatisticsSink for DashboardGraphSink {
+    fn report(&mut self, statistics: &GraphStatistics) {
+        alvr_events::send_event(EventType::GraphStatistics(statistics.clone()));
+    }
+}
+
+struct CsvRecorderSink {
+    file: BufWriter<File>,
+    begin_instant: Instant,
+}
+
+impl CsvRecorderSink {
+    fn new() -> Option<Self> {
+        let path = FILESYSTEM_LAYOUT.log_dir.join(format!(
+            "statistics.{}.csv",
+            chrono::Local::now().format("%F.%H-%M-%S")
+        ));
+
+        let mut file = match File::create(&path) {
+            Ok(file) => BufWriter::new(file),
+            Err(e) => {
+                error!("Failed to record statistics on disk: {e}");
+                return None;
+            }
+        };
+
+        let mut header = "time_s".to_owned();
+        for (name, ..) in numeric_values(&GraphStatistics::default()) {
+            write!(header, ",{name}").ok();
+        }
+        header.push_str(",markers");
+        writeln!(file, "{header}").ok()?;
+
+        info!("Recording statistics to {}", path.display());
+
+        Some(Self {
+            file,
+            begin_instant: Instant::now(),
+        })
+    }
+}
+
+impl StatisticsSink for CsvRecorderSink {
+    fn report(&mut self, statistics: &GraphStatistics) {
+        let mut row = format!("{:.4}", self.begin_instant.elapsed().as_secs_f32());
+        for (.., value) in numeric_values(statistics) {
+            row.push(',');
+            if let Some(value) = value {
+                write!(row, "{value}").ok();
+            }
+        }
+        write!(
+            row,
+            ",\"{}\"",
+            statistics.markers.join("; ").replace('"', "\"\"")
+        )
+        .ok();
+
+        writeln!(self.file, "{row}").ok();
+    }
+}
+
+#[derive(Default)]
+struct PrometheusMetrics {
+    gauges: Vec<(&'static str, f32)>,
+    counters: BTreeMap<&'static str, f64>,
+}
+
+// Written by the sink, read on every scrape of the web server endpoint
+static PROMETHEUS_METRICS: Lazy<Mutex<Option<PrometheusMetrics>>> = Lazy::new(|| Mutex::new(None));
+
+struct PrometheusSink;
+
+impl PrometheusSink {
+    fn new() -> Self {
+        *PROMETHEUS_METRICS.lock() = Some(PrometheusMetrics::default());
+
+        Self
+    }
+}
+
+impl StatisticsSink for PrometheusSink {
+    fn report(&mut self, statistics: &GraphStatistics) {
+        if let Some(metrics) = &mut *PROMETHEUS_METRICS.lock() {
+            metrics.gauges.clear();
+            for (name, kind, value) in numeric_values(statistics) {
+                match (kind, value) {
+                    (ValueKind::Gauge, Some(value)) => metrics.gauges.push((name, value)),
+                    (ValueKind::Increment, Some(value)) => {
+                        *metrics.counters.entry(name).or_default() += value as f64
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+impl Drop for PrometheusSink {
+    fn drop(&mut self) {
+        *PROMETHEUS_METRICS.lock() = None;
+    }
+}
+
+// Text exposition format. Empty while not streaming or if the exporter is disabled
+pub fn prometheus_metrics() -> String {
+    let mut text = String::new();
+
+    if let Some(metrics) = &*PROMETHEUS_METRICS.lock() {
+        for (name, value) in &metrics.gauges {
+            writeln!(text, "# TYPE alvr_{name} gauge\nalvr_{name} {value}").ok();
+        }
+        for (name, value) in &metrics.counters {
+            writeln!(
+                text,
+                "# TYPE alvr_{name}_total counter\nalvr_{name}_total {value}"
+            )
+            .ok();
+        }
+    }
+
+    text
+}
+
+// Commas, spaces and equal signs must be escaped in measurement names and tags
+fn escape_line_protocol(value: &str) -> String {
+    value
+        .replace(',', "\\,")
+        .replace(' ', "\\ ")
+        .replace('=', "\\=")
+}
+
+struct InfluxDbSink {
+    socket: UdpSocket,
+    prefix: String,
+}
+
+impl InfluxDbSink {
+    fn new(config: &InfluxDbConfig, client_hostname: &str) -> Option<Self> {
+        let socket = match UdpSocket::bind("0.0.0.0:0")
+            .and_then(|socket| socket.connect(&config.address).map(|_| socket))
+            .and_then(|socket| socket.set_nonblocking(true).map(|_| socket))
+        {
+            Ok(socket) => socket,
+            Err(e) => {
+                warn!("Failed to connect to InfluxDB at {}: {e}", config.address);
+                return None;
+            }
+        };
+
+        Some(Self {
+            socket,
+            prefix: format!(
+                "{},client={}",
+                escape_line_protocol(&config.measurement),
+                escape_line_protocol(client_hostname)
+            ),
+        })
+    }
+}
+
+impl StatisticsSink for InfluxDbSink {
+    fn report(&mut self, statistics: &GraphStatistics) {
+        let mut line = self.prefix.clone();
+        let mut separator = ' ';
+        for (name, _, value) in numeric_values(statistics) {
+            if let Some(value) = value {
+                write!(line, "{separator}{name}={value}").ok();
+                separator = ',';
+            }
+        }
+
+        let timestamp_ns = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        write!(line, " {timestamp_ns}").ok();
+
+        // Samples are dropped if the listener is unreachable or the socket buffer is full
+        self.socket.send(line.as_bytes()).ok();
+    }
+}
+
+pub fn create_sinks(
+    config: &StatisticsSinksConfig,
+    client_hostname: &str,
+) -> Vec<Box<dyn StatisticsSink>> {
+    let mut sinks: Vec<Box<dyn StatisticsSink>> = vec![Box::new(DashboardGraphSink)];
+
+    if config.csv_recorder {
+        if let Some(sink) = CsvRecorderSink::new() {
+            sinks.push(Box::new(sink));
+        }
+    }
+
+    if config.prometheus_exporter {
+        sinks.push(Box::new(PrometheusSink::new()));
+    }
+
+    if let Switch::Enabled(influxdb_config) = &config.influxdb {
+        if let Some(sink) = InfluxDbSink::new(influxdb_config, client_hostname) {
+            sinks.push(Box::new(sink));
+        }
+    }
+
+    sinks
+}
@@ -1,14 +1,17 @@
 use crate::{to_ffi_quat, FfiDeviceMotion, FfiHandSkeleton};
 use alvr_common::{
-    glam::{EulerRot, Quat, Vec3},
-    DeviceMotion, Pose, HEAD_ID, LEFT_HAND_ID, RIGHT_HAND_ID,
+    glam::{EulerRot, Quat, Vec2, Vec3},
+    DeviceMotion, Fov, Pose, HEAD_ID, LEFT_HAND_ID, RIGHT_HAND_ID,
 };
+use alvr_packets::Tracking;
 use alvr_session::{
     settings_schema::Switch, HeadsetConfig, PositionRecenteringMode, RotationRecenteringMode,
 };
 use std::{
     collections::HashMap,
     f32::consts::{FRAC_PI_2, PI},
+    ptr,
+    time::Duration,
 };
 
 const DEG_TO_RAD: f32 = PI / 180.0;
@@ -56,18 +59,46 @@ struct MotionConfig {
 }
 
 pub struct TrackingManager {
-    last_head_pose: Pose,     // client's reference space
-    recentering_origin: Pose, // client's reference space
+    last_head_pose: Pose,              // client's reference space
+    last_left_hand_pose: Option<Pose>, // client's reference space
+    recentering_origin: Pose,          // client's reference space
+    colocation_origin: Option<Pose>,   // client's reference space, overrides recentering
+    height_offset: f32,                // per-client, from the floor calibration
+    seated_height_offset: Option<f32>, // overrides the floor calibration
 }
 
 impl TrackingManager {
     pub fn new() -> TrackingManager {
         TrackingManager {
             last_head_pose: Pose::default(),
+            last_left_hand_pose: None,
             recentering_origin: Pose::default(),
+            colocation_origin: None,
+            height_offset: 0.0,
+            seated_height_offset: None,
         }
     }
 
+    pub fn last_left_hand_pose(&self) -> Option<Pose> {
+        self.last_left_hand_pose
+    }
+
+    pub fn origin(&self) -> Pose {
+        self.colocation_origin.unwrap_or(self.recentering_origin)
+    }
+
+    pub fn set_colocation_origin(&mut self, origin: Option<Pose>) {
+        self.colocation_origin = origin;
+    }
+
+    pub fn set_height_offset(&mut self, offset: f32) {
+        self.height_offset = offset;
+    }
+
+    pub fn set_seated_height_offset(&mut self, offset: Option<f32>) {
+        self.seated_height_offset = offset;
+    }
+
     pub fn recenter(
         &mut self,
         position_recentering_mode: PositionRecenteringMode,
@@ -102,12 +133,12 @@ impl TrackingManager {
     }
 
     pub fn recenter_pose(&self, pose: Pose) -> Pose {
-        let inverse_origin_orientation = self.recentering_origin.orientation.conjugate();
+        let origin = self.origin();
+        let inverse_origin_orientation = origin.orientation.conjugate();
 
         Pose {
             orientation: inverse_origin_orientation * pose.orientation,
-            position: inverse_origin_orientation
-                * (pose.position - self.recentering_origin.position),
+            position: inverse_origin_orientation * (pose.position - origin.position),
         }
     }
 
@@ -162,18 +193,21 @@ impl TrackingManager {
 
         let (left_hand_skeleton_offset, right_hand_skeleton_offset) =
             get_hand_skeleton_offsets(config);
+        let world_scale = config.world_scale;
 
         let mut transformed_motions = vec![];
         for &(device_id, mut motion) in device_motions {
             if device_id == *HEAD_ID {
                 self.last_head_pose = motion.pose;
+            } else if device_id == *LEFT_HAND_ID && !hand_skeletons_enabled[0] {
+                self.last_left_hand_pose = Some(motion.pose);
             }
 
             if let Some(config) = device_motion_configs.get(&device_id) {
                 // Recenter
                 motion.pose = self.recenter_pose(motion.pose);
 
-                let inverse_origin_orientation = self.recentering_origin.orientation.conjugate();
+                let inverse_origin_orientation = self.origin().orientation.conjugate();
                 motion.linear_velocity = inverse_origin_orientation * motion.linear_velocity;
                 motion.angular_velocity = inverse_origin_orientation * motion.angular_velocity;
 
@@ -187,6 +221,7 @@ impl TrackingManager {
                 };
                 motion.pose.orientation *= pose_offset.orientation;
                 motion.pose.position += motion.pose.orientation * pose_offset.position;
+                motion.pose.position.y += self.seated_height_offset.unwrap_or(self.height_offset);
 
                 motion.linear_velocity += motion
                     .angular_velocity
@@ -194,6 +229,10 @@ impl TrackingManager {
                 motion.angular_velocity =
                     motion.pose.orientation.conjugate() * motion.angular_velocity;
 
+                // The world looks larger when the user moves less than in the real space
+                motion.pose.position /= world_scale;
+                motion.linear_velocity /= world_scale;
+
                 fn cutoff(v: Vec3, threshold: f32) -> Vec3 {
                     if v.length_squared() > threshold * threshold {
                         v
@@ -221,6 +260,28 @@ impl TrackingManager {
 
         transformed_motions
     }
+
+    // Motions and OpenVR hand skeletons of a tracking packet, shared by live and replayed tracking
+    pub fn transform_tracking(
+        &mut self,
+        config: &HeadsetConfig,
+        tracking: &Tracking,
+    ) -> (Vec<(u64, DeviceMotion)>, [Option<[Pose; 26]>; 2]) {
+        let motions = self.transform_motions(
+            config,
+            &tracking.device_motions,
+            [
+                tracking.hand_skeletons[0].is_some(),
+                tracking.hand_skeletons[1].is_some(),
+            ],
+        );
+        let hand_skeletons = [
+            tracking.hand_skeletons[0].map(|s| to_openvr_hand_skeleton(config, *LEFT_HAND_ID, s)),
+            tracking.hand_skeletons[1].map(|s| to_openvr_hand_skeleton(config, *RIGHT_HAND_ID, s)),
+        ];
+
+        (motions, hand_skeletons)
+    }
 }
 
 pub fn to_openvr_hand_skeleton(
@@ -266,7 +327,7 @@ pub fn to_openvr_hand_skeleton(
         position: gj[1].position,
     };
 
-    [
+    let skeleton = [
         // Palm. NB: this is ignored by SteamVR
         Pose::default(),
         // Wrist
@@ -322,7 +383,13 @@ pub fn to_openvr_hand_skeleton(
         local_pose(id, gj[22], gj[23]),
         local_pose(id, gj[23], gj[24]),
         local_pose(id, gj[24], gj[25]),
-    ]
+    ];
+
+    // Hands are scaled together with the tracked positions
+    skeleton.map(|pose| Pose {
+        orientation: pose.orientation,
+        position: pose.position / config.world_scale,
+    })
 }
 
 pub fn to_ffi_motion(device_id: u64, motion: DeviceMotion) -> FfiDeviceMotion {
@@ -335,24 +402,151 @@ pub fn to_ffi_motion(device_id: u64, motion: DeviceMotion) -> FfiDeviceMotion {
     }
 }
 
+// Bones of each finger in the OpenVR skeleton, from the thumb to the pinky. The first bone of each
+// finger is attached to the wrist
+const OPENVR_FINGER_BONES: [&[usize]; 5] = [
+    &[2, 3, 4, 5],
+    &[6, 7, 8, 9, 10],
+    &[11, 12, 13, 14, 15],
+    &[16, 17, 18, 19, 20],
+    &[21, 22, 23, 24, 25],
+];
+// Sum of the joint flexion angles of a closed fist
+const THUMB_MAX_FLEXION_RAD: f32 = 1.6;
+const FINGER_MAX_FLEXION_RAD: f32 = 4.4;
+
+// Poses of the bones relative to the root
+fn openvr_model_poses(skeleton: &[Pose; 26]) -> [Pose; 26] {
+    let mut model_poses = [Pose::default(); 26];
+    model_poses[1] = skeleton[1];
+
+    for bones in OPENVR_FINGER_BONES {
+        let mut parent = model_poses[1];
+        for &bone in bones {
+            model_poses[bone] = parent * skeleton[bone];
+            parent = model_poses[bone];
+        }
+    }
+
+    model_poses
+}
+
+// Measured from the angles between consecutive finger segments, so it does not depend on the bone
+// frames of reference
+fn finger_curls(model_poses: &[Pose; 26]) -> [f32; 5] {
+    let mut curls = [0.0; 5];
+
+    for (finger, bones) in OPENVR_FINGER_BONES.iter().enumerate() {
+        let segments = bones
+            .windows(2)
+            .map(|pair| model_poses[pair[1]].position - model_poses[pair[0]].position)
+            .collect::<Vec<_>>();
+        let flexion = segments
+            .windows(2)
+            .map(|pair| pair[0].angle_between(pair[1]))
+            .filter(|angle| angle.is_finite())
+            .sum::<f32>();
+
+        let max_flexion = if finger == 0 {
+            THUMB_MAX_FLEXION_RAD
+        } else {
+            FINGER_MAX_FLEXION_RAD
+        };
+        curls[finger] = (flexion / max_flexion).clamp(0.0, 1.0);
+    }
+
+    curls
+}
+
+// Submits the transformed tracking to the driver
+pub fn set_driver_tracking(
+    target_timestamp: Duration,
+    controller_pose_time_offset: Duration,
+    motions: Vec<(u64, DeviceMotion)>,
+    hand_skeletons: [Option<[Pose; 26]>; 2],
+    controllers_tracked: bool,
+) {
+    let ffi_motions = motions
+        .into_iter()
+        .map(|(id, motion)| to_ffi_motion(id, motion))
+        .collect::<Vec<_>>();
+    let [left_hand_skeleton, right_hand_skeleton] =
+        hand_skeletons.map(|skeleton| skeleton.map(to_ffi_skeleton));
+
+    unsafe {
+        crate::SetTracking(
+            target_timestamp.as_nanos() as _,
+            controller_pose_time_offset.as_secs_f32(),
+            ffi_motions.as_ptr(),
+            ffi_motions.len() as _,
+            if let Some(skeleton) = &left_hand_skeleton {
+                skeleton
+            } else {
+                ptr::null()
+            },
+            if let Some(skeleton) = &right_hand_skeleton {
+                skeleton
+            } else {
+                ptr::null()
+            },
+            controllers_tracked.into(),
+        )
+    };
+}
+
 pub fn to_ffi_skeleton(skeleton: [Pose; 26]) -> FfiHandSkeleton {
+    let model_poses = openvr_model_poses(&skeleton);
+
+    // The auxiliary bones are the distal finger bones, relative to the root
+    let bones = skeleton
+        .into_iter()
+        .chain(
+            OPENVR_FINGER_BONES
+                .iter()
+                .map(|bones| model_poses[bones[bones.len() - 2]]),
+        )
+        .collect::<Vec<_>>();
+
     FfiHandSkeleton {
-        jointRotations: skeleton
+        jointRotations: bones
             .iter()
             .map(|j| to_ffi_quat(j.orientation))
             .collect::<Vec<_>>()
             .try_into()
             .unwrap(),
-        jointPositions: skeleton
+        jointPositions: bones
             .iter()
             .map(|j| j.position.to_array())
             .collect::<Vec<_>>()
             .try_into()
             .unwrap(),
+        fingerCurls: finger_curls(&model_poses),
     }
 }
 
 // Head and eyesmust be in the same (nt recentered) convention
+// Project a head-relative gaze on the view plane. Returns normalized coordinates with the origin at
+// the top left corner, or None if the gaze points outside of the view.
+pub fn to_view_coords(local_eye_gaze: Pose, fov: Fov) -> Option<Vec2> {
+    let direction = local_eye_gaze.orientation * -Vec3::Z;
+    if direction.z >= 0.0 {
+        return None;
+    }
+
+    let tan_x = direction.x / -direction.z;
+    let tan_y = direction.y / -direction.z;
+
+    let (tan_left, tan_right) = (fov.left.tan(), fov.right.tan());
+    let (tan_up, tan_down) = (fov.up.tan(), fov.down.tan());
+
+    let coords = Vec2::new(
+        (tan_x - tan_left) / (tan_right - tan_left),
+        (tan_up - tan_y) / (tan_up - tan_down),
+    );
+
+    (coords.cmpge(Vec2::ZERO).all() && coords.cmple(Vec2::ONE).all()).then_some(coords)
+}
+
 pub fn to_local_eyes(
     raw_global_head: Pose,
     raw_global_eyes: [Option<Pose>; 2],
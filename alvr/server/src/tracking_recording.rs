@@ -0,0 +1,166 @@
+// Recording of the raw tracking packets received from the client, and replay into the driver
+// without a client connected. Each line of a recording is a JSON-encoded RecordedTracking.
+
+use crate::{
+    connection::IS_STREAMING,
+    tracking::{self, TrackingManager},
+    FILESYSTEM_LAYOUT, SERVER_DATA_MANAGER,
+};
+use alvr_common::{
+    error, info, once_cell::sync::Lazy, parking_lot::Mutex, settings_schema::Switch, warn,
+    RelaxedAtomic,
+};
+use alvr_packets::Tracking;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+#[derive(Serialize)]
+struct RecordedTrackingRef<'a> {
+    time_offset: Duration, // since the start of the recording
+    tracking: &'a Tracking,
+}
+
+#[derive(Deserialize)]
+struct RecordedTracking {
+    time_offset: Duration,
+    tracking: Tracking,
+}
+
+struct Recording {
+    file: BufWriter<File>,
+    begin_instant: Instant,
+}
+
+static RECORDING: Lazy<Mutex<Option<Recording>>> = Lazy::new(|| Mutex::new(None));
+static IS_REPLAYING: Lazy<RelaxedAtomic> = Lazy::new(|| RelaxedAtomic::new(false));
+
+pub fn start_recording() {
+    let path = FILESYSTEM_LAYOUT.log_dir.join(format!(
+        "tracking_recording.{}.jsonl",
+        chrono::Local::now().format("%F.%H-%M-%S")
+    ));
+
+    match File::create(&path) {
+        Ok(file) => {
+            info!("Recording tracking to {}", path.display());
+
+            *RECORDING.lock() = Some(Recording {
+                file: BufWriter::new(file),
+                begin_instant: Instant::now(),
+            });
+        }
+        Err(e) => error!("Failed to record tracking on disk: {e}"),
+    }
+}
+
+pub fn stop_recording() {
+    if let Some(mut recording) = RECORDING.lock().take() {
+        recording.file.flush().ok();
+
+        info!("Tracking recording stopped");
+    }
+}
+
+// Called for every tracking packet received from the client, before any transformation
+pub fn record(tracking: &Tracking) {
+    let mut recording_lock = RECORDING.lock();
+    let Some(recording) = &mut *recording_lock else {
+        return;
+    };
+
+    let Ok(line) = serde_json::to_string(&RecordedTrackingRef {
+        time_offset: recording.begin_instant.elapsed(),
+        tracking,
+    }) else {
+        return;
+    };
+
+    if let Err(e) = writeln!(recording.file, "{line}") {
+        error!("Failed to write tracking recording: {e}");
+        *recording_lock = None;
+    }
+}
+
+pub fn stop_replay() {
+    IS_REPLAYING.set(false);
+}
+
+// Feeds a recording into the driver with the original timing. Motions go through the same
+// transformations as live tracking, using the current settings. The replay stops when a client
+// connects.
+pub fn replay(path: PathBuf) {
+    if IS_STREAMING.value() {
+        warn!("Cannot replay tracking while a client is connected");
+        return;
+    }
+    if IS_REPLAYING.value() {
+        warn!("A tracking replay is already running");
+        return;
+    }
+
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            error!("Failed to open tracking recording {}: {e}", path.display());
+            return;
+        }
+    };
+
+    info!("Replaying tracking from {}", path.display());
+    IS_REPLAYING.set(true);
+
+    thread::spawn(move || {
+        let mut tracking_manager = TrackingManager::new();
+        let begin_instant = Instant::now();
+
+        for line in BufReader::new(file).lines() {
+            if !IS_REPLAYING.value() || IS_STREAMING.value() {
+                break;
+            }
+
+            let Ok(line) = line else {
+                break;
+            };
+            let Ok(RecordedTracking {
+                time_offset,
+                tracking,
+            }) = serde_json::from_str(&line)
+            else {
+                continue;
+            };
+
+            thread::sleep(time_offset.saturating_sub(begin_instant.elapsed()));
+
+            let (motions, hand_skeletons, track_controllers) = {
+                let data_manager_lock = SERVER_DATA_MANAGER.read();
+                let config = &data_manager_lock.settings().headset;
+
+                let (motions, hand_skeletons) =
+                    tracking_manager.transform_tracking(config, &tracking);
+                let track_controllers = matches!(
+                    &config.controllers,
+                    Switch::Enabled(config) if config.tracked
+                );
+
+                (motions, hand_skeletons, track_controllers)
+            };
+
+            tracking::set_driver_tracking(
+                tracking.target_timestamp,
+                Duration::ZERO,
+                motions,
+                hand_skeletons,
+                track_controllers,
+            );
+        }
+
+        IS_REPLAYING.set(false);
+        info!("Tracking replay finished");
+    });
+}
@@ -1,35 +1,63 @@
 use crate::{
-    bindings::FfiButtonValue, connection::ClientDisconnectRequest, DECODER_CONFIG,
+    ab_comparison,
+    bindings::FfiButtonValue,
+    colocation,
+    connection::{self, ClientDisconnectRequest, PAIRING_TOKEN},
+    controller_profiles, encoder_benchmark, floor_calibration, microphone_test, mirror,
+    seated_mode, session_timer, statistics, statistics_sinks, tracking_recording, DECODER_CONFIG,
     DISCONNECT_CLIENT_NOTIFIER, FILESYSTEM_LAYOUT, SERVER_DATA_MANAGER, VIDEO_MIRROR_SENDER,
     VIDEO_RECORDING_FILE,
 };
 use alvr_common::{
     anyhow::{self, Result},
-    error, info, log, warn,
+    error, info, log, warn, ALVR_VERSION,
 };
 use alvr_events::{ButtonEvent, Event, EventType};
-use alvr_packets::{ButtonValue, ClientListAction, ServerRequest};
+use alvr_packets::{
+    ApiCaption, ApiClient, ApiClientAction, ApiClientRequest, ApiError, ApiVersion, ButtonValue,
+    ClientListAction, PathValuePair, ServerRequest, WEB_API_VERSION,
+};
 use alvr_session::ConnectionState;
 use bytes::Buf;
 use futures::SinkExt;
 use headers::HeaderMapExt;
 use hyper::{
     header::{HeaderValue, ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONTENT_TYPE},
-    service, Body, Request, Response, StatusCode,
+    service, Body, Method, Request, Response, StatusCode,
 };
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json as json;
-use std::{net::SocketAddr, thread};
+use std::{
+    net::SocketAddr,
+    thread,
+    time::{Duration, Instant},
+};
 use tokio::sync::broadcast::{self, error::RecvError};
 use tokio_tungstenite::{tungstenite::protocol, WebSocketStream};
 use tokio_util::codec::{BytesCodec, FramedRead};
 
 pub const WS_BROADCAST_CAPACITY: usize = 256;
 
+// Shows /api/mirror-frames, meant for OBS browser sources
+const MIRROR_PAGE: &str = include_str!("../resources/mirror.html");
+
 fn reply(code: StatusCode) -> Result<Response<Body>> {
     Ok(Response::builder().status(code).body(Body::empty())?)
 }
 
+fn reply_json(value: &impl Serialize) -> Result<Response<Body>> {
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(json::to_string(value)?.into())?)
+}
+
+fn reply_api_error(code: StatusCode, message: String) -> Result<Response<Body>> {
+    let mut response = reply_json(&ApiError { message })?;
+    *response.status_mut() = code;
+
+    Ok(response)
+}
+
 async fn from_request_body<T: DeserializeOwned>(request: Request<Body>) -> Result<T> {
     Ok(json::from_reader(
         hyper::body::aggregate(request).await?.reader(),
@@ -89,6 +117,231 @@ async fn websocket<T: Clone + Send + 'static>(
     }
 }
 
+fn set_values(descs: Vec<PathValuePair>) -> Result<()> {
+    let paths = descs
+        .iter()
+        .map(|desc| {
+            desc.path
+                .iter()
+                .map(|segment| format!("{segment:?}"))
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .collect::<Vec<_>>();
+
+    SERVER_DATA_MANAGER.write().set_values(descs)?;
+
+    for path in paths {
+        statistics::report_marker(format!("Setting changed: {path}"));
+    }
+
+    controller_profiles::update_emulation_mode();
+    connection::update_color_blindness_filter();
+    connection::update_world_scale();
+
+    Ok(())
+}
+
+fn update_client_list(hostname: String, action: ClientListAction) {
+    let mut data_manager = SERVER_DATA_MANAGER.write();
+    if matches!(action, ClientListAction::RemoveEntry) {
+        if let Some(entry) = data_manager.client_list().get(&hostname) {
+            if entry.connection_state != ConnectionState::Disconnected {
+                data_manager.update_client_list(
+                    hostname.clone(),
+                    ClientListAction::SetConnectionState(ConnectionState::Disconnecting {
+                        should_be_removed: true,
+                    }),
+                );
+            } else {
+                data_manager.update_client_list(hostname, action);
+            }
+        }
+    } else {
+        data_manager.update_client_list(hostname, action);
+    }
+
+    if let Some(notifier) = &*DISCONNECT_CLIENT_NOTIFIER.lock() {
+        notifier
+            .send(ClientDisconnectRequest::Disconnect(
+                "Client list changed from the dashboard".into(),
+            ))
+            .ok();
+    }
+}
+
+fn set_buttons(buttons: Vec<ButtonEvent>) {
+    for button in buttons {
+        let value = match button.value {
+            ButtonValue::Binary(value) => FfiButtonValue {
+                type_: crate::FfiButtonType_BUTTON_TYPE_BINARY,
+                __bindgen_anon_1: crate::FfiButtonValue__bindgen_ty_1 {
+                    binary: value.into(),
+                },
+            },
+
+            ButtonValue::Scalar(value) => FfiButtonValue {
+                type_: crate::FfiButtonType_BUTTON_TYPE_SCALAR,
+                __bindgen_anon_1: crate::FfiButtonValue__bindgen_ty_1 { scalar: value },
+            },
+        };
+
+        unsafe { crate::SetButton(alvr_common::hash_string(&button.path), value) };
+    }
+}
+
+// Stable API for third party tools. See the Web API wiki page. The unversioned endpoints are
+// reserved for the dashboard and can change at any release.
+async fn api_v1(
+    request: Request<Body>,
+    events_sender: broadcast::Sender<Event>,
+) -> Result<Response<Body>> {
+    let method = request.method().clone();
+    let endpoint = request
+        .uri()
+        .path()
+        .trim_start_matches("/api/v1")
+        .to_owned();
+
+    match (&method, endpoint.as_str()) {
+        (&Method::GET, "/version") => reply_json(&ApiVersion {
+            api_version: WEB_API_VERSION,
+            server_version: ALVR_VERSION.to_string(),
+        }),
+        (&Method::GET, "/session") => reply_json(SERVER_DATA_MANAGER.read().session()),
+        (&Method::POST, "/session/values") => {
+            let descs = match from_request_body::<Vec<PathValuePair>>(request).await {
+                Ok(descs) => descs,
+                Err(e) => return reply_api_error(StatusCode::BAD_REQUEST, e.to_string()),
+            };
+
+            if let Err(e) = set_values(descs) {
+                reply_api_error(StatusCode::BAD_REQUEST, e.to_string())
+            } else {
+                reply(StatusCode::OK)
+            }
+        }
+        (&Method::GET, "/clients") => {
+            let clients = SERVER_DATA_MANAGER
+                .read()
+                .client_list()
+                .iter()
+                .map(|(hostname, entry)| ApiClient {
+                    hostname: hostname.clone(),
+                    display_name: entry.display_name.clone(),
+                    ips: entry
+                        .manual_ips
+                        .iter()
+                        .chain(&entry.current_ip)
+                        .copied()
+                        .collect(),
+                    trusted: entry.trusted,
+                    connected: matches!(
+                        entry.connection_state,
+                        ConnectionState::Connected | ConnectionState::Streaming
+                    ),
+                })
+                .collect::<Vec<_>>();
+
+            reply_json(&clients)
+        }
+        (&Method::POST, "/clients") => {
+            let request = match from_request_body::<ApiClientRequest>(request).await {
+                Ok(request) => request,
+                Err(e) => return reply_api_error(StatusCode::BAD_REQUEST, e.to_string()),
+            };
+
+            if !SERVER_DATA_MANAGER
+                .read()
+                .client_list()
+                .contains_key(&request.hostname)
+            {
+                return reply_api_error(
+                    StatusCode::NOT_FOUND,
+                    format!("Unknown client {}", request.hostname),
+                );
+            }
+
+            let action = match request.action {
+                ApiClientAction::Trust => ClientListAction::Trust,
+                ApiClientAction::Remove => ClientListAction::RemoveEntry,
+            };
+            update_client_list(request.hostname, action);
+
+            reply(StatusCode::OK)
+        }
+        (&Method::POST, "/steamvr/restart") => {
+            thread::spawn(crate::restart_driver);
+
+            reply(StatusCode::OK)
+        }
+        (&Method::POST, "/steamvr/shutdown") => {
+            // This lint is bugged with extern "C"
+            #[allow(clippy::redundant_closure)]
+            thread::spawn(|| crate::shutdown_driver());
+
+            reply(StatusCode::OK)
+        }
+        (&Method::POST, "/stream/pause") => {
+            connection::set_stream_paused(true);
+
+            reply(StatusCode::OK)
+        }
+        (&Method::POST, "/stream/resume") => {
+            connection::set_stream_paused(false);
+
+            reply(StatusCode::OK)
+        }
+        (&Method::POST, "/colocation/capture") => {
+            colocation::request_capture();
+
+            reply(StatusCode::OK)
+        }
+        (&Method::POST, "/captions") => {
+            let caption = match from_request_body::<ApiCaption>(request).await {
+                Ok(caption) => caption,
+                Err(e) => return reply_api_error(StatusCode::BAD_REQUEST, e.to_string()),
+            };
+            if let Some(duration_s) = caption.duration_s {
+                if !duration_s.is_finite() || duration_s < 0.0 {
+                    return reply_api_error(
+                        StatusCode::BAD_REQUEST,
+                        format!("Invalid caption duration {duration_s}"),
+                    );
+                }
+            }
+            let duration = caption.duration_s.map(Duration::from_secs_f32);
+
+            if let Err(e) = connection::send_caption(caption.text, duration) {
+                reply_api_error(StatusCode::CONFLICT, e.to_string())
+            } else {
+                reply(StatusCode::OK)
+            }
+        }
+        (&Method::POST, "/buttons") => match from_request_body(request).await {
+            Ok(buttons) => {
+                set_buttons(buttons);
+
+                reply(StatusCode::OK)
+            }
+            Err(e) => reply_api_error(StatusCode::BAD_REQUEST, e.to_string()),
+        },
+        (&Method::GET, "/metrics") => Ok(Response::builder()
+            .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+            .body(statistics_sinks::prometheus_metrics().into())?),
+        (&Method::GET, "/events") => {
+            websocket(request, events_sender, |e| {
+                protocol::Message::Text(json::to_string(&e).unwrap())
+            })
+            .await
+        }
+        _ => reply_api_error(
+            StatusCode::NOT_FOUND,
+            format!("Unknown endpoint {method} {endpoint}"),
+        ),
+    }
+}
+
 async fn http_api(
     request: Request<Body>,
     events_sender: broadcast::Sender<Event>,
@@ -108,35 +361,18 @@ async fn http_api(
                         )));
                     }
                     ServerRequest::UpdateSession(session) => {
-                        *SERVER_DATA_MANAGER.write().session_mut() = *session
+                        *SERVER_DATA_MANAGER.write().session_mut() = *session;
+
+                        controller_profiles::update_emulation_mode();
+                        connection::update_color_blindness_filter();
+                        connection::update_world_scale();
+                        statistics::report_marker("Settings changed");
                     }
                     ServerRequest::SetValues(descs) => {
-                        SERVER_DATA_MANAGER.write().set_values(descs).ok();
+                        set_values(descs).ok();
                     }
                     ServerRequest::UpdateClientList { hostname, action } => {
-                        let mut data_manager = SERVER_DATA_MANAGER.write();
-                        if matches!(action, ClientListAction::RemoveEntry) {
-                            if let Some(entry) = data_manager.client_list().get(&hostname) {
-                                if entry.connection_state != ConnectionState::Disconnected {
-                                    data_manager.update_client_list(
-                                        hostname.clone(),
-                                        ClientListAction::SetConnectionState(
-                                            ConnectionState::Disconnecting {
-                                                should_be_removed: true,
-                                            },
-                                        ),
-                                    );
-                                } else {
-                                    data_manager.update_client_list(hostname, action);
-                                }
-                            }
-                        } else {
-                            data_manager.update_client_list(hostname, action);
-                        }
-
-                        if let Some(notifier) = &*DISCONNECT_CLIENT_NOTIFIER.lock() {
-                            notifier.send(ClientDisconnectRequest::Disconnect).ok();
-                        }
+                        update_client_list(hostname, action)
                     }
                     ServerRequest::GetAudioDevices => {
                         if let Ok(list) = SERVER_DATA_MANAGER.read().get_audio_devices_list() {
@@ -144,9 +380,19 @@ async fn http_api(
                         }
                     }
                     ServerRequest::CaptureFrame => unsafe { crate::CaptureFrame() },
-                    ServerRequest::InsertIdr => unsafe { crate::RequestIDR() },
+                    ServerRequest::InsertIdr => {
+                        statistics::report_marker("IDR requested from dashboard");
+                        unsafe { crate::RequestIDR() }
+                    }
                     ServerRequest::StartRecording => crate::create_recording_file(),
                     ServerRequest::StopRecording => *VIDEO_RECORDING_FILE.lock() = None,
+                    ServerRequest::StartAbComparison => ab_comparison::start(),
+                    ServerRequest::StopAbComparison => ab_comparison::stop(),
+                    ServerRequest::StartEncoderBenchmark => encoder_benchmark::start(),
+                    ServerRequest::StartTrackingRecording => tracking_recording::start_recording(),
+                    ServerRequest::StopTrackingRecording => tracking_recording::stop_recording(),
+                    ServerRequest::ReplayTracking(path) => tracking_recording::replay(path),
+                    ServerRequest::StopTrackingReplay => tracking_recording::stop_replay(),
                     ServerRequest::FirewallRules(action) => {
                         if alvr_server_io::firewall_rules(action).is_ok() {
                             info!("Setting firewall rules succeeded!");
@@ -177,6 +423,21 @@ async fn http_api(
                             alvr_events::send_event(EventType::DriversList(list));
                         }
                     }
+                    ServerRequest::GetStreamHistory => {
+                        alvr_events::send_event(EventType::StreamHistory(
+                            alvr_server_io::load_stream_history(
+                                &FILESYSTEM_LAYOUT.stream_history(),
+                            ),
+                        ));
+                        alvr_events::send_event(EventType::AbComparisonReports(
+                            alvr_server_io::load_ab_comparison_reports(
+                                &FILESYSTEM_LAYOUT.ab_comparison_reports(),
+                            ),
+                        ));
+                        alvr_events::send_event(EventType::EncoderBenchmarkReport(
+                            encoder_benchmark::last_report(),
+                        ));
+                    }
                     ServerRequest::RestartSteamvr => {
                         thread::spawn(crate::restart_driver);
                     }
@@ -185,6 +446,35 @@ async fn http_api(
                         #[allow(clippy::redundant_closure)]
                         thread::spawn(|| crate::shutdown_driver());
                     }
+                    ServerRequest::CaptureColocationMarker => colocation::request_capture(),
+                    ServerRequest::CalibrateFloorHeight => floor_calibration::request_capture(),
+                    ServerRequest::NudgeFloorHeight(delta) => floor_calibration::nudge(delta),
+                    ServerRequest::ToggleSeatedMode => seated_mode::toggle(),
+                    ServerRequest::PauseStream => connection::set_stream_paused(true),
+                    ServerRequest::ResumeStream => connection::set_stream_paused(false),
+                    ServerRequest::ResetSessionTimer(hostname) => session_timer::reset(&hostname),
+                    ServerRequest::ScanClientWifi => connection::request_wifi_scan(),
+                    ServerRequest::TestHaptics {
+                        device_id,
+                        duration,
+                        frequency,
+                        amplitude,
+                    } => connection::test_haptics(device_id, duration, frequency, amplitude),
+                    ServerRequest::SetMicrophoneTest(active) => microphone_test::set_active(active),
+                    ServerRequest::StartMicrophoneLoopback => microphone_test::start_loopback(),
+                    ServerRequest::CreatePairingToken => {
+                        if let Some(server_ip) = crate::sockets::local_ip() {
+                            let token = rand::random();
+                            *PAIRING_TOKEN.lock() = Some((token, Instant::now()));
+
+                            alvr_events::send_event(EventType::PairingUri(
+                                alvr_sockets::pairing_uri(server_ip, token),
+                            ));
+                        } else {
+                            error!("Failed to find the streamer IP for pairing");
+                        }
+                    }
+                    ServerRequest::ClearPairingToken => *PAIRING_TOKEN.lock() = None,
                 }
 
                 reply(StatusCode::OK)?
@@ -221,30 +511,19 @@ async fn http_api(
 
             res
         }
+        "/api/mirror-frames" => {
+            websocket(request, mirror::subscribe(), protocol::Message::Binary).await?
+        }
+        "/mirror" => Response::builder()
+            .header(CONTENT_TYPE, "text/html")
+            .body(MIRROR_PAGE.into())?,
         "/api/set-buttons" => {
-            let buttons = from_request_body::<Vec<ButtonEvent>>(request).await?;
-
-            for button in buttons {
-                let value = match button.value {
-                    ButtonValue::Binary(value) => FfiButtonValue {
-                        type_: crate::FfiButtonType_BUTTON_TYPE_BINARY,
-                        __bindgen_anon_1: crate::FfiButtonValue__bindgen_ty_1 {
-                            binary: value.into(),
-                        },
-                    },
-
-                    ButtonValue::Scalar(value) => FfiButtonValue {
-                        type_: crate::FfiButtonType_BUTTON_TYPE_SCALAR,
-                        __bindgen_anon_1: crate::FfiButtonValue__bindgen_ty_1 { scalar: value },
-                    },
-                };
-
-                unsafe { crate::SetButton(alvr_common::hash_string(&button.path), value) };
-            }
+            set_buttons(from_request_body(request).await?);
 
             reply(StatusCode::OK)?
         }
         "/api/ping" => reply(StatusCode::OK)?,
+        path if path.starts_with("/api/v1/") => api_v1(request, events_sender).await?,
         other_uri => {
             if other_uri.contains("..") {
                 // Attempted tree traversal
@@ -1,10 +1,12 @@
 mod firewall;
 mod openvr_drivers;
 mod openvrpaths;
+mod stream_history;
 
 pub use firewall::*;
 pub use openvr_drivers::*;
 pub use openvrpaths::*;
+pub use stream_history::*;
 
 use alvr_common::{
     anyhow::{bail, Result},
@@ -12,7 +14,9 @@ use alvr_common::{
 };
 use alvr_events::EventType;
 use alvr_packets::{AudioDevicesList, ClientListAction, GpuVendor, PathSegment, PathValuePair};
-use alvr_session::{ClientConnectionConfig, ConnectionState, SessionConfig, Settings};
+use alvr_session::{
+    ClientCalibration, ClientConnectionConfig, ConnectionState, SessionConfig, Settings,
+};
 use cpal::traits::{DeviceTrait, HostTrait};
 use serde_json as json;
 use std::{
@@ -74,6 +78,9 @@ impl ServerDataManager {
         let config_dir = session_path.parent().unwrap();
         fs::create_dir_all(config_dir).ok();
         let session_desc = Self::load_session(session_path, config_dir);
+        for hostname in session_desc.client_connections.keys() {
+            alvr_common::register_sensitive_name(hostname);
+        }
 
         let vk_adapters: Vec<wgpu::Adapter> = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::VULKAN,
@@ -212,12 +219,19 @@ impl ServerDataManager {
                 manual_ips,
             } => {
                 if let Entry::Vacant(new_entry) = maybe_client_entry {
+                    alvr_common::register_sensitive_name(new_entry.key());
+
                     let client_connection_desc = ClientConnectionConfig {
                         display_name: "Unknown".into(),
                         current_ip: None,
                         manual_ips: manual_ips.into_iter().collect(),
                         trusted,
                         connection_state: ConnectionState::Disconnected,
+                        session_time_limit_min: None,
+                        max_bitrate_mbps: None,
+                        calibration: ClientCalibration::default(),
+                        hardware_info: None,
+                        rejected_decoder_options: vec![],
                     };
                     new_entry.insert(client_connection_desc);
 
@@ -238,6 +252,43 @@ impl ServerDataManager {
                     updated = true;
                 }
             }
+            ClientListAction::SetSessionTimeLimit(limit_min) => {
+                if let Entry::Occupied(mut entry) = maybe_client_entry {
+                    entry.get_mut().session_time_limit_min = limit_min;
+
+                    updated = true;
+                }
+            }
+            ClientListAction::SetMaxBitrate(bitrate_mbps) => {
+                if let Entry::Occupied(mut entry) = maybe_client_entry {
+                    entry.get_mut().max_bitrate_mbps = bitrate_mbps;
+
+                    updated = true;
+                }
+            }
+            ClientListAction::SetCalibration(calibration) => {
+                if let Entry::Occupied(mut entry) = maybe_client_entry {
+                    entry.get_mut().calibration = calibration;
+
+                    updated = true;
+                }
+            }
+            ClientListAction::SetHardwareInfo(info) => {
+                if let Entry::Occupied(mut entry) = maybe_client_entry {
+                    entry.get_mut().hardware_info = Some(info);
+
+                    updated = true;
+                }
+            }
+            ClientListAction::SetRejectedDecoderOptions(keys) => {
+                if let Entry::Occupied(mut entry) = maybe_client_entry {
+                    if entry.get().rejected_decoder_options != keys {
+                        entry.get_mut().rejected_decoder_options = keys;
+
+                        updated = true;
+                    }
+                }
+            }
             ClientListAction::SetManualIps(ips) => {
                 if let Entry::Occupied(mut entry) = maybe_client_entry {
                     entry.get_mut().manual_ips = ips.into_iter().collect();
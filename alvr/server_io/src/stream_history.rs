@@ -0,0 +1,62 @@
+use alvr_common::anyhow::Result;
+use alvr_events::{AbComparisonReport, EncoderBenchmarkReport, StreamSummary};
+use serde_json as json;
+use std::{fs, path::Path};
+
+pub const STREAM_HISTORY_MAX_SIZE: usize = 20;
+pub const AB_COMPARISON_REPORTS_MAX_SIZE: usize = 20;
+
+// Oldest first. A missing or corrupted file results in an empty history
+pub fn load_stream_history(path: &Path) -> Vec<StreamSummary> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|text| json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn append_stream_summary(path: &Path, summary: StreamSummary) -> Result<Vec<StreamSummary>> {
+    let mut history = load_stream_history(path);
+    history.push(summary);
+    if history.len() > STREAM_HISTORY_MAX_SIZE {
+        history.drain(0..history.len() - STREAM_HISTORY_MAX_SIZE);
+    }
+
+    fs::write(path, json::to_string_pretty(&history)?)?;
+
+    Ok(history)
+}
+
+pub fn load_ab_comparison_reports(path: &Path) -> Vec<AbComparisonReport> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|text| json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+// Only the last benchmark is kept
+pub fn load_encoder_benchmark_report(path: &Path) -> Option<EncoderBenchmarkReport> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|text| json::from_str(&text).ok())
+}
+
+pub fn save_encoder_benchmark_report(path: &Path, report: &EncoderBenchmarkReport) -> Result<()> {
+    fs::write(path, json::to_string_pretty(report)?)?;
+
+    Ok(())
+}
+
+pub fn append_ab_comparison_report(
+    path: &Path,
+    report: AbComparisonReport,
+) -> Result<Vec<AbComparisonReport>> {
+    let mut reports = load_ab_comparison_reports(path);
+    reports.push(report);
+    if reports.len() > AB_COMPARISON_REPORTS_MAX_SIZE {
+        reports.drain(0..reports.len() - AB_COMPARISON_REPORTS_MAX_SIZE);
+    }
+
+    fs::write(path, json::to_string_pretty(&reports)?)?;
+
+    Ok(reports)
+}
@@ -0,0 +1,66 @@
+// Registry of in-development features. Experiments are opted into by name in the session, the
+// client advertises the ones it knows during the handshake and the streamer enables only those
+// known by both sides. Gated code checks is_enabled() at runtime, on both the client and streamer.
+
+use alvr_common::{once_cell::sync::Lazy, parking_lot::RwLock, warn};
+use std::collections::BTreeSet;
+
+pub struct Experiment {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+// Entries are added together with the code they gate, and removed when the feature becomes a
+// regular setting
+pub const EXPERIMENTS: &[Experiment] = &[];
+
+static ENABLED_EXPERIMENTS: Lazy<RwLock<BTreeSet<String>>> =
+    Lazy::new(|| RwLock::new(BTreeSet::new()));
+
+fn is_known(name: &str) -> bool {
+    EXPERIMENTS.iter().any(|experiment| experiment.name == name)
+}
+
+pub fn supported_experiments() -> Vec<String> {
+    EXPERIMENTS
+        .iter()
+        .map(|experiment| experiment.name.to_owned())
+        .collect()
+}
+
+// Experiments requested in the session that both sides know about
+pub fn negotiate(requested: &[String], peer_supported: &[String]) -> Vec<String> {
+    requested
+        .iter()
+        .filter(|name| {
+            if !is_known(name) {
+                warn!("Unknown experiment \"{name}\", ignored");
+                false
+            } else if !peer_supported.contains(name) {
+                warn!("Experiment \"{name}\" is not supported by the client, ignored");
+                false
+            } else {
+                true
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+// Called at the start of every stream
+pub fn set_enabled(names: &[String]) {
+    *ENABLED_EXPERIMENTS.write() = names
+        .iter()
+        .filter(|name| is_known(name))
+        .cloned()
+        .collect();
+}
+
+pub fn is_enabled(name: &str) -> bool {
+    ENABLED_EXPERIMENTS.read().contains(name)
+}
+
+// Sorted, used to tag logs and statistics
+pub fn enabled_experiments() -> Vec<String> {
+    ENABLED_EXPERIMENTS.read().iter().cloned().collect()
+}
@@ -1,3 +1,4 @@
+pub mod experiments;
 mod settings;
 
 pub use settings::*;
@@ -6,7 +7,7 @@ pub use settings_schema;
 use alvr_common::{
     anyhow::{bail, Result},
     semver::Version,
-    ToAny, ALVR_VERSION,
+    Pose, ToAny, ALVR_VERSION,
 };
 use serde::{Deserialize, Serialize};
 use serde_json as json;
@@ -48,6 +49,8 @@ pub struct OpenvrConfig {
     pub codec: u32,
     pub refresh_rate: u32,
     pub use_10bit_encoder: bool,
+    pub slice_count: u32,
+    pub periodic_intra_refresh_frames: u32, // 0 if disabled
     pub enable_vbaq: bool,
     pub use_preproc: bool,
     pub preproc_sigma: u32,
@@ -71,6 +74,9 @@ pub struct OpenvrConfig {
     pub foveation_center_shift_y: f32,
     pub foveation_edge_ratio_x: f32,
     pub foveation_edge_ratio_y: f32,
+    pub enable_gaze_roi: bool,
+    pub gaze_roi_radius: f32,
+    pub gaze_roi_quality_boost: f32,
     pub enable_color_correction: bool,
     pub brightness: f32,
     pub contrast: f32,
@@ -109,6 +115,38 @@ pub enum ConnectionState {
     Disconnecting { should_be_removed: bool },
 }
 
+// Data measured for a specific client, restored when it connects again
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct ClientCalibration {
+    // Pose of the colocation marker in the client's reference space
+    pub playspace_alignment: Option<Pose>,
+    // Last refresh rate requested by the client, if different from the preferred FPS setting.
+    // Overrides the setting until it is reset from the dashboard
+    pub preferred_refresh_rate: Option<f32>,
+    // Vertical offset in meters between the headset floor and the SteamVR floor, applied to all
+    // tracked devices
+    #[serde(default)]
+    pub floor_height_offset: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VideoDecoderInfo {
+    pub name: String,
+    pub codecs: Vec<CodecType>,
+    // None if the platform does not tell
+    pub hardware_accelerated: Option<bool>,
+}
+
+// Reported by the client during the handshake
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct ClientHardwareInfo {
+    pub soc_model: String,
+    pub os_version: String, // includes the firmware build
+    pub runtime_name: String,
+    pub available_ram_mb: u64,
+    pub decoders: Vec<VideoDecoderInfo>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ClientConnectionConfig {
     pub display_name: String,
@@ -116,6 +154,20 @@ pub struct ClientConnectionConfig {
     pub manual_ips: HashSet<IpAddr>,
     pub trusted: bool,
     pub connection_state: ConnectionState,
+    // Overrides the default session time limit
+    #[serde(default)]
+    pub session_time_limit_min: Option<u64>,
+    // Caps the encoder bitrate for this client, whatever the bitrate mode
+    #[serde(default)]
+    pub max_bitrate_mbps: Option<u64>,
+    #[serde(default)]
+    pub calibration: ClientCalibration,
+    // From the last connection
+    #[serde(default)]
+    pub hardware_info: Option<ClientHardwareInfo>,
+    // Keys of the MediaCodec extra options that the client decoder failed to start with
+    #[serde(default)]
+    pub rejected_decoder_options: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -273,7 +325,7 @@ fn extrapolate_session_settings_from_session_settings(
                                 .iter()
                                 .any(|named_entry| variant_str == named_entry.name)
                         })
-                        .is_some()
+                        .unwrap_or(false)
                 })
                 .unwrap_or_else(|| old_session_settings["variant"].clone());
 
@@ -556,6 +608,53 @@ fn json_session_settings_to_settings(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use settings_schema::Switch;
+    use std::{env, fs};
+
+    fn test_data_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_data")
+    }
+
+    // Upgrades a full session saved by a previous version and compares the resulting settings with
+    // the committed snapshot. Run the tests with ALVR_UPDATE_SNAPSHOTS=1 to regenerate the
+    // snapshots after an intended change
+    fn check_session_snapshot(name: &str) -> Settings {
+        let session_path = test_data_dir()
+            .join("sessions")
+            .join(format!("{name}.json"));
+        let session_json =
+            json::from_str::<json::Value>(&fs::read_to_string(session_path).unwrap()).unwrap();
+
+        let mut session = SessionConfig::default();
+        session.merge_from_json(&session_json).unwrap();
+        let settings = session.to_settings();
+
+        let snapshot_path = test_data_dir()
+            .join("snapshots")
+            .join(format!("{name}.json"));
+        if env::var_os("ALVR_UPDATE_SNAPSHOTS").is_some() {
+            fs::write(&snapshot_path, json::to_string_pretty(&settings).unwrap()).unwrap();
+
+            return settings;
+        }
+
+        let Ok(snapshot_string) = fs::read_to_string(&snapshot_path) else {
+            panic!(
+                "Missing snapshot {}, run the tests with ALVR_UPDATE_SNAPSHOTS=1 to create it",
+                snapshot_path.display()
+            );
+        };
+        // Compared after a round trip through Settings, so floats have the same precision
+        let snapshot = json::from_str::<Settings>(&snapshot_string)
+            .unwrap_or_else(|e| panic!("Snapshot {} is outdated: {e}", snapshot_path.display()));
+        assert!(
+            json::to_value(&settings).unwrap() == json::to_value(&snapshot).unwrap(),
+            "Settings upgraded from session {name} differ from {}",
+            snapshot_path.display()
+        );
+
+        settings
+    }
 
     #[test]
     fn test_manual_session_to_settings() {
@@ -602,4 +701,67 @@ mod tests {
             .merge_from_json(&json::from_str(input_json_string).unwrap())
             .unwrap();
     }
+
+    #[test]
+    fn test_session_snapshot_v21_dev00() {
+        let settings = check_session_snapshot("v21.0.0-dev00");
+
+        assert_eq!(settings.video.preferred_fps, 90.0);
+        assert!(matches!(settings.video.preferred_codec, CodecType::Hevc));
+        assert!(matches!(
+            settings.video.bitrate.mode,
+            BitrateMode::ConstantMbps(50)
+        ));
+        assert!(matches!(
+            settings.video.transcoding_view_resolution,
+            FrameSize::Scale(scale) if scale == 0.75
+        ));
+        assert!(matches!(settings.headset.controllers, Switch::Disabled));
+        assert!(matches!(
+            settings.connection.stream_protocol,
+            SocketProtocol::Tcp
+        ));
+        assert_eq!(settings.connection.stream_port, 9950);
+
+        // Settings added later take their default value
+        assert_eq!(settings.headset.world_scale, 1.0);
+        assert!(matches!(
+            settings.video.color_blindness_filter,
+            Switch::Disabled
+        ));
+        assert!(settings.experiments.is_empty());
+    }
+
+    #[test]
+    fn test_session_snapshot_invalid_values() {
+        let settings = check_session_snapshot("v21.0.0-dev00-invalid");
+        let default_settings = SessionConfig::default().to_settings();
+
+        // Values with the wrong type or unknown variants are replaced by the defaults
+        assert_eq!(
+            settings.video.preferred_fps,
+            default_settings.video.preferred_fps
+        );
+        assert!(matches!(settings.video.preferred_codec, CodecType::H264));
+        assert!(matches!(
+            settings.connection.client_discovery,
+            Switch::Enabled(_)
+        ));
+
+        // Floats are truncated for integer settings
+        assert!(matches!(
+            settings.video.bitrate.mode,
+            BitrateMode::ConstantMbps(50)
+        ));
+
+        // Valid values next to them are kept
+        let Switch::Enabled(controllers) = settings.headset.controllers else {
+            panic!("Controllers should be enabled");
+        };
+        assert!(!controllers.tracked);
+        let Switch::Enabled(haptics) = controllers.haptics else {
+            panic!("Haptics should be enabled");
+        };
+        assert_eq!(haptics.intensity_multiplier, 1.0);
+    }
 }
@@ -156,7 +156,7 @@ pub struct AmfConfig {
 pub struct SoftwareEncodingConfig {
     #[schema(strings(
         display_name = "Force software encoding",
-        help = "Forces the encoder to use CPU instead of GPU"
+        help = "Forces the encoder to use CPU instead of GPU. Software encoding is also used automatically, with a warning, when no hardware encoder is available"
     ))]
     #[schema(flag = "steamvr-restart")]
     pub force_software_encoding: bool,
@@ -166,6 +166,22 @@ pub struct SoftwareEncodingConfig {
     pub thread_count: u32,
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct IntraRefreshConfig {
+    #[schema(strings(help = "Number of frames over which the whole image is refreshed"))]
+    #[schema(gui(slider(min = 10, max = 300, logarithmic)), suffix = " frames")]
+    pub period_frames: u32,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+#[schema(gui = "button_group")]
+pub enum KeyframeInterval {
+    #[schema(strings(display_name = "On demand only"))]
+    OnDemand,
+    Automatic,
+    Manual(#[schema(gui(slider(min = 0.5, max = 10.0, step = 0.5)), suffix = "s")] f32),
+}
+
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
 
 pub struct EncoderConfig {
@@ -192,6 +208,27 @@ CABAC produces better compression but it's significantly slower and may lead to
     #[schema(flag = "steamvr-restart")]
     pub use_10bit: bool,
 
+    #[schema(strings(
+        help = "Split each frame into this many slices. Headsets whose decoders work on slices in parallel can decode faster, at the cost of a slightly worse compression. On the client, the decoder is set to low latency mode when using more than one slice"
+    ))]
+    #[schema(gui(slider(min = 1, max = 8)))]
+    #[schema(flag = "steamvr-restart")]
+    pub slice_count: u32,
+
+    #[schema(strings(
+        display_name = "Periodic intra refresh",
+        help = "Refresh a rolling band of the image with intra coded blocks instead of sending full keyframes. The stream recovers from packet loss without waiting for a keyframe and without bitrate spikes, at the cost of a slightly worse compression. Not supported with VAAPI, which keeps requesting keyframes after packet loss"
+    ))]
+    #[schema(flag = "steamvr-restart")]
+    pub intra_refresh: Switch<IntraRefreshConfig>,
+
+    #[schema(strings(
+        display_name = "Periodic keyframes",
+        help = "Keyframes are normally sent only when the client needs one, so recorded or mirrored streams cannot be seeked. Automatic sends a keyframe every 2 seconds while the stream is recorded or mirrored from the dashboard. Manual always sends them at the chosen interval"
+    ))]
+    #[schema(flag = "real-time")]
+    pub keyframe_interval: KeyframeInterval,
+
     #[schema(strings(display_name = "NVENC"))]
     #[schema(flag = "steamvr-restart")]
     pub nvenc: NvencConfig,
@@ -221,6 +258,36 @@ pub struct EncoderLatencyLimiter {
     pub max_saturation_multiplier: f32,
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LatencyBudgetConfig {
+    #[schema(strings(display_name = "Target motion-to-photon latency"))]
+    #[schema(flag = "real-time")]
+    #[schema(gui(slider(min = 20, max = 120)), suffix = "ms")]
+    pub target_motion_to_photon_ms: u64,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[schema(gui = "button_group")]
+pub enum ExtraLatencyMode {
+    #[schema(strings(display_name = "Runtime default"))]
+    Default,
+    Enabled,
+    Disabled,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct ClientPresentConfig {
+    #[schema(strings(
+        help = "Let the headset runtime queue an additional frame before presenting. Enabling it makes the stream smoother on a loaded headset at the cost of one frame of latency. Ignored if the client does not support it"
+    ))]
+    pub extra_latency_mode: ExtraLatencyMode,
+
+    #[schema(strings(
+        help = "Render directly to the buffer being scanned out. This removes one frame of latency but can cause tearing. Ignored if the client does not support it"
+    ))]
+    pub front_buffer_rendering: bool,
+}
+
 #[derive(SettingsSchema, Serialize, Deserialize, Clone, PartialEq)]
 pub struct DecoderLatencyLimiter {
     #[schema(strings(
@@ -247,6 +314,16 @@ pub struct DecoderLatencyLimiter {
     pub latency_overstep_multiplier: f32,
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DecoderOverloadLimiter {
+    #[schema(strings(
+        help = "Controls how much the bitrate is reduced each time the client reports that the decoder cannot keep up"
+    ))]
+    #[schema(flag = "real-time")]
+    #[schema(gui(slider(min = 0.5, max = 1.0, step = 0.01)))]
+    pub overload_multiplier: f32,
+}
+
 #[derive(SettingsSchema, Serialize, Deserialize, Clone, PartialEq)]
 #[schema(gui = "button_group")]
 pub enum BitrateMode {
@@ -283,6 +360,12 @@ pub enum BitrateMode {
         ))]
         #[schema(flag = "real-time")]
         decoder_latency_limiter: Switch<DecoderLatencyLimiter>,
+
+        #[schema(strings(
+            help = "Reduce the bitrate when the client repeatedly drops frames because the decoder is saturated"
+        ))]
+        #[schema(flag = "real-time")]
+        decoder_overload_limiter: Switch<DecoderOverloadLimiter>,
     },
 }
 
@@ -342,6 +425,22 @@ pub struct ClientsideFoveation {
     pub vertical_offset_deg: f32,
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[schema(gui = "button_group")]
+pub enum ColorBlindnessType {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ColorBlindnessFilterConfig {
+    pub deficiency: ColorBlindnessType,
+
+    #[schema(gui(slider(min = 0.0, max = 1.0, step = 0.01)))]
+    pub strength: f32,
+}
+
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
 pub struct FoveatedRenderingConfig {
     #[schema(strings(display_name = "Center region width"))]
@@ -375,6 +474,21 @@ pub struct FoveatedRenderingConfig {
     pub edge_ratio_y: f32,
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct GazeRoiConfig {
+    #[schema(strings(help = "Radius of the boosted region, relative to the eye view width"))]
+    #[schema(gui(slider(min = 0.05, max = 0.5, step = 0.01)))]
+    #[schema(flag = "steamvr-restart")]
+    pub radius: f32,
+
+    #[schema(strings(
+        help = "How much the quantization is lowered inside the region. Higher values improve quality around the gaze point at the expense of the periphery"
+    ))]
+    #[schema(gui(slider(min = 0.0, max = 1.0, step = 0.01)))]
+    #[schema(flag = "steamvr-restart")]
+    pub quality_boost: f32,
+}
+
 #[repr(C)]
 #[derive(SettingsSchema, Clone, Copy, Serialize, Deserialize, Pod, Zeroable)]
 pub struct ColorCorrectionConfig {
@@ -399,8 +513,18 @@ pub struct ColorCorrectionConfig {
     pub sharpening: f32,
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct OverlayLayersConfig {
+    #[schema(strings(
+        display_name = "Max FPS",
+        help = "Overlays are captured at most this many times per second"
+    ))]
+    #[schema(gui(slider(min = 1.0, max = 30.0, step = 1.0)), suffix = "FPS")]
+    pub max_fps: f32,
+}
+
 #[repr(u8)]
-#[derive(SettingsSchema, Serialize, Deserialize, Debug, Copy, Clone)]
+#[derive(SettingsSchema, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
 #[schema(gui = "button_group")]
 pub enum CodecType {
     #[schema(strings(display_name = "h264"))]
@@ -415,6 +539,13 @@ pub struct VideoConfig {
     #[schema(flag = "steamvr-restart")]
     pub adapter_index: u32,
 
+    #[schema(strings(
+        display_name = "Use headset recommended resolution",
+        help = "For known headset models, use their recommended resolutions instead of the two settings below"
+    ))]
+    #[schema(flag = "steamvr-restart")]
+    pub use_headset_recommended_resolution: bool,
+
     #[schema(strings(
         help = "Resolution used for encoding and decoding. Relative to a single eye view."
     ))]
@@ -445,10 +576,44 @@ pub struct VideoConfig {
     #[schema(gui(slider(min = 0.50, max = 0.99, step = 0.01)))]
     pub buffering_history_weight: f32,
 
+    #[schema(strings(
+        help = "Derive the encoder latency limit and the client buffering from a single latency target, and keep adjusting them using the measured latency. These override the individual settings, the values in use are shown in the Statistics tab. The client buffering is applied at the next connection. The controller prediction keeps following the measured latency."
+    ))]
+    #[schema(flag = "real-time")]
+    pub latency_budget: Switch<LatencyBudgetConfig>,
+
+    #[schema(strings(
+        help = "Number of times a frame cached from a previous stream is decoded before the stream starts, to warm up the decoder. This reduces the delay before the first frame is shown on some devices"
+    ))]
+    #[schema(gui(slider(min = 0, max = 5)))]
+    pub decoder_priming_frames: u32,
+
     #[schema(strings(help = "This works only on Windows"))]
     #[schema(flag = "real-time")]
     pub optimize_game_render_latency: bool,
 
+    #[schema(strings(
+        help = "Render and encode a field of view larger than the displayed one. When a frame is late, the headset reprojects it to the latest head pose and shows the extra area instead of black borders. The resolution is increased by the same amount to keep the pixel density. Applied at the next connection."
+    ))]
+    #[schema(gui(slider(min = 1.0, max = 30.0, step = 1.0)), suffix = "%")]
+    pub reprojection_overscan: Switch<f32>,
+
+    #[schema(strings(
+        help = "Display each frame with the head pose it was rendered for, so the headset corrects for the head movement since then. Disable only to compare the stream with and without reprojection. Applied at the next connection."
+    ))]
+    pub client_reprojection: bool,
+
+    #[schema(strings(
+        help = "Present mode of the client rendering. Applied at the next connection."
+    ))]
+    pub client_present: ClientPresentConfig,
+
+    #[schema(strings(
+        help = "Daltonization filter applied by the headset when displaying the stream, to make colors easier to tell apart. It doesn't affect the encoder bitrate."
+    ))]
+    #[schema(flag = "real-time")]
+    pub color_blindness_filter: Switch<ColorBlindnessFilterConfig>,
+
     pub bitrate: BitrateConfig,
 
     #[schema(strings(
@@ -460,17 +625,30 @@ pub struct VideoConfig {
     #[schema(flag = "steamvr-restart")]
     pub encoder_config: EncoderConfig,
 
+    #[schema(flag = "dangerous")]
     pub mediacodec_extra_options: Vec<(String, MediacodecDataType)>,
 
     #[schema(flag = "steamvr-restart")]
     pub foveated_rendering: Switch<FoveatedRenderingConfig>,
 
+    #[schema(strings(
+        display_name = "Gaze region of interest",
+        help = "Increase the encoding quality around the gaze point. Used only when foveated encoding is disabled or not supported by the client, and requires eye tracking. This works only on Linux"
+    ))]
+    #[schema(flag = "steamvr-restart")]
+    pub gaze_roi: Switch<GazeRoiConfig>,
+
     pub clientside_foveation: Switch<ClientsideFoveation>,
 
     pub dynamic_oculus_foveation: bool,
 
     #[schema(flag = "steamvr-restart")]
     pub color_correction: Switch<ColorCorrectionConfig>,
+
+    #[schema(strings(
+        help = "Stream the SteamVR dashboard and the other overlays separately from the game, lossless and at a low frame rate. The headset shows them head-locked at full resolution, without foveation and compression artifacts. Applied at the next connection. This works only on Windows"
+    ))]
+    pub overlay_layers: Switch<OverlayLayersConfig>,
 }
 
 #[derive(SettingsSchema, Serialize, Deserialize, Clone, Copy)]
@@ -491,15 +669,50 @@ pub enum CustomAudioDeviceConfig {
     Index(usize),
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct AudioBufferingAutoTuneConfig {
+    #[schema(strings(display_name = "Minimum buffering"))]
+    #[schema(gui(slider(min = 0, max = 200)), suffix = "ms")]
+    pub min_buffering_ms: u64,
+
+    #[schema(strings(display_name = "Maximum buffering"))]
+    #[schema(gui(slider(min = 0, max = 500)), suffix = "ms")]
+    pub max_buffering_ms: u64,
+}
+
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
 pub struct AudioBufferingConfig {
-    #[schema(strings(display_name = "Average buffering"))]
+    #[schema(strings(
+        display_name = "Average buffering",
+        help = "When auto-tuning is enabled this is the starting value"
+    ))]
     #[schema(gui(slider(min = 0, max = 200)), suffix = "ms")]
     pub average_buffering_ms: u64,
 
     #[schema(strings(display_name = "Batch size"))]
     #[schema(gui(slider(min = 1, max = 20)), suffix = "ms")]
     pub batch_ms: u64,
+
+    #[schema(strings(
+        help = "Increase the buffering after underruns and overruns and slowly reduce it while the playback is stable"
+    ))]
+    pub auto_tune: Switch<AudioBufferingAutoTuneConfig>,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, Copy)]
+#[schema(gui = "button_group")]
+pub enum BinauralDownmixLocation {
+    Client,
+    #[schema(strings(display_name = "Streamer"))]
+    Server,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct MultichannelAudioConfig {
+    #[schema(strings(
+        help = "Downmixing on the client sends all channels over the network. The streamer is used if the client does not support it."
+    ))]
+    pub binaural_downmix_location: BinauralDownmixLocation,
 }
 
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
@@ -507,6 +720,11 @@ pub struct GameAudioConfig {
     pub device: Option<CustomAudioDeviceConfig>,
     pub mute_when_streaming: bool,
     pub buffering: AudioBufferingConfig,
+    #[schema(strings(
+        display_name = "5.1/7.1 surround",
+        help = "Capture surround audio devices and downmix them to binaural stereo, preserving positional audio"
+    ))]
+    pub multichannel: Switch<MultichannelAudioConfig>,
 }
 
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
@@ -534,6 +752,12 @@ pub enum MicrophoneDevicesConfig {
 pub struct MicrophoneConfig {
     pub devices: MicrophoneDevicesConfig,
     pub buffering: AudioBufferingConfig,
+
+    #[schema(strings(
+        help = "Microphone packets received by the streamer and not yet played. When full, the oldest packets are dropped"
+    ))]
+    #[schema(gui(slider(min = 1, max = 50)))]
+    pub max_queued_packets: usize,
 }
 
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
@@ -598,7 +822,7 @@ pub struct FaceTrackingConfig {
     pub sink: FaceTrackingSinkConfig,
 }
 
-#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
 pub enum ControllersEmulationMode {
     #[schema(strings(display_name = "Rift S Touch"))]
     RiftSTouch,
@@ -610,6 +834,35 @@ pub enum ControllersEmulationMode {
     ViveTracker,
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, Debug)]
+pub struct EmulationModeOverride {
+    #[schema(strings(help = "Name of the game executable, for example vrchat.exe"))]
+    pub executable_name: String,
+
+    pub emulation_mode: ControllersEmulationMode,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct ControllerBindingsConfig {
+    #[schema(strings(
+        display_name = "App keys",
+        help = "SteamVR app keys that use the generated bindings by default, for example steam.app.438100. Only apps using the legacy input system are affected."
+    ))]
+    pub app_keys: Vec<String>,
+
+    #[schema(strings(display_name = "Swap A/B and X/Y"))]
+    pub swap_a_b: bool,
+
+    #[schema(strings(help = "Only affects emulated controllers with a trackpad"))]
+    pub trackpad_as_joystick: bool,
+
+    #[schema(gui(slider(min = 0.05, max = 1.0, step = 0.01)))]
+    pub trigger_click_threshold: f32,
+
+    #[schema(gui(slider(min = 0.05, max = 1.0, step = 0.01)))]
+    pub grip_click_threshold: f32,
+}
+
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
 pub struct HapticsConfig {
     #[schema(flag = "real-time")]
@@ -626,6 +879,123 @@ pub struct HapticsConfig {
     pub min_duration_s: f32,
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChordAction {
+    Recenter,
+    TogglePassthrough,
+    TogglePerformanceOverlay,
+    PauseStream,
+    ToggleSeatedMode,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct ControllerChordConfig {
+    #[schema(strings(
+        help = "Input paths, for example /user/hand/left/input/thumbstick/click. The action is triggered when all buttons are held"
+    ))]
+    pub buttons: Vec<String>,
+
+    pub action: ChordAction,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[schema(gui = "button_group")]
+pub enum DesktopPointerHand {
+    Left,
+    Right,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct DesktopPointerConfig {
+    pub hand: DesktopPointerHand,
+
+    #[schema(strings(
+        help = "Controller rotation needed to move the cursor from the left to the right edge of the desktop"
+    ))]
+    #[schema(gui(slider(min = 10.0, max = 180.0, step = 1.0)), suffix = "°")]
+    pub horizontal_range_deg: f32,
+
+    #[schema(strings(
+        help = "Controller rotation needed to move the cursor from the top to the bottom edge of the desktop"
+    ))]
+    #[schema(gui(slider(min = 10.0, max = 180.0, step = 1.0)), suffix = "°")]
+    pub vertical_range_deg: f32,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HapticsSourceDevice {
+    LeftController,
+    RightController,
+    Any,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExternalHapticsDevice {
+    #[schema(strings(display_name = "bHaptics"))]
+    Bhaptics,
+    #[schema(strings(display_name = "OWO"))]
+    Owo,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, Debug)]
+pub struct HapticsForwardingRule {
+    pub source: HapticsSourceDevice,
+
+    pub target: ExternalHapticsDevice,
+
+    #[schema(gui(slider(min = 0.0, max = 5.0, step = 0.1)))]
+    pub intensity_multiplier: f32,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct ExternalHapticsConfig {
+    #[schema(strings(
+        help = "Haptic events coming from the selected source are also sent to the target accessory, which must be paired with the headset."
+    ))]
+    pub rules: Vec<HapticsForwardingRule>,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ColocationConfig {
+    #[schema(strings(
+        help = "Only streamers with the same group name share the playspace anchor"
+    ))]
+    pub group_name: String,
+
+    #[schema(strings(help = "UDP port used to exchange the anchor with the other streamers"))]
+    pub peer_port: u16,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CaptionsConfig {
+    #[schema(strings(
+        help = "Horizontal center of the caption region, from the left (0) to the right (1) edge of the view"
+    ))]
+    #[schema(gui(slider(min = 0.0, max = 1.0, step = 0.01)))]
+    pub region_center_x: f32,
+
+    #[schema(strings(
+        help = "Vertical center of the caption region, from the top (0) to the bottom (1) edge of the view"
+    ))]
+    #[schema(gui(slider(min = 0.0, max = 1.0, step = 0.01)))]
+    pub region_center_y: f32,
+
+    #[schema(strings(help = "Width of the caption region, as a fraction of the view"))]
+    #[schema(gui(slider(min = 0.1, max = 1.0, step = 0.01)))]
+    pub region_width: f32,
+
+    #[schema(strings(help = "Height of the caption region, as a fraction of the view"))]
+    #[schema(gui(slider(min = 0.05, max = 1.0, step = 0.01)))]
+    pub region_height: f32,
+
+    #[schema(gui(slider(min = 16.0, max = 96.0, step = 1.0)), suffix = "px")]
+    pub font_size: f32,
+
+    #[schema(strings(help = "Used when the caption tool doesn't specify a duration"))]
+    #[schema(gui(slider(min = 1.0, max = 30.0, step = 0.5)), suffix = "s")]
+    pub default_duration_s: f32,
+}
+
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
 pub struct ControllersConfig {
     #[schema(strings(
@@ -633,9 +1003,32 @@ pub struct ControllersConfig {
     ))]
     pub tracked: bool,
 
-    #[schema(flag = "steamvr-restart")]
+    #[schema(strings(
+        help = "Switching to or from Vive Tracker requires a SteamVR restart, other modes are applied immediately."
+    ))]
+    #[schema(flag = "real-time")]
     pub emulation_mode: ControllersEmulationMode,
 
+    #[schema(strings(
+        display_name = "Per-game emulation mode",
+        help = "The emulation mode is switched automatically while one of these games is running."
+    ))]
+    #[schema(flag = "real-time")]
+    pub per_game_emulation_mode: Vec<EmulationModeOverride>,
+
+    #[schema(strings(
+        display_name = "Per-game tuning",
+        help = "Adjust prediction, thumbstick deadzone and haptics intensity for the running game using the tuning database. User entries can be added to app_tuning.json in the config directory."
+    ))]
+    #[schema(flag = "real-time")]
+    pub per_game_tuning: bool,
+
+    #[schema(strings(
+        help = "Generate SteamVR default bindings for the emulated controllers instead of editing them in the SteamVR binding UI"
+    ))]
+    #[schema(flag = "steamvr-restart")]
+    pub custom_bindings: Switch<ControllerBindingsConfig>,
+
     #[schema(flag = "steamvr-restart")]
     pub extra_openvr_props: Vec<OpenvrPropEntry>,
 
@@ -686,6 +1079,48 @@ Currently this cannot be reliably estimated automatically. The correct value sho
 
     #[schema(flag = "real-time")]
     pub haptics: Switch<HapticsConfig>,
+
+    #[schema(strings(
+        help = "Behavior of a controller whose poses stop arriving, for example when it is asleep or out of view of the headset cameras"
+    ))]
+    #[schema(flag = "real-time")]
+    pub tracking_loss: ControllerTrackingLossConfig,
+
+    #[schema(strings(
+        display_name = "Desktop pointer",
+        help = r"Hold both thumbsticks pressed to toggle desktop pointer mode. While active, the selected controller moves the desktop mouse cursor, the trigger is the left click and the grip is the right click. These buttons are not sent to SteamVR while the mode is active.
+Only supported on Windows. Reconnect HMD to apply."
+    ))]
+    pub desktop_pointer: Switch<DesktopPointerConfig>,
+
+    #[schema(strings(
+        display_name = "Controller chords",
+        help = "Button combinations handled by the headset instead of being sent to SteamVR. The button that completes a chord is not sent to SteamVR. Reconnect HMD to apply."
+    ))]
+    pub chords: Vec<ControllerChordConfig>,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[schema(gui = "button_group")]
+pub enum TrackingLossAction {
+    #[schema(strings(display_name = "Freeze at last pose"))]
+    Freeze,
+    #[schema(strings(display_name = "Mark not tracking"))]
+    NotTracking,
+    #[schema(strings(display_name = "Hide"))]
+    Hide,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct ControllerTrackingLossConfig {
+    #[schema(strings(help = "Time without poses after which a controller is considered lost"))]
+    #[schema(gui(slider(min = 50, max = 2000, step = 50)), suffix = "ms")]
+    pub timeout_ms: u64,
+
+    #[schema(strings(
+        help = "Freeze: the controller stays valid at its last pose, without motion. Mark not tracking: SteamVR shows the controller as out of range. Hide: the controller is reported as disconnected until it is tracked again"
+    ))]
+    pub action: TrackingLossAction,
 }
 
 #[derive(SettingsSchema, Serialize, Deserialize, Clone, Copy)]
@@ -724,6 +1159,11 @@ pub struct HeadsetConfig {
     #[schema(flag = "steamvr-restart")]
     pub controllers: Switch<ControllersConfig>,
 
+    #[schema(strings(
+        help = "Forward haptics to Bluetooth accessories connected to the headset, like haptic vests. Reconnect HMD to apply."
+    ))]
+    pub external_haptics: Switch<ExternalHapticsConfig>,
+
     #[schema(strings(
         help = r#"Disabled: the playspace origin is determined by the room-scale guardian setup.
 Local floor: the origin is on the floor and resets when long pressing the oculus button.
@@ -739,6 +1179,31 @@ Tilted: the world gets tilted when long pressing the oculus button. This is usef
     ))]
     #[schema(flag = "real-time")]
     pub rotation_recentering_mode: RotationRecenteringMode,
+
+    #[schema(strings(
+        help = "Values above 1 make the world look larger, values below 1 make it look smaller. Tracked positions and the distance between the eyes are divided by this value. Can also compensate for a headset that reports a wrong IPD."
+    ))]
+    #[schema(flag = "real-time")]
+    #[schema(gui(slider(min = 0.5, max = 2.0, step = 0.01)), suffix = "x")]
+    pub world_scale: f32,
+
+    #[schema(strings(
+        help = "Vertical offset applied in seated mode instead of the floor calibration. Seated mode is toggled from the Connections tab or with a controller chord."
+    ))]
+    #[schema(flag = "real-time")]
+    #[schema(gui(slider(min = 0.0, max = 1.5, step = 0.01)), suffix = "m")]
+    pub seated_mode_height_offset: f32,
+
+    #[schema(strings(
+        help = "Align the playspace with other ALVR streamers on the same LAN, for colocated multiplayer. Each user places the left controller on the same physical marker and presses \"Align playspace\" in the Connections tab. Recentering is disabled while aligned."
+    ))]
+    #[schema(flag = "real-time")]
+    pub colocation: Switch<ColocationConfig>,
+
+    #[schema(strings(
+        help = "Shows captions sent by speech-to-text tools on the PC through the web API (POST /api/v1/captions)"
+    ))]
+    pub captions: Switch<CaptionsConfig>,
 }
 
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
@@ -750,6 +1215,14 @@ pub enum SocketProtocol {
     Tcp,
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone, Copy)]
+pub enum ClientDisconnectAction {
+    ReturnToLobby,
+    QuitApp,
+    RestartDiscovery,
+    TurnOffDisplay,
+}
+
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
 pub struct DiscoveryConfig {
     #[schema(strings(
@@ -758,39 +1231,185 @@ pub struct DiscoveryConfig {
     pub auto_trust_clients: bool,
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct SessionTimeLimitConfig {
+    #[schema(strings(help = "Used for clients without a custom limit"))]
+    #[schema(gui(slider(min = 1, max = 120)), suffix = "min")]
+    pub default_limit_min: u64,
+
+    #[schema(strings(
+        help = "The remaining time is shown in the headset for this long before the session ends"
+    ))]
+    #[schema(gui(slider(min = 0, max = 300, step = 10)), suffix = "s")]
+    pub countdown_s: u64,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct LobbyMessageConfig {
+    pub title: String,
+    pub body: String,
+}
+
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
 pub enum SocketBufferSize {
     Default,
     Maximum,
+    #[schema(strings(
+        help = "Start from the system default and grow the receive buffer while packets are lost"
+    ))]
+    Autotune,
     Custom(#[schema(suffix = "B")] u32),
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct NetworkConditionsConfig {
+    #[schema(strings(help = "Constant delay added to every stream shard"))]
+    #[schema(gui(slider(min = 0, max = 500)), suffix = "ms")]
+    pub latency_ms: u64,
+
+    #[schema(strings(help = "Random delay added on top of the constant latency"))]
+    #[schema(gui(slider(min = 0, max = 100)), suffix = "ms")]
+    pub jitter_ms: u64,
+
+    #[schema(strings(
+        help = "Percentage of shards that are allowed to overtake or fall behind other shards"
+    ))]
+    #[schema(gui(slider(min = 0.0, max = 100.0, step = 0.1)), suffix = "%")]
+    pub reordering_percentage: f32,
+
+    #[schema(gui(slider(min = 0.0, max = 100.0, step = 0.1)), suffix = "%")]
+    pub packet_loss_percentage: f32,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct NetworkSimulationConfig {
+    pub video: Switch<NetworkConditionsConfig>,
+
+    #[schema(strings(help = "Tracking and controller input"))]
+    pub tracking: Switch<NetworkConditionsConfig>,
+
+    #[schema(strings(help = "Audio, haptics, statistics and overlay layers"))]
+    pub other_streams: Switch<NetworkConditionsConfig>,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct VideoPacingConfig {
+    #[schema(strings(help = "Portion of the frame interval used to send each video frame"))]
+    #[schema(gui(slider(min = 10.0, max = 100.0, step = 1.0)), suffix = "%")]
+    pub frame_interval_percentage: f32,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct PacketRedundancyConfig {
+    #[schema(gui(slider(min = 0, max = 20)), suffix = "ms")]
+    pub max_duplicate_delay_ms: u64,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct MirrorPageConfig {
+    #[schema(strings(
+        help = "On Windows the image is downscaled by halves, so the width can be down to half of this value"
+    ))]
+    #[schema(gui(slider(min = 320, max = 3840, step = 10)), suffix = "px")]
+    pub max_width: u32,
+    #[schema(gui(slider(min = 10.0, max = 120.0, step = 1.0)), suffix = "FPS")]
+    pub max_fps: f32,
+    #[schema(strings(help = "Show both eyes side by side instead of only the left eye"))]
+    pub both_eyes: bool,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct JumboFramesConfig {
+    #[schema(strings(
+        help = "Largest packet size probed, 8972 bytes for a 9000 bytes MTU. Smaller sizes are probed if it doesn't go through"
+    ))]
+    #[schema(gui(slider(min = 1500, max = 65507, logarithmic)), suffix = "B")]
+    pub max_packet_size: usize,
+}
+
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
 pub struct ConnectionConfig {
     #[schema(strings(
         help = r#"UDP: Faster, but less stable than TCP. Try this if your network is well optimized and free of interference.
 TCP: Slower than UDP, but more stable. Pick this if you experience video or audio stutters with UDP."#
     ))]
+    #[schema(flag = "dangerous")]
     pub stream_protocol: SocketProtocol,
 
     pub client_discovery: Switch<DiscoveryConfig>,
 
+    #[schema(strings(
+        help = "Message shown in the headset lobby instead of the pairing instructions, for demo or arcade deployments. Takes effect after the first connection."
+    ))]
+    pub lobby_welcome_message: Switch<LobbyMessageConfig>,
+
+    #[schema(strings(
+        help = "Operator mode for rental and arcade deployments. Streaming sessions are limited in time, then the client goes back to the lobby and cannot reconnect until its timer is reset in the Connections tab. The limit can be changed per client."
+    ))]
+    #[schema(flag = "real-time")]
+    pub session_time_limit: Switch<SessionTimeLimitConfig>,
+
     pub stream_port: u16,
     pub web_server_port: u16,
     pub osc_local_port: u16,
 
+    #[schema(strings(
+        help = "Expose the streaming state and basic controls to local applications like desktop widgets and OBS plugins, through a Unix socket or a Windows named pipe. See the Web API wiki page."
+    ))]
+    #[schema(flag = "steamvr-restart")]
+    pub local_ipc: bool,
+
+    #[schema(strings(
+        help = "Headset view served at http://localhost:<web server port>/mirror, to be used as an OBS browser source instead of the SteamVR mirror window. The streamer captures the game view before encoding, so it works with any codec and is not distorted by foveated encoding. The dashboard and overlays are not shown on Windows. Changes apply when the page is reloaded."
+    ))]
+    #[schema(flag = "real-time")]
+    pub mirror_page: MirrorPageConfig,
+
+    #[schema(strings(
+        help = "Timeout of each step of the connection handshake. Increase it for VPN, WAN or hotspot links."
+    ))]
+    #[schema(gui(slider(min = 1000, max = 10000, step = 500)), suffix = "ms")]
+    pub handshake_timeout_ms: u64,
+
+    #[schema(strings(
+        display_name = "Device reconnect grace period",
+        help = "After the client disconnects, the headset and controllers stay present in SteamVR without tracking for this long before being reported as disconnected. This lets the SteamVR session survive a brief headset standby."
+    ))]
+    #[schema(flag = "real-time")]
+    #[schema(gui(slider(min = 0, max = 120)), suffix = "s")]
+    pub device_presence_grace_period_s: u64,
+
+    #[schema(strings(
+        display_name = "High latency link warning",
+        help = "Suggest the Constrained network preset when the round trip time measured while connecting is above this value"
+    ))]
+    #[schema(gui(slider(min = 10, max = 500, logarithmic)), suffix = "ms")]
+    pub high_latency_warning_ms: Switch<u64>,
+
     #[schema(strings(display_name = "Streamer send buffer size"))]
+    #[schema(flag = "dangerous")]
     pub server_send_buffer_bytes: SocketBufferSize,
 
     #[schema(strings(display_name = "Streamer receive buffer size"))]
+    #[schema(flag = "dangerous")]
     pub server_recv_buffer_bytes: SocketBufferSize,
 
     #[schema(strings(display_name = "Client send buffer size"))]
+    #[schema(flag = "dangerous")]
     pub client_send_buffer_bytes: SocketBufferSize,
 
     #[schema(strings(display_name = "Client receive buffer size"))]
+    #[schema(flag = "dangerous")]
     pub client_recv_buffer_bytes: SocketBufferSize,
 
+    #[schema(strings(
+        display_name = "Video packet reorder window",
+        help = r#"Number of later video packets the client waits for before considering a missing packet lost. Some Wi-Fi 6 routers and VPNs deliver packets out of order; a window of 0 counts any reordering as loss.
+A lost packet delays the following frames by up to this many packets."#
+    ))]
+    #[schema(gui(slider(min = 0, max = 8)), suffix = " packets")]
+    pub video_packet_reorder_window: u32,
+
     #[schema(strings(
         help = r#"The server discards video packets if it can't push them to the network.
 This could happen on TCP. A IDR frame is requested in this case."#
@@ -820,11 +1439,73 @@ For now works only on Windows+Nvidia"#
     #[schema(flag = "real-time")]
     pub on_disconnect_script: String,
 
+    #[schema(strings(help = r#"What the client does when the stream ends.
+Return to lobby: Show the lobby with the disconnection reason.
+Quit app: Close the client app.
+Restart discovery: Show the lobby welcome message and search for the streamer right away.
+Turn off display: Stop rendering until the next stream starts."#))]
+    pub client_disconnect_action: ClientDisconnectAction,
+
+    #[schema(flag = "dangerous")]
     #[schema(gui(slider(min = 1024, max = 65507, logarithmic)), suffix = "B")]
     pub packet_size: i32,
 
+    #[schema(strings(
+        help = "Use packets larger than the packet size for the stream if the network path supports them without fragmentation, which is probed at the start of every stream. Requires jumbo frames enabled on the network adapters of both the PC and the headset, for example with a direct Ethernet or USB connection. UDP only."
+    ))]
+    pub jumbo_frames: Switch<JumboFramesConfig>,
+
+    #[schema(strings(
+        help = "Hold the Wi-Fi low latency lock only while streaming instead of while the client app is open, to save battery in the lobby. Applied at the next connection."
+    ))]
+    pub wifi_lock_only_while_streaming: bool,
+
+    #[schema(strings(
+        help = "Spread the shards of each video frame over part of the frame interval instead of sending them in a burst. Can reduce packet loss with consumer Wi-Fi access points at high bitrates, but adds some latency."
+    ))]
+    #[schema(flag = "real-time")]
+    pub video_pacing: Switch<VideoPacingConfig>,
+
+    #[schema(strings(
+        help = "Send tracking and haptics packets twice, the second time after a short random delay. The late copies are discarded on receive. Uses a little more bandwidth but reduces input hitches on lossy networks. Applied at the next connection."
+    ))]
+    pub input_packet_redundancy: Switch<PacketRedundancyConfig>,
+
     #[schema(suffix = " frames")]
     pub statistics_history_size: usize,
+
+    #[schema(strings(
+        help = "Developer option. Artificially degrade the streams on both sides, separately for video, tracking and the other streams. Applied at the next connection."
+    ))]
+    #[schema(flag = "hidden")]
+    pub network_simulation: Switch<NetworkSimulationConfig>,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct InfluxDbConfig {
+    #[schema(strings(help = "Host and port of the UDP listener of InfluxDB or Telegraf"))]
+    pub address: String,
+    pub measurement: String,
+}
+
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct StatisticsSinksConfig {
+    #[schema(strings(
+        display_name = "CSV recorder",
+        help = "Write the statistics of every frame to a CSV file in the log folder, one file per stream."
+    ))]
+    pub csv_recorder: bool,
+
+    #[schema(strings(
+        help = "Serve the latest statistics in the Prometheus text format at /api/v1/metrics on the web server port."
+    ))]
+    pub prometheus_exporter: bool,
+
+    #[schema(strings(
+        display_name = "InfluxDB",
+        help = "Send the statistics of every frame in the InfluxDB line protocol over UDP."
+    ))]
+    pub influxdb: Switch<InfluxDbConfig>,
 }
 
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
@@ -845,6 +1526,15 @@ pub struct LoggingConfig {
     #[schema(strings(help = "This applies only to certain error or warning messages."))]
     #[schema(flag = "steamvr-restart")]
     pub prefer_backtrace: bool,
+    #[schema(strings(
+        help = "Replace IP addresses, hostnames and Wi-Fi network names in logs, so they can be shared publicly."
+    ))]
+    #[schema(flag = "steamvr-restart")]
+    pub anonymize_logs: bool,
+    #[schema(strings(
+        help = "Destinations of the per-frame statistics besides the dashboard graphs. Applied at the next connection."
+    ))]
+    pub statistics_sinks: StatisticsSinksConfig,
 }
 
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
@@ -867,6 +1557,13 @@ No action: All driver registration actions should be performed manually, ALVR in
 
     #[schema(strings(display_name = "Open and close SteamVR with dashboard"))]
     pub open_close_steamvr_with_dashboard: bool,
+
+    #[schema(strings(
+        display_name = "Restart SteamVR on crash",
+        help = "The dashboard restarts SteamVR if it crashes or unloads the driver while a client is connected. The client reconnects once the streamer is back."
+    ))]
+    #[schema(flag = "real-time")]
+    pub restart_steamvr_on_crash: bool,
 }
 
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
@@ -876,6 +1573,41 @@ pub struct RollingVideoFilesConfig {
     pub duration_s: u64,
 }
 
+#[derive(SettingsSchema, Serialize, Deserialize, Clone)]
+pub struct AbComparisonConfig {
+    #[schema(strings(display_name = "Variant A bitrate"))]
+    #[schema(gui(slider(min = 5, max = 1000, logarithmic)), suffix = "Mbps")]
+    pub variant_a_bitrate_mbps: u64,
+
+    #[schema(strings(display_name = "Variant B bitrate"))]
+    #[schema(gui(slider(min = 5, max = 1000, logarithmic)), suffix = "Mbps")]
+    pub variant_b_bitrate_mbps: u64,
+
+    #[schema(strings(
+        display_name = "Variant A codec",
+        help = "If disabled, the codec of the stream is used"
+    ))]
+    pub variant_a_codec: Switch<CodecType>,
+
+    #[schema(strings(
+        display_name = "Variant B codec",
+        help = "If disabled, the codec of the stream is used"
+    ))]
+    pub variant_b_codec: Switch<CodecType>,
+
+    #[schema(strings(display_name = "Phase duration"))]
+    #[schema(gui(slider(min = 10, max = 300, step = 5)), suffix = "s")]
+    pub phase_duration_s: u64,
+
+    #[schema(gui(slider(min = 1, max = 10)))]
+    pub rounds: u64,
+
+    #[schema(strings(
+        help = "Ask the client to save a screenshot in the middle of every phase. Not all clients support it"
+    ))]
+    pub capture_client_screenshots: bool,
+}
+
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
 pub struct CaptureConfig {
     #[schema(strings(display_name = "Start video recording at client connection"))]
@@ -885,6 +1617,12 @@ pub struct CaptureConfig {
 
     #[schema(flag = "steamvr-restart")]
     pub capture_frame_dir: String,
+
+    #[schema(strings(
+        display_name = "A/B comparison",
+        help = "Started from the History tab. The bitrate and the codec alternate between the two variants while statistics are recorded for each of them. The encoder is recreated when the codec changes"
+    ))]
+    pub ab_comparison: AbComparisonConfig,
 }
 
 #[derive(SettingsSchema, Serialize, Deserialize, Clone)]
@@ -904,6 +1642,12 @@ pub struct Settings {
     pub steamvr_launcher: SteamvrLauncher,
     pub capture: CaptureConfig,
     pub patches: Patches,
+
+    #[schema(strings(
+        help = "Names of in-development features to enable, for nightly builds. They take effect at the next connection, only if the client supports them too. Enabled experiments are listed in the session log and in the stream history."
+    ))]
+    pub experiments: Vec<String>,
+
     pub open_setup_wizard: bool,
 }
 
@@ -942,6 +1686,12 @@ pub fn session_settings_default() -> SettingsDefault {
         },
         content: vec![],
     };
+    let network_conditions = NetworkConditionsConfigDefault {
+        latency_ms: 20,
+        jitter_ms: 5,
+        reordering_percentage: 0.0,
+        packet_loss_percentage: 0.0,
+    };
     let socket_buffer = SocketBufferSizeDefault {
         Custom: 100000,
         variant: SocketBufferSizeDefaultVariant::Maximum,
@@ -950,12 +1700,40 @@ pub fn session_settings_default() -> SettingsDefault {
     SettingsDefault {
         video: VideoConfigDefault {
             adapter_index: 0,
+            use_headset_recommended_resolution: false,
             transcoding_view_resolution: view_resolution.clone(),
             emulated_headset_view_resolution: view_resolution,
             preferred_fps: 72.,
             max_buffering_frames: 2.0,
             buffering_history_weight: 0.90,
+            latency_budget: SwitchDefault {
+                enabled: false,
+                content: LatencyBudgetConfigDefault {
+                    target_motion_to_photon_ms: 45,
+                },
+            },
+            decoder_priming_frames: 2,
             optimize_game_render_latency: true,
+            reprojection_overscan: SwitchDefault {
+                enabled: false,
+                content: 10.0,
+            },
+            client_reprojection: true,
+            client_present: ClientPresentConfigDefault {
+                extra_latency_mode: ExtraLatencyModeDefault {
+                    variant: ExtraLatencyModeDefaultVariant::Default,
+                },
+                front_buffer_rendering: false,
+            },
+            color_blindness_filter: SwitchDefault {
+                enabled: false,
+                content: ColorBlindnessFilterConfigDefault {
+                    deficiency: ColorBlindnessTypeDefault {
+                        variant: ColorBlindnessTypeDefaultVariant::Deuteranopia,
+                    },
+                    strength: 1.0,
+                },
+            },
             bitrate: BitrateConfigDefault {
                 mode: BitrateModeDefault {
                     ConstantMbps: 30,
@@ -987,6 +1765,12 @@ pub fn session_settings_default() -> SettingsDefault {
                                 latency_overstep_multiplier: 0.99,
                             },
                         },
+                        decoder_overload_limiter: SwitchDefault {
+                            enabled: true,
+                            content: DecoderOverloadLimiterDefault {
+                                overload_multiplier: 0.8,
+                            },
+                        },
                     },
                     variant: BitrateModeDefaultVariant::Adaptive,
                 },
@@ -1011,6 +1795,15 @@ pub fn session_settings_default() -> SettingsDefault {
                     variant: EntropyCodingDefaultVariant::Cavlc,
                 },
                 use_10bit: false,
+                slice_count: 1,
+                intra_refresh: SwitchDefault {
+                    enabled: false,
+                    content: IntraRefreshConfigDefault { period_frames: 90 },
+                },
+                keyframe_interval: KeyframeIntervalDefault {
+                    Manual: 2.0,
+                    variant: KeyframeIntervalDefaultVariant::Automatic,
+                },
                 nvenc: NvencConfigDefault {
                     quality_preset: EncoderQualityPresetNvidiaDefault {
                         variant: EncoderQualityPresetNvidiaDefaultVariant::P1,
@@ -1090,6 +1883,13 @@ pub fn session_settings_default() -> SettingsDefault {
                     edge_ratio_y: 5.,
                 },
             },
+            gaze_roi: SwitchDefault {
+                enabled: false,
+                content: GazeRoiConfigDefault {
+                    radius: 0.2,
+                    quality_boost: 0.3,
+                },
+            },
             clientside_foveation: SwitchDefault {
                 enabled: true,
                 content: ClientsideFoveationDefault {
@@ -1120,6 +1920,10 @@ pub fn session_settings_default() -> SettingsDefault {
                     sharpening: 0.,
                 },
             },
+            overlay_layers: SwitchDefault {
+                enabled: false,
+                content: OverlayLayersConfigDefault { max_fps: 10.0 },
+            },
         },
         audio: AudioConfigDefault {
             linux_backend: LinuxAudioBackendDefault {
@@ -1133,9 +1937,24 @@ pub fn session_settings_default() -> SettingsDefault {
                         content: default_custom_audio_device.clone(),
                     },
                     mute_when_streaming: true,
+                    multichannel: SwitchDefault {
+                        enabled: false,
+                        content: MultichannelAudioConfigDefault {
+                            binaural_downmix_location: BinauralDownmixLocationDefault {
+                                variant: BinauralDownmixLocationDefaultVariant::Client,
+                            },
+                        },
+                    },
                     buffering: AudioBufferingConfigDefault {
                         average_buffering_ms: 50,
                         batch_ms: 10,
+                        auto_tune: SwitchDefault {
+                            enabled: false,
+                            content: AudioBufferingAutoTuneConfigDefault {
+                                min_buffering_ms: 20,
+                                max_buffering_ms: 200,
+                            },
+                        },
                     },
                 },
             },
@@ -1152,7 +1971,15 @@ pub fn session_settings_default() -> SettingsDefault {
                     buffering: AudioBufferingConfigDefault {
                         average_buffering_ms: 50,
                         batch_ms: 10,
+                        auto_tune: SwitchDefault {
+                            enabled: false,
+                            content: AudioBufferingAutoTuneConfigDefault {
+                                min_buffering_ms: 20,
+                                max_buffering_ms: 200,
+                            },
+                        },
                     },
+                    max_queued_packets: 10,
                 },
             },
         },
@@ -1188,6 +2015,29 @@ pub fn session_settings_default() -> SettingsDefault {
                     emulation_mode: ControllersEmulationModeDefault {
                         variant: ControllersEmulationModeDefaultVariant::Quest2Touch,
                     },
+                    per_game_emulation_mode: VectorDefault {
+                        element: EmulationModeOverrideDefault {
+                            executable_name: "".into(),
+                            emulation_mode: ControllersEmulationModeDefault {
+                                variant: ControllersEmulationModeDefaultVariant::ValveIndex,
+                            },
+                        },
+                        content: vec![],
+                    },
+                    per_game_tuning: true,
+                    custom_bindings: SwitchDefault {
+                        enabled: false,
+                        content: ControllerBindingsConfigDefault {
+                            app_keys: VectorDefault {
+                                element: "".into(),
+                                content: vec![],
+                            },
+                            swap_a_b: false,
+                            trackpad_as_joystick: true,
+                            trigger_click_threshold: 0.5,
+                            grip_click_threshold: 0.5,
+                        },
+                    },
                     tracked: true,
                     extra_openvr_props: default_custom_openvr_props,
                     steamvr_pipeline_frames: 3.0,
@@ -1213,6 +2063,51 @@ pub fn session_settings_default() -> SettingsDefault {
                             min_duration_s: 0.01,
                         },
                     },
+                    tracking_loss: ControllerTrackingLossConfigDefault {
+                        timeout_ms: 500,
+                        action: TrackingLossActionDefault {
+                            variant: TrackingLossActionDefaultVariant::NotTracking,
+                        },
+                    },
+                    desktop_pointer: SwitchDefault {
+                        enabled: false,
+                        content: DesktopPointerConfigDefault {
+                            hand: DesktopPointerHandDefault {
+                                variant: DesktopPointerHandDefaultVariant::Right,
+                            },
+                            horizontal_range_deg: 60.0,
+                            vertical_range_deg: 35.0,
+                        },
+                    },
+                    chords: VectorDefault {
+                        element: ControllerChordConfigDefault {
+                            buttons: VectorDefault {
+                                element: "".into(),
+                                content: vec![],
+                            },
+                            action: ChordActionDefault {
+                                variant: ChordActionDefaultVariant::Recenter,
+                            },
+                        },
+                        content: vec![],
+                    },
+                },
+            },
+            external_haptics: SwitchDefault {
+                enabled: false,
+                content: ExternalHapticsConfigDefault {
+                    rules: VectorDefault {
+                        element: HapticsForwardingRuleDefault {
+                            source: HapticsSourceDeviceDefault {
+                                variant: HapticsSourceDeviceDefaultVariant::Any,
+                            },
+                            target: ExternalHapticsDeviceDefault {
+                                variant: ExternalHapticsDeviceDefaultVariant::Bhaptics,
+                            },
+                            intensity_multiplier: 1.0,
+                        },
+                        content: vec![],
+                    },
                 },
             },
             position_recentering_mode: PositionRecenteringModeDefault {
@@ -1222,6 +2117,26 @@ pub fn session_settings_default() -> SettingsDefault {
             rotation_recentering_mode: RotationRecenteringModeDefault {
                 variant: RotationRecenteringModeDefaultVariant::Yaw,
             },
+            world_scale: 1.0,
+            seated_mode_height_offset: 0.5,
+            colocation: SwitchDefault {
+                enabled: false,
+                content: ColocationConfigDefault {
+                    group_name: "default".into(),
+                    peer_port: 9945,
+                },
+            },
+            captions: SwitchDefault {
+                enabled: false,
+                content: CaptionsConfigDefault {
+                    region_center_x: 0.5,
+                    region_center_y: 0.8,
+                    region_width: 0.6,
+                    region_height: 0.2,
+                    font_size: 40.0,
+                    default_duration_s: 5.0,
+                },
+            },
         },
         connection: ConnectionConfigDefault {
             stream_protocol: SocketProtocolDefault {
@@ -1233,20 +2148,89 @@ pub fn session_settings_default() -> SettingsDefault {
                     auto_trust_clients: cfg!(debug_assertions),
                 },
             },
+            lobby_welcome_message: SwitchDefault {
+                enabled: false,
+                content: LobbyMessageConfigDefault {
+                    title: "Welcome!".into(),
+                    body: "Put on the headset and wait for the session to start".into(),
+                },
+            },
+            session_time_limit: SwitchDefault {
+                enabled: false,
+                content: SessionTimeLimitConfigDefault {
+                    default_limit_min: 15,
+                    countdown_s: 60,
+                },
+            },
             web_server_port: 8082,
             stream_port: 9944,
             osc_local_port: 9942,
+            local_ipc: true,
+            mirror_page: MirrorPageConfigDefault {
+                max_width: 1920,
+                max_fps: 60.0,
+                both_eyes: false,
+            },
+            handshake_timeout_ms: 2000,
+            device_presence_grace_period_s: 30,
+            high_latency_warning_ms: SwitchDefault {
+                enabled: true,
+                content: 50,
+            },
             server_send_buffer_bytes: socket_buffer.clone(),
             server_recv_buffer_bytes: socket_buffer.clone(),
             client_send_buffer_bytes: socket_buffer.clone(),
-            client_recv_buffer_bytes: socket_buffer,
+            client_recv_buffer_bytes: SocketBufferSizeDefault {
+                variant: SocketBufferSizeDefaultVariant::Autotune,
+                ..socket_buffer
+            },
+            video_packet_reorder_window: 0,
             max_queued_server_video_frames: 1024,
             avoid_video_glitching: false,
             aggressive_keyframe_resend: false,
             on_connect_script: "".into(),
             on_disconnect_script: "".into(),
+            client_disconnect_action: ClientDisconnectActionDefault {
+                variant: ClientDisconnectActionDefaultVariant::ReturnToLobby,
+            },
             packet_size: 1400,
+            jumbo_frames: SwitchDefault {
+                enabled: false,
+                content: JumboFramesConfigDefault {
+                    max_packet_size: 8972,
+                },
+            },
+            wifi_lock_only_while_streaming: false,
+            video_pacing: SwitchDefault {
+                enabled: false,
+                content: VideoPacingConfigDefault {
+                    frame_interval_percentage: 50.0,
+                },
+            },
+            input_packet_redundancy: SwitchDefault {
+                enabled: false,
+                content: PacketRedundancyConfigDefault {
+                    max_duplicate_delay_ms: 4,
+                },
+            },
             statistics_history_size: 256,
+            network_simulation: SwitchDefault {
+                enabled: false,
+                content: NetworkSimulationConfigDefault {
+                    video: SwitchDefault {
+                        enabled: true,
+                        content: network_conditions.clone(),
+                    },
+                    tracking: SwitchDefault {
+                        enabled: true,
+                        content: network_conditions.clone(),
+                    },
+                    other_streams: SwitchDefault {
+                        enabled: true,
+                        content: network_conditions,
+                    },
+                },
+            },
         },
         logging: LoggingConfigDefault {
             client_log_report_level: SwitchDefault {
@@ -1268,12 +2252,25 @@ pub fn session_settings_default() -> SettingsDefault {
             },
             show_raw_events: false,
             prefer_backtrace: false,
+            anonymize_logs: false,
+            statistics_sinks: StatisticsSinksConfigDefault {
+                csv_recorder: false,
+                prometheus_exporter: false,
+                influxdb: SwitchDefault {
+                    enabled: false,
+                    content: InfluxDbConfigDefault {
+                        address: "127.0.0.1:8089".into(),
+                        measurement: "alvr".into(),
+                    },
+                },
+            },
         },
         steamvr_launcher: SteamvrLauncherDefault {
             driver_launch_action: DriverLaunchActionDefault {
                 variant: DriverLaunchActionDefaultVariant::UnregisterOtherDriversAtStartup,
             },
             open_close_steamvr_with_dashboard: false,
+            restart_steamvr_on_crash: true,
         },
         capture: CaptureConfigDefault {
             startup_video_recording: false,
@@ -1286,10 +2283,33 @@ pub fn session_settings_default() -> SettingsDefault {
             } else {
                 "".into()
             },
+            ab_comparison: AbComparisonConfigDefault {
+                variant_a_bitrate_mbps: 100,
+                variant_b_bitrate_mbps: 50,
+                variant_a_codec: SwitchDefault {
+                    enabled: false,
+                    content: CodecTypeDefault {
+                        variant: CodecTypeDefaultVariant::H264,
+                    },
+                },
+                variant_b_codec: SwitchDefault {
+                    enabled: false,
+                    content: CodecTypeDefault {
+                        variant: CodecTypeDefaultVariant::Hevc,
+                    },
+                },
+                phase_duration_s: 30,
+                rounds: 3,
+                capture_client_screenshots: false,
+            },
         },
         patches: PatchesDefault {
             linux_async_reprojection: false,
         },
+        experiments: VectorDefault {
+            element: "".into(),
+            content: vec![],
+        },
         open_setup_wizard: alvr_common::is_stable() || alvr_common::is_nightly(),
     }
 }
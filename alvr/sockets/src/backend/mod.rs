@@ -1,10 +1,20 @@
+pub mod simulated;
 pub mod tcp;
 pub mod udp;
 
-use alvr_common::{anyhow::Result, ConResult};
+use alvr_common::{
+    anyhow::{bail, Result},
+    ConResult,
+};
 
 pub trait SocketWriter: Send {
     fn send(&mut self, buffer: &[u8]) -> Result<()>;
+
+    // Packets larger than the path MTU fail to send or are dropped instead of being fragmented.
+    // Used to probe the path MTU
+    fn set_dont_fragment(&self, _value: bool) -> Result<()> {
+        bail!("Not supported by this socket")
+    }
 }
 
 // Trait used to abstract different socket (or other input/output) implementations. The funtionality
@@ -16,4 +26,8 @@ pub trait SocketReader: Send {
     fn recv(&mut self, buffer: &mut [u8]) -> ConResult<usize>;
 
     fn peek(&self, buffer: &mut [u8]) -> ConResult<usize>;
+
+    fn recv_buffer_size(&self) -> Result<usize>;
+
+    fn set_recv_buffer_size(&self, size: usize) -> Result<()>;
 }
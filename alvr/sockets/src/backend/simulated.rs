@@ -0,0 +1,113 @@
+// Developer-only socket writer that degrades the connection on purpose. Shards are the unit of
+// delay, reordering and loss, since each shard is self-describing and can be handled independently
+// by the receiver even when using TCP. The conditions are chosen per stream, using the stream ID in
+// the shard prefix. Shards of streams without conditions are sent unaltered.
+
+use super::SocketWriter;
+use alvr_common::anyhow::Result;
+use alvr_session::NetworkConditionsConfig;
+use rand::Rng;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+const IDLE_TIMEOUT: Duration = Duration::from_millis(500);
+
+pub struct SimulatedSocketWriter {
+    // By stream ID
+    conditions: HashMap<u16, NetworkConditionsConfig>,
+    shard_sender: mpsc::Sender<(Instant, Vec<u8>)>,
+    // By stream ID
+    last_deadlines: HashMap<u16, Instant>,
+}
+
+impl SimulatedSocketWriter {
+    pub fn new(
+        inner: Box<dyn SocketWriter>,
+        conditions: HashMap<u16, NetworkConditionsConfig>,
+    ) -> Self {
+        let (shard_sender, shard_receiver) = mpsc::channel();
+
+        // The thread exits when the writer is dropped or the inner socket errors out
+        thread::spawn(move || delivery_loop(inner, shard_receiver));
+
+        Self {
+            conditions,
+            shard_sender,
+            last_deadlines: HashMap::new(),
+        }
+    }
+}
+
+impl SocketWriter for SimulatedSocketWriter {
+    fn send(&mut self, buffer: &[u8]) -> Result<()> {
+        let stream_id = u16::from_be_bytes(buffer[4..6].try_into().unwrap());
+        let Some(config) = self.conditions.get(&stream_id) else {
+            self.shard_sender.send((Instant::now(), buffer.to_vec()))?;
+
+            return Ok(());
+        };
+
+        let mut rng = rand::thread_rng();
+
+        if rng.gen::<f32>() * 100.0 < config.packet_loss_percentage {
+            return Ok(());
+        }
+
+        let mut delay = Duration::from_millis(config.latency_ms);
+        if config.jitter_ms > 0 {
+            delay += Duration::from_millis(rng.gen_range(0..=config.jitter_ms));
+        }
+        let mut deadline = Instant::now() + delay;
+
+        // Unless this shard has been picked for reordering, it cannot overtake previous shards of
+        // the same stream
+        if rng.gen::<f32>() * 100.0 >= config.reordering_percentage {
+            let last_deadline = self.last_deadlines.entry(stream_id).or_insert(deadline);
+            deadline = deadline.max(*last_deadline);
+            *last_deadline = deadline;
+        }
+
+        self.shard_sender.send((deadline, buffer.to_vec()))?;
+
+        Ok(())
+    }
+}
+
+fn delivery_loop(
+    mut inner: Box<dyn SocketWriter>,
+    shard_receiver: mpsc::Receiver<(Instant, Vec<u8>)>,
+) {
+    // The sequence number is used to break ties between shards with the same deadline
+    let mut queue = BinaryHeap::<Reverse<(Instant, u64, Vec<u8>)>>::new();
+    let mut sequence = 0_u64;
+
+    loop {
+        let timeout = queue
+            .peek()
+            .map(|Reverse((deadline, ..))| deadline.saturating_duration_since(Instant::now()))
+            .unwrap_or(IDLE_TIMEOUT);
+
+        match shard_receiver.recv_timeout(timeout) {
+            Ok((deadline, shard)) => {
+                queue.push(Reverse((deadline, sequence, shard)));
+                sequence = sequence.wrapping_add(1);
+            }
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        let now = Instant::now();
+        while matches!(queue.peek(), Some(Reverse((deadline, ..))) if *deadline <= now) {
+            let Reverse((_, _, shard)) = queue.pop().unwrap();
+
+            if inner.send(&shard).is_err() {
+                return;
+            }
+        }
+    }
+}
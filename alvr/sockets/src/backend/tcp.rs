@@ -3,11 +3,12 @@ use crate::LOCAL_IP;
 use super::{SocketReader, SocketWriter};
 use alvr_common::{anyhow::Result, con_bail, ConResult, HandleTryAgain, ToCon};
 use alvr_session::SocketBufferSize;
+use socket2::SockRef;
 use std::{
     io::Read,
     io::Write,
     net::{IpAddr, SocketAddr, TcpListener, TcpStream},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 pub fn bind(
@@ -53,15 +54,19 @@ pub fn connect_to_client(
     port: u16,
     send_buffer_bytes: SocketBufferSize,
     recv_buffer_bytes: SocketBufferSize,
-) -> ConResult<(TcpStream, TcpStream)> {
+) -> ConResult<(TcpStream, TcpStream, Duration)> {
     let split_timeout = timeout / client_ips.len() as u32;
 
     let mut res = alvr_common::try_again();
+    // The TCP handshake takes one round trip
+    let mut round_trip_time = Duration::ZERO;
     for ip in client_ips {
+        let attempt_instant = Instant::now();
         res = TcpStream::connect_timeout(&SocketAddr::new(*ip, port), split_timeout)
             .handle_try_again();
 
         if res.is_ok() {
+            round_trip_time = attempt_instant.elapsed();
             break;
         }
     }
@@ -74,7 +79,7 @@ pub fn connect_to_client(
 
     socket.set_nodelay(true).to_con()?;
 
-    Ok((socket.try_clone().to_con()?, socket))
+    Ok((socket.try_clone().to_con()?, socket, round_trip_time))
 }
 
 impl SocketWriter for TcpStream {
@@ -93,4 +98,12 @@ impl SocketReader for TcpStream {
     fn peek(&self, buffer: &mut [u8]) -> ConResult<usize> {
         TcpStream::peek(self, buffer).handle_try_again()
     }
+
+    fn recv_buffer_size(&self) -> Result<usize> {
+        Ok(SockRef::from(self).recv_buffer_size()?)
+    }
+
+    fn set_recv_buffer_size(&self, size: usize) -> Result<()> {
+        Ok(SockRef::from(self).set_recv_buffer_size(size)?)
+    }
 }
@@ -1,6 +1,8 @@
 use crate::LOCAL_IP;
 
 use super::{SocketReader, SocketWriter};
+#[cfg(any(target_os = "linux", target_os = "android", windows))]
+use alvr_common::anyhow::bail;
 use alvr_common::{anyhow::Result, ConResult, HandleTryAgain};
 use alvr_session::SocketBufferSize;
 use socket2::{MaybeUninitSlice, Socket};
@@ -43,6 +45,53 @@ impl SocketWriter for UdpSocket {
 
         Ok(())
     }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn set_dont_fragment(&self, value: bool) -> Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        let mode: c_int = if value {
+            libc::IP_PMTUDISC_DO
+        } else {
+            libc::IP_PMTUDISC_WANT // system default
+        };
+        let res = unsafe {
+            libc::setsockopt(
+                self.as_raw_fd(),
+                libc::IPPROTO_IP,
+                libc::IP_MTU_DISCOVER,
+                &mode as *const c_int as _,
+                mem::size_of::<c_int>() as _,
+            )
+        };
+        if res != 0 {
+            bail!("setsockopt failed: {}", std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn set_dont_fragment(&self, value: bool) -> Result<()> {
+        use std::os::windows::io::AsRawSocket;
+        use windows_sys::Win32::Networking::WinSock;
+
+        let value = value as u32;
+        let res = unsafe {
+            WinSock::setsockopt(
+                self.as_raw_socket() as _,
+                WinSock::IPPROTO_IP as _,
+                WinSock::IP_DONTFRAGMENT as _,
+                &value as *const u32 as _,
+                mem::size_of::<u32>() as _,
+            )
+        };
+        if res != 0 {
+            bail!("setsockopt failed: {}", std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
 }
 
 impl SocketReader for Socket {
@@ -62,4 +111,12 @@ impl SocketReader for Socket {
             .handle_try_again()?
             .0)
     }
+
+    fn recv_buffer_size(&self) -> Result<usize> {
+        Ok(Socket::recv_buffer_size(self)?)
+    }
+
+    fn set_recv_buffer_size(&self, size: usize) -> Result<()> {
+        Ok(Socket::set_recv_buffer_size(self, size)?)
+    }
 }
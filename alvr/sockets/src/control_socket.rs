@@ -1,29 +1,45 @@
 use crate::backend::{tcp, SocketReader, SocketWriter};
 
 use super::CONTROL_PORT;
-use alvr_common::{anyhow::Result, ConResult, HandleTryAgain, ToCon};
+use alvr_common::{
+    anyhow::{bail, Result},
+    parking_lot::{Condvar, Mutex},
+    ConResult, HandleTryAgain, ToCon,
+};
 use alvr_session::SocketBufferSize;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
+    collections::VecDeque,
     marker::PhantomData,
     mem,
     net::{IpAddr, TcpListener, TcpStream},
+    sync::Arc,
+    thread,
     time::{Duration, Instant},
 };
 
 // This corresponds to the length of the payload
 const FRAMED_PREFIX_LENGTH: usize = mem::size_of::<u32>();
 
+// How often the send thread checks whether all the channel senders were dropped
+const SEND_THREAD_IDLE_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+const FLUSH_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+// Normal packets queued while the socket is stalled. Urgent packets are rare and not bounded
+const MAX_QUEUED_NORMAL_PACKETS: usize = 1024;
+
 struct RecvState {
     packet_length: usize, // contains length prefix
     packet_cursor: usize, // counts also the length prefix bytes
 }
 
+// Returns the number of bytes sent, including the length prefix
 fn framed_send<S: Serialize>(
     socket: &mut TcpStream,
     buffer: &mut Vec<u8>,
     packet: &S,
-) -> Result<()> {
+) -> Result<usize> {
     let serialized_size = bincode::serialized_size(&packet)? as usize;
     let packet_size = serialized_size + FRAMED_PREFIX_LENGTH;
 
@@ -36,15 +52,16 @@ fn framed_send<S: Serialize>(
 
     socket.send(&buffer[0..packet_size])?;
 
-    Ok(())
+    Ok(packet_size)
 }
 
+// Returns the packet and the number of bytes received, including the length prefix
 fn framed_recv<R: DeserializeOwned>(
     socket: &mut TcpStream,
     buffer: &mut Vec<u8>,
     maybe_recv_state: &mut Option<RecvState>,
     timeout: Duration,
-) -> ConResult<R> {
+) -> ConResult<(R, usize)> {
     let deadline = Instant::now() + timeout;
 
     let recv_state_mut = if let Some(state) = maybe_recv_state {
@@ -85,23 +102,178 @@ fn framed_recv<R: DeserializeOwned>(
         }
     }
 
-    let packet = bincode::deserialize(&buffer[FRAMED_PREFIX_LENGTH..recv_state_mut.packet_length])
-        .to_con()?;
+    let packet_length = recv_state_mut.packet_length;
+    let packet = bincode::deserialize(&buffer[FRAMED_PREFIX_LENGTH..packet_length]).to_con()?;
 
     *maybe_recv_state = None;
 
-    Ok(packet)
+    Ok((packet, packet_length))
 }
 
 pub struct ControlSocketSender<T> {
     inner: TcpStream,
     buffer: Vec<u8>,
+    sent_bytes: usize, // since the last call to take_sent_bytes()
     _phantom: PhantomData<T>,
 }
 
 impl<S: Serialize> ControlSocketSender<S> {
     pub fn send(&mut self, packet: &S) -> Result<()> {
-        framed_send(&mut self.inner, &mut self.buffer, packet)
+        self.sent_bytes += framed_send(&mut self.inner, &mut self.buffer, packet)?;
+
+        Ok(())
+    }
+
+    pub fn take_sent_bytes(&mut self) -> usize {
+        mem::take(&mut self.sent_bytes)
+    }
+}
+
+impl<S: Serialize + Send + 'static> ControlSocketSender<S> {
+    // Moves the socket to a dedicated send thread. The thread exits when all the channel senders
+    // are dropped or when the socket fails
+    pub fn into_channel(mut self) -> ControlChannelSender<S> {
+        let shared = Arc::new(ControlChannelShared {
+            queue: Mutex::new(ControlSendQueue {
+                urgent: VecDeque::new(),
+                normal: VecDeque::new(),
+                is_sending: false,
+                error: None,
+                sent_bytes: 0,
+            }),
+            condvar: Condvar::new(),
+        });
+
+        thread::spawn({
+            let shared = Arc::clone(&shared);
+            move || loop {
+                let packet = {
+                    let mut queue = shared.queue.lock();
+                    loop {
+                        if let Some(packet) = queue
+                            .urgent
+                            .pop_front()
+                            .or_else(|| queue.normal.pop_front())
+                        {
+                            queue.is_sending = true;
+                            break packet;
+                        }
+                        if Arc::strong_count(&shared) == 1 {
+                            return;
+                        }
+
+                        shared
+                            .condvar
+                            .wait_for(&mut queue, SEND_THREAD_IDLE_CHECK_INTERVAL);
+                    }
+                };
+
+                // The queue is not locked while sending
+                let res = self.send(&packet);
+
+                let mut queue = shared.queue.lock();
+                queue.is_sending = false;
+                queue.sent_bytes += self.take_sent_bytes();
+                if let Err(e) = res {
+                    queue.error = Some(e.to_string());
+                    return;
+                }
+            }
+        });
+
+        ControlChannelSender { shared }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SendPriority {
+    Normal,
+    // Sent before any queued normal packet
+    Urgent,
+}
+
+struct ControlSendQueue<S> {
+    urgent: VecDeque<S>,
+    normal: VecDeque<S>,
+    // A packet was taken from the queue but is not written to the socket yet
+    is_sending: bool,
+    error: Option<String>,
+    sent_bytes: usize, // since the last call to take_sent_bytes()
+}
+
+struct ControlChannelShared<S> {
+    queue: Mutex<ControlSendQueue<S>>,
+    condvar: Condvar,
+}
+
+// Handle to the send thread of a control socket. Sending only enqueues the packet, so callers
+// never block on the socket nor on each other. A socket error is returned by the following sends.
+// If the socket stalls and the normal queue is full, normal packets are rejected with an error
+pub struct ControlChannelSender<S> {
+    shared: Arc<ControlChannelShared<S>>,
+}
+
+impl<S> Clone for ControlChannelSender<S> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<S> ControlChannelSender<S> {
+    pub fn send_with_priority(&self, packet: S, priority: SendPriority) -> Result<()> {
+        let mut queue = self.shared.queue.lock();
+        if let Some(e) = &queue.error {
+            bail!("Control socket send failed: {e}");
+        }
+
+        match priority {
+            SendPriority::Normal => {
+                if queue.normal.len() >= MAX_QUEUED_NORMAL_PACKETS {
+                    bail!("Control socket send queue full");
+                }
+                queue.normal.push_back(packet)
+            }
+            SendPriority::Urgent => queue.urgent.push_back(packet),
+        }
+        self.shared.condvar.notify_one();
+
+        Ok(())
+    }
+
+    pub fn send(&self, packet: S) -> Result<()> {
+        self.send_with_priority(packet, SendPriority::Normal)
+    }
+
+    pub fn send_urgent(&self, packet: S) -> Result<()> {
+        self.send_with_priority(packet, SendPriority::Urgent)
+    }
+
+    pub fn take_sent_bytes(&self) -> usize {
+        mem::take(&mut self.shared.queue.lock().sent_bytes)
+    }
+
+    // Waits until all the queued packets are written to the socket. Use before the process or the
+    // connection could be terminated
+    pub fn flush(&self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            {
+                let queue = self.shared.queue.lock();
+                if let Some(e) = &queue.error {
+                    bail!("Control socket send failed: {e}");
+                }
+                if queue.urgent.is_empty() && queue.normal.is_empty() && !queue.is_sending {
+                    return Ok(());
+                }
+            }
+
+            if Instant::now() > deadline {
+                bail!("Timed out flushing the control socket");
+            }
+            thread::sleep(FLUSH_POLL_INTERVAL);
+        }
     }
 }
 
@@ -109,17 +281,25 @@ pub struct ControlSocketReceiver<T> {
     inner: TcpStream,
     buffer: Vec<u8>,
     recv_state: Option<RecvState>,
+    received_bytes: usize, // since the last call to take_received_bytes()
     _phantom: PhantomData<T>,
 }
 
 impl<R: DeserializeOwned> ControlSocketReceiver<R> {
     pub fn recv(&mut self, timeout: Duration) -> ConResult<R> {
-        framed_recv(
+        let (packet, size) = framed_recv(
             &mut self.inner,
             &mut self.buffer,
             &mut self.recv_state,
             timeout,
-        )
+        )?;
+        self.received_bytes += size;
+
+        Ok(packet)
+    }
+
+    pub fn take_received_bytes(&mut self) -> usize {
+        mem::take(&mut self.received_bytes)
     }
 }
 
@@ -138,6 +318,7 @@ pub fn get_server_listener(timeout: Duration) -> Result<TcpListener> {
 // the specified types can be exchanged
 pub struct ProtoControlSocket {
     inner: TcpStream,
+    connect_round_trip_time: Option<Duration>,
 }
 
 pub enum PeerType<'a> {
@@ -147,31 +328,45 @@ pub enum PeerType<'a> {
 
 impl ProtoControlSocket {
     pub fn connect_to(timeout: Duration, peer: PeerType<'_>) -> ConResult<(Self, IpAddr)> {
-        let socket = match peer {
+        let (socket, connect_round_trip_time) = match peer {
             PeerType::AnyClient(ips) => {
-                tcp::connect_to_client(
+                let (socket, _, round_trip_time) = tcp::connect_to_client(
                     timeout,
                     &ips,
                     CONTROL_PORT,
                     SocketBufferSize::Default,
                     SocketBufferSize::Default,
-                )?
-                .0
+                )?;
+
+                (socket, Some(round_trip_time))
+            }
+            PeerType::Server(listener) => {
+                (tcp::accept_from_server(listener, None, timeout)?.0, None)
             }
-            PeerType::Server(listener) => tcp::accept_from_server(listener, None, timeout)?.0,
         };
 
         let peer_ip = socket.peer_addr().to_con()?.ip();
 
-        Ok((Self { inner: socket }, peer_ip))
+        Ok((
+            Self {
+                inner: socket,
+                connect_round_trip_time,
+            },
+            peer_ip,
+        ))
+    }
+
+    // Only available on the side that initiated the connection
+    pub fn connect_round_trip_time(&self) -> Option<Duration> {
+        self.connect_round_trip_time
     }
 
     pub fn send<S: Serialize>(&mut self, packet: &S) -> Result<()> {
-        framed_send(&mut self.inner, &mut vec![], packet)
+        framed_send(&mut self.inner, &mut vec![], packet).map(|_| ())
     }
 
     pub fn recv<R: DeserializeOwned>(&mut self, timeout: Duration) -> ConResult<R> {
-        framed_recv(&mut self.inner, &mut vec![], &mut None, timeout)
+        framed_recv(&mut self.inner, &mut vec![], &mut None, timeout).map(|(packet, _)| packet)
     }
 
     pub fn split<S: Serialize, R: DeserializeOwned>(
@@ -184,12 +379,14 @@ impl ProtoControlSocket {
             ControlSocketSender {
                 inner: self.inner.try_clone()?,
                 buffer: vec![],
+                sent_bytes: 0,
                 _phantom: PhantomData,
             },
             ControlSocketReceiver {
                 inner: self.inner,
                 buffer: vec![],
                 recv_state: None,
+                received_bytes: 0,
                 _phantom: PhantomData,
             },
         ))
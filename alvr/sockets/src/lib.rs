@@ -15,8 +15,22 @@ pub use stream_socket::*;
 pub const LOCAL_IP: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
 pub const CONTROL_PORT: u16 = 9943;
 pub const HANDSHAKE_PACKET_SIZE_BYTES: usize = 56; // this may change in future protocols
+
+// Handshake packet followed by the one-time trust token, sent directly to the streamer
+pub const PAIRING_PACKET_SIZE_BYTES: usize = HANDSHAKE_PACKET_SIZE_BYTES + 8;
 pub const KEEPALIVE_INTERVAL: Duration = Duration::from_millis(500);
 
+// Encoded in the QR code shown by the dashboard
+pub fn pairing_uri(server_ip: IpAddr, token: u64) -> String {
+    format!("alvr://{server_ip}?token={token:016x}")
+}
+
+pub fn parse_pairing_uri(uri: &str) -> Option<(IpAddr, u64)> {
+    let (ip, token) = uri.strip_prefix("alvr://")?.split_once("?token=")?;
+
+    Some((ip.parse().ok()?, u64::from_str_radix(token, 16).ok()?))
+}
+
 fn set_socket_buffers(
     socket: &socket2::Socket,
     send_buffer_bytes: SocketBufferSize,
@@ -29,8 +43,9 @@ fn set_socket_buffers(
     );
 
     {
+        // Only the receive buffer is autotuned, since overflows are detected as packet loss
         let maybe_size = match send_buffer_bytes {
-            SocketBufferSize::Default => None,
+            SocketBufferSize::Default | SocketBufferSize::Autotune => None,
             SocketBufferSize::Maximum => Some(u32::MAX),
             SocketBufferSize::Custom(size) => Some(size),
         };
@@ -49,7 +64,7 @@ fn set_socket_buffers(
 
     {
         let maybe_size = match recv_buffer_bytes {
-            SocketBufferSize::Default => None,
+            SocketBufferSize::Default | SocketBufferSize::Autotune => None,
             SocketBufferSize::Maximum => Some(u32::MAX),
             SocketBufferSize::Custom(size) => Some(size),
         };
@@ -16,20 +16,28 @@
 // Note: We can't clone the underlying socket for each StreamSender and the mutex around the socket
 // cannot be removed. This is because we need to make sure at least shards are written whole.
 
-use crate::backend::{tcp, udp, SocketReader, SocketWriter};
+use crate::backend::{simulated::SimulatedSocketWriter, tcp, udp, SocketReader, SocketWriter};
 use alvr_common::{
-    anyhow::Result, debug, parking_lot::Mutex, AnyhowToCon, ConResult, HandleTryAgain, ToCon,
+    anyhow::{bail, Result},
+    con_bail, debug, info,
+    parking_lot::{Condvar, Mutex},
+    AnyhowToCon, ConResult, ConnectionError, ToCon,
 };
-use alvr_session::{SocketBufferSize, SocketProtocol};
+use alvr_session::{NetworkConditionsConfig, SocketBufferSize, SocketProtocol};
+use rand::Rng;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
     cmp::Ordering,
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     marker::PhantomData,
     mem,
     net::{IpAddr, TcpListener, UdpSocket},
-    sync::{mpsc, Arc},
-    time::Duration,
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 const SHARD_PREFIX_SIZE: usize = mem::size_of::<u32>() // packet length - field itself (4 bytes)
@@ -38,6 +46,44 @@ const SHARD_PREFIX_SIZE: usize = mem::size_of::<u32>() // packet length - field
     + mem::size_of::<u32>() // shards count
     + mem::size_of::<u32>(); // shards index
 
+// Receive buffer autotuning. The buffer is doubled when packets were lost since the last check
+const AUTOTUNE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const AUTOTUNE_MAX_RECV_BUFFER_SIZE: usize = 32 * 1024 * 1024;
+
+// Shards are sent in small bursts when pacing, since sleeping for shorter intervals is not accurate
+const MIN_PACING_SLEEP: Duration = Duration::from_millis(1);
+
+// Maximum time StreamSocket::recv() waits for a free buffer with BackpressurePolicy::Block
+const BLOCK_WAIT_TIMEOUT: Duration = Duration::from_millis(10);
+
+// Packets queued by a StreamChannelSender while the socket is stalled. They are sent at most once
+// per frame, so older packets are stale and discarded first
+const MAX_QUEUED_CHANNEL_PACKETS: usize = 64;
+// How often the send thread checks whether all the channel senders were dropped
+const CHANNEL_IDLE_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+// Packet size probes are single shards outside of any stream. A probe containing only the prefix
+// marks the end of the probing
+const PACKET_SIZE_PROBE_STREAM_ID: u16 = u16::MAX;
+// Each probe is sent multiple times to tolerate some packet loss
+const PACKET_SIZE_PROBE_COPIES: usize = 3;
+const MAX_UDP_PACKET_SIZE: usize = 65507;
+
+/// What to do when a packet is received for a stream whose buffers are all in use, because packets
+/// are not read fast enough
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BackpressurePolicy {
+    /// Discard the oldest unread packet to make room for the new one
+    DropOldest,
+    /// Discard the incoming packet
+    DropNewest,
+    /// Stop reading from the socket until a buffer is freed. This stalls every stream of the
+    /// socket, use only for streams with low traffic that must not lose packets
+    Block,
+    /// Keep only the latest packet, any unread packet is discarded as soon as a new one is complete
+    Coalesce,
+}
+
 /// Memory buffer that contains a hidden prefix
 #[derive(Default)]
 pub struct Buffer<H = ()> {
@@ -84,18 +130,65 @@ impl<H> Buffer<H> {
     }
 }
 
+/// Bytes that went through the socket for a stream, including the shard prefixes and the
+/// redundant copies
+#[derive(Clone, Copy, Default, Debug)]
+pub struct StreamTraffic {
+    pub sent_bytes: usize,
+    pub received_bytes: usize,
+}
+
 #[derive(Clone)]
 pub struct StreamSender<H> {
     inner: Arc<Mutex<Box<dyn SocketWriter>>>,
+    // Shared by all the senders of the socket, by stream ID
+    sent_bytes: Arc<Mutex<HashMap<u16, usize>>>,
     stream_id: u16,
     max_packet_size: usize,
     // if the packet index overflows the worst that happens is a false positive packet loss
     next_packet_index: u32,
     used_buffers: Vec<Vec<u8>>,
+    pacing_duration: Option<Duration>,
+    // Shards are copied and sent again by a separate thread, after the deadline
+    duplicate_sender: Option<(mpsc::Sender<(Instant, Vec<u8>)>, Duration)>,
     _phantom: PhantomData<H>,
 }
 
+fn duplicates_loop(
+    socket: Arc<Mutex<Box<dyn SocketWriter>>>,
+    shard_receiver: mpsc::Receiver<(Instant, Vec<u8>)>,
+) {
+    // Shards are sent in order, so a copy can be delayed further by the previous one. The loop
+    // exits when the sender is dropped or the socket errors out
+    while let Ok((deadline, shard)) = shard_receiver.recv() {
+        thread::sleep(deadline.saturating_duration_since(Instant::now()));
+
+        if socket.lock().send(&shard).is_err() {
+            return;
+        }
+    }
+}
+
 impl<H> StreamSender<H> {
+    /// Spread the shards of each packet over the specified duration instead of sending them
+    /// back-to-back. The sender is blocked until the last shard is sent.
+    pub fn set_pacing(&mut self, duration: Option<Duration>) {
+        self.pacing_duration = duration;
+    }
+
+    /// Send every packet a second time after a random delay up to max_delay. The receiver discards
+    /// the copy that arrives last using the packet index.
+    pub fn set_redundancy(&mut self, max_delay: Option<Duration>) {
+        self.duplicate_sender = max_delay.map(|max_delay| {
+            let (sender, receiver) = mpsc::channel();
+
+            let socket = Arc::clone(&self.inner);
+            thread::spawn(move || duplicates_loop(socket, receiver));
+
+            (sender, max_delay)
+        });
+    }
+
     /// Shard and send a buffer with zero copies and zero allocations.
     /// The prefix of each shard is written over the previously sent shard to avoid reallocations.
     pub fn send(&mut self, mut buffer: Buffer<H>) -> Result<()> {
@@ -104,7 +197,20 @@ impl<H> StreamSender<H> {
         let data_size = actual_buffer_size - SHARD_PREFIX_SIZE;
         let shards_count = (data_size as f32 / max_shard_data_size as f32).ceil() as usize;
 
+        let mut sent_bytes = 0;
+        let begin_instant = Instant::now();
+        let duplicate_deadline = self.duplicate_sender.as_ref().map(|(_, max_delay)| {
+            begin_instant + max_delay.mul_f32(rand::thread_rng().gen::<f32>())
+        });
         for idx in 0..shards_count {
+            if let Some(duration) = self.pacing_duration {
+                let shard_deadline = begin_instant + duration * idx as u32 / shards_count as u32;
+                let ahead_time = shard_deadline.saturating_duration_since(Instant::now());
+                if ahead_time >= MIN_PACING_SLEEP {
+                    thread::sleep(ahead_time);
+                }
+            }
+
             // this overlaps with the previous shard, this is intended behavior and allows to
             // reduce allocations
             let packet_start_position = idx * max_shard_data_size;
@@ -126,8 +232,18 @@ impl<H> StreamSender<H> {
             sub_buffer[14..18].copy_from_slice(&(idx as u32).to_be_bytes());
 
             self.inner.lock().send(&sub_buffer[..packet_length])?;
+            sent_bytes += packet_length;
+
+            if let (Some((sender, _)), Some(deadline)) =
+                (&self.duplicate_sender, duplicate_deadline)
+            {
+                sender.send((deadline, sub_buffer[..packet_length].to_vec()))?;
+                sent_bytes += packet_length;
+            }
         }
 
+        *self.sent_bytes.lock().entry(self.stream_id).or_default() += sent_bytes;
+
         self.next_packet_index += 1;
 
         self.used_buffers.push(buffer.inner);
@@ -163,6 +279,90 @@ impl<H: Serialize> StreamSender<H> {
     }
 }
 
+impl<H: Serialize + Send + 'static> StreamSender<H> {
+    /// Move the sender to a dedicated send thread. The thread exits when all the channel senders
+    /// are dropped or when the socket fails.
+    pub fn into_channel(mut self) -> StreamChannelSender<H> {
+        let shared = Arc::new(StreamChannelShared {
+            queue: Mutex::new(StreamSendQueue {
+                headers: VecDeque::new(),
+                error: None,
+            }),
+            condvar: Condvar::new(),
+        });
+
+        thread::spawn({
+            let shared = Arc::clone(&shared);
+            move || loop {
+                let header = {
+                    let mut queue = shared.queue.lock();
+                    loop {
+                        if let Some(header) = queue.headers.pop_front() {
+                            break header;
+                        }
+                        if Arc::strong_count(&shared) == 1 {
+                            return;
+                        }
+
+                        shared
+                            .condvar
+                            .wait_for(&mut queue, CHANNEL_IDLE_CHECK_INTERVAL);
+                    }
+                };
+
+                // The queue is not locked while sending, which can block because of pacing
+                if let Err(e) = self.send_header(&header) {
+                    shared.queue.lock().error = Some(e.to_string());
+                    return;
+                }
+            }
+        });
+
+        StreamChannelSender { shared }
+    }
+}
+
+struct StreamSendQueue<H> {
+    headers: VecDeque<H>,
+    error: Option<String>,
+}
+
+struct StreamChannelShared<H> {
+    queue: Mutex<StreamSendQueue<H>>,
+    condvar: Condvar,
+}
+
+/// Handle to the send thread of a header-only stream. Sending only enqueues the header, so callers
+/// never block on the socket nor on each other. A socket error is returned by the following sends.
+pub struct StreamChannelSender<H> {
+    shared: Arc<StreamChannelShared<H>>,
+}
+
+impl<H> Clone for StreamChannelSender<H> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<H> StreamChannelSender<H> {
+    pub fn send_header(&self, header: H) -> Result<()> {
+        let mut queue = self.shared.queue.lock();
+        if let Some(e) = &queue.error {
+            bail!("Stream socket send failed: {e}");
+        }
+
+        if queue.headers.len() >= MAX_QUEUED_CHANNEL_PACKETS {
+            queue.headers.pop_front();
+        }
+        queue.headers.push_back(header);
+        self.shared.condvar.notify_one();
+
+        Ok(())
+    }
+}
+
 pub struct ReceiverData<H> {
     buffer: Option<Vec<u8>>,
     size: usize, // counting the prefix
@@ -204,10 +404,21 @@ struct ReconstructedPacket {
     size: usize, // contains prefix
 }
 
+// Reconstructed packets waiting to be read. Unlike a channel, the socket side can remove packets
+// that are already queued
+#[derive(Default)]
+struct PacketQueue {
+    packets: Mutex<VecDeque<ReconstructedPacket>>,
+    condvar: Condvar,
+    // Discarded because of backpressure since the last packet was read
+    dropped_packets: AtomicUsize,
+}
+
 pub struct StreamReceiver<H> {
-    packet_receiver: mpsc::Receiver<ReconstructedPacket>,
+    packet_queue: Arc<PacketQueue>,
     used_buffer_queue: mpsc::Sender<Vec<u8>>,
     last_packet_index: Option<u32>,
+    lost_packets: Arc<AtomicUsize>, // shared by all streams of the socket
     _phantom: PhantomData<H>,
 }
 
@@ -227,10 +438,27 @@ fn wrapping_cmp(lhs: u32, rhs: u32) -> Ordering {
 /// Returns true if a packet has been recontructed and copied into the buffer.
 impl<H: DeserializeOwned + Serialize> StreamReceiver<H> {
     pub fn recv(&mut self, timeout: Duration) -> ConResult<ReceiverData<H>> {
-        let packet = self
-            .packet_receiver
-            .recv_timeout(timeout)
-            .handle_try_again()?;
+        let packet = {
+            let mut packets = self.packet_queue.packets.lock();
+            if packets.is_empty() {
+                self.packet_queue.condvar.wait_for(&mut packets, timeout);
+            }
+
+            packets.pop_front()
+        };
+        let Some(packet) = packet else {
+            // The socket is the only other owner of the queue
+            if Arc::strong_count(&self.packet_queue) == 1 {
+                con_bail!("Stream socket closed");
+            }
+
+            return alvr_common::try_again();
+        };
+
+        let dropped_packets = self
+            .packet_queue
+            .dropped_packets
+            .swap(0, AtomicOrdering::Relaxed);
 
         let mut had_packet_loss = false;
 
@@ -239,8 +467,13 @@ impl<H: DeserializeOwned + Serialize> StreamReceiver<H> {
             match wrapping_cmp(packet.index, last_idx.wrapping_add(1)) {
                 Ordering::Equal => (),
                 Ordering::Greater => {
-                    // Skipped some indices
-                    had_packet_loss = true
+                    // Skipped some indices. Packets dropped locally because of backpressure are
+                    // not counted as network loss
+                    had_packet_loss = true;
+                    let skipped = packet.index.wrapping_sub(last_idx).wrapping_sub(1) as usize;
+                    if skipped > dropped_packets {
+                        self.lost_packets.fetch_add(1, AtomicOrdering::Relaxed);
+                    }
                 }
                 Ordering::Less => {
                     // Old packet, discard
@@ -261,6 +494,26 @@ impl<H: DeserializeOwned + Serialize> StreamReceiver<H> {
     }
 }
 
+fn packet_size_probe(size: usize) -> Vec<u8> {
+    let mut probe = vec![0; size];
+    probe[0..4].copy_from_slice(&((size - mem::size_of::<u32>()) as u32).to_be_bytes());
+    probe[4..6].copy_from_slice(&PACKET_SIZE_PROBE_STREAM_ID.to_be_bytes());
+    probe[10..14].copy_from_slice(&1_u32.to_be_bytes()); // shards count
+
+    probe
+}
+
+fn wrap_send_socket(
+    socket: Box<dyn SocketWriter>,
+    network_simulation: HashMap<u16, NetworkConditionsConfig>,
+) -> Box<dyn SocketWriter> {
+    if network_simulation.is_empty() {
+        socket
+    } else {
+        Box::new(SimulatedSocketWriter::new(socket, network_simulation))
+    }
+}
+
 pub enum StreamSocketBuilder {
     Tcp(TcpListener),
     Udp(UdpSocket),
@@ -293,6 +546,8 @@ impl StreamSocketBuilder {
         port: u16,
         max_packet_size: usize,
         timeout: Duration,
+        // Network conditions to simulate, by stream ID
+        network_simulation: HashMap<u16, NetworkConditionsConfig>,
     ) -> ConResult<StreamSocket> {
         let (send_socket, receive_socket): (Box<dyn SocketWriter>, Box<dyn SocketReader>) =
             match self {
@@ -314,10 +569,19 @@ impl StreamSocketBuilder {
             // +4 is a workaround to retain compatibilty with old protocol
             // todo: remove +4
             max_packet_size: max_packet_size + 4,
-            send_socket: Arc::new(Mutex::new(send_socket)),
+            send_socket: Arc::new(Mutex::new(wrap_send_socket(
+                send_socket,
+                network_simulation,
+            ))),
             receive_socket,
             shard_recv_state: None,
             stream_recv_components: HashMap::new(),
+            lost_packets: Arc::new(AtomicUsize::new(0)),
+            recv_buffer_autotune: None,
+            dropped_packets: 0,
+            reordered_packets: 0,
+            sent_bytes: Arc::new(Mutex::new(HashMap::new())),
+            received_bytes: HashMap::new(),
         })
     }
 
@@ -330,6 +594,8 @@ impl StreamSocketBuilder {
         send_buffer_bytes: SocketBufferSize,
         recv_buffer_bytes: SocketBufferSize,
         max_packet_size: usize,
+        // Network conditions to simulate, by stream ID
+        network_simulation: HashMap<u16, NetworkConditionsConfig>,
     ) -> ConResult<StreamSocket> {
         let (send_socket, receive_socket): (Box<dyn SocketWriter>, Box<dyn SocketReader>) =
             match protocol {
@@ -341,7 +607,7 @@ impl StreamSocketBuilder {
                     (Box::new(send_socket), Box::new(receive_socket))
                 }
                 SocketProtocol::Tcp => {
-                    let (send_socket, receive_socket) = tcp::connect_to_client(
+                    let (send_socket, receive_socket, _) = tcp::connect_to_client(
                         timeout,
                         &[client_ip],
                         port,
@@ -357,10 +623,19 @@ impl StreamSocketBuilder {
             // +4 is a workaround to retain compatibilty with old protocol
             // todo: remove +4
             max_packet_size: max_packet_size + 4,
-            send_socket: Arc::new(Mutex::new(send_socket)),
+            send_socket: Arc::new(Mutex::new(wrap_send_socket(
+                send_socket,
+                network_simulation,
+            ))),
             receive_socket,
             shard_recv_state: None,
             stream_recv_components: HashMap::new(),
+            lost_packets: Arc::new(AtomicUsize::new(0)),
+            recv_buffer_autotune: None,
+            dropped_packets: 0,
+            reordered_packets: 0,
+            sent_bytes: Arc::new(Mutex::new(HashMap::new())),
+            received_bytes: HashMap::new(),
         })
     }
 }
@@ -383,11 +658,153 @@ struct InProgressPacket {
 }
 
 struct StreamRecvComponents {
+    backpressure: BackpressurePolicy,
     used_buffer_sender: mpsc::Sender<Vec<u8>>,
     used_buffer_receiver: mpsc::Receiver<Vec<u8>>,
-    packet_queue: mpsc::Sender<ReconstructedPacket>,
+    packet_queue: Arc<PacketQueue>,
     in_progress_packets: HashMap<u32, InProgressPacket>,
     discarded_shards_sink: InProgressPacket,
+    // Used to count a discarded packet once instead of once per shard
+    last_discarded_packet_index: Option<u32>,
+    reorder_window: u32,
+    // Completed packets held back until the previous ones complete or fall out of the window,
+    // sorted by index
+    held_packets: VecDeque<ReconstructedPacket>,
+    next_packet_index: Option<u32>,
+    newest_packet_index: Option<u32>,
+}
+
+impl StreamRecvComponents {
+    fn new(backpressure: BackpressurePolicy) -> Self {
+        let (used_buffer_sender, used_buffer_receiver) = mpsc::channel();
+
+        Self {
+            backpressure,
+            used_buffer_sender,
+            used_buffer_receiver,
+            packet_queue: Arc::new(PacketQueue::default()),
+            in_progress_packets: HashMap::new(),
+            discarded_shards_sink: InProgressPacket {
+                buffer: vec![],
+                buffer_length: 0,
+                received_shard_indices: HashSet::new(),
+            },
+            last_discarded_packet_index: None,
+            reorder_window: 0,
+            held_packets: VecDeque::new(),
+            next_packet_index: None,
+            newest_packet_index: None,
+        }
+    }
+
+    fn report_dropped_packet(&self, dropped_packets: &mut usize) {
+        self.packet_queue
+            .dropped_packets
+            .fetch_add(1, AtomicOrdering::Relaxed);
+        *dropped_packets += 1;
+    }
+
+    fn queue_packet(&self, packet: ReconstructedPacket, dropped_packets: &mut usize) {
+        {
+            let mut packets = self.packet_queue.packets.lock();
+            if self.backpressure == BackpressurePolicy::Coalesce {
+                for stale_packet in packets.drain(..) {
+                    self.used_buffer_sender.send(stale_packet.buffer).ok();
+                    self.report_dropped_packet(dropped_packets);
+                }
+            }
+            packets.push_back(packet);
+        }
+        self.packet_queue.condvar.notify_one();
+    }
+
+    // True for the redundant copies of packets that were already completed. They must not take a
+    // buffer, which could evict a packet waiting to be read
+    fn is_completed_packet(&self, packet_index: u32) -> bool {
+        self.next_packet_index
+            .is_some_and(|next_index| wrapping_cmp(packet_index, next_index) == Ordering::Less)
+            || self
+                .held_packets
+                .iter()
+                .any(|packet| packet.index == packet_index)
+    }
+
+    // Packets are passed to the receiver in order. A packet completed after a later one is counted
+    // as reordered if it is still within the reorder window, otherwise it is discarded and the
+    // receiver sees it as lost
+    fn complete_packet(
+        &mut self,
+        packet: ReconstructedPacket,
+        dropped_packets: &mut usize,
+        reordered_packets: &mut usize,
+    ) {
+        if let Some(next_index) = self.next_packet_index {
+            if wrapping_cmp(packet.index, next_index) == Ordering::Less {
+                self.used_buffer_sender.send(packet.buffer).ok();
+                return;
+            }
+        }
+
+        match self.newest_packet_index {
+            Some(newest_index) if wrapping_cmp(packet.index, newest_index) == Ordering::Less => {
+                *reordered_packets += 1;
+            }
+            _ => self.newest_packet_index = Some(packet.index),
+        }
+
+        let position = self
+            .held_packets
+            .iter()
+            .position(|held| wrapping_cmp(held.index, packet.index) == Ordering::Greater)
+            .unwrap_or(self.held_packets.len());
+        self.held_packets.insert(position, packet);
+
+        let newest_index = self.newest_packet_index.unwrap_or_default();
+        while let Some(packet) = self.held_packets.front() {
+            let is_next = self.next_packet_index.unwrap_or(packet.index) == packet.index;
+            let is_out_of_window = newest_index.wrapping_sub(packet.index) >= self.reorder_window;
+            if !is_next && !is_out_of_window {
+                break;
+            }
+
+            let packet = self.held_packets.pop_front().unwrap();
+            self.next_packet_index = Some(packet.index.wrapping_add(1));
+            self.queue_packet(packet, dropped_packets);
+        }
+    }
+
+    fn acquire_buffer(&mut self, dropped_packets: &mut usize) -> Option<Vec<u8>> {
+        if let Ok(buffer) = self.used_buffer_receiver.try_recv() {
+            return Some(buffer);
+        }
+
+        // In case no used buffers were found, recycle one of the in progress packets, chances are
+        // these buffers are "dead" because one of their shards has been dropped by the network.
+        if let Some(idx) = self.in_progress_packets.keys().next().copied() {
+            return Some(self.in_progress_packets.remove(&idx).unwrap().buffer);
+        }
+
+        match self.backpressure {
+            BackpressurePolicy::DropOldest | BackpressurePolicy::Coalesce => {
+                let packet = self.packet_queue.packets.lock().pop_front()?;
+                self.report_dropped_packet(dropped_packets);
+
+                Some(packet.buffer)
+            }
+            BackpressurePolicy::Block => self
+                .used_buffer_receiver
+                .recv_timeout(BLOCK_WAIT_TIMEOUT)
+                .ok(),
+            BackpressurePolicy::DropNewest => None,
+        }
+    }
+}
+
+impl Drop for StreamRecvComponents {
+    fn drop(&mut self) {
+        // Wake up the receiver so it can notice the socket is closed
+        self.packet_queue.condvar.notify_all();
+    }
 }
 
 // Note: used buffers don't *have* to be split by stream ID, but doing so improves memory usage
@@ -398,59 +815,204 @@ pub struct StreamSocket {
     receive_socket: Box<dyn SocketReader>,
     shard_recv_state: Option<RecvState>,
     stream_recv_components: HashMap<u16, StreamRecvComponents>,
+    lost_packets: Arc<AtomicUsize>,
+    recv_buffer_autotune: Option<RecvBufferAutotune>,
+    // Packets of any stream discarded because of backpressure, since the last call to
+    // take_dropped_packets()
+    dropped_packets: usize,
+    // Packets of any stream completed out of order within the reorder window, since the last call
+    // to take_reordered_packets()
+    reordered_packets: usize,
+    // By stream ID, since the last call to take_traffic()
+    sent_bytes: Arc<Mutex<HashMap<u16, usize>>>,
+    received_bytes: HashMap<u16, usize>,
+}
+
+struct RecvBufferAutotune {
+    last_check_instant: Instant,
+    last_lost_packets: usize,
 }
 
 impl StreamSocket {
+    /// Grow the receive buffer at runtime while packets are being lost
+    pub fn enable_recv_buffer_autotune(&mut self) {
+        self.recv_buffer_autotune = Some(RecvBufferAutotune {
+            last_check_instant: Instant::now(),
+            last_lost_packets: self.lost_packets.load(AtomicOrdering::Relaxed),
+        });
+    }
+
+    fn update_recv_buffer_autotune(&mut self) {
+        let Some(autotune) = &mut self.recv_buffer_autotune else {
+            return;
+        };
+        if autotune.last_check_instant.elapsed() < AUTOTUNE_CHECK_INTERVAL {
+            return;
+        }
+        autotune.last_check_instant = Instant::now();
+
+        let lost_packets = self.lost_packets.load(AtomicOrdering::Relaxed);
+        if lost_packets == autotune.last_lost_packets {
+            return;
+        }
+        autotune.last_lost_packets = lost_packets;
+
+        let Ok(size) = self.receive_socket.recv_buffer_size() else {
+            return;
+        };
+        if size >= AUTOTUNE_MAX_RECV_BUFFER_SIZE {
+            return;
+        }
+
+        let new_size = usize::min(size * 2, AUTOTUNE_MAX_RECV_BUFFER_SIZE);
+        match self.receive_socket.set_recv_buffer_size(new_size) {
+            Ok(()) => info!(
+                "Packet loss detected, socket recv buffer set to {}B",
+                self.receive_socket.recv_buffer_size().unwrap_or(new_size)
+            ),
+            Err(e) => debug!("Failed to grow socket recv buffer: {e}"),
+        }
+    }
+
     pub fn request_stream<T>(&self, stream_id: u16) -> StreamSender<T> {
         StreamSender {
             inner: Arc::clone(&self.send_socket),
+            sent_bytes: Arc::clone(&self.sent_bytes),
             stream_id,
             max_packet_size: self.max_packet_size,
             next_packet_index: 0,
             used_buffers: vec![],
+            pacing_duration: None,
+            duplicate_sender: None,
             _phantom: PhantomData,
         }
     }
 
+    /// Shard size used by the streams requested or subscribed after this call, prefix included.
+    /// Both peers must use the same size
+    pub fn set_max_packet_size(&mut self, size: usize) {
+        self.max_packet_size = size;
+    }
+
+    /// Send probes of the given sizes with fragmentation disabled, followed by an end marker.
+    /// Probes larger than the MTU of the local network interface are skipped
+    pub fn send_packet_size_probes(&self, sizes: &[usize]) -> Result<()> {
+        let mut socket = self.send_socket.lock();
+
+        socket.set_dont_fragment(true)?;
+        for &size in sizes {
+            let probe = packet_size_probe(usize::min(size, MAX_UDP_PACKET_SIZE));
+            for _ in 0..PACKET_SIZE_PROBE_COPIES {
+                if let Err(e) = socket.send(&probe) {
+                    debug!("Packet size probe of {size}B not sent: {e}");
+                    break;
+                }
+            }
+        }
+        socket.set_dont_fragment(false)?;
+
+        let end_marker = packet_size_probe(SHARD_PREFIX_SIZE);
+        for _ in 0..PACKET_SIZE_PROBE_COPIES {
+            socket.send(&end_marker)?;
+        }
+
+        Ok(())
+    }
+
+    /// Size of the largest probe received before the end marker or the timeout. Must be called
+    /// before the peer starts sending any stream
+    pub fn recv_packet_size_probes(&mut self, timeout: Duration) -> Option<usize> {
+        let deadline = Instant::now() + timeout;
+        let mut buffer = vec![0; MAX_UDP_PACKET_SIZE];
+
+        let mut max_size = None;
+        while Instant::now() < deadline {
+            let size = match self.receive_socket.recv(&mut buffer) {
+                Ok(size) => size,
+                Err(ConnectionError::TryAgain(_)) => continue,
+                Err(_) => break,
+            };
+
+            if size < SHARD_PREFIX_SIZE
+                || u16::from_be_bytes(buffer[4..6].try_into().unwrap())
+                    != PACKET_SIZE_PROBE_STREAM_ID
+            {
+                continue;
+            }
+            if size == SHARD_PREFIX_SIZE {
+                break;
+            }
+
+            max_size = max_size.max(Some(size));
+        }
+
+        max_size
+    }
+
+    /// Packets discarded because of backpressure since the previous call, for all streams
+    pub fn take_dropped_packets(&mut self) -> usize {
+        mem::take(&mut self.dropped_packets)
+    }
+
+    /// Packets completed out of order but passed in order to the receiver since the previous call,
+    /// for all streams
+    pub fn take_reordered_packets(&mut self) -> usize {
+        mem::take(&mut self.reordered_packets)
+    }
+
+    /// Wait for up to `window` later packets before considering a missing packet of this stream as
+    /// lost. Must be called after subscribe_to_stream()
+    pub fn set_reorder_window(&mut self, stream_id: u16, window: u32) {
+        if let Some(components) = self.stream_recv_components.get_mut(&stream_id) {
+            components.reorder_window = window;
+        }
+    }
+
+    /// Traffic since the previous call, by stream ID
+    pub fn take_traffic(&mut self) -> HashMap<u16, StreamTraffic> {
+        let mut traffic = HashMap::<u16, StreamTraffic>::new();
+
+        for (stream_id, bytes) in mem::take(&mut *self.sent_bytes.lock()) {
+            traffic.entry(stream_id).or_default().sent_bytes = bytes;
+        }
+        for (stream_id, bytes) in mem::take(&mut self.received_bytes) {
+            traffic.entry(stream_id).or_default().received_bytes = bytes;
+        }
+
+        traffic
+    }
+
     // max_concurrent_buffers: number of buffers allocated by this call which will be reused to
-    // receive packets for this stream ID. If packets are not read fast enough, the shards received
-    // for this particular stream will be discarded
+    // receive packets for this stream ID. If packets are not read fast enough, backpressure decides
+    // which packets of this particular stream are discarded
     pub fn subscribe_to_stream<T>(
         &mut self,
         stream_id: u16,
         max_concurrent_buffers: usize,
+        backpressure: BackpressurePolicy,
     ) -> StreamReceiver<T> {
-        let (packet_sender, packet_receiver) = mpsc::channel();
-        let (used_buffer_sender, used_buffer_receiver) = mpsc::channel();
+        let components = StreamRecvComponents::new(backpressure);
 
         for _ in 0..max_concurrent_buffers {
-            used_buffer_sender.send(vec![]).ok();
+            components.used_buffer_sender.send(vec![]).ok();
         }
 
-        self.stream_recv_components.insert(
-            stream_id,
-            StreamRecvComponents {
-                used_buffer_sender: used_buffer_sender.clone(),
-                used_buffer_receiver,
-                packet_queue: packet_sender,
-                in_progress_packets: HashMap::new(),
-                discarded_shards_sink: InProgressPacket {
-                    buffer: vec![],
-                    buffer_length: 0,
-                    received_shard_indices: HashSet::new(),
-                },
-            },
-        );
-
-        StreamReceiver {
-            packet_receiver,
-            used_buffer_queue: used_buffer_sender,
+        let receiver = StreamReceiver {
+            packet_queue: Arc::clone(&components.packet_queue),
+            used_buffer_queue: components.used_buffer_sender.clone(),
             _phantom: PhantomData,
             last_packet_index: None,
-        }
+            lost_packets: Arc::clone(&self.lost_packets),
+        };
+
+        self.stream_recv_components.insert(stream_id, components);
+
+        receiver
     }
 
     pub fn recv(&mut self) -> ConResult {
+        self.update_recv_buffer_autotune();
+
         let shard_recv_state_mut = if let Some(state) = &mut self.shard_recv_state {
             state
         } else {
@@ -469,6 +1031,8 @@ impl StreamSocket {
             let shards_count = u32::from_be_bytes(bytes[10..14].try_into().unwrap()) as usize;
             let shard_index = u32::from_be_bytes(bytes[14..18].try_into().unwrap()) as usize;
 
+            *self.received_bytes.entry(stream_id).or_default() += shard_length;
+
             self.shard_recv_state.insert(RecvState {
                 shard_length,
                 stream_id,
@@ -499,13 +1063,13 @@ impl StreamSocket {
             .get_mut(&shard_recv_state_mut.packet_index)
         {
             packet
-        } else if let Some(buffer) = components.used_buffer_receiver.try_recv().ok().or_else(|| {
-            // By default, try to dequeue a used buffer. In case none were found, recycle one of the
-            // in progress packets, chances are these buffers are "dead" because one of their shards
-            // has been dropped by the network.
-            let idx = *components.in_progress_packets.iter().next()?.0;
-            Some(components.in_progress_packets.remove(&idx).unwrap().buffer)
-        }) {
+        } else if components.is_completed_packet(shard_recv_state_mut.packet_index) {
+            shard_recv_state_mut.should_discard = true;
+            shard_recv_state_mut.packet_cursor = 0;
+            shard_recv_state_mut.shard_index = 0;
+
+            &mut components.discarded_shards_sink
+        } else if let Some(buffer) = components.acquire_buffer(&mut self.dropped_packets) {
             // NB: Can't use entry pattern because we want to allow bailing out on the line above
             components.in_progress_packets.insert(
                 shard_recv_state_mut.packet_index,
@@ -522,8 +1086,16 @@ impl StreamSocket {
                 .in_progress_packets
                 .get_mut(&shard_recv_state_mut.packet_index)
                 .unwrap()
+        } else if components.backpressure == BackpressurePolicy::Block {
+            // The shard is left in the socket and read again on the next call
+            return alvr_common::try_again();
         } else {
             // This branch may be hit in case the thread related to the stream hangs for some reason
+            if components.last_discarded_packet_index != Some(shard_recv_state_mut.packet_index) {
+                components.last_discarded_packet_index = Some(shard_recv_state_mut.packet_index);
+                components.report_dropped_packet(&mut self.dropped_packets);
+            }
+
             shard_recv_state_mut.should_discard = true;
             shard_recv_state_mut.packet_cursor = 0; // reset cursor from old shards
                                                     // always write at the start of the packet so the buffer doesn't grow much
@@ -586,23 +1158,31 @@ impl StreamSocket {
         // Check if packet is complete and send
         if in_progress_packet.received_shard_indices.len() == shard_recv_state_mut.shards_count {
             let size = in_progress_packet.buffer_length;
-            components
-                .packet_queue
-                .send(ReconstructedPacket {
-                    index: shard_recv_state_mut.packet_index,
-                    buffer: components
-                        .in_progress_packets
-                        .remove(&shard_recv_state_mut.packet_index)
-                        .unwrap()
-                        .buffer,
-                    size,
-                })
-                .ok();
-
-            // Keep only shards with later packet index (using wrapping logic)
-            while let Some((idx, _)) = components.in_progress_packets.iter().find(|(idx, _)| {
-                wrapping_cmp(**idx, shard_recv_state_mut.packet_index) == Ordering::Less
-            }) {
+            let packet = ReconstructedPacket {
+                index: shard_recv_state_mut.packet_index,
+                buffer: components
+                    .in_progress_packets
+                    .remove(&shard_recv_state_mut.packet_index)
+                    .unwrap()
+                    .buffer,
+                size,
+            };
+            components.complete_packet(
+                packet,
+                &mut self.dropped_packets,
+                &mut self.reordered_packets,
+            );
+
+            // Keep only shards of packets that can still be passed to the receiver (using wrapping
+            // logic)
+            let next_packet_index = components
+                .next_packet_index
+                .unwrap_or(shard_recv_state_mut.packet_index);
+            while let Some((idx, _)) = components
+                .in_progress_packets
+                .iter()
+                .find(|(idx, _)| wrapping_cmp(**idx, next_packet_index) == Ordering::Less)
+            {
                 let idx = *idx; // fix borrow rule
                 let packet = components.in_progress_packets.remove(&idx).unwrap();
 
@@ -617,3 +1197,274 @@ impl StreamSocket {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recv_components(reorder_window: u32) -> StreamRecvComponents {
+        let mut components = StreamRecvComponents::new(BackpressurePolicy::DropOldest);
+        components.reorder_window = reorder_window;
+
+        components
+    }
+
+    // Returns the number of packets counted as reordered
+    fn complete_packets(components: &mut StreamRecvComponents, indices: &[u32]) -> usize {
+        let mut dropped_packets = 0;
+        let mut reordered_packets = 0;
+        for &index in indices {
+            components.complete_packet(
+                ReconstructedPacket {
+                    index,
+                    buffer: vec![],
+                    size: 0,
+                },
+                &mut dropped_packets,
+                &mut reordered_packets,
+            );
+        }
+        assert_eq!(dropped_packets, 0);
+
+        reordered_packets
+    }
+
+    fn queued_indices(components: &StreamRecvComponents) -> Vec<u32> {
+        components
+            .packet_queue
+            .packets
+            .lock()
+            .iter()
+            .map(|packet| packet.index)
+            .collect()
+    }
+
+    const TEST_STREAM_ID: u16 = 1;
+    const TEST_MAX_PACKET_SIZE: usize = SHARD_PREFIX_SIZE + 16;
+
+    struct NullSocketWriter;
+
+    impl SocketWriter for NullSocketWriter {
+        fn send(&mut self, _: &[u8]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    // Behaves like a TCP stream, shards can be read in multiple calls
+    struct QueueSocketReader(Arc<Mutex<VecDeque<u8>>>);
+
+    impl SocketReader for QueueSocketReader {
+        fn recv(&mut self, buffer: &mut [u8]) -> ConResult<usize> {
+            let mut data = self.0.lock();
+            let count = usize::min(buffer.len(), data.len());
+            if count == 0 {
+                return alvr_common::try_again();
+            }
+            for (byte, data_byte) in buffer.iter_mut().zip(data.drain(..count)) {
+                *byte = data_byte;
+            }
+
+            Ok(count)
+        }
+
+        fn peek(&self, buffer: &mut [u8]) -> ConResult<usize> {
+            let data = self.0.lock();
+            let count = usize::min(buffer.len(), data.len());
+            for (byte, data_byte) in buffer.iter_mut().zip(data.iter()) {
+                *byte = *data_byte;
+            }
+
+            Ok(count)
+        }
+
+        fn recv_buffer_size(&self) -> Result<usize> {
+            Ok(0)
+        }
+
+        fn set_recv_buffer_size(&self, _: usize) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    // Returns the socket and the queue of incoming bytes
+    fn test_socket(
+        backpressure: BackpressurePolicy,
+        max_concurrent_buffers: usize,
+    ) -> (StreamSocket, Arc<Mutex<VecDeque<u8>>>) {
+        let incoming = Arc::new(Mutex::new(VecDeque::new()));
+        let mut socket = StreamSocket {
+            max_packet_size: TEST_MAX_PACKET_SIZE,
+            send_socket: Arc::new(Mutex::new(Box::new(NullSocketWriter))),
+            receive_socket: Box::new(QueueSocketReader(Arc::clone(&incoming))),
+            shard_recv_state: None,
+            stream_recv_components: HashMap::new(),
+            lost_packets: Arc::new(AtomicUsize::new(0)),
+            recv_buffer_autotune: None,
+            dropped_packets: 0,
+            reordered_packets: 0,
+            sent_bytes: Arc::new(Mutex::new(HashMap::new())),
+            received_bytes: HashMap::new(),
+        };
+        socket.subscribe_to_stream::<()>(TEST_STREAM_ID, max_concurrent_buffers, backpressure);
+
+        (socket, incoming)
+    }
+
+    // Single shard packet
+    fn push_packet(incoming: &Mutex<VecDeque<u8>>, packet_index: u32) {
+        let payload = [packet_index as u8; 4];
+        let mut incoming = incoming.lock();
+        incoming.extend(
+            ((SHARD_PREFIX_SIZE + payload.len() - mem::size_of::<u32>()) as u32).to_be_bytes(),
+        );
+        incoming.extend(TEST_STREAM_ID.to_be_bytes());
+        incoming.extend(packet_index.to_be_bytes());
+        incoming.extend(1_u32.to_be_bytes()); // shards count
+        incoming.extend(0_u32.to_be_bytes()); // shard index
+        incoming.extend(payload);
+    }
+
+    // Reads shards until the socket has no more data or a shard must wait for a buffer
+    fn recv_all(socket: &mut StreamSocket) {
+        while socket.recv().is_ok() {}
+    }
+
+    fn socket_queued_indices(socket: &StreamSocket) -> Vec<u32> {
+        queued_indices(&socket.stream_recv_components[&TEST_STREAM_ID])
+    }
+
+    #[test]
+    fn test_complete_packet_in_order() {
+        let mut components = recv_components(2);
+
+        assert_eq!(complete_packets(&mut components, &[0, 1, 2, 3]), 0);
+        assert_eq!(queued_indices(&components), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_complete_packet_reordered() {
+        let mut components = recv_components(2);
+
+        assert_eq!(complete_packets(&mut components, &[0, 2]), 0);
+        // Packet 2 is held until packet 1 completes
+        assert_eq!(queued_indices(&components), [0]);
+
+        assert_eq!(complete_packets(&mut components, &[1]), 1);
+        assert_eq!(queued_indices(&components), [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_complete_packet_without_window() {
+        let mut components = recv_components(0);
+
+        complete_packets(&mut components, &[0, 2, 1]);
+        // Packet 1 is not waited for, it arrives too late
+        assert_eq!(queued_indices(&components), [0, 2]);
+        assert_eq!(components.used_buffer_receiver.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_complete_packet_out_of_window() {
+        let mut components = recv_components(2);
+
+        complete_packets(&mut components, &[0, 2, 3]);
+        assert_eq!(queued_indices(&components), [0]);
+
+        // Packet 1 falls out of the window and is considered lost
+        complete_packets(&mut components, &[4]);
+        assert_eq!(queued_indices(&components), [0, 2, 3, 4]);
+
+        // When it finally completes, it is discarded and its buffer is recycled
+        complete_packets(&mut components, &[1]);
+        assert_eq!(queued_indices(&components), [0, 2, 3, 4]);
+        assert_eq!(components.used_buffer_receiver.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_complete_packet_index_wraparound() {
+        let mut components = recv_components(2);
+
+        assert_eq!(
+            complete_packets(&mut components, &[u32::MAX - 1, u32::MAX, 1, 0]),
+            1
+        );
+        assert_eq!(queued_indices(&components), [u32::MAX - 1, u32::MAX, 0, 1]);
+    }
+
+    #[test]
+    fn test_recv_discards_redundant_copy_after_delivery() {
+        let (mut socket, incoming) = test_socket(BackpressurePolicy::DropOldest, 1);
+
+        push_packet(&incoming, 0);
+        recv_all(&mut socket);
+        assert_eq!(socket_queued_indices(&socket), [0]);
+
+        // The only buffer holds packet 0, the copy must not evict it
+        push_packet(&incoming, 0);
+        recv_all(&mut socket);
+        assert!(incoming.lock().is_empty());
+        assert_eq!(socket_queued_indices(&socket), [0]);
+        assert_eq!(socket.take_dropped_packets(), 0);
+    }
+
+    #[test]
+    fn test_backpressure_drop_oldest() {
+        let (mut socket, incoming) = test_socket(BackpressurePolicy::DropOldest, 1);
+
+        push_packet(&incoming, 0);
+        push_packet(&incoming, 1);
+        recv_all(&mut socket);
+
+        assert_eq!(socket_queued_indices(&socket), [1]);
+        assert_eq!(socket.take_dropped_packets(), 1);
+    }
+
+    #[test]
+    fn test_backpressure_drop_newest() {
+        let (mut socket, incoming) = test_socket(BackpressurePolicy::DropNewest, 1);
+
+        push_packet(&incoming, 0);
+        push_packet(&incoming, 1);
+        recv_all(&mut socket);
+
+        assert!(incoming.lock().is_empty());
+        assert_eq!(socket_queued_indices(&socket), [0]);
+        assert_eq!(socket.take_dropped_packets(), 1);
+    }
+
+    #[test]
+    fn test_backpressure_block() {
+        let (mut socket, incoming) = test_socket(BackpressurePolicy::Block, 1);
+
+        push_packet(&incoming, 0);
+        push_packet(&incoming, 1);
+        recv_all(&mut socket);
+
+        // Packet 1 is left in the socket until the buffer of packet 0 is returned
+        assert!(!incoming.lock().is_empty());
+        assert_eq!(socket_queued_indices(&socket), [0]);
+        assert_eq!(socket.take_dropped_packets(), 0);
+
+        let components = &socket.stream_recv_components[&TEST_STREAM_ID];
+        let packet = components.packet_queue.packets.lock().pop_front().unwrap();
+        components.used_buffer_sender.send(packet.buffer).ok();
+        recv_all(&mut socket);
+
+        assert!(incoming.lock().is_empty());
+        assert_eq!(socket_queued_indices(&socket), [1]);
+        assert_eq!(socket.take_dropped_packets(), 0);
+    }
+
+    #[test]
+    fn test_backpressure_coalesce() {
+        // Enough buffers for both packets, the unread one is discarded anyway
+        let (mut socket, incoming) = test_socket(BackpressurePolicy::Coalesce, 2);
+
+        push_packet(&incoming, 0);
+        push_packet(&incoming, 1);
+        recv_all(&mut socket);
+
+        assert_eq!(socket_queued_indices(&socket), [1]);
+        assert_eq!(socket.take_dropped_packets(), 1);
+    }
+}
@@ -115,6 +115,18 @@ pub fn build_streamer(
         .unwrap();
     }
 
+    // Build command line interface
+    {
+        let _push_guard = sh.push_dir(afs::crate_dir("ctl"));
+        cmd!(sh, "cargo build {common_flags_ref...}").run().unwrap();
+
+        sh.copy_file(
+            artifacts_dir.join(afs::exec_fname("alvr_ctl")),
+            build_layout.ctl_exe(),
+        )
+        .unwrap();
+    }
+
     // copy dependencies
     if cfg!(windows) {
         command::copy_recursive(